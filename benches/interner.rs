@@ -0,0 +1,65 @@
+use crabrl::StringInterner;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use parking_lot::Mutex;
+use string_interner::{DefaultBackend, Symbol};
+
+const LOOKUPS_PER_THREAD: usize = 20_000;
+
+/// Concept names with the skew of a real filing: a few hundred distinct
+/// QNames looked up over and over
+fn workload() -> Vec<String> {
+    (0..LOOKUPS_PER_THREAD)
+        .map(|i| format!("us-gaap:Concept{}", (i * 7919) % 400))
+        .collect()
+}
+
+fn run_threads(threads: usize, names: &[String], intern: impl Fn(&str) -> u32 + Sync) {
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                for name in names {
+                    black_box(intern(name));
+                }
+            });
+        }
+    });
+}
+
+fn intern_parallel(c: &mut Criterion) {
+    let names = workload();
+    let mut group = c.benchmark_group("intern_parallel");
+
+    for threads in [1, 4, 8] {
+        group.throughput(Throughput::Elements((threads * LOOKUPS_PER_THREAD) as u64));
+
+        group.bench_with_input(
+            BenchmarkId::new("mutex", threads),
+            &threads,
+            |b, &threads| {
+                b.iter(|| {
+                    let interner =
+                        Mutex::new(string_interner::StringInterner::<DefaultBackend>::new());
+                    run_threads(threads, &names, |s| {
+                        interner.lock().get_or_intern(s).to_usize() as u32
+                    });
+                });
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("sharded", threads),
+            &threads,
+            |b, &threads| {
+                b.iter(|| {
+                    let interner = StringInterner::new();
+                    run_threads(threads, &names, |s| interner.intern(s));
+                });
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, intern_parallel);
+criterion_main!(benches);
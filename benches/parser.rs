@@ -1,5 +1,5 @@
 use crabrl::Parser;
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 use std::path::Path;
 
 fn parse_sample_sec_file(c: &mut Criterion) {
@@ -33,5 +33,50 @@ fn parse_sample_sec_file(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, parse_sample_sec_file);
+/// A fund filing in miniature: one context per (series, class) member and
+/// one fact per context, referenced in reverse document order
+fn fund_filing(contexts: usize) -> String {
+    let mut xml = String::from(
+        r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:xbrldi="http://xbrl.org/2006/xbrldi" xmlns:dei="http://xbrl.sec.gov/dei/2023" xmlns:rr="http://xbrl.sec.gov/rr/2023">
+<xbrli:unit id="pure"><xbrli:measure>xbrli:pure</xbrli:measure></xbrli:unit>
+"#,
+    );
+    for i in 0..contexts {
+        xml.push_str(&format!(
+            r#"<xbrli:context id="S{series}_C{i}"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier>
+<xbrli:segment><xbrldi:explicitMember dimension="dei:LegalEntityAxis">rr:Series{series}Member</xbrldi:explicitMember><xbrldi:explicitMember dimension="rr:ProspectusShareClassAxis">rr:Class{i}Member</xbrldi:explicitMember></xbrli:segment></xbrli:entity>
+<xbrli:period><xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2023-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+"#,
+            series = i / 8,
+        ));
+    }
+    for i in (0..contexts).rev() {
+        xml.push_str(&format!(
+            "<rr:ExpensesOverAssets contextRef=\"S{}_C{}\" unitRef=\"pure\" decimals=\"4\">0.0{:03}</rr:ExpensesOverAssets>\n",
+            i / 8,
+            i,
+            i % 1000
+        ));
+    }
+    xml.push_str("</xbrli:xbrl>");
+    xml
+}
+
+/// Parse time per context should stay flat as the context count grows
+fn parse_many_contexts(c: &mut Criterion) {
+    let parser = Parser::new();
+    let mut group = c.benchmark_group("parse_many_contexts");
+    group.sample_size(10);
+
+    for contexts in [1_000, 10_000, 50_000] {
+        let xml = fund_filing(contexts);
+        group.throughput(Throughput::Elements(contexts as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(contexts), &xml, |b, xml| {
+            b.iter(|| parser.parse_str(black_box(xml)));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse_sample_sec_file, parse_many_contexts);
 criterion_main!(benches);
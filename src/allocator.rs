@@ -1,84 +1,101 @@
+//! Arena allocation and string interning for the parser
+//!
+//! Arena references borrow the allocator, and the operations that free or
+//! swap chunks take `&mut self`, so the borrow checker rules out references
+//! that outlive their memory.
+
+use crate::interner::StringInterner;
 use bumpalo::Bump;
-use std::cell::RefCell;
 use std::mem::MaybeUninit;
-use std::ptr::NonNull;
-use std::sync::Arc;
-use parking_lot::Mutex;
-use string_interner::{DefaultBackend, Symbol};
-use string_interner::symbol::SymbolU32;
 
 const ARENA_SIZE: usize = 64 * 1024 * 1024; // 64MB arenas
 const POOL_SIZE: usize = 1024;
 
+/// Bump arena for values that live as long as a parse.
+///
+/// Destructors of allocated values are never run; allocate only types
+/// without meaningful `Drop` (strings and plain data).
 #[repr(align(64))]
 pub struct ArenaAllocator {
-    current: RefCell<Bump>,
-    arenas: RefCell<Vec<Bump>>,
-    string_interner: Arc<Mutex<string_interner::StringInterner<DefaultBackend>>>,
+    current: Bump,
+    // Filled arenas kept alive until the next reset
+    arenas: Vec<Bump>,
+    string_interner: StringInterner,
 }
 
 impl ArenaAllocator {
     pub fn new() -> Self {
+        Self::with_capacity(ARENA_SIZE)
+    }
+
+    /// Arena whose first chunk holds `bytes`; it grows on demand
+    pub fn with_capacity(bytes: usize) -> Self {
+        Self::with_interner(bytes, StringInterner::new())
+    }
+
+    /// Arena that interns into `interner`, which may be shared with other
+    /// arenas
+    pub fn with_interner(bytes: usize, interner: StringInterner) -> Self {
         Self {
-            current: RefCell::new(Bump::with_capacity(ARENA_SIZE)),
-            arenas: RefCell::new(Vec::with_capacity(16)),
-            string_interner: Arc::new(Mutex::new(string_interner::StringInterner::new())),
+            current: Bump::with_capacity(bytes),
+            arenas: Vec::with_capacity(16),
+            string_interner: interner,
         }
     }
 
+    pub fn interner(&self) -> &StringInterner {
+        &self.string_interner
+    }
+
     #[inline(always)]
     pub fn alloc<T>(&self, val: T) -> &T {
-        unsafe {
-            let ptr = self.current.borrow().alloc(val) as *const T;
-            &*ptr
-        }
+        self.current.alloc(val)
     }
 
     #[inline(always)]
     pub fn alloc_slice<T: Copy>(&self, slice: &[T]) -> &[T] {
-        unsafe {
-            let ptr = self.current.borrow().alloc_slice_copy(slice) as *const [T];
-            &*ptr
-        }
+        self.current.alloc_slice_copy(slice)
     }
 
     #[inline(always)]
     pub fn alloc_str(&self, s: &str) -> &str {
-        unsafe {
-            let ptr = self.current.borrow().alloc_str(s) as *const str;
-            &*ptr
-        }
+        self.current.alloc_str(s)
     }
 
     #[inline(always)]
     pub fn intern_string(&self, s: &str) -> u32 {
-        let mut interner = self.string_interner.lock();
-        interner.get_or_intern(s).to_usize() as u32
+        self.string_interner.intern(s)
     }
 
     #[inline(always)]
     pub fn get_interned(&self, id: u32) -> Option<String> {
-        let interner = self.string_interner.lock();
-        let symbol = SymbolU32::try_from_usize(id as usize)?;
-        interner.resolve(symbol)
-            .map(|s| s.to_string())
-    }
-
-    pub fn reset(&self) {
-        let mut current = self.current.borrow_mut();
-        current.reset();
-        
-        let mut arenas = self.arenas.borrow_mut();
-        for arena in arenas.iter_mut() {
+        self.string_interner.resolve(id)
+    }
+
+    /// Free every allocation for reuse; interned strings are kept
+    pub fn reset(&mut self) {
+        self.current.reset();
+        for arena in &mut self.arenas {
             arena.reset();
         }
     }
 
-    pub fn new_arena(&self) {
-        let mut arenas = self.arenas.borrow_mut();
-        let old = std::mem::replace(&mut *self.current.borrow_mut(), 
-                                    Bump::with_capacity(ARENA_SIZE));
-        arenas.push(old);
+    /// Bytes reserved by every chunk, used or not
+    pub fn allocated_bytes(&self) -> usize {
+        self.current.allocated_bytes()
+            + self.arenas.iter().map(Bump::allocated_bytes).sum::<usize>()
+    }
+
+    /// Start a fresh chunk, keeping the filled one alive
+    pub fn new_arena(&mut self) {
+        let old = std::mem::replace(&mut self.current, Bump::with_capacity(ARENA_SIZE));
+        self.arenas.push(old);
+    }
+}
+
+impl Default for ArenaAllocator {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -98,7 +115,9 @@ impl<T> ObjectPool<T> {
 
     #[inline(always)]
     pub fn acquire(&mut self) -> Box<T> {
-        self.pool.pop().unwrap_or_else(|| Box::new((self.factory)()))
+        self.pool
+            .pop()
+            .unwrap_or_else(|| Box::new((self.factory)()))
     }
 
     #[inline(always)]
@@ -119,7 +138,7 @@ impl<const N: usize> StackBuffer<N> {
     #[inline(always)]
     pub const fn new() -> Self {
         Self {
-            data: unsafe { MaybeUninit::uninit().assume_init() },
+            data: [MaybeUninit::uninit(); N],
             len: 0,
         }
     }
@@ -137,12 +156,9 @@ impl<const N: usize> StackBuffer<N> {
 
     #[inline(always)]
     pub fn as_slice(&self) -> &[u8] {
-        unsafe {
-            std::slice::from_raw_parts(
-                self.data.as_ptr() as *const u8,
-                self.len
-            )
-        }
+        // SAFETY: `push` is the only writer and initializes bytes in order,
+        // so the first `len` bytes are initialized; `len <= N` always holds
+        unsafe { std::slice::from_raw_parts(self.data.as_ptr() as *const u8, self.len) }
     }
 
     #[inline(always)]
@@ -151,6 +167,12 @@ impl<const N: usize> StackBuffer<N> {
     }
 }
 
+impl<const N: usize> Default for StackBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -170,8 +192,32 @@ mod tests {
         let id1 = arena.intern_string("test");
         let id2 = arena.intern_string("test");
         assert_eq!(id1, id2);
-        
+
         let s = arena.get_interned(id1).unwrap();
         assert_eq!(s, "test");
     }
+
+    #[test]
+    fn test_arena_reuse_after_reset() {
+        let mut arena = ArenaAllocator::with_capacity(64);
+        let first = arena.alloc_slice(&[1u32, 2, 3]).to_vec();
+        arena.new_arena();
+        let s = arena.alloc_str("kept across chunks");
+        assert_eq!(s, "kept across chunks");
+        arena.reset();
+        assert_eq!(arena.alloc_slice(&first), [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_stack_buffer() {
+        let mut buffer = StackBuffer::<4>::new();
+        assert!(buffer.as_slice().is_empty());
+        for byte in b"abcd" {
+            assert!(buffer.push(*byte));
+        }
+        assert!(!buffer.push(b'e'));
+        assert_eq!(buffer.as_slice(), b"abcd");
+        buffer.clear();
+        assert!(buffer.as_slice().is_empty());
+    }
 }
@@ -0,0 +1,153 @@
+//! Audit log of validation runs
+//!
+//! Records every check the validator ran on a document, including checks
+//! that found nothing and checks that were disabled, so the log evidences
+//! what was verified rather than only what failed.
+
+use crate::model::Document;
+use crate::Result;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+use std::io::Write;
+
+/// One validation check as run on a document
+#[derive(Debug, Clone)]
+pub struct CheckRecord {
+    pub check: &'static str,
+    /// Rule identifiers the check can report, see [`crate::validator::RULES`]
+    pub rules: &'static [&'static str],
+    /// `false` when the check is disabled in the validator's configuration
+    pub executed: bool,
+    pub duration_us: u64,
+    /// Sizes of what the check inspected, e.g. `("facts", 1075)`
+    pub inputs: Vec<(&'static str, usize)>,
+    pub findings: usize,
+}
+
+impl CheckRecord {
+    pub fn to_json(&self) -> Value {
+        let inputs: serde_json::Map<String, Value> = self
+            .inputs
+            .iter()
+            .map(|(name, count)| (name.to_string(), json!(count)))
+            .collect();
+        json!({
+            "check": self.check,
+            "rules": self.rules,
+            "executed": self.executed,
+            "duration_us": self.duration_us,
+            "inputs": inputs,
+            "findings": self.findings,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    /// Source URI of the document, when known
+    pub document: Option<String>,
+    /// SHA-256 of the document bytes, when known
+    pub sha256: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub strict: bool,
+    pub checks: Vec<CheckRecord>,
+}
+
+impl AuditLog {
+    pub fn new(
+        doc: &Document,
+        strict: bool,
+        started_at: DateTime<Utc>,
+        checks: Vec<CheckRecord>,
+    ) -> Self {
+        let provenance = doc.provenance.as_ref();
+        Self {
+            document: provenance.and_then(|p| p.uri.clone()),
+            sha256: provenance.map(|p| p.sha256.clone()),
+            started_at,
+            strict,
+            checks,
+        }
+    }
+
+    pub fn findings(&self) -> usize {
+        self.checks.iter().map(|c| c.findings).sum()
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "crabrl_version": env!("CARGO_PKG_VERSION"),
+            "document": self.document,
+            "sha256": self.sha256,
+            "started_at": self.started_at.to_rfc3339(),
+            "strict": self.strict,
+            "findings": self.findings(),
+            "checks": self.checks.iter().map(CheckRecord::to_json).collect::<Vec<_>>(),
+        })
+    }
+
+    pub fn write_json<W: Write>(&self, writer: &mut W) -> Result<()> {
+        serde_json::to_writer_pretty(&mut *writer, &self.to_json())
+            .map_err(|e| crate::Error::Export(e.to_string()))?;
+        writeln!(writer)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Parser, ValidationConfig, Validator};
+
+    #[test]
+    fn test_audit_records_every_check() {
+        let doc = Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <a:Cash contextRef="c" unitRef="USD" decimals="0">7</a:Cash>
+  <a:Cash contextRef="c" unitRef="USD" decimals="0">7</a:Cash>
+</xbrli:xbrl>"#,
+            )
+            .unwrap()
+            .with_source_uri("filing.xml");
+
+        let result = Validator::with_config(ValidationConfig::default())
+            .validate(&doc)
+            .unwrap();
+        assert!(result.audit.is_none());
+
+        let result = Validator::sec_edgar().with_audit().validate(&doc).unwrap();
+        let audit = result.audit.unwrap();
+        let checks: Vec<_> = audit.checks.iter().map(|c| c.check).collect();
+        assert_eq!(
+            checks,
+            [
+                "contexts",
+                "units",
+                "fact-references",
+                "duplicate-facts",
+                "mixed-units",
+                "calculations",
+                "period-alignment",
+                "decimals",
+                "concept-metadata",
+                "tuples",
+                "definitions",
+                "role-types",
+                "efm"
+            ]
+        );
+        assert!(audit.checks.iter().all(|c| c.executed));
+        assert_eq!(audit.findings(), result.findings.len());
+
+        let mut json = Vec::new();
+        audit.write_json(&mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json["document"], "filing.xml");
+        assert_eq!(json["checks"][3]["inputs"]["facts"], 2);
+        // The repeated fact is a complete duplicate, only reported on request
+        assert_eq!(json["checks"][3]["findings"], 0);
+    }
+}
@@ -0,0 +1,601 @@
+//! Calculation linkbase checks (XBRL Calculations 1.1, round-to-nearest)
+//!
+//! Summations are checked per extended link role. A summation binds for a
+//! parent fact when at least one child has a fact in the same context and
+//! unit. Values are compared after rounding to the lowest `decimals` among
+//! the facts involved. Duplicate facts that agree are merged into the most
+//! precise one; summations touching inconsistent duplicates are skipped.
+//!
+//! [`check_completeness`] covers what binding leaves out: a total reported
+//! without any of its children, and children reported without their total.
+//!
+//! [`Document::recalculate`] runs the network the other way: given
+//! hypothetical values for some facts, it re-derives the totals above them.
+//! [`check_calculations_in_role`] and [`Document::recalculate_in_role`]
+//! restrict either direction to one role's network, for concepts summed
+//! differently in several statements.
+
+use crate::model::Document;
+use crate::numeric::{consistent, lower_precision, round};
+use crate::validator::ValidationError;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+/// (concept id, context id, unit id)
+type FactKey = (u32, u32, u32);
+
+/// Numeric facts grouped by concept, context and unit. A group maps to its
+/// most precise fact, or `None` when its duplicates disagree.
+fn bind_facts(doc: &Document) -> HashMap<FactKey, Option<usize>> {
+    let mut groups: HashMap<FactKey, Vec<usize>> = HashMap::new();
+    for fact in doc.iter_facts() {
+        // Summations bind sibling facts; tuple contents are not bound
+        if fact.context().is_none()
+            || fact.value().as_decimal().is_none()
+            || !fact.tuple_path().is_empty()
+        {
+            continue;
+        }
+        let i = fact.index();
+        let key = (
+            doc.facts.concept_ids[i],
+            doc.facts.context_ids[i],
+            doc.facts.unit_ids[i],
+        );
+        groups.entry(key).or_default().push(i);
+    }
+
+    let value = |i: usize| doc.facts.values[i].as_decimal().unwrap_or_default();
+    groups
+        .into_iter()
+        .map(|(key, facts)| {
+            let best = *facts
+                .iter()
+                .max_by_key(|&&i| doc.facts.decimals[i].map_or(i16::MAX, i16::from))
+                .expect("groups are never empty");
+            let decimals = |i: usize| doc.facts.decimals[i];
+            let agree = facts
+                .iter()
+                .all(|&i| consistent(value(best), decimals(best), value(i), decimals(i)));
+            (key, agree.then_some(best))
+        })
+        .collect()
+}
+
+/// Weighted children per (role, parent), in document order, of every role
+/// or only `role`, after prohibition and override; repeated arcs between
+/// the same pair count once
+fn summations<'d>(
+    doc: &'d Document,
+    role: Option<&str>,
+) -> BTreeMap<(&'d str, &'d str), Vec<(&'d str, Decimal)>> {
+    let mut summations: BTreeMap<(&str, &str), Vec<(&str, Decimal)>> = BTreeMap::new();
+    for link in doc.calculation_network(role) {
+        let children = summations
+            .entry((link.role.as_str(), link.from.as_str()))
+            .or_default();
+        if !children.iter().any(|(to, _)| *to == link.to) {
+            let weight = Decimal::from_f64(link.weight).unwrap_or_default();
+            children.push((link.to.as_str(), weight));
+        }
+    }
+    summations
+}
+
+/// Check every summation-item relationship against the document's facts
+pub fn check_calculations(doc: &Document) -> Vec<ValidationError> {
+    check_summations(doc, summations(doc, None))
+}
+
+/// [`check_calculations`] restricted to the network of one extended link
+/// role
+pub fn check_calculations_in_role(doc: &Document, role: &str) -> Vec<ValidationError> {
+    check_summations(doc, summations(doc, Some(role)))
+}
+
+fn check_summations(
+    doc: &Document,
+    summations: BTreeMap<(&str, &str), Vec<(&str, Decimal)>>,
+) -> Vec<ValidationError> {
+    if summations.is_empty() {
+        return Vec::new();
+    }
+
+    let concept_ids: HashMap<&str, u32> = doc
+        .concept_names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name.as_str(), i as u32))
+        .collect();
+    let bound = bind_facts(doc);
+    let value = |i: usize| doc.facts.values[i].as_decimal().unwrap_or_default();
+
+    let mut errors = Vec::new();
+    for ((role, parent), children) in &summations {
+        let Some(&parent_id) = concept_ids.get(parent) else {
+            continue;
+        };
+        let mut parent_facts: Vec<(&FactKey, usize)> = bound
+            .iter()
+            .filter(|(key, _)| key.0 == parent_id)
+            .filter_map(|(key, fact)| fact.map(|i| (key, i)))
+            .collect();
+        parent_facts.sort_by_key(|(_, i)| *i);
+
+        for ((_, context, unit), parent_fact) in parent_facts {
+            let mut contributing = Vec::new();
+            let mut inconsistent_duplicates = false;
+            for (child, weight) in children {
+                let Some(&child_id) = concept_ids.get(child) else {
+                    continue;
+                };
+                match bound.get(&(child_id, *context, *unit)) {
+                    Some(Some(i)) => contributing.push((*i, *weight)),
+                    Some(None) => inconsistent_duplicates = true,
+                    None => {}
+                }
+            }
+            if contributing.is_empty() || inconsistent_duplicates {
+                continue;
+            }
+
+            let decimals = contributing
+                .iter()
+                .map(|(i, _)| doc.facts.decimals[*i])
+                .fold(doc.facts.decimals[parent_fact], lower_precision);
+            let sum: Decimal = contributing
+                .iter()
+                .map(|(i, weight)| round(value(*i), decimals) * weight)
+                .sum();
+            let expected = round(sum, decimals);
+            let actual = round(value(parent_fact), decimals);
+            if expected != actual {
+                errors.push(ValidationError::CalculationInconsistency {
+                    concept: parent.to_string(),
+                    role: role.to_string(),
+                    context_id: doc.contexts[*context as usize].id.clone(),
+                    expected,
+                    actual,
+                    fact_index: parent_fact,
+                    contributing: contributing.iter().map(|(i, _)| *i).collect(),
+                });
+            }
+        }
+    }
+    errors
+}
+
+/// Summations reported only partially in a context and unit
+pub fn check_completeness(doc: &Document) -> Vec<ValidationError> {
+    let summations = summations(doc, None);
+    if summations.is_empty() {
+        return Vec::new();
+    }
+
+    let concept_ids: HashMap<&str, u32> = doc
+        .concept_names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name.as_str(), i as u32))
+        .collect();
+    // First numeric fact per concept, context and unit
+    let mut first: BTreeMap<FactKey, usize> = BTreeMap::new();
+    for fact in doc.iter_facts() {
+        if fact.context().is_none()
+            || fact.value().as_decimal().is_none()
+            || !fact.tuple_path().is_empty()
+        {
+            continue;
+        }
+        let i = fact.index();
+        let key = (
+            doc.facts.concept_ids[i],
+            doc.facts.context_ids[i],
+            doc.facts.unit_ids[i],
+        );
+        first.entry(key).or_insert(i);
+    }
+    let slots: BTreeSet<(u32, u32)> = first.keys().map(|(_, c, u)| (*c, *u)).collect();
+
+    let mut errors = Vec::new();
+    for ((role, parent), children) in &summations {
+        let parent_id = concept_ids.get(parent);
+        let child_ids: Vec<u32> = children
+            .iter()
+            .filter_map(|(c, _)| concept_ids.get(c).copied())
+            .collect();
+        for &(context, unit) in &slots {
+            let total = parent_id.and_then(|id| first.get(&(*id, context, unit)));
+            let child = child_ids
+                .iter()
+                .filter_map(|id| first.get(&(*id, context, unit)))
+                .min();
+            let (missing_total, fact_index) = match (total, child) {
+                (Some(&i), None) => (false, i),
+                (None, Some(&i)) => (true, i),
+                _ => continue,
+            };
+            errors.push(ValidationError::IncompleteSummation {
+                concept: parent.to_string(),
+                role: role.to_string(),
+                context_id: doc.contexts[context as usize].id.clone(),
+                missing_total,
+                fact_index,
+            });
+        }
+    }
+    errors.sort_by_key(|e| e.fact_index());
+    errors
+}
+
+/// A fact whose value changes in a what-if recalculation
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recalculation {
+    pub fact_index: usize,
+    pub concept: String,
+    pub context_id: String,
+    pub original: Decimal,
+    pub value: Decimal,
+    /// Facts whose change moved this one; empty for overridden facts
+    pub derived_from: Vec<usize>,
+}
+
+impl Document {
+    /// Extended link roles of the calculation networks, in document order
+    pub fn calculation_roles(&self) -> Vec<&str> {
+        let mut roles: Vec<&str> = Vec::new();
+        for link in &self.calculation_links {
+            if !roles.contains(&link.role.as_str()) {
+                roles.push(&link.role);
+            }
+        }
+        roles
+    }
+
+    /// Replace the values of some facts, given as (fact index, value), and
+    /// re-derive the calculation totals above them. A total moves by the
+    /// weighted change of its children, so children that are not reported
+    /// keep their implied share. Returns every changed fact: overrides
+    /// first, then totals in the order they were reached.
+    ///
+    /// Every role's network takes part; a concept summed in several
+    /// statements moves each of its totals. Use
+    /// [`Document::recalculate_in_role`] to follow one statement.
+    pub fn recalculate(&self, overrides: &[(usize, Decimal)]) -> Vec<Recalculation> {
+        self.recalculate_network(None, overrides)
+    }
+
+    /// [`Document::recalculate`] through the network of one extended link
+    /// role only
+    pub fn recalculate_in_role(
+        &self,
+        role: &str,
+        overrides: &[(usize, Decimal)],
+    ) -> Vec<Recalculation> {
+        self.recalculate_network(Some(role), overrides)
+    }
+
+    fn recalculate_network(
+        &self,
+        role: Option<&str>,
+        overrides: &[(usize, Decimal)],
+    ) -> Vec<Recalculation> {
+        // Totals per child concept; an arc repeated in several roles counts once
+        let mut parents: HashMap<&str, Vec<(&str, Decimal)>> = HashMap::new();
+        for link in self.calculation_network(role) {
+            let totals = parents.entry(link.to.as_str()).or_default();
+            if !totals.iter().any(|(from, _)| *from == link.from) {
+                let weight = Decimal::from_f64(link.weight).unwrap_or_default();
+                totals.push((link.from.as_str(), weight));
+            }
+        }
+        let concept_ids: HashMap<&str, u32> = self
+            .concept_names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i as u32))
+            .collect();
+        let bound = bind_facts(self);
+
+        let mut changes: Vec<Recalculation> = Vec::new();
+        let mut positions: HashMap<usize, usize> = HashMap::new();
+        let mut change = |i: usize, delta: Decimal, source: Option<usize>| -> Option<()> {
+            let fact = self.fact(i)?;
+            let position = *positions.entry(i).or_insert_with(|| {
+                let original = fact.value().as_decimal().unwrap_or_default();
+                changes.push(Recalculation {
+                    fact_index: i,
+                    concept: fact.concept().to_string(),
+                    context_id: fact.context().map(|c| c.id.clone()).unwrap_or_default(),
+                    original,
+                    value: original,
+                    derived_from: Vec::new(),
+                });
+                changes.len() - 1
+            });
+            let entry = &mut changes[position];
+            entry.value += delta;
+            if let Some(source) = source.filter(|s| !entry.derived_from.contains(s)) {
+                entry.derived_from.push(source);
+            }
+            Some(())
+        };
+
+        let mut queue = VecDeque::new();
+        for &(i, value) in overrides {
+            let Some(current) = self.facts.values.get(i).and_then(|v| v.as_decimal()) else {
+                continue;
+            };
+            let delta = value - current;
+            if change(i, delta, None).is_some() {
+                queue.push_back((i, delta));
+            }
+        }
+
+        // Bounded so a cyclic network cannot propagate forever
+        let mut budget = (self.calculation_links.len() + 1) * (overrides.len() + 1);
+        while let Some((child, delta)) = queue.pop_front() {
+            if budget == 0 {
+                break;
+            }
+            budget -= 1;
+            let (context, unit) = (self.facts.context_ids[child], self.facts.unit_ids[child]);
+            let concept = self.concept_names[self.facts.concept_ids[child] as usize].as_str();
+            for (parent, weight) in parents.get(concept).into_iter().flatten() {
+                let Some(&Some(total)) = concept_ids
+                    .get(parent)
+                    .and_then(|id| bound.get(&(*id, context, unit)))
+                else {
+                    continue;
+                };
+                let delta = delta * weight;
+                if !delta.is_zero() && change(total, delta, Some(child)).is_some() {
+                    queue.push_back((total, delta));
+                }
+            }
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::CalculationLink;
+    use crate::Parser;
+
+    fn document(facts: &str) -> Document {
+        let mut doc = Parser::new()
+            .parse_str(&format!(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="FY"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2023-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  {}
+</xbrli:xbrl>"#,
+                facts
+            ))
+            .unwrap();
+        for (to, weight) in [("a:Product", 1.0), ("a:Service", 1.0), ("a:Returns", -1.0)] {
+            doc.calculation_links.push(CalculationLink {
+                role: "http://example.com/role/IncomeStatement".to_string(),
+                from: "a:Revenue".to_string(),
+                to: to.to_string(),
+                weight,
+                order: 1.0,
+                priority: None,
+                use_attribute: None,
+            });
+        }
+        doc
+    }
+
+    fn fact(concept: &str, value: &str, decimals: i8) -> String {
+        format!(
+            r#"<{c} contextRef="FY" unitRef="USD" decimals="{d}">{v}</{c}>"#,
+            c = concept,
+            d = decimals,
+            v = value
+        )
+    }
+
+    #[test]
+    fn test_rounding_tolerance() {
+        // 1.4M + 2.4M - 0.2M = 3.6M exactly, but at millions precision the
+        // reported children round to 1 + 2 - 0 = 3
+        let doc = document(
+            &[
+                fact("a:Revenue", "3000000", -6),
+                fact("a:Product", "1400000", -5),
+                fact("a:Service", "2400000", -5),
+                fact("a:Returns", "200000", -5),
+            ]
+            .concat(),
+        );
+        assert!(check_calculations(&doc).is_empty());
+    }
+
+    #[test]
+    fn test_inconsistency_reports_facts() {
+        let doc = document(
+            &[
+                fact("a:Revenue", "5000", 0),
+                fact("a:Product", "3000", 0),
+                fact("a:Service", "1500", 0),
+            ]
+            .concat(),
+        );
+        let errors = check_calculations(&doc);
+        assert_eq!(errors.len(), 1);
+        match &errors[0] {
+            ValidationError::CalculationInconsistency {
+                expected,
+                actual,
+                fact_index,
+                contributing,
+                context_id,
+                ..
+            } => {
+                assert_eq!(
+                    (*expected, *actual),
+                    (Decimal::from(4500), Decimal::from(5000))
+                );
+                assert_eq!(*fact_index, 0);
+                assert_eq!(contributing, &[1, 2]);
+                assert_eq!(context_id, "FY");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_exact_cent_arithmetic() {
+        // Beyond f64's precision a one-cent difference used to vanish
+        let facts = |total: &str| {
+            document(
+                &[
+                    fact("a:Revenue", total, 2),
+                    fact("a:Product", "90071992547409.93", 2),
+                    fact("a:Service", "0.01", 2),
+                ]
+                .concat(),
+            )
+        };
+        assert!(check_calculations(&facts("90071992547409.94")).is_empty());
+        let errors = check_calculations(&facts("90071992547409.95"));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0]
+            .to_string()
+            .contains("expected 90071992547409.94, found 90071992547409.95"));
+    }
+
+    #[test]
+    fn test_inconsistent_duplicates_skip_summation() {
+        let doc = document(
+            &[
+                fact("a:Revenue", "5000", 0),
+                fact("a:Product", "3000", 0),
+                fact("a:Product", "3100", 0),
+            ]
+            .concat(),
+        );
+        assert!(check_calculations(&doc).is_empty());
+    }
+
+    #[test]
+    fn test_recalculate() {
+        let mut doc = document(
+            &[
+                fact("a:Revenue", "5000", 0),
+                fact("a:Product", "3000", 0),
+                fact("a:Returns", "500", 0),
+                fact("a:GrossProfit", "2000", 0),
+            ]
+            .concat(),
+        );
+        doc.calculation_links.push(CalculationLink {
+            role: "http://example.com/role/IncomeStatement".to_string(),
+            from: "a:GrossProfit".to_string(),
+            to: "a:Revenue".to_string(),
+            weight: 1.0,
+            order: 1.0,
+            priority: None,
+            use_attribute: None,
+        });
+
+        // Service is not reported, so its implied 2500 stays in Revenue
+        let changes = doc.recalculate(&[(1, Decimal::from(3500)), (2, Decimal::from(300))]);
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|c| (c.fact_index, c.value, c.derived_from.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (1, Decimal::from(3500), vec![]),
+                (2, Decimal::from(300), vec![]),
+                (0, Decimal::from(5700), vec![1, 2]),
+                (3, Decimal::from(2700), vec![0]),
+            ]
+        );
+        assert_eq!(changes[2].original, Decimal::from(5000));
+        assert_eq!(changes[2].context_id, "FY");
+    }
+
+    #[test]
+    fn test_networks_by_role() {
+        // Revenue is split by product in one statement and by region in
+        // another; each network balances on its own
+        let segments = "http://example.com/role/Segments";
+        let mut doc = document(
+            &[
+                fact("a:Revenue", "5000", 0),
+                fact("a:Product", "3000", 0),
+                fact("a:Service", "2000", 0),
+                fact("a:Domestic", "4000", 0),
+                fact("a:Foreign", "900", 0),
+            ]
+            .concat(),
+        );
+        for to in ["a:Domestic", "a:Foreign"] {
+            doc.calculation_links.push(CalculationLink {
+                role: segments.to_string(),
+                from: "a:Revenue".to_string(),
+                to: to.to_string(),
+                weight: 1.0,
+                order: 1.0,
+                priority: None,
+                use_attribute: None,
+            });
+        }
+        assert_eq!(
+            doc.calculation_roles(),
+            ["http://example.com/role/IncomeStatement", segments]
+        );
+
+        let errors = check_calculations(&doc);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("expected 4900, found 5000"));
+        assert_eq!(check_calculations_in_role(&doc, segments).len(), 1);
+        let income = "http://example.com/role/IncomeStatement";
+        assert!(check_calculations_in_role(&doc, income).is_empty());
+
+        // Only the selected statement's total follows a change
+        let changes = doc.recalculate_in_role(segments, &[(3, Decimal::from(4100))]);
+        let moved: Vec<_> = changes.iter().map(|c| (c.fact_index, c.value)).collect();
+        assert_eq!(moved, [(3, Decimal::from(4100)), (0, Decimal::from(5100))]);
+        assert_eq!(
+            doc.recalculate_in_role(income, &[(3, Decimal::from(4100))])
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_incomplete_summations() {
+        assert!(check_completeness(&document(&fact("a:Cost", "10", 0))).is_empty());
+
+        let doc = document(&fact("a:Revenue", "5000", 0));
+        let errors = check_completeness(&doc);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            ValidationError::IncompleteSummation {
+                missing_total: false,
+                fact_index: 0,
+                ..
+            }
+        ));
+
+        let doc = document(&[fact("a:Cost", "10", 0), fact("a:Service", "1500", 0)].concat());
+        let errors = check_completeness(&doc);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].fact_index(), Some(1));
+        assert!(errors[0].to_string().contains("a:Revenue in context FY"));
+
+        let complete =
+            document(&[fact("a:Revenue", "5000", 0), fact("a:Product", "5000", 0)].concat());
+        assert!(check_completeness(&complete).is_empty());
+    }
+}
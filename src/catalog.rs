@@ -0,0 +1,262 @@
+//! Offline taxonomy catalog
+//!
+//! Maps remote schema URLs to local copies so schema-aware validation runs
+//! without network access. A bundle is a directory laid out as
+//! `<host>/<path>` with an OASIS `catalog.xml` at its root; `crabrl bundle`
+//! builds one containing the core XBRL schemas, dei and the UTR.
+
+use crate::{Error, Result};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::path::{Path, PathBuf};
+
+/// Schemas every filing depends on, bundled for offline use
+pub const CORE_SCHEMAS: &[&str] = &[
+    "http://www.xbrl.org/2003/xbrl-instance-2003-12-31.xsd",
+    "http://www.xbrl.org/2003/xbrl-linkbase-2003-12-31.xsd",
+    "http://www.xbrl.org/2003/xl-2003-12-31.xsd",
+    "http://www.xbrl.org/2003/xlink-2003-12-31.xsd",
+    "http://www.xbrl.org/2005/xbrldt-2005.xsd",
+    "http://www.xbrl.org/2006/xbrldi-2006.xsd",
+    "http://www.xbrl.org/dtr/type/numeric-2009-12-16.xsd",
+    "http://www.xbrl.org/dtr/type/nonNumeric-2009-12-16.xsd",
+    "http://www.xbrl.org/utr/utr.xml",
+    "https://xbrl.sec.gov/dei/2023/dei-2023.xsd",
+    "https://xbrl.sec.gov/dei/2024/dei-2024.xsd",
+];
+
+/// Local path of `url` inside a bundle: scheme dropped, host kept
+pub fn bundle_path(url: &str) -> Option<&str> {
+    url.strip_prefix("http://")
+        .or_else(|| url.strip_prefix("https://"))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Rule {
+    Uri { name: String, path: PathBuf },
+    Rewrite { prefix: String, path: PathBuf },
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    rules: Vec<Rule>,
+}
+
+impl Catalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bundle directory from `CRABRL_TAXONOMY_DIR`, falling back to the
+    /// user cache directory
+    pub fn default_dir() -> Option<PathBuf> {
+        if let Some(dir) = std::env::var_os("CRABRL_TAXONOMY_DIR") {
+            return Some(PathBuf::from(dir));
+        }
+        let cache = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("LOCALAPPDATA").map(PathBuf::from))
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".cache")))?;
+        Some(cache.join("crabrl").join("taxonomies"))
+    }
+
+    /// Catalog of the default bundle, or an empty catalog if there is none
+    pub fn bundled() -> Self {
+        Self::default_dir()
+            .and_then(|dir| Self::from_dir(dir).ok())
+            .unwrap_or_default()
+    }
+
+    /// Load `<dir>/catalog.xml`, or map `<host>/<path>` under `dir` when the
+    /// bundle has no catalog file
+    pub fn from_dir<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref();
+        let catalog_file = dir.join("catalog.xml");
+        if catalog_file.exists() {
+            return Self::load(catalog_file);
+        }
+        if !dir.is_dir() {
+            return Err(Error::NotFound(dir.display().to_string()));
+        }
+        let mut catalog = Self::new();
+        for scheme in ["http://", "https://"] {
+            catalog.add_rewrite(scheme, dir);
+        }
+        Ok(catalog)
+    }
+
+    /// Parse an OASIS XML catalog (`uri` and `rewriteURI` entries)
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let base = path.parent().unwrap_or(Path::new(""));
+        let content = std::fs::read(path)?;
+        let mut reader = Reader::from_reader(content.as_slice());
+        let mut buf = Vec::new();
+        let mut catalog = Self::new();
+
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                    let attr = |name: &[u8]| {
+                        e.attributes()
+                            .flatten()
+                            .find(|a| a.key.as_ref() == name)
+                            .map(|a| String::from_utf8_lossy(&a.value).into_owned())
+                    };
+                    match e.local_name().as_ref() {
+                        b"uri" => {
+                            if let (Some(name), Some(uri)) = (attr(b"name"), attr(b"uri")) {
+                                catalog.add_uri(&name, base.join(uri));
+                            }
+                        }
+                        b"rewriteURI" => {
+                            if let (Some(prefix), Some(rewrite)) =
+                                (attr(b"uriStartString"), attr(b"rewritePrefix"))
+                            {
+                                catalog.add_rewrite(&prefix, base.join(rewrite));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                Ok(Event::Eof) => break,
+                Err(e) => return Err(Error::xml(format!("Catalog {}: {}", path.display(), e))),
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        Ok(catalog)
+    }
+
+    pub fn add_uri<P: Into<PathBuf>>(&mut self, name: &str, path: P) {
+        self.rules.push(Rule::Uri {
+            name: name.to_string(),
+            path: path.into(),
+        });
+    }
+
+    pub fn add_rewrite<P: Into<PathBuf>>(&mut self, prefix: &str, path: P) {
+        self.rules.push(Rule::Rewrite {
+            prefix: prefix.to_string(),
+            path: path.into(),
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Local file for `url`, if the catalog maps it and the file exists.
+    /// Exact `uri` entries win over the longest matching rewrite prefix.
+    pub fn resolve(&self, url: &str) -> Option<PathBuf> {
+        let exact = self.rules.iter().find_map(|rule| match rule {
+            Rule::Uri { name, path } if name == url => Some(path.clone()),
+            _ => None,
+        });
+        let rewritten = || {
+            self.rules
+                .iter()
+                .filter_map(|rule| match rule {
+                    Rule::Rewrite { prefix, path } => url
+                        .strip_prefix(prefix.as_str())
+                        .map(|rest| (prefix.len(), path.join(rest))),
+                    _ => None,
+                })
+                .max_by_key(|(len, _)| *len)
+                .map(|(_, path)| path)
+        };
+        exact.or_else(rewritten).filter(|path| path.is_file())
+    }
+
+    /// Serialize as an OASIS XML catalog, with paths relative to `base`
+    pub fn to_xml(&self, base: &Path) -> String {
+        let relative = |path: &Path| {
+            let path = path.strip_prefix(base).unwrap_or(path);
+            let mut text = path.to_string_lossy().replace('\\', "/");
+            if text.is_empty() {
+                text.push('.');
+            }
+            text
+        };
+        let mut xml = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <catalog xmlns=\"urn:oasis:names:tc:entity:xmlns:xml:catalog\">\n",
+        );
+        for rule in &self.rules {
+            match rule {
+                Rule::Uri { name, path } => xml.push_str(&format!(
+                    "  <uri name=\"{}\" uri=\"{}\"/>\n",
+                    name,
+                    relative(path)
+                )),
+                Rule::Rewrite { prefix, path } => {
+                    let mut target = relative(path);
+                    if !target.ends_with('/') {
+                        target.push('/');
+                    }
+                    xml.push_str(&format!(
+                        "  <rewriteURI uriStartString=\"{}\" rewritePrefix=\"{}\"/>\n",
+                        prefix, target
+                    ))
+                }
+            }
+        }
+        xml.push_str("</catalog>\n");
+        xml
+    }
+}
+
+/// Download [`CORE_SCHEMAS`] into `dir` and write its `catalog.xml`
+#[cfg(feature = "http")]
+pub fn build_bundle<P: AsRef<Path>>(client: &crate::http::HttpClient, dir: P) -> Result<Catalog> {
+    let dir = dir.as_ref();
+    for url in CORE_SCHEMAS {
+        let relative = bundle_path(url).ok_or_else(|| Error::Parse(url.to_string()))?;
+        let target = dir.join(relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&target, client.get(url)?.as_slice())?;
+    }
+
+    let mut catalog = Catalog::new();
+    for scheme in ["http://", "https://"] {
+        catalog.add_rewrite(scheme, dir);
+    }
+    std::fs::write(dir.join("catalog.xml"), catalog.to_xml(dir))?;
+    Ok(catalog)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let schema = dir
+            .path()
+            .join(bundle_path("http://www.xbrl.org/2003/xbrl-instance-2003-12-31.xsd").unwrap());
+        std::fs::create_dir_all(schema.parent().unwrap()).unwrap();
+        std::fs::write(&schema, "<schema/>").unwrap();
+
+        let mut catalog = Catalog::new();
+        for scheme in ["http://", "https://"] {
+            catalog.add_rewrite(scheme, dir.path());
+        }
+        std::fs::write(dir.path().join("catalog.xml"), catalog.to_xml(dir.path())).unwrap();
+
+        let loaded = Catalog::from_dir(dir.path()).unwrap();
+        for url in [
+            "http://www.xbrl.org/2003/xbrl-instance-2003-12-31.xsd",
+            "https://www.xbrl.org/2003/xbrl-instance-2003-12-31.xsd",
+        ] {
+            assert_eq!(loaded.resolve(url), Some(schema.clone()));
+        }
+        assert_eq!(
+            loaded.resolve("http://www.xbrl.org/2003/xl-2003-12-31.xsd"),
+            None
+        );
+    }
+}
@@ -0,0 +1,371 @@
+//! Stable columnar fact schema
+//!
+//! One row per fact, with context and unit resolved into plain columns. The
+//! column set and order are part of the public API so that downstream tables
+//! (Arrow, DuckDB, Parquet) can rely on them across releases.
+
+use crate::model::{Context, Document, FactValue, Period};
+use std::collections::BTreeMap;
+
+/// Column names of the fact table, in order
+pub const FACT_COLUMNS: &[&str] = &[
+    "fact_id",
+    "concept",
+    "context_id",
+    "entity_scheme",
+    "entity_identifier",
+    "period_type",
+    "period_start",
+    "period_end",
+    "dimensions",
+    "unit",
+    "value",
+    "numeric_value",
+    "decimals",
+    "is_nil",
+    "numerator",
+    "denominator",
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct FactTable {
+    pub fact_id: Vec<Option<String>>,
+    pub concept: Vec<String>,
+    pub context_id: Vec<Option<String>>,
+    pub entity_scheme: Vec<Option<String>>,
+    pub entity_identifier: Vec<Option<String>>,
+    pub period_type: Vec<Option<&'static str>>,
+    pub period_start: Vec<Option<String>>,
+    pub period_end: Vec<Option<String>>,
+    pub dimensions: Vec<Option<String>>,
+    pub unit: Vec<Option<String>>,
+    pub value: Vec<Option<String>>,
+    pub numeric_value: Vec<Option<f64>>,
+    pub decimals: Vec<Option<i8>>,
+    pub is_nil: Vec<bool>,
+    /// Exact parts of fraction items; `value` holds `numerator/denominator`
+    pub numerator: Vec<Option<String>>,
+    pub denominator: Vec<Option<String>>,
+}
+
+impl FactTable {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            fact_id: Vec::with_capacity(capacity),
+            concept: Vec::with_capacity(capacity),
+            context_id: Vec::with_capacity(capacity),
+            entity_scheme: Vec::with_capacity(capacity),
+            entity_identifier: Vec::with_capacity(capacity),
+            period_type: Vec::with_capacity(capacity),
+            period_start: Vec::with_capacity(capacity),
+            period_end: Vec::with_capacity(capacity),
+            dimensions: Vec::with_capacity(capacity),
+            unit: Vec::with_capacity(capacity),
+            value: Vec::with_capacity(capacity),
+            numeric_value: Vec::with_capacity(capacity),
+            decimals: Vec::with_capacity(capacity),
+            is_nil: Vec::with_capacity(capacity),
+            numerator: Vec::with_capacity(capacity),
+            denominator: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.concept.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.concept.is_empty()
+    }
+
+    pub fn from_document(doc: &Document) -> Self {
+        let mut table = Self::with_capacity(doc.facts.len());
+
+        for fact in doc.iter_facts() {
+            let context = fact.context();
+            let (period_type, start, end) = period_cells(context.map(|c| &c.period));
+            let dimensions = context.and_then(dimensions_cell);
+            let value = fact.value();
+
+            table.fact_id.push(fact.id().map(str::to_string));
+            table.concept.push(fact.concept().to_string());
+            table.context_id.push(context.map(|c| c.id.clone()));
+            table
+                .entity_scheme
+                .push(context.map(|c| c.entity.scheme.clone()));
+            table
+                .entity_identifier
+                .push(context.map(|c| c.entity.identifier.clone()));
+            table.period_type.push(period_type);
+            table.period_start.push(start);
+            table.period_end.push(end);
+            table.dimensions.push(dimensions);
+            table.unit.push(fact.unit().map(|u| u.canonical()));
+            table
+                .value
+                .push((!value.is_nil()).then(|| value.to_string()));
+            table.numeric_value.push(value.as_f64());
+            table.decimals.push(fact.decimals());
+            table.is_nil.push(value.is_nil());
+            let (numerator, denominator) = match value {
+                FactValue::Fraction {
+                    numerator,
+                    denominator,
+                } => (Some(numerator.to_string()), Some(denominator.to_string())),
+                _ => (None, None),
+            };
+            table.numerator.push(numerator);
+            table.denominator.push(denominator);
+        }
+
+        table
+    }
+}
+
+/// `period_type`, `period_start` and `period_end` of a period
+pub(crate) fn period_cells(
+    period: Option<&Period>,
+) -> (Option<&'static str>, Option<String>, Option<String>) {
+    match period {
+        Some(Period::Instant { date, .. }) => (Some("instant"), None, Some(date.clone())),
+        Some(Period::Duration { start, end, .. }) => {
+            (Some("duration"), Some(start.clone()), Some(end.clone()))
+        }
+        Some(Period::Forever) => (Some("forever"), None, None),
+        None => (None, None, None),
+    }
+}
+
+/// `dimensions` of a context: `dimension=member` pairs joined by `;`
+pub(crate) fn dimensions_cell(context: &Context) -> Option<String> {
+    let dims = context.dimensions();
+    (!dims.is_empty()).then(|| {
+        dims.iter()
+            .map(|(dim, member)| format!("{}={}", dim, member))
+            .collect::<Vec<_>>()
+            .join(";")
+    })
+}
+
+/// A fact with its context and unit resolved, independent of the document.
+///
+/// Instants have no `period_start`; forever periods have neither bound.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NormalizedFact {
+    pub entity_scheme: String,
+    pub entity: String,
+    pub concept: String,
+    pub period_start: Option<String>,
+    pub period_end: Option<String>,
+    /// Canonical unit string, e.g. `iso4217:USD` or `iso4217:USD/xbrli:shares`
+    pub unit: Option<String>,
+    /// Dimension QName to member QName (or typed member value)
+    pub members: BTreeMap<String, String>,
+    pub value: FactValue,
+    pub decimals: Option<i8>,
+}
+
+impl Document {
+    /// Flatten facts into the stable columnar fact schema
+    pub fn fact_table(&self) -> FactTable {
+        FactTable::from_document(self)
+    }
+
+    /// Facts as self-contained records; facts whose context is missing are
+    /// skipped
+    pub fn normalized_facts(&self) -> Vec<NormalizedFact> {
+        self.iter_facts()
+            .filter_map(|fact| {
+                let context = fact.context()?;
+                let (period_start, period_end) = match &context.period {
+                    Period::Instant { date, .. } => (None, Some(date.clone())),
+                    Period::Duration { start, end, .. } => (Some(start.clone()), Some(end.clone())),
+                    Period::Forever => (None, None),
+                };
+                Some(NormalizedFact {
+                    entity_scheme: context.entity.scheme.clone(),
+                    entity: context.entity.identifier.clone(),
+                    concept: fact.concept().to_string(),
+                    period_start,
+                    period_end,
+                    unit: fact.unit().map(|u| u.canonical()),
+                    members: context
+                        .dimensions()
+                        .into_iter()
+                        .map(|(dim, member)| (dim.to_string(), member.to_string()))
+                        .collect(),
+                    value: fact.value().clone(),
+                    decimals: fact.decimals(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "arrow")]
+mod arrow_export {
+    use super::FactTable;
+    use crate::{model::Document, Error, Result};
+    use arrow_array::{ArrayRef, BooleanArray, Float64Array, Int8Array, RecordBatch, StringArray};
+    use arrow_schema::{DataType, Field, Schema};
+    use std::path::Path;
+    use std::sync::Arc;
+
+    /// Arrow schema of [`FactTable`]
+    pub fn fact_schema() -> Schema {
+        Schema::new(vec![
+            Field::new("fact_id", DataType::Utf8, true),
+            Field::new("concept", DataType::Utf8, false),
+            Field::new("context_id", DataType::Utf8, true),
+            Field::new("entity_scheme", DataType::Utf8, true),
+            Field::new("entity_identifier", DataType::Utf8, true),
+            Field::new("period_type", DataType::Utf8, true),
+            Field::new("period_start", DataType::Utf8, true),
+            Field::new("period_end", DataType::Utf8, true),
+            Field::new("dimensions", DataType::Utf8, true),
+            Field::new("unit", DataType::Utf8, true),
+            Field::new("value", DataType::Utf8, true),
+            Field::new("numeric_value", DataType::Float64, true),
+            Field::new("decimals", DataType::Int8, true),
+            Field::new("is_nil", DataType::Boolean, false),
+            Field::new("numerator", DataType::Utf8, true),
+            Field::new("denominator", DataType::Utf8, true),
+        ])
+    }
+
+    impl FactTable {
+        pub fn to_record_batch(&self) -> Result<RecordBatch> {
+            let columns: Vec<ArrayRef> = vec![
+                Arc::new(StringArray::from(self.fact_id.clone())),
+                Arc::new(StringArray::from(self.concept.clone())),
+                Arc::new(StringArray::from(self.context_id.clone())),
+                Arc::new(StringArray::from(self.entity_scheme.clone())),
+                Arc::new(StringArray::from(self.entity_identifier.clone())),
+                Arc::new(StringArray::from(self.period_type.clone())),
+                Arc::new(StringArray::from(self.period_start.clone())),
+                Arc::new(StringArray::from(self.period_end.clone())),
+                Arc::new(StringArray::from(self.dimensions.clone())),
+                Arc::new(StringArray::from(self.unit.clone())),
+                Arc::new(StringArray::from(self.value.clone())),
+                Arc::new(Float64Array::from(self.numeric_value.clone())),
+                Arc::new(Int8Array::from(self.decimals.clone())),
+                Arc::new(BooleanArray::from(self.is_nil.clone())),
+                Arc::new(StringArray::from(self.numerator.clone())),
+                Arc::new(StringArray::from(self.denominator.clone())),
+            ];
+
+            RecordBatch::try_new(Arc::new(fact_schema()), columns)
+                .map_err(|e| Error::Export(format!("Arrow record batch: {}", e)))
+        }
+    }
+
+    impl Document {
+        /// Facts as an Arrow record batch using [`fact_schema`]
+        pub fn to_arrow(&self) -> Result<RecordBatch> {
+            self.fact_table().to_record_batch()
+        }
+    }
+
+    /// Parse an XBRL file straight into a record batch.
+    ///
+    /// This is the entry point for embedding in DuckDB: hand the batch to
+    /// `duckdb::arrow_recordbatch_to_query_params` and query it with
+    /// `SELECT * FROM arrow(?, ?)`.
+    pub fn read_xbrl<P: AsRef<Path>>(path: P) -> Result<RecordBatch> {
+        crate::Parser::new().parse_file(path)?.to_arrow()
+    }
+}
+
+#[cfg(feature = "arrow")]
+pub use arrow_export::{fact_schema, read_xbrl};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::*;
+
+    fn sample_document() -> Document {
+        let mut doc = Document::new();
+        doc.concept_names.push("us-gaap:Assets".to_string());
+        doc.contexts.push(Context {
+            id: "c1".to_string(),
+            entity: Entity {
+                identifier: "0000320193".to_string(),
+                scheme: "http://www.sec.gov/CIK".to_string(),
+                segment: Some(Segment {
+                    explicit_members: vec![DimensionMember {
+                        dimension: "us-gaap:StatementBusinessSegmentsAxis".to_string(),
+                        member: "aapl:AmericasSegmentMember".to_string(),
+                    }],
+                    typed_members: Vec::new(),
+                }),
+            },
+            period: Period::instant("2023-09-30"),
+            scenario: None,
+        });
+        doc.units.push(Unit {
+            id: "USD".to_string(),
+            unit_type: UnitType::Simple(vec![Measure {
+                prefix: "iso4217".to_string(),
+                namespace: crate::efm::ISO4217_NAMESPACE.to_string(),
+                name: "USD".to_string(),
+            }]),
+        });
+        doc.facts.push(
+            0,
+            0,
+            1,
+            FactValue::Decimal(Decimal::from(352_755_000_000i64)),
+            Some(-6),
+            Some("f1".to_string()),
+        );
+        doc
+    }
+
+    #[test]
+    fn test_fact_table_rows() {
+        let table = FactTable::from_document(&sample_document());
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.concept[0], "us-gaap:Assets");
+        assert_eq!(table.period_type[0], Some("instant"));
+        assert_eq!(table.period_end[0].as_deref(), Some("2023-09-30"));
+        assert_eq!(table.unit[0].as_deref(), Some("iso4217:USD"));
+        assert_eq!(
+            table.dimensions[0].as_deref(),
+            Some("us-gaap:StatementBusinessSegmentsAxis=aapl:AmericasSegmentMember")
+        );
+        assert_eq!(table.numeric_value[0], Some(352755000000.0));
+        assert!(!table.is_nil[0]);
+    }
+
+    #[test]
+    fn test_normalized_facts() {
+        let facts = sample_document().normalized_facts();
+        assert_eq!(facts.len(), 1);
+        let fact = &facts[0];
+        assert_eq!(fact.entity, "0000320193");
+        assert_eq!(fact.period_start, None);
+        assert_eq!(fact.period_end.as_deref(), Some("2023-09-30"));
+        assert_eq!(fact.unit.as_deref(), Some("iso4217:USD"));
+        assert_eq!(
+            fact.members["us-gaap:StatementBusinessSegmentsAxis"],
+            "aapl:AmericasSegmentMember"
+        );
+        assert_eq!(fact.value.as_f64(), Some(352755000000.0));
+        assert_eq!(fact.decimals, Some(-6));
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_to_arrow_schema() {
+        let batch = sample_document().to_arrow().unwrap();
+        assert_eq!(batch.num_rows(), 1);
+        let names: Vec<_> = batch
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect();
+        assert_eq!(names, FACT_COLUMNS);
+    }
+}
@@ -0,0 +1,121 @@
+//! Compressed output streams
+//!
+//! Exports write through [`CompressedWriter`] so large datasets can be
+//! produced as `.gz` or `.zst` directly, without a temporary file.
+
+use crate::{Error, Result};
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// `gz`/`gzip` or `zst`/`zstd`
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" => Some(Compression::None),
+            "gz" | "gzip" => Some(Compression::Gzip),
+            "zst" | "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// File extension the compressed output conventionally carries
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            Compression::None => None,
+            Compression::Gzip => Some("gz"),
+            Compression::Zstd => Some("zst"),
+        }
+    }
+
+    pub fn writer<W: Write>(self, inner: W) -> Result<CompressedWriter<W>> {
+        Ok(match self {
+            Compression::None => CompressedWriter::Plain(inner),
+            Compression::Gzip => CompressedWriter::Gzip(flate2::write::GzEncoder::new(
+                inner,
+                flate2::Compression::default(),
+            )),
+            Compression::Zstd => {
+                CompressedWriter::Zstd(zstd::Encoder::new(inner, zstd::DEFAULT_COMPRESSION_LEVEL)?)
+            }
+        })
+    }
+}
+
+/// A writer compressing into `W`. Call [`CompressedWriter::finish`] to write
+/// the trailer; dropping it instead leaves the stream truncated.
+pub enum CompressedWriter<W: Write> {
+    Plain(W),
+    Gzip(flate2::write::GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+}
+
+impl<W: Write> CompressedWriter<W> {
+    /// Complete the stream and return the inner writer, flushed
+    pub fn finish(self) -> Result<W> {
+        let mut inner = match self {
+            CompressedWriter::Plain(inner) => inner,
+            CompressedWriter::Gzip(encoder) => encoder.finish()?,
+            CompressedWriter::Zstd(encoder) => encoder
+                .finish()
+                .map_err(|e| Error::Export(format!("zstd: {}", e)))?,
+        };
+        inner.flush()?;
+        Ok(inner)
+    }
+}
+
+impl<W: Write> Write for CompressedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            CompressedWriter::Plain(w) => w.write(buf),
+            CompressedWriter::Gzip(w) => w.write(buf),
+            CompressedWriter::Zstd(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            CompressedWriter::Plain(w) => w.flush(),
+            CompressedWriter::Gzip(w) => w.flush(),
+            CompressedWriter::Zstd(w) => w.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_compressed_round_trip() {
+        let data = "concept,value\nus-gaap:Revenues,100\n".repeat(100);
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            let mut writer = compression.writer(Vec::new()).unwrap();
+            writer.write_all(data.as_bytes()).unwrap();
+            let compressed = writer.finish().unwrap();
+            assert!(compressed.len() < data.len());
+
+            let mut decoded = String::new();
+            match compression {
+                Compression::Gzip => flate2::read::GzDecoder::new(&compressed[..])
+                    .read_to_string(&mut decoded)
+                    .unwrap(),
+                _ => zstd::Decoder::new(&compressed[..])
+                    .unwrap()
+                    .read_to_string(&mut decoded)
+                    .unwrap(),
+            };
+            assert_eq!(decoded, data);
+        }
+        assert_eq!(Compression::parse("zst"), Some(Compression::Zstd));
+        assert_eq!(Compression::parse("bz2"), None);
+    }
+}
@@ -0,0 +1,352 @@
+//! Duplicate and amended filing detection across a directory of filings
+//!
+//! A corpus pulled from EDGAR often holds several versions of one report:
+//! the original, its amendments, a re-filing, or the same filing as both the
+//! iXBRL document and its extracted instance. Filings are grouped by CIK,
+//! form type and period end (from dei), and the latest accepted version of
+//! each group is the one to aggregate.
+
+use crate::ixbrl::{is_inline_xbrl, InlineParser};
+use crate::model::Document;
+use crate::{Parser, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// File extensions considered when scanning a directory
+const EXTENSIONS: &[&str] = &["xml", "xbrl", "htm", "html", "xhtml", "txt"];
+
+/// One parsed filing of the corpus
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorpusEntry {
+    pub path: PathBuf,
+    /// SHA-256 of the file
+    pub sha256: String,
+    /// SHA-256 over the document's normalized facts, independent of order
+    /// and serialization; equal for an iXBRL document and its extracted
+    /// instance
+    pub fact_digest: String,
+    /// Zero-padded CIK from dei, or the entity identifier of the contexts
+    pub cik: Option<String>,
+    pub document_type: Option<String>,
+    pub period_end: Option<NaiveDate>,
+    pub amendment: bool,
+    /// Acceptance time from the EDGAR header of a `.txt` submission, else the
+    /// file's modification time
+    pub accepted: Option<NaiveDateTime>,
+}
+
+impl CorpusEntry {
+    pub fn from_document(
+        path: impl Into<PathBuf>,
+        doc: &Document,
+        accepted: Option<NaiveDateTime>,
+    ) -> Self {
+        let dei = doc.dei();
+        let cik = dei.cik.clone().or_else(|| {
+            doc.contexts
+                .iter()
+                .find(|c| c.entity.scheme.contains("sec.gov/CIK"))
+                .and_then(|c| crate::dei::normalize_cik(&c.entity.identifier))
+        });
+        let amendment = dei.amendment_flag == Some(true)
+            || dei
+                .document_type
+                .as_deref()
+                .is_some_and(|t| t.trim().ends_with("/A"));
+        Self {
+            path: path.into(),
+            sha256: doc
+                .provenance
+                .as_ref()
+                .map(|p| p.sha256.clone())
+                .unwrap_or_default(),
+            fact_digest: fact_digest(doc),
+            cik,
+            document_type: dei.document_type,
+            period_end: dei.period_end_date,
+            amendment,
+            accepted,
+        }
+    }
+
+    /// Form type without an amendment suffix, e.g. `10-K` for `10-K/A`
+    pub fn base_form(&self) -> Option<&str> {
+        let form = self.document_type.as_deref()?.trim();
+        Some(form.strip_suffix("/A").unwrap_or(form))
+    }
+}
+
+/// Digest of the normalized facts, sorted so that fact order does not matter
+pub fn fact_digest(doc: &Document) -> String {
+    use sha2::{Digest, Sha256};
+    let mut facts: Vec<String> = doc
+        .normalized_facts()
+        .iter()
+        .map(|f| format!("{:?}", f))
+        .collect();
+    facts.sort_unstable();
+    let mut hasher = Sha256::new();
+    for fact in &facts {
+        hasher.update(fact.as_bytes());
+        hasher.update(b"\n");
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Why a filing is superseded by the latest one of its group
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Supersession {
+    /// Same facts as the latest version
+    Identical,
+    /// The latest version is an amendment
+    Amended,
+    /// Filed again with different facts but without an amendment flag
+    Refiled,
+}
+
+/// Filings reporting on the same entity, form and period
+#[derive(Debug, Clone)]
+pub struct FilingGroup {
+    pub cik: String,
+    pub form: String,
+    pub period_end: NaiveDate,
+    /// Oldest first; the last entry is the version to use
+    pub entries: Vec<CorpusEntry>,
+}
+
+impl FilingGroup {
+    pub fn latest(&self) -> &CorpusEntry {
+        self.entries.last().expect("groups are never empty")
+    }
+
+    pub fn has_duplicates(&self) -> bool {
+        self.entries.len() > 1
+    }
+
+    /// Every entry but the latest, with the reason it is superseded
+    pub fn superseded(&self) -> impl Iterator<Item = (&CorpusEntry, Supersession)> {
+        let latest = self.latest();
+        self.entries[..self.entries.len() - 1]
+            .iter()
+            .map(move |entry| {
+                let reason = if entry.fact_digest == latest.fact_digest {
+                    Supersession::Identical
+                } else if latest.amendment {
+                    Supersession::Amended
+                } else {
+                    Supersession::Refiled
+                };
+                (entry, reason)
+            })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CorpusScan {
+    pub groups: Vec<FilingGroup>,
+    /// Filings without a CIK, form type or period end, which cannot be grouped
+    pub ungrouped: Vec<CorpusEntry>,
+    /// Files that failed to read or parse
+    pub errors: Vec<(PathBuf, String)>,
+}
+
+impl CorpusScan {
+    /// Groups holding more than one version
+    pub fn duplicates(&self) -> impl Iterator<Item = &FilingGroup> {
+        self.groups.iter().filter(|g| g.has_duplicates())
+    }
+
+    /// The version to use of every group, plus the ungroupable filings
+    pub fn latest(&self) -> impl Iterator<Item = &CorpusEntry> {
+        self.groups
+            .iter()
+            .map(FilingGroup::latest)
+            .chain(&self.ungrouped)
+    }
+}
+
+/// Group filings by CIK, form type and period end. Within a group entries
+/// are ordered by acceptance time, then originals before amendments.
+pub fn group_filings(entries: Vec<CorpusEntry>) -> CorpusScan {
+    let mut scan = CorpusScan::default();
+    let mut groups: BTreeMap<(String, String, NaiveDate), Vec<CorpusEntry>> = BTreeMap::new();
+
+    for entry in entries {
+        match (entry.cik.clone(), entry.base_form(), entry.period_end) {
+            (Some(cik), Some(form), Some(period_end)) => {
+                let key = (cik, form.to_string(), period_end);
+                groups.entry(key).or_default().push(entry);
+            }
+            _ => scan.ungrouped.push(entry),
+        }
+    }
+
+    for ((cik, form, period_end), mut entries) in groups {
+        entries.sort_by(|a, b| {
+            (a.accepted, a.amendment, &a.path).cmp(&(b.accepted, b.amendment, &b.path))
+        });
+        scan.groups.push(FilingGroup {
+            cik,
+            form,
+            period_end,
+            entries,
+        });
+    }
+    scan
+}
+
+/// Parse every filing under `dir` (recursively) and group the versions
+pub fn scan_directory<P: AsRef<Path>>(dir: P) -> Result<CorpusScan> {
+    let mut paths = Vec::new();
+    collect_files(dir.as_ref(), &mut paths)?;
+    paths.sort();
+
+    #[cfg(feature = "parallel")]
+    let results: Vec<Result<Option<CorpusEntry>>> = {
+        use rayon::prelude::*;
+        paths.par_iter().map(|p| read_entry(p)).collect()
+    };
+    #[cfg(not(feature = "parallel"))]
+    let results: Vec<Result<Option<CorpusEntry>>> = paths.iter().map(|p| read_entry(p)).collect();
+
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    for (path, result) in paths.into_iter().zip(results) {
+        match result {
+            Ok(Some(entry)) => entries.push(entry),
+            Ok(None) => {}
+            Err(e) => errors.push((path, e.to_string())),
+        }
+    }
+
+    let mut scan = group_filings(entries);
+    scan.errors = errors;
+    Ok(scan)
+}
+
+fn collect_files(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, paths)?;
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The filing in `path`, or `None` for files without facts (schemas,
+/// linkbases, plain HTML)
+fn read_entry(path: &Path) -> Result<Option<CorpusEntry>> {
+    let data = std::fs::read(path)?;
+    let is_submission = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("txt"));
+
+    let (doc, accepted) = if is_submission {
+        let accepted = acceptance_datetime(&data);
+        match Parser::new().parse_multi(&data)?.into_iter().next() {
+            Some(doc) => (doc, accepted),
+            None => return Ok(None),
+        }
+    } else if is_inline_xbrl(&data) {
+        (InlineParser::new().parse_bytes(&data)?, None)
+    } else {
+        (Parser::new().parse_bytes(&data)?, None)
+    };
+    if doc.facts.is_empty() {
+        return Ok(None);
+    }
+
+    let accepted = accepted.or_else(|| {
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+        Some(chrono::DateTime::<chrono::Utc>::from(modified).naive_utc())
+    });
+    Ok(Some(CorpusEntry::from_document(path, &doc, accepted)))
+}
+
+/// `<ACCEPTANCE-DATETIME>` of an EDGAR submission header, e.g. `20231102180812`
+pub fn acceptance_datetime(data: &[u8]) -> Option<NaiveDateTime> {
+    const TAG: &[u8] = b"<ACCEPTANCE-DATETIME>";
+    let head = &data[..data.len().min(64 * 1024)];
+    let start = memchr::memmem::find(head, TAG)? + TAG.len();
+    let value = head.get(start..start + 14)?;
+    NaiveDateTime::parse_from_str(std::str::from_utf8(value).ok()?, "%Y%m%d%H%M%S").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(document_type: &str, amendment: bool, revenue: u64) -> String {
+        format!(
+            r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:dei="http://xbrl.sec.gov/dei/2023" xmlns:us-gaap="http://fasb.org/us-gaap/2023">
+  <xbrli:context id="FY"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2022-10-01</xbrli:startDate><xbrli:endDate>2023-09-30</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <dei:DocumentType contextRef="FY">{}</dei:DocumentType>
+  <dei:AmendmentFlag contextRef="FY">{}</dei:AmendmentFlag>
+  <dei:DocumentPeriodEndDate contextRef="FY">2023-09-30</dei:DocumentPeriodEndDate>
+  <us-gaap:Revenues contextRef="FY" unitRef="USD" decimals="-6">{}</us-gaap:Revenues>
+</xbrli:xbrl>"#,
+            document_type, amendment, revenue
+        )
+    }
+
+    #[test]
+    fn test_scan_picks_latest_amendment() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("original.xml"),
+            instance("10-K", false, 383_285_000_000),
+        )
+        .unwrap();
+        std::fs::create_dir(dir.path().join("copies")).unwrap();
+        std::fs::write(
+            dir.path().join("copies/original-copy.xml"),
+            instance("10-K", false, 383_285_000_000),
+        )
+        .unwrap();
+        // The amendment carries an EDGAR acceptance time after the others
+        std::fs::write(
+            dir.path().join("amendment.txt"),
+            format!(
+                "<SEC-DOCUMENT>\n<ACCEPTANCE-DATETIME>29991231120000\n<XBRL>\n{}\n</XBRL>",
+                instance("10-K", true, 383_300_000_000)
+            ),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "no filing here").unwrap();
+
+        let scan = scan_directory(dir.path()).unwrap();
+        assert!(scan.errors.is_empty(), "{:?}", scan.errors);
+        assert_eq!(scan.groups.len(), 1);
+        let group = &scan.groups[0];
+        assert_eq!(
+            (group.cik.as_str(), group.form.as_str()),
+            ("0000320193", "10-K")
+        );
+        assert_eq!(group.entries.len(), 3);
+        assert!(group.latest().path.ends_with("amendment.txt"));
+        assert_eq!(
+            group.latest().accepted,
+            NaiveDate::from_ymd_opt(2999, 12, 31).and_then(|d| d.and_hms_opt(12, 0, 0))
+        );
+
+        let reasons: Vec<Supersession> = group.superseded().map(|(_, r)| r).collect();
+        assert_eq!(reasons, [Supersession::Amended, Supersession::Amended]);
+        let copies: Vec<&CorpusEntry> = group.entries[..2].iter().collect();
+        assert_eq!(copies[0].fact_digest, copies[1].fact_digest);
+        assert_eq!(scan.latest().count(), 1);
+    }
+}
@@ -0,0 +1,135 @@
+//! Concept coverage against a disclosure checklist
+//!
+//! A checklist lists the concepts a filing is expected to tag, one QName
+//! per line; blank lines and `#` comments are ignored. The report tells
+//! which of them are tagged, in which contexts, and which are missing.
+
+use crate::model::Document;
+use crate::Result;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+#[derive(Debug, Clone, Default)]
+pub struct Checklist {
+    pub concepts: Vec<String>,
+}
+
+impl Checklist {
+    pub fn parse(text: &str) -> Self {
+        let mut seen = BTreeSet::new();
+        let concepts = text
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or("").trim())
+            .filter(|concept| !concept.is_empty() && seen.insert(*concept))
+            .map(str::to_string)
+            .collect();
+        Self { concepts }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?))
+    }
+}
+
+/// A checklist concept the document tags
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConceptCoverage {
+    pub concept: String,
+    pub facts: usize,
+    /// Ids of the contexts its facts are reported in, sorted
+    pub contexts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CoverageReport {
+    /// Tagged concepts, in checklist order
+    pub tagged: Vec<ConceptCoverage>,
+    /// Untagged concepts, in checklist order
+    pub missing: Vec<String>,
+}
+
+impl CoverageReport {
+    /// Share of checklist concepts that are tagged, 1.0 for an empty checklist
+    pub fn ratio(&self) -> f64 {
+        let total = self.tagged.len() + self.missing.len();
+        if total == 0 {
+            1.0
+        } else {
+            self.tagged.len() as f64 / total as f64
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "ratio": self.ratio(),
+            "tagged": self.tagged.iter().map(|c| json!({
+                "concept": c.concept,
+                "facts": c.facts,
+                "contexts": c.contexts,
+            })).collect::<Vec<_>>(),
+            "missing": self.missing,
+        })
+    }
+}
+
+/// Which checklist concepts `doc` tags, nil facts included
+pub fn check_coverage(doc: &Document, checklist: &Checklist) -> CoverageReport {
+    let mut report = CoverageReport::default();
+    for concept in &checklist.concepts {
+        let mut facts = 0;
+        let mut contexts = BTreeSet::new();
+        for fact in doc.iter_facts().filter(|f| f.concept() == concept) {
+            facts += 1;
+            if let Some(context) = fact.context() {
+                contexts.insert(context.id.clone());
+            }
+        }
+        if facts == 0 {
+            report.missing.push(concept.clone());
+        } else {
+            report.tagged.push(ConceptCoverage {
+                concept: concept.clone(),
+                facts,
+                contexts: contexts.into_iter().collect(),
+            });
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    #[test]
+    fn test_check_coverage() {
+        let doc = Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:dei="http://xbrl.sec.gov/dei/2023" xmlns:us-gaap="http://fasb.org/us-gaap/2023">
+  <xbrli:context id="FY"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2023-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:context id="PY"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2022-01-01</xbrli:startDate><xbrli:endDate>2022-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <dei:DocumentType contextRef="FY">10-K</dei:DocumentType>
+  <us-gaap:Revenues contextRef="PY" unitRef="USD" decimals="0">90</us-gaap:Revenues>
+  <us-gaap:Revenues contextRef="FY" unitRef="USD" decimals="0">100</us-gaap:Revenues>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+        let checklist = Checklist::parse(
+            "# Cover page\ndei:DocumentType\n\nus-gaap:Revenues  # income statement\nus-gaap:Assets\ndei:DocumentType\n",
+        );
+        assert_eq!(checklist.concepts.len(), 3);
+
+        let report = check_coverage(&doc, &checklist);
+        assert_eq!(report.missing, ["us-gaap:Assets"]);
+        assert_eq!(report.tagged[1].concept, "us-gaap:Revenues");
+        assert_eq!(report.tagged[1].facts, 2);
+        assert_eq!(report.tagged[1].contexts, ["FY", "PY"]);
+        assert!((report.ratio() - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(report.to_json()["missing"][0], "us-gaap:Assets");
+    }
+}
@@ -0,0 +1,416 @@
+//! Definition linkbase relationships beyond dimensions (XBRL 2.1 §5.2.6)
+//!
+//! [`Document::definition_network`] selects the arcs of one arcrole, in one
+//! extended link role or all of them. Two of the standard arcroles constrain
+//! facts: an `essence-alias` pair reported in the same context and unit must
+//! carry the same value, and reporting the source of a `requires-element`
+//! arc requires at least one fact of its target. `general-special` and
+//! `similar-tuples` carry no fact constraints.
+//!
+//! The arcs themselves are checked against the loaded schemas: an essence
+//! and its alias share their item type, `periodType` and `balance`,
+//! `similar-tuples` arcs join tuples, and neither `general-special` nor
+//! `essence-alias` networks may contain a directed cycle.
+
+use crate::model::{ContextKey, DefinitionLink, Document, FactView};
+use crate::numeric::consistent;
+use crate::validator::ValidationError;
+use std::collections::{HashMap, HashSet};
+
+pub const GENERAL_SPECIAL_ARCROLE: &str = "http://www.xbrl.org/2003/arcrole/general-special";
+pub const ESSENCE_ALIAS_ARCROLE: &str = "http://www.xbrl.org/2003/arcrole/essence-alias";
+pub const SIMILAR_TUPLES_ARCROLE: &str = "http://www.xbrl.org/2003/arcrole/similar-tuples";
+pub const REQUIRES_ELEMENT_ARCROLE: &str = "http://www.xbrl.org/2003/arcrole/requires-element";
+
+impl Document {
+    /// Effective definition arcs with `arcrole`, in document order, of the
+    /// extended link `role` or of every role when `None`; prohibited and
+    /// overridden arcs are left out, see [`crate::network`]
+    pub fn definition_network<'d>(
+        &'d self,
+        arcrole: &'d str,
+        role: Option<&'d str>,
+    ) -> impl Iterator<Item = &'d DefinitionLink> {
+        crate::network::resolve(
+            self.definition_links
+                .iter()
+                .filter(move |link| link.arcrole == arcrole && role.is_none_or(|r| r == link.role)),
+        )
+        .into_iter()
+    }
+
+    /// Concepts `concept` specializes, directly or through others, nearest
+    /// first, following `general-special` arcs of every role
+    pub fn generalizations<'d>(&'d self, concept: &str) -> Vec<&'d str> {
+        let pairs = pairs(self, GENERAL_SPECIAL_ARCROLE);
+        let mut found: Vec<&str> = Vec::new();
+        let mut next = 0;
+        let mut current = concept;
+        loop {
+            for (general, special) in &pairs {
+                if *special == current && *general != concept && !found.contains(general) {
+                    found.push(general);
+                }
+            }
+            let Some(general) = found.get(next) else {
+                return found;
+            };
+            current = general;
+            next += 1;
+        }
+    }
+}
+
+/// (from, to) pairs of the arcs with `arcrole`; an arc repeated in several
+/// roles counts once
+fn pairs<'d>(doc: &'d Document, arcrole: &'d str) -> Vec<(&'d str, &'d str)> {
+    let mut pairs = Vec::new();
+    for link in doc.definition_network(arcrole, None) {
+        let pair = (link.from.as_str(), link.to.as_str());
+        if !pairs.contains(&pair) {
+            pairs.push(pair);
+        }
+    }
+    pairs
+}
+
+/// Alias facts whose value differs from the essence fact reported in the
+/// same context, unit and tuple
+pub fn check_essence_alias(doc: &Document) -> Vec<ValidationError> {
+    let pairs = pairs(doc, ESSENCE_ALIAS_ARCROLE);
+    if pairs.is_empty() {
+        return Vec::new();
+    }
+
+    let keys: Vec<ContextKey> = doc.contexts.iter().map(|c| c.equivalence_key()).collect();
+    let mut by_concept: HashMap<&str, Vec<FactView>> = HashMap::new();
+    for fact in doc.iter_facts() {
+        if fact.context().is_some() && !fact.value().is_nil() {
+            by_concept.entry(fact.concept()).or_default().push(fact);
+        }
+    }
+    let same_slot = |a: &FactView, b: &FactView| {
+        let context = |f: &FactView| &keys[doc.facts.context_ids[f.index()] as usize];
+        context(a) == context(b)
+            && a.unit().map(|u| u.canonical()) == b.unit().map(|u| u.canonical())
+            && a.tuple_path() == b.tuple_path()
+    };
+    let agree = |a: &FactView, b: &FactView| match (a.value().as_decimal(), b.value().as_decimal())
+    {
+        (Some(x), Some(y)) => consistent(x, a.decimals(), y, b.decimals()),
+        _ => a.value() == b.value(),
+    };
+
+    let mut errors = Vec::new();
+    for (essence, alias) in pairs {
+        let (Some(essences), Some(aliases)) = (by_concept.get(essence), by_concept.get(alias))
+        else {
+            continue;
+        };
+        for alias_fact in aliases {
+            let conflict = essences
+                .iter()
+                .find(|e| same_slot(e, alias_fact) && !agree(e, alias_fact));
+            if let Some(essence_fact) = conflict {
+                errors.push(ValidationError::EssenceAliasInconsistent {
+                    essence: essence.to_string(),
+                    alias: alias.to_string(),
+                    context_id: alias_fact
+                        .context()
+                        .map(|c| c.id.clone())
+                        .unwrap_or_default(),
+                    essence_value: essence_fact.value().to_string(),
+                    alias_value: alias_fact.value().to_string(),
+                    fact_index: alias_fact.index(),
+                });
+            }
+        }
+    }
+    errors.sort_by_key(|e| e.fact_index());
+    errors
+}
+
+/// Concepts reported without a fact of a concept they require
+pub fn check_requires_element(doc: &Document) -> Vec<ValidationError> {
+    let pairs = pairs(doc, REQUIRES_ELEMENT_ARCROLE);
+    if pairs.is_empty() {
+        return Vec::new();
+    }
+
+    // First fact of every reported concept; nil facts count as reported
+    let mut first: HashMap<&str, usize> = HashMap::new();
+    for fact in doc.iter_facts() {
+        first.entry(fact.concept()).or_insert(fact.index());
+    }
+    let mut errors = Vec::new();
+    for (concept, required) in pairs {
+        let Some(&fact_index) = first.get(concept) else {
+            continue;
+        };
+        if !first.contains_key(required) {
+            errors.push(ValidationError::RequiredElementMissing {
+                concept: concept.to_string(),
+                required: required.to_string(),
+                fact_index,
+            });
+        }
+    }
+    errors.sort_by_key(|e| e.fact_index());
+    errors
+}
+
+/// Definition arcs their arcrole does not allow between their concepts;
+/// concepts no loaded schema declares are not compared
+pub fn check_definition_arcs(doc: &Document) -> Vec<ValidationError> {
+    // Prefixes differ between schemas, so types compare by local name
+    let local = |qname: &str| qname.rsplit(':').next().unwrap_or(qname).to_string();
+    let is_tuple = |concept: &str| {
+        doc.concept_info(concept)
+            .and_then(|e| e.substitution_group.as_deref())
+            .map(|group| local(group) == "tuple")
+    };
+    let mut errors = Vec::new();
+    let mut invalid = |link: &DefinitionLink, detail: String| {
+        errors.push(ValidationError::InvalidDefinitionArc {
+            arcrole: link.arcrole.clone(),
+            from: link.from.clone(),
+            to: link.to.clone(),
+            detail,
+        })
+    };
+
+    for link in doc.definition_network(ESSENCE_ALIAS_ARCROLE, None) {
+        let (Some(essence), Some(alias)) =
+            (doc.concept_info(&link.from), doc.concept_info(&link.to))
+        else {
+            continue;
+        };
+        let attributes = [
+            (
+                "type",
+                Some(local(&essence.element_type)),
+                Some(local(&alias.element_type)),
+            ),
+            (
+                "periodType",
+                essence.period_type.clone(),
+                alias.period_type.clone(),
+            ),
+            ("balance", essence.balance.clone(), alias.balance.clone()),
+        ];
+        for (attribute, essence, alias) in attributes {
+            if essence != alias {
+                let show = |v: Option<String>| v.unwrap_or_else(|| "none".to_string());
+                invalid(
+                    link,
+                    format!(
+                        "the essence has {} {} but the alias has {}",
+                        attribute,
+                        show(essence),
+                        show(alias)
+                    ),
+                );
+            }
+        }
+    }
+    for link in doc.definition_network(SIMILAR_TUPLES_ARCROLE, None) {
+        for concept in [&link.from, &link.to] {
+            if is_tuple(concept) == Some(false) {
+                invalid(link, format!("{} is not a tuple", concept));
+            }
+        }
+    }
+    for arcrole in [GENERAL_SPECIAL_ARCROLE, ESSENCE_ALIAS_ARCROLE] {
+        for link in cycle_closing_arcs(doc, arcrole) {
+            invalid(
+                link,
+                "closes a directed cycle, which the arcrole does not allow".to_string(),
+            );
+        }
+    }
+    errors
+}
+
+/// Arcs with `arcrole` that lead back to a concept already on the path to
+/// them, within one extended link role
+fn cycle_closing_arcs<'d>(doc: &'d Document, arcrole: &'d str) -> Vec<&'d DefinitionLink> {
+    let mut by_role: HashMap<&str, HashMap<&str, Vec<&DefinitionLink>>> = HashMap::new();
+    for link in doc.definition_network(arcrole, None) {
+        by_role
+            .entry(link.role.as_str())
+            .or_default()
+            .entry(link.from.as_str())
+            .or_default()
+            .push(link);
+    }
+    let mut roles: Vec<_> = by_role.into_iter().collect();
+    roles.sort_by_key(|(role, _)| *role);
+
+    let mut closing = Vec::new();
+    for (_, arcs) in roles {
+        let mut roots: Vec<&str> = arcs.keys().copied().collect();
+        roots.sort();
+        let mut done: HashSet<&str> = HashSet::new();
+        for root in roots {
+            if done.contains(root) {
+                continue;
+            }
+            // Depth-first, with the concepts on the current path
+            let mut path: Vec<&str> = vec![root];
+            let mut stack = vec![(root, 0)];
+            while let Some((concept, next)) = stack.pop() {
+                let outgoing = arcs.get(concept).map_or(&[][..], Vec::as_slice);
+                let Some(link) = outgoing.get(next) else {
+                    done.insert(concept);
+                    path.pop();
+                    continue;
+                };
+                stack.push((concept, next + 1));
+                let target = link.to.as_str();
+                if path.contains(&target) {
+                    closing.push(*link);
+                } else if !done.contains(target) {
+                    path.push(target);
+                    stack.push((target, 0));
+                }
+            }
+        }
+    }
+    closing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    #[test]
+    fn test_definition_arcroles() {
+        let mut doc = Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:context id="p"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2022-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <a:Cash contextRef="c" unitRef="USD" decimals="-3">1000000</a:Cash>
+  <a:CashAlias contextRef="c" unitRef="USD" decimals="0">1000200</a:CashAlias>
+  <a:Cash contextRef="p" unitRef="USD" decimals="0">900</a:Cash>
+  <a:CashAlias contextRef="p" unitRef="USD" decimals="0">950</a:CashAlias>
+  <a:Name contextRef="c">Acme</a:Name>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+        let arc = |role: &str, from: &str, to: &str, arcrole: &str| DefinitionLink {
+            role: role.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            arcrole: arcrole.to_string(),
+            order: 1.0,
+            priority: None,
+            use_attribute: None,
+        };
+        let role = "http://example.com/role/Cash";
+        doc.definition_links.extend([
+            arc(role, "a:Cash", "a:CashAlias", ESSENCE_ALIAS_ARCROLE),
+            arc(role, "a:Name", "a:Ticker", REQUIRES_ELEMENT_ARCROLE),
+            arc(role, "a:Name", "a:Cash", REQUIRES_ELEMENT_ARCROLE),
+            arc(
+                "http://example.com/role/Other",
+                "a:Name",
+                "a:Ticker",
+                REQUIRES_ELEMENT_ARCROLE,
+            ),
+            arc(role, "a:Debt", "a:Ticker", REQUIRES_ELEMENT_ARCROLE),
+        ]);
+
+        assert_eq!(
+            doc.definition_network(REQUIRES_ELEMENT_ARCROLE, None)
+                .count(),
+            4
+        );
+        assert_eq!(
+            doc.definition_network(REQUIRES_ELEMENT_ARCROLE, Some(role))
+                .count(),
+            3
+        );
+        assert_eq!(
+            doc.definition_network(GENERAL_SPECIAL_ARCROLE, None)
+                .count(),
+            0
+        );
+
+        // 1,000,200 agrees with 1,000,000 at thousands; 950 is not 900
+        let errors = check_essence_alias(&doc);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].fact_index(), Some(3));
+        assert_eq!(
+            errors[0].to_string(),
+            "a:CashAlias = 950 is an alias of a:Cash = 900 in context p, but the values differ"
+        );
+
+        // Reported once per missing pair, across roles
+        let errors = check_requires_element(&doc);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].fact_index(), Some(4));
+        assert_eq!(errors[0].rule(), "requires-element");
+    }
+
+    #[test]
+    fn test_definition_arc_constraints() {
+        let mut doc = Document::new();
+        doc.namespaces
+            .insert("a".to_string(), "http://example.com/a".to_string());
+        doc.add_schema(
+            crate::dts::parse_schema(
+                br#"<xsd:schema xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xbrli="http://www.xbrl.org/2003/instance" targetNamespace="http://example.com/a">
+  <xsd:element name="Cash" type="xbrli:monetaryItemType" substitutionGroup="xbrli:item" xbrli:periodType="instant" xbrli:balance="debit"/>
+  <xsd:element name="CashAlias" type="xbrli:monetaryItemType" substitutionGroup="xbrli:item" xbrli:periodType="instant" xbrli:balance="debit"/>
+  <xsd:element name="CashFlow" type="xbrli:monetaryItemType" substitutionGroup="xbrli:item" xbrli:periodType="duration" xbrli:balance="debit"/>
+  <xsd:element name="Address" substitutionGroup="xbrli:tuple"><xsd:complexType><xsd:sequence/></xsd:complexType></xsd:element>
+  <xsd:element name="Location" substitutionGroup="xbrli:tuple"><xsd:complexType><xsd:sequence/></xsd:complexType></xsd:element>
+</xsd:schema>"#,
+            )
+            .unwrap(),
+        );
+        let arc = |role: &str, from: &str, to: &str, arcrole: &str| DefinitionLink {
+            role: role.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            arcrole: arcrole.to_string(),
+            order: 1.0,
+            priority: None,
+            use_attribute: None,
+        };
+        let role = "http://example.com/role/Arcs";
+        let other = "http://example.com/role/Other";
+        doc.definition_links.extend([
+            arc(role, "a:Cash", "a:CashAlias", ESSENCE_ALIAS_ARCROLE),
+            arc(role, "a:Cash", "a:CashFlow", ESSENCE_ALIAS_ARCROLE),
+            arc(role, "a:Address", "a:Location", SIMILAR_TUPLES_ARCROLE),
+            arc(role, "a:Address", "a:Cash", SIMILAR_TUPLES_ARCROLE),
+            arc(role, "a:Assets", "a:Cash", GENERAL_SPECIAL_ARCROLE),
+            arc(role, "a:Cash", "a:Restricted", GENERAL_SPECIAL_ARCROLE),
+            arc(role, "a:Restricted", "a:Cash", GENERAL_SPECIAL_ARCROLE),
+            // A cycle only across roles is allowed
+            arc(other, "a:Restricted", "a:Assets", GENERAL_SPECIAL_ARCROLE),
+        ]);
+
+        let errors = check_definition_arcs(&doc);
+        let found: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            found,
+            [
+                "essence-alias arc from a:Cash to a:CashFlow: the essence has periodType instant but the alias has duration",
+                "similar-tuples arc from a:Address to a:Cash: a:Cash is not a tuple",
+                "general-special arc from a:Restricted to a:Cash: closes a directed cycle, which the arcrole does not allow",
+            ]
+        );
+        assert_eq!(errors[0].rule(), "definition-arc");
+
+        assert_eq!(doc.generalizations("a:Restricted"), ["a:Cash", "a:Assets"]);
+        assert_eq!(doc.generalizations("a:Cash"), ["a:Assets", "a:Restricted"]);
+        assert!(doc.generalizations("a:Address").is_empty());
+    }
+}
@@ -0,0 +1,1145 @@
+//! Typed access to Document and Entity Information (dei) cover-page facts
+
+use crate::model::{Document, FactView, Period};
+use crate::validator::ValidationError;
+use chrono::NaiveDate;
+use std::collections::{HashMap, HashSet};
+
+/// Dimension used by cover pages to break dei facts down per share class
+pub const SHARE_CLASS_AXIS: &str = "StatementClassOfStockAxis";
+
+/// `dei:DocumentType` values (`submissionTypeItemType`). Amendments use the
+/// base form type with `dei:AmendmentFlag` set, so `/A` types are not listed.
+pub const DOCUMENT_TYPES: &[&str] = &[
+    "1-K",
+    "1-SA",
+    "1-U",
+    "1-Z",
+    "10-12B",
+    "10-12G",
+    "10-D",
+    "10-K",
+    "10-KT",
+    "10-Q",
+    "10-QT",
+    "11-K",
+    "11-KT",
+    "15F-12B",
+    "15F-12G",
+    "15F-15D",
+    "18-K",
+    "20-F",
+    "20FR12B",
+    "20FR12G",
+    "40-F",
+    "40FR12B",
+    "40FR12G",
+    "424A",
+    "424B1",
+    "424B2",
+    "424B3",
+    "424B4",
+    "424B5",
+    "424B7",
+    "424B8",
+    "485BPOS",
+    "497",
+    "6-K",
+    "8-K",
+    "8-K12B",
+    "8-K12G3",
+    "8-K15D5",
+    "ABS-15G",
+    "ABS-EE",
+    "ANNLRPT",
+    "DSTRBRPT",
+    "F-1",
+    "F-10",
+    "F-10EF",
+    "F-10POS",
+    "F-3",
+    "F-3ASR",
+    "F-3D",
+    "F-3DPOS",
+    "F-3MEF",
+    "F-4",
+    "F-4 POS",
+    "F-4EF",
+    "F-4MEF",
+    "F-6",
+    "F-6 POS",
+    "F-6EF",
+    "F-7",
+    "F-7 POS",
+    "F-8",
+    "F-8 POS",
+    "F-80",
+    "F-80POS",
+    "F-9",
+    "F-9 POS",
+    "F-N",
+    "F-X",
+    "K SDR",
+    "L SDR",
+    "N-1A",
+    "N-2",
+    "N-2 POSASR",
+    "N-2ASR",
+    "N-2MEF",
+    "N-CSR",
+    "N-CSRS",
+    "N-Q",
+    "N-VP",
+    "N-VPFS",
+    "POS AM",
+    "POS EX",
+    "POS462B",
+    "POS462C",
+    "POSASR",
+    "RW",
+    "S-1",
+    "S-11",
+    "S-11MEF",
+    "S-1MEF",
+    "S-20",
+    "S-3",
+    "S-3ASR",
+    "S-3D",
+    "S-3DPOS",
+    "S-3MEF",
+    "S-4",
+    "S-4 POS",
+    "S-4EF",
+    "S-4MEF",
+    "S-6",
+    "S-8",
+    "S-8 POS",
+    "S-B",
+    "S-BMEF",
+    "SD",
+    "SF-1",
+    "SF-3",
+    "SP 15D2",
+    "Other",
+];
+
+/// `dei:EntityFilerCategory` values (`filerCategoryItemType`)
+pub const FILER_CATEGORIES: &[&str] = &[
+    "Large Accelerated Filer",
+    "Accelerated Filer",
+    "Non-accelerated Filer",
+];
+
+/// `dei:DocumentFiscalPeriodFocus` values (`fiscalPeriodItemType`)
+pub const FISCAL_PERIODS: &[&str] = &[
+    "FY", "Q1", "Q2", "Q3", "Q4", "H1", "H2", "M9", "T1", "T2", "T3", "M8", "CY",
+];
+
+/// Lexical constraint on a dei concept's value
+#[derive(Debug, Clone, Copy)]
+enum DeiConstraint {
+    OneOf(&'static [&'static str]),
+    Boolean,
+    YesNo,
+    MonthDay,
+    Year,
+}
+
+impl DeiConstraint {
+    fn accepts(self, value: &str) -> bool {
+        match self {
+            DeiConstraint::OneOf(values) => values.contains(&value),
+            DeiConstraint::Boolean => matches!(value, "true" | "false" | "1" | "0"),
+            DeiConstraint::YesNo => matches!(value, "Yes" | "No"),
+            DeiConstraint::MonthDay => MonthDay::parse(value).is_some(),
+            DeiConstraint::Year => value.len() == 4 && value.bytes().all(|b| b.is_ascii_digit()),
+        }
+    }
+
+    fn expected(self) -> String {
+        match self {
+            DeiConstraint::OneOf(values) if values.len() <= 13 => values.join(", "),
+            DeiConstraint::OneOf(_) => "an EDGAR submission type".to_string(),
+            DeiConstraint::Boolean => "true or false".to_string(),
+            DeiConstraint::YesNo => "Yes or No".to_string(),
+            DeiConstraint::MonthDay => "--MM-DD".to_string(),
+            DeiConstraint::Year => "YYYY".to_string(),
+        }
+    }
+}
+
+/// dei concepts whose values come from a fixed set or pattern
+const CONSTRAINED: &[(&str, DeiConstraint)] = &[
+    ("DocumentType", DeiConstraint::OneOf(DOCUMENT_TYPES)),
+    (
+        "EntityFilerCategory",
+        DeiConstraint::OneOf(FILER_CATEGORIES),
+    ),
+    ("AmendmentFlag", DeiConstraint::Boolean),
+    ("CurrentFiscalYearEndDate", DeiConstraint::MonthDay),
+    (
+        "DocumentFiscalPeriodFocus",
+        DeiConstraint::OneOf(FISCAL_PERIODS),
+    ),
+    ("DocumentFiscalYearFocus", DeiConstraint::Year),
+    ("EntitySmallBusiness", DeiConstraint::Boolean),
+    ("EntityEmergingGrowthCompany", DeiConstraint::Boolean),
+    ("EntityShellCompany", DeiConstraint::Boolean),
+    ("IcfrAuditorAttestationFlag", DeiConstraint::Boolean),
+    ("EntityWellKnownSeasonedIssuer", DeiConstraint::YesNo),
+    ("EntityVoluntaryFilers", DeiConstraint::YesNo),
+    ("EntityCurrentReportingStatus", DeiConstraint::YesNo),
+];
+
+/// Month/day of a `--MM-DD` gMonthDay value, e.g. a fiscal year end
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MonthDay {
+    pub month: u32,
+    pub day: u32,
+}
+
+impl MonthDay {
+    pub fn parse(value: &str) -> Option<Self> {
+        let rest = value.trim().strip_prefix("--")?;
+        let (month, day) = rest.split_once('-')?;
+        if month.len() != 2 || day.len() != 2 {
+            return None;
+        }
+        let month: u32 = month.parse().ok()?;
+        let day: u32 = day.parse().ok()?;
+        // 2000 is a leap year, so --02-29 is accepted
+        NaiveDate::from_ymd_opt(2000, month, day)?;
+        Some(Self { month, day })
+    }
+}
+
+/// A dei fact that was reported but could not be interpreted
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeiFieldError {
+    pub concept: &'static str,
+    pub value: String,
+    pub reason: &'static str,
+}
+
+/// Cover-page information of a filing
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dei {
+    pub document_type: Option<String>,
+    pub period_end_date: Option<NaiveDate>,
+    pub registrant_name: Option<String>,
+    /// Central Index Key, zero-padded to 10 digits
+    pub cik: Option<String>,
+    pub fiscal_year_end: Option<MonthDay>,
+    pub amendment_flag: Option<bool>,
+    /// Total shares outstanding across all classes
+    pub shares_outstanding: Option<f64>,
+    pub errors: Vec<DeiFieldError>,
+}
+
+impl Dei {
+    pub fn from_document(doc: &Document) -> Self {
+        let mut dei = Dei::default();
+
+        if let Some(value) = dei_text(doc, "DocumentType") {
+            dei.document_type = Some(value);
+        }
+
+        if let Some(value) = dei_text(doc, "DocumentPeriodEndDate") {
+            match NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d") {
+                Ok(date) => dei.period_end_date = Some(date),
+                Err(_) => dei.reject("DocumentPeriodEndDate", value, "expected YYYY-MM-DD"),
+            }
+        }
+
+        if let Some(value) = dei_text(doc, "EntityRegistrantName") {
+            if value.trim().is_empty() {
+                dei.reject("EntityRegistrantName", value, "empty registrant name");
+            } else {
+                dei.registrant_name = Some(value.trim().to_string());
+            }
+        }
+
+        if let Some(value) = dei_text(doc, "EntityCentralIndexKey") {
+            match normalize_cik(&value) {
+                Some(cik) => dei.cik = Some(cik),
+                None => dei.reject("EntityCentralIndexKey", value, "expected up to 10 digits"),
+            }
+        }
+
+        if let Some(value) = dei_text(doc, "CurrentFiscalYearEndDate") {
+            match MonthDay::parse(&value) {
+                Some(md) => dei.fiscal_year_end = Some(md),
+                None => dei.reject("CurrentFiscalYearEndDate", value, "expected --MM-DD"),
+            }
+        }
+
+        if let Some(value) = dei_text(doc, "AmendmentFlag") {
+            match value.trim() {
+                "true" | "1" => dei.amendment_flag = Some(true),
+                "false" | "0" => dei.amendment_flag = Some(false),
+                _ => dei.reject("AmendmentFlag", value, "expected boolean"),
+            }
+        }
+
+        let shares: Vec<FactView<'_>> = dei_facts(doc, "EntityCommonStockSharesOutstanding");
+        if !shares.is_empty() {
+            // A plain total wins over per-class breakdowns
+            let undimensioned = shares
+                .iter()
+                .find(|f| f.context().is_some_and(|c| c.dimensions().is_empty()));
+            let total = match undimensioned {
+                Some(fact) => fact.value().as_f64(),
+                None => shares.iter().map(|f| f.value().as_f64()).sum(),
+            };
+            match total {
+                Some(total) => dei.shares_outstanding = Some(total),
+                None => dei.reject(
+                    "EntityCommonStockSharesOutstanding",
+                    shares[0].value().to_string(),
+                    "expected numeric value",
+                ),
+            }
+        }
+
+        dei
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    fn reject(&mut self, concept: &'static str, value: String, reason: &'static str) {
+        self.errors.push(DeiFieldError {
+            concept,
+            value,
+            reason,
+        });
+    }
+}
+
+/// Zero-pad a CIK to its canonical 10-digit form
+pub fn normalize_cik(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() || value.len() > 10 || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!("{:0>10}", value))
+}
+
+pub(crate) fn is_dei_concept(concept: &str, local_name: &str) -> bool {
+    concept
+        .split_once(':')
+        .is_some_and(|(prefix, local)| local == local_name && prefix.starts_with("dei"))
+}
+
+pub(crate) fn dei_facts<'a>(doc: &'a Document, local_name: &str) -> Vec<FactView<'a>> {
+    doc.iter_facts()
+        .filter(|f| is_dei_concept(f.concept(), local_name))
+        .collect()
+}
+
+/// First non-nil value, preferring facts without dimensions
+fn dei_text(doc: &Document, local_name: &str) -> Option<String> {
+    let facts = dei_facts(doc, local_name);
+    facts
+        .iter()
+        .filter(|f| !f.value().is_nil())
+        .min_by_key(|f| f.context().map(|c| c.dimensions().len()).unwrap_or(0))
+        .map(|f| f.value().to_string())
+}
+
+/// Cover-page values reported for one share class
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShareClass {
+    /// Member QName on `StatementClassOfStockAxis`
+    pub member: String,
+    pub shares_outstanding: Option<f64>,
+    pub trading_symbol: Option<String>,
+    pub security_12b_title: Option<String>,
+    pub security_exchange_name: Option<String>,
+}
+
+impl ShareClass {
+    /// Registered under Section 12(b) if any exchange listing fact is present
+    pub fn is_exchange_registered(&self) -> bool {
+        self.trading_symbol.is_some()
+            || self.security_12b_title.is_some()
+            || self.security_exchange_name.is_some()
+    }
+}
+
+/// Share class member of the fact's context, if any
+fn share_class_member<'a>(fact: &FactView<'a>) -> Option<&'a str> {
+    fact.context()?
+        .dimensions()
+        .into_iter()
+        .find(|(dim, _)| dim.rsplit(':').next() == Some(SHARE_CLASS_AXIS))
+        .map(|(_, member)| member)
+}
+
+impl Document {
+    /// Typed dei cover-page information
+    pub fn dei(&self) -> Dei {
+        Dei::from_document(self)
+    }
+
+    /// Members of `StatementClassOfStockAxis` used by dei facts, in document order
+    pub fn share_class_members(&self) -> Vec<&str> {
+        let mut members: Vec<&str> = Vec::new();
+        for fact in self.iter_facts() {
+            if !fact.concept().starts_with("dei") {
+                continue;
+            }
+            if let Some(member) = share_class_member(&fact) {
+                if !members.contains(&member) {
+                    members.push(member);
+                }
+            }
+        }
+        members
+    }
+
+    /// Per-class cover-page values for every share class member
+    pub fn share_classes(&self) -> Vec<ShareClass> {
+        let mut classes: Vec<ShareClass> = self
+            .share_class_members()
+            .into_iter()
+            .map(|member| ShareClass {
+                member: member.to_string(),
+                ..Default::default()
+            })
+            .collect();
+
+        for fact in self.iter_facts() {
+            let Some(member) = share_class_member(&fact) else {
+                continue;
+            };
+            let Some(class) = classes.iter_mut().find(|c| c.member == member) else {
+                continue;
+            };
+            let value = fact.value();
+            if value.is_nil() {
+                continue;
+            }
+            let concept = fact.concept();
+            if is_dei_concept(concept, "EntityCommonStockSharesOutstanding") {
+                class.shares_outstanding = value.as_f64();
+            } else if is_dei_concept(concept, "TradingSymbol") {
+                class.trading_symbol = Some(value.to_string());
+            } else if is_dei_concept(concept, "Security12bTitle") {
+                class.security_12b_title = Some(value.to_string());
+            } else if is_dei_concept(concept, "SecurityExchangeName") {
+                class.security_exchange_name = Some(value.to_string());
+            }
+        }
+
+        classes
+    }
+}
+
+/// Check that cover-page tagging is complete for every share class
+pub fn validate_share_classes(doc: &Document) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+
+    for class in doc.share_classes() {
+        if class.shares_outstanding.is_none() {
+            errors.push(ValidationError::MissingRequiredElement {
+                element: format!(
+                    "dei:EntityCommonStockSharesOutstanding for share class {}",
+                    class.member
+                ),
+            });
+        }
+
+        if class.is_exchange_registered() {
+            let listing = [
+                ("dei:Security12bTitle", class.security_12b_title.is_some()),
+                ("dei:TradingSymbol", class.trading_symbol.is_some()),
+                (
+                    "dei:SecurityExchangeName",
+                    class.security_exchange_name.is_some(),
+                ),
+            ];
+            for (concept, present) in listing {
+                if !present {
+                    errors.push(ValidationError::MissingRequiredElement {
+                        element: format!("{} for share class {}", concept, class.member),
+                    });
+                }
+            }
+        }
+    }
+
+    errors
+}
+
+/// A [`DeiConstraint`] with its enumeration hashed, see [`crate::efm::RulePack`]
+#[derive(Debug, Clone)]
+pub(crate) struct CompiledConstraint {
+    constraint: DeiConstraint,
+    values: HashSet<&'static str>,
+    expected: String,
+}
+
+impl CompiledConstraint {
+    fn accepts(&self, value: &str) -> bool {
+        match self.constraint {
+            DeiConstraint::OneOf(_) => self.values.contains(value),
+            constraint => constraint.accepts(value),
+        }
+    }
+}
+
+/// Constraints of [`CONSTRAINED`] keyed by local name
+pub(crate) fn compile_constraints() -> HashMap<&'static str, CompiledConstraint> {
+    CONSTRAINED
+        .iter()
+        .map(|(local, constraint)| {
+            let values = match constraint {
+                DeiConstraint::OneOf(values) => values.iter().copied().collect(),
+                _ => HashSet::new(),
+            };
+            let compiled = CompiledConstraint {
+                constraint: *constraint,
+                values,
+                expected: constraint.expected(),
+            };
+            (*local, compiled)
+        })
+        .collect()
+}
+
+/// Check every dei fact with an enumerated or patterned value set
+pub fn validate_dei_values(doc: &Document) -> Vec<ValidationError> {
+    crate::efm::RulePack::shared().check_dei_values(doc)
+}
+
+pub(crate) fn check_dei_values(
+    doc: &Document,
+    constraints: &HashMap<&'static str, CompiledConstraint>,
+) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+
+    for fact in doc.iter_facts() {
+        if fact.value().is_nil() {
+            continue;
+        }
+        let concept = fact.concept();
+        let constraint = concept
+            .split_once(':')
+            .filter(|(prefix, _)| prefix.starts_with("dei"))
+            .and_then(|(_, local)| constraints.get(local));
+        let Some(constraint) = constraint else {
+            continue;
+        };
+        let value = fact.value().to_string();
+        if !constraint.accepts(value.trim()) {
+            errors.push(ValidationError::InvalidDeiValue {
+                concept: concept.to_string(),
+                value,
+                expected: constraint.expected.clone(),
+                fact_index: fact.index(),
+            });
+        }
+    }
+
+    errors
+}
+
+/// Period lengths, in months, a fiscal period focus may be reported over
+/// (the period itself or the fiscal year to date), and how many months
+/// after the fiscal year end it closes. `None` for trimester and calendar
+/// foci, which are not cross-checked.
+fn focus_months(focus: &str) -> Option<(&'static [u32], u32)> {
+    Some(match focus {
+        "FY" => (&[12], 12),
+        "Q1" => (&[3], 3),
+        "Q2" => (&[3, 6], 6),
+        "Q3" => (&[3, 9], 9),
+        "Q4" => (&[3, 12], 12),
+        "H1" => (&[6], 6),
+        "H2" => (&[6, 12], 12),
+        "M9" => (&[9], 9),
+        _ => return None,
+    })
+}
+
+// 52-53 week years and quarters of 13 weeks drift from calendar months
+const FOCUS_TOLERANCE_DAYS: i64 = 20;
+
+fn months_to_days(months: u32) -> i64 {
+    (months as f64 * 30.44).round() as i64
+}
+
+/// Days from the last fiscal year end on or before `date` to `date`
+fn days_since_fiscal_year_end(date: NaiveDate, fiscal_year_end: MonthDay) -> Option<i64> {
+    use chrono::Datelike;
+    (date.year() - 1..=date.year())
+        .rev()
+        .filter_map(|year| {
+            // --02-29 falls back to the 28th in common years
+            NaiveDate::from_ymd_opt(year, fiscal_year_end.month, fiscal_year_end.day)
+                .or_else(|| NaiveDate::from_ymd_opt(year, fiscal_year_end.month, 28))
+        })
+        .find(|end| *end <= date)
+        .map(|end| (date - end).num_days())
+}
+
+/// Cross-check `dei:DocumentFiscalYearFocus` and
+/// `dei:DocumentFiscalPeriodFocus` against the document period end date, the
+/// fiscal year end and the duration of the context they are reported in
+pub fn validate_fiscal_focus(doc: &Document) -> Vec<ValidationError> {
+    use chrono::Datelike;
+    let mut errors = Vec::new();
+    let dei = doc.dei();
+    let mut reject = |fact: &FactView<'_>, expected: String| {
+        errors.push(ValidationError::InvalidDeiValue {
+            concept: fact.concept().to_string(),
+            value: fact.value().to_string(),
+            expected,
+            fact_index: fact.index(),
+        });
+    };
+
+    if let Some(period_end) = dei.period_end_date {
+        for fact in dei_facts(doc, "DocumentFiscalYearFocus") {
+            let Ok(year) = fact.value().to_string().trim().parse::<i32>() else {
+                continue;
+            };
+            // Fiscal years ending early in a calendar year are often named
+            // after the previous one
+            if (year - period_end.year()).abs() > 1 {
+                reject(
+                    &fact,
+                    format!(
+                        "a fiscal year within one year of DocumentPeriodEndDate {}",
+                        period_end
+                    ),
+                );
+            }
+        }
+    }
+
+    for fact in dei_facts(doc, "DocumentFiscalPeriodFocus") {
+        let focus = fact.value().to_string();
+        let Some((lengths, months_after_year_end)) = focus_months(focus.trim()) else {
+            continue;
+        };
+
+        if let Some(period @ Period::Duration { .. }) = fact.context().map(|c| &c.period) {
+            if let Some(duration) = period.duration() {
+                let days = duration.num_days();
+                let fits = lengths
+                    .iter()
+                    .any(|m| (days - months_to_days(*m)).abs() <= FOCUS_TOLERANCE_DAYS);
+                if !fits {
+                    let months: Vec<String> = lengths.iter().map(u32::to_string).collect();
+                    reject(
+                        &fact,
+                        format!(
+                            "a {} month context for {}, found {} days",
+                            months.join(" or "),
+                            focus.trim(),
+                            days
+                        ),
+                    );
+                }
+            }
+        }
+
+        let (Some(period_end), Some(fiscal_year_end)) = (dei.period_end_date, dei.fiscal_year_end)
+        else {
+            continue;
+        };
+        let Some(elapsed) = days_since_fiscal_year_end(period_end, fiscal_year_end) else {
+            continue;
+        };
+        let expected = months_to_days(months_after_year_end);
+        let (off, closes) = match months_after_year_end {
+            // Closing on the fiscal year end reads as 0 or 12 months in
+            12 => (
+                elapsed.min((elapsed - expected).abs()),
+                "at the fiscal year end".to_string(),
+            ),
+            m => (
+                (elapsed - expected).abs(),
+                format!("about {} months after the fiscal year end", m),
+            ),
+        };
+        if off > FOCUS_TOLERANCE_DAYS {
+            reject(
+                &fact,
+                format!(
+                    "a period closing {} --{:02}-{:02}, but DocumentPeriodEndDate is {}",
+                    closes, fiscal_year_end.month, fiscal_year_end.day, period_end
+                ),
+            );
+        }
+    }
+
+    errors
+}
+
+// 52-53 week years end up to a week from the calendar date
+const ALIGNMENT_TOLERANCE_DAYS: i64 = 7;
+
+/// Whether `date` is `anchor` or `years` years before it
+fn years_before(date: NaiveDate, anchor: NaiveDate, years: i32) -> bool {
+    use chrono::Datelike;
+    // Feb 29 falls back to the 28th in common years
+    let shifted = anchor
+        .with_year(anchor.year() - years)
+        .or_else(|| anchor.pred_opt()?.with_year(anchor.year() - years));
+    shifted.is_some_and(|s| (date - s).num_days().abs() <= ALIGNMENT_TOLERANCE_DAYS)
+}
+
+/// Whether `date` falls in the current or the prior fiscal period of `anchor`
+fn current_or_prior(date: NaiveDate, anchor: NaiveDate) -> bool {
+    (0..=1).any(|years| years_before(date, anchor, years))
+}
+
+/// Whether `date` is `anchor` or a whole number of years before it
+fn on_anniversary(date: NaiveDate, anchor: NaiveDate) -> bool {
+    use chrono::Datelike;
+    let years = anchor.year() - date.year();
+    (years - 1..=years + 1)
+        .filter(|y| *y >= 0)
+        .any(|y| years_before(date, anchor, y))
+}
+
+/// Cross-check the reporting-period contexts of undimensioned facts against
+/// the reporting period, the context of `dei:DocumentType` ending on
+/// `dei:DocumentPeriodEndDate`. A duration starting with the current or the
+/// prior fiscal period, and covering at least half of it, must end with it.
+/// Other durations, such as event periods or quarters within a year, are
+/// not checked. Instants are checked only for balance-sheet concepts, those
+/// the loaded schemas declare with a balance and an instant period type:
+/// they fall on the period end, a year end before it, a fiscal year end,
+/// or the day before a period ending on one of those. Each offending
+/// context is reported once, with its first fact.
+pub fn validate_period_alignment(doc: &Document) -> Vec<ValidationError> {
+    let dei = doc.dei();
+    let required = doc
+        .iter_facts()
+        .find(|f| is_dei_concept(f.concept(), "DocumentType") && !f.value().is_nil())
+        .and_then(|f| f.context());
+    let Some(period_start) = required
+        .filter(|c| matches!(c.period, Period::Duration { .. }))
+        .and_then(|c| c.period.start_date())
+    else {
+        return Vec::new();
+    };
+    let Some(period_end) = dei
+        .period_end_date
+        .or_else(|| required.and_then(|c| c.period.end_date()))
+    else {
+        return Vec::new();
+    };
+    // Shorter durations from the period start are events or interim periods
+    let min_days = (period_end - period_start).num_days() / 2;
+    let on_period_boundary = |date: NaiveDate| {
+        on_anniversary(date, period_end)
+            || dei.fiscal_year_end.is_some_and(|fye| {
+                days_since_fiscal_year_end(date, fye).is_some_and(|days| {
+                    days <= ALIGNMENT_TOLERANCE_DAYS || days >= 365 - ALIGNMENT_TOLERANCE_DAYS
+                })
+            })
+    };
+    // Opening balances are reported the day before a duration starts
+    let openings: HashSet<NaiveDate> = doc
+        .contexts
+        .iter()
+        .filter(|c| matches!(c.period, Period::Duration { .. }))
+        .filter(|c| {
+            c.period
+                .end_date()
+                .is_some_and(|end| on_anniversary(end, period_end))
+        })
+        .filter_map(|c| c.period.start_date()?.pred_opt())
+        .collect();
+
+    let mut errors = Vec::new();
+    let mut reported = HashSet::new();
+    for fact in doc.iter_facts() {
+        let Some(context) = fact.context() else {
+            continue;
+        };
+        let (prefix, _) = fact.concept().split_once(':').unwrap_or_default();
+        if prefix.starts_with("dei")
+            || fact.value().is_nil()
+            || !context.dimensions().is_empty()
+            || reported.contains(&context.id)
+        {
+            continue;
+        }
+        let misaligned = match &context.period {
+            Period::Duration { .. } => {
+                let (Some(start), Some(end)) =
+                    (context.period.start_date(), context.period.end_date())
+                else {
+                    continue;
+                };
+                ((end - start).num_days() >= min_days
+                    && current_or_prior(start, period_start)
+                    && !current_or_prior(end, period_end))
+                .then(|| format!("a period ending {} or a year before", period_end))
+            }
+            Period::Instant { .. } => {
+                let balance_sheet = doc.concept_info(fact.concept()).is_some_and(|e| {
+                    e.balance.is_some() && e.period_type.as_deref() == Some("instant")
+                });
+                context
+                    .period
+                    .end_date()
+                    .filter(|date| {
+                        balance_sheet && !on_period_boundary(*date) && !openings.contains(date)
+                    })
+                    .map(|_| {
+                        format!(
+                            "an instant on {}, a year end before, or opening a reported period",
+                            period_end
+                        )
+                    })
+            }
+            Period::Forever => None,
+        };
+        if let Some(expected) = misaligned {
+            reported.insert(&context.id);
+            errors.push(ValidationError::MisalignedPeriod {
+                context_id: context.id.clone(),
+                period: context.period.canonical(),
+                expected,
+                fact_index: fact.index(),
+            });
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::*;
+
+    fn add_text(doc: &mut Document, concept: &str, value: &str) {
+        let concept_id = doc.intern_concept(concept);
+        doc.facts.push(
+            concept_id,
+            0,
+            0,
+            FactValue::Text(value.to_string()),
+            None,
+            None,
+        );
+    }
+
+    fn document() -> Document {
+        let mut doc = Document::new();
+        doc.contexts.push(Context {
+            id: "FY2023".to_string(),
+            entity: Entity {
+                identifier: "320193".to_string(),
+                scheme: "http://www.sec.gov/CIK".to_string(),
+                segment: None,
+            },
+            period: Period::between("2022-10-01", "2023-09-30".to_string()),
+            scenario: None,
+        });
+        doc
+    }
+
+    #[test]
+    fn test_dei_fields() {
+        let mut doc = document();
+        add_text(&mut doc, "dei:DocumentType", "10-K");
+        add_text(&mut doc, "dei:DocumentPeriodEndDate", "2023-09-30");
+        add_text(&mut doc, "dei:EntityRegistrantName", "Apple Inc.");
+        add_text(&mut doc, "dei:EntityCentralIndexKey", "320193");
+        add_text(&mut doc, "dei:CurrentFiscalYearEndDate", "--09-30");
+        add_text(&mut doc, "dei:AmendmentFlag", "false");
+        let id = doc.intern_concept("dei:EntityCommonStockSharesOutstanding");
+        doc.facts
+            .push(id, 0, 0, FactValue::Integer(15550061000), Some(0), None);
+
+        let dei = doc.dei();
+        assert!(dei.is_valid(), "{:?}", dei.errors);
+        assert_eq!(dei.document_type.as_deref(), Some("10-K"));
+        assert_eq!(dei.period_end_date, NaiveDate::from_ymd_opt(2023, 9, 30));
+        assert_eq!(dei.cik.as_deref(), Some("0000320193"));
+        assert_eq!(dei.fiscal_year_end, Some(MonthDay { month: 9, day: 30 }));
+        assert_eq!(dei.amendment_flag, Some(false));
+        assert_eq!(dei.shares_outstanding, Some(15550061000.0));
+    }
+
+    #[test]
+    fn test_share_classes() {
+        let mut doc = document();
+        for (i, member) in ["us-gaap:CommonClassAMember", "us-gaap:CommonClassBMember"]
+            .iter()
+            .enumerate()
+        {
+            let mut ctx = doc.contexts[0].clone();
+            ctx.id = format!("class{}", i);
+            ctx.entity.segment = Some(Segment {
+                explicit_members: vec![DimensionMember {
+                    dimension: "us-gaap:StatementClassOfStockAxis".to_string(),
+                    member: member.to_string(),
+                }],
+                typed_members: Vec::new(),
+            });
+            doc.contexts.push(ctx);
+        }
+        let shares = doc.intern_concept("dei:EntityCommonStockSharesOutstanding");
+        let symbol = doc.intern_concept("dei:TradingSymbol");
+        doc.facts
+            .push(shares, 1, 0, FactValue::Integer(100), Some(0), None);
+        doc.facts
+            .push(shares, 2, 0, FactValue::Integer(50), Some(0), None);
+        doc.facts.push(
+            symbol,
+            1,
+            0,
+            FactValue::Text("GOOGL".to_string()),
+            None,
+            None,
+        );
+
+        let classes = doc.share_classes();
+        assert_eq!(classes.len(), 2);
+        assert_eq!(classes[0].trading_symbol.as_deref(), Some("GOOGL"));
+        assert_eq!(classes[1].shares_outstanding, Some(50.0));
+        assert_eq!(doc.dei().shares_outstanding, Some(150.0));
+
+        // Class A lists a symbol but no 12(b) title or exchange
+        let errors = validate_share_classes(&doc);
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn test_dei_enumerations() {
+        let mut doc = document();
+        add_text(&mut doc, "dei:DocumentType", "10-K");
+        add_text(&mut doc, "dei:DocumentType", "10-K/A");
+        add_text(
+            &mut doc,
+            "dei:EntityFilerCategory",
+            "Large Accelerated Filer",
+        );
+        add_text(
+            &mut doc,
+            "dei:EntityFilerCategory",
+            "Smaller Reporting Company",
+        );
+        add_text(&mut doc, "dei:AmendmentFlag", "no");
+        add_text(&mut doc, "dei:CurrentFiscalYearEndDate", "09-30");
+        add_text(&mut doc, "dei:EntityCurrentReportingStatus", "Yes");
+
+        let errors = validate_dei_values(&doc);
+        let invalid: Vec<(usize, &str)> = errors
+            .iter()
+            .map(|e| match e {
+                ValidationError::InvalidDeiValue {
+                    fact_index, value, ..
+                } => (*fact_index, value.as_str()),
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            invalid,
+            [
+                (1, "10-K/A"),
+                (3, "Smaller Reporting Company"),
+                (4, "no"),
+                (5, "09-30")
+            ]
+        );
+        assert_eq!(errors[1].code(), "efm.6.5.20.deiValue");
+        assert!(errors[1].to_string().contains("Non-accelerated Filer"));
+    }
+
+    #[test]
+    fn test_fiscal_focus() {
+        let mut doc = document();
+        add_text(&mut doc, "dei:DocumentPeriodEndDate", "2023-09-30");
+        add_text(&mut doc, "dei:CurrentFiscalYearEndDate", "--09-30");
+        add_text(&mut doc, "dei:DocumentFiscalYearFocus", "2023");
+        add_text(&mut doc, "dei:DocumentFiscalPeriodFocus", "FY");
+        assert!(validate_fiscal_focus(&doc).is_empty());
+
+        // A 13-week second quarter of a 52-53 week year
+        let mut quarter = document();
+        quarter.contexts[0].period = Period::between("2023-01-01", "2023-04-01".to_string());
+        add_text(&mut quarter, "dei:DocumentPeriodEndDate", "2023-04-01");
+        add_text(&mut quarter, "dei:CurrentFiscalYearEndDate", "--09-30");
+        add_text(&mut quarter, "dei:DocumentFiscalPeriodFocus", "Q2");
+        assert!(validate_fiscal_focus(&quarter).is_empty());
+
+        // Q2 over a full year ending on the fiscal year end, focus 2021
+        doc.facts.values[2] = FactValue::Text("2021".to_string());
+        doc.facts.values[3] = FactValue::Text("Q2".to_string());
+        let errors = validate_fiscal_focus(&doc);
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors.iter().all(|e| e.rule() == "dei-value"));
+        assert!(errors[1].to_string().contains("3 or 6 month context"));
+        assert!(errors[2].to_string().contains("about 6 months after"));
+    }
+
+    #[test]
+    fn test_period_alignment() {
+        let mut doc = document();
+        add_text(&mut doc, "dei:DocumentType", "10-K");
+        add_text(&mut doc, "dei:DocumentPeriodEndDate", "2023-09-30");
+        add_text(&mut doc, "dei:CurrentFiscalYearEndDate", "--09-30");
+        let periods = [
+            ("PY", Some("2021-10-01"), "2022-09-30"),
+            ("I2023", None, "2023-09-30"),
+            ("Mar15", None, "2023-03-15"),
+            ("H1", Some("2023-01-01"), "2023-06-30"),
+            ("Event", Some("2022-10-03"), "2022-10-03"),
+            ("Short", Some("2022-10-01"), "2023-08-31"),
+            ("PYShort", Some("2021-10-01"), "2022-07-31"),
+        ];
+        for (id, start, end) in periods {
+            let mut ctx = doc.contexts[0].clone();
+            ctx.id = id.to_string();
+            ctx.period = match start {
+                Some(start) => Period::between(start.to_string(), end.to_string()),
+                None => Period::instant(end.to_string()),
+            };
+            doc.contexts.push(ctx);
+        }
+        let assets = doc.intern_concept("us-gaap:Assets");
+        let revenue = doc.intern_concept("us-gaap:Revenues");
+        // Instants, interim and event periods are not reporting periods
+        for (concept, context) in [
+            (revenue, 0),
+            (revenue, 1),
+            (assets, 2),
+            (assets, 3),
+            (revenue, 4),
+            (revenue, 5),
+        ] {
+            doc.facts
+                .push(concept, context, 1, FactValue::Integer(1), Some(0), None);
+        }
+        assert!(validate_period_alignment(&doc).is_empty());
+
+        for (concept, context) in [(revenue, 6), (revenue, 6), (revenue, 7)] {
+            doc.facts
+                .push(concept, context, 1, FactValue::Integer(1), Some(0), None);
+        }
+        let errors = validate_period_alignment(&doc);
+        let contexts: Vec<_> = errors
+            .iter()
+            .map(|e| match e {
+                ValidationError::MisalignedPeriod { context_id, .. } => context_id.as_str(),
+                _ => "",
+            })
+            .collect();
+        assert_eq!(contexts, ["Short", "PYShort"]);
+        assert_eq!(errors[0].fact_index(), Some(9));
+        assert_eq!(errors[0].rule(), "period-alignment");
+        assert_eq!(errors[0].severity(), crate::Severity::Warning);
+
+        // Without a reporting period there is nothing to align to
+        assert!(validate_period_alignment(&document()).is_empty());
+    }
+
+    #[test]
+    fn test_balance_sheet_instants() {
+        let mut doc = document();
+        add_text(&mut doc, "dei:DocumentType", "10-K");
+        add_text(&mut doc, "dei:DocumentPeriodEndDate", "2023-09-30");
+        for (id, date) in [
+            ("I2023", "2023-09-30"),
+            ("I2022", "2022-09-30"),
+            ("Opening", "2020-12-31"),
+            ("Mar15", "2023-03-15"),
+        ] {
+            let mut ctx = doc.contexts[0].clone();
+            ctx.id = id.to_string();
+            ctx.period = Period::instant(date.to_string());
+            doc.contexts.push(ctx);
+        }
+        let mut nine_months = doc.contexts[0].clone();
+        nine_months.id = "9M2021".to_string();
+        nine_months.period = Period::between("2021-01-01", "2021-09-30".to_string());
+        doc.contexts.push(nine_months);
+        let assets = doc.intern_concept("us-gaap:Assets");
+        let shares = doc.intern_concept("us-gaap:StockRepurchasedDuringPeriodShares");
+        for (concept, context) in [
+            (assets, 1),
+            (assets, 2),
+            (assets, 3),
+            (shares, 4),
+            (assets, 4),
+        ] {
+            doc.facts
+                .push(concept, context, 1, FactValue::Integer(1), Some(0), None);
+        }
+        // Without schemas no concept is known to be a balance-sheet item
+        assert!(validate_period_alignment(&doc).is_empty());
+
+        let element = |name: &str, period_type: &str, balance: Option<&str>| SchemaElement {
+            name: name.to_string(),
+            id: None,
+            element_type: "xbrli:monetaryItemType".to_string(),
+            substitution_group: Some("xbrli:item".to_string()),
+            period_type: Some(period_type.to_string()),
+            balance: balance.map(str::to_string),
+            abstract_element: false,
+            nillable: false,
+            content: None,
+        };
+        doc.namespaces.insert(
+            "us-gaap".to_string(),
+            "http://fasb.org/us-gaap/2023".to_string(),
+        );
+        doc.schemas.push(Schema {
+            target_namespace: "http://fasb.org/us-gaap/2023".to_string(),
+            elements: [
+                element("Assets", "instant", Some("debit")),
+                element("StockRepurchasedDuringPeriodShares", "instant", None),
+            ]
+            .into_iter()
+            .map(|e| (e.name.clone(), e))
+            .collect(),
+            types: HashMap::new(),
+            imports: Vec::new(),
+            role_types: Vec::new(),
+            arcrole_types: Vec::new(),
+            groups: HashMap::new(),
+            location: None,
+        });
+        let errors = validate_period_alignment(&doc);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(matches!(
+            &errors[0],
+            ValidationError::MisalignedPeriod { context_id, fact_index: 6, .. } if context_id == "Mar15"
+        ));
+
+        // Selectable without the EFM checks
+        let config = crate::ValidationConfig {
+            period_alignment: true,
+            ..Default::default()
+        };
+        let result = crate::Validator::with_config(config)
+            .validate(&doc)
+            .unwrap();
+        assert!(result.findings.iter().any(|f| f.rule == "period-alignment"));
+        let result = crate::Validator::new().validate(&doc).unwrap();
+        assert!(!result.findings.iter().any(|f| f.rule == "period-alignment"));
+    }
+
+    #[test]
+    fn test_dei_invalid_values() {
+        let mut doc = document();
+        add_text(&mut doc, "dei:DocumentPeriodEndDate", "September 30, 2023");
+        add_text(&mut doc, "dei:CurrentFiscalYearEndDate", "--13-01");
+
+        let dei = doc.dei();
+        assert_eq!(dei.period_end_date, None);
+        assert_eq!(dei.errors.len(), 2);
+        assert_eq!(dei.errors[0].concept, "DocumentPeriodEndDate");
+    }
+}
@@ -0,0 +1,323 @@
+//! Fact-level comparison of two documents
+//!
+//! Facts are aligned on what they report rather than on context ids, which
+//! differ between filings: entity, concept, period, unit and dimension
+//! members. Periods compare by the time they cover, so an end date of
+//! `2023-09-30` matches `2023-10-01T00:00:00`. Within one document,
+//! duplicates of the same key collapse to the most precise fact.
+//!
+//! [`DocumentDiff::to_json`] lists the added, removed and changed facts,
+//! with the difference of changed numeric values.
+//!
+//! [`restatements`] builds on the alignment to find values a later filing
+//! changed for the periods both documents cover.
+
+use crate::columnar::NormalizedFact;
+use crate::model::{canonical_end, canonical_start, Decimal, Document, FactValue};
+use crate::numeric::round;
+use serde_json::{json, Value};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// What a fact reports, independent of the document it comes from
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FactKey {
+    pub entity: String,
+    pub concept: String,
+    pub period_start: Option<String>,
+    pub period_end: Option<String>,
+    pub unit: Option<String>,
+    pub members: BTreeMap<String, String>,
+}
+
+impl FactKey {
+    pub fn of(fact: &NormalizedFact) -> Self {
+        Self {
+            entity: fact.entity.clone(),
+            concept: fact.concept.clone(),
+            period_start: fact.period_start.as_deref().map(canonical_start),
+            period_end: fact.period_end.as_deref().map(canonical_end),
+            unit: fact.unit.clone(),
+            members: fact.members.clone(),
+        }
+    }
+}
+
+/// Facts keyed for alignment, the most precise one per key
+fn keyed_facts(doc: &Document) -> BTreeMap<FactKey, NormalizedFact> {
+    let mut facts: BTreeMap<FactKey, NormalizedFact> = BTreeMap::new();
+    for fact in doc.normalized_facts() {
+        let precision = |f: &NormalizedFact| f.decimals.map_or(i16::MAX, i16::from);
+        match facts.entry(FactKey::of(&fact)) {
+            Entry::Occupied(mut e) => {
+                if precision(&fact) > precision(e.get()) {
+                    e.insert(fact);
+                }
+            }
+            Entry::Vacant(e) => {
+                e.insert(fact);
+            }
+        }
+    }
+    facts
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DocumentDiff {
+    /// Only in the new document
+    pub added: Vec<NormalizedFact>,
+    /// Only in the old document
+    pub removed: Vec<NormalizedFact>,
+    /// `(old, new)` pairs whose values differ
+    pub changed: Vec<(NormalizedFact, NormalizedFact)>,
+    pub unchanged: usize,
+}
+
+impl DocumentDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "added": self.added.iter().map(fact_json).collect::<Vec<_>>(),
+            "removed": self.removed.iter().map(fact_json).collect::<Vec<_>>(),
+            "changed": self.changed.iter().map(|(old, new)| json!({
+                "old": fact_json(old),
+                "new": fact_json(new),
+                "delta": delta(old, new).map(|d| d.to_string()),
+            })).collect::<Vec<_>>(),
+            "unchanged": self.unchanged,
+        })
+    }
+}
+
+/// `new - old` when both values are numeric
+pub fn delta(old: &NormalizedFact, new: &NormalizedFact) -> Option<Decimal> {
+    new.value.as_decimal()?.checked_sub(old.value.as_decimal()?)
+}
+
+fn fact_json(fact: &NormalizedFact) -> Value {
+    json!({
+        "concept": fact.concept,
+        "entity": fact.entity,
+        "period_start": fact.period_start,
+        "period_end": fact.period_end,
+        "unit": fact.unit,
+        "dimensions": fact.members,
+        "value": match &fact.value {
+            FactValue::Nil => Value::Null,
+            value => json!(value.to_string()),
+        },
+        "decimals": fact.decimals,
+    })
+}
+
+/// Align the facts of two documents and compare their values. Numeric values
+/// are compared exactly; see [`restatements`] for a decimals-aware check.
+pub fn diff_documents(old: &Document, new: &Document) -> DocumentDiff {
+    let mut old_facts = keyed_facts(old);
+    let mut diff = DocumentDiff::default();
+    for (key, fact) in keyed_facts(new) {
+        match old_facts.remove(&key) {
+            Some(previous) if previous.value == fact.value => diff.unchanged += 1,
+            Some(previous) => diff.changed.push((previous, fact)),
+            None => diff.added.push(fact),
+        }
+    }
+    diff.removed = old_facts.into_values().collect();
+    diff
+}
+
+/// A numeric fact whose value changed between filings
+#[derive(Debug, Clone, PartialEq)]
+pub struct Restatement {
+    pub original: NormalizedFact,
+    pub restated: NormalizedFact,
+    /// `restated - original`, at the precision both are reported to
+    pub difference: Decimal,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RestatementReport {
+    /// Earliest and latest date covered by both documents
+    pub overlap: Option<(String, String)>,
+    /// Numeric facts reported by both documents within the overlap
+    pub compared: usize,
+    pub restatements: Vec<Restatement>,
+}
+
+/// Numeric facts of `original` that `later` reports again with a value
+/// differing beyond the precision both report. Only periods inside the date
+/// range both documents cover are compared.
+pub fn restatements(original: &Document, later: &Document) -> RestatementReport {
+    let (old_facts, new_facts) = (keyed_facts(original), keyed_facts(later));
+    let mut report = RestatementReport {
+        overlap: overlap(&old_facts, &new_facts),
+        ..Default::default()
+    };
+    let Some((from, to)) = report.overlap.clone() else {
+        return report;
+    };
+
+    for (key, restated) in &new_facts {
+        // Forever facts hold for every period, so they are always compared
+        let forever = key.period_start.is_none() && key.period_end.is_none();
+        let within = forever
+            || key.period_start.as_deref().unwrap_or("") >= from.as_str()
+                && key
+                    .period_end
+                    .as_deref()
+                    .is_some_and(|end| end <= to.as_str());
+        let Some(original) = old_facts.get(key).filter(|_| within) else {
+            continue;
+        };
+        let (Some(old_value), Some(new_value)) =
+            (original.value.as_decimal(), restated.value.as_decimal())
+        else {
+            continue;
+        };
+        report.compared += 1;
+
+        let decimals = match (original.decimals, restated.decimals) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        let difference = round(new_value, decimals) - round(old_value, decimals);
+        if !difference.is_zero() {
+            report.restatements.push(Restatement {
+                original: original.clone(),
+                restated: restated.clone(),
+                difference,
+            });
+        }
+    }
+    report
+}
+
+/// Date range covered by the numeric facts of both documents
+fn overlap(
+    a: &BTreeMap<FactKey, NormalizedFact>,
+    b: &BTreeMap<FactKey, NormalizedFact>,
+) -> Option<(String, String)> {
+    fn span(facts: &BTreeMap<FactKey, NormalizedFact>) -> Option<(&str, &str)> {
+        let dates: BTreeSet<&str> = facts
+            .iter()
+            .filter(|(_, f)| f.value.as_decimal().is_some())
+            .flat_map(|(k, _)| [k.period_start.as_deref(), k.period_end.as_deref()])
+            .flatten()
+            .collect();
+        Some((dates.first()?, dates.last()?))
+    }
+    let (a_from, a_to) = span(a)?;
+    let (b_from, b_to) = span(b)?;
+    let (from, to) = (a_from.max(b_from), a_to.min(b_to));
+    (from <= to).then(|| (from.to_string(), to.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    /// A filing whose FY2023 context has id `context`
+    fn filing(context: &str, facts: &str) -> Document {
+        Parser::new()
+            .parse_str(&format!(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="{ctx}"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2023-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  {facts}
+</xbrli:xbrl>"#,
+                ctx = context,
+                facts = facts.replace("CTX", context)
+            ))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_restatements_beyond_decimals() {
+        let original = filing(
+            "FY2023",
+            r#"<a:Revenue contextRef="CTX" unitRef="USD" decimals="-3">1200000</a:Revenue>
+  <a:Cost contextRef="CTX" unitRef="USD" decimals="-3">500000</a:Cost>
+  <a:Policy contextRef="CTX">Old</a:Policy>"#,
+        );
+        // Same period under another context id; cost is only more precise,
+        // revenue is restated
+        let later = filing(
+            "c-2023",
+            r#"<a:Revenue contextRef="CTX" unitRef="USD" decimals="-3">1150000</a:Revenue>
+  <a:Cost contextRef="CTX" unitRef="USD" decimals="0">500400</a:Cost>
+  <a:Policy contextRef="CTX">New</a:Policy>"#,
+        );
+
+        let report = restatements(&original, &later);
+        assert_eq!(
+            report.overlap,
+            Some(("2023-01-01".to_string(), "2023-12-31".to_string()))
+        );
+        assert_eq!(report.compared, 2);
+        assert_eq!(report.restatements.len(), 1);
+        let restated = &report.restatements[0];
+        assert_eq!(restated.original.concept, "a:Revenue");
+        assert_eq!(restated.difference, Decimal::from(-50_000));
+
+        let diff = diff_documents(&original, &later);
+        assert_eq!((diff.changed.len(), diff.unchanged), (3, 0));
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        let json = diff.to_json();
+        let revenue = &json["changed"][2];
+        assert_eq!(revenue["new"]["concept"], "a:Revenue");
+        assert_eq!(revenue["old"]["value"], "1200000");
+        assert_eq!(revenue["delta"], "-50000");
+        assert!(json["changed"][1]["delta"].is_null());
+    }
+
+    #[test]
+    fn test_diff_deltas_and_json() {
+        let old = filing(
+            "FY2023",
+            r#"<a:Revenue contextRef="CTX" unitRef="USD" decimals="2">1.50</a:Revenue>
+  <a:Cost contextRef="CTX" unitRef="USD" decimals="0">500</a:Cost>
+  <a:Assets contextRef="CTX" unitRef="USD" decimals="0">900</a:Assets>
+  <a:Policy contextRef="CTX">Same</a:Policy>"#,
+        );
+        let new = filing(
+            "c-1",
+            r#"<a:Revenue contextRef="CTX" unitRef="USD" decimals="2">2.25</a:Revenue>
+  <a:Cost contextRef="CTX" unitRef="USD" xsi:nil="true" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"/>
+  <a:Liabilities contextRef="CTX" unitRef="USD" decimals="0">-40</a:Liabilities>
+  <a:Policy contextRef="CTX">Same</a:Policy>"#,
+        );
+
+        let diff = diff_documents(&old, &new);
+        assert!(!diff.is_empty());
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].concept, "a:Liabilities");
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].concept, "a:Assets");
+        let (cost, revenue) = (&diff.changed[0], &diff.changed[1]);
+        assert_eq!(revenue.0.concept, "a:Revenue");
+        assert_eq!(delta(&revenue.0, &revenue.1), "0.75".parse().ok());
+        // A value turning nil has no delta
+        assert_eq!(delta(&cost.0, &cost.1), None);
+
+        let json = diff.to_json();
+        assert_eq!(json["unchanged"], 1);
+        let added = &json["added"][0];
+        assert_eq!(added["value"], "-40");
+        assert_eq!(added["unit"], "iso4217:USD");
+        assert_eq!(added["entity"], "0000000001");
+        assert_eq!(added["period_start"], "2023-01-01");
+        assert_eq!(added["decimals"], 0);
+        assert_eq!(json["removed"][0]["concept"], "a:Assets");
+        assert_eq!(json["changed"][0]["new"]["value"], Value::Null);
+        assert!(json["changed"][0]["delta"].is_null());
+        assert_eq!(json["changed"][1]["delta"], "0.75");
+
+        assert!(diff_documents(&old, &old).is_empty());
+    }
+}
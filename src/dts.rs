@@ -0,0 +1,955 @@
+//! Discoverable Taxonomy Set (DTS) discovery
+//!
+//! Starting from an instance's schemaRef, follows `xsd:import`,
+//! `xsd:include`, `link:linkbaseRef`, `link:loc`, `link:roleRef` and
+//! `link:arcroleRef` references (XBRL 2.1 §3.2). Remote documents are read
+//! from the offline [`Catalog`] when possible; anything else is subject to
+//! the loader's [`NetworkPolicy`].
+
+use crate::catalog::Catalog;
+use crate::model::{
+    ArcroleType, Document, Occurs, Particle, RoleType, Schema, SchemaElement, SchemaImport,
+    SchemaType, TypeRestriction,
+};
+use crate::{Error, Result};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// What DTS discovery may do when a document is not available locally.
+/// Nothing is fetched unless [`NetworkPolicy::Allow`] is chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NetworkPolicy {
+    /// Fail discovery on the first remote fetch
+    Deny,
+    /// Leave the document out of the DTS and record a warning
+    #[default]
+    WarnAndSkip,
+    /// Fetch over HTTP (requires the `http` feature)
+    Allow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DtsDocumentKind {
+    Schema,
+    Linkbase,
+}
+
+#[derive(Debug, Clone)]
+pub struct DtsDocument {
+    pub uri: String,
+    pub kind: DtsDocumentKind,
+    /// Local file the document was read from; `None` when fetched remotely
+    pub local_path: Option<PathBuf>,
+    /// Resolved `xsd:import` and `xsd:include` locations
+    pub imports: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Dts {
+    pub documents: Vec<DtsDocument>,
+    /// Remote documents left out under [`NetworkPolicy::WarnAndSkip`]
+    pub skipped: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl Dts {
+    pub fn contains(&self, uri: &str) -> bool {
+        self.documents.iter().any(|d| d.uri == uri)
+    }
+
+    /// True if every document came from a local file
+    pub fn is_offline(&self) -> bool {
+        self.documents.iter().all(|d| d.local_path.is_some())
+    }
+
+    /// Schemas that import or include themselves through a chain of other
+    /// schemas, each cycle listed from its first discovered member
+    pub fn import_cycles(&self) -> Vec<Vec<String>> {
+        let index: HashMap<&str, usize> = self
+            .documents
+            .iter()
+            .enumerate()
+            .map(|(i, d)| (d.uri.as_str(), i))
+            .collect();
+        let mut cycles = Vec::new();
+        let mut done = vec![false; self.documents.len()];
+        for start in 0..self.documents.len() {
+            let mut path = Vec::new();
+            self.find_cycles(start, &index, &mut path, &mut done, &mut cycles);
+        }
+        cycles
+    }
+
+    // Depth-first walk over import edges; an edge back into `path` closes a
+    // cycle. Finished documents are not walked again.
+    fn find_cycles(
+        &self,
+        node: usize,
+        index: &HashMap<&str, usize>,
+        path: &mut Vec<usize>,
+        done: &mut [bool],
+        cycles: &mut Vec<Vec<String>>,
+    ) {
+        if done[node] {
+            return;
+        }
+        if let Some(at) = path.iter().position(|&n| n == node) {
+            cycles.push(
+                path[at..]
+                    .iter()
+                    .map(|&n| self.documents[n].uri.clone())
+                    .collect(),
+            );
+            return;
+        }
+        path.push(node);
+        for import in &self.documents[node].imports {
+            if let Some(&next) = index.get(import.as_str()) {
+                self.find_cycles(next, index, path, done, cycles);
+            }
+        }
+        path.pop();
+        done[node] = true;
+    }
+}
+
+impl Document {
+    /// Parse every schema of a discovered DTS that is available locally into
+    /// [`Document::schemas`]
+    pub fn load_dts_schemas(&mut self, dts: &Dts) -> Result<()> {
+        for document in &dts.documents {
+            if let (DtsDocumentKind::Schema, Some(path)) = (document.kind, &document.local_path) {
+                let mut schema = parse_schema(&std::fs::read(path)?)?;
+                schema.location = Some(document.uri.clone());
+                self.add_schema(schema);
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a schema to [`Document::schemas`], registering the roles and
+    /// arcroles it declares. A URI declared again keeps its first
+    /// declaration.
+    pub fn add_schema(&mut self, schema: Schema) {
+        for role in &schema.role_types {
+            if !self.role_types.iter().any(|r| r.role_uri == role.role_uri) {
+                self.role_types.push(role.clone());
+            }
+        }
+        for arcrole in &schema.arcrole_types {
+            if !self
+                .arcrole_types
+                .iter()
+                .any(|a| a.arcrole_uri == arcrole.arcrole_uri)
+            {
+                self.arcrole_types.push(arcrole.clone());
+            }
+        }
+        self.schemas.push(schema);
+    }
+
+    /// Load both the schemas and the linkbases of a discovered DTS
+    pub fn load_dts(&mut self, dts: &Dts) -> Result<()> {
+        self.load_dts_schemas(dts)?;
+        self.load_dts_linkbases(dts)
+    }
+}
+
+#[derive(Default)]
+pub struct DtsLoader {
+    catalog: Catalog,
+    policy: NetworkPolicy,
+    #[cfg(feature = "http")]
+    client: Option<crate::http::HttpClient>,
+}
+
+impl DtsLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_catalog(mut self, catalog: Catalog) -> Self {
+        self.catalog = catalog;
+        self
+    }
+
+    pub fn with_network_policy(mut self, policy: NetworkPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    #[cfg(feature = "http")]
+    pub fn with_http_client(mut self, client: crate::http::HttpClient) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    pub fn network_policy(&self) -> NetworkPolicy {
+        self.policy
+    }
+
+    /// Discover the DTS reachable from `entry`, a schema or linkbase given as
+    /// a URL or a local path. Every document is read once, so reference
+    /// cycles terminate; schema import cycles are reported as warnings.
+    pub fn discover(&self, entry: &str) -> Result<Dts> {
+        let mut dts = Dts::default();
+        let mut seen = HashSet::new();
+        let mut queue = vec![entry.to_string()];
+
+        while let Some(uri) = queue.pop() {
+            if !seen.insert(uri.clone()) {
+                continue;
+            }
+            let is_entry = dts.documents.is_empty() && uri == entry;
+            let Some((content, local_path)) = self.load(&uri, is_entry, &mut dts)? else {
+                continue;
+            };
+
+            let (kind, imports, references) = scan_references(&content);
+            let imports: Vec<String> = imports.iter().map(|h| resolve_href(&uri, h)).collect();
+            // Reversed so documents are discovered in reference order
+            for href in references.iter().rev() {
+                queue.push(resolve_href(&uri, href));
+            }
+            queue.extend(imports.iter().rev().cloned());
+            dts.documents.push(DtsDocument {
+                uri,
+                kind,
+                local_path,
+                imports,
+            });
+        }
+
+        for cycle in dts.import_cycles() {
+            dts.warnings
+                .push(format!("Schema import cycle: {}", cycle.join(" -> ")));
+        }
+        Ok(dts)
+    }
+
+    fn load(
+        &self,
+        uri: &str,
+        is_entry: bool,
+        dts: &mut Dts,
+    ) -> Result<Option<(Vec<u8>, Option<PathBuf>)>> {
+        // Local paths first: a missing entry is an error, a missing
+        // referenced document only a warning
+        if !is_remote(uri) {
+            let path = PathBuf::from(uri.strip_prefix("file://").unwrap_or(uri));
+            return match std::fs::read(&path) {
+                Ok(content) => Ok(Some((content, Some(path)))),
+                Err(e) if !is_entry && e.kind() == std::io::ErrorKind::NotFound => {
+                    dts.warnings
+                        .push(format!("Referenced document {} not found", uri));
+                    Ok(None)
+                }
+                Err(e) => Err(e.into()),
+            };
+        }
+        if let Some(path) = self.catalog.resolve(uri) {
+            return Ok(Some((std::fs::read(&path)?, Some(path))));
+        }
+
+        match self.policy {
+            NetworkPolicy::Deny => Err(Error::SchemaResolution {
+                location: uri.to_string(),
+                message: "not available locally and network access is denied".to_string(),
+            }),
+            NetworkPolicy::WarnAndSkip => {
+                dts.warnings.push(format!(
+                    "Skipped remote document {} (network disabled)",
+                    uri
+                ));
+                dts.skipped.push(uri.to_string());
+                Ok(None)
+            }
+            NetworkPolicy::Allow => self.fetch(uri).map(|body| Some((body, None))),
+        }
+    }
+
+    #[cfg(feature = "http")]
+    fn fetch(&self, uri: &str) -> Result<Vec<u8>> {
+        let body = match &self.client {
+            Some(client) => client.get(uri)?,
+            None => crate::http::HttpClient::default().get(uri)?,
+        };
+        Ok(body.to_vec())
+    }
+
+    #[cfg(not(feature = "http"))]
+    fn fetch(&self, uri: &str) -> Result<Vec<u8>> {
+        Err(Error::SchemaResolution {
+            location: uri.to_string(),
+            message: "not available locally and crabrl was built without the http feature"
+                .to_string(),
+        })
+    }
+}
+
+/// Elements, types and imports declared by one schema document
+pub fn parse_schema(content: &[u8]) -> Result<Schema> {
+    let mut schema = Schema {
+        target_namespace: String::new(),
+        elements: HashMap::new(),
+        types: HashMap::new(),
+        imports: Vec::new(),
+        role_types: Vec::new(),
+        arcrole_types: Vec::new(),
+        groups: HashMap::new(),
+        location: None,
+    };
+
+    let mut reader = quick_xml::Reader::from_reader(content);
+    reader.config_mut().trim_text(true);
+
+    let mut buf = Vec::new();
+    let mut current_element: Option<SchemaElement> = None;
+    let mut current_type: Option<SchemaType> = None;
+    let mut current_role: Option<RoleType> = None;
+    let mut current_arcrole: Option<ArcroleType> = None;
+    // Text of the roleType or arcroleType child being read, `definition`
+    // or `usedOn`
+    let mut role_child: Option<(String, String)> = None;
+    // Prefixes declared on the schema element, which `ref` QNames use
+    let mut prefixes: HashMap<String, String> = HashMap::new();
+    // Named model group being defined
+    let mut current_group: Option<String> = None;
+    // Open sequence, choice and all compositors, innermost last
+    let mut compositors: Vec<(Compositor, Occurs, Vec<Particle>)> = Vec::new();
+    // Open local element declarations; their own content is not modelled
+    let mut local_elements = 0usize;
+
+    loop {
+        let event = reader.read_event_into(&mut buf);
+        let is_empty = matches!(event, Ok(Event::Empty(_)));
+        match event {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                let local = e.local_name();
+                let local_name = std::str::from_utf8(local.as_ref()).unwrap_or("");
+                let attrs: Vec<(String, String)> = e
+                    .attributes()
+                    .flatten()
+                    .map(|a| {
+                        (
+                            String::from_utf8_lossy(a.key.as_ref()).into_owned(),
+                            String::from_utf8_lossy(&a.value).into_owned(),
+                        )
+                    })
+                    .collect();
+                let get = |key: &str| {
+                    attrs
+                        .iter()
+                        .find(|(k, _)| k == key || k.ends_with(&format!(":{}", key)))
+                        .map(|(_, v)| v.clone())
+                };
+
+                let occurs = || Occurs {
+                    min: get("minOccurs").and_then(|v| v.parse().ok()).unwrap_or(1),
+                    max: match get("maxOccurs").as_deref() {
+                        Some("unbounded") => None,
+                        Some(v) => Some(v.parse().unwrap_or(1)),
+                        None => Some(1),
+                    },
+                };
+                let qualify = |qname: &str| {
+                    let (prefix, name) = qname.split_once(':').unwrap_or(("", qname));
+                    let namespace = prefixes.get(prefix).cloned().unwrap_or_default();
+                    (namespace, name.to_string())
+                };
+                let declaring = current_element.is_some()
+                    || current_type.is_some()
+                    || current_group.is_some()
+                    || !compositors.is_empty();
+
+                match local_name {
+                    "schema" => {
+                        schema.target_namespace = get("targetNamespace").unwrap_or_default();
+                        for (key, value) in &attrs {
+                            if key == "xmlns" {
+                                prefixes.insert(String::new(), value.clone());
+                            } else if let Some(prefix) = key.strip_prefix("xmlns:") {
+                                prefixes.insert(prefix.to_string(), value.clone());
+                            }
+                        }
+                    }
+                    "element" if local_elements > 0 && !is_empty => local_elements += 1,
+                    "element" if local_elements > 0 => {}
+                    // A particle of the content model being read
+                    "element" if declaring => {
+                        let (namespace, name) = match (get("ref"), get("name")) {
+                            (Some(reference), _) => qualify(&reference),
+                            (None, Some(name)) => (schema.target_namespace.clone(), name),
+                            (None, None) => (String::new(), String::new()),
+                        };
+                        if let Some((_, _, particles)) = compositors.last_mut() {
+                            if !name.is_empty() {
+                                particles.push(Particle::Element {
+                                    namespace,
+                                    name,
+                                    occurs: occurs(),
+                                });
+                            }
+                        }
+                        if !is_empty {
+                            local_elements += 1;
+                        }
+                    }
+                    "element" => {
+                        let element = SchemaElement {
+                            name: get("name").unwrap_or_default(),
+                            id: get("id"),
+                            element_type: get("type").unwrap_or_default(),
+                            substitution_group: get("substitutionGroup"),
+                            period_type: get("periodType"),
+                            balance: get("balance"),
+                            abstract_element: get("abstract").as_deref() == Some("true"),
+                            nillable: get("nillable").as_deref() == Some("true"),
+                            content: None,
+                        };
+
+                        if !element.name.is_empty() {
+                            if is_empty {
+                                schema.elements.insert(element.name.clone(), element);
+                            } else {
+                                current_element = Some(element);
+                            }
+                        }
+                    }
+                    "complexType" | "simpleType" if local_elements == 0 => {
+                        if let Some(name) = get("name") {
+                            current_type = Some(SchemaType {
+                                name,
+                                base_type: None,
+                                restrictions: Vec::new(),
+                                content: None,
+                            });
+                        }
+                    }
+                    "sequence" | "choice" | "all" if local_elements == 0 => {
+                        let compositor = match local_name {
+                            "sequence" => Compositor::Sequence,
+                            "choice" => Compositor::Choice,
+                            _ => Compositor::All,
+                        };
+                        compositors.push((compositor, occurs(), Vec::new()));
+                        if is_empty {
+                            let particle = close_compositor(&mut compositors);
+                            attach(
+                                particle,
+                                &mut compositors,
+                                &current_group,
+                                &mut schema,
+                                &mut current_type,
+                                &mut current_element,
+                            );
+                        }
+                    }
+                    "group" if local_elements == 0 => match (get("ref"), get("name")) {
+                        (Some(reference), _) => {
+                            let (namespace, name) = qualify(&reference);
+                            attach(
+                                Particle::Group {
+                                    namespace,
+                                    name,
+                                    occurs: occurs(),
+                                },
+                                &mut compositors,
+                                &current_group,
+                                &mut schema,
+                                &mut current_type,
+                                &mut current_element,
+                            );
+                        }
+                        (None, Some(name)) if !declaring && !is_empty => {
+                            current_group = Some(name);
+                        }
+                        _ => {}
+                    },
+                    "restriction" => {
+                        if let Some(ref mut t) = current_type {
+                            t.base_type = get("base");
+                        }
+                    }
+                    "minInclusive" | "maxInclusive" | "minExclusive" | "maxExclusive"
+                    | "pattern" | "length" | "minLength" | "maxLength" | "enumeration" => {
+                        if let (Some(t), Some(value)) = (current_type.as_mut(), get("value")) {
+                            push_restriction(t, local_name, value);
+                        }
+                    }
+                    "roleType" => {
+                        let role = RoleType {
+                            role_uri: get("roleURI").unwrap_or_default(),
+                            id: get("id"),
+                            definition: None,
+                            used_on: Vec::new(),
+                        };
+                        if is_empty {
+                            schema.role_types.push(role);
+                        } else {
+                            current_role = Some(role);
+                        }
+                    }
+                    "arcroleType" => {
+                        let arcrole = ArcroleType {
+                            arcrole_uri: get("arcroleURI").unwrap_or_default(),
+                            id: get("id"),
+                            cycles_allowed: get("cyclesAllowed").unwrap_or_default(),
+                            definition: None,
+                            used_on: Vec::new(),
+                        };
+                        if is_empty {
+                            schema.arcrole_types.push(arcrole);
+                        } else {
+                            current_arcrole = Some(arcrole);
+                        }
+                    }
+                    "definition" | "usedOn"
+                        if (current_role.is_some() || current_arcrole.is_some()) && !is_empty =>
+                    {
+                        role_child = Some((local_name.to_string(), String::new()));
+                    }
+                    "import" | "include" => {
+                        let import = SchemaImport {
+                            namespace: get("namespace").unwrap_or_default(),
+                            schema_location: get("schemaLocation").unwrap_or_default(),
+                        };
+
+                        if !import.namespace.is_empty() || !import.schema_location.is_empty() {
+                            schema.imports.push(import);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::Text(ref e)) => {
+                if let Some((_, text)) = role_child.as_mut() {
+                    match e.unescape() {
+                        Ok(t) => text.push_str(&t),
+                        Err(_) => text.push_str(&String::from_utf8_lossy(e.as_ref())),
+                    }
+                }
+            }
+            Ok(Event::End(ref e)) => match e.local_name().as_ref() {
+                b"definition" | b"usedOn" => {
+                    if let Some((child, text)) = role_child.take() {
+                        let (definition, used_on) = match (&mut current_role, &mut current_arcrole)
+                        {
+                            (Some(role), _) => (&mut role.definition, &mut role.used_on),
+                            (_, Some(arcrole)) => (&mut arcrole.definition, &mut arcrole.used_on),
+                            _ => continue,
+                        };
+                        match child.as_str() {
+                            "definition" => *definition = Some(text),
+                            _ => used_on.push(text),
+                        }
+                    }
+                }
+                b"roleType" => {
+                    if let Some(role) = current_role.take() {
+                        schema.role_types.push(role);
+                    }
+                }
+                b"arcroleType" => {
+                    if let Some(arcrole) = current_arcrole.take() {
+                        schema.arcrole_types.push(arcrole);
+                    }
+                }
+                b"element" if local_elements > 0 => local_elements -= 1,
+                b"element" => {
+                    if let Some(element) = current_element.take() {
+                        schema.elements.insert(element.name.clone(), element);
+                    }
+                }
+                _ if local_elements > 0 => {}
+                b"complexType" | b"simpleType" => {
+                    if let Some(schema_type) = current_type.take() {
+                        schema.types.insert(schema_type.name.clone(), schema_type);
+                    }
+                }
+                b"sequence" | b"choice" | b"all" if !compositors.is_empty() => {
+                    let particle = close_compositor(&mut compositors);
+                    attach(
+                        particle,
+                        &mut compositors,
+                        &current_group,
+                        &mut schema,
+                        &mut current_type,
+                        &mut current_element,
+                    );
+                }
+                b"group" if compositors.is_empty() => current_group = None,
+                _ => {}
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(Error::xml(format!("Schema parse error: {}", e))),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(schema)
+}
+
+#[derive(Clone, Copy)]
+enum Compositor {
+    Sequence,
+    Choice,
+    All,
+}
+
+/// Particle of the innermost open compositor, which must exist
+fn close_compositor(compositors: &mut Vec<(Compositor, Occurs, Vec<Particle>)>) -> Particle {
+    let (compositor, occurs, particles) = compositors.pop().expect("open compositor");
+    match compositor {
+        Compositor::Sequence => Particle::Sequence { particles, occurs },
+        Compositor::Choice => Particle::Choice { particles, occurs },
+        Compositor::All => Particle::All { particles, occurs },
+    }
+}
+
+/// Add a finished particle to the enclosing compositor, or make it the
+/// content of the group, type or element being declared
+fn attach(
+    particle: Particle,
+    compositors: &mut [(Compositor, Occurs, Vec<Particle>)],
+    group: &Option<String>,
+    schema: &mut Schema,
+    current_type: &mut Option<SchemaType>,
+    current_element: &mut Option<SchemaElement>,
+) {
+    if let Some((_, _, particles)) = compositors.last_mut() {
+        particles.push(particle);
+    } else if let Some(name) = group {
+        schema.groups.insert(name.clone(), particle);
+    } else if let Some(schema_type) = current_type {
+        schema_type.content = Some(particle);
+    } else if let Some(element) = current_element {
+        element.content = Some(particle);
+    }
+}
+
+fn push_restriction(t: &mut SchemaType, facet: &str, value: String) {
+    let restriction = match facet {
+        "minInclusive" => TypeRestriction::MinInclusive(value),
+        "maxInclusive" => TypeRestriction::MaxInclusive(value),
+        "minExclusive" => TypeRestriction::MinExclusive(value),
+        "maxExclusive" => TypeRestriction::MaxExclusive(value),
+        "pattern" => TypeRestriction::Pattern(value),
+        "length" => TypeRestriction::Length(value.parse().unwrap_or(0)),
+        "minLength" => TypeRestriction::MinLength(value.parse().unwrap_or(0)),
+        "maxLength" => TypeRestriction::MaxLength(value.parse().unwrap_or(0)),
+        "enumeration" => {
+            // Find or create the enumeration restriction
+            for restriction in &mut t.restrictions {
+                if let TypeRestriction::Enumeration(values) = restriction {
+                    values.push(value);
+                    return;
+                }
+            }
+            TypeRestriction::Enumeration(vec![value])
+        }
+        _ => return,
+    };
+    t.restrictions.push(restriction);
+}
+
+fn is_remote(uri: &str) -> bool {
+    uri.starts_with("http://") || uri.starts_with("https://")
+}
+
+/// Resolve `href` relative to the document it appears in, dropping fragments
+pub(crate) fn resolve_href(base: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or(href);
+    if is_remote(href) || Path::new(href).is_absolute() {
+        return href.to_string();
+    }
+    if is_remote(base) {
+        let (scheme, rest) = base.split_once("://").unwrap();
+        let mut parts: Vec<&str> = rest.split('/').collect();
+        parts.pop();
+        for segment in href.split('/') {
+            match segment {
+                "." | "" => {}
+                ".." => {
+                    if parts.len() > 1 {
+                        parts.pop();
+                    }
+                }
+                s => parts.push(s),
+            }
+        }
+        return format!("{}://{}", scheme, parts.join("/"));
+    }
+    let base_dir = Path::new(base).parent().unwrap_or(Path::new(""));
+    let mut path = PathBuf::new();
+    for component in base_dir.join(href).components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                path.pop();
+            }
+            c => path.push(c),
+        }
+    }
+    path.to_string_lossy().into_owned()
+}
+
+/// Document kind, its `xsd:import`/`xsd:include` locations and the other
+/// DTS-relevant hrefs it contains
+fn scan_references(content: &[u8]) -> (DtsDocumentKind, Vec<String>, Vec<String>) {
+    let mut reader = Reader::from_reader(content);
+    let mut buf = Vec::new();
+    let mut kind = None;
+    let mut imports = Vec::new();
+    let mut hrefs = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
+                let local = e.local_name();
+                let attr = |name: &[u8]| {
+                    e.attributes()
+                        .flatten()
+                        .find(|a| a.key.as_ref() == name)
+                        .map(|a| String::from_utf8_lossy(&a.value).into_owned())
+                };
+                match local.as_ref() {
+                    b"schema" => {
+                        kind.get_or_insert(DtsDocumentKind::Schema);
+                    }
+                    b"linkbase" => {
+                        kind.get_or_insert(DtsDocumentKind::Linkbase);
+                    }
+                    b"import" | b"include" => imports.extend(attr(b"schemaLocation")),
+                    b"linkbaseRef" | b"loc" | b"roleRef" | b"arcroleRef" => {
+                        hrefs.extend(attr(b"xlink:href"))
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    (kind.unwrap_or(DtsDocumentKind::Schema), imports, hrefs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REMOTE: &str = "http://www.xbrl.org/2003/xbrl-instance-2003-12-31.xsd";
+
+    fn write_taxonomy(dir: &Path) -> String {
+        let schema = dir.join("aapl-20230930.xsd");
+        std::fs::write(
+            &schema,
+            format!(
+                r#"<xsd:schema xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink">
+  <xsd:import namespace="http://www.xbrl.org/2003/instance" schemaLocation="{}"/>
+  <xsd:annotation><xsd:appinfo>
+    <link:linkbaseRef xlink:type="simple" xlink:href="aapl-20230930_lab.xml"/>
+  </xsd:appinfo></xsd:annotation>
+</xsd:schema>"#,
+                REMOTE
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("aapl-20230930_lab.xml"),
+            r#"<link:linkbase xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink">
+  <link:labelLink><link:loc xlink:href="aapl-20230930.xsd#aapl_Segment"/></link:labelLink>
+</link:linkbase>"#,
+        )
+        .unwrap();
+        schema.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_content_models() {
+        let schema = parse_schema(
+            br#"<xsd:schema xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:ex="http://example.com/a" targetNamespace="http://example.com/a">
+  <xsd:element name="Street" type="xbrli:stringItemType" substitutionGroup="xbrli:item" xbrli:periodType="instant"/>
+  <xsd:element name="City" type="xbrli:stringItemType" substitutionGroup="xbrli:item" xbrli:periodType="instant"/>
+  <xsd:element name="Zip" type="xbrli:stringItemType" substitutionGroup="xbrli:item" xbrli:periodType="instant"/>
+  <xsd:element name="Country" type="xbrli:stringItemType" substitutionGroup="xbrli:item" xbrli:periodType="instant"/>
+  <xsd:group name="Locality">
+    <xsd:choice>
+      <xsd:element ref="ex:City"/>
+      <xsd:element ref="ex:Zip"/>
+    </xsd:choice>
+  </xsd:group>
+  <xsd:element name="Address" substitutionGroup="xbrli:tuple">
+    <xsd:complexType>
+      <xsd:complexContent>
+        <xsd:restriction base="xsd:anyType">
+          <xsd:sequence>
+            <xsd:element ref="ex:Street" maxOccurs="unbounded"/>
+            <xsd:group ref="ex:Locality"/>
+            <xsd:element ref="ex:Country" minOccurs="0"/>
+          </xsd:sequence>
+        </xsd:restriction>
+      </xsd:complexContent>
+    </xsd:complexType>
+  </xsd:element>
+  <xsd:complexType name="OfficerType">
+    <xsd:sequence>
+      <xsd:element name="Note" type="xsd:string"/>
+      <xsd:element ref="ex:Address" minOccurs="0" maxOccurs="2"/>
+    </xsd:sequence>
+  </xsd:complexType>
+  <xsd:element name="Officer" type="ex:OfficerType" substitutionGroup="xbrli:tuple"/>
+</xsd:schema>"#,
+        )
+        .unwrap();
+        // Local declarations are particles, not concepts
+        let mut names: Vec<_> = schema.elements.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(
+            names,
+            ["Address", "City", "Country", "Officer", "Street", "Zip"]
+        );
+        assert!(schema.groups.contains_key("Locality"));
+
+        let mut doc = Document::new();
+        doc.namespaces
+            .insert("ex".to_string(), "http://example.com/a".to_string());
+        doc.add_schema(schema);
+
+        let address = doc.content_model("ex:Address").unwrap();
+        let Particle::Sequence { particles, .. } = &address else {
+            panic!("expected a sequence, got {:?}", address);
+        };
+        assert_eq!(particles[0].occurs(), Occurs { min: 1, max: None });
+        assert_eq!(
+            particles[2].occurs(),
+            Occurs {
+                min: 0,
+                max: Some(1)
+            }
+        );
+        // The group reference is replaced by the group's choice
+        let names: Vec<_> = address.elements().iter().map(|(_, name)| *name).collect();
+        assert_eq!(names, ["Street", "City", "Zip", "Country"]);
+        assert!(address
+            .elements()
+            .iter()
+            .all(|(namespace, _)| *namespace == "http://example.com/a"));
+
+        let officer = doc.content_model("ex:Officer").unwrap();
+        let names: Vec<_> = officer.elements().iter().map(|(_, name)| *name).collect();
+        assert_eq!(names, ["Note", "Address"]);
+        assert!(doc.content_model("ex:Street").is_none());
+    }
+
+    #[test]
+    fn test_network_policies() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = write_taxonomy(dir.path());
+
+        let denied = DtsLoader::new()
+            .with_network_policy(NetworkPolicy::Deny)
+            .discover(&entry);
+        assert!(matches!(denied, Err(Error::SchemaResolution { .. })));
+
+        // The default: nothing is fetched without opting in
+        assert_eq!(
+            DtsLoader::new().network_policy(),
+            NetworkPolicy::WarnAndSkip
+        );
+        let dts = DtsLoader::new().discover(&entry).unwrap();
+        assert_eq!(dts.documents.len(), 2);
+        assert_eq!(dts.skipped, [REMOTE]);
+        assert_eq!(dts.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_catalog_satisfies_deny() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = write_taxonomy(dir.path());
+        let bundle = dir.path().join("bundle");
+        let local = bundle.join(crate::catalog::bundle_path(REMOTE).unwrap());
+        std::fs::create_dir_all(local.parent().unwrap()).unwrap();
+        std::fs::write(
+            &local,
+            "<xsd:schema xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\"/>",
+        )
+        .unwrap();
+
+        let dts = DtsLoader::new()
+            .with_catalog(Catalog::from_dir(&bundle).unwrap())
+            .with_network_policy(NetworkPolicy::Deny)
+            .discover(&entry)
+            .unwrap();
+        assert_eq!(dts.documents.len(), 3);
+        assert!(dts.contains(REMOTE));
+        assert!(dts.is_offline());
+        assert_eq!(
+            resolve_href(
+                "https://xbrl.sec.gov/dei/2024/dei-2024.xsd",
+                "../../country/2024/country-2024.xsd"
+            ),
+            "https://xbrl.sec.gov/country/2024/country-2024.xsd"
+        );
+    }
+
+    #[test]
+    fn test_load_schemas_with_import_cycle() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("a.xsd"),
+            r#"<xsd:schema xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink" targetNamespace="http://example.com/a">
+  <xsd:import namespace="http://example.com/b" schemaLocation="b.xsd"/>
+  <xsd:annotation><xsd:appinfo>
+    <link:linkbaseRef xlink:type="simple" xlink:href="a_lab.xml"/>
+  </xsd:appinfo></xsd:annotation>
+  <xsd:element name="Revenue" type="xbrli:monetaryItemType" xbrli:periodType="duration"/>
+</xsd:schema>"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("b.xsd"),
+            r#"<xsd:schema xmlns:xsd="http://www.w3.org/2001/XMLSchema" targetNamespace="http://example.com/b">
+  <xsd:include schemaLocation="./a.xsd"/>
+  <xsd:element name="Segment" type="xsd:string"/>
+</xsd:schema>"#,
+        )
+        .unwrap();
+        let instance = dir.path().join("a.xml");
+        std::fs::write(
+            &instance,
+            r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink">
+  <link:schemaRef xlink:type="simple" xlink:href="a.xsd"/>
+</xbrli:xbrl>"#,
+        )
+        .unwrap();
+
+        let entry = dir.path().join("a.xsd").to_string_lossy().into_owned();
+        let dts = DtsLoader::new().discover(&entry).unwrap();
+        assert_eq!(dts.documents.len(), 2);
+        assert_eq!(dts.import_cycles().len(), 1);
+        assert_eq!(dts.import_cycles()[0][0], entry);
+        // The cycle and the missing label linkbase
+        assert_eq!(dts.warnings.len(), 2);
+
+        let doc = crate::Parser::new()
+            .with_schema_loading(true)
+            .parse_file(&instance)
+            .unwrap();
+        let namespaces: Vec<&str> = doc
+            .schemas
+            .iter()
+            .map(|s| s.target_namespace.as_str())
+            .collect();
+        assert_eq!(namespaces, ["http://example.com/a", "http://example.com/b"]);
+        assert_eq!(
+            doc.schemas[0].elements["Revenue"].period_type.as_deref(),
+            Some("duration")
+        );
+    }
+}
@@ -0,0 +1,636 @@
+//! EDGAR filing indexes
+//!
+//! Parses the quarterly full-index and daily-index files (`company.idx`,
+//! `form.idx`, `crawler.idx`, `master.idx`) and the per-company submissions
+//! JSON into typed records, and selects filings by date range and form type
+//! for bulk harvesting. With the `http` feature, [`EdgarClient`] downloads
+//! single filings and parses their XBRL instance.
+
+use crate::{Error, Result};
+use chrono::{Datelike, NaiveDate};
+
+pub const ARCHIVES_URL: &str = "https://www.sec.gov/Archives";
+pub const SUBMISSIONS_URL: &str = "https://data.sec.gov/submissions";
+
+/// One filing listed in an EDGAR index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    pub company_name: String,
+    pub form_type: String,
+    pub cik: u64,
+    pub date_filed: NaiveDate,
+    /// Path relative to the Archives root, e.g. `edgar/data/320193/0000320193-23-000106.txt`
+    pub file_name: String,
+}
+
+impl IndexEntry {
+    pub fn url(&self) -> String {
+        if self.file_name.starts_with("http") {
+            return self.file_name.clone();
+        }
+        format!(
+            "{}/{}",
+            ARCHIVES_URL,
+            self.file_name.trim_start_matches('/')
+        )
+    }
+
+    /// Accession number (`0000320193-23-000106`) taken from the file name
+    pub fn accession_number(&self) -> Option<&str> {
+        self.file_name
+            .rsplit('/')
+            .map(|part| part.split('.').next().unwrap_or(part))
+            .map(|part| part.strip_suffix("-index").unwrap_or(part))
+            .find(|part| is_accession_number(part))
+    }
+}
+
+fn is_accession_number(s: &str) -> bool {
+    let parts: Vec<&str> = s.split('-').collect();
+    parts.len() == 3
+        && [10, 2, 6]
+            .iter()
+            .zip(&parts)
+            .all(|(len, p)| p.len() == *len && p.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Filter over index entries
+#[derive(Debug, Clone, Default)]
+pub struct IndexQuery {
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    form_types: Vec<String>,
+    include_amendments: bool,
+}
+
+impl IndexQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inclusive filing date range
+    pub fn between(mut self, from: NaiveDate, to: NaiveDate) -> Self {
+        self.from = Some(from);
+        self.to = Some(to);
+        self
+    }
+
+    pub fn form(mut self, form_type: &str) -> Self {
+        self.form_types.push(form_type.to_string());
+        self
+    }
+
+    /// Also match `/A` amendments of the requested form types
+    pub fn with_amendments(mut self) -> Self {
+        self.include_amendments = true;
+        self
+    }
+
+    pub fn matches(&self, entry: &IndexEntry) -> bool {
+        if self.from.is_some_and(|from| entry.date_filed < from)
+            || self.to.is_some_and(|to| entry.date_filed > to)
+        {
+            return false;
+        }
+        self.form_types.is_empty()
+            || self.form_types.iter().any(|form| {
+                entry.form_type == *form
+                    || self.include_amendments
+                        && entry.form_type.strip_suffix("/A") == Some(form.as_str())
+            })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FilingIndex {
+    entries: Vec<IndexEntry>,
+}
+
+impl FilingIndex {
+    /// Parse an index file, detecting the layout from its header
+    pub fn parse(text: &str) -> Result<Self> {
+        if text.trim_start().starts_with('{') {
+            return Self::from_submissions_json(text);
+        }
+        let pipe_header = text
+            .lines()
+            .position(|line| line.starts_with("CIK|Company Name|Form Type|Date Filed|"));
+        match pipe_header {
+            Some(header) => Self::parse_pipe_delimited(text, header),
+            None => Self::parse_fixed_width(text),
+        }
+    }
+
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let bytes = std::fs::read(path)?;
+        Self::parse(&String::from_utf8_lossy(&bytes))
+    }
+
+    fn parse_pipe_delimited(text: &str, header: usize) -> Result<Self> {
+        let mut entries = Vec::new();
+        for line in text.lines().skip(header + 1) {
+            if line.starts_with('-') || line.trim().is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('|').collect();
+            if fields.len() < 5 {
+                return Err(Error::Parse(format!("Malformed index line: {}", line)));
+            }
+            entries.push(IndexEntry {
+                cik: parse_cik(fields[0])?,
+                company_name: fields[1].trim().to_string(),
+                form_type: fields[2].trim().to_string(),
+                date_filed: parse_date(fields[3])?,
+                file_name: fields[4].trim().to_string(),
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    /// `company.idx`, `form.idx` and `crawler.idx` share a fixed-width
+    /// layout whose column order differs; column offsets come from the header
+    fn parse_fixed_width(text: &str) -> Result<Self> {
+        let lines: Vec<&str> = text.lines().collect();
+        let header = lines
+            .iter()
+            .position(|line| line.contains("Form Type") && line.contains("Date Filed"))
+            .ok_or_else(|| Error::Parse("EDGAR index header not found".to_string()))?;
+        let header_line = lines[header];
+
+        let mut columns: Vec<(usize, &str)> = [
+            "Company Name",
+            "Form Type",
+            "CIK",
+            "Date Filed",
+            "File Name",
+            "URL",
+        ]
+        .iter()
+        .filter_map(|name| header_line.find(name).map(|pos| (pos, *name)))
+        .collect();
+        columns.sort();
+
+        let mut entries = Vec::new();
+        for line in &lines[header + 1..] {
+            if line.starts_with('-') || line.trim().is_empty() {
+                continue;
+            }
+            let field = |name: &str| -> &str {
+                let Some(i) = columns.iter().position(|(_, n)| *n == name) else {
+                    return "";
+                };
+                let start = columns[i].0.min(line.len());
+                let end = columns
+                    .get(i + 1)
+                    .map_or(line.len(), |(pos, _)| (*pos).min(line.len()));
+                line.get(start..end).unwrap_or("").trim()
+            };
+            let file_name = match field("File Name") {
+                "" => field("URL"),
+                name => name,
+            };
+            entries.push(IndexEntry {
+                company_name: field("Company Name").to_string(),
+                form_type: field("Form Type").to_string(),
+                cik: parse_cik(field("CIK"))?,
+                date_filed: parse_date(field("Date Filed"))?,
+                file_name: file_name.to_string(),
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    /// Recent filings from a `data.sec.gov/submissions/CIK##########.json` document
+    pub fn from_submissions_json(text: &str) -> Result<Self> {
+        let json: serde_json::Value = serde_json::from_str(text)
+            .map_err(|e| Error::Parse(format!("Submissions JSON: {}", e)))?;
+        let cik = match &json["cik"] {
+            serde_json::Value::String(s) => parse_cik(s)?,
+            value => value
+                .as_u64()
+                .ok_or_else(|| Error::Parse("Submissions JSON has no cik".to_string()))?,
+        };
+        let company_name = json["name"].as_str().unwrap_or_default().to_string();
+        let recent = &json["filings"]["recent"];
+        let column = |name: &str| -> Vec<&str> {
+            recent[name]
+                .as_array()
+                .map(|values| values.iter().map(|v| v.as_str().unwrap_or("")).collect())
+                .unwrap_or_default()
+        };
+        let accessions = column("accessionNumber");
+        let forms = column("form");
+        let dates = column("filingDate");
+        let documents = column("primaryDocument");
+
+        let mut entries = Vec::with_capacity(accessions.len());
+        for (i, accession) in accessions.iter().enumerate() {
+            let document = documents.get(i).copied().unwrap_or("");
+            entries.push(IndexEntry {
+                company_name: company_name.clone(),
+                form_type: forms.get(i).copied().unwrap_or("").to_string(),
+                cik,
+                date_filed: parse_date(dates.get(i).copied().unwrap_or(""))?,
+                file_name: format!(
+                    "edgar/data/{}/{}/{}",
+                    cik,
+                    accession.replace('-', ""),
+                    document
+                ),
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn filings<'a>(&'a self, query: &'a IndexQuery) -> impl Iterator<Item = &'a IndexEntry> {
+        self.entries.iter().filter(move |e| query.matches(e))
+    }
+
+    /// Merge another index, e.g. the next quarter or day
+    pub fn extend(&mut self, other: FilingIndex) {
+        self.entries.extend(other.entries);
+    }
+}
+
+#[cfg(feature = "http")]
+impl FilingIndex {
+    /// Download and parse an index, e.g. a path from [`full_index_paths`]
+    pub fn fetch(client: &crate::http::HttpClient, path: &str) -> Result<Self> {
+        let url = format!("{}/{}", ARCHIVES_URL, path.trim_start_matches('/'));
+        Self::parse(&String::from_utf8_lossy(&client.get(&url)?))
+    }
+}
+
+#[cfg(feature = "http")]
+impl IndexEntry {
+    pub fn fetch(&self, client: &crate::http::HttpClient) -> Result<std::sync::Arc<Vec<u8>>> {
+        client.get(&self.url())
+    }
+}
+
+/// Document to parse among the files of a filing folder: the instance EDGAR
+/// extracts from inline XBRL (`*_htm.xml`), then any other instance `.xml`,
+/// then `.htm` candidates that may carry inline XBRL, in listing order
+pub fn instance_candidates<'a>(names: &[&'a str]) -> Vec<&'a str> {
+    const NOT_INSTANCES: [&str; 5] = ["_cal.xml", "_def.xml", "_lab.xml", "_pre.xml", "_ref.xml"];
+    let lower = |name: &str| name.to_ascii_lowercase();
+    let extracted = names.iter().filter(|n| lower(n).ends_with("_htm.xml"));
+    let instances = names.iter().filter(|n| {
+        let name = lower(n);
+        name.ends_with(".xml")
+            && !name.ends_with("_htm.xml")
+            && !NOT_INSTANCES.iter().any(|suffix| name.ends_with(suffix))
+            && name != "filingsummary.xml"
+    });
+    let inline = names.iter().filter(|n| {
+        let name = lower(n);
+        (name.ends_with(".htm") || name.ends_with(".html")) && !name.ends_with("-index.htm")
+    });
+    extracted.chain(instances).chain(inline).copied().collect()
+}
+
+/// Accession number without dashes, as used in Archives folder names
+#[cfg(feature = "http")]
+fn accession_folder(accession: &str) -> Result<String> {
+    let folder: String = accession.chars().filter(|c| *c != '-').collect();
+    if folder.len() != 18 || !folder.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(Error::Parse(format!(
+            "Invalid accession number: {:?}",
+            accession
+        )));
+    }
+    Ok(folder)
+}
+
+/// Downloads filings from EDGAR and parses their XBRL instance.
+///
+/// EDGAR asks for a User-Agent naming the requester and allows at most ten
+/// requests per second; the client spaces its requests accordingly.
+#[cfg(feature = "http")]
+pub struct EdgarClient {
+    http: crate::http::HttpClient,
+    min_interval: std::time::Duration,
+    next_request: parking_lot::Mutex<std::time::Instant>,
+    archives_url: String,
+    submissions_url: String,
+}
+
+#[cfg(feature = "http")]
+impl EdgarClient {
+    /// `user_agent` should name the requester, e.g. `"Jane Doe jane@example.com"`
+    pub fn new(user_agent: &str) -> Self {
+        Self::with_http_config(crate::http::HttpConfig::new().with_user_agent(user_agent))
+    }
+
+    pub fn with_http_config(config: crate::http::HttpConfig) -> Self {
+        Self {
+            http: crate::http::HttpClient::new(config),
+            min_interval: std::time::Duration::from_millis(100),
+            next_request: parking_lot::Mutex::new(std::time::Instant::now()),
+            archives_url: ARCHIVES_URL.to_string(),
+            submissions_url: SUBMISSIONS_URL.to_string(),
+        }
+    }
+
+    pub fn with_rate_limit(mut self, requests_per_second: u32) -> Self {
+        self.min_interval = std::time::Duration::from_secs(1) / requests_per_second.max(1);
+        self
+    }
+
+    /// Point the client at mirrors of the Archives and submissions hosts
+    pub fn with_base_urls(mut self, archives_url: &str, submissions_url: &str) -> Self {
+        self.archives_url = archives_url.trim_end_matches('/').to_string();
+        self.submissions_url = submissions_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    fn get(&self, url: &str) -> Result<std::sync::Arc<Vec<u8>>> {
+        let wait = {
+            let mut next = self.next_request.lock();
+            let now = std::time::Instant::now();
+            let slot = (*next).max(now);
+            *next = slot + self.min_interval;
+            slot - now
+        };
+        std::thread::sleep(wait);
+        self.http.get(url)
+    }
+
+    /// File names in the filing's Archives folder
+    pub fn filing_files(&self, cik: u64, accession: &str) -> Result<Vec<String>> {
+        let url = format!(
+            "{}/edgar/data/{}/{}/index.json",
+            self.archives_url,
+            cik,
+            accession_folder(accession)?
+        );
+        let json: serde_json::Value = serde_json::from_slice(&self.get(&url)?)
+            .map_err(|e| Error::Parse(format!("Filing index {}: {}", url, e)))?;
+        let items = json["directory"]["item"]
+            .as_array()
+            .ok_or_else(|| Error::Parse(format!("Filing index {} lists no files", url)))?;
+        Ok(items
+            .iter()
+            .filter_map(|item| item["name"].as_str().map(str::to_string))
+            .collect())
+    }
+
+    /// Download filing `accession` of `cik` and parse its XBRL instance
+    pub fn fetch_filing(&self, cik: u64, accession: &str) -> Result<crate::Document> {
+        let files = self.filing_files(cik, accession)?;
+        let names: Vec<&str> = files.iter().map(String::as_str).collect();
+        let folder = format!(
+            "{}/edgar/data/{}/{}",
+            self.archives_url,
+            cik,
+            accession_folder(accession)?
+        );
+        for name in instance_candidates(&names) {
+            let url = format!("{}/{}", folder, name);
+            let data = self.get(&url)?;
+            let doc = if crate::ixbrl::is_inline_xbrl(&data) {
+                crate::InlineParser::new().parse_bytes(&data)?
+            } else if name.to_ascii_lowercase().ends_with(".xml") {
+                crate::Parser::new().parse_bytes(&data)?
+            } else {
+                continue;
+            };
+            return Ok(doc.with_source_uri(url));
+        }
+        Err(Error::NotFound(format!(
+            "No XBRL instance in filing {}",
+            accession
+        )))
+    }
+
+    /// Recent filings of `cik`, from its submissions JSON
+    pub fn submissions(&self, cik: u64) -> Result<FilingIndex> {
+        let url = format!("{}/CIK{:010}.json", self.submissions_url, cik);
+        FilingIndex::from_submissions_json(&String::from_utf8_lossy(&self.get(&url)?))
+    }
+
+    /// Most recent filing of `form_type` (amendments excluded) by `cik`
+    pub fn fetch_latest(&self, cik: u64, form_type: &str) -> Result<crate::Document> {
+        let index = self.submissions(cik)?;
+        let query = IndexQuery::new().form(form_type);
+        let entry = index
+            .filings(&query)
+            .max_by_key(|e| e.date_filed)
+            .ok_or_else(|| Error::NotFound(format!("No {} filing for CIK {}", form_type, cik)))?;
+        // Submissions entries point into the accession folder
+        let accession = entry
+            .accession_number()
+            .map(str::to_string)
+            .or_else(|| entry.file_name.rsplit('/').nth(1).map(str::to_string))
+            .ok_or_else(|| Error::Parse(format!("No accession in {}", entry.file_name)))?;
+        self.fetch_filing(cik, &accession)
+    }
+}
+
+fn parse_cik(s: &str) -> Result<u64> {
+    s.trim()
+        .parse()
+        .map_err(|_| Error::Parse(format!("Invalid CIK in index: {:?}", s)))
+}
+
+fn parse_date(s: &str) -> Result<NaiveDate> {
+    let s = s.trim();
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(s, "%Y%m%d"))
+        .map_err(|_| Error::Parse(format!("Invalid date in index: {:?}", s)))
+}
+
+fn quarter(date: NaiveDate) -> u32 {
+    (date.month() - 1) / 3 + 1
+}
+
+/// Archives-relative path of a quarterly full index, e.g.
+/// `edgar/full-index/2023/QTR3/company.idx`
+pub fn full_index_path(year: i32, quarter: u32, kind: &str) -> String {
+    format!("edgar/full-index/{}/QTR{}/{}.idx", year, quarter, kind)
+}
+
+/// Archives-relative path of a daily index, e.g.
+/// `edgar/daily-index/2023/QTR3/company.20230915.idx`
+pub fn daily_index_path(date: NaiveDate, kind: &str) -> String {
+    format!(
+        "edgar/daily-index/{}/QTR{}/{}.{}.idx",
+        date.year(),
+        quarter(date),
+        kind,
+        date.format("%Y%m%d")
+    )
+}
+
+/// Full-index files covering an inclusive date range, oldest first
+pub fn full_index_paths(from: NaiveDate, to: NaiveDate, kind: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    let (mut year, mut q) = (from.year(), quarter(from));
+    while (year, q) <= (to.year(), quarter(to)) {
+        paths.push(full_index_path(year, q, kind));
+        if q == 4 {
+            year += 1;
+            q = 1;
+        } else {
+            q += 1;
+        }
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMPANY_IDX: &str = "\
+Description:           Master Index of EDGAR Dissemination Feed by Company Name
+Last Data Received:    September 30, 2023
+
+Company Name                                                  Form Type   CIK         Date Filed  File Name
+---------------------------------------------------------------------------------------------------------------------------------------------
+Apple Inc.                                                    10-K        320193      2023-11-03  edgar/data/320193/0000320193-23-000106.txt
+Apple Inc.                                                    SC 13G/A    320193      2023-08-10  edgar/data/320193/0000320193-23-000077.txt
+MICROSOFT CORP                                                10-K/A      789019      2023-07-27  edgar/data/789019/0000950170-23-035122.txt
+";
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_company_idx() {
+        let index = FilingIndex::parse(COMPANY_IDX).unwrap();
+        assert_eq!(index.len(), 3);
+        let first = &index.entries()[0];
+        assert_eq!(first.company_name, "Apple Inc.");
+        assert_eq!(first.form_type, "10-K");
+        assert_eq!(first.cik, 320193);
+        assert_eq!(first.accession_number(), Some("0000320193-23-000106"));
+        assert_eq!(
+            first.url(),
+            "https://www.sec.gov/Archives/edgar/data/320193/0000320193-23-000106.txt"
+        );
+        assert_eq!(index.entries()[1].form_type, "SC 13G/A");
+
+        let query = IndexQuery::new()
+            .between(date("2023-07-01"), date("2023-09-30"))
+            .form("10-K")
+            .with_amendments();
+        let ciks: Vec<u64> = index.filings(&query).map(|e| e.cik).collect();
+        assert_eq!(ciks, [789019]);
+    }
+
+    #[test]
+    fn test_master_idx_and_submissions() {
+        let master = "CIK|Company Name|Form Type|Date Filed|Filename\n\
+                      --------------------------------------------------------------------------------\n\
+                      320193|Apple Inc.|8-K|20231102|edgar/data/320193/0000320193-23-000104.txt\n";
+        let index = FilingIndex::parse(master).unwrap();
+        assert_eq!(index.entries()[0].date_filed, date("2023-11-02"));
+
+        let json = r#"{"cik":"320193","name":"Apple Inc.","filings":{"recent":{
+            "accessionNumber":["0000320193-23-000106"],"filingDate":["2023-11-03"],
+            "form":["10-K"],"primaryDocument":["aapl-20230930.htm"]}}}"#;
+        let index = FilingIndex::parse(json).unwrap();
+        let entry = &index.entries()[0];
+        assert_eq!(
+            entry.file_name,
+            "edgar/data/320193/000032019323000106/aapl-20230930.htm"
+        );
+        assert_eq!(entry.form_type, "10-K");
+
+        assert_eq!(
+            full_index_paths(date("2023-11-15"), date("2024-02-01"), "company"),
+            [
+                "edgar/full-index/2023/QTR4/company.idx",
+                "edgar/full-index/2024/QTR1/company.idx"
+            ]
+        );
+        assert_eq!(
+            daily_index_path(date("2023-09-15"), "master"),
+            "edgar/daily-index/2023/QTR3/master.20230915.idx"
+        );
+    }
+
+    #[test]
+    fn test_instance_candidates() {
+        let names = [
+            "0000320193-23-000106-index.htm",
+            "aapl-20230930.htm",
+            "aapl-20230930.xsd",
+            "aapl-20230930_cal.xml",
+            "aapl-20230930_htm.xml",
+            "FilingSummary.xml",
+            "R1.htm",
+        ];
+        assert_eq!(
+            instance_candidates(&names),
+            ["aapl-20230930_htm.xml", "aapl-20230930.htm", "R1.htm"]
+        );
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_fetch_latest_filing() {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let mut paths = Vec::new();
+            for _ in 0..3 {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = String::new();
+                BufReader::new(stream.try_clone().unwrap())
+                    .read_line(&mut request)
+                    .unwrap();
+                let path = request.split(' ').nth(1).unwrap().to_string();
+                let body = match path.as_str() {
+                    "/submissions/CIK0000000001.json" => {
+                        r#"{"cik":"1","name":"Example","filings":{"recent":{
+                        "accessionNumber":["0000000001-23-000002","0000000001-24-000001"],
+                        "filingDate":["2023-11-03","2024-02-01"],"form":["10-K","8-K"],
+                        "primaryDocument":["ex-20230930.htm","ex-8k.htm"]}}}"#
+                    }
+                    "/Archives/edgar/data/1/000000000123000002/index.json" => {
+                        r#"{"directory":{"item":[{"name":"ex-20230930.htm"},{"name":"ex-20230930_htm.xml"}]}}"#
+                    }
+                    _ => r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance"/>"#,
+                };
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .unwrap();
+                paths.push(path);
+            }
+            paths
+        });
+
+        let client = EdgarClient::new("Test test@example.com")
+            .with_rate_limit(100)
+            .with_base_urls(
+                &format!("{}/Archives", base),
+                &format!("{}/submissions", base),
+            );
+        let doc = client.fetch_latest(1, "10-K").unwrap();
+        let source = doc.provenance.and_then(|p| p.uri).unwrap();
+        assert!(source.ends_with("/000000000123000002/ex-20230930_htm.xml"));
+        assert_eq!(server.join().unwrap().len(), 3);
+        assert!(accession_folder("0000000001-23").is_err());
+    }
+}
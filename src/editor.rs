@@ -0,0 +1,332 @@
+//! Audited corrections to a parsed document
+//!
+//! [`DocumentEditor`] borrows the document mutably, so no [`FactView`] can
+//! observe it mid-edit. Every change goes through a method that keeps the
+//! fact columns, concept table and source map consistent, and is appended
+//! to an edit log recording the value before and after.
+//!
+//! [`FactView`]: crate::model::FactView
+
+use crate::model::{Document, FactValue, FootnoteTarget, Period};
+use crate::{Error, Result};
+
+/// One applied change. Fact indexes are those at the time of the edit.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Edit {
+    Decimals {
+        fact_index: usize,
+        before: Option<i8>,
+        after: Option<i8>,
+    },
+    Value {
+        fact_index: usize,
+        before: FactValue,
+        after: FactValue,
+    },
+    Concept {
+        fact_index: usize,
+        before: String,
+        after: String,
+    },
+    Context {
+        fact_index: usize,
+        before: Option<String>,
+        after: String,
+    },
+    Unit {
+        fact_index: usize,
+        before: Option<String>,
+        after: Option<String>,
+    },
+    RemoveFact {
+        fact_index: usize,
+        concept: String,
+        value: FactValue,
+    },
+    Period {
+        context: String,
+        before: Period,
+        after: Period,
+    },
+}
+
+/// Entry of the edit log, with the reason given for it
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EditRecord {
+    pub edit: Edit,
+    pub reason: Option<String>,
+}
+
+pub struct DocumentEditor<'a> {
+    doc: &'a mut Document,
+    log: Vec<EditRecord>,
+    reason: Option<String>,
+}
+
+impl<'a> DocumentEditor<'a> {
+    pub fn new(doc: &'a mut Document) -> Self {
+        Self {
+            doc,
+            log: Vec::new(),
+            reason: None,
+        }
+    }
+
+    /// Reason recorded with the edits that follow, until changed
+    pub fn with_reason(mut self, reason: &str) -> Self {
+        self.reason = Some(reason.to_string());
+        self
+    }
+
+    pub fn set_reason(&mut self, reason: Option<&str>) {
+        self.reason = reason.map(str::to_string);
+    }
+
+    pub fn document(&self) -> &Document {
+        self.doc
+    }
+
+    pub fn log(&self) -> &[EditRecord] {
+        &self.log
+    }
+
+    /// End editing and return the log
+    pub fn finish(self) -> Vec<EditRecord> {
+        self.log
+    }
+
+    fn record(&mut self, edit: Edit) {
+        self.log.push(EditRecord {
+            edit,
+            reason: self.reason.clone(),
+        });
+    }
+
+    fn check_fact(&self, index: usize) -> Result<()> {
+        if index < self.doc.facts.len() {
+            Ok(())
+        } else {
+            Err(Error::NotFound(format!("fact {}", index)))
+        }
+    }
+
+    fn context_id(&self, id: u32) -> Option<String> {
+        self.doc.contexts.get(id as usize).map(|c| c.id.clone())
+    }
+
+    fn unit_id(&self, id: u32) -> Option<String> {
+        match id {
+            0 => None,
+            id => self.doc.units.get(id as usize - 1).map(|u| u.id.clone()),
+        }
+    }
+
+    /// Change the `decimals` of a numeric fact
+    pub fn set_decimals(&mut self, index: usize, decimals: Option<i8>) -> Result<()> {
+        self.check_fact(index)?;
+        if self.doc.facts.unit_ids[index] == 0 {
+            return Err(Error::validation(
+                "editor.notNumeric",
+                format!("fact {} is not numeric and has no decimals", index),
+            ));
+        }
+        let before = std::mem::replace(&mut self.doc.facts.decimals[index], decimals);
+        self.record(Edit::Decimals {
+            fact_index: index,
+            before,
+            after: decimals,
+        });
+        Ok(())
+    }
+
+    pub fn set_value(&mut self, index: usize, value: FactValue) -> Result<()> {
+        self.check_fact(index)?;
+        let before = std::mem::replace(&mut self.doc.facts.values[index], value.clone());
+        self.record(Edit::Value {
+            fact_index: index,
+            before,
+            after: value,
+        });
+        Ok(())
+    }
+
+    /// Report a fact under another concept, interning its QName
+    pub fn retag(&mut self, index: usize, concept: &str) -> Result<()> {
+        self.check_fact(index)?;
+        let before = self.doc.concept_names[self.doc.facts.concept_ids[index] as usize].clone();
+        self.doc.facts.concept_ids[index] = self.doc.intern_concept(concept);
+        self.record(Edit::Concept {
+            fact_index: index,
+            before,
+            after: concept.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Move a fact to the context with id `context`
+    pub fn set_context(&mut self, index: usize, context: &str) -> Result<()> {
+        self.check_fact(index)?;
+        let id = self
+            .doc
+            .contexts
+            .iter()
+            .position(|c| c.id == context)
+            .ok_or_else(|| Error::NotFound(format!("context {}", context)))?;
+        let before = self.context_id(self.doc.facts.context_ids[index]);
+        self.doc.facts.context_ids[index] = id as u32;
+        self.record(Edit::Context {
+            fact_index: index,
+            before,
+            after: context.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Change a fact's unit to the unit with id `unit`, or drop it along
+    /// with its decimals
+    pub fn set_unit(&mut self, index: usize, unit: Option<&str>) -> Result<()> {
+        self.check_fact(index)?;
+        let id = match unit {
+            Some(unit) => {
+                self.doc
+                    .units
+                    .iter()
+                    .position(|u| u.id == unit)
+                    .ok_or_else(|| Error::NotFound(format!("unit {}", unit)))?
+                    + 1
+            }
+            None => 0,
+        };
+        let before = self.unit_id(self.doc.facts.unit_ids[index]);
+        self.doc.facts.unit_ids[index] = id as u32;
+        if id == 0 {
+            self.doc.facts.decimals[index] = None;
+        }
+        self.record(Edit::Unit {
+            fact_index: index,
+            before,
+            after: unit.map(str::to_string),
+        });
+        Ok(())
+    }
+
+    /// Remove a fact; the facts after it move down one index
+    pub fn remove_fact(&mut self, index: usize) -> Result<()> {
+        self.check_fact(index)?;
+        let facts = &mut self.doc.facts;
+        let concept = self.doc.concept_names[facts.concept_ids.remove(index) as usize].clone();
+        facts.context_ids.remove(index);
+        facts.unit_ids.remove(index);
+        let value = facts.values.remove(index);
+        facts.decimals.remove(index);
+        let id = facts.ids.remove(index);
+        facts.footnote_refs.remove(index);
+        facts.scales.remove(index);
+        facts.tuple_paths.remove(index);
+
+        if let Some(map) = &mut self.doc.source_map {
+            if index < map.fact_offsets.len() {
+                let offset = map.fact_offsets.remove(index);
+                if let Some(id) = &id {
+                    map.ids.retain(|(i, o)| !(i == id && *o == offset));
+                }
+            }
+        }
+        if let Some(id) = &id {
+            for footnote in &mut self.doc.footnotes {
+                footnote.fact_refs.retain(|r| r != id);
+            }
+            self.doc.footnote_arcs.retain(|a| {
+                a.from != *id && !matches!(&a.to, FootnoteTarget::Fact(to) if to == id)
+            });
+        }
+        self.record(Edit::RemoveFact {
+            fact_index: index,
+            concept,
+            value,
+        });
+        Ok(())
+    }
+
+    /// Change the period of the context with id `context`, and so of every
+    /// fact reported in it
+    pub fn set_period(&mut self, context: &str, period: Period) -> Result<()> {
+        let target = self
+            .doc
+            .contexts
+            .iter_mut()
+            .find(|c| c.id == context)
+            .ok_or_else(|| Error::NotFound(format!("context {}", context)))?;
+        let before = std::mem::replace(&mut target.period, period.clone());
+        self.record(Edit::Period {
+            context: context.to_string(),
+            before,
+            after: period,
+        });
+        Ok(())
+    }
+}
+
+impl Document {
+    /// Start an audited edit session
+    pub fn edit(&mut self) -> DocumentEditor<'_> {
+        DocumentEditor::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    #[test]
+    fn test_document_editor() {
+        let mut doc = Parser::new()
+            .parse_str(
+                r##"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a" xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://example.com">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <a:Cash id="f1" contextRef="c" unitRef="USD" decimals="0">100</a:Cash>
+  <a:Revenue contextRef="c" unitRef="USD" decimals="3">250</a:Revenue>
+  <a:Name contextRef="c">Example</a:Name>
+  <link:footnoteLink xlink:type="extended" xlink:role="http://www.xbrl.org/2003/role/link">
+    <link:loc xlink:type="locator" xlink:href="#f1" xlink:label="fact1"/>
+    <link:footnote xlink:type="resource" xlink:label="fn1" xml:lang="en">Restated</link:footnote>
+    <link:footnoteArc xlink:type="arc" xlink:from="fact1" xlink:to="fn1"/>
+  </link:footnoteLink>
+</xbrli:xbrl>"##,
+            )
+            .unwrap();
+
+        let mut editor = doc.edit().with_reason("scaling error");
+        editor.set_decimals(1, Some(-3)).unwrap();
+        assert!(editor.set_decimals(2, Some(0)).is_err());
+        editor.set_reason(Some("retag"));
+        editor.retag(1, "a:Revenues").unwrap();
+        editor.remove_fact(0).unwrap();
+        assert!(editor.set_context(0, "missing").is_err());
+        let log = editor.finish();
+
+        assert_eq!(log.len(), 3);
+        assert_eq!(log[0].reason.as_deref(), Some("scaling error"));
+        assert_eq!(
+            log[1].edit,
+            Edit::Concept {
+                fact_index: 1,
+                before: "a:Revenue".to_string(),
+                after: "a:Revenues".to_string(),
+            }
+        );
+
+        let revenue = doc.fact(0).unwrap();
+        assert_eq!(revenue.concept(), "a:Revenues");
+        assert_eq!(revenue.decimals(), Some(-3));
+        assert_eq!(doc.facts.len(), 2);
+        assert_eq!(doc.source_map.as_ref().unwrap().fact_offsets.len(), 2);
+        assert!(doc.footnotes[0].fact_refs.is_empty());
+        assert!(doc.footnote_arcs.is_empty());
+    }
+}
@@ -0,0 +1,598 @@
+//! EDGAR Filer Manual (EFM) checks
+//!
+//! Each finding is a [`ValidationError::EfmViolation`] carrying the EFM
+//! section it derives from. The required context is the context of the
+//! `dei:DocumentType` fact; cover-page facts are looked up in contexts with
+//! its period and no dimensions.
+//!
+//! The rule sets are compiled into a [`RulePack`] once and shared; building
+//! one per validation would dominate the cost of checking small filings.
+
+use crate::dei::{check_dei_values, compile_constraints, is_dei_concept, CompiledConstraint};
+use crate::model::{canonical_end, parse_date_union, Document, FactView, UnitType};
+use crate::validator::ValidationError;
+use crate::Severity;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, OnceLock};
+
+pub(crate) const ISO4217_NAMESPACE: &str = "http://www.xbrl.org/2003/iso4217";
+const XBRLI_NAMESPACE: &str = "http://www.xbrl.org/2003/instance";
+
+/// dei facts every submission must report
+const REQUIRED_DEI: &[&str] = &[
+    "DocumentType",
+    "DocumentPeriodEndDate",
+    "EntityRegistrantName",
+    "EntityCentralIndexKey",
+    "AmendmentFlag",
+];
+
+/// Additional dei facts for periodic reports
+const PERIODIC_DEI: &[&str] = &[
+    "DocumentFiscalYearFocus",
+    "DocumentFiscalPeriodFocus",
+    "CurrentFiscalYearEndDate",
+];
+const PERIODIC_FORMS: &[&str] = &["10-K", "10-KT", "10-Q", "10-QT", "20-F", "40-F"];
+
+/// Additional dei facts for domestic periodic reports
+const DOMESTIC_DEI: &[&str] = &[
+    "EntityFilerCategory",
+    "EntityCurrentReportingStatus",
+    "EntityShellCompany",
+    "EntityCommonStockSharesOutstanding",
+];
+const DOMESTIC_FORMS: &[&str] = &["10-K", "10-KT", "10-Q", "10-QT"];
+
+/// Additional dei facts for annual reports
+const ANNUAL_DEI: &[&str] = &[
+    "EntityWellKnownSeasonedIssuer",
+    "EntityVoluntaryFilers",
+    "EntityPublicFloat",
+];
+const ANNUAL_FORMS: &[&str] = &["10-K", "10-KT"];
+
+/// dei facts reported as of a date other than the period end
+const OFF_PERIOD_DEI: &[&str] = &["EntityCommonStockSharesOutstanding", "EntityPublicFloat"];
+
+/// Days `dei:DocumentPeriodEndDate` may differ from the required context's
+/// end, for 52-53 week fiscal years
+const PERIOD_END_TOLERANCE_DAYS: i64 = 3;
+
+/// us-gaap elements deprecated in recent taxonomies, with their replacement
+pub const DEPRECATED_US_GAAP: &[(&str, &str)] = &[
+    (
+        "SalesRevenueNet",
+        "RevenueFromContractWithCustomerExcludingAssessedTax",
+    ),
+    (
+        "SalesRevenueGoodsNet",
+        "RevenueFromContractWithCustomerExcludingAssessedTax",
+    ),
+    (
+        "SalesRevenueServicesNet",
+        "RevenueFromContractWithCustomerExcludingAssessedTax",
+    ),
+    (
+        "CashAndCashEquivalentsPeriodIncreaseDecrease",
+        "CashCashEquivalentsRestrictedCashAndRestrictedCashEquivalentsPeriodIncreaseDecreaseIncludingExchangeRateEffect",
+    ),
+    (
+        "AvailableForSaleSecuritiesEquitySecurities",
+        "EquitySecuritiesFvNi",
+    ),
+];
+
+/// Credit-balance us-gaap elements that are never negative
+pub const NON_NEGATIVE_CREDIT: &[&str] = &[
+    "Revenues",
+    "RevenueFromContractWithCustomerExcludingAssessedTax",
+    "RevenueFromContractWithCustomerIncludingAssessedTax",
+    "Liabilities",
+    "LiabilitiesCurrent",
+    "LiabilitiesNoncurrent",
+    "AccountsPayableCurrent",
+    "LongTermDebt",
+    "LongTermDebtNoncurrent",
+    "AccumulatedDepreciationDepletionAndAmortizationPropertyPlantAndEquipment",
+];
+
+fn violation(
+    severity: Severity,
+    code: &'static str,
+    section: &'static str,
+    message: String,
+    fact_index: Option<usize>,
+) -> ValidationError {
+    ValidationError::EfmViolation {
+        severity,
+        code,
+        section,
+        message,
+        fact_index,
+    }
+}
+
+fn local_name(concept: &str) -> &str {
+    concept.rsplit_once(':').map_or(concept, |(_, local)| local)
+}
+
+fn is_us_gaap(concept: &str) -> bool {
+    concept
+        .split_once(':')
+        .is_some_and(|(prefix, _)| prefix.starts_with("us-gaap"))
+}
+
+/// Run every EFM check with the built-in rule sets
+pub fn check_efm(doc: &Document) -> Vec<ValidationError> {
+    RulePack::shared().check(doc)
+}
+
+/// EFM rule sets compiled for lookup: required dei facts per form type,
+/// dei value enumerations, deprecated and never-negative elements.
+/// Immutable once built, so one pack can serve any number of threads.
+#[derive(Debug, Clone)]
+pub struct RulePack {
+    /// Required dei facts by form type; other forms use [`REQUIRED_DEI`]
+    required_dei: HashMap<&'static str, Vec<&'static str>>,
+    dei_values: HashMap<&'static str, CompiledConstraint>,
+    /// us-gaap local name to its replacement
+    deprecated: HashMap<String, String>,
+    non_negative: HashSet<String>,
+}
+
+impl Default for RulePack {
+    fn default() -> Self {
+        let mut required_dei: HashMap<&'static str, Vec<&'static str>> = HashMap::new();
+        for (forms, concepts) in [
+            (PERIODIC_FORMS, PERIODIC_DEI),
+            (DOMESTIC_FORMS, DOMESTIC_DEI),
+            (ANNUAL_FORMS, ANNUAL_DEI),
+        ] {
+            for form in forms {
+                required_dei
+                    .entry(form)
+                    .or_insert_with(|| REQUIRED_DEI.to_vec())
+                    .extend(concepts);
+            }
+        }
+        Self {
+            required_dei,
+            dei_values: compile_constraints(),
+            deprecated: DEPRECATED_US_GAAP
+                .iter()
+                .map(|(d, r)| (d.to_string(), r.to_string()))
+                .collect(),
+            non_negative: NON_NEGATIVE_CREDIT.iter().map(|c| c.to_string()).collect(),
+        }
+    }
+}
+
+impl RulePack {
+    /// Compile the built-in rule sets
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The built-in pack, compiled on first use
+    pub fn shared() -> Arc<RulePack> {
+        static SHARED: OnceLock<Arc<RulePack>> = OnceLock::new();
+        SHARED.get_or_init(|| Arc::new(RulePack::new())).clone()
+    }
+
+    /// Flag a us-gaap element (local name) as deprecated
+    pub fn with_deprecated(mut self, element: &str, replacement: &str) -> Self {
+        self.deprecated
+            .insert(element.to_string(), replacement.to_string());
+        self
+    }
+
+    /// Flag a us-gaap element (local name) that must not be negative
+    pub fn with_non_negative(mut self, element: &str) -> Self {
+        self.non_negative.insert(element.to_string());
+        self
+    }
+
+    fn required_dei(&self, form: &str) -> &[&'static str] {
+        self.required_dei
+            .get(form)
+            .map_or(REQUIRED_DEI, Vec::as_slice)
+    }
+
+    /// Run every EFM check
+    pub fn check(&self, doc: &Document) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        self.check_required_dei(doc, &mut errors);
+        errors.extend(check_dei_values(doc, &self.dei_values));
+        self.check_deprecated(doc, &mut errors);
+        self.check_credit_signs(doc, &mut errors);
+        check_units(doc, &mut errors);
+        errors
+    }
+
+    /// dei facts outside their enumeration or lexical pattern
+    pub fn check_dei_values(&self, doc: &Document) -> Vec<ValidationError> {
+        check_dei_values(doc, &self.dei_values)
+    }
+
+    /// EFM 6.5.20 cover-page facts per form type, and 6.5.19 alignment of the
+    /// required context with `dei:DocumentPeriodEndDate`
+    fn check_required_dei(&self, doc: &Document, errors: &mut Vec<ValidationError>) {
+        let document_type = doc
+            .iter_facts()
+            .find(|f| is_dei_concept(f.concept(), "DocumentType") && !f.value().is_nil());
+        let Some(document_type) = document_type else {
+            errors.push(violation(
+                Severity::Error,
+                "efm.6.5.20.documentType",
+                "EFM 6.5.20",
+                "dei:DocumentType is required".to_string(),
+                None,
+            ));
+            return;
+        };
+        let Some(required) = document_type.context() else {
+            return;
+        };
+        let form = document_type.value().to_string();
+        let form = form.trim();
+
+        let in_required_period = |fact: &FactView<'_>, local: &str| {
+            OFF_PERIOD_DEI.contains(&local)
+                || fact.context().is_some_and(|c| {
+                    c.period.same_as(&required.period) && c.dimensions().is_empty()
+                })
+        };
+        let reported: HashSet<&str> = doc
+            .iter_facts()
+            .filter(|f| !f.value().is_nil())
+            .filter_map(|f| {
+                let (prefix, local) = f.concept().split_once(':')?;
+                (prefix.starts_with("dei") && in_required_period(&f, local)).then_some(local)
+            })
+            .collect();
+
+        let mut required_dei = self.required_dei(form).to_vec();
+        let amended = doc.iter_facts().any(|f| {
+            is_dei_concept(f.concept(), "AmendmentFlag") && f.value().to_string().trim() == "true"
+        });
+        if amended {
+            required_dei.push("AmendmentDescription");
+        }
+        for concept in required_dei {
+            if !reported.contains(concept) {
+                errors.push(violation(
+                    Severity::Error,
+                    "efm.6.5.20.requiredDei",
+                    "EFM 6.5.20",
+                    format!("dei:{} is required for {}", concept, form),
+                    None,
+                ));
+            }
+        }
+
+        let period_end = doc
+            .iter_facts()
+            .find(|f| is_dei_concept(f.concept(), "DocumentPeriodEndDate") && !f.value().is_nil());
+        let Some(period_end) = period_end else {
+            return;
+        };
+        // Both ends compare as the end of their day
+        let stated = parse_date_union(&period_end.value().to_string(), true);
+        let context_end = required.period.effective_end();
+        if let (Some(stated), Some(context_end)) = (stated, context_end) {
+            if (stated - context_end).num_days().abs() > PERIOD_END_TOLERANCE_DAYS {
+                let (stated, context_end) = (
+                    canonical_end(&period_end.value().to_string()),
+                    required
+                        .period
+                        .lexical_end()
+                        .map(canonical_end)
+                        .unwrap_or_default(),
+                );
+                errors.push(violation(
+                    Severity::Error,
+                    "efm.6.5.19.periodEndDate",
+                    "EFM 6.5.19",
+                    format!(
+                    "dei:DocumentPeriodEndDate {} does not match the end {} of required context {}",
+                    stated, context_end, required.id
+                ),
+                    Some(period_end.index()),
+                ));
+            }
+        }
+    }
+
+    /// EFM 6.6.40 deprecated elements, reported once per element
+    fn check_deprecated(&self, doc: &Document, errors: &mut Vec<ValidationError>) {
+        let mut seen = HashSet::new();
+        for fact in doc.iter_facts() {
+            let concept = fact.concept();
+            if !is_us_gaap(concept) || !seen.insert(concept) {
+                continue;
+            }
+            let local = local_name(concept);
+            if let Some(replacement) = self.deprecated.get(local) {
+                errors.push(violation(
+                    Severity::Error,
+                    "efm.6.6.40.deprecatedElement",
+                    "EFM 6.6.40",
+                    format!(
+                        "{} is deprecated; use us-gaap:{} instead",
+                        concept, replacement
+                    ),
+                    Some(fact.index()),
+                ));
+            }
+        }
+    }
+
+    /// EFM 6.6.37 negative values of credit elements that are never negative.
+    /// Facts with dimensions are skipped, as eliminations may be negative.
+    fn check_credit_signs(&self, doc: &Document, errors: &mut Vec<ValidationError>) {
+        for fact in doc.iter_facts() {
+            let concept = fact.concept();
+            if !is_us_gaap(concept) || !self.non_negative.contains(local_name(concept)) {
+                continue;
+            }
+            let negative = fact
+                .value()
+                .as_decimal()
+                .is_some_and(|v| v.is_sign_negative() && !v.is_zero());
+            let undimensioned = fact.context().is_some_and(|c| c.dimensions().is_empty());
+            if negative && undimensioned {
+                errors.push(violation(
+                    Severity::Warning,
+                    "efm.6.6.37.negativeValue",
+                    "EFM 6.6.37",
+                    format!("{} should not be negative, found {}", concept, fact.value()),
+                    Some(fact.index()),
+                ));
+            }
+        }
+    }
+}
+
+/// EFM 6.5.38 unit codes: ISO 4217 currency codes, shares for share counts
+/// and currency per share for per-share amounts
+fn check_units(doc: &Document, errors: &mut Vec<ValidationError>) {
+    for unit in &doc.units {
+        let measures = match &unit.unit_type {
+            UnitType::Simple(m) | UnitType::Multiply(m) => m.iter().collect::<Vec<_>>(),
+            UnitType::Divide {
+                numerator,
+                denominator,
+            } => numerator.iter().chain(denominator).collect(),
+        };
+        for measure in measures {
+            let code = &measure.name;
+            let valid = code.len() == 3 && code.bytes().all(|b| b.is_ascii_uppercase());
+            if measure.in_namespace(ISO4217_NAMESPACE, "iso4217") && !valid {
+                errors.push(violation(
+                    Severity::Error,
+                    "efm.6.5.38.unitCode",
+                    "EFM 6.5.38",
+                    format!("Unit {} uses invalid currency code {}", unit.id, code),
+                    None,
+                ));
+            }
+        }
+    }
+
+    let is_shares = |measures: &[crate::model::Measure]| {
+        measures.len() == 1
+            && measures[0].name == "shares"
+            && measures[0].in_namespace(XBRLI_NAMESPACE, "xbrli")
+    };
+    for fact in doc.iter_facts() {
+        let (Some(unit), Some(_)) = (fact.unit(), fact.value().as_decimal()) else {
+            continue;
+        };
+        let concept = fact.concept();
+        let local = local_name(concept);
+        let expected = if local.ends_with("SharesOutstanding")
+            || local.ends_with("SharesIssued")
+            || local.ends_with("SharesAuthorized")
+        {
+            match &unit.unit_type {
+                UnitType::Simple(m) if is_shares(m) => continue,
+                _ => "xbrli:shares",
+            }
+        } else if local.starts_with("EarningsPerShare") {
+            match &unit.unit_type {
+                UnitType::Divide { denominator, .. } if is_shares(denominator) => continue,
+                _ => "a currency per xbrli:shares",
+            }
+        } else {
+            continue;
+        };
+        errors.push(violation(
+            Severity::Error,
+            "efm.6.5.38.unitCode",
+            "EFM 6.5.38",
+            format!(
+                "{} should be reported in {}, found {}",
+                concept,
+                expected,
+                unit.canonical()
+            ),
+            Some(fact.index()),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    fn codes(xml: &str) -> Vec<String> {
+        let doc = Parser::new().parse_str(xml).unwrap();
+        check_efm(&doc).iter().map(|e| e.to_string()).collect()
+    }
+
+    #[test]
+    fn test_efm_rules() {
+        let findings = codes(
+            r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:iso4217="http://www.xbrl.org/2003/iso4217"
+    xmlns:dei="http://xbrl.sec.gov/dei/2023" xmlns:us-gaap="http://fasb.org/us-gaap/2023">
+  <xbrli:context id="FY"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2023-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <xbrli:unit id="usd"><xbrli:measure>iso4217:usd</xbrli:measure></xbrli:unit>
+  <dei:DocumentType contextRef="FY">8-K</dei:DocumentType>
+  <dei:DocumentPeriodEndDate contextRef="FY">2023-12-20</dei:DocumentPeriodEndDate>
+  <dei:EntityRegistrantName contextRef="FY">Example</dei:EntityRegistrantName>
+  <dei:EntityCentralIndexKey contextRef="FY">0000000001</dei:EntityCentralIndexKey>
+  <us-gaap:SalesRevenueNet contextRef="FY" unitRef="USD" decimals="0">10</us-gaap:SalesRevenueNet>
+  <us-gaap:Liabilities contextRef="FY" unitRef="USD" decimals="0">-5</us-gaap:Liabilities>
+  <us-gaap:EarningsPerShareBasic contextRef="FY" unitRef="USD" decimals="2">1.25</us-gaap:EarningsPerShareBasic>
+</xbrli:xbrl>"#,
+        );
+        assert_eq!(
+            findings,
+            [
+                "EFM 6.5.20: dei:AmendmentFlag is required for 8-K",
+                "EFM 6.5.19: dei:DocumentPeriodEndDate 2023-12-20 does not match the end 2023-12-31 of required context FY",
+                "EFM 6.6.40: us-gaap:SalesRevenueNet is deprecated; use us-gaap:RevenueFromContractWithCustomerExcludingAssessedTax instead",
+                "EFM 6.6.37: us-gaap:Liabilities should not be negative, found -5",
+                "EFM 6.5.38: Unit usd uses invalid currency code usd",
+                "EFM 6.5.38: us-gaap:EarningsPerShareBasic should be reported in a currency per xbrli:shares, found iso4217:USD",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_warnings_keep_sec_edgar_valid() {
+        let doc = Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:iso4217="http://www.xbrl.org/2003/iso4217"
+    xmlns:dei="http://xbrl.sec.gov/dei/2023" xmlns:us-gaap="http://fasb.org/us-gaap/2023">
+  <xbrli:context id="FY"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2023-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <dei:DocumentType contextRef="FY">8-K</dei:DocumentType>
+  <dei:AmendmentFlag contextRef="FY">false</dei:AmendmentFlag>
+  <dei:DocumentPeriodEndDate contextRef="FY">2023-12-31</dei:DocumentPeriodEndDate>
+  <dei:EntityRegistrantName contextRef="FY">Example</dei:EntityRegistrantName>
+  <dei:EntityCentralIndexKey contextRef="FY">0000000001</dei:EntityCentralIndexKey>
+  <us-gaap:Liabilities contextRef="FY" unitRef="USD" decimals="0">-5</us-gaap:Liabilities>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+        // Strict validation fails on errors only; rule warnings stay warnings
+        let result = crate::Validator::sec_edgar().validate(&doc).unwrap();
+        assert_eq!(result.findings.len(), 1);
+        assert_eq!(result.findings[0].code, "efm.6.6.37.negativeValue");
+        assert_eq!(result.findings[0].severity, Severity::Warning);
+        assert_eq!(
+            result.warnings,
+            ["EFM 6.6.37: us-gaap:Liabilities should not be negative, found -5"]
+        );
+        assert!(result.errors.is_empty());
+        assert!(result.is_valid);
+    }
+
+    #[test]
+    fn test_unprefixed_measures() {
+        // Measures in the default namespace, as most EDGAR instances write
+        // them
+        let doc = Parser::new()
+            .parse_str(
+                r#"<xbrl xmlns="http://www.xbrl.org/2003/instance" xmlns:iso4217="http://www.xbrl.org/2003/iso4217" xmlns:us-gaap="http://fasb.org/us-gaap/2023">
+  <context id="I"><entity><identifier scheme="http://www.sec.gov/CIK">0000000001</identifier></entity>
+    <period><instant>2023-12-31</instant></period></context>
+  <unit id="shares"><measure>shares</measure></unit>
+  <unit id="usdPerShare"><divide><unitNumerator><measure>iso4217:USD</measure></unitNumerator>
+    <unitDenominator><measure>shares</measure></unitDenominator></divide></unit>
+  <us-gaap:CommonStockSharesOutstanding contextRef="I" unitRef="shares" decimals="-3">15550061000</us-gaap:CommonStockSharesOutstanding>
+  <us-gaap:EarningsPerShareBasic contextRef="I" unitRef="usdPerShare" decimals="2">6.16</us-gaap:EarningsPerShareBasic>
+</xbrl>"#,
+            )
+            .unwrap();
+        let UnitType::Simple(measures) = &doc.units[0].unit_type else {
+            panic!("expected a simple unit");
+        };
+        assert_eq!(measures[0].namespace, XBRLI_NAMESPACE);
+        let mut errors = Vec::new();
+        check_units(&doc, &mut errors);
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_required_dei_per_form() {
+        let findings = codes(
+            r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:dei="http://xbrl.sec.gov/dei/2023">
+  <xbrli:context id="Q"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2023-07-01</xbrli:startDate><xbrli:endDate>2023-09-30</xbrli:endDate></xbrli:period></xbrli:context>
+  <dei:DocumentType contextRef="Q">10-Q</dei:DocumentType>
+  <dei:AmendmentFlag contextRef="Q">true</dei:AmendmentFlag>
+</xbrli:xbrl>"#,
+        );
+        let missing: Vec<&str> = findings
+            .iter()
+            .filter_map(|f| f.strip_prefix("EFM 6.5.20: dei:"))
+            .filter_map(|f| f.strip_suffix(" is required for 10-Q"))
+            .collect();
+        assert_eq!(
+            missing,
+            [
+                "DocumentPeriodEndDate",
+                "EntityRegistrantName",
+                "EntityCentralIndexKey",
+                "DocumentFiscalYearFocus",
+                "DocumentFiscalPeriodFocus",
+                "CurrentFiscalYearEndDate",
+                "EntityFilerCategory",
+                "EntityCurrentReportingStatus",
+                "EntityShellCompany",
+                "EntityCommonStockSharesOutstanding",
+                "AmendmentDescription",
+            ]
+        );
+        assert_eq!(
+            codes(r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance"/>"#),
+            ["EFM 6.5.20: dei:DocumentType is required"]
+        );
+    }
+
+    #[test]
+    fn test_shared_rule_pack() {
+        assert!(Arc::ptr_eq(&RulePack::shared(), &RulePack::shared()));
+
+        let doc = Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:us-gaap="http://fasb.org/us-gaap/2023"
+    xmlns:dei="http://xbrl.sec.gov/dei/2023">
+  <xbrli:context id="FY"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <dei:DocumentType contextRef="FY">10-X</dei:DocumentType>
+  <us-gaap:OldCash contextRef="FY" unitRef="USD" decimals="0">-1</us-gaap:OldCash>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+        let pack = Arc::new(
+            RulePack::new()
+                .with_deprecated("OldCash", "Cash")
+                .with_non_negative("OldCash"),
+        );
+        let validator = crate::validator::XbrlValidator::new().with_rule_pack(pack);
+
+        let doc = Arc::new(doc);
+        let validator = Arc::new(validator);
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let (doc, validator) = (doc.clone(), validator.clone());
+                std::thread::spawn(move || validator.check(&doc))
+            })
+            .collect();
+        for handle in handles {
+            let rules: Vec<&str> = handle.join().unwrap().iter().map(|e| e.code()).collect();
+            assert!(rules.contains(&"efm.6.5.20.deiValue"));
+            assert!(rules.contains(&"efm.6.6.40.deprecatedElement"));
+            assert!(rules.contains(&"efm.6.6.37.negativeValue"));
+        }
+    }
+}
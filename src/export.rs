@@ -0,0 +1,741 @@
+//! JSON and CSV fact exports
+//!
+//! Both use the columns of [`FactTable`]; optional extra columns are appended
+//! after the stable ones.
+//!
+//! With preferred labels enabled, a fact is exported once per statement it
+//! appears in (presentation role and `preferredLabel`), with the value as
+//! that statement shows it: negated roles flip the sign and period start/end
+//! roles mark which edge of the column the instant belongs to.
+//!
+//! Numeric values are always in base units: iXBRL `scale` is applied while
+//! parsing, and the `scale` column records it. Documents that state a
+//! multiplier only in their labels ("Revenue (in thousands)") can have it
+//! applied with [`ExportOptions::with_implied_scales`].
+//!
+//! [`NdjsonStream`] writes the same columns while the parser streams, for
+//! instances too large to hold in memory.
+
+use crate::columnar::{dimensions_cell, period_cells, FactTable, FACT_COLUMNS};
+use crate::ixbrl::apply_scale;
+use crate::linkbase::{label_semantics, PeriodEdge, LABEL_ROLE};
+use crate::model::{Context, Document, FactValue, Unit};
+use crate::parser::{parse_fraction, ParseEvent, ParseHandler};
+use crate::Result;
+use rust_decimal::prelude::ToPrimitive;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::io::Write;
+
+#[derive(Debug, Clone, Default)]
+pub struct ExportOptions {
+    /// Add a `documentation` column with the concept's documentation label
+    /// in this language
+    pub documentation_lang: Option<String>,
+    /// Add a `dimension_labels` column with the standard labels of each
+    /// axis and member in this language
+    pub dimension_label_lang: Option<String>,
+    /// Apply presentation `preferredLabel` semantics, with labels in this
+    /// language
+    pub preferred_label_lang: Option<String>,
+    /// Add a `scale` column with the power of ten applied to reach base units
+    pub scale_column: bool,
+    /// Scale unscaled values whose label in this language states a
+    /// multiplier, e.g. "(in millions)"
+    pub implied_scale_lang: Option<String>,
+}
+
+impl ExportOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_documentation(mut self, lang: &str) -> Self {
+        self.documentation_lang = Some(lang.to_string());
+        self
+    }
+
+    pub fn with_dimension_labels(mut self, lang: &str) -> Self {
+        self.dimension_label_lang = Some(lang.to_string());
+        self
+    }
+
+    pub fn with_preferred_labels(mut self, lang: &str) -> Self {
+        self.preferred_label_lang = Some(lang.to_string());
+        self
+    }
+
+    pub fn with_scale_column(mut self) -> Self {
+        self.scale_column = true;
+        self
+    }
+
+    /// Treat values as reported in the multiplier their label states. Only
+    /// for documents known to report presented rather than base-unit values;
+    /// implies [`ExportOptions::with_scale_column`].
+    pub fn with_implied_scales(mut self, lang: &str) -> Self {
+        self.implied_scale_lang = Some(lang.to_string());
+        self.scale_column = true;
+        self
+    }
+
+    fn extra_columns(&self) -> Vec<&'static str> {
+        let mut columns = Vec::new();
+        if self.documentation_lang.is_some() {
+            columns.push("documentation");
+        }
+        if self.dimension_label_lang.is_some() {
+            columns.push("dimension_labels");
+        }
+        if self.scale_column {
+            columns.push("scale");
+        }
+        if self.preferred_label_lang.is_some() {
+            columns.extend([
+                "presentation_role",
+                "preferred_label",
+                "label",
+                "period_label",
+                "presented_value",
+            ]);
+        }
+        columns
+    }
+}
+
+/// Where a fact is shown: presentation role and preferred label role
+#[derive(Clone, Copy)]
+struct Placement<'a> {
+    role: &'a str,
+    preferred_label: Option<&'a str>,
+}
+
+/// Rows to export: every fact once, or once per placement when preferred
+/// labels are applied. Facts that are not presented keep a single row.
+fn export_rows<'a>(
+    doc: &'a Document,
+    table: &FactTable,
+    options: &ExportOptions,
+) -> Vec<(usize, Option<Placement<'a>>)> {
+    if options.preferred_label_lang.is_none() {
+        return (0..table.len()).map(|i| (i, None)).collect();
+    }
+
+    let mut placements: HashMap<&str, Vec<Placement>> = HashMap::new();
+    for link in &doc.presentation_links {
+        let placement = Placement {
+            role: &link.role,
+            preferred_label: link.preferred_label.as_deref(),
+        };
+        let entry = placements.entry(link.to.as_str()).or_default();
+        if !entry
+            .iter()
+            .any(|p| p.role == placement.role && p.preferred_label == placement.preferred_label)
+        {
+            entry.push(placement);
+        }
+    }
+
+    let mut rows = Vec::with_capacity(table.len());
+    for i in 0..table.len() {
+        match placements.get(table.concept[i].as_str()) {
+            Some(found) => rows.extend(found.iter().map(|p| (i, Some(*p)))),
+            None => rows.push((i, None)),
+        }
+    }
+    rows
+}
+
+/// Multiplier stated in a label such as "Revenue (in thousands)"
+pub fn label_scale(label: &str) -> Option<i8> {
+    let label = label.to_ascii_lowercase();
+    [("thousands", 3), ("millions", 6), ("billions", 9)]
+        .into_iter()
+        .find(|(word, _)| label.contains(&format!("in {}", word)))
+        .map(|(_, scale)| scale)
+}
+
+/// Fact table to export with the scale applied to each row. Label-implied
+/// scales rewrite the value, numeric value and decimals of unscaled facts.
+fn export_table(doc: &Document, options: &ExportOptions) -> (FactTable, Vec<Option<i8>>) {
+    let mut table = doc.fact_table();
+    let mut scales = doc.facts.scales.clone();
+    let Some(lang) = &options.implied_scale_lang else {
+        return (table, scales);
+    };
+
+    for (i, applied) in scales.iter_mut().enumerate() {
+        let FactValue::Decimal(value) = doc.facts.values[i] else {
+            continue;
+        };
+        if applied.is_some() || table.unit[i].is_none() {
+            continue;
+        }
+        let Some(scale) = doc
+            .label(&table.concept[i], LABEL_ROLE, lang)
+            .and_then(label_scale)
+        else {
+            continue;
+        };
+        let Some(scaled) = apply_scale(value, scale.into()) else {
+            continue;
+        };
+        table.value[i] = Some(scaled.to_string());
+        table.numeric_value[i] = scaled.to_f64();
+        table.decimals[i] = table.decimals[i].and_then(|d| d.checked_sub(scale));
+        *applied = Some(scale);
+    }
+    (table, scales)
+}
+
+/// One row as text cells, in [`FACT_COLUMNS`] order
+fn row_cells(table: &FactTable, i: usize) -> [Option<String>; 16] {
+    [
+        table.fact_id[i].clone(),
+        Some(table.concept[i].clone()),
+        table.context_id[i].clone(),
+        table.entity_scheme[i].clone(),
+        table.entity_identifier[i].clone(),
+        table.period_type[i].map(str::to_string),
+        table.period_start[i].clone(),
+        table.period_end[i].clone(),
+        table.dimensions[i].clone(),
+        table.unit[i].clone(),
+        table.value[i].clone(),
+        table.numeric_value[i].map(|v| v.to_string()),
+        table.decimals[i].map(|v| v.to_string()),
+        Some(table.is_nil[i].to_string()),
+        table.numerator[i].clone(),
+        table.denominator[i].clone(),
+    ]
+}
+
+/// Numeric value as the statement presents it
+fn presented_value(
+    doc: &Document,
+    table: &FactTable,
+    i: usize,
+    placement: Option<Placement>,
+) -> Option<f64> {
+    let sign = doc
+        .fact(i)?
+        .natural_sign(placement.and_then(|p| p.preferred_label))?;
+    table.numeric_value[i].map(|v| v.abs() * f64::from(sign.presented))
+}
+
+fn extra_cells(
+    doc: &Document,
+    table: &FactTable,
+    scales: &[Option<i8>],
+    i: usize,
+    placement: Option<Placement>,
+    options: &ExportOptions,
+) -> Vec<Option<String>> {
+    let concept = &table.concept[i];
+    let mut cells = Vec::new();
+    if let Some(lang) = &options.documentation_lang {
+        cells.push(doc.documentation(concept, lang).map(str::to_string));
+    }
+    if let Some(lang) = &options.dimension_label_lang {
+        let context = doc.fact(i).and_then(|f| f.context());
+        cells.push(context.and_then(|c| dimension_labels(doc, c, lang)));
+    }
+    if options.scale_column {
+        cells.push(Some(scales[i].unwrap_or(0).to_string()));
+    }
+    if let Some(lang) = &options.preferred_label_lang {
+        let preferred = placement.and_then(|p| p.preferred_label);
+        let label = preferred
+            .and_then(|role| doc.label(concept, role, lang))
+            .or_else(|| doc.label(concept, LABEL_ROLE, lang));
+        let period = preferred.and_then(|role| label_semantics(role).period);
+        cells.push(placement.map(|p| p.role.to_string()));
+        cells.push(preferred.map(str::to_string));
+        cells.push(label.map(str::to_string));
+        cells.push(period.map(|edge| match edge {
+            PeriodEdge::Start => "start".to_string(),
+            PeriodEdge::End => "end".to_string(),
+        }));
+        cells.push(presented_value(doc, table, i, placement).map(|v| v.to_string()));
+    }
+    cells
+}
+
+/// `Axis label / Member label` pairs joined by `; `. QNames without a
+/// label are kept, and typed members show the text of their value.
+fn dimension_labels(doc: &Document, context: &Context, lang: &str) -> Option<String> {
+    let dims = context.dimensions();
+    let label = |qname: &str| {
+        doc.label_for(qname, LABEL_ROLE, lang)
+            .unwrap_or(qname)
+            .to_string()
+    };
+    let member = |value: &str| {
+        if !value.contains('<') {
+            return label(value);
+        }
+        let mut text = String::new();
+        let mut in_tag = false;
+        for c in value.chars() {
+            match c {
+                '<' => in_tag = true,
+                '>' => in_tag = false,
+                c if !in_tag => text.push(c),
+                _ => {}
+            }
+        }
+        text.trim().to_string()
+    };
+    (!dims.is_empty()).then(|| {
+        dims.iter()
+            .map(|(dim, value)| format!("{} / {}", label(dim), member(value)))
+            .collect::<Vec<_>>()
+            .join("; ")
+    })
+}
+
+pub(crate) fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Column names and text cells of the exported rows, as written to CSV;
+/// missing values are empty
+pub fn to_rows(doc: &Document, options: &ExportOptions) -> (Vec<&'static str>, Vec<Vec<String>>) {
+    let (table, scales) = export_table(doc, options);
+    let header = FACT_COLUMNS
+        .iter()
+        .copied()
+        .chain(options.extra_columns())
+        .collect();
+    let rows = export_rows(doc, &table, options)
+        .into_iter()
+        .map(|(i, placement)| {
+            row_cells(&table, i)
+                .into_iter()
+                .chain(extra_cells(doc, &table, &scales, i, placement, options))
+                .map(Option::unwrap_or_default)
+                .collect()
+        })
+        .collect();
+    (header, rows)
+}
+
+pub fn write_csv<W: Write>(doc: &Document, writer: &mut W, options: &ExportOptions) -> Result<()> {
+    let (header, rows) = to_rows(doc, options);
+    writeln!(writer, "{}", header.join(","))?;
+    for row in rows {
+        let cells: Vec<String> = row.iter().map(|cell| csv_field(cell)).collect();
+        writeln!(writer, "{}", cells.join(","))?;
+    }
+    Ok(())
+}
+
+pub fn to_csv(doc: &Document, options: &ExportOptions) -> Result<String> {
+    let mut out = Vec::new();
+    write_csv(doc, &mut out, options)?;
+    Ok(String::from_utf8_lossy(&out).into_owned())
+}
+
+/// Facts as a JSON array of objects keyed by column name
+pub fn to_json(doc: &Document, options: &ExportOptions) -> Value {
+    let (table, scales) = export_table(doc, options);
+    let rows = export_rows(doc, &table, options)
+        .into_iter()
+        .map(|(i, placement)| {
+            let mut row = Map::new();
+            for (column, cell) in FACT_COLUMNS.iter().zip(row_cells(&table, i)) {
+                row.insert(
+                    column.to_string(),
+                    cell.map(Value::String).unwrap_or(Value::Null),
+                );
+            }
+            // Keep numeric columns typed
+            row.insert("numeric_value".to_string(), json!(table.numeric_value[i]));
+            row.insert("decimals".to_string(), json!(table.decimals[i]));
+            row.insert("is_nil".to_string(), json!(table.is_nil[i]));
+            for (column, cell) in options
+                .extra_columns()
+                .into_iter()
+                .zip(extra_cells(doc, &table, &scales, i, placement, options))
+            {
+                row.insert(column.to_string(), json!(cell));
+            }
+            if options.scale_column {
+                row.insert("scale".to_string(), json!(scales[i].unwrap_or(0)));
+            }
+            if options.preferred_label_lang.is_some() {
+                row.insert(
+                    "presented_value".to_string(),
+                    json!(presented_value(doc, &table, i, placement)),
+                );
+            }
+            Value::Object(row)
+        })
+        .collect();
+    Value::Array(rows)
+}
+
+/// Facts as newline-delimited JSON, one [`to_json`] object per line
+pub fn write_ndjson<W: Write>(
+    doc: &Document,
+    writer: &mut W,
+    options: &ExportOptions,
+) -> Result<()> {
+    if let Value::Array(rows) = to_json(doc, options) {
+        for row in rows {
+            writeln!(writer, "{}", row)?;
+        }
+    }
+    Ok(())
+}
+
+/// [`ParseHandler`] writing each fact as an NDJSON line as soon as it is
+/// parsed, holding only contexts and units.
+///
+/// Facts are resolved against the contexts and units read before them; a
+/// fact whose context or unit follows it keeps the `context_id` and `unit`
+/// reference but has null entity, period and dimension columns. Such facts
+/// are counted in [`NdjsonStream::unresolved`].
+pub struct NdjsonStream<W: Write> {
+    writer: W,
+    contexts: HashMap<String, Context>,
+    units: HashMap<String, Unit>,
+    pub facts: usize,
+    pub unresolved: usize,
+}
+
+impl<W: Write> NdjsonStream<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            contexts: HashMap::new(),
+            units: HashMap::new(),
+            facts: 0,
+            unresolved: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn write_fact(&mut self, fact: &crate::model::Fact) -> Result<()> {
+        let context = self.contexts.get(&fact.context_ref);
+        let unit = match &fact.unit_ref {
+            Some(id) => self.units.get(id).map(Unit::canonical),
+            None => None,
+        };
+        if context.is_none() || (fact.unit_ref.is_some() && unit.is_none()) {
+            self.unresolved += 1;
+        }
+        let (period_type, period_start, period_end) = period_cells(context.map(|c| &c.period));
+        let fraction = (fact.unit_ref.is_some() && !fact.nil)
+            .then(|| parse_fraction(&fact.value))
+            .flatten();
+        let numeric_value = match fraction {
+            Some((numerator, denominator)) => FactValue::Fraction {
+                numerator,
+                denominator,
+            }
+            .as_f64(),
+            None => (fact.unit_ref.is_some() && !fact.nil)
+                .then(|| fact.value.trim().parse::<f64>().ok())
+                .flatten(),
+        };
+        let row = json!({
+            "fact_id": fact.id,
+            "concept": fact.concept,
+            "context_id": fact.context_ref,
+            "entity_scheme": context.map(|c| &c.entity.scheme),
+            "entity_identifier": context.map(|c| &c.entity.identifier),
+            "period_type": period_type,
+            "period_start": period_start,
+            "period_end": period_end,
+            "dimensions": context.and_then(dimensions_cell),
+            // Unresolved units keep their reference
+            "unit": unit.or_else(|| fact.unit_ref.clone()),
+            "value": (!fact.nil).then_some(&fact.value),
+            "numeric_value": numeric_value,
+            "decimals": fact.decimals,
+            "is_nil": fact.nil,
+            "numerator": fraction.map(|(n, _)| n.to_string()),
+            "denominator": fraction.map(|(_, d)| d.to_string()),
+        });
+        writeln!(self.writer, "{}", row)?;
+        self.facts += 1;
+        Ok(())
+    }
+}
+
+impl<W: Write> ParseHandler for NdjsonStream<W> {
+    fn handle(&mut self, event: ParseEvent<'_>) -> Result<()> {
+        match event {
+            ParseEvent::Context(context) => {
+                self.contexts.insert(context.id.clone(), context.clone());
+            }
+            ParseEvent::Unit(unit) => {
+                self.units.insert(unit.id.to_string(), unit.clone());
+            }
+            ParseEvent::Fact(fact) => self.write_fact(fact)?,
+            ParseEvent::Footnote(_) | ParseEvent::FootnoteArc(_) | ParseEvent::Tuple(_) => {}
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Decimal, FactValue, LabelLink, PresentationLink};
+
+    #[test]
+    fn test_exports_with_documentation() {
+        let mut doc = Document::new();
+        let concept = doc.intern_concept("us-gaap:Revenues");
+        doc.facts.push(
+            concept,
+            0,
+            0,
+            FactValue::Decimal(Decimal::from(10)),
+            Some(0),
+            None,
+        );
+        doc.label_links.push(LabelLink {
+            concept: "us-gaap:Revenues".to_string(),
+            label: "Revenue, \"net\"".to_string(),
+            role: crate::linkbase::DOCUMENTATION_ROLE.to_string(),
+            lang: "en-US".to_string(),
+        });
+        let options = ExportOptions::new().with_documentation("en");
+
+        let csv = to_csv(&doc, &options).unwrap();
+        let mut lines = csv.lines();
+        assert!(lines
+            .next()
+            .unwrap()
+            .ends_with(",is_nil,numerator,denominator,documentation"));
+        assert!(lines
+            .next()
+            .unwrap()
+            .ends_with(",10,0,false,,,\"Revenue, \"\"net\"\"\""));
+
+        let json = to_json(&doc, &options);
+        assert_eq!(json[0]["documentation"], "Revenue, \"net\"");
+        assert_eq!(json[0]["numeric_value"], 10.0);
+
+        let plain = to_json(&doc, &ExportOptions::new());
+        assert!(plain[0].get("documentation").is_none());
+    }
+
+    #[test]
+    fn test_exports_with_dimension_labels() {
+        let mut doc = crate::Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:xbrldi="http://xbrl.org/2006/xbrldi" xmlns:us-gaap="http://fasb.org/us-gaap/2023" xmlns:a="http://example.com/a">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier>
+    <xbrli:segment><xbrldi:explicitMember dimension="srt:ProductOrServiceAxis">a:SoftwareMember</xbrldi:explicitMember>
+      <xbrldi:typedMember dimension="a:RegionAxis"><a:Region>EMEA</a:Region></xbrldi:typedMember></xbrli:segment></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:context id="d"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <us-gaap:Revenues contextRef="c">10</us-gaap:Revenues>
+  <us-gaap:Revenues contextRef="d">30</us-gaap:Revenues>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+        for (concept, label) in [
+            ("srt:ProductOrServiceAxis", "Products and Services [Axis]"),
+            ("a:SoftwareMember", "Software [Member]"),
+        ] {
+            doc.label_links.push(LabelLink {
+                concept: concept.to_string(),
+                label: label.to_string(),
+                role: LABEL_ROLE.to_string(),
+                lang: "en".to_string(),
+            });
+        }
+        let options = ExportOptions::new().with_dimension_labels("en");
+
+        let json = to_json(&doc, &options);
+        let region = json[0]["dimensions"]
+            .as_str()
+            .unwrap()
+            .contains("a:RegionAxis=");
+        assert!(region);
+        assert_eq!(
+            json[0]["dimension_labels"],
+            "a:RegionAxis / EMEA; Products and Services [Axis] / Software [Member]"
+        );
+        assert!(json[1]["dimension_labels"].is_null());
+
+        let csv = to_csv(&doc, &options).unwrap();
+        assert!(csv
+            .lines()
+            .next()
+            .unwrap()
+            .ends_with(",is_nil,numerator,denominator,dimension_labels"));
+    }
+
+    #[test]
+    fn test_exports_with_scales() {
+        let mut doc = Document::new();
+        doc.units.push(crate::model::Unit {
+            id: "USD".to_string(),
+            unit_type: crate::model::UnitType::Simple(vec![crate::model::Measure {
+                prefix: "iso4217".to_string(),
+                namespace: crate::efm::ISO4217_NAMESPACE.to_string(),
+                name: "USD".to_string(),
+            }]),
+        });
+        let revenue = doc.intern_concept("ex:Revenue");
+        let cost = doc.intern_concept("ex:Cost");
+        // Scaled by iXBRL while parsing
+        doc.facts.push(
+            revenue,
+            0,
+            1,
+            FactValue::Decimal(Decimal::from(89_498_000_000i64)),
+            Some(-6),
+            None,
+        );
+        doc.facts.scales[0] = Some(6);
+        // Reported in thousands, as only its label says
+        doc.facts.push(
+            cost,
+            0,
+            1,
+            FactValue::Decimal(Decimal::from(1500)),
+            Some(0),
+            None,
+        );
+        doc.label_links.push(LabelLink {
+            concept: "ex:Cost".to_string(),
+            label: "Cost of sales (in thousands)".to_string(),
+            role: LABEL_ROLE.to_string(),
+            lang: "en".to_string(),
+        });
+
+        let json = to_json(&doc, &ExportOptions::new().with_scale_column());
+        assert_eq!(json[0]["scale"], 6);
+        assert_eq!(json[1]["scale"], 0);
+        assert_eq!(json[1]["value"], "1500");
+
+        let csv = to_csv(&doc, &ExportOptions::new().with_implied_scales("en")).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].ends_with(",is_nil,numerator,denominator,scale"));
+        assert!(lines[1].ends_with(",89498000000,89498000000,-6,false,,,6"));
+        assert!(lines[2].ends_with(",1500000,1500000,-3,false,,,3"));
+        assert_eq!(label_scale("Revenues (In Millions)"), Some(6));
+        assert_eq!(label_scale("Revenues"), None);
+    }
+
+    #[test]
+    fn test_exports_with_preferred_labels() {
+        let mut doc = Document::new();
+        let concept = doc.intern_concept("us-gaap:PaymentsOfDividends");
+        doc.facts.push(
+            concept,
+            0,
+            0,
+            FactValue::Decimal(Decimal::from(250)),
+            Some(0),
+            None,
+        );
+        let other = doc.intern_concept("dei:EntityRegistrantName");
+        doc.facts
+            .push(other, 0, 0, FactValue::Text("Acme".into()), None, None);
+        let negated = "http://www.xbrl.org/2009/role/negatedLabel";
+        for (role, preferred) in [
+            ("http://acme.com/role/CashFlows", Some(negated)),
+            ("http://acme.com/role/Equity", None),
+        ] {
+            doc.presentation_links.push(PresentationLink {
+                role: role.to_string(),
+                from: "us-gaap:StatementLineItems".to_string(),
+                to: "us-gaap:PaymentsOfDividends".to_string(),
+                order: 1.0,
+                priority: None,
+                use_attribute: None,
+                preferred_label: preferred.map(str::to_string),
+            });
+        }
+        doc.label_links.push(LabelLink {
+            concept: "us-gaap:PaymentsOfDividends".to_string(),
+            label: "Dividends paid".to_string(),
+            role: negated.to_string(),
+            lang: "en-US".to_string(),
+        });
+        let options = ExportOptions::new().with_preferred_labels("en");
+
+        let json = to_json(&doc, &options);
+        assert_eq!(json.as_array().unwrap().len(), 3);
+        assert_eq!(
+            json[0]["presentation_role"],
+            "http://acme.com/role/CashFlows"
+        );
+        assert_eq!(json[0]["label"], "Dividends paid");
+        assert_eq!(json[0]["presented_value"], -250.0);
+        assert_eq!(json[0]["numeric_value"], 250.0);
+        assert_eq!(json[1]["presented_value"], 250.0);
+        assert!(json[1]["label"].is_null());
+        assert!(json[2]["presentation_role"].is_null());
+
+        let csv = to_csv(&doc, &options).unwrap();
+        assert!(csv
+            .lines()
+            .nth(1)
+            .unwrap()
+            .ends_with(",Dividends paid,,-250"));
+    }
+
+    #[test]
+    fn test_label_semantics() {
+        let start = label_semantics(crate::linkbase::PERIOD_START_LABEL_ROLE);
+        assert_eq!(start.period, Some(PeriodEdge::Start));
+        assert!(!start.negated);
+        let end = label_semantics("http://www.xbrl.org/2009/role/negatedPeriodEndLabel");
+        assert_eq!(end.period, Some(PeriodEdge::End));
+        assert!(end.negated);
+        assert!(label_semantics("http://www.xbrl.org/2009/role/negatedTotalLabel").total);
+        assert_eq!(label_semantics(LABEL_ROLE), Default::default());
+    }
+
+    #[test]
+    fn test_streaming_ndjson_matches_document_export() {
+        let instance = r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <a:Early contextRef="FY" unitRef="USD" decimals="0">1</a:Early>
+  <xbrli:context id="FY"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2023-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <a:Revenue id="f1" contextRef="FY" unitRef="USD" decimals="-3">125000</a:Revenue>
+  <a:Name contextRef="FY">Example</a:Name>
+</xbrli:xbrl>"#;
+        let parser = crate::Parser::new();
+        let mut stream = NdjsonStream::new(Vec::new());
+        parser
+            .parse_bytes_streaming(instance.as_bytes(), &mut stream)
+            .unwrap();
+        assert_eq!((stream.facts, stream.unresolved), (3, 1));
+        let streamed = String::from_utf8(stream.into_inner()).unwrap();
+
+        let doc = parser.parse_str(instance).unwrap();
+        let mut whole = Vec::new();
+        write_ndjson(&doc, &mut whole, &ExportOptions::new()).unwrap();
+        let whole = String::from_utf8(whole).unwrap();
+
+        let rows = |text: &str| -> Vec<Value> {
+            text.lines()
+                .map(|l| serde_json::from_str(l).unwrap())
+                .collect()
+        };
+        let (streamed, whole) = (rows(&streamed), rows(&whole));
+        assert_eq!(streamed[1..], whole[1..]);
+        assert_eq!(streamed[0]["period_end"], Value::Null);
+        assert_eq!(streamed[0]["unit"], "USD");
+        assert_eq!(whole[0]["period_end"], "2023-12-31");
+    }
+}
@@ -0,0 +1,208 @@
+//! Combined view over the documents of one filing
+//!
+//! A filing is a primary instance (XBRL or iXBRL) plus any exhibits that
+//! carry their own inline XBRL, e.g. an EX-99 earnings release. Exhibits use
+//! a relative schemaRef back to the primary document's extension taxonomy;
+//! resolving those refs against each document's location tells us which
+//! documents belong together.
+
+use crate::ixbrl::{is_inline_xbrl, InlineParser};
+use crate::model::{Document, FactView};
+use crate::{Parser, ParserOptions, Result};
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Clone)]
+pub struct FilingDocument {
+    pub name: String,
+    pub location: Option<PathBuf>,
+    pub document: Document,
+}
+
+impl FilingDocument {
+    /// schemaRef resolved against the document's own location
+    pub fn resolved_schema(&self) -> Option<String> {
+        let href = self.document.schema_ref.as_deref()?;
+        if href.contains("://") {
+            return Some(href.to_string());
+        }
+        let base = self
+            .location
+            .as_deref()
+            .and_then(Path::parent)
+            .unwrap_or(Path::new(""));
+        Some(normalize(&base.join(href)).to_string_lossy().into_owned())
+    }
+}
+
+/// Documents of one filing, keyed by document name; the first one added is
+/// the primary document
+#[derive(Clone, Default)]
+pub struct Filing {
+    documents: Vec<FilingDocument>,
+    options: ParserOptions,
+}
+
+impl Filing {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Parse a file (XBRL or iXBRL) and add it under its file name
+    pub fn add_file<P: AsRef<Path>>(&mut self, path: P) -> Result<&FilingDocument> {
+        let path = path.as_ref();
+        let data = std::fs::read(path)?;
+        let document = if is_inline_xbrl(&data) {
+            InlineParser::new()
+                .with_options(self.options.clone())
+                .parse_bytes(&data)?
+        } else {
+            Parser::new()
+                .with_options(self.options.clone())
+                .parse_bytes(&data)?
+        };
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let document = document.with_source_uri(path.display().to_string());
+        Ok(self.insert(name, Some(path.to_path_buf()), document))
+    }
+
+    /// Add an already parsed document; replaces any document with the same name
+    pub fn add_document(&mut self, name: &str, document: Document) -> &FilingDocument {
+        self.insert(name.to_string(), None, document)
+    }
+
+    fn insert(
+        &mut self,
+        name: String,
+        location: Option<PathBuf>,
+        document: Document,
+    ) -> &FilingDocument {
+        let entry = FilingDocument {
+            name,
+            location,
+            document,
+        };
+        let index = match self.documents.iter().position(|d| d.name == entry.name) {
+            Some(i) => {
+                self.documents[i] = entry;
+                i
+            }
+            None => {
+                self.documents.push(entry);
+                self.documents.len() - 1
+            }
+        };
+        &self.documents[index]
+    }
+
+    pub fn primary(&self) -> Option<&FilingDocument> {
+        self.documents.first()
+    }
+
+    pub fn document(&self, name: &str) -> Option<&FilingDocument> {
+        self.documents.iter().find(|d| d.name == name)
+    }
+
+    pub fn documents(&self) -> &[FilingDocument] {
+        &self.documents
+    }
+
+    /// The primary document's extension taxonomy, resolved
+    pub fn extension_schema(&self) -> Option<String> {
+        self.primary()?.resolved_schema()
+    }
+
+    /// Non-primary documents whose schemaRef resolves to the primary
+    /// document's extension taxonomy
+    pub fn exhibits(&self) -> impl Iterator<Item = &FilingDocument> {
+        let schema = self.extension_schema();
+        self.documents
+            .iter()
+            .skip(1)
+            .filter(move |d| schema.is_some() && d.resolved_schema() == schema)
+    }
+
+    /// Facts of the primary document and its exhibits, tagged with the name
+    /// of the document they came from
+    pub fn facts(&self) -> impl Iterator<Item = (&str, FactView<'_>)> {
+        self.primary()
+            .into_iter()
+            .chain(self.exhibits())
+            .flat_map(|d| d.document.iter_facts().map(move |f| (d.name.as_str(), f)))
+    }
+}
+
+/// Lexically resolve `.` and `..` components
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Decimal, FactValue};
+
+    fn document(schema_ref: &str, concept: &str) -> Document {
+        let mut doc = Document::new();
+        doc.schema_ref = Some(schema_ref.to_string());
+        let id = doc.intern_concept(concept);
+        doc.facts
+            .push(id, 0, 0, FactValue::Decimal(Decimal::ONE), None, None);
+        doc
+    }
+
+    #[test]
+    fn test_exhibits_merge_by_schema_ref() {
+        let mut filing = Filing::new();
+        filing.insert(
+            "aapl-20231028.htm".to_string(),
+            Some(PathBuf::from("filing/aapl-20231028.htm")),
+            document("aapl-20231028.xsd", "us-gaap:Revenues"),
+        );
+        filing.insert(
+            "ex99-1.htm".to_string(),
+            Some(PathBuf::from("filing/exhibits/ex99-1.htm")),
+            document("../aapl-20231028.xsd", "us-gaap:NetIncomeLoss"),
+        );
+        filing.insert(
+            "other.htm".to_string(),
+            Some(PathBuf::from("filing/other.htm")),
+            document("msft-20231028.xsd", "us-gaap:Assets"),
+        );
+
+        assert_eq!(
+            filing.extension_schema().as_deref(),
+            Path::new("filing").join("aapl-20231028.xsd").to_str()
+        );
+        let exhibits: Vec<_> = filing.exhibits().map(|d| d.name.as_str()).collect();
+        assert_eq!(exhibits, ["ex99-1.htm"]);
+
+        let facts: Vec<_> = filing.facts().map(|(doc, f)| (doc, f.concept())).collect();
+        assert_eq!(
+            facts,
+            [
+                ("aapl-20231028.htm", "us-gaap:Revenues"),
+                ("ex99-1.htm", "us-gaap:NetIncomeLoss"),
+            ]
+        );
+    }
+}
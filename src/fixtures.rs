@@ -0,0 +1,372 @@
+//! Public filings for benchmarks and conformance runs
+//!
+//! `crabrl fixtures pull` downloads a curated set of filings into
+//! `fixtures/<tier>/<filer>/`, in size tiers so a quick run can stop at
+//! `small`. Every built-in file has its digest pinned here and is checked
+//! against it. Manifest entries without a digest are recorded in
+//! `fixtures/SHA256SUMS` when first downloaded and checked against that
+//! record afterwards, so everyone who shares the file benchmarks the same
+//! bytes. Files already present with the expected digest are not fetched
+//! again.
+//!
+//! The built-in set holds only small and medium SEC filings. There are no
+//! built-in huge-tier or ESEF filings: pull those from a JSON manifest of
+//! your own, see [`load_manifest`].
+
+use crate::{Error, Result};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Digests of the downloaded fixtures, in `sha256sum` format
+pub const CHECKSUMS_FILE: &str = "SHA256SUMS";
+
+/// Size class of a fixture
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Tier {
+    /// Instances up to about 2 MB, enough for a quick run
+    Small,
+    /// Large 10-K instances with their linkbases
+    Medium,
+    /// The largest filings, for memory and throughput limits; only pulled
+    /// from a manifest, none are built in
+    Huge,
+}
+
+impl Tier {
+    pub const ALL: [Tier; 3] = [Tier::Small, Tier::Medium, Tier::Huge];
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "small" => Some(Tier::Small),
+            "medium" => Some(Tier::Medium),
+            "huge" => Some(Tier::Huge),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Tier::Small => "small",
+            Tier::Medium => "medium",
+            Tier::Huge => "huge",
+        }
+    }
+}
+
+/// A file to download
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fixture {
+    pub tier: Tier,
+    /// Where the filing comes from, such as `sec` or `esef`
+    pub source: String,
+    /// Relative path below the fixtures directory
+    pub path: String,
+    pub url: String,
+    /// Lowercase hex SHA-256 of the file; `None` records it on first
+    /// download
+    pub sha256: Option<String>,
+}
+
+const EDGAR: &str = "https://www.sec.gov/Archives/edgar/data";
+
+/// (tier, path, filing folder under [`EDGAR`], SHA-256)
+const SEC_FIXTURES: &[(Tier, &str, &str, &str)] = &[
+    (
+        Tier::Small,
+        "small/apple/aapl-20230930_htm.xml",
+        "320193/000032019323000106",
+        "9ba479d9d5d674416fe64f2a7d3e306f5b5c30ecb0aa9d87737b80ad740f76d9",
+    ),
+    (
+        Tier::Small,
+        "small/apple/aapl-20230930.xsd",
+        "320193/000032019323000106",
+        "cfb3e49a01af2843495d4a517210526d9a2235a2a09a8105d2b88ef64539b7b5",
+    ),
+    (
+        Tier::Small,
+        "small/apple/aapl-20230930_cal.xml",
+        "320193/000032019323000106",
+        "2506e7bc4f8ab5cba92314f72a3f00a866ff73352d2e72d249ae7ea435452895",
+    ),
+    (
+        Tier::Small,
+        "small/apple/aapl-20230930_lab.xml",
+        "320193/000032019323000106",
+        "5f479dc90d9d634d40f99df4b92b48cceb04a2d6177f84007e655c25493ea119",
+    ),
+    (
+        Tier::Medium,
+        "medium/alphabet/goog-20231231_htm.xml",
+        "1652044/000165204424000022",
+        "c4d075d235fbae1754d1d624386d7fcd5400cfadc1c9851ced689066613e5876",
+    ),
+    (
+        Tier::Medium,
+        "medium/alphabet/goog-20231231.xsd",
+        "1652044/000165204424000022",
+        "67cfcf3576e1429262f929be4b51b83a5a736bd875ef324849b7969e00c1783a",
+    ),
+    (
+        Tier::Medium,
+        "medium/alphabet/goog-20231231_cal.xml",
+        "1652044/000165204424000022",
+        "b4de865aad6a7aa8a385889811e7ec8487a2ba35c61cc808e45444512a83670a",
+    ),
+    (
+        Tier::Medium,
+        "medium/tesla/tsla-20231231_htm.xml",
+        "1318605/000162828024002390",
+        "12411c663110a20364112f7ddbd7222b6bb75db369762adb4e74e6f562e4e79f",
+    ),
+    (
+        Tier::Medium,
+        "medium/tesla/tsla-20231231.xsd",
+        "1318605/000162828024002390",
+        "9d5345cb351a5fea6e6015c0a662977ae6775ddf87ec7719e6fb8eb2df76932c",
+    ),
+    (
+        Tier::Medium,
+        "medium/tesla/tsla-20231231_def.xml",
+        "1318605/000162828024002390",
+        "fbd380eac8c1de718e34ec02e2dc85f5987011e55f7859587cf45e054800b3cf",
+    ),
+];
+
+/// The curated SEC filings
+pub fn builtin() -> Vec<Fixture> {
+    SEC_FIXTURES
+        .iter()
+        .map(|&(tier, path, folder, sha256)| {
+            let file = path.rsplit('/').next().unwrap_or(path);
+            Fixture {
+                tier,
+                source: "sec".to_string(),
+                path: path.to_string(),
+                url: format!("{}/{}/{}", EDGAR, folder, file),
+                sha256: Some(sha256.to_string()),
+            }
+        })
+        .collect()
+}
+
+/// Read fixtures from a JSON array of objects with `tier`, `source`,
+/// `path`, `url` and optionally `sha256`
+pub fn load_manifest<P: AsRef<Path>>(path: P) -> Result<Vec<Fixture>> {
+    let text = std::fs::read_to_string(path)?;
+    let json: Value = serde_json::from_str(&text)
+        .map_err(|e| Error::Parse(format!("Invalid fixture manifest: {}", e)))?;
+    let entries = json
+        .as_array()
+        .ok_or_else(|| Error::Parse("Fixture manifest is not an array".to_string()))?;
+    entries.iter().map(fixture_from_json).collect()
+}
+
+fn fixture_from_json(entry: &Value) -> Result<Fixture> {
+    let field = |name: &str| {
+        entry[name]
+            .as_str()
+            .ok_or_else(|| Error::Parse(format!("Fixture without a `{}`: {}", name, entry)))
+    };
+    let tier = field("tier")?;
+    let tier =
+        Tier::parse(tier).ok_or_else(|| Error::Parse(format!("Unknown fixture tier {}", tier)))?;
+    let path = field("path")?;
+    // Fixtures stay inside the fixtures directory
+    if !Path::new(path)
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+    {
+        return Err(Error::Parse(format!(
+            "Fixture path {} leaves the directory",
+            path
+        )));
+    }
+    Ok(Fixture {
+        tier,
+        source: field("source")?.to_string(),
+        path: path.to_string(),
+        url: field("url")?.to_string(),
+        sha256: entry["sha256"].as_str().map(str::to_ascii_lowercase),
+    })
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Recorded digests by fixture path; empty when nothing was recorded yet
+pub fn read_checksums<P: AsRef<Path>>(dir: P) -> Result<BTreeMap<String, String>> {
+    let text = match std::fs::read_to_string(dir.as_ref().join(CHECKSUMS_FILE)) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(text
+        .lines()
+        .filter_map(|line| line.split_once("  "))
+        .map(|(digest, path)| (path.to_string(), digest.to_string()))
+        .collect())
+}
+
+fn write_checksums(dir: &Path, checksums: &BTreeMap<String, String>) -> Result<()> {
+    let text: String = checksums
+        .iter()
+        .map(|(path, digest)| format!("{}  {}\n", digest, path))
+        .collect();
+    std::fs::write(dir.join(CHECKSUMS_FILE), text)?;
+    Ok(())
+}
+
+/// What pulling did with a fixture
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PullStatus {
+    /// Already present with the expected digest
+    Present,
+    Downloaded,
+}
+
+/// Bring `fixtures` into `dir`, fetching the bytes of a URL with `fetch`.
+/// Stops at the first download whose digest is not the expected one.
+pub fn pull_with(
+    dir: &Path,
+    fixtures: &[Fixture],
+    mut fetch: impl FnMut(&str) -> Result<Vec<u8>>,
+) -> Result<Vec<(PathBuf, PullStatus)>> {
+    std::fs::create_dir_all(dir)?;
+    let mut checksums = read_checksums(dir)?;
+    let mut pulled = Vec::new();
+    for fixture in fixtures {
+        let target = dir.join(&fixture.path);
+        let expected = fixture
+            .sha256
+            .clone()
+            .or_else(|| checksums.get(&fixture.path).cloned());
+
+        // A present file that does not match is downloaded again
+        if let Ok(data) = std::fs::read(&target) {
+            let digest = sha256_hex(&data);
+            if expected.as_ref().is_none_or(|e| *e == digest) {
+                if checksums.get(&fixture.path) != Some(&digest) {
+                    checksums.insert(fixture.path.clone(), digest);
+                    write_checksums(dir, &checksums)?;
+                }
+                pulled.push((target, PullStatus::Present));
+                continue;
+            }
+        }
+
+        let data = fetch(&fixture.url)?;
+        let digest = sha256_hex(&data);
+        if let Some(expected) = expected.filter(|e| *e != digest) {
+            return Err(Error::Network {
+                url: fixture.url.clone(),
+                status: None,
+                message: format!(
+                    "SHA-256 {} does not match the expected {}",
+                    digest, expected
+                ),
+            });
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&target, &data)?;
+        checksums.insert(fixture.path.clone(), digest);
+        write_checksums(dir, &checksums)?;
+        pulled.push((target, PullStatus::Downloaded));
+    }
+    Ok(pulled)
+}
+
+/// [`pull_with`] over HTTP
+#[cfg(feature = "http")]
+pub fn pull(
+    client: &crate::http::HttpClient,
+    dir: &Path,
+    fixtures: &[Fixture],
+) -> Result<Vec<(PathBuf, PullStatus)>> {
+    pull_with(dir, fixtures, |url| Ok(client.get(url)?.to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pull_fixtures() {
+        let fixtures = builtin();
+        assert!(fixtures.iter().any(|f| f.tier == Tier::Medium));
+        // Built-in fixtures are never trusted on first download
+        assert!(fixtures.iter().all(|f| f.sha256.is_some()));
+        assert_eq!(
+            fixtures[0].url,
+            "https://www.sec.gov/Archives/edgar/data/320193/000032019323000106/aapl-20230930_htm.xml"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = dir.path().join("manifest.json");
+        std::fs::write(
+            &manifest,
+            format!(
+                r#"[
+  {{"tier": "small", "source": "esef", "path": "small/a/report.xhtml", "url": "https://example.com/a", "sha256": "{}"}},
+  {{"tier": "huge", "source": "esef", "path": "huge/b/report.xhtml", "url": "https://example.com/b"}}
+]"#,
+                sha256_hex(b"a").to_uppercase()
+            ),
+        )
+        .unwrap();
+        let fixtures = load_manifest(&manifest).unwrap();
+        assert_eq!(
+            fixtures[0].sha256.as_deref(),
+            Some(sha256_hex(b"a").as_str())
+        );
+        std::fs::write(
+            &manifest,
+            r#"[{"tier": "small", "source": "esef", "path": "../a", "url": "https://example.com/a"}]"#,
+        )
+        .unwrap();
+        assert!(load_manifest(&manifest).is_err());
+
+        let out = dir.path().join("fixtures");
+        let mut fetched = Vec::new();
+        let pulled = pull_with(&out, &fixtures, |url| {
+            fetched.push(url.to_string());
+            Ok(url
+                .trim_start_matches("https://example.com/")
+                .as_bytes()
+                .to_vec())
+        })
+        .unwrap();
+        assert_eq!(fetched.len(), 2);
+        assert!(pulled.iter().all(|(_, s)| *s == PullStatus::Downloaded));
+        // The unpinned fixture's digest is recorded
+        let checksums = read_checksums(&out).unwrap();
+        assert_eq!(checksums["huge/b/report.xhtml"], sha256_hex(b"b"));
+
+        // Nothing is fetched again; a changed file is, and must match
+        std::fs::write(out.join("huge/b/report.xhtml"), "tampered").unwrap();
+        let mut fetched = Vec::new();
+        let pulled = pull_with(&out, &fixtures, |url| {
+            fetched.push(url.to_string());
+            Ok(b"b".to_vec())
+        })
+        .unwrap();
+        assert_eq!(fetched, ["https://example.com/b"]);
+        assert_eq!(pulled[0].1, PullStatus::Present);
+        assert_eq!(
+            std::fs::read(out.join("huge/b/report.xhtml")).unwrap(),
+            b"b"
+        );
+
+        std::fs::remove_file(out.join("small/a/report.xhtml")).unwrap();
+        let err = pull_with(&out, &fixtures, |_| Ok(b"x".to_vec())).unwrap_err();
+        assert!(err.to_string().contains("does not match"));
+    }
+}
@@ -0,0 +1,471 @@
+//! HTTP client shared by the taxonomy and EDGAR fetchers
+//!
+//! Bulk jobs hit sec.gov and taxonomy hosts thousands of times, so every
+//! request goes through a concurrency limit, retries transient failures with
+//! exponential backoff (honoring `Retry-After`), and revalidates cached
+//! bodies with `If-None-Match` / `If-Modified-Since` instead of downloading
+//! them again. Cached bodies are kept on disk when a cache directory is
+//! configured; in memory only the most recently used ones are kept, up to a
+//! byte budget, so long jobs do not accumulate every body they fetched.
+
+use crate::{Error, Result};
+use parking_lot::{Condvar, Mutex};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    /// sec.gov rejects requests without a descriptive User-Agent with a contact
+    pub user_agent: String,
+    pub max_retries: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub max_concurrency: usize,
+    pub timeout: Duration,
+    /// Persist bodies and validators here so conditional GETs work across runs
+    pub cache_dir: Option<PathBuf>,
+    /// Bytes of recently used bodies kept in memory for revalidation
+    pub memory_cache_bytes: usize,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            user_agent: format!("crabrl/{}", env!("CARGO_PKG_VERSION")),
+            max_retries: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            max_concurrency: 4,
+            timeout: Duration::from_secs(60),
+            cache_dir: None,
+            memory_cache_bytes: 64 << 20,
+        }
+    }
+}
+
+impl HttpConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = user_agent.to_string();
+        self
+    }
+
+    pub fn with_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    pub fn with_max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_cache_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Keep at most `bytes` of bodies in memory; 0 revalidates from the disk
+    /// cache only
+    pub fn with_memory_cache(mut self, bytes: usize) -> Self {
+        self.memory_cache_bytes = bytes;
+        self
+    }
+
+    /// Delay before retry number `attempt` (0-based)
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(1u32 << attempt.min(16))
+            .min(self.max_backoff)
+    }
+}
+
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+#[derive(Clone)]
+struct CachedBody {
+    validators: Validators,
+    body: Arc<Vec<u8>>,
+}
+
+/// Cached bodies by URL, evicting the least recently used beyond a byte
+/// budget
+struct MemoryCache {
+    entries: HashMap<String, (CachedBody, u64)>,
+    bytes: usize,
+    capacity: usize,
+    clock: u64,
+}
+
+impl MemoryCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            bytes: 0,
+            capacity,
+            clock: 0,
+        }
+    }
+
+    fn get(&mut self, url: &str) -> Option<CachedBody> {
+        self.clock += 1;
+        let (cached, used) = self.entries.get_mut(url)?;
+        *used = self.clock;
+        Some(cached.clone())
+    }
+
+    fn insert(&mut self, url: &str, cached: CachedBody) {
+        if let Some((old, _)) = self.entries.remove(url) {
+            self.bytes -= old.body.len();
+        }
+        if cached.body.len() > self.capacity {
+            return;
+        }
+        while self.bytes + cached.body.len() > self.capacity {
+            let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(url, _)| url.clone())
+            else {
+                break;
+            };
+            if let Some((old, _)) = self.entries.remove(&oldest) {
+                self.bytes -= old.body.len();
+            }
+        }
+        self.clock += 1;
+        self.bytes += cached.body.len();
+        self.entries.insert(url.to_string(), (cached, self.clock));
+    }
+}
+
+/// Counting semaphore bounding in-flight requests
+struct Permits {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+struct Permit<'a>(&'a Permits);
+
+impl Permits {
+    fn acquire(&self) -> Permit<'_> {
+        let mut available = self.available.lock();
+        while *available == 0 {
+            self.released.wait(&mut available);
+        }
+        *available -= 1;
+        Permit(self)
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        *self.0.available.lock() += 1;
+        self.0.released.notify_one();
+    }
+}
+
+pub struct HttpClient {
+    agent: ureq::Agent,
+    config: HttpConfig,
+    permits: Permits,
+    cache: Mutex<MemoryCache>,
+}
+
+impl Default for HttpClient {
+    fn default() -> Self {
+        Self::new(HttpConfig::default())
+    }
+}
+
+enum Attempt {
+    Done(Arc<Vec<u8>>),
+    Retry(Option<Duration>, String),
+}
+
+impl HttpClient {
+    pub fn new(config: HttpConfig) -> Self {
+        let agent = ureq::AgentBuilder::new()
+            .user_agent(&config.user_agent)
+            .timeout(config.timeout)
+            .build();
+        Self {
+            agent,
+            permits: Permits {
+                available: Mutex::new(config.max_concurrency.max(1)),
+                released: Condvar::new(),
+            },
+            cache: Mutex::new(MemoryCache::new(config.memory_cache_bytes)),
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &HttpConfig {
+        &self.config
+    }
+
+    /// GET `url`, revalidating any cached copy and retrying transient
+    /// failures; the concurrency permit is only held while a request is in
+    /// flight, not while backing off
+    pub fn get(&self, url: &str) -> Result<Arc<Vec<u8>>> {
+        let cached = self.cached(url);
+
+        let mut attempt = 0;
+        loop {
+            let permit = self.permits.acquire();
+            let outcome = self.attempt(url, cached.as_ref())?;
+            drop(permit);
+            match outcome {
+                Attempt::Done(body) => return Ok(body),
+                Attempt::Retry(retry_after, reason) => {
+                    if attempt >= self.config.max_retries {
+                        return Err(Error::Network {
+                            url: url.to_string(),
+                            status: None,
+                            message: format!("giving up after {} retries ({})", attempt, reason),
+                        });
+                    }
+                    let delay = retry_after
+                        .unwrap_or_else(|| self.config.backoff(attempt))
+                        .min(self.config.max_backoff);
+                    std::thread::sleep(delay);
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    fn attempt(&self, url: &str, cached: Option<&CachedBody>) -> Result<Attempt> {
+        let mut request = self.agent.get(url);
+        if let Some(cached) = cached {
+            if let Some(etag) = &cached.validators.etag {
+                request = request.set("If-None-Match", etag);
+            }
+            if let Some(modified) = &cached.validators.last_modified {
+                request = request.set("If-Modified-Since", modified);
+            }
+        }
+
+        let response = match request.call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(status, response)) => {
+                return match status {
+                    404 | 410 => Err(Error::NotFound(url.to_string())),
+                    429 | 500 | 502 | 503 | 504 => Ok(Attempt::Retry(
+                        retry_after(&response),
+                        format!("HTTP {}", status),
+                    )),
+                    _ => Err(Error::Network {
+                        url: url.to_string(),
+                        status: Some(status),
+                        message: response.status_text().to_string(),
+                    }),
+                };
+            }
+            Err(ureq::Error::Transport(transport)) => {
+                return Ok(Attempt::Retry(None, transport.to_string()));
+            }
+        };
+
+        if response.status() == 304 {
+            // Only requests with a cached copy carry validators, so a 304
+            // without one has no body to fall back on
+            return match cached {
+                Some(cached) => Ok(Attempt::Done(cached.body.clone())),
+                None => Err(Error::Network {
+                    url: url.to_string(),
+                    status: Some(304),
+                    message: "Not Modified for a request without a cached copy".to_string(),
+                }),
+            };
+        }
+
+        let validators = Validators {
+            etag: response.header("ETag").map(str::to_string),
+            last_modified: response.header("Last-Modified").map(str::to_string),
+        };
+        let mut body = Vec::new();
+        if let Err(e) = response.into_reader().read_to_end(&mut body) {
+            return Ok(Attempt::Retry(None, e.to_string()));
+        }
+        let body = Arc::new(body);
+        self.store(
+            url,
+            CachedBody {
+                validators,
+                body: body.clone(),
+            },
+        );
+        Ok(Attempt::Done(body))
+    }
+
+    fn cache_paths(&self, url: &str) -> Option<(PathBuf, PathBuf)> {
+        use sha2::{Digest, Sha256};
+        let dir = self.config.cache_dir.as_ref()?;
+        let key: String = Sha256::digest(url.as_bytes())
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        Some((
+            dir.join(format!("{}.body", key)),
+            dir.join(format!("{}.json", key)),
+        ))
+    }
+
+    fn cached(&self, url: &str) -> Option<CachedBody> {
+        if let Some(cached) = self.cache.lock().get(url) {
+            return Some(cached);
+        }
+        let (body_path, meta_path) = self.cache_paths(url)?;
+        let validators = serde_json::from_slice(&std::fs::read(meta_path).ok()?).ok()?;
+        let body = Arc::new(std::fs::read(body_path).ok()?);
+        let cached = CachedBody { validators, body };
+        self.cache.lock().insert(url, cached.clone());
+        Some(cached)
+    }
+
+    fn store(&self, url: &str, cached: CachedBody) {
+        let revalidatable =
+            cached.validators.etag.is_some() || cached.validators.last_modified.is_some();
+        if !revalidatable {
+            return;
+        }
+        // The disk cache is best effort; a failed write only costs a re-download
+        if let Some((body_path, meta_path)) = self.cache_paths(url) {
+            let _ = std::fs::create_dir_all(body_path.parent().unwrap());
+            if std::fs::write(&body_path, cached.body.as_slice()).is_ok() {
+                if let Ok(meta) = serde_json::to_vec(&cached.validators) {
+                    let _ = std::fs::write(meta_path, meta);
+                }
+            }
+        }
+        self.cache.lock().insert(url, cached);
+    }
+}
+
+fn retry_after(response: &ureq::Response) -> Option<Duration> {
+    response
+        .header("Retry-After")
+        .and_then(|v| v.trim().parse().ok())
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// Serve canned responses, one per connection, and return the request heads
+    fn serve(responses: Vec<&'static str>) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/index.idx", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut head = String::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    head.push_str(&line);
+                }
+                requests.push(head);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn test_retry_and_conditional_get() {
+        let (url, server) = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+            "HTTP/1.1 304 Not Modified\r\nETag: \"v1\"\r\nConnection: close\r\n\r\n",
+        ]);
+        let client = HttpClient::new(
+            HttpConfig::new()
+                .with_backoff(Duration::from_millis(1), Duration::from_millis(10))
+                .with_max_concurrency(1),
+        );
+
+        assert_eq!(client.get(&url).unwrap().as_slice(), b"hello");
+        assert_eq!(client.get(&url).unwrap().as_slice(), b"hello");
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert!(!requests[1].to_ascii_lowercase().contains("if-none-match"));
+        assert!(requests[2]
+            .to_ascii_lowercase()
+            .contains("if-none-match: \"v1\""));
+    }
+
+    #[test]
+    fn test_memory_cache_is_bounded() {
+        let (url, server) = serve(vec![
+            "HTTP/1.1 200 OK\r\nETag: \"v1\"\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+            "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n",
+        ]);
+        let client = HttpClient::new(HttpConfig::new().with_memory_cache(4));
+
+        // The body exceeds the budget, so nothing is kept to revalidate and
+        // the second request is unconditional; its bare 304 is an error
+        assert_eq!(client.get(&url).unwrap().as_slice(), b"hello");
+        let err = client.get(&url).unwrap_err();
+        assert_eq!(err.status(), Some(304));
+        let requests = server.join().unwrap();
+        assert!(!requests[1].to_ascii_lowercase().contains("if-none-match"));
+
+        let body = |text: &str| CachedBody {
+            validators: Validators {
+                etag: None,
+                last_modified: None,
+            },
+            body: Arc::new(text.as_bytes().to_vec()),
+        };
+        let mut cache = MemoryCache::new(10);
+        cache.insert("a", body("aaaa"));
+        cache.insert("b", body("bbbb"));
+        assert!(cache.get("a").is_some());
+        cache.insert("c", body("cccc"));
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("a").is_some() && cache.get("c").is_some());
+        assert_eq!(cache.bytes, 8);
+    }
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let config = HttpConfig::new().with_backoff(Duration::from_secs(1), Duration::from_secs(8));
+        assert_eq!(config.backoff(0), Duration::from_secs(1));
+        assert_eq!(config.backoff(2), Duration::from_secs(4));
+        assert_eq!(config.backoff(10), Duration::from_secs(8));
+    }
+}
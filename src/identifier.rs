@@ -0,0 +1,316 @@
+//! Entity identifier validation
+//!
+//! Each context's `xbrli:identifier` is checked by the validator registered
+//! for its declared `scheme`; identifiers of unknown schemes pass. The
+//! standard registry covers SEC CIKs, UK Companies House numbers,
+//! Australian Business Numbers, Danish CVR numbers and ISINs, and callers
+//! can register their own.
+
+use crate::model::Document;
+use crate::validator::ValidationError;
+use std::collections::HashSet;
+use std::sync::{Arc, OnceLock};
+
+pub const CIK_SCHEME: &str = "http://www.sec.gov/CIK";
+pub const COMPANIES_HOUSE_SCHEME: &str = "http://www.companieshouse.gov.uk/";
+pub const ABN_SCHEME: &str = "http://www.ato.gov.au/abn";
+pub const CVR_SCHEME: &str = "http://www.dcca.dk/cvr";
+pub const ISIN_SCHEME: &str = "http://standards.iso.org/iso/6166";
+
+type Check = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// How identifiers of one scheme are validated
+#[derive(Clone)]
+pub struct IdentifierScheme {
+    /// Short name reported as the finding's concept, e.g. `CIK`
+    pub name: String,
+    pub scheme: String,
+    /// Format reported when an identifier fails the check
+    pub expected: String,
+    check: Check,
+}
+
+impl IdentifierScheme {
+    pub fn new<F>(name: &str, scheme: &str, expected: &str, check: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            scheme: scheme.to_string(),
+            expected: expected.to_string(),
+            check: Arc::new(check),
+        }
+    }
+
+    pub fn is_valid(&self, identifier: &str) -> bool {
+        (self.check)(identifier)
+    }
+}
+
+impl std::fmt::Debug for IdentifierScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IdentifierScheme")
+            .field("name", &self.name)
+            .field("scheme", &self.scheme)
+            .finish()
+    }
+}
+
+/// Scheme URIs compare without protocol, `www.`, trailing slash or case
+fn scheme_key(scheme: &str) -> String {
+    let scheme = scheme.trim().to_ascii_lowercase();
+    let scheme = scheme
+        .strip_prefix("https://")
+        .or_else(|| scheme.strip_prefix("http://"))
+        .unwrap_or(&scheme);
+    let scheme = scheme.strip_prefix("www.").unwrap_or(scheme);
+    scheme.trim_end_matches('/').to_string()
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IdentifierRegistry {
+    schemes: Vec<IdentifierScheme>,
+}
+
+impl IdentifierRegistry {
+    /// A registry without any scheme
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    pub fn standard() -> Self {
+        Self::empty()
+            .with(IdentifierScheme::new(
+                "CIK",
+                CIK_SCHEME,
+                "10-digit number",
+                is_cik,
+            ))
+            .with(IdentifierScheme::new(
+                "CompanyNumber",
+                COMPANIES_HOUSE_SCHEME,
+                "Companies House number",
+                is_company_number,
+            ))
+            .with(IdentifierScheme::new(
+                "ABN",
+                ABN_SCHEME,
+                "11-digit ABN",
+                is_abn,
+            ))
+            .with(IdentifierScheme::new(
+                "CVR",
+                CVR_SCHEME,
+                "8-digit CVR number",
+                is_cvr,
+            ))
+            .with(IdentifierScheme::new("ISIN", ISIN_SCHEME, "ISIN", is_isin))
+    }
+
+    /// The standard registry, built on first use
+    pub fn shared() -> Arc<IdentifierRegistry> {
+        static SHARED: OnceLock<Arc<IdentifierRegistry>> = OnceLock::new();
+        SHARED
+            .get_or_init(|| Arc::new(IdentifierRegistry::standard()))
+            .clone()
+    }
+
+    /// Add `scheme`, replacing a validator registered for the same URI
+    pub fn with(mut self, scheme: IdentifierScheme) -> Self {
+        self.register(scheme);
+        self
+    }
+
+    pub fn register(&mut self, scheme: IdentifierScheme) {
+        let key = scheme_key(&scheme.scheme);
+        self.schemes.retain(|s| scheme_key(&s.scheme) != key);
+        self.schemes.push(scheme);
+    }
+
+    /// Validator for a declared scheme URI
+    pub fn find(&self, scheme: &str) -> Option<&IdentifierScheme> {
+        let key = scheme_key(scheme);
+        self.schemes.iter().find(|s| scheme_key(&s.scheme) == key)
+    }
+
+    /// One finding per distinct invalid (scheme, identifier) of `doc`
+    pub fn check(&self, doc: &Document) -> Vec<ValidationError> {
+        let mut seen = HashSet::new();
+        let mut errors = Vec::new();
+        for ctx in &doc.contexts {
+            let entity = &ctx.entity;
+            let Some(scheme) = self.find(&entity.scheme) else {
+                continue;
+            };
+            if seen.insert((scheme.scheme.as_str(), entity.identifier.as_str()))
+                && !scheme.is_valid(&entity.identifier)
+            {
+                errors.push(ValidationError::InvalidDataType {
+                    concept: scheme.name.clone(),
+                    expected_type: scheme.expected.clone(),
+                    actual_value: entity.identifier.clone(),
+                });
+            }
+        }
+        errors
+    }
+}
+
+fn digits(value: &str) -> Option<Vec<u32>> {
+    value.chars().map(|c| c.to_digit(10)).collect()
+}
+
+/// SEC Central Index Key, zero-padded to 10 digits
+pub fn is_cik(value: &str) -> bool {
+    value.len() == 10 && value.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Companies House number: 8 digits, or a 2-letter prefix such as `SC` or
+/// `OC` and 6 digits
+pub fn is_company_number(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes.len() == 8
+        && bytes[2..].iter().all(u8::is_ascii_digit)
+        && (bytes[..2].iter().all(u8::is_ascii_digit)
+            || bytes[..2].iter().all(u8::is_ascii_uppercase)
+            || &bytes[..2] == b"R0")
+}
+
+/// Australian Business Number with its mod-89 check, spaces allowed
+pub fn is_abn(value: &str) -> bool {
+    const WEIGHTS: [u32; 11] = [10, 1, 3, 5, 7, 9, 11, 13, 15, 17, 19];
+    let compact: String = value.chars().filter(|c| *c != ' ').collect();
+    match digits(&compact) {
+        Some(mut d) if d.len() == 11 && d[0] > 0 => {
+            d[0] -= 1;
+            d.iter()
+                .zip(WEIGHTS)
+                .map(|(d, w)| d * w)
+                .sum::<u32>()
+                .is_multiple_of(89)
+        }
+        _ => false,
+    }
+}
+
+/// Danish CVR number with its mod-11 check
+pub fn is_cvr(value: &str) -> bool {
+    const WEIGHTS: [u32; 8] = [2, 7, 6, 5, 4, 3, 2, 1];
+    match digits(value) {
+        Some(d) if d.len() == 8 && d[0] > 0 => d
+            .iter()
+            .zip(WEIGHTS)
+            .map(|(d, w)| d * w)
+            .sum::<u32>()
+            .is_multiple_of(11),
+        _ => false,
+    }
+}
+
+/// ISO 6166 ISIN: country prefix, 9 alphanumerics and a Luhn check digit
+pub fn is_isin(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    if bytes.len() != 12
+        || !bytes[..2].iter().all(u8::is_ascii_uppercase)
+        || !bytes[2..11]
+            .iter()
+            .all(|b| b.is_ascii_digit() || b.is_ascii_uppercase())
+        || !bytes[11].is_ascii_digit()
+    {
+        return false;
+    }
+    // Letters expand to two digits, A = 10 through Z = 35
+    let expanded: Vec<u32> = value
+        .chars()
+        .flat_map(|c| {
+            let n = c.to_digit(36).unwrap_or(0);
+            if n >= 10 {
+                vec![n / 10, n % 10]
+            } else {
+                vec![n]
+            }
+        })
+        .collect();
+    let sum: u32 = expanded
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| {
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                doubled / 10 + doubled % 10
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Context, Entity, Period};
+
+    fn context(id: &str, scheme: &str, identifier: &str) -> Context {
+        Context {
+            id: id.to_string(),
+            entity: Entity {
+                identifier: identifier.to_string(),
+                scheme: scheme.to_string(),
+                segment: None,
+            },
+            period: Period::instant("2023-12-31"),
+            scenario: None,
+        }
+    }
+
+    #[test]
+    fn test_identifier_formats() {
+        assert!(is_cik("0000320193"));
+        assert!(!is_cik("320193"));
+        assert!(is_company_number("00445790"));
+        assert!(is_company_number("SC123456"));
+        assert!(!is_company_number("S1234567"));
+        assert!(is_abn("51 824 753 556"));
+        assert!(!is_abn("51824753557"));
+        assert!(is_cvr("10150817"));
+        assert!(!is_cvr("10150818"));
+        assert!(is_isin("US0378331005"));
+        assert!(!is_isin("US0378331006"));
+    }
+
+    #[test]
+    fn test_registry_selects_by_scheme() {
+        let mut doc = Document::new();
+        doc.contexts.extend([
+            context("a", "https://www.sec.gov/cik/", "320193"),
+            context("b", "http://www.sec.gov/CIK", "320193"),
+            context("c", "http://www.companieshouse.gov.uk/", "SC123456"),
+            context("d", "http://example.com/lei", "not-an-lei"),
+        ]);
+        let errors = IdentifierRegistry::standard().check(&doc);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            ValidationError::InvalidDataType { concept, .. } if concept == "CIK"
+        ));
+
+        // A registered scheme is checked, and replaces a built-in one
+        let registry = IdentifierRegistry::standard()
+            .with(IdentifierScheme::new(
+                "LEI",
+                "http://example.com/lei",
+                "20-character LEI",
+                |id| id.len() == 20,
+            ))
+            .with(IdentifierScheme::new("CIK", CIK_SCHEME, "any", |_| true));
+        let errors = registry.check(&doc);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            ValidationError::InvalidDataType { concept, .. } if concept == "LEI"
+        ));
+    }
+}
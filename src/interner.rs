@@ -0,0 +1,238 @@
+//! String interning shared across documents
+//!
+//! A [`StringInterner`] is a cheap-to-clone handle: every parser given the
+//! same handle interns concept QNames into one table, so a corpus stores each
+//! name once and symbols are comparable between documents. Once a corpus is
+//! loaded, [`StringInterner::freeze`] takes a read-only snapshot that can be
+//! queried from many threads without locking.
+//!
+//! The table is split into shards selected by hash so that parallel parsers
+//! interning different names do not serialize on one lock.
+
+use parking_lot::RwLock;
+use std::sync::Arc;
+use string_interner::symbol::SymbolU32;
+use string_interner::{DefaultBackend, Symbol};
+
+type Table = string_interner::StringInterner<DefaultBackend>;
+
+/// Number of independently locked tables. Strings are spread by hash, so
+/// parallel parsers rarely contend on the same lock.
+const SHARDS: usize = 16;
+
+/// Stable FNV-1a hash choosing a string's shard
+fn shard_of(s: &str) -> usize {
+    let mut hash: u32 = 0x811c_9dc5;
+    for byte in s.bytes() {
+        hash = (hash ^ byte as u32).wrapping_mul(0x0100_0193);
+    }
+    hash as usize % SHARDS
+}
+
+/// Strings one shard can hold before its symbols no longer fit in a u32
+const MAX_PER_SHARD: usize = (u32::MAX as usize + 1) / SHARDS;
+
+// Symbols carry their shard in the low bits: `local * SHARDS + shard`.
+// Panics once a shard outgrows `MAX_PER_SHARD`, rather than handing out a
+// wrapped id that resolves to another string.
+fn encode(shard: usize, sym: SymbolU32) -> u32 {
+    sym.to_usize()
+        .checked_mul(SHARDS)
+        .and_then(|id| u32::try_from(id + shard).ok())
+        .unwrap_or_else(|| {
+            panic!(
+                "string interner shard {} is full: more than {} strings",
+                shard, MAX_PER_SHARD
+            )
+        })
+}
+
+fn decode(id: u32) -> Option<(usize, SymbolU32)> {
+    let id = id as usize;
+    Some((id % SHARDS, SymbolU32::try_from_usize(id / SHARDS)?))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InternerStats {
+    /// Distinct strings interned
+    pub strings: usize,
+    /// Total length of the distinct strings
+    pub bytes: usize,
+    /// Entries in the fullest shard; far above `strings / shards` means the
+    /// hash spreads poorly
+    pub largest_shard: usize,
+}
+
+// Padded so locks of neighbouring shards do not share a cache line
+#[repr(align(64))]
+struct Shard {
+    table: RwLock<Table>,
+}
+
+#[derive(Clone)]
+pub struct StringInterner {
+    shards: Arc<[Shard]>,
+}
+
+impl StringInterner {
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Interner with room for about `strings` entries before reallocating
+    pub fn with_capacity(strings: usize) -> Self {
+        let per_shard = strings.div_ceil(SHARDS);
+        Self {
+            shards: (0..SHARDS)
+                .map(|_| Shard {
+                    table: RwLock::new(Table::with_capacity(per_shard)),
+                })
+                .collect(),
+        }
+    }
+
+    /// Symbol for `s`, adding it if needed. Panics if the string's shard
+    /// already holds 2^28 strings, about 4 billion across the interner.
+    pub fn intern(&self, s: &str) -> u32 {
+        let index = shard_of(s);
+        let shard = &self.shards[index];
+        // Most lookups hit once a corpus warms up; only take the write lock
+        // for new strings
+        if let Some(sym) = shard.table.read().get(s) {
+            return encode(index, sym);
+        }
+        let sym = shard.table.write().get_or_intern(s);
+        encode(index, sym)
+    }
+
+    /// Symbol for `s` if it was interned before
+    pub fn get(&self, s: &str) -> Option<u32> {
+        let index = shard_of(s);
+        let sym = self.shards[index].table.read().get(s)?;
+        Some(encode(index, sym))
+    }
+
+    pub fn resolve(&self, id: u32) -> Option<String> {
+        let (shard, sym) = decode(id)?;
+        self.shards[shard]
+            .table
+            .read()
+            .resolve(sym)
+            .map(str::to_string)
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|s| s.table.read().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stats(&self) -> InternerStats {
+        let mut stats = InternerStats::default();
+        for shard in self.shards.iter() {
+            let table = shard.table.read();
+            stats.strings += table.len();
+            stats.bytes += table.iter().map(|(_, s)| s.len()).sum::<usize>();
+            stats.largest_shard = stats.largest_shard.max(table.len());
+        }
+        stats
+    }
+
+    /// Read-only snapshot of the current tables. Symbols stay the same;
+    /// strings interned afterwards are not visible in the snapshot.
+    pub fn freeze(&self) -> FrozenInterner {
+        FrozenInterner {
+            tables: self.shards.iter().map(|s| s.table.read().clone()).collect(),
+        }
+    }
+}
+
+impl Default for StringInterner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Immutable interner snapshot; lookups take no locks
+#[derive(Clone)]
+pub struct FrozenInterner {
+    tables: Arc<[Table]>,
+}
+
+impl FrozenInterner {
+    pub fn get(&self, s: &str) -> Option<u32> {
+        let index = shard_of(s);
+        self.tables[index].get(s).map(|sym| encode(index, sym))
+    }
+
+    pub fn resolve(&self, id: u32) -> Option<&str> {
+        let (shard, sym) = decode(id)?;
+        self.tables[shard].resolve(sym)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tables.iter().map(Table::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_interner_and_freeze() {
+        let interner = StringInterner::with_capacity(16);
+        let handle = interner.clone();
+        let a = interner.intern("us-gaap:Assets");
+        assert_eq!(handle.intern("us-gaap:Assets"), a);
+        assert_eq!(handle.resolve(a).as_deref(), Some("us-gaap:Assets"));
+
+        let stats = interner.stats();
+        assert_eq!(stats.strings, 1);
+        assert_eq!(stats.bytes, "us-gaap:Assets".len());
+        assert_eq!(stats.largest_shard, 1);
+
+        let frozen = interner.freeze();
+        interner.intern("us-gaap:Liabilities");
+        assert_eq!(frozen.get("us-gaap:Assets"), Some(a));
+        assert_eq!(frozen.resolve(a), Some("us-gaap:Assets"));
+        assert_eq!(frozen.get("us-gaap:Liabilities"), None);
+        assert_eq!(frozen.len(), 1);
+    }
+
+    #[test]
+    fn test_concurrent_interning() {
+        let interner = StringInterner::new();
+        let names: Vec<String> = (0..500).map(|i| format!("us-gaap:Concept{}", i)).collect();
+        let ids: Vec<Vec<u32>> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| names.iter().map(|n| interner.intern(n)).collect()))
+                .collect();
+            workers.into_iter().map(|w| w.join().unwrap()).collect()
+        });
+
+        assert!(ids.windows(2).all(|w| w[0] == w[1]));
+        assert_eq!(interner.len(), names.len());
+        for (name, id) in names.iter().zip(&ids[0]) {
+            assert_eq!(interner.resolve(*id).as_deref(), Some(name.as_str()));
+        }
+        assert!(interner.stats().largest_shard < names.len() / 4);
+    }
+
+    #[test]
+    fn test_symbol_limit() {
+        let last = SymbolU32::try_from_usize(MAX_PER_SHARD - 1).unwrap();
+        assert_eq!(encode(SHARDS - 1, last), u32::MAX);
+        assert_eq!(decode(u32::MAX), Some((SHARDS - 1, last)));
+
+        let beyond = SymbolU32::try_from_usize(MAX_PER_SHARD).unwrap();
+        let overflow = std::panic::catch_unwind(|| encode(0, beyond));
+        assert!(overflow.is_err());
+    }
+}
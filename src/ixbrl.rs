@@ -0,0 +1,979 @@
+//! Inline XBRL (iXBRL) extraction
+//!
+//! Pulls the embedded XBRL data out of an XHTML document: the schemaRef from
+//! `ix:references`, contexts and units from `ix:resources`, and facts tagged
+//! with `ix:nonFraction` / `ix:nonNumeric` anywhere in the body. The text of
+//! a nonNumeric fact runs on through its chain of `ix:continuation`s.
+//!
+//! Values go through their ixt `format`: the numeric, date, boolean and
+//! empty formats of the transformation registries are implemented, and a
+//! fact in any other format fails the parse instead of keeping its
+//! displayed text.
+
+use crate::parser::{parse_decimal, UNRESOLVED};
+use crate::transform::TransformFact;
+use crate::{model::*, Error, ParserOptions, Result};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::ResolveResult;
+use quick_xml::reader::NsReader;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::path::Path;
+
+pub const INLINE_XBRL_NAMESPACE: &str = "http://www.xbrl.org/2013/inlineXBRL";
+
+/// True if the bytes look like an Inline XBRL document
+pub fn is_inline_xbrl(data: &[u8]) -> bool {
+    let head = &data[..data.len().min(64 * 1024)];
+    String::from_utf8_lossy(head).contains(INLINE_XBRL_NAMESPACE)
+}
+
+#[derive(Default)]
+pub struct InlineParser {
+    options: ParserOptions,
+}
+
+impl InlineParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn parse_file<P: AsRef<Path>>(&self, path: P) -> Result<Document> {
+        let path = path.as_ref();
+        let content = std::fs::read(path)?;
+        Ok(self
+            .parse_bytes(&content)?
+            .with_source_uri(path.display().to_string()))
+    }
+
+    pub fn parse_bytes(&self, data: &[u8]) -> Result<Document> {
+        let mut state = InlineState::default();
+        let mut reader = NsReader::from_reader(data);
+        reader.config_mut().check_end_names = false;
+        let mut buf = Vec::new();
+
+        loop {
+            // Inline elements are told apart by namespace, whatever their prefix
+            let (ns, event) = match reader.read_resolved_event_into(&mut buf) {
+                Ok(resolved) => resolved,
+                Err(e) => return Err(Error::xml(format!("Inline XBRL parse error: {}", e))),
+            };
+            let inline = matches!(ns, ResolveResult::Bound(ns) if ns.as_ref() == INLINE_XBRL_NAMESPACE.as_bytes());
+            match event {
+                Event::Start(ref e) => state.start(e, false, inline),
+                Event::Empty(ref e) => {
+                    state.start(e, true, inline);
+                    state.end(e.local_name().as_ref(), inline);
+                }
+                Event::End(ref e) => state.end(e.local_name().as_ref(), inline),
+                Event::Text(ref e) => {
+                    let text = e
+                        .unescape()
+                        .map(|t| t.into_owned())
+                        .unwrap_or_else(|_| String::from_utf8_lossy(e.as_ref()).into_owned());
+                    state.text(&text);
+                }
+                Event::CData(ref e) => state.text(&String::from_utf8_lossy(e.as_ref())),
+                Event::Eof => break,
+                _ => {}
+            }
+            buf.clear();
+        }
+
+        let mut doc = state.into_document(&self.options)?;
+        doc.provenance = Some(Provenance::from_bytes(data));
+        Ok(doc)
+    }
+}
+
+struct RawFact {
+    name: String,
+    context_ref: String,
+    unit_ref: Option<String>,
+    decimals: Option<i8>,
+    id: Option<String>,
+    nil: bool,
+    numeric: bool,
+    scale: i32,
+    negative: bool,
+    format: Option<String>,
+    /// Id of the `ix:continuation` holding the rest of the text
+    continued_at: Option<String>,
+    text: String,
+}
+
+/// An `ix:continuation`: more text of a nonNumeric fact, possibly continued
+/// in turn
+struct Continuation {
+    id: String,
+    continued_at: Option<String>,
+    text: String,
+}
+
+#[derive(Default)]
+struct ContextBuilder {
+    id: String,
+    scheme: String,
+    identifier: String,
+    start: Option<String>,
+    end: Option<String>,
+    instant: Option<String>,
+    forever: bool,
+    segment: Vec<DimensionMember>,
+    scenario: Vec<DimensionMember>,
+    segment_typed: Vec<TypedMember>,
+    scenario_typed: Vec<TypedMember>,
+    in_scenario: bool,
+    pending_dimension: Option<(String, bool)>,
+}
+
+#[derive(Default)]
+struct UnitBuilder {
+    id: String,
+    measures: Vec<Measure>,
+    numerator: Vec<Measure>,
+    denominator: Vec<Measure>,
+    divide: bool,
+    in_denominator: bool,
+}
+
+#[derive(Default)]
+struct InlineState {
+    doc: Document,
+    facts: Vec<RawFact>,
+    open_facts: Vec<RawFact>,
+    continuations: HashMap<String, Continuation>,
+    open_continuations: Vec<Continuation>,
+    context: Option<ContextBuilder>,
+    unit: Option<UnitBuilder>,
+    text: String,
+    exclude_depth: usize,
+}
+
+fn attr(e: &BytesStart<'_>, local: &str) -> Option<String> {
+    e.attributes().flatten().find_map(|a| {
+        let key = a.key.as_ref();
+        let key_local = key.rsplit(|b| *b == b':').next().unwrap_or(key);
+        (key_local == local.as_bytes()).then(|| {
+            a.unescape_value()
+                .map(|v| v.into_owned())
+                .unwrap_or_else(|_| String::from_utf8_lossy(&a.value).into_owned())
+        })
+    })
+}
+
+impl InlineState {
+    fn start(&mut self, e: &BytesStart<'_>, empty: bool, inline: bool) {
+        let local = e.local_name();
+        let local = local.as_ref();
+        self.text.clear();
+
+        // Prefix bindings, so QNames resolve and the instance can be written
+        for a in e.attributes().flatten() {
+            if let Some(prefix) = a.key.as_ref().strip_prefix(b"xmlns:") {
+                self.doc
+                    .namespaces
+                    .entry(String::from_utf8_lossy(prefix).into_owned())
+                    .or_insert_with(|| String::from_utf8_lossy(&a.value).into_owned());
+            }
+        }
+
+        if inline {
+            match local {
+                b"nonFraction" | b"nonNumeric" => {
+                    let nil = attr(e, "nil").is_some_and(|v| v == "true" || v == "1");
+                    self.open_facts.push(RawFact {
+                        name: attr(e, "name").unwrap_or_default(),
+                        context_ref: attr(e, "contextRef").unwrap_or_default(),
+                        unit_ref: attr(e, "unitRef"),
+                        decimals: attr(e, "decimals").and_then(|v| v.parse().ok()),
+                        id: attr(e, "id"),
+                        nil: nil || empty && local == b"nonFraction",
+                        numeric: local == b"nonFraction",
+                        scale: attr(e, "scale").and_then(|v| v.parse().ok()).unwrap_or(0),
+                        negative: attr(e, "sign").as_deref() == Some("-"),
+                        format: attr(e, "format"),
+                        continued_at: attr(e, "continuedAt"),
+                        text: String::new(),
+                    });
+                }
+                b"continuation" => self.open_continuations.push(Continuation {
+                    id: attr(e, "id").unwrap_or_default(),
+                    continued_at: attr(e, "continuedAt"),
+                    text: String::new(),
+                }),
+                b"exclude" => self.exclude_depth += 1,
+                _ => {}
+            }
+            return;
+        }
+
+        if let Some(ctx) = self.context.as_mut() {
+            match local {
+                b"identifier" => ctx.scheme = attr(e, "scheme").unwrap_or_default(),
+                b"scenario" => ctx.in_scenario = true,
+                b"forever" => ctx.forever = true,
+                b"explicitMember" => {
+                    ctx.pending_dimension = Some((attr(e, "dimension").unwrap_or_default(), false))
+                }
+                b"typedMember" => {
+                    ctx.pending_dimension = Some((attr(e, "dimension").unwrap_or_default(), true))
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        if let Some(unit) = self.unit.as_mut() {
+            match local {
+                b"divide" => unit.divide = true,
+                b"unitDenominator" => unit.in_denominator = true,
+                _ => {}
+            }
+            return;
+        }
+
+        match local {
+            b"context" => {
+                self.context = Some(ContextBuilder {
+                    id: attr(e, "id").unwrap_or_default(),
+                    ..Default::default()
+                })
+            }
+            b"unit" => {
+                self.unit = Some(UnitBuilder {
+                    id: attr(e, "id").unwrap_or_default(),
+                    ..Default::default()
+                })
+            }
+            b"schemaRef" => {
+                if let Some(href) = attr(e, "href") {
+                    self.doc.schema_ref.get_or_insert(href);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.exclude_depth == 0 {
+            for fact in &mut self.open_facts {
+                fact.text.push_str(text);
+            }
+            for continuation in &mut self.open_continuations {
+                continuation.text.push_str(text);
+            }
+        }
+        self.text.push_str(text);
+    }
+
+    fn end(&mut self, local: &[u8], inline: bool) {
+        let text = std::mem::take(&mut self.text);
+        let text = text.trim();
+
+        if let Some(ctx) = self.context.as_mut() {
+            match local {
+                b"identifier" => ctx.identifier = text.to_string(),
+                b"startDate" => ctx.start = Some(text.to_string()),
+                b"endDate" => ctx.end = Some(text.to_string()),
+                b"instant" => ctx.instant = Some(text.to_string()),
+                b"explicitMember" | b"typedMember" => {
+                    if let Some((dimension, typed)) = ctx.pending_dimension.take() {
+                        let value = text.to_string();
+                        match (typed, ctx.in_scenario) {
+                            (false, false) => ctx.segment.push(DimensionMember {
+                                dimension,
+                                member: value,
+                            }),
+                            (false, true) => ctx.scenario.push(DimensionMember {
+                                dimension,
+                                member: value,
+                            }),
+                            (true, false) => {
+                                ctx.segment_typed.push(TypedMember { dimension, value })
+                            }
+                            (true, true) => {
+                                ctx.scenario_typed.push(TypedMember { dimension, value })
+                            }
+                        }
+                    }
+                }
+                b"context" => {
+                    let ctx = self.context.take().unwrap();
+                    // Dropped without a complete period, as the XML parser does
+                    if let Some(ctx) = ctx.build() {
+                        self.doc.contexts.push(ctx);
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        if let Some(unit) = self.unit.as_mut() {
+            match local {
+                b"measure" => {
+                    let m = Measure::from_qname(text, &self.doc.namespaces);
+                    match (unit.divide, unit.in_denominator) {
+                        (false, _) => unit.measures.push(m),
+                        (true, false) => unit.numerator.push(m),
+                        (true, true) => unit.denominator.push(m),
+                    }
+                }
+                b"unit" => {
+                    let unit = self.unit.take().unwrap();
+                    let unit_type = if unit.divide {
+                        UnitType::Divide {
+                            numerator: unit.numerator,
+                            denominator: unit.denominator,
+                        }
+                    } else {
+                        UnitType::Simple(unit.measures)
+                    };
+                    self.doc.units.push(Unit {
+                        id: unit.id,
+                        unit_type,
+                    });
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        if !inline {
+            return;
+        }
+        match local {
+            b"nonFraction" | b"nonNumeric" => {
+                if let Some(fact) = self.open_facts.pop() {
+                    self.facts.push(fact);
+                }
+            }
+            b"continuation" => {
+                if let Some(continuation) = self.open_continuations.pop() {
+                    self.continuations
+                        .insert(continuation.id.clone(), continuation);
+                }
+            }
+            b"exclude" => self.exclude_depth = self.exclude_depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    fn into_document(mut self, options: &ParserOptions) -> Result<Document> {
+        let context_index: HashMap<String, u32> = self
+            .doc
+            .contexts
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (c.id.clone(), i as u32))
+            .collect();
+        let unit_index: HashMap<String, u32> = self
+            .doc
+            .units
+            .iter()
+            .enumerate()
+            .map(|(i, u)| (u.id.clone(), (i + 1) as u32))
+            .collect();
+        let mut concept_index: HashMap<String, u32> = HashMap::new();
+
+        for mut fact in std::mem::take(&mut self.facts) {
+            // Prefixes resolve against the document's bindings
+            let namespace = self
+                .doc
+                .resolve_qname(&fact.name)
+                .map(|(namespace, _)| namespace.to_string());
+            if !options.wants_concept(&fact.name, namespace.as_deref()) {
+                continue;
+            }
+            self.continue_text(&mut fact)?;
+            // User transforms see the displayed text, before the ixt format
+            if !options.transforms.is_empty() {
+                let target = TransformFact {
+                    concept: &fact.name,
+                    namespace: namespace.as_deref(),
+                    item_type: None,
+                    numeric: fact.numeric,
+                };
+                if let Some(text) = options.transforms.apply(&target, &fact.text) {
+                    fact.text = text;
+                }
+            }
+            // Kept with unresolved references, which validation reports
+            let context_id = context_index
+                .get(&fact.context_ref)
+                .copied()
+                .unwrap_or(UNRESOLVED);
+            let unit_id = match &fact.unit_ref {
+                Some(unit_ref) => unit_index.get(unit_ref).copied().unwrap_or(UNRESOLVED),
+                None => 0,
+            };
+            let concept_id = *concept_index.entry(fact.name.clone()).or_insert_with(|| {
+                self.doc.concept_names.push(fact.name.clone());
+                (self.doc.concept_names.len() - 1) as u32
+            });
+            let value = fact.value()?;
+            let scaled = matches!(value, FactValue::Decimal(_)) && fact.scale != 0;
+            self.doc.facts.push(
+                concept_id,
+                context_id,
+                unit_id,
+                value,
+                fact.decimals,
+                fact.id,
+            );
+            if let (true, Some(last)) = (scaled, self.doc.facts.scales.last_mut()) {
+                *last = i8::try_from(fact.scale).ok();
+            }
+        }
+
+        Ok(self.doc)
+    }
+
+    /// Append the text of the fact's `ix:continuation` chain
+    fn continue_text(&self, fact: &mut RawFact) -> Result<()> {
+        let mut seen = Vec::new();
+        let mut next = fact.continued_at.as_deref();
+        while let Some(id) = next {
+            if seen.contains(&id) {
+                return Err(Error::xml(format!(
+                    "ix:continuation {} of {} continues into itself",
+                    id, fact.name
+                )));
+            }
+            let Some(continuation) = self.continuations.get(id) else {
+                return Err(Error::xml(format!(
+                    "{} continues at {}, which is not an ix:continuation",
+                    fact.name, id
+                )));
+            };
+            fact.text.push_str(&continuation.text);
+            seen.push(id);
+            next = continuation.continued_at.as_deref();
+        }
+        Ok(())
+    }
+}
+
+impl ContextBuilder {
+    fn build(self) -> Option<Context> {
+        let period = match (self.instant, self.start, self.end) {
+            (Some(date), _, _) => Period::instant(date),
+            (None, Some(start), Some(end)) => Period::between(start, end),
+            _ if self.forever => Period::Forever,
+            _ => return None,
+        };
+        let segment =
+            (!self.segment.is_empty() || !self.segment_typed.is_empty()).then_some(Segment {
+                explicit_members: self.segment,
+                typed_members: self.segment_typed,
+            });
+        let scenario =
+            (!self.scenario.is_empty() || !self.scenario_typed.is_empty()).then_some(Scenario {
+                explicit_members: self.scenario,
+                typed_members: self.scenario_typed,
+            });
+        Some(Context {
+            id: self.id,
+            entity: Entity {
+                identifier: self.identifier,
+                scheme: self.scheme,
+                segment,
+            },
+            period,
+            scenario,
+        })
+    }
+}
+
+impl RawFact {
+    /// The fact's value after its ixt format; formats this parser does not
+    /// implement are an error rather than a value left as displayed
+    fn value(&self) -> Result<FactValue> {
+        if self.nil {
+            return Ok(FactValue::Nil);
+        }
+        let text = self.text.trim();
+        let format = self.format.as_deref().map(format_name);
+        if !self.numeric {
+            let Some(format) = format else {
+                return Ok(FactValue::Text(text.to_string()));
+            };
+            if !TEXT_FORMATS.contains(&format) {
+                return Err(self.unsupported_format());
+            }
+            return transform_text(text, format)
+                .map(FactValue::Text)
+                .ok_or_else(|| {
+                    Error::validation(
+                        "ixt.format",
+                        format!(
+                            "{} value {:?} does not match format {}",
+                            self.name,
+                            text,
+                            self.format.as_deref().unwrap_or_default()
+                        ),
+                    )
+                });
+        }
+        if format.is_some_and(|f| !NUMERIC_FORMATS.contains(&f)) {
+            return Err(self.unsupported_format());
+        }
+        Ok(
+            match transform_number(text, self.format.as_deref())
+                .and_then(|n| apply_scale(n, self.scale))
+            {
+                Some(n) => FactValue::Decimal(if self.negative { -n } else { n }),
+                None => FactValue::Text(text.to_string()),
+            },
+        )
+    }
+
+    fn unsupported_format(&self) -> Error {
+        Error::validation(
+            "ixt.format",
+            format!(
+                "Unsupported inline XBRL format {} on {}",
+                self.format.as_deref().unwrap_or_default(),
+                self.name
+            ),
+        )
+    }
+}
+
+/// `n * 10^scale`, exactly; `None` if it leaves the range of [`Decimal`]
+pub(crate) fn apply_scale(n: Decimal, scale: i32) -> Option<Decimal> {
+    let factor = if scale >= 0 {
+        Decimal::try_from_i128_with_scale(10i128.checked_pow(scale as u32)?, 0).ok()?
+    } else {
+        Decimal::try_new(1, scale.unsigned_abs()).ok()?
+    };
+    n.checked_mul(factor).map(|d| d.normalize())
+}
+
+/// Local name of an ixt format QName
+fn format_name(format: &str) -> &str {
+    format.rsplit(':').next().unwrap_or(format)
+}
+
+/// Numeric formats of the ixt registries, in their current and earlier
+/// names, that [`transform_number`] implements
+const NUMERIC_FORMATS: &[&str] = &[
+    "num-dot-decimal",
+    "num-comma-decimal",
+    "numdotdecimal",
+    "numcommadecimal",
+    "numcommadot",
+    "numdotcomma",
+    "numspacedot",
+    "numspacecomma",
+    "numcomma",
+    "fixed-zero",
+    "zerodash",
+    "numdash",
+];
+
+/// Date, boolean and empty formats that [`transform_text`] implements
+const TEXT_FORMATS: &[&str] = &[
+    "date-day-month-year",
+    "date-month-day-year",
+    "date-year-month-day",
+    "date-day-monthname-year-en",
+    "date-monthname-day-year-en",
+    "date-month-year",
+    "date-year-month",
+    "date-monthname-year-en",
+    "date-year-monthname-en",
+    "date-day-month",
+    "date-month-day",
+    "date-day-monthname-en",
+    "date-monthname-day-en",
+    "datedaymonthyear",
+    "datemonthdayyear",
+    "dateyearmonthday",
+    "dateslasheu",
+    "datedoteu",
+    "dateslashus",
+    "datedotus",
+    "datedaymonthyearen",
+    "datelonguk",
+    "dateshortuk",
+    "datemonthdayyearen",
+    "datelongus",
+    "dateshortus",
+    "datemonthyearen",
+    "datelongmonthyear",
+    "dateshortmonthyear",
+    "datelongyearmonth",
+    "dateshortyearmonth",
+    "datedaymonth",
+    "dateslashdaymontheu",
+    "datemonthday",
+    "dateslashmonthdayus",
+    "datedaymonthen",
+    "datelongdaymonthuk",
+    "dateshortdaymonthuk",
+    "datemonthdayen",
+    "datelongmonthdayus",
+    "dateshortmonthdayus",
+    "fixed-true",
+    "booleantrue",
+    "fixed-false",
+    "booleanfalse",
+    "fixed-empty",
+    "nocontent",
+];
+
+/// Order of the parts of a date format: `D`ay, `M`onth number, month
+/// `N`ame and `Y`ear
+fn date_pattern(format: &str) -> Option<&'static str> {
+    Some(match format {
+        "date-day-month-year" | "datedaymonthyear" | "dateslasheu" | "datedoteu" => "DMY",
+        "date-month-day-year" | "datemonthdayyear" | "dateslashus" | "datedotus" => "MDY",
+        "date-year-month-day" | "dateyearmonthday" => "YMD",
+        "date-day-monthname-year-en" | "datedaymonthyearen" | "datelonguk" | "dateshortuk" => "DNY",
+        "date-monthname-day-year-en" | "datemonthdayyearen" | "datelongus" | "dateshortus" => "NDY",
+        "date-month-year" => "MY",
+        "date-year-month" => "YM",
+        "date-monthname-year-en"
+        | "datemonthyearen"
+        | "datelongmonthyear"
+        | "dateshortmonthyear" => "NY",
+        "date-year-monthname-en" | "datelongyearmonth" | "dateshortyearmonth" => "YN",
+        "date-day-month" | "datedaymonth" | "dateslashdaymontheu" => "DM",
+        "date-month-day" | "datemonthday" | "dateslashmonthdayus" => "MD",
+        "date-day-monthname-en"
+        | "datedaymonthen"
+        | "datelongdaymonthuk"
+        | "dateshortdaymonthuk" => "DN",
+        "date-monthname-day-en"
+        | "datemonthdayen"
+        | "datelongmonthdayus"
+        | "dateshortmonthdayus" => "ND",
+        _ => return None,
+    })
+}
+
+/// Number of an English month name or its abbreviation
+fn month_number(name: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ];
+    let name = name.to_ascii_lowercase();
+    let name = name.trim_end_matches('.');
+    if name.len() < 3 {
+        return None;
+    }
+    MONTHS
+        .iter()
+        .position(|month| month.starts_with(name))
+        .map(|i| i as u32 + 1)
+}
+
+/// Apply a date, boolean or empty ixt format, giving the value as
+/// `xs:date`, `xs:gYearMonth` or `xs:gMonthDay`; two-digit years are in
+/// the 2000s. `None` if `text` does not match the format.
+pub(crate) fn transform_text(text: &str, format: &str) -> Option<String> {
+    match format {
+        "fixed-true" | "booleantrue" => return Some("true".to_string()),
+        "fixed-false" | "booleanfalse" => return Some("false".to_string()),
+        "fixed-empty" | "nocontent" => return Some(String::new()),
+        _ => {}
+    }
+    let pattern = date_pattern(format)?;
+    let tokens: Vec<&str> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .collect();
+    if tokens.len() != pattern.len() {
+        return None;
+    }
+    let (mut year, mut month, mut day) = (None, None, None);
+    for (part, token) in pattern.chars().zip(tokens) {
+        match part {
+            'Y' => {
+                let y: i32 = token.parse().ok()?;
+                year = Some(match token.len() {
+                    2 => 2000 + y,
+                    4 => y,
+                    _ => return None,
+                });
+            }
+            'M' => month = Some(token.parse().ok()?),
+            'N' => month = Some(month_number(token)?),
+            _ => {
+                // Ordinal days such as 31st
+                let digits = token.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+                if !matches!(&token[digits.len()..], "" | "st" | "nd" | "rd" | "th") {
+                    return None;
+                }
+                day = Some(digits.parse().ok()?);
+            }
+        }
+    }
+    match (year, month?, day) {
+        (Some(year), month, Some(day)) => chrono::NaiveDate::from_ymd_opt(year, month, day)
+            .map(|date| date.format("%Y-%m-%d").to_string()),
+        (Some(year), month, None) => (1..=12)
+            .contains(&month)
+            .then(|| format!("{:04}-{:02}", year, month)),
+        // Checked against a leap year, as February 29 is a valid gMonthDay
+        (None, month, Some(day)) => chrono::NaiveDate::from_ymd_opt(2000, month, day)
+            .map(|_| format!("--{:02}-{:02}", month, day)),
+        (None, _, None) => None,
+    }
+}
+
+/// Apply the common ixt numeric transformation formats
+pub(crate) fn transform_number(text: &str, format: Option<&str>) -> Option<Decimal> {
+    let format = format.map(format_name).unwrap_or("");
+    match format {
+        "fixed-zero" | "zerodash" | "numdash" => return Some(Decimal::ZERO),
+        _ => {}
+    }
+    if text.is_empty() || text == "-" || text == "—" {
+        return Some(Decimal::ZERO).filter(|_| format.contains("zero") || format.contains("dash"));
+    }
+    let comma_decimal = matches!(
+        format,
+        "num-comma-decimal" | "numcommadecimal" | "numdotcomma" | "numspacecomma" | "numcomma"
+    );
+    let cleaned: String = text
+        .chars()
+        .filter_map(|c| match c {
+            '0'..='9' => Some(c),
+            '.' if !comma_decimal => Some('.'),
+            ',' if comma_decimal => Some('.'),
+            _ => None,
+        })
+        .collect();
+    parse_decimal(&cleaned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:ix="http://www.xbrl.org/2013/inlineXBRL"
+      xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:link="http://www.xbrl.org/2003/linkbase"
+      xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:us-gaap="http://fasb.org/us-gaap/2023">
+<body>
+<div style="display:none"><ix:header>
+  <ix:references><link:schemaRef xlink:type="simple" xlink:href="aapl-20230930.xsd"/></ix:references>
+  <ix:resources>
+    <xbrli:context id="c1">
+      <xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+      <xbrli:period><xbrli:startDate>2023-07-02</xbrli:startDate><xbrli:endDate>2023-09-30</xbrli:endDate></xbrli:period>
+    </xbrli:context>
+    <xbrli:unit id="usd"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  </ix:resources>
+</ix:header></div>
+<p>Net sales were $<ix:nonFraction name="us-gaap:Revenues" contextRef="c1" unitRef="usd" decimals="-6" scale="6" format="ixt:num-dot-decimal">89,498</ix:nonFraction> million.</p>
+<p><ix:nonNumeric name="us-gaap:SubsequentEventsTextBlock" contextRef="c1">None <b>reported</b>.</ix:nonNumeric></p>
+</body></html>"#;
+
+    #[test]
+    fn test_parse_inline_facts() {
+        assert!(is_inline_xbrl(SAMPLE.as_bytes()));
+        let doc = InlineParser::new().parse_bytes(SAMPLE.as_bytes()).unwrap();
+        assert_eq!(doc.schema_ref.as_deref(), Some("aapl-20230930.xsd"));
+        assert_eq!(doc.contexts.len(), 1);
+        assert_eq!(doc.units.len(), 1);
+        assert_eq!(doc.facts.len(), 2);
+
+        let revenue = doc.fact(0).unwrap();
+        assert_eq!(revenue.concept(), "us-gaap:Revenues");
+        assert_eq!(revenue.value().as_f64(), Some(89_498_000_000.0));
+        assert_eq!(revenue.scale(), Some(6));
+        assert_eq!(revenue.unit().unwrap().canonical(), "iso4217:USD");
+
+        let text = doc.fact(1).unwrap();
+        assert_eq!(text.value().to_string(), "None reported.");
+    }
+
+    #[test]
+    fn test_inline_namespace_not_prefix() {
+        // Another prefix for the inline namespace is still inline XBRL...
+        let renamed = SAMPLE
+            .replace("xmlns:ix=", "xmlns:inline=")
+            .replace("<ix:", "<inline:")
+            .replace("</ix:", "</inline:");
+        let doc = InlineParser::new().parse_bytes(renamed.as_bytes()).unwrap();
+        assert_eq!(doc.facts.len(), 2);
+
+        // ...and an ix prefix bound elsewhere is not
+        let foreign = SAMPLE.replace(
+            "<p>Net sales",
+            r#"<p xmlns:ix="http://example.com/other"><ix:nonFraction name="us-gaap:Assets" contextRef="c1" unitRef="usd" decimals="0">1</ix:nonFraction></p><p>Net sales"#,
+        );
+        let doc = InlineParser::new().parse_bytes(foreign.as_bytes()).unwrap();
+        assert_eq!(doc.facts.len(), 2);
+        assert_eq!(doc.fact(0).unwrap().concept(), "us-gaap:Revenues");
+    }
+
+    #[test]
+    fn test_continuation() {
+        let html = SAMPLE.replace(
+            r#"contextRef="c1">None <b>reported</b>.</ix:nonNumeric></p>"#,
+            r#"contextRef="c1" continuedAt="k1">None</ix:nonNumeric></p>
+<p>Unrelated text.</p>
+<ix:continuation id="k2"> in this period.</ix:continuation>
+<ix:continuation id="k1" continuedAt="k2"> <b>reported</b><ix:exclude> (page 7)</ix:exclude></ix:continuation>"#,
+        );
+        let doc = InlineParser::new().parse_bytes(html.as_bytes()).unwrap();
+        assert_eq!(
+            doc.fact(1).unwrap().value().to_string(),
+            "None reported in this period."
+        );
+
+        let cycle = html.replace(r#"id="k2">"#, r#"id="k2" continuedAt="k1">"#);
+        assert!(InlineParser::new().parse_bytes(cycle.as_bytes()).is_err());
+        let dangling = html.replace(r#"id="k2">"#, r#"id="k3">"#);
+        assert!(InlineParser::new()
+            .parse_bytes(dangling.as_bytes())
+            .is_err());
+    }
+
+    #[test]
+    fn test_periods() {
+        let with_period = |period: &str| {
+            let html = SAMPLE.replace(
+                "<xbrli:period><xbrli:startDate>2023-07-02</xbrli:startDate><xbrli:endDate>2023-09-30</xbrli:endDate></xbrli:period>",
+                period,
+            );
+            InlineParser::new().parse_bytes(html.as_bytes()).unwrap()
+        };
+        let doc = with_period("<xbrli:period><xbrli:forever/></xbrli:period>");
+        assert_eq!(doc.contexts[0].period, Period::Forever);
+
+        // A start date alone is no period, so the context is dropped
+        let doc = with_period(
+            "<xbrli:period><xbrli:startDate>2023-07-02</xbrli:startDate></xbrli:period>",
+        );
+        assert!(doc.contexts.is_empty());
+        assert_eq!(doc.facts.context_ids[0], UNRESOLVED);
+    }
+
+    #[test]
+    fn test_concept_namespace() {
+        let options = ParserOptions::new()
+            .with_concept_filter(
+                crate::ConceptFilter::new().include("{http://fasb.org/us-gaap/2023}*"),
+            )
+            .with_transform(
+                crate::TransformTarget::concept("{http://fasb.org/us-gaap/2023}*"),
+                |value: &str| Some(value.to_uppercase()),
+            );
+        let html = SAMPLE.replace(
+            "<p>Net sales",
+            r#"<p xmlns:dei="http://xbrl.sec.gov/dei/2023"><ix:nonNumeric name="dei:EntityRegistrantName" contextRef="c1">Apple Inc.</ix:nonNumeric></p><p>Net sales"#,
+        );
+        let doc = InlineParser::new()
+            .with_options(options)
+            .parse_bytes(html.as_bytes())
+            .unwrap();
+        let values: Vec<_> = doc.iter_facts().map(|f| f.value().to_string()).collect();
+        assert_eq!(values, ["89498000000", "NONE REPORTED."]);
+    }
+
+    #[test]
+    fn test_unresolved_refs_kept() {
+        let html = SAMPLE.replace(
+            r#"contextRef="c1" unitRef="usd""#,
+            r#"contextRef="c9" unitRef="eur""#,
+        );
+        let doc = InlineParser::new().parse_bytes(html.as_bytes()).unwrap();
+        assert_eq!(doc.facts.len(), 2);
+        assert_eq!(doc.facts.context_ids[0], UNRESOLVED);
+        assert_eq!(doc.facts.unit_ids[0], UNRESOLVED);
+
+        let errors = crate::validator::XbrlValidator::new().check(&doc);
+        assert!(errors.iter().any(|e| matches!(
+            e,
+            crate::validator::ValidationError::InvalidContextRef { fact_index: 0, .. }
+        )));
+    }
+
+    #[test]
+    fn test_extracted_instance() {
+        let doc = InlineParser::new().parse_bytes(SAMPLE.as_bytes()).unwrap();
+        assert_eq!(
+            doc.resolve_qname("us-gaap:Revenues"),
+            Some(("http://fasb.org/us-gaap/2023", "Revenues"))
+        );
+
+        // The extracted instance declares the concepts' prefixes
+        let xml = doc.to_xml().unwrap();
+        assert!(xml.contains(r#"xmlns:us-gaap="http://fasb.org/us-gaap/2023""#));
+        let instance = crate::Parser::new().parse_str(&xml).unwrap();
+        let values: Vec<_> = instance
+            .iter_facts()
+            .map(|f| f.value().to_string())
+            .collect();
+        assert_eq!(values, ["89498000000", "None reported."]);
+    }
+
+    #[test]
+    fn test_date_formats() {
+        let cases = [
+            (
+                "December 31, 2023",
+                "date-monthname-day-year-en",
+                "2023-12-31",
+            ),
+            ("31st Dec. 2023", "date-day-monthname-year-en", "2023-12-31"),
+            ("12/31/23", "dateslashus", "2023-12-31"),
+            ("31.12.2023", "date-day-month-year", "2023-12-31"),
+            ("Sept 2023", "date-monthname-year-en", "2023-09"),
+            ("February 29", "date-monthname-day-en", "--02-29"),
+            ("No", "fixed-false", "false"),
+        ];
+        for (text, format, value) in cases {
+            assert_eq!(
+                transform_text(text, format).as_deref(),
+                Some(value),
+                "{}",
+                text
+            );
+        }
+        assert_eq!(
+            transform_text("February 30, 2023", "date-monthname-day-year-en"),
+            None
+        );
+
+        let inline = |fact: &str| {
+            let html = SAMPLE.replace(
+                r#"<p><ix:nonNumeric name="us-gaap:SubsequentEventsTextBlock""#,
+                &format!(
+                    "{}<p><ix:nonNumeric name=\"us-gaap:SubsequentEventsTextBlock\"",
+                    fact
+                ),
+            );
+            InlineParser::new().parse_bytes(html.as_bytes())
+        };
+        let doc = inline(r#"<ix:nonNumeric name="dei:DocumentPeriodEndDate" contextRef="c1" format="ixt:date-monthname-day-year-en">December 31, 2023</ix:nonNumeric>"#).unwrap();
+        assert_eq!(doc.fact(1).unwrap().value().to_string(), "2023-12-31");
+
+        let err = inline(r#"<ix:nonNumeric name="dei:DocumentPeriodEndDate" contextRef="c1" format="ixt:date-ordinal-day-monthname-year-en">31st December 2023</ix:nonNumeric>"#).err().unwrap();
+        assert_eq!(err.kind(), crate::ErrorKind::Validation);
+        assert_eq!(err.code(), Some("ixt.format"));
+        assert!(inline(r#"<ix:nonFraction name="us-gaap:Revenues" contextRef="c1" unitRef="usd" decimals="0" format="ixt:num-word-en">nine</ix:nonFraction>"#).is_err());
+    }
+}
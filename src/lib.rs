@@ -1,23 +1,86 @@
 //! crabrl - High-performance XBRL parser and validator
 //!
+//! [`prelude`] holds the stable API; the scanner and arena behind the parser
+//! are internal.
+//!
 //! Licensed under AGPL-3.0
 
+#[doc(hidden)]
+pub mod allocator;
+pub mod audit;
+pub mod calculation;
+pub mod catalog;
+pub mod columnar;
+#[cfg(feature = "compress")]
+pub mod compress;
+pub mod corpus;
+pub mod coverage;
+pub mod definition;
+pub mod dei;
+pub mod diff;
+pub mod dts;
+pub mod edgar;
+pub mod editor;
+pub mod efm;
+pub mod export;
+pub mod filing;
+pub mod fixtures;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod identifier;
+pub mod interner;
+pub mod ixbrl;
+pub mod linkbase;
+#[cfg(feature = "mapping")]
+pub mod mapping;
 pub mod model;
-pub mod simple_parser;
+pub mod network;
+pub mod numeric;
+pub mod oim;
+pub mod options;
+pub mod parser;
+pub mod periods;
+#[cfg(feature = "parallel")]
+pub mod pool;
+pub mod prelude;
+pub mod presentation;
+pub mod query;
+pub mod report;
+#[doc(hidden)]
+pub mod simd;
+pub mod stats;
+#[cfg(feature = "serde")]
+pub mod store;
+#[cfg(feature = "tokio")]
+pub mod stream;
+pub mod transform;
 pub mod validator;
+pub mod writer;
 
-// Use simple parser for now
-pub use simple_parser::Parser;
+pub use parser::{detect_input, InputKind, ParseEvent, ParseHandler, ParseStats, Parser};
+#[cfg(feature = "tokio")]
+pub use stream::FactEvent;
 
 // Re-export main types
-pub use model::{Context, Document, Fact, Unit};
+pub use columnar::NormalizedFact;
+pub use dei::Dei;
+pub use dts::{DtsLoader, NetworkPolicy};
+pub use filing::Filing;
+pub use interner::StringInterner;
+pub use ixbrl::InlineParser;
+pub use model::{
+    Balance, Context, Decimal, Document, Fact, FactView, NaturalSign, ParseDiagnostic, Provenance,
+    Shared, Unit, Unsupported,
+};
+pub use options::{ConceptFilter, ParserOptions};
+pub use transform::{TransformTarget, ValueTransform};
 
 // Create validator wrapper for the CLI
 #[derive(Default)]
 pub struct Validator {
     inner: validator::XbrlValidator,
-    #[allow(dead_code)]
     strict: bool,
+    audit: bool,
 }
 
 impl Validator {
@@ -30,36 +93,102 @@ impl Validator {
         if config.strict {
             inner = inner.strict();
         }
+        if config.efm {
+            inner = inner.with_efm();
+        }
+        if config.period_alignment {
+            inner = inner.with_period_alignment();
+        }
+        if config.forbid_forever {
+            inner = inner.forbid_forever();
+        }
+        if config.redundant_duplicates {
+            inner = inner.with_redundant_duplicates();
+        }
         Self {
             inner,
             strict: config.strict,
+            audit: false,
         }
     }
 
     pub fn sec_edgar() -> Self {
         Self {
-            inner: validator::XbrlValidator::new().strict(),
+            inner: validator::XbrlValidator::new()
+                .strict()
+                .with_efm()
+                .with_period_alignment()
+                .forbid_forever(),
             strict: true,
+            audit: false,
         }
     }
 
+    /// Cross-check reporting-period contexts against the DEI reporting
+    /// period, as the SEC EDGAR profile does
+    pub fn with_period_alignment(mut self) -> Self {
+        self.inner = self.inner.with_period_alignment();
+        self
+    }
+
+    /// Run the EDGAR Filer Manual checks with a precompiled, shareable pack
+    pub fn with_rule_pack(mut self, pack: std::sync::Arc<efm::RulePack>) -> Self {
+        self.inner = self.inner.with_rule_pack(pack);
+        self
+    }
+
+    /// Run the checks in `pool` instead of rayon's global pool, see
+    /// [`crate::pool`]
+    #[cfg(feature = "parallel")]
+    pub fn with_thread_pool(mut self, pool: std::sync::Arc<rayon::ThreadPool>) -> Self {
+        self.inner = self.inner.with_thread_pool(pool);
+        self
+    }
+
+    /// Record every check run in [`ValidationResult::audit`]
+    pub fn with_audit(mut self) -> Self {
+        self.audit = true;
+        self
+    }
+
     pub fn validate(&self, doc: &Document) -> Result<ValidationResult> {
         let start = std::time::Instant::now();
+        let started_at = chrono::Utc::now();
 
-        // Clone doc for validation (validator mutates it)
-        let mut doc_copy = doc.clone();
-
-        // Run validation
-        let is_valid = self.inner.validate(&mut doc_copy).is_ok();
+        let (errors, checks) = self.inner.check_audited(doc);
+        let audit = self
+            .audit
+            .then(|| audit::AuditLog::new(doc, self.strict, started_at, checks));
+        // Each rule sets the severity of its findings, but only strict
+        // validation fails the document; otherwise errors are reported as
+        // warnings
+        let findings: Vec<Finding> = errors
+            .iter()
+            .map(|e| {
+                let severity = match e.severity() {
+                    Severity::Error if !self.strict => Severity::Warning,
+                    severity => severity,
+                };
+                Finding::new(e, severity, doc)
+            })
+            .collect();
+        let messages = |severity| {
+            findings
+                .iter()
+                .filter(|f| f.severity == severity)
+                .map(|f| f.message.clone())
+                .collect::<Vec<_>>()
+        };
+        let errors = messages(Severity::Error);
+        let warnings = messages(Severity::Warning);
 
         Ok(ValidationResult {
-            is_valid,
-            errors: if is_valid {
-                Vec::new()
-            } else {
-                vec!["Validation failed".to_string()]
-            },
-            warnings: Vec::new(),
+            is_valid: errors.is_empty(),
+            errors,
+            warnings,
+            findings,
+            audit,
+            unsupported: doc.unsupported.clone(),
             stats: ValidationStats {
                 facts_validated: doc.facts.len(),
                 duration_ms: start.elapsed().as_millis() as u64,
@@ -72,11 +201,26 @@ impl Validator {
 #[derive(Default)]
 pub struct ValidationConfig {
     pub strict: bool,
+    /// Run the EDGAR Filer Manual checks
+    pub efm: bool,
+    /// Cross-check reporting-period contexts against the DEI reporting
+    /// period
+    pub period_alignment: bool,
+    /// Report contexts with a forever period
+    pub forbid_forever: bool,
+    /// Report complete and consistent duplicate facts, as warnings
+    pub redundant_duplicates: bool,
 }
 
 impl ValidationConfig {
     pub fn sec_edgar() -> Self {
-        Self { strict: true }
+        Self {
+            strict: true,
+            efm: true,
+            period_alignment: true,
+            forbid_forever: true,
+            redundant_duplicates: false,
+        }
     }
 }
 
@@ -85,9 +229,89 @@ pub struct ValidationResult {
     pub is_valid: bool,
     pub errors: Vec<String>,
     pub warnings: Vec<String>,
+    pub findings: Vec<Finding>,
+    /// Checks run, when the validator was built [`Validator::with_audit`]
+    pub audit: Option<audit::AuditLog>,
+    /// Features of the document that were skipped, so checks depending on
+    /// them did not run
+    pub unsupported: Vec<model::Unsupported>,
     pub stats: ValidationStats,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// A single validation finding with enough context for reports
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub severity: Severity,
+    pub rule: &'static str,
+    /// Error code such as `xbrl.2.1.contextRef`
+    pub code: &'static str,
+    pub citation: &'static str,
+    pub message: String,
+    /// Source document, with a `#fragment` pointing at the fact when known
+    pub location: Option<String>,
+    /// Position of the offending element in the source, when known
+    pub position: Option<model::SourcePosition>,
+    pub error: validator::ValidationError,
+}
+
+impl Finding {
+    pub fn new(error: &validator::ValidationError, severity: Severity, doc: &Document) -> Self {
+        let source = doc.provenance.as_ref().and_then(|p| p.uri.as_deref());
+        let fact = error.fact_index().and_then(|i| doc.fact(i));
+        let location = match (source, fact) {
+            (Some(uri), Some(fact)) => Some(match fact.id() {
+                Some(id) => format!("{}#{}", uri, id),
+                None => format!("{} (fact {})", uri, fact.index()),
+            }),
+            (Some(uri), None) => Some(uri.to_string()),
+            (None, Some(fact)) => Some(
+                fact.id()
+                    .map(|id| format!("#{}", id))
+                    .unwrap_or_else(|| format!("fact {}", fact.index())),
+            ),
+            (None, None) => None,
+        };
+        let position = doc.source_map.as_ref().and_then(|map| {
+            let offset = match error {
+                validator::ValidationError::DuplicateId { id }
+                | validator::ValidationError::EquivalentContexts { context_id: id, .. } => {
+                    map.id_offset(id)
+                }
+                _ => error
+                    .fact_index()
+                    .and_then(|i| map.fact_offsets.get(i).copied()),
+            }?;
+            Some(map.position(offset))
+        });
+        Self {
+            severity,
+            rule: error.rule(),
+            code: error.code(),
+            citation: error.citation(),
+            message: error.to_string(),
+            location,
+            position,
+            error: error.clone(),
+        }
+    }
+}
+
 pub struct ValidationStats {
     pub facts_validated: usize,
     pub duration_ms: u64,
@@ -95,12 +319,130 @@ pub struct ValidationStats {
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Why an operation failed. New variants may be added, so matches need a
+/// wildcard arm; [`Error::kind`] gives the class without the details.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     Io(std::io::Error),
+    /// Malformed input other than XML markup: JSON, CSV, TOML, EDGAR indexes
     Parse(String),
-    Validation(String),
+    /// Malformed XML or instance markup, with where in the source it was
+    /// found when known
+    XmlSyntax {
+        message: String,
+        position: Option<model::SourcePosition>,
+        /// The offending line around the position
+        snippet: Option<String>,
+    },
+    /// Input that ends before the xbrl root element is closed, as a filing
+    /// cut off mid-download does; `at_byte` is the length of the input
+    Truncated {
+        at_byte: usize,
+    },
+    /// A QName whose prefix is not bound to a namespace
+    Namespace {
+        prefix: String,
+        message: String,
+    },
+    /// A schema or linkbase of the DTS that could not be located or read
+    SchemaResolution {
+        location: String,
+        message: String,
+    },
+    /// Input beyond a hard limit of the document model
+    Limit {
+        what: &'static str,
+        max: usize,
+    },
+    /// A failed HTTP request; `status` is absent for transport failures
+    Network {
+        url: String,
+        status: Option<u16>,
+        message: String,
+    },
+    /// A rejected value or edit
+    Validation {
+        /// Stable code such as `xsd.minInclusive`
+        code: &'static str,
+        message: String,
+    },
     NotFound(String),
+    Export(String),
+}
+
+/// Class of an [`Error`], for branching without matching on details
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    Io,
+    Parse,
+    XmlSyntax,
+    Truncated,
+    Namespace,
+    SchemaResolution,
+    Limit,
+    Network,
+    Validation,
+    NotFound,
+    Export,
+}
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Io(_) => ErrorKind::Io,
+            Error::Parse(_) => ErrorKind::Parse,
+            Error::XmlSyntax { .. } => ErrorKind::XmlSyntax,
+            Error::Truncated { .. } => ErrorKind::Truncated,
+            Error::Namespace { .. } => ErrorKind::Namespace,
+            Error::SchemaResolution { .. } => ErrorKind::SchemaResolution,
+            Error::Limit { .. } => ErrorKind::Limit,
+            Error::Network { .. } => ErrorKind::Network,
+            Error::Validation { .. } => ErrorKind::Validation,
+            Error::NotFound(_) => ErrorKind::NotFound,
+            Error::Export(_) => ErrorKind::Export,
+        }
+    }
+
+    /// Code of a validation error
+    pub fn code(&self) -> Option<&'static str> {
+        match self {
+            Error::Validation { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Source position of an XML syntax error
+    pub fn position(&self) -> Option<model::SourcePosition> {
+        match self {
+            Error::XmlSyntax { position, .. } => *position,
+            _ => None,
+        }
+    }
+
+    /// HTTP status of a network error
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Error::Network { status, .. } => *status,
+            _ => None,
+        }
+    }
+
+    pub(crate) fn xml(message: impl Into<String>) -> Self {
+        Error::XmlSyntax {
+            message: message.into(),
+            position: None,
+            snippet: None,
+        }
+    }
+
+    pub(crate) fn validation(code: &'static str, message: impl Into<String>) -> Self {
+        Error::Validation {
+            code,
+            message: message.into(),
+        }
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -108,16 +450,126 @@ impl std::fmt::Display for Error {
         match self {
             Error::Io(e) => write!(f, "IO error: {}", e),
             Error::Parse(s) => write!(f, "Parse error: {}", s),
-            Error::Validation(s) => write!(f, "Validation error: {}", s),
+            Error::XmlSyntax {
+                message,
+                position: Some(position),
+                snippet,
+            } => {
+                write!(
+                    f,
+                    "Parse error at line {}, column {}: {}",
+                    position.line, position.column, message
+                )?;
+                match snippet {
+                    Some(snippet) => write!(f, "\n  {}", snippet),
+                    None => Ok(()),
+                }
+            }
+            Error::XmlSyntax { message, .. } => write!(f, "Parse error: {}", message),
+            Error::Truncated { at_byte } => write!(
+                f,
+                "Truncated input: ends at byte {} before the document is complete",
+                at_byte
+            ),
+            Error::Namespace { prefix, message } => {
+                write!(f, "Namespace error: prefix {}: {}", prefix, message)
+            }
+            Error::SchemaResolution { location, message } => {
+                write!(f, "Cannot resolve {}: {}", location, message)
+            }
+            Error::Limit { what, max } => write!(f, "Limit exceeded: more than {} {}", max, what),
+            Error::Network {
+                url,
+                status: Some(status),
+                message,
+            } => write!(f, "HTTP error: {}: HTTP {} ({})", url, status, message),
+            Error::Network { url, message, .. } => write!(f, "HTTP error: {}: {}", url, message),
+            Error::Validation { code, message } => {
+                write!(f, "Validation error [{}]: {}", code, message)
+            }
             Error::NotFound(s) => write!(f, "Not found: {}", s),
+            Error::Export(s) => write!(f, "Export error: {}", s),
         }
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
         Error::Io(err)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_accessors() {
+        let syntax = Parser::new()
+            .parse_str("<xbrli:xbrl xmlns:xbrli=\"http://www.xbrl.org/2003/instance\">\n  <a:X contextRef=c>1</a:X>\n</xbrli:xbrl>")
+            .err()
+            .unwrap();
+        assert_eq!(syntax.kind(), ErrorKind::XmlSyntax);
+        assert_eq!(syntax.position().map(|p| (p.line, p.column)), Some((2, 19)));
+        assert_eq!((syntax.code(), syntax.status()), (None, None));
+
+        let doc = Parser::new()
+            .parse_str("<xbrli:xbrl xmlns:xbrli=\"http://www.xbrl.org/2003/instance\" xmlns:a=\"http://example.com/a\"><a:X contextRef=\"missing\">1</a:X></xbrli:xbrl>")
+            .unwrap();
+        let validation = validator::XbrlValidator::new()
+            .strict()
+            .validate(&doc)
+            .err()
+            .unwrap();
+        assert_eq!(validation.kind(), ErrorKind::Validation);
+        assert_eq!(validation.code(), Some("validation.failed"));
+        assert_eq!(validation.position(), None);
+
+        // Fact names must use a declared prefix; recovery skips the fact
+        let unbound = "<xbrli:xbrl xmlns:xbrli=\"http://www.xbrl.org/2003/instance\"><b:Y contextRef=\"c\">1</b:Y></xbrli:xbrl>";
+        let namespace = Parser::new().parse_str(unbound).err().unwrap();
+        assert_eq!(namespace.kind(), ErrorKind::Namespace);
+        assert!(matches!(&namespace, Error::Namespace { prefix, .. } if prefix == "b"));
+        let doc = Parser::new()
+            .with_error_recovery(true)
+            .parse_str(unbound)
+            .unwrap();
+        assert_eq!(doc.facts.len(), 0);
+        assert_eq!(
+            doc.diagnostics[0].message,
+            "fact b:Y uses an undeclared prefix"
+        );
+
+        let network = Error::Network {
+            url: "https://www.sec.gov/x.xml".to_string(),
+            status: Some(404),
+            message: "Not Found".to_string(),
+        };
+        assert_eq!(network.kind(), ErrorKind::Network);
+        assert_eq!(network.status(), Some(404));
+        assert_eq!(network.code(), None);
+
+        let truncated = Parser::new()
+            .parse_str("<xbrli:xbrl xmlns:xbrli=\"http://www.xbrl.org/2003/instance\">")
+            .err()
+            .unwrap();
+        assert_eq!(truncated.kind(), ErrorKind::Truncated);
+        assert_eq!(truncated.position(), None);
+
+        let io = Parser::new()
+            .parse_file("/nonexistent/filing.xml")
+            .err()
+            .unwrap();
+        assert_eq!(io.kind(), ErrorKind::Io);
+        assert!(std::error::Error::source(&io).is_some());
+    }
+}
@@ -1,438 +1,1351 @@
 // Linkbase processing for XBRL
-use crate::{Error, Result, model::*};
-use compact_str::CompactString;
-use std::collections::HashMap;
+//
+// Arcs and resources are resolved per extended link: `xlink:label` values
+// are only unique inside the extended link that declares them, and
+// locators are mapped to concept QNames through their `#prefix_Name`
+// fragment. Generic links (`gen:link`) contribute their generic labels and
+// references: those of concepts join the standard ones, those of roleType
+// locators title extended link roles. Elements and XLink attributes are matched by namespace, so any
+// prefix (or none) works.
+use crate::validator::ValidationError;
+use crate::{model::*, Error, Result};
+use quick_xml::events::attributes::Attribute;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::{QName, ResolveResult};
+use quick_xml::reader::NsReader;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
+pub const LABEL_ROLE: &str = "http://www.xbrl.org/2003/role/label";
+pub const TERSE_LABEL_ROLE: &str = "http://www.xbrl.org/2003/role/terseLabel";
+pub const VERBOSE_LABEL_ROLE: &str = "http://www.xbrl.org/2003/role/verboseLabel";
+pub const DOCUMENTATION_ROLE: &str = "http://www.xbrl.org/2003/role/documentation";
+pub const TOTAL_LABEL_ROLE: &str = "http://www.xbrl.org/2003/role/totalLabel";
+pub const PERIOD_START_LABEL_ROLE: &str = "http://www.xbrl.org/2003/role/periodStartLabel";
+pub const PERIOD_END_LABEL_ROLE: &str = "http://www.xbrl.org/2003/role/periodEndLabel";
+pub const NEGATED_LABEL_ROLE: &str = "http://www.xbrl.org/2009/role/negatedLabel";
+/// Standard role of generic labels
+pub const GENERIC_LABEL_ROLE: &str = "http://www.xbrl.org/2008/role/label";
+
+/// Which edge of a duration column an instant is shown at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PeriodEdge {
+    Start,
+    End,
+}
+
+/// How a statement renders a value under a preferred label role
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LabelSemantics {
+    pub negated: bool,
+    pub total: bool,
+    pub period: Option<PeriodEdge>,
+}
+
+/// Rendering semantics of a label role. Covers the 2003 roles
+/// (`periodStartLabel`, `totalLabel`, ...) and the 2009 negated variants
+/// (`negatedPeriodEndLabel`, `negatedTerseLabel`, ...).
+pub fn label_semantics(role: &str) -> LabelSemantics {
+    let name = role.rsplit('/').next().unwrap_or(role);
+    let (negated, rest) = match name.strip_prefix("negated") {
+        Some(rest) => (true, rest.to_ascii_lowercase()),
+        None => (false, name.to_ascii_lowercase()),
+    };
+    let period = match rest.as_str() {
+        "periodstartlabel" => Some(PeriodEdge::Start),
+        "periodendlabel" => Some(PeriodEdge::End),
+        _ => None,
+    };
+    LabelSemantics {
+        negated,
+        total: rest == "totallabel",
+        period,
+    }
+}
+
+#[derive(Default)]
 pub struct LinkbaseProcessor {
-    presentation_links: HashMap<CompactString, Vec<PresentationLink>>,
-    calculation_links: HashMap<CompactString, Vec<CalculationLink>>,
-    definition_links: HashMap<CompactString, Vec<DefinitionLink>>,
-    label_links: HashMap<CompactString, Vec<LabelLink>>,
-    reference_links: HashMap<CompactString, Vec<ReferenceLink>>,
+    presentation_links: Vec<PresentationLink>,
+    calculation_links: Vec<CalculationLink>,
+    definition_links: Vec<DefinitionLink>,
+    label_links: Vec<LabelLink>,
+    reference_links: Vec<ReferenceLink>,
+    role_labels: Vec<LabelLink>,
+    /// Concept QName per schema element id, see [`Document::element_ids`]
+    element_ids: HashMap<String, String>,
+    /// Role URI per roleType id, see [`Document::role_ids`]
+    role_ids: HashMap<String, String>,
+    /// Location of the linkbase, roleRef hrefs are relative to it
+    base: Option<String>,
+    role_refs: HashMap<String, String>,
+    unsupported: Vec<Unsupported>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LinkKind {
+    Presentation,
+    Calculation,
+    Definition,
+    Label,
+    Reference,
+    Generic,
+}
+
+struct Arc {
+    from: String,
+    to: String,
+    arcrole: String,
+    order: f32,
+    weight: f64,
+    priority: Option<i32>,
+    use_attribute: Option<String>,
+    preferred_label: Option<String>,
+}
+
+enum Resource {
+    Label {
+        role: String,
+        lang: String,
+        text: String,
+    },
+    Reference(Reference),
+}
+
+/// State of the extended link being read
+struct ExtendedLink {
+    kind: LinkKind,
+    role: String,
+    locators: HashMap<String, Vec<String>>,
+    /// Role URIs of locators pointing at roleType declarations
+    roles: HashMap<String, Vec<String>>,
+    resources: HashMap<String, Vec<Resource>>,
+    arcs: Vec<Arc>,
+}
+
+/// Element id a locator href points at: a bare name (`#us-gaap_Revenues`)
+/// or an XPointer `element()` scheme (`#element(us-gaap_Revenues)`)
+fn href_fragment(href: &str) -> Option<&str> {
+    let fragment = href.rsplit_once('#')?.1;
+    Some(
+        fragment
+            .strip_prefix("element(")
+            .and_then(|f| f.strip_suffix(')'))
+            .unwrap_or(fragment),
+    )
+}
+
+/// Concept QName from a locator href (`us-gaap-2023.xsd#us-gaap_Revenues`),
+/// assuming the `prefix_name` id convention
+pub fn concept_from_href(href: &str) -> Option<String> {
+    let (prefix, local) = href_fragment(href)?.split_once('_')?;
+    Some(format!("{}:{}", prefix, local))
+}
+
+/// Arcroles of the XBRL 2.1 linkbases and XBRL Dimensions 1.0
+const KNOWN_ARCROLES: &[&str] = &[
+    "http://www.xbrl.org/2003/arcrole/parent-child",
+    "http://www.xbrl.org/2003/arcrole/summation-item",
+    "https://xbrl.org/2023/arcrole/summation-item",
+    "http://www.xbrl.org/2003/arcrole/general-special",
+    "http://www.xbrl.org/2003/arcrole/essence-alias",
+    "http://www.xbrl.org/2003/arcrole/similar-tuples",
+    "http://www.xbrl.org/2003/arcrole/requires-element",
+    "http://www.xbrl.org/2003/arcrole/concept-label",
+    "http://www.xbrl.org/2003/arcrole/concept-reference",
+    "http://xbrl.org/arcrole/2008/element-label",
+    "http://xbrl.org/arcrole/2008/element-reference",
+    "http://xbrl.org/int/dim/arcrole/all",
+    "http://xbrl.org/int/dim/arcrole/notAll",
+    "http://xbrl.org/int/dim/arcrole/hypercube-dimension",
+    "http://xbrl.org/int/dim/arcrole/dimension-domain",
+    "http://xbrl.org/int/dim/arcrole/domain-member",
+    "http://xbrl.org/int/dim/arcrole/dimension-default",
+];
+
+/// Namespaces of formula resources: formulas, variables, filters and
+/// assertions (Formula 1.0)
+const FORMULA_NAMESPACES: &[&str] = &[
+    "http://xbrl.org/2008/formula",
+    "http://xbrl.org/2008/variable",
+    "http://xbrl.org/2008/filter",
+    "http://xbrl.org/2008/assertion",
+];
+
+/// Extended link role every linkbase may use without declaring it
+pub const STANDARD_LINK_ROLE: &str = "http://www.xbrl.org/2003/role/link";
+
+pub const LINKBASE_NS: &str = "http://www.xbrl.org/2003/linkbase";
+pub const XLINK_NS: &str = "http://www.w3.org/1999/xlink";
+pub const GENERIC_NS: &str = "http://xbrl.org/2008/generic";
+pub const GENERIC_LABEL_NS: &str = "http://xbrl.org/2008/label";
+pub const GENERIC_REFERENCE_NS: &str = "http://xbrl.org/2008/reference";
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+fn attr_value(a: &Attribute<'_>) -> String {
+    a.unescape_value()
+        .map(|v| v.into_owned())
+        .unwrap_or_else(|_| String::from_utf8_lossy(&a.value).into_owned())
+}
+
+/// Unqualified attribute `name`
+fn attr(e: &BytesStart<'_>, name: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.as_ref() == name)
+        .map(|a| attr_value(&a))
+}
+
+fn in_namespace(reader: &NsReader<&[u8]>, name: QName<'_>, namespace: &str) -> bool {
+    matches!(reader.resolve_element(name).0, ResolveResult::Bound(ns) if ns.as_ref() == namespace.as_bytes())
+}
+
+fn is_formula_element(reader: &NsReader<&[u8]>, name: QName<'_>) -> bool {
+    matches!(reader.resolve_element(name).0, ResolveResult::Bound(ns)
+        if FORMULA_NAMESPACES.iter().any(|f| ns.as_ref().starts_with(f.as_bytes())))
+}
+
+/// Attribute `local` in `namespace`, whatever prefix the document binds it to
+fn ns_attr(
+    reader: &NsReader<&[u8]>,
+    e: &BytesStart<'_>,
+    namespace: &str,
+    local: &[u8],
+) -> Option<String> {
+    e.attributes().flatten().find_map(|a| {
+        let (ns, name) = reader.resolve_attribute(a.key);
+        let bound = matches!(ns, ResolveResult::Bound(ns) if ns.as_ref() == namespace.as_bytes());
+        (bound && name.as_ref() == local).then(|| attr_value(&a))
+    })
+}
+
+fn xlink_attr(reader: &NsReader<&[u8]>, e: &BytesStart<'_>, local: &[u8]) -> Option<String> {
+    ns_attr(reader, e, XLINK_NS, local)
+}
+
+fn xml_lang(reader: &NsReader<&[u8]>, e: &BytesStart<'_>) -> Option<String> {
+    ns_attr(reader, e, XML_NS, b"lang")
 }
 
 impl LinkbaseProcessor {
     pub fn new() -> Self {
-        Self {
-            presentation_links: HashMap::new(),
-            calculation_links: HashMap::new(),
-            definition_links: HashMap::new(),
-            label_links: HashMap::new(),
-            reference_links: HashMap::new(),
-        }
+        Self::default()
     }
 
-    pub fn load_linkbase<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
-        let content = std::fs::read(path)?;
-        self.parse_linkbase(&content)
+    /// Resolve locators through these element ids before falling back to
+    /// the `prefix_name` convention
+    pub fn with_element_ids(mut self, element_ids: HashMap<String, String>) -> Self {
+        self.element_ids = element_ids;
+        self
     }
 
-    pub fn parse_linkbase(&mut self, data: &[u8]) -> Result<()> {
-        // Skip BOM if present
-        let data = if data.starts_with(&[0xEF, 0xBB, 0xBF]) {
-            &data[3..]
-        } else {
-            data
-        };
+    /// Map generic label locators to extended link roles through these
+    /// roleType ids
+    pub fn with_role_ids(mut self, role_ids: HashMap<String, String>) -> Self {
+        self.role_ids = role_ids;
+        self
+    }
 
-        let text = std::str::from_utf8(data)
-            .map_err(|_| Error::Parse("Invalid UTF-8 in linkbase".to_string()))?;
+    /// Resolve `link:roleRef` and `link:arcroleRef` hrefs against the
+    /// location `base` of the linkbase
+    pub fn with_base(mut self, base: impl Into<String>) -> Self {
+        self.base = Some(base.into());
+        self
+    }
 
-        // Detect linkbase type and parse accordingly
-        if text.contains("presentationLink") {
-            self.parse_presentation_linkbase(text)?;
-        }
-        if text.contains("calculationLink") {
-            self.parse_calculation_linkbase(text)?;
-        }
-        if text.contains("definitionLink") {
-            self.parse_definition_linkbase(text)?;
-        }
-        if text.contains("labelLink") {
-            self.parse_label_linkbase(text)?;
-        }
-        if text.contains("referenceLink") {
-            self.parse_reference_linkbase(text)?;
-        }
+    /// Concept QName a locator href points at
+    pub fn resolve_href(&self, href: &str) -> Option<String> {
+        href_fragment(href)
+            .and_then(|id| self.element_ids.get(id).cloned())
+            .or_else(|| concept_from_href(href))
+    }
 
-        Ok(())
+    pub fn load_linkbase<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let content = std::fs::read(path)?;
+        self.parse_linkbase(&content)
     }
 
-    fn parse_presentation_linkbase(&mut self, text: &str) -> Result<()> {
-        // Parse presentation arcs
-        let mut pos = 0;
-        while let Some(arc_start) = text[pos..].find("<link:presentationArc") {
-            let arc_start = pos + arc_start;
-            pos = arc_start + 1;
-
-            if let Some(arc_end) = text[arc_start..].find("/>") {
-                let arc_text = &text[arc_start..arc_start + arc_end];
-                
-                let mut link = PresentationLink {
-                    from: CompactString::new(""),
-                    to: CompactString::new(""),
-                    order: 1.0,
-                    priority: None,
-                    use_attribute: None,
-                };
+    pub fn parse_linkbase(&mut self, data: &[u8]) -> Result<()> {
+        let mut reader = NsReader::from_reader(data);
+        let mut buf = Vec::new();
+        let mut link: Option<ExtendedLink> = None;
+        // Resource being read and its accumulated text
+        let mut resource: Option<(String, Resource)> = None;
+        let mut reference_part: Option<String> = None;
+        let mut text = String::new();
 
-                // Extract from
-                if let Some(from_start) = arc_text.find("xlink:from=\"") {
-                    let from_start = from_start + 12;
-                    if let Some(from_end) = arc_text[from_start..].find('"') {
-                        link.from = CompactString::from(&arc_text[from_start..from_start + from_end]);
+        loop {
+            let event = reader
+                .read_event_into(&mut buf)
+                .map_err(|e| Error::xml(format!("Linkbase parse error: {}", e)))?;
+            match event {
+                Event::Start(ref e) | Event::Empty(ref e) => {
+                    let empty = matches!(event, Event::Empty(_));
+                    let local = e.local_name();
+                    let linkbase_element = in_namespace(&reader, e.name(), LINKBASE_NS);
+                    let generic_element = in_namespace(&reader, e.name(), GENERIC_NS);
+                    let xlink = |name: &[u8]| xlink_attr(&reader, e, name);
+                    let kind = match local.as_ref() {
+                        b"link" if generic_element => Some(LinkKind::Generic),
+                        _ if !linkbase_element => None,
+                        b"presentationLink" => Some(LinkKind::Presentation),
+                        b"calculationLink" => Some(LinkKind::Calculation),
+                        b"definitionLink" => Some(LinkKind::Definition),
+                        b"labelLink" => Some(LinkKind::Label),
+                        b"referenceLink" => Some(LinkKind::Reference),
+                        _ => None,
+                    };
+                    if is_formula_element(&reader, e.name()) {
+                        self.note_unsupported(Unsupported::Formula);
+                    } else if kind.is_none()
+                        && link.is_none()
+                        && xlink(b"type").as_deref() == Some("extended")
+                    {
+                        let name = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+                        self.note_unsupported(Unsupported::CustomLinkbase(name));
                     }
-                }
-
-                // Extract to
-                if let Some(to_start) = arc_text.find("xlink:to=\"") {
-                    let to_start = to_start + 10;
-                    if let Some(to_end) = arc_text[to_start..].find('"') {
-                        link.to = CompactString::from(&arc_text[to_start..to_start + to_end]);
+                    if let Some(kind) = kind {
+                        link = Some(ExtendedLink {
+                            kind,
+                            role: xlink(b"role").unwrap_or_default(),
+                            locators: HashMap::new(),
+                            roles: HashMap::new(),
+                            resources: HashMap::new(),
+                            arcs: Vec::new(),
+                        });
+                        continue;
                     }
-                }
-
-                // Extract order
-                if let Some(order_start) = arc_text.find("order=\"") {
-                    let order_start = order_start + 7;
-                    if let Some(order_end) = arc_text[order_start..].find('"') {
-                        if let Ok(order) = arc_text[order_start..order_start + order_end].parse() {
-                            link.order = order;
+                    let Some(current) = link.as_mut() else {
+                        let uri = match local.as_ref() {
+                            b"roleRef" if linkbase_element => attr(e, b"roleURI"),
+                            b"arcroleRef" if linkbase_element => attr(e, b"arcroleURI"),
+                            _ => None,
+                        };
+                        if let (Some(uri), Some(href)) = (uri, xlink(b"href")) {
+                            let schema = match &self.base {
+                                Some(base) => crate::dts::resolve_href(base, &href),
+                                None => href.split('#').next().unwrap_or_default().to_string(),
+                            };
+                            self.role_refs.entry(uri).or_insert(schema);
                         }
-                    }
-                }
+                        continue;
+                    };
+                    let arc = match local.as_ref() {
+                        b"presentationArc" | b"calculationArc" | b"definitionArc" | b"labelArc"
+                        | b"referenceArc" => linkbase_element,
+                        b"arc" => generic_element && current.kind == LinkKind::Generic,
+                        _ => false,
+                    };
+                    let generic_resource = |namespace: &str| {
+                        current.kind == LinkKind::Generic
+                            && in_namespace(&reader, e.name(), namespace)
+                    };
 
-                // Extract priority
-                if let Some(priority_start) = arc_text.find("priority=\"") {
-                    let priority_start = priority_start + 10;
-                    if let Some(priority_end) = arc_text[priority_start..].find('"') {
-                        if let Ok(priority) = arc_text[priority_start..priority_start + priority_end].parse() {
-                            link.priority = Some(priority);
+                    match local.as_ref() {
+                        b"loc" if linkbase_element => {
+                            if let (Some(label), Some(href)) = (xlink(b"label"), xlink(b"href")) {
+                                let role =
+                                    href_fragment(&href).and_then(|id| self.role_ids.get(id));
+                                if let Some(role) = role {
+                                    current.roles.entry(label).or_default().push(role.clone());
+                                } else if let Some(concept) = self.resolve_href(&href) {
+                                    current.locators.entry(label).or_default().push(concept);
+                                }
+                            }
+                        }
+                        _ if arc => {
+                            let arcrole = xlink(b"arcrole").unwrap_or_default();
+                            if !arcrole.is_empty() && !KNOWN_ARCROLES.contains(&arcrole.as_str()) {
+                                self.note_unsupported(Unsupported::Arcrole(arcrole.clone()));
+                            }
+                            current.arcs.push(Arc {
+                                from: xlink(b"from").unwrap_or_default(),
+                                to: xlink(b"to").unwrap_or_default(),
+                                arcrole,
+                                order: attr(e, b"order")
+                                    .and_then(|v| v.parse().ok())
+                                    .unwrap_or(1.0),
+                                weight: attr(e, b"weight")
+                                    .and_then(|v| v.parse().ok())
+                                    .unwrap_or(1.0),
+                                priority: attr(e, b"priority").and_then(|v| v.parse().ok()),
+                                use_attribute: attr(e, b"use"),
+                                preferred_label: attr(e, b"preferredLabel"),
+                            });
+                        }
+                        b"label"
+                            if (linkbase_element && current.kind == LinkKind::Label
+                                || generic_resource(GENERIC_LABEL_NS))
+                                && !empty =>
+                        {
+                            text.clear();
+                            let default_role = match current.kind {
+                                LinkKind::Generic => GENERIC_LABEL_ROLE,
+                                _ => LABEL_ROLE,
+                            };
+                            resource = Some((
+                                xlink(b"label").unwrap_or_default(),
+                                Resource::Label {
+                                    role: xlink(b"role")
+                                        .unwrap_or_else(|| default_role.to_string()),
+                                    lang: xml_lang(&reader, e).unwrap_or_default(),
+                                    text: String::new(),
+                                },
+                            ));
+                        }
+                        b"reference"
+                            if (linkbase_element && current.kind == LinkKind::Reference
+                                || generic_resource(GENERIC_REFERENCE_NS))
+                                && !empty =>
+                        {
+                            resource = Some((
+                                xlink(b"label").unwrap_or_default(),
+                                Resource::Reference(Reference {
+                                    role: xlink(b"role").unwrap_or_default(),
+                                    parts: HashMap::new(),
+                                }),
+                            ));
+                        }
+                        // Reference parts live in their own namespaces
+                        part if resource.is_some() && !empty => {
+                            if matches!(resource, Some((_, Resource::Reference(_)))) {
+                                text.clear();
+                                reference_part = Some(String::from_utf8_lossy(part).into_owned());
+                            }
                         }
+                        _ => {}
                     }
                 }
-
-                // Extract use
-                if let Some(use_start) = arc_text.find("use=\"") {
-                    let use_start = use_start + 5;
-                    if let Some(use_end) = arc_text[use_start..].find('"') {
-                        link.use_attribute = Some(CompactString::from(&arc_text[use_start..use_start + use_end]));
+                Event::Text(ref e) if resource.is_some() => match e.unescape() {
+                    Ok(t) => text.push_str(&t),
+                    Err(_) => text.push_str(&String::from_utf8_lossy(e.as_ref())),
+                },
+                Event::CData(ref e) if resource.is_some() => {
+                    text.push_str(&String::from_utf8_lossy(e.as_ref()));
+                }
+                Event::End(ref e) => {
+                    let local = e.local_name();
+                    let linkbase_element = in_namespace(&reader, e.name(), LINKBASE_NS);
+                    let generic_element = in_namespace(&reader, e.name(), GENERIC_NS);
+                    let resource_element = linkbase_element
+                        || in_namespace(&reader, e.name(), GENERIC_LABEL_NS)
+                        || in_namespace(&reader, e.name(), GENERIC_REFERENCE_NS);
+                    match local.as_ref() {
+                        b"presentationLink" | b"calculationLink" | b"definitionLink"
+                        | b"labelLink" | b"referenceLink"
+                            if linkbase_element =>
+                        {
+                            if let Some(finished) = link.take() {
+                                self.resolve(finished);
+                            }
+                        }
+                        b"link" if generic_element => {
+                            if let Some(finished) = link.take() {
+                                self.resolve(finished);
+                            }
+                        }
+                        b"label" | b"reference" if resource_element => {
+                            if let (Some(current), Some((label, mut res))) =
+                                (link.as_mut(), resource.take())
+                            {
+                                if let Resource::Label { text: t, .. } = &mut res {
+                                    *t = text.trim().to_string();
+                                }
+                                current.resources.entry(label).or_default().push(res);
+                            }
+                        }
+                        _ => {
+                            if let (Some(part), Some((_, Resource::Reference(reference)))) =
+                                (reference_part.take(), resource.as_mut())
+                            {
+                                reference.parts.insert(part, text.trim().to_string());
+                            }
+                        }
                     }
                 }
-
-                self.presentation_links
-                    .entry(link.from.clone())
-                    .or_insert_with(Vec::new)
-                    .push(link);
+                Event::Eof => break,
+                _ => {}
             }
+            buf.clear();
         }
 
         Ok(())
     }
 
-    fn parse_calculation_linkbase(&mut self, text: &str) -> Result<()> {
-        // Parse calculation arcs
-        let mut pos = 0;
-        while let Some(arc_start) = text[pos..].find("<link:calculationArc") {
-            let arc_start = pos + arc_start;
-            pos = arc_start + 1;
-
-            if let Some(arc_end) = text[arc_start..].find("/>") {
-                let arc_text = &text[arc_start..arc_start + arc_end];
-                
-                let mut link = CalculationLink {
-                    from: CompactString::new(""),
-                    to: CompactString::new(""),
-                    weight: 1.0,
-                    order: 1.0,
-                };
+    fn note_unsupported(&mut self, feature: Unsupported) {
+        if !self.unsupported.contains(&feature) {
+            self.unsupported.push(feature);
+        }
+    }
 
-                // Extract from
-                if let Some(from_start) = arc_text.find("xlink:from=\"") {
-                    let from_start = from_start + 12;
-                    if let Some(from_end) = arc_text[from_start..].find('"') {
-                        link.from = CompactString::from(&arc_text[from_start..from_start + from_end]);
-                    }
-                }
+    fn resolve(&mut self, link: ExtendedLink) {
+        let concepts = |label: &str| link.locators.get(label).into_iter().flatten();
 
-                // Extract to
-                if let Some(to_start) = arc_text.find("xlink:to=\"") {
-                    let to_start = to_start + 10;
-                    if let Some(to_end) = arc_text[to_start..].find('"') {
-                        link.to = CompactString::from(&arc_text[to_start..to_start + to_end]);
+        for arc in &link.arcs {
+            for from in concepts(&arc.from) {
+                match link.kind {
+                    LinkKind::Presentation | LinkKind::Calculation | LinkKind::Definition => {
+                        for to in concepts(&arc.to) {
+                            self.push_relationship(&link, arc, from, to);
+                        }
                     }
-                }
-
-                // Extract weight
-                if let Some(weight_start) = arc_text.find("weight=\"") {
-                    let weight_start = weight_start + 8;
-                    if let Some(weight_end) = arc_text[weight_start..].find('"') {
-                        if let Ok(weight) = arc_text[weight_start..weight_start + weight_end].parse() {
-                            link.weight = weight;
+                    LinkKind::Label | LinkKind::Reference | LinkKind::Generic => {
+                        for resource in link.resources.get(&arc.to).into_iter().flatten() {
+                            match resource {
+                                Resource::Label { role, lang, text } => {
+                                    self.label_links.push(LabelLink {
+                                        concept: from.clone(),
+                                        label: text.clone(),
+                                        role: role.clone(),
+                                        lang: lang.clone(),
+                                    })
+                                }
+                                Resource::Reference(reference) => {
+                                    self.reference_links.push(ReferenceLink {
+                                        concept: from.clone(),
+                                        reference: reference.clone(),
+                                    })
+                                }
+                            }
                         }
                     }
                 }
-
-                // Extract order
-                if let Some(order_start) = arc_text.find("order=\"") {
-                    let order_start = order_start + 7;
-                    if let Some(order_end) = arc_text[order_start..].find('"') {
-                        if let Ok(order) = arc_text[order_start..order_start + order_end].parse() {
-                            link.order = order;
-                        }
+            }
+            for role in link.roles.get(&arc.from).into_iter().flatten() {
+                for resource in link.resources.get(&arc.to).into_iter().flatten() {
+                    if let Resource::Label {
+                        role: label_role,
+                        lang,
+                        text,
+                    } = resource
+                    {
+                        self.role_labels.push(LabelLink {
+                            concept: role.clone(),
+                            label: text.clone(),
+                            role: label_role.clone(),
+                            lang: lang.clone(),
+                        });
                     }
                 }
-
-                self.calculation_links
-                    .entry(link.from.clone())
-                    .or_insert_with(Vec::new)
-                    .push(link);
             }
         }
-
-        Ok(())
     }
 
-    fn parse_definition_linkbase(&mut self, text: &str) -> Result<()> {
-        // Parse definition arcs
-        let mut pos = 0;
-        while let Some(arc_start) = text[pos..].find("<link:definitionArc") {
-            let arc_start = pos + arc_start;
-            pos = arc_start + 1;
-
-            if let Some(arc_end) = text[arc_start..].find("/>") {
-                let arc_text = &text[arc_start..arc_start + arc_end];
-                
-                let mut link = DefinitionLink {
-                    from: CompactString::new(""),
-                    to: CompactString::new(""),
-                    arcrole: CompactString::new(""),
-                    order: 1.0,
-                };
+    fn push_relationship(&mut self, link: &ExtendedLink, arc: &Arc, from: &str, to: &str) {
+        match link.kind {
+            LinkKind::Presentation => self.presentation_links.push(PresentationLink {
+                role: link.role.clone(),
+                from: from.to_string(),
+                to: to.to_string(),
+                order: arc.order,
+                priority: arc.priority,
+                use_attribute: arc.use_attribute.clone(),
+                preferred_label: arc.preferred_label.clone(),
+            }),
+            LinkKind::Calculation => self.calculation_links.push(CalculationLink {
+                role: link.role.clone(),
+                from: from.to_string(),
+                to: to.to_string(),
+                weight: arc.weight,
+                order: arc.order,
+                priority: arc.priority,
+                use_attribute: arc.use_attribute.clone(),
+            }),
+            LinkKind::Definition => self.definition_links.push(DefinitionLink {
+                role: link.role.clone(),
+                from: from.to_string(),
+                to: to.to_string(),
+                arcrole: arc.arcrole.clone(),
+                order: arc.order,
+                priority: arc.priority,
+                use_attribute: arc.use_attribute.clone(),
+            }),
+            LinkKind::Label | LinkKind::Reference | LinkKind::Generic => {}
+        }
+    }
 
-                // Extract from
-                if let Some(from_start) = arc_text.find("xlink:from=\"") {
-                    let from_start = from_start + 12;
-                    if let Some(from_end) = arc_text[from_start..].find('"') {
-                        link.from = CompactString::from(&arc_text[from_start..from_start + from_end]);
-                    }
-                }
+    pub fn presentation_links(&self) -> &[PresentationLink] {
+        &self.presentation_links
+    }
 
-                // Extract to
-                if let Some(to_start) = arc_text.find("xlink:to=\"") {
-                    let to_start = to_start + 10;
-                    if let Some(to_end) = arc_text[to_start..].find('"') {
-                        link.to = CompactString::from(&arc_text[to_start..to_start + to_end]);
-                    }
-                }
+    pub fn calculation_links(&self) -> &[CalculationLink] {
+        &self.calculation_links
+    }
 
-                // Extract arcrole
-                if let Some(arcrole_start) = arc_text.find("xlink:arcrole=\"") {
-                    let arcrole_start = arcrole_start + 15;
-                    if let Some(arcrole_end) = arc_text[arcrole_start..].find('"') {
-                        link.arcrole = CompactString::from(&arc_text[arcrole_start..arcrole_start + arcrole_end]);
-                    }
-                }
+    pub fn definition_links(&self) -> &[DefinitionLink] {
+        &self.definition_links
+    }
 
-                // Extract order
-                if let Some(order_start) = arc_text.find("order=\"") {
-                    let order_start = order_start + 7;
-                    if let Some(order_end) = arc_text[order_start..].find('"') {
-                        if let Ok(order) = arc_text[order_start..order_start + order_end].parse() {
-                            link.order = order;
-                        }
-                    }
-                }
+    pub fn label_links(&self) -> &[LabelLink] {
+        &self.label_links
+    }
 
-                self.definition_links
-                    .entry(link.from.clone())
-                    .or_insert_with(Vec::new)
-                    .push(link);
-            }
-        }
+    pub fn reference_links(&self) -> &[ReferenceLink] {
+        &self.reference_links
+    }
 
-        Ok(())
+    /// Generic labels of extended link roles; `concept` holds the role URI
+    pub fn role_labels(&self) -> &[LabelLink] {
+        &self.role_labels
     }
 
-    fn parse_label_linkbase(&mut self, text: &str) -> Result<()> {
-        // Parse labels
-        let mut pos = 0;
-        while let Some(label_start) = text[pos..].find("<link:label") {
-            let label_start = pos + label_start;
-            pos = label_start + 1;
-
-            if let Some(label_end) = text[label_start..].find("</link:label>") {
-                let label_text = &text[label_start..label_start + label_end];
-                
-                let mut link = LabelLink {
-                    concept: CompactString::new(""),
-                    label: CompactString::new(""),
-                    role: CompactString::new(""),
-                    lang: CompactString::new("en"),
-                };
+    /// Schema location per role and arcrole URI of the `link:roleRef` and
+    /// `link:arcroleRef` elements read so far
+    pub fn role_refs(&self) -> &HashMap<String, String> {
+        &self.role_refs
+    }
 
-                // Extract label ID for concept mapping
-                if let Some(id_start) = label_text.find("xlink:label=\"") {
-                    let id_start = id_start + 13;
-                    if let Some(id_end) = label_text[id_start..].find('"') {
-                        link.concept = CompactString::from(&label_text[id_start..id_start + id_end]);
-                    }
-                }
+    /// Features read past so far, each listed once
+    pub fn unsupported(&self) -> &[Unsupported] {
+        &self.unsupported
+    }
 
-                // Extract role
-                if let Some(role_start) = label_text.find("xlink:role=\"") {
-                    let role_start = role_start + 12;
-                    if let Some(role_end) = label_text[role_start..].find('"') {
-                        link.role = CompactString::from(&label_text[role_start..role_start + role_end]);
-                    }
-                }
+    pub fn get_presentation_tree(&self, root: &str) -> Vec<&PresentationLink> {
+        let mut children: Vec<_> = self
+            .presentation_links
+            .iter()
+            .filter(|l| l.from == root)
+            .collect();
+        children.sort_by(|a, b| a.order.total_cmp(&b.order));
+        children
+    }
 
-                // Extract lang
-                if let Some(lang_start) = label_text.find("xml:lang=\"") {
-                    let lang_start = lang_start + 10;
-                    if let Some(lang_end) = label_text[lang_start..].find('"') {
-                        link.lang = CompactString::from(&label_text[lang_start..lang_start + lang_end]);
-                    }
-                }
+    pub fn calculate_total(&self, parent: &str, facts: &HashMap<String, f64>) -> f64 {
+        let mut children = self
+            .calculation_links
+            .iter()
+            .filter(|l| l.from == parent)
+            .peekable();
+        if children.peek().is_none() {
+            return facts.get(parent).copied().unwrap_or(0.0);
+        }
+        children
+            .map(|l| facts.get(&l.to).map(|v| v * l.weight).unwrap_or(0.0))
+            .sum()
+    }
 
-                // Extract label text content
-                if let Some(content_start) = label_text.find('>') {
-                    let content = &label_text[content_start + 1..];
-                    link.label = CompactString::from(content.trim());
-                }
+    /// Label for `concept` with `role` in the first of `langs` that has one
+    pub fn get_label(&self, concept: &str, role: &str, langs: &[&str]) -> Option<&str> {
+        langs
+            .iter()
+            .find_map(|lang| find_label(&self.label_links, concept, role, lang))
+    }
 
-                self.label_links
-                    .entry(link.concept.clone())
-                    .or_insert_with(Vec::new)
-                    .push(link);
+    /// Move everything read so far into `doc`
+    pub fn apply_to(self, doc: &mut Document) {
+        doc.presentation_links.extend(self.presentation_links);
+        doc.calculation_links.extend(self.calculation_links);
+        doc.definition_links.extend(self.definition_links);
+        doc.label_links.extend(self.label_links);
+        doc.reference_links.extend(self.reference_links);
+        doc.role_labels.extend(self.role_labels);
+        for (uri, schema) in self.role_refs {
+            doc.role_refs.entry(uri).or_insert(schema);
+        }
+        let unloaded = doc
+            .role_refs
+            .keys()
+            .filter_map(|uri| doc.unloaded_role_schema(uri))
+            .map(|schema| Unsupported::IncompleteDts(schema.to_string()));
+        let features: Vec<_> = self.unsupported.into_iter().chain(unloaded).collect();
+        for feature in features {
+            if !doc.unsupported.contains(&feature) {
+                doc.unsupported.push(feature);
             }
         }
-
-        Ok(())
     }
+}
 
-    fn parse_reference_linkbase(&mut self, text: &str) -> Result<()> {
-        // Parse references - simplified version
-        let mut pos = 0;
-        while let Some(ref_start) = text[pos..].find("<link:reference") {
-            let ref_start = pos + ref_start;
-            pos = ref_start + 1;
+/// Best label for `concept` with `role` in `lang`; a language without region
+/// (`en`) also matches regional variants (`en-US`) and vice versa
+fn find_label<'a>(
+    labels: &'a [LabelLink],
+    concept: &str,
+    role: &str,
+    lang: &str,
+) -> Option<&'a str> {
+    let primary = |l: &str| l.split('-').next().unwrap_or(l).to_ascii_lowercase();
+    let candidates = || {
+        labels
+            .iter()
+            .filter(|l| l.concept == concept && l.role == role)
+    };
+    candidates()
+        .find(|l| l.lang.eq_ignore_ascii_case(lang))
+        .or_else(|| candidates().find(|l| primary(&l.lang) == primary(lang)))
+        .map(|l| l.label.as_str())
+}
 
-            if let Some(ref_end) = text[ref_start..].find("</link:reference>") {
-                let ref_text = &text[ref_start..ref_start + ref_end];
-                
-                let mut reference = Reference {
-                    role: CompactString::new(""),
-                    parts: HashMap::new(),
-                };
+impl Document {
+    /// Read a linkbase file and attach its relationships and resources
+    pub fn load_linkbase<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let base = path.as_ref().to_string_lossy().into_owned();
+        self.load_linkbase_at(path, base)
+    }
 
-                // Extract role
-                if let Some(role_start) = ref_text.find("xlink:role=\"") {
-                    let role_start = role_start + 12;
-                    if let Some(role_end) = ref_text[role_start..].find('"') {
-                        reference.role = CompactString::from(&ref_text[role_start..role_start + role_end]);
-                    }
-                }
+    /// [`Document::load_linkbase`] for a linkbase whose references are
+    /// relative to `base`
+    fn load_linkbase_at<P: AsRef<Path>>(&mut self, path: P, base: String) -> Result<()> {
+        let mut processor = LinkbaseProcessor::new()
+            .with_element_ids(self.element_ids())
+            .with_role_ids(self.role_ids())
+            .with_base(base);
+        processor.load_linkbase(path)?;
+        processor.apply_to(self);
+        Ok(())
+    }
 
-                // Parse reference parts (simplified)
-                let parts = ["Name", "Number", "Section", "Subsection", "Paragraph", "Subparagraph", "Clause"];
-                for part in &parts {
-                    let tag = format!("<link:{}", part);
-                    if let Some(part_start) = ref_text.find(&tag) {
-                        let part_start = part_start + tag.len();
-                        if let Some(content_start) = ref_text[part_start..].find('>') {
-                            let content_start = part_start + content_start + 1;
-                            if let Some(content_end) = ref_text[content_start..].find('<') {
-                                let content = &ref_text[content_start..content_start + content_end];
-                                reference.parts.insert(
-                                    CompactString::from(*part),
-                                    content.trim().to_string()
-                                );
-                            }
-                        }
-                    }
-                }
+    /// Load every linkbase of a discovered DTS that is available locally
+    pub fn load_dts_linkbases(&mut self, dts: &crate::dts::Dts) -> Result<()> {
+        for document in &dts.documents {
+            if let (crate::dts::DtsDocumentKind::Linkbase, Some(path)) =
+                (document.kind, &document.local_path)
+            {
+                self.load_linkbase_at(path, document.uri.clone())?;
+            }
+        }
+        Ok(())
+    }
 
-                // Find concept this reference belongs to
-                if let Some(label_start) = ref_text.find("xlink:label=\"") {
-                    let label_start = label_start + 13;
-                    if let Some(label_end) = ref_text[label_start..].find('"') {
-                        let concept = CompactString::from(&ref_text[label_start..label_start + label_end]);
-                        
-                        let link = ReferenceLink {
-                            concept: concept.clone(),
-                            reference,
-                        };
-                        
-                        self.reference_links
-                            .entry(concept)
-                            .or_insert_with(Vec::new)
-                            .push(link);
-                    }
+    /// Location of the schema a `link:roleRef` or `link:arcroleRef` points
+    /// at for `uri`, when no loaded schema declares `uri` and that schema is
+    /// not in the loaded DTS
+    pub fn unloaded_role_schema(&self, uri: &str) -> Option<&str> {
+        let declared = self.role_types.iter().any(|t| t.role_uri == uri)
+            || self.arcrole_types.iter().any(|t| t.arcrole_uri == uri);
+        let schema = self.role_refs.get(uri)?;
+        let loaded = self
+            .schemas
+            .iter()
+            .any(|s| s.location.as_deref() == Some(schema.as_str()));
+        (!declared && !loaded).then_some(schema.as_str())
+    }
+
+    /// Concept QName per element id of the loaded schemas, prefixed as in
+    /// this document. Schemas whose namespace the document does not declare
+    /// fall back to the prefix of the `prefix_name` id convention.
+    pub fn element_ids(&self) -> HashMap<String, String> {
+        let prefixes: HashMap<&str, &str> = self
+            .namespaces
+            .iter()
+            .map(|(prefix, uri)| (uri.as_str(), prefix.as_str()))
+            .collect();
+        let mut ids = HashMap::new();
+        for schema in &self.schemas {
+            for element in schema.elements.values() {
+                let Some(id) = &element.id else {
+                    continue;
+                };
+                let prefix = prefixes
+                    .get(schema.target_namespace.as_str())
+                    .copied()
+                    .or_else(|| id.split_once('_').map(|(prefix, _)| prefix));
+                if let Some(prefix) = prefix.filter(|p| !p.is_empty()) {
+                    ids.insert(id.clone(), format!("{}:{}", prefix, element.name));
                 }
             }
         }
+        ids
+    }
 
-        Ok(())
+    /// Role URI per roleType id of the loaded schemas
+    pub fn role_ids(&self) -> HashMap<String, String> {
+        self.role_types
+            .iter()
+            .filter_map(|t| Some((t.id.clone()?, t.role_uri.clone())))
+            .collect()
     }
 
-    pub fn get_presentation_tree(&self, root: &str) -> Vec<&PresentationLink> {
-        self.presentation_links
-            .get(root)
-            .map(|links| {
-                let mut sorted = links.iter().collect::<Vec<_>>();
-                sorted.sort_by(|a, b| a.order.partial_cmp(&b.order).unwrap());
-                sorted
+    /// Title of an extended link role: its generic label in `lang`, else
+    /// the roleType definition, else a generic label in any language
+    pub fn role_label(&self, role: &str, lang: &str) -> Option<&str> {
+        [GENERIC_LABEL_ROLE, LABEL_ROLE]
+            .iter()
+            .find_map(|label_role| find_label(&self.role_labels, role, label_role, lang))
+            .or_else(|| {
+                self.role_types
+                    .iter()
+                    .find(|t| t.role_uri == role)
+                    .and_then(|t| t.definition.as_deref())
+            })
+            .or_else(|| {
+                self.role_labels
+                    .iter()
+                    .find(|l| l.concept == role)
+                    .map(|l| l.label.as_str())
             })
-            .unwrap_or_default()
     }
 
-    pub fn calculate_total(&self, parent: &str, facts: &HashMap<String, f64>) -> f64 {
-        if let Some(links) = self.calculation_links.get(parent) {
-            links.iter()
-                .map(|link| {
-                    facts.get(link.to.as_str())
-                        .map(|value| value * link.weight)
-                        .unwrap_or(0.0)
-                })
-                .sum()
-        } else {
-            facts.get(parent).copied().unwrap_or(0.0)
-        }
+    pub fn label(&self, concept: &str, role: &str, lang: &str) -> Option<&str> {
+        find_label(&self.label_links, concept, role, lang)
+    }
+
+    /// Label for `concept` negotiated against `langs`, most preferred
+    /// first: the `role` label (terse, verbose, documentation, ...) in the
+    /// first language that has one, else the standard label the same way.
+    /// Each language also matches its regional variants.
+    pub fn get_label(&self, concept: &str, role: &str, langs: &[&str]) -> Option<&str> {
+        let negotiate = |role: &str| {
+            langs
+                .iter()
+                .find_map(|lang| self.label(concept, role, lang))
+        };
+        negotiate(role).or_else(|| negotiate(LABEL_ROLE))
+    }
+
+    /// Distinct `xml:lang` values of the labels, sorted
+    pub fn available_languages(&self) -> Vec<&str> {
+        let mut languages: Vec<&str> = self.label_links.iter().map(|l| l.lang.as_str()).collect();
+        languages.sort_unstable();
+        languages.dedup();
+        languages
     }
 
-    pub fn get_label(&self, concept: &str, role: &str, lang: &str) -> Option<&str> {
-        self.label_links
-            .get(concept)
-            .and_then(|labels| {
-                labels.iter()
-                    .find(|l| l.role == role && l.lang == lang)
-                    .or_else(|| labels.iter().find(|l| l.lang == lang))
-                    .or_else(|| labels.first())
+    /// Label to show for `concept`: the `role` label, else the standard
+    /// label, in `lang` or failing that any language
+    pub fn label_for(&self, concept: &str, role: &str, lang: &str) -> Option<&str> {
+        self.label(concept, role, lang)
+            .or_else(|| self.label(concept, LABEL_ROLE, lang))
+            .or_else(|| {
+                let any = |role: &str| {
+                    self.label_links
+                        .iter()
+                        .find(|l| l.concept == concept && l.role == role)
+                };
+                any(role)
+                    .or_else(|| any(LABEL_ROLE))
+                    .map(|l| l.label.as_str())
             })
-            .map(|l| l.label.as_str())
-    }
-
-    pub fn validate_calculations(&self, facts: &HashMap<String, f64>) -> Vec<ValidationError> {
-        let mut errors = Vec::new();
-
-        for (parent, links) in &self.calculation_links {
-            let calculated = self.calculate_total(parent, facts);
-            if let Some(&actual) = facts.get(parent.as_str()) {
-                let diff = (calculated - actual).abs();
-                let tolerance = 0.01; // Allow small rounding differences
-                
-                if diff > tolerance {
-                    errors.push(ValidationError::CalculationInconsistency {
-                        concept: parent.to_string(),
-                        expected: calculated,
-                        actual,
-                    });
-                }
-            }
+    }
+
+    /// Text of the documentation-role label for `concept`
+    pub fn documentation(&self, concept: &str, lang: &str) -> Option<&str> {
+        self.label(concept, DOCUMENTATION_ROLE, lang)
+    }
+}
+
+/// Extended link roles and definition arcroles the loaded linkbases use
+/// without a declaration in the loaded schemas that allows them on their
+/// element, each reported once. The standard link role and the XBRL 2.1,
+/// Dimensions and generic arcroles need no declaration. Documents without
+/// schemas are not checked.
+pub fn check_role_types(doc: &Document) -> Vec<ValidationError> {
+    if doc.schemas.is_empty() {
+        return Vec::new();
+    }
+    let roles = doc
+        .presentation_links
+        .iter()
+        .map(|l| (l.role.as_str(), "presentationLink"))
+        .chain(
+            doc.calculation_links
+                .iter()
+                .map(|l| (l.role.as_str(), "calculationLink")),
+        )
+        .chain(
+            doc.definition_links
+                .iter()
+                .map(|l| (l.role.as_str(), "definitionLink")),
+        );
+
+    let mut errors = Vec::new();
+    let mut seen = HashSet::new();
+    for (role, element) in roles {
+        if role == STANDARD_LINK_ROLE || !seen.insert((role, element)) {
+            continue;
+        }
+        let declared = doc.role_types.iter().find(|t| t.role_uri == role);
+        // A role of a schema outside the loaded DTS is a gap in the DTS,
+        // noted in Document::unsupported, not an undeclared role
+        if doc.unloaded_role_schema(role).is_some() {
+            continue;
+        }
+        if !declared.is_some_and(|t| t.used_on(element)) {
+            errors.push(ValidationError::UndeclaredRole {
+                role: role.to_string(),
+                element: format!("link:{}", element),
+                declared: declared.is_some(),
+            });
+        }
+    }
+    for link in doc.definition_links.iter() {
+        let arcrole = link.arcrole.as_str();
+        if KNOWN_ARCROLES.contains(&arcrole) || !seen.insert((arcrole, "definitionArc")) {
+            continue;
+        }
+        let declared = doc.arcrole_types.iter().find(|t| t.arcrole_uri == arcrole);
+        if doc.unloaded_role_schema(arcrole).is_some() {
+            continue;
         }
+        if !declared.is_some_and(|t| t.used_on("definitionArc")) {
+            errors.push(ValidationError::UndeclaredArcrole {
+                arcrole: arcrole.to_string(),
+                element: "link:definitionArc".to_string(),
+                declared: declared.is_some(),
+            });
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LABELS: &str = r#"<link:linkbase xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink">
+  <link:labelLink xlink:type="extended" xlink:role="http://www.xbrl.org/2003/role/link">
+    <link:loc xlink:type="locator" xlink:href="https://xbrl.fasb.org/us-gaap/2023/elts/us-gaap-2023.xsd#us-gaap_Revenues" xlink:label="loc_Revenues"/>
+    <link:label xlink:type="resource" xlink:label="lab_Revenues" xlink:role="http://www.xbrl.org/2003/role/label" xml:lang="en-US">Revenues</link:label>
+    <link:label xlink:type="resource" xlink:label="lab_Revenues" xlink:role="http://www.xbrl.org/2003/role/documentation" xml:lang="en-US">Amount of revenue recognized from goods sold &amp; services rendered.</link:label>
+    <link:labelArc xlink:type="arc" xlink:arcrole="http://www.xbrl.org/2003/arcrole/concept-label" xlink:from="loc_Revenues" xlink:to="lab_Revenues"/>
+  </link:labelLink>
+</link:linkbase>"#;
+
+    const CALCULATION: &str = r#"<link:linkbase xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink">
+  <link:calculationLink xlink:type="extended" xlink:role="http://example.com/role/IncomeStatement">
+    <link:loc xlink:type="locator" xlink:href="us-gaap.xsd#us-gaap_GrossProfit" xlink:label="gp"/>
+    <link:loc xlink:type="locator" xlink:href="us-gaap.xsd#us-gaap_Revenues" xlink:label="rev"/>
+    <link:loc xlink:type="locator" xlink:href="us-gaap.xsd#us-gaap_CostOfRevenue" xlink:label="cost"/>
+    <link:calculationArc xlink:type="arc" xlink:arcrole="http://www.xbrl.org/2003/arcrole/summation-item" xlink:from="gp" xlink:to="rev" order="1" weight="1.0"/>
+    <link:calculationArc xlink:type="arc" xlink:arcrole="http://www.xbrl.org/2003/arcrole/summation-item" xlink:from="gp" xlink:to="cost" order="2" weight="-1.0"/>
+  </link:calculationLink>
+</link:linkbase>"#;
+
+    #[test]
+    fn test_documentation_label() {
+        let mut processor = LinkbaseProcessor::new();
+        processor.parse_linkbase(LABELS.as_bytes()).unwrap();
+        let mut doc = Document::new();
+        processor.apply_to(&mut doc);
+
+        assert_eq!(
+            doc.documentation("us-gaap:Revenues", "en"),
+            Some("Amount of revenue recognized from goods sold & services rendered.")
+        );
+        assert_eq!(
+            doc.label("us-gaap:Revenues", LABEL_ROLE, "en-US"),
+            Some("Revenues")
+        );
+        assert_eq!(doc.documentation("us-gaap:Revenues", "de"), None);
+
+        // Missing roles and languages fall back to the standard label
+        assert_eq!(
+            doc.label_for("us-gaap:Revenues", TERSE_LABEL_ROLE, "de"),
+            Some("Revenues")
+        );
+        assert_eq!(doc.label_for("us-gaap:Assets", LABEL_ROLE, "en"), None);
+    }
+
+    #[test]
+    fn test_dts_labels() {
+        // Labels reach the document through the schemaRef alone
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("ex.xsd"),
+            r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink" targetNamespace="http://example.com/ex">
+  <xs:annotation><xs:appinfo><link:linkbaseRef xlink:type="simple" xlink:href="ex_lab.xml"/></xs:appinfo></xs:annotation>
+  <xs:element id="ex_Revenue" name="Revenue" type="xbrli:monetaryItemType" substitutionGroup="xbrli:item" xbrli:periodType="duration"/>
+</xs:schema>"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("ex_lab.xml"),
+            r#"<link:linkbase xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink">
+  <link:labelLink xlink:type="extended" xlink:role="http://www.xbrl.org/2003/role/link">
+    <link:loc xlink:type="locator" xlink:href="ex.xsd#ex_Revenue" xlink:label="loc"/>
+    <link:label xlink:type="resource" xlink:label="lab" xlink:role="http://www.xbrl.org/2003/role/label" xml:lang="en-US">Revenue</link:label>
+    <link:label xlink:type="resource" xlink:label="lab" xlink:role="http://www.xbrl.org/2003/role/terseLabel" xml:lang="en-US">Sales</link:label>
+    <link:label xlink:type="resource" xlink:label="lab" xlink:role="http://www.xbrl.org/2003/role/label" xml:lang="de">Umsatz</link:label>
+    <link:labelArc xlink:type="arc" xlink:arcrole="http://www.xbrl.org/2003/arcrole/concept-label" xlink:from="loc" xlink:to="lab"/>
+  </link:labelLink>
+</link:linkbase>"#,
+        )
+        .unwrap();
+        let instance = dir.path().join("ex.xml");
+        std::fs::write(
+            &instance,
+            r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:ex="http://example.com/ex">
+  <link:schemaRef xlink:type="simple" xlink:href="ex.xsd"/>
+</xbrli:xbrl>"#,
+        )
+        .unwrap();
+
+        let doc = crate::Parser::new()
+            .with_schema_loading(true)
+            .with_linkbase_loading(true)
+            .parse_file(&instance)
+            .unwrap();
+        assert_eq!(
+            doc.label_for("ex:Revenue", LABEL_ROLE, "en"),
+            Some("Revenue")
+        );
+        assert_eq!(
+            doc.label_for("ex:Revenue", TERSE_LABEL_ROLE, "en-US"),
+            Some("Sales")
+        );
+        assert_eq!(
+            doc.label_for("ex:Revenue", LABEL_ROLE, "de"),
+            Some("Umsatz")
+        );
+        // No German terse label, so the German standard label
+        assert_eq!(
+            doc.label_for("ex:Revenue", TERSE_LABEL_ROLE, "de"),
+            Some("Umsatz")
+        );
+        assert_eq!(doc.label_for("ex:Assets", LABEL_ROLE, "en"), None);
+    }
+
+    #[test]
+    fn test_label_languages() {
+        let mut doc = Document::new();
+        let label = |role: &str, lang: &str, text: &str| LabelLink {
+            concept: "ifrs-full:Revenue".to_string(),
+            label: text.to_string(),
+            role: role.to_string(),
+            lang: lang.to_string(),
+        };
+        doc.label_links.extend([
+            label(LABEL_ROLE, "en-GB", "Revenue"),
+            label(LABEL_ROLE, "de", "Umsatzerlöse"),
+            label(TERSE_LABEL_ROLE, "en-GB", "Revenue, terse"),
+            label(
+                VERBOSE_LABEL_ROLE,
+                "fr",
+                "Produits des activités ordinaires",
+            ),
+            label(DOCUMENTATION_ROLE, "de", "Erlöse aus Verträgen mit Kunden"),
+        ]);
+        assert_eq!(doc.available_languages(), ["de", "en-GB", "fr"]);
+
+        let revenue = "ifrs-full:Revenue";
+        assert_eq!(
+            doc.get_label(revenue, LABEL_ROLE, &["de", "en"]),
+            Some("Umsatzerlöse")
+        );
+        // A role missing in the preferred language comes from the next one
+        assert_eq!(
+            doc.get_label(revenue, TERSE_LABEL_ROLE, &["de", "en"]),
+            Some("Revenue, terse")
+        );
+        assert_eq!(
+            doc.get_label(revenue, DOCUMENTATION_ROLE, &["en", "de"]),
+            Some("Erlöse aus Verträgen mit Kunden")
+        );
+        // Else the standard label in the preferred languages
+        assert_eq!(
+            doc.get_label(revenue, VERBOSE_LABEL_ROLE, &["en", "de"]),
+            Some("Revenue")
+        );
+        assert_eq!(doc.get_label(revenue, LABEL_ROLE, &["es"]), None);
+    }
+
+    #[test]
+    fn test_linkbase_prefixes() {
+        // Default namespace for the linkbase, another prefix for XLink, and
+        // attributes spread over lines in any order
+        let unprefixed = r#"<linkbase xmlns="http://www.xbrl.org/2003/linkbase" xmlns:xl="http://www.w3.org/1999/xlink">
+  <labelLink xl:role="http://www.xbrl.org/2003/role/link" xl:type="extended">
+    <loc xl:label="loc_Revenues" xl:type="locator"
+         xl:href="us-gaap.xsd#us-gaap_Revenues"/>
+    <label xml:lang="en" xl:role="http://www.xbrl.org/2003/role/label"
+           xl:type="resource" xl:label="lab">Revenues</label>
+    <labelArc xl:to="lab"
+              xl:from="loc_Revenues" xl:type="arc"/>
+  </labelLink>
+  <presentationLink xl:role="http://example.com/role/IS" xl:type="extended">
+    <loc xl:label="a" xl:href="us-gaap.xsd#us-gaap_IncomeStatementAbstract"/>
+    <loc xl:label="b" xl:href="us-gaap.xsd#us-gaap_Revenues"/>
+    <presentationArc order="2" xl:to="b" xl:from="a" xl:type="arc"/>
+  </presentationLink>
+</linkbase>"#;
+        let mut processor = LinkbaseProcessor::new();
+        processor.parse_linkbase(unprefixed.as_bytes()).unwrap();
+        assert_eq!(
+            processor.get_label("us-gaap:Revenues", LABEL_ROLE, &["en"]),
+            Some("Revenues")
+        );
+        let arcs = processor.presentation_links();
+        assert_eq!(arcs.len(), 1);
+        assert_eq!(
+            (arcs[0].from.as_str(), arcs[0].to.as_str(), arcs[0].order),
+            ("us-gaap:IncomeStatementAbstract", "us-gaap:Revenues", 2.0)
+        );
+
+        // Elements outside the linkbase namespace are not arcs
+        let foreign = unprefixed.replace(
+            "xmlns=\"http://www.xbrl.org/2003/linkbase\"",
+            "xmlns=\"http://example.com/other\"",
+        );
+        let mut processor = LinkbaseProcessor::new();
+        processor.parse_linkbase(foreign.as_bytes()).unwrap();
+        assert!(processor.presentation_links().is_empty());
+        assert!(processor.label_links().is_empty());
+    }
+
+    #[test]
+    fn test_locators_resolve_element_ids() {
+        let mut doc = Document::new();
+        doc.namespaces
+            .insert("ex".to_string(), "http://example.com/a".to_string());
+        doc.schemas.push(
+            crate::dts::parse_schema(
+                br#"<xsd:schema xmlns:xsd="http://www.w3.org/2001/XMLSchema" targetNamespace="http://example.com/a">
+  <xsd:element name="Revenues" id="rev" type="xbrli:monetaryItemType"/>
+</xsd:schema>"#,
+            )
+            .unwrap(),
+        );
+        let processor = LinkbaseProcessor::new().with_element_ids(doc.element_ids());
+        assert_eq!(
+            processor.resolve_href("a.xsd#rev").as_deref(),
+            Some("ex:Revenues")
+        );
+        assert_eq!(
+            processor.resolve_href("a.xsd#element(rev)").as_deref(),
+            Some("ex:Revenues")
+        );
+        // Ids not in a loaded schema keep the prefix_name convention
+        assert_eq!(
+            processor
+                .resolve_href("us-gaap.xsd#us-gaap_Assets")
+                .as_deref(),
+            Some("us-gaap:Assets")
+        );
+        assert_eq!(processor.resolve_href("a.xsd#unknown"), None);
+    }
+
+    #[test]
+    fn test_generic_links() {
+        let mut doc = Document::new();
+        doc.add_schema(
+            crate::dts::parse_schema(
+                br#"<xsd:schema xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:link="http://www.xbrl.org/2003/linkbase" targetNamespace="http://example.com/a">
+  <xsd:annotation><xsd:appinfo>
+    <link:roleType roleURI="http://example.com/role/BalanceSheet" id="BalanceSheet">
+      <link:definition>[210000] Statement of financial position</link:definition>
+      <link:usedOn>link:presentationLink</link:usedOn>
+      <link:usedOn>link:calculationLink</link:usedOn>
+    </link:roleType>
+    <link:roleType roleURI="http://example.com/role/Notes" id="Notes"/>
+  </xsd:appinfo></xsd:annotation>
+</xsd:schema>"#,
+            )
+            .unwrap(),
+        );
+        let role_type = &doc.schemas[0].role_types[0];
+        assert_eq!(role_type.used_on.len(), 2);
+        assert_eq!(
+            doc.role_label("http://example.com/role/BalanceSheet", "en"),
+            Some("[210000] Statement of financial position")
+        );
+
+        let linkbase = r#"<link:linkbase xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:gen="http://xbrl.org/2008/generic" xmlns:label="http://xbrl.org/2008/label" xmlns:reference="http://xbrl.org/2008/reference" xmlns:ref="http://www.xbrl.org/2006/ref">
+  <gen:link xlink:type="extended" xlink:role="http://www.xbrl.org/2003/role/link">
+    <link:loc xlink:type="locator" xlink:href="a.xsd#BalanceSheet" xlink:label="role"/>
+    <label:label xlink:type="resource" xlink:label="role_label" xlink:role="http://www.xbrl.org/2008/role/label" xml:lang="en">Statement of Financial Position</label:label>
+    <label:label xlink:type="resource" xlink:label="role_label" xml:lang="de">Bilanz</label:label>
+    <gen:arc xlink:type="arc" xlink:arcrole="http://xbrl.org/arcrole/2008/element-label" xlink:from="role" xlink:to="role_label"/>
+    <link:loc xlink:type="locator" xlink:href="a.xsd#ex_Cash" xlink:label="cash"/>
+    <label:label xlink:type="resource" xlink:label="cash_label" xlink:role="http://www.xbrl.org/2003/role/terseLabel" xml:lang="en">Cash</label:label>
+    <reference:reference xlink:type="resource" xlink:label="cash_ref"><ref:Name>IAS</ref:Name><ref:Number>7</ref:Number></reference:reference>
+    <gen:arc xlink:type="arc" xlink:arcrole="http://xbrl.org/arcrole/2008/element-label" xlink:from="cash" xlink:to="cash_label"/>
+    <gen:arc xlink:type="arc" xlink:arcrole="http://xbrl.org/arcrole/2008/element-reference" xlink:from="cash" xlink:to="cash_ref"/>
+  </gen:link>
+</link:linkbase>"#;
+        let mut processor = LinkbaseProcessor::new()
+            .with_element_ids(doc.element_ids())
+            .with_role_ids(doc.role_ids());
+        processor.parse_linkbase(linkbase.as_bytes()).unwrap();
+        assert!(processor.unsupported().is_empty());
+        assert_eq!(processor.role_labels().len(), 2);
+        processor.apply_to(&mut doc);
+
+        // Generic role labels win over the roleType definition
+        let role = "http://example.com/role/BalanceSheet";
+        assert_eq!(
+            doc.role_label(role, "en-GB"),
+            Some("Statement of Financial Position")
+        );
+        assert_eq!(doc.role_label(role, "de"), Some("Bilanz"));
+        assert_eq!(doc.role_label("http://example.com/role/Notes", "en"), None);
+
+        assert_eq!(doc.label("ex:Cash", TERSE_LABEL_ROLE, "en"), Some("Cash"));
+        let reference = &doc.reference_links[0];
+        assert_eq!(reference.concept, "ex:Cash");
+        assert_eq!(reference.reference.parts["Number"], "7");
+    }
+
+    #[test]
+    fn test_role_type_registry() {
+        let mut doc = Document::new();
+        let definition = |role: &str, arcrole: &str| DefinitionLink {
+            role: role.to_string(),
+            from: "a:X".to_string(),
+            to: "a:Y".to_string(),
+            arcrole: arcrole.to_string(),
+            order: 1.0,
+            priority: None,
+            use_attribute: None,
+        };
+        doc.definition_links.extend([
+            definition(STANDARD_LINK_ROLE, "http://example.com/arcrole/declared"),
+            definition(
+                "http://example.com/role/Notes",
+                "http://example.com/arcrole/other",
+            ),
+            definition(
+                "http://example.com/role/Undeclared",
+                "http://xbrl.org/int/dim/arcrole/all",
+            ),
+        ]);
+        // Nothing to check against without schemas
+        assert!(check_role_types(&doc).is_empty());
+
+        doc.add_schema(
+            crate::dts::parse_schema(
+                br#"<xsd:schema xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:link="http://www.xbrl.org/2003/linkbase" targetNamespace="http://example.com/a">
+  <xsd:annotation><xsd:appinfo>
+    <link:roleType roleURI="http://example.com/role/Notes" id="Notes">
+      <link:usedOn>link:presentationLink</link:usedOn>
+    </link:roleType>
+    <link:arcroleType arcroleURI="http://example.com/arcrole/declared" id="declared" cyclesAllowed="none">
+      <link:definition>Declared</link:definition>
+      <link:usedOn>link:definitionArc</link:usedOn>
+    </link:arcroleType>
+  </xsd:appinfo></xsd:annotation>
+</xsd:schema>"#,
+            )
+            .unwrap(),
+        );
+        assert_eq!(doc.role_types.len(), 1);
+        let arcrole = &doc.arcrole_types[0];
+        assert_eq!(arcrole.cycles_allowed, "none");
+        assert_eq!(arcrole.definition.as_deref(), Some("Declared"));
+        assert!(arcrole.used_on("definitionArc"));
+
+        let errors = check_role_types(&doc);
+        let messages: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Role http://example.com/role/Notes is used on link:definitionLink but its roleType does not list it in usedOn",
+                "Role http://example.com/role/Undeclared is used on link:definitionLink but no loaded schema declares it",
+                "Arcrole http://example.com/arcrole/other is used on link:definitionArc but no loaded schema declares it",
+            ]
+        );
+        assert_eq!(errors[0].rule(), "role-type");
+        assert_eq!(errors[2].code(), "xbrl.2.1.arcroleNotDeclared");
+    }
+
+    #[test]
+    fn test_role_of_unloaded_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("ex.xsd"),
+            r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink" targetNamespace="http://example.com/ex">
+  <xs:annotation><xs:appinfo>
+    <link:linkbaseRef xlink:type="simple" xlink:href="ex_pre.xml"/>
+    <link:roleType roleURI="http://example.com/role/Notes" id="Notes">
+      <link:usedOn>link:presentationLink</link:usedOn>
+    </link:roleType>
+  </xs:appinfo></xs:annotation>
+  <xs:element id="ex_Revenue" name="Revenue" type="xbrli:monetaryItemType" substitutionGroup="xbrli:item" xbrli:periodType="duration"/>
+  <xs:element id="ex_Cost" name="Cost" type="xbrli:monetaryItemType" substitutionGroup="xbrli:item" xbrli:periodType="duration"/>
+</xs:schema>"#,
+        )
+        .unwrap();
+        let link = |role: &str| {
+            format!(
+                r#"<link:presentationLink xlink:type="extended" xlink:role="{role}">
+    <link:loc xlink:type="locator" xlink:href="ex.xsd#ex_Revenue" xlink:label="rev"/>
+    <link:loc xlink:type="locator" xlink:href="ex.xsd#ex_Cost" xlink:label="cost"/>
+    <link:presentationArc xlink:type="arc" xlink:arcrole="http://www.xbrl.org/2003/arcrole/parent-child" xlink:from="rev" xlink:to="cost"/>
+  </link:presentationLink>"#
+            )
+        };
+        std::fs::write(
+            dir.path().join("ex_pre.xml"),
+            format!(
+                r#"<link:linkbase xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink">
+  <link:roleRef roleURI="http://example.com/role/Notes" xlink:type="simple" xlink:href="ex.xsd#Notes"/>
+  <link:roleRef roleURI="http://example.com/role/Remote" xlink:type="simple" xlink:href="https://example.com/remote.xsd#Remote"/>
+  {}
+  {}
+  {}
+</link:linkbase>"#,
+                link("http://example.com/role/Notes"),
+                link("http://example.com/role/Remote"),
+                link("http://example.com/role/Undeclared"),
+            ),
+        )
+        .unwrap();
+
+        let dts = crate::dts::DtsLoader::new()
+            .with_network_policy(crate::dts::NetworkPolicy::WarnAndSkip)
+            .discover(dir.path().join("ex.xsd").to_str().unwrap())
+            .unwrap();
+        assert_eq!(dts.skipped, ["https://example.com/remote.xsd"]);
+        let mut doc = Document::new();
+        doc.load_dts(&dts).unwrap();
+
+        // Remote is declared outside the loaded DTS; Undeclared nowhere
+        let errors = check_role_types(&doc);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(matches!(
+            &errors[0],
+            ValidationError::UndeclaredRole { role, .. } if role == "http://example.com/role/Undeclared"
+        ));
+        assert_eq!(
+            doc.unsupported,
+            [Unsupported::IncompleteDts(
+                "https://example.com/remote.xsd".to_string()
+            )]
+        );
+        assert_eq!(
+            doc.unloaded_role_schema("http://example.com/role/Notes"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_calculation_arcs() {
+        let mut processor = LinkbaseProcessor::new();
+        processor.parse_linkbase(CALCULATION.as_bytes()).unwrap();
+        let links = processor.calculation_links();
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[1].from, "us-gaap:GrossProfit");
+        assert_eq!(links[1].to, "us-gaap:CostOfRevenue");
+        assert_eq!(links[1].weight, -1.0);
+        assert_eq!(links[1].role, "http://example.com/role/IncomeStatement");
+        assert!(processor.unsupported().is_empty());
+    }
+
+    #[test]
+    fn test_unsupported_features() {
+        let linkbase = r#"<link:linkbase xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:gen="http://xbrl.org/2008/generic" xmlns:va="http://xbrl.org/2008/assertion/value">
+  <link:definitionLink xlink:type="extended" xlink:role="http://www.xbrl.org/2003/role/link">
+    <link:loc xlink:type="locator" xlink:href="ex.xsd#ex_A" xlink:label="a"/>
+    <link:loc xlink:type="locator" xlink:href="ex.xsd#ex_B" xlink:label="b"/>
+    <link:definitionArc xlink:type="arc" xlink:arcrole="http://example.com/arcrole/custom" xlink:from="a" xlink:to="b"/>
+    <link:definitionArc xlink:type="arc" xlink:arcrole="http://example.com/arcrole/custom" xlink:from="b" xlink:to="a"/>
+  </link:definitionLink>
+  <gen:link xlink:type="extended" xlink:role="http://www.xbrl.org/2003/role/link">
+    <va:valueAssertion xlink:type="resource" xlink:label="check" test="true()"/>
+  </gen:link>
+</link:linkbase>"#;
+        let mut processor = LinkbaseProcessor::new();
+        processor.parse_linkbase(linkbase.as_bytes()).unwrap();
+        assert_eq!(
+            processor.unsupported(),
+            [
+                Unsupported::Arcrole("http://example.com/arcrole/custom".to_string()),
+                Unsupported::Formula,
+            ]
+        );
 
-        errors
+        let mut doc = Document::new();
+        processor.apply_to(&mut doc);
+        assert_eq!(doc.unsupported.len(), 2);
     }
 }
@@ -3,9 +3,13 @@
 use anyhow::{Context, Result};
 use clap::{Parser as ClapParser, Subcommand};
 use colored::*;
-use std::path::PathBuf;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
+use crabrl::export::{self, ExportOptions};
+use crabrl::network::Relationship;
+use crabrl::report::{self, Report};
 use crabrl::{Parser, ValidationConfig, Validator};
 
 /// High-performance XBRL parser and validator
@@ -19,18 +23,52 @@ struct Cli {
 
 #[derive(Subcommand)]
 enum Commands {
-    /// Parse an XBRL file
+    /// Parse an XBRL instance, or list the concepts and arcs of a schema or
+    /// linkbase
     Parse {
         /// Input file
         input: PathBuf,
 
-        /// Output as JSON
+        /// Output as JSON, the same as `--format json`
         #[arg(short, long)]
         json: bool,
 
+        /// Output the facts as CSV, the same as `--format csv`
+        #[arg(long, conflicts_with = "json")]
+        csv: bool,
+
+        /// Output as JSON with the facts as an xBRL-JSON report
+        #[arg(long, conflicts_with_all = ["json", "csv"])]
+        oim: bool,
+
+        /// Linkbase files to load (labels, presentation, calculation); the
+        /// label options otherwise load the linkbases of the instance's DTS
+        #[arg(long)]
+        linkbase: Vec<PathBuf>,
+
+        /// Add concept documentation labels in this language to JSON/CSV output
+        #[arg(long, value_name = "LANG")]
+        documentation: Option<String>,
+
+        /// Export values as presented: one row per statement placement, with
+        /// preferred labels in this language and negated roles sign-flipped
+        #[arg(long, value_name = "LANG")]
+        preferred_labels: Option<String>,
+
+        /// List facts with the standard label of their concept in this
+        /// language (default en); JSON/CSV output gains a column with the
+        /// labels of each fact's axes and members
+        #[arg(long, value_name = "LANG", num_args = 0..=1, default_missing_value = "en")]
+        labels: Option<String>,
+
         /// Show statistics
         #[arg(short, long)]
         stats: bool,
+
+        /// Output format (text, json, csv, sarif, junit, html); JSON and CSV
+        /// include the facts
+        #[arg(short, long, default_value = "text")]
+        format: String,
     },
 
     /// Validate an XBRL file
@@ -45,6 +83,152 @@ enum Commands {
         /// Treat warnings as errors
         #[arg(long)]
         strict: bool,
+
+        /// Also report complete and consistent duplicate facts, as warnings
+        #[arg(long)]
+        redundant_duplicates: bool,
+
+        /// Also check reporting-period contexts against the DEI reporting
+        /// period; sec-edgar always does
+        #[arg(long)]
+        period_alignment: bool,
+
+        /// Output format (text, json, csv, sarif, junit, html)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Output as JSON, the same as `--format json`
+        #[arg(short, long)]
+        json: bool,
+
+        /// Write a JSON log of every check run, with timings and inputs
+        #[arg(long)]
+        audit: Option<PathBuf>,
+    },
+
+    /// Compare the facts of two filings
+    Diff {
+        /// Earlier filing
+        old: PathBuf,
+
+        /// Later filing
+        new: PathBuf,
+
+        /// Output as JSON, the same as `--format json`
+        #[arg(short, long)]
+        json: bool,
+
+        /// Output format (text, json, csv, sarif, junit, html); JSON includes
+        /// the added, removed and changed facts
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Convert an instance or Inline XBRL document to another format
+    Convert {
+        /// Input file
+        input: PathBuf,
+
+        /// Output format: ndjson, json, csv, xml (an XBRL 2.1 instance),
+        /// ixbrl-extract (the instance embedded in Inline XBRL), oim-json
+        /// (xBRL-JSON) or oim-csv (an xBRL-CSV package, into --output)
+        #[arg(long, default_value = "ndjson")]
+        to: String,
+
+        /// Write to this file, or directory for oim-csv, instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Indent JSON output
+        #[arg(long)]
+        pretty: bool,
+
+        /// Write facts while reading the input, keeping only contexts, units
+        /// and the element being read in memory (ndjson only)
+        #[arg(long)]
+        streaming: bool,
+
+        /// Compress the output (gz, zst); needs the `compress` feature
+        #[arg(long, value_name = "FORMAT")]
+        compress: Option<String>,
+    },
+
+    /// Print a statement's structure with fact values by period
+    Render {
+        /// Input file
+        input: PathBuf,
+
+        /// Presentation role URI; lists the available roles when omitted
+        #[arg(long)]
+        role: Option<String>,
+
+        /// Linkbase files to load (presentation, labels, generic labels)
+        #[arg(long)]
+        linkbase: Vec<PathBuf>,
+
+        /// Label language
+        #[arg(long, default_value = "en")]
+        lang: String,
+    },
+
+    /// Report which concepts of a disclosure checklist a filing tags
+    Coverage {
+        /// Input file
+        input: PathBuf,
+
+        /// Checklist file with one concept QName per line
+        #[arg(short, long)]
+        checklist: PathBuf,
+
+        /// Output as JSON, the same as `--format json`
+        #[arg(short, long)]
+        json: bool,
+
+        /// Output format (text, json, csv, sarif, junit, html)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Print the facts matching a concept, period and dimension members
+    Query {
+        /// Input file
+        input: PathBuf,
+
+        /// Concept QName or local name, e.g. us-gaap:Revenues
+        #[arg(short, long)]
+        concept: Option<String>,
+
+        /// Period: a year, month or day the period ends in (2023, 2023-12,
+        /// 2023-12-31), a duration 2023-01-01..2023-12-31, or forever
+        #[arg(short, long)]
+        period: Option<String>,
+
+        /// Dimension member the facts must carry, as Axis=Member; repeatable
+        #[arg(short, long, value_name = "AXIS=MEMBER")]
+        dimension: Vec<String>,
+
+        /// Output as JSON, the same as `--format json`
+        #[arg(short, long)]
+        json: bool,
+
+        /// Output format (text, json, csv, sarif, junit, html)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+
+    /// Extract a dataset defined by a concept mapping file, as CSV
+    Extract {
+        /// Input files
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+
+        /// Mapping file (TOML) listing the output fields
+        #[arg(short, long)]
+        mapping: PathBuf,
+
+        /// Compress the output (gz, zst); needs the `compress` feature
+        #[arg(long, value_name = "FORMAT")]
+        compress: Option<String>,
     },
 
     /// Benchmark parsing performance
@@ -56,6 +240,232 @@ enum Commands {
         #[arg(short, long, default_value = "100")]
         iterations: usize,
     },
+
+    /// Download the core taxonomy schemas for offline validation
+    #[cfg(feature = "http")]
+    Bundle {
+        /// Output directory (defaults to $CRABRL_TAXONOMY_DIR or the user cache)
+        output: Option<PathBuf>,
+
+        /// User-Agent sent to sec.gov, e.g. "Company admin@example.com"
+        #[arg(long)]
+        user_agent: Option<String>,
+    },
+
+    /// Manage the public filings used by benches and conformance runs
+    #[cfg(feature = "http")]
+    Fixtures {
+        #[command(subcommand)]
+        command: FixturesCommand,
+    },
+}
+
+#[cfg(feature = "http")]
+#[derive(Subcommand)]
+enum FixturesCommand {
+    /// Download filings up to a size tier and check their SHA-256
+    Pull {
+        /// Largest tier to pull (small, medium, huge); smaller tiers are
+        /// included. Only small and medium filings are built in; huge ones
+        /// are pulled only from --manifest.
+        #[arg(long, default_value = "small")]
+        tier: String,
+
+        /// Directory to download into
+        #[arg(long, default_value = "fixtures")]
+        dir: PathBuf,
+
+        /// JSON manifest of further filings; the only source of huge-tier
+        /// and ESEF filings, none of which are built in
+        #[arg(long)]
+        manifest: Option<PathBuf>,
+
+        /// User-Agent sent to sec.gov, e.g. "Company admin@example.com"
+        #[arg(long)]
+        user_agent: Option<String>,
+    },
+}
+
+type Sink = std::io::BufWriter<Box<dyn Write>>;
+#[cfg(feature = "compress")]
+type Output = crabrl::compress::CompressedWriter<Sink>;
+#[cfg(not(feature = "compress"))]
+type Output = Sink;
+
+/// Buffered stdout, or the file at `path`, compressed as `compress` names
+fn open_output(path: Option<&Path>, compress: Option<&str>) -> Result<Output> {
+    let sink: Box<dyn Write> = match path {
+        Some(path) => Box::new(
+            std::fs::File::create(path)
+                .with_context(|| format!("Failed to create {}", path.display()))?,
+        ),
+        None => Box::new(std::io::stdout()),
+    };
+    let sink = std::io::BufWriter::new(sink);
+    #[cfg(feature = "compress")]
+    {
+        let compression = match compress {
+            Some(name) => crabrl::compress::Compression::parse(name)
+                .with_context(|| format!("Unknown compression {}", name))?,
+            None => crabrl::compress::Compression::None,
+        };
+        Ok(compression.writer(sink)?)
+    }
+    #[cfg(not(feature = "compress"))]
+    {
+        anyhow::ensure!(
+            compress.is_none(),
+            "--compress requires crabrl built with the `compress` feature"
+        );
+        Ok(sink)
+    }
+}
+
+/// Print `report` in the named format
+fn print_report(report: &Report, format: &str) -> Result<()> {
+    use std::io::IsTerminal;
+    let formatter =
+        report::formatter(format, std::io::stdout().is_terminal()).with_context(|| {
+            format!(
+                "Unknown format {}; expected one of {}",
+                format,
+                report::FORMATS.join(", ")
+            )
+        })?;
+    print!("{}", formatter.format(report));
+    Ok(())
+}
+
+/// Columns of the diff table, see [`diff_row`]
+const DIFF_COLUMNS: [&str; 7] = [
+    "change", "concept", "period", "members", "old", "new", "delta",
+];
+
+/// Cells of a diff table row: change, concept, period, dimension members,
+/// old value, new value and the numeric delta
+fn diff_row(
+    change: &str,
+    old: Option<&crabrl::NormalizedFact>,
+    new: Option<&crabrl::NormalizedFact>,
+) -> [String; 7] {
+    let fact = new.or(old).expect("a diff row has a fact");
+    let period = match (&fact.period_start, &fact.period_end) {
+        (Some(start), Some(end)) => format!("{}..{}", start, end),
+        (None, Some(end)) => end.clone(),
+        _ => "forever".to_string(),
+    };
+    let members: Vec<String> = fact
+        .members
+        .iter()
+        .map(|(dimension, member)| format!("{}={}", dimension, member))
+        .collect();
+    let value = |fact: Option<&crabrl::NormalizedFact>| {
+        fact.map(|f| f.value.to_string()).unwrap_or_default()
+    };
+    let delta = match (old, new) {
+        (Some(old), Some(new)) => crabrl::diff::delta(old, new)
+            .map(|d| format!("{:+}", d))
+            .unwrap_or_default(),
+        _ => String::new(),
+    };
+    [
+        change.to_string(),
+        fact.concept.clone(),
+        period,
+        members.join(","),
+        value(old),
+        value(new),
+        delta,
+    ]
+}
+
+/// Concepts and arcs of a standalone schema or linkbase; `None` for
+/// instances
+fn taxonomy_report(source: &str, doc: &crabrl::Document) -> Option<Report> {
+    let arcs =
+        doc.presentation_links.len() + doc.calculation_links.len() + doc.definition_links.len();
+    let resources = doc.label_links.len() + doc.reference_links.len();
+    if !doc.facts.is_empty() || (doc.schemas.is_empty() && arcs + resources == 0) {
+        return None;
+    }
+
+    let mut report = Report::new("parse", source);
+    let mut concepts = 0;
+    for schema in &doc.schemas {
+        let mut elements: Vec<_> = schema.elements.values().collect();
+        elements.sort_by(|a, b| a.name.cmp(&b.name));
+        concepts += elements.len();
+        for element in elements {
+            let mut line = format!("{}  {}", element.name, element.element_type);
+            for detail in [&element.period_type, &element.balance]
+                .into_iter()
+                .flatten()
+            {
+                line.push_str(&format!("  {}", detail));
+            }
+            if element.abstract_element {
+                line.push_str("  abstract");
+            }
+            report = report.with_line(line);
+        }
+    }
+    // Prohibiting arcs are listed as read, marked, not resolved away
+    let prohibited = |arc: &dyn Relationship| if arc.prohibited() { "  prohibited" } else { "" };
+    for link in &doc.presentation_links {
+        report = report.with_line(format!(
+            "presentation  {}  {} -> {}{}",
+            link.role,
+            link.from,
+            link.to,
+            prohibited(link)
+        ));
+    }
+    for link in &doc.calculation_links {
+        report = report.with_line(format!(
+            "calculation  {}  {} -> {} ({:+}){}",
+            link.role,
+            link.from,
+            link.to,
+            link.weight,
+            prohibited(link)
+        ));
+    }
+    for link in &doc.definition_links {
+        report = report.with_line(format!(
+            "definition  {}  {} -> {} ({}){}",
+            link.role,
+            link.from,
+            link.to,
+            link.arcrole,
+            prohibited(link)
+        ));
+    }
+    for label in &doc.label_links {
+        report = report.with_line(format!(
+            "label  {}  {} [{}]  {}",
+            label.concept, label.role, label.lang, label.label
+        ));
+    }
+    if !doc.schemas.is_empty() {
+        report = report.with_summary("Concepts", concepts);
+    }
+    if arcs + resources > 0 {
+        report = report
+            .with_summary("Arcs", arcs)
+            .with_summary("Labels", doc.label_links.len())
+            .with_summary("References", doc.reference_links.len());
+    }
+    Some(report)
+}
+
+/// Complete a compressed stream and flush stdout
+fn finish_output(out: Output) -> Result<()> {
+    #[cfg(feature = "compress")]
+    let mut out = out.finish()?;
+    #[cfg(not(feature = "compress"))]
+    let mut out = out;
+    out.flush()?;
+    Ok(())
 }
 
 fn main() -> Result<()> {
@@ -64,81 +474,476 @@ fn main() -> Result<()> {
     match cli.command {
         Commands::Parse {
             input,
-            json: _,
+            json,
+            csv,
+            oim,
+            linkbase,
+            documentation,
+            preferred_labels,
+            labels,
             stats,
+            format,
         } => {
-            let start = Instant::now();
-            let parser = Parser::new();
-            let doc = parser
-                .parse_file(&input)
+            // Without explicit linkbases, labels come from the DTS
+            let load_labels = linkbase.is_empty()
+                && (labels.is_some() || documentation.is_some() || preferred_labels.is_some());
+            let parser = Parser::new()
+                .with_schema_loading(load_labels)
+                .with_linkbase_loading(load_labels);
+            let (mut doc, parse_stats) = parser
+                .parse_file_with_stats(&input)
                 .with_context(|| format!("Failed to parse {}", input.display()))?;
-            let elapsed = start.elapsed();
+            let elapsed = parse_stats.total;
+
+            for path in &linkbase {
+                doc.load_linkbase(path)
+                    .with_context(|| format!("Failed to load linkbase {}", path.display()))?;
+            }
 
-            println!("{} {}", "✓".green().bold(), input.display());
-            println!("  Facts: {}", doc.facts.len());
-            println!("  Contexts: {}", doc.contexts.len());
-            println!("  Units: {}", doc.units.len());
+            let format = match (json || oim, csv) {
+                (true, _) => "json",
+                (_, true) => "csv",
+                _ => format.as_str(),
+            };
+            if let Some(report) = taxonomy_report(&input.display().to_string(), &doc) {
+                print_report(&report.with_duration(elapsed.as_millis() as u64), format)?;
+                return Ok(());
+            }
+
+            let mut options = ExportOptions::new();
+            if let Some(lang) = &documentation {
+                options = options.with_documentation(lang);
+            }
+            if let Some(lang) = &preferred_labels {
+                options = options.with_preferred_labels(lang);
+            }
+            if let Some(lang) = &labels {
+                options = options.with_dimension_labels(lang);
+            }
+
+            let mut report = Report::new("parse", &input.display().to_string())
+                .with_summary("Facts", doc.facts.len())
+                .with_summary("Contexts", doc.contexts.len())
+                .with_summary("Units", doc.units.len())
+                .with_duration(elapsed.as_millis() as u64);
+            // The facts themselves only go to the formats that carry data
+            match format {
+                "json" if oim => report = report.with_data(crabrl::oim::json::to_json(&doc)),
+                "json" => report = report.with_data(export::to_json(&doc, &options)),
+                "csv" => {
+                    let (columns, rows) = export::to_rows(&doc, &options);
+                    report = report.with_table(&columns, rows);
+                }
+                _ => {}
+            }
+
+            if let Some(lang) = &labels {
+                for fact in doc.iter_facts() {
+                    let label = doc
+                        .label_for(fact.concept(), crabrl::linkbase::LABEL_ROLE, lang)
+                        .unwrap_or("");
+                    report = report.with_line(format!(
+                        "{}  {}  {}",
+                        fact.concept(),
+                        label,
+                        fact.value()
+                    ));
+                }
+            }
 
             if stats {
-                println!("  Time: {:.2}ms", elapsed.as_secs_f64() * 1000.0);
-                println!(
-                    "  Throughput: {:.0} facts/sec",
-                    doc.facts.len() as f64 / elapsed.as_secs_f64()
-                );
+                let ms = |d: std::time::Duration| format!("{:.2}ms", d.as_secs_f64() * 1000.0);
+                report = report
+                    .with_summary("Time", ms(elapsed))
+                    .with_summary(
+                        "Phases",
+                        format!(
+                            "read {}, decode {}, scan {}, resolve {}",
+                            ms(parse_stats.read),
+                            ms(parse_stats.decode),
+                            ms(parse_stats.scan),
+                            ms(parse_stats.resolve)
+                        ),
+                    )
+                    .with_summary("Bytes", parse_stats.bytes)
+                    .with_summary("Elements", parse_stats.elements)
+                    .with_summary("Arena", format!("{} bytes", parse_stats.arena_bytes))
+                    .with_summary(
+                        "Throughput",
+                        format!(
+                            "{:.0} facts/sec, {:.1} MB/s",
+                            parse_stats.facts_per_sec(),
+                            parse_stats.bytes_per_sec() / 1e6
+                        ),
+                    );
+                for concept in doc.concept_stats() {
+                    let mut line = format!(
+                        "{}  {} facts in {} contexts",
+                        concept.concept, concept.facts, concept.contexts
+                    );
+                    if concept.nil > 0 {
+                        line.push_str(&format!(", {} nil", concept.nil));
+                    }
+                    if let (Some(min), Some(max)) = (concept.min, concept.max) {
+                        line.push_str(&format!(", min {} max {}", min, max));
+                    }
+                    if let Some(mean) = concept.mean {
+                        line.push_str(&format!(" mean {}", mean.round_dp(2)));
+                    }
+                    report = report.with_line(line);
+                }
             }
+            print_report(&report, format)?;
         }
 
         Commands::Validate {
             input,
             profile,
             strict,
+            redundant_duplicates,
+            period_alignment,
+            format,
+            json,
+            audit,
         } => {
             let parser = Parser::new();
             let doc = parser
                 .parse_file(&input)
                 .with_context(|| format!("Failed to parse {}", input.display()))?;
 
-            let config = match profile.as_str() {
+            let base = match profile.as_str() {
                 "sec-edgar" => ValidationConfig::sec_edgar(),
                 _ => ValidationConfig::default(),
             };
+            let config = ValidationConfig {
+                redundant_duplicates,
+                period_alignment: period_alignment || base.period_alignment,
+                ..base
+            };
 
-            let validator = Validator::with_config(config);
+            let mut validator = Validator::with_config(config);
+            if audit.is_some() {
+                validator = validator.with_audit();
+            }
             let result = validator.validate(&doc)?;
+            if let (Some(path), Some(log)) = (&audit, &result.audit) {
+                let mut file = std::fs::File::create(path)
+                    .with_context(|| format!("Failed to create {}", path.display()))?;
+                log.write_json(&mut file)?;
+            }
 
-            if result.is_valid {
-                println!(
-                    "{} {} - Document is valid",
-                    "✓".green().bold(),
-                    input.display()
-                );
-            } else {
-                println!(
-                    "{} {} - Validation failed",
-                    "✗".red().bold(),
-                    input.display()
-                );
-                println!("  Errors: {}", result.errors.len());
-                println!("  Warnings: {}", result.warnings.len());
+            let source = input.display().to_string();
+            let format = if json { "json" } else { format.as_str() };
+            print_report(&Report::validation(&source, &result), format)?;
+            if !result.is_valid || (strict && !result.warnings.is_empty()) {
+                std::process::exit(1);
+            }
+        }
 
-                for error in result.errors.iter().take(5) {
-                    println!("  {} {}", "ERROR:".red(), error);
-                }
+        Commands::Diff {
+            old,
+            new,
+            json,
+            format,
+        } => {
+            let parser = Parser::new();
+            let start = Instant::now();
+            let [old_doc, new_doc] = [&old, &new].map(|path| {
+                parser
+                    .parse_file(path)
+                    .with_context(|| format!("Failed to parse {}", path.display()))
+            });
+            let diff = crabrl::diff::diff_documents(&old_doc?, &new_doc?);
 
-                if result.errors.len() > 5 {
-                    println!("  ... and {} more errors", result.errors.len() - 5);
+            let mut report =
+                Report::new("diff", &format!("{} -> {}", old.display(), new.display()))
+                    .with_status(
+                        diff.is_empty(),
+                        &format!(
+                            "{} added, {} removed, {} changed",
+                            diff.added.len(),
+                            diff.removed.len(),
+                            diff.changed.len()
+                        ),
+                    )
+                    .with_summary("Added", diff.added.len())
+                    .with_summary("Removed", diff.removed.len())
+                    .with_summary("Changed", diff.changed.len())
+                    .with_summary("Unchanged", diff.unchanged)
+                    .with_duration(start.elapsed().as_millis() as u64)
+                    .with_data(diff.to_json());
+            let rows = diff
+                .added
+                .iter()
+                .map(|fact| diff_row("+", None, Some(fact)))
+                .chain(
+                    diff.removed
+                        .iter()
+                        .map(|fact| diff_row("-", Some(fact), None)),
+                )
+                .chain(
+                    diff.changed
+                        .iter()
+                        .map(|(before, after)| diff_row("~", Some(before), Some(after))),
+                )
+                .map(Vec::from)
+                .collect();
+            report = report.with_table(&DIFF_COLUMNS, rows);
+            let format = if json { "json" } else { format.as_str() };
+            print_report(&report, format)?;
+            if !diff.is_empty() {
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Convert {
+            input,
+            to,
+            output,
+            pretty,
+            streaming,
+            compress,
+        } => {
+            let parser = Parser::new();
+            if streaming {
+                anyhow::ensure!(to == "ndjson", "--streaming only supports --to ndjson");
+                let mut out = open_output(output.as_deref(), compress.as_deref())?;
+                let mut stream = export::NdjsonStream::new(&mut out);
+                parser
+                    .parse_streaming(&input, &mut stream)
+                    .with_context(|| format!("Failed to parse {}", input.display()))?;
+                if stream.unresolved > 0 {
+                    eprintln!(
+                        "{} {} of {} facts precede their context or unit and were written unresolved",
+                        "warning:".yellow(),
+                        stream.unresolved,
+                        stream.facts
+                    );
                 }
+                finish_output(out)?;
+                return Ok(());
+            }
+
+            // Inline XBRL is read for its embedded instance
+            let mut head = Vec::new();
+            std::fs::File::open(&input)
+                .and_then(|file| file.take(64 * 1024).read_to_end(&mut head))
+                .with_context(|| format!("Failed to read {}", input.display()))?;
+            let inline = crabrl::ixbrl::is_inline_xbrl(&head);
+            anyhow::ensure!(
+                inline || to != "ixbrl-extract",
+                "{} is not an Inline XBRL document",
+                input.display()
+            );
+            let doc = match inline {
+                true => crabrl::InlineParser::new().parse_file(&input),
+                false => parser.parse_file(&input),
+            }
+            .with_context(|| format!("Failed to parse {}", input.display()))?;
 
-                if strict && !result.warnings.is_empty() {
-                    std::process::exit(1);
+            if to == "oim-csv" {
+                let dir = output.context("--to oim-csv needs an --output directory")?;
+                anyhow::ensure!(compress.is_none(), "--to oim-csv cannot be compressed");
+                crabrl::oim::csv::write_package(&doc, &dir)
+                    .with_context(|| format!("Failed to write {}", dir.display()))?;
+                return Ok(());
+            }
+            let mut out = open_output(output.as_deref(), compress.as_deref())?;
+            let options = ExportOptions::new();
+            let mut write_json = |json: serde_json::Value| -> Result<()> {
+                match pretty {
+                    true => serde_json::to_writer_pretty(&mut out, &json)?,
+                    false => serde_json::to_writer(&mut out, &json)?,
                 }
+                writeln!(out)?;
+                Ok(())
+            };
+            match to.as_str() {
+                "json" => write_json(export::to_json(&doc, &options))?,
+                "oim-json" => write_json(crabrl::oim::json::to_json(&doc))?,
+                "ndjson" => export::write_ndjson(&doc, &mut out, &options)?,
+                "csv" => export::write_csv(&doc, &mut out, &options)?,
+                "xml" | "ixbrl-extract" => crabrl::writer::InstanceWriter::new().write(&doc, &mut out)?,
+                other => anyhow::bail!(
+                    "Unknown output format {}; expected ndjson, json, csv, xml, ixbrl-extract, oim-json or oim-csv",
+                    other
+                ),
+            }
+            finish_output(out)?;
+        }
 
-                if !result.is_valid {
-                    std::process::exit(1);
+        Commands::Render {
+            input,
+            role,
+            linkbase,
+            lang,
+        } => {
+            // The DTS schemas declare the roles that generic labels title
+            let mut doc = Parser::new()
+                .with_schema_loading(true)
+                .parse_file(&input)
+                .with_context(|| format!("Failed to parse {}", input.display()))?;
+            for path in &linkbase {
+                doc.load_linkbase(path)
+                    .with_context(|| format!("Failed to load linkbase {}", path.display()))?;
+            }
+            match role {
+                Some(role) => print!(
+                    "{}",
+                    crabrl::presentation::render_presentation(&doc, &role, &lang)
+                ),
+                None => {
+                    for role in doc.presentation_roles() {
+                        match doc.role_label(role, &lang) {
+                            Some(title) => println!("{}  {}", role, title),
+                            None => println!("{}", role),
+                        }
+                    }
                 }
             }
         }
 
+        Commands::Coverage {
+            input,
+            checklist,
+            json,
+            format,
+        } => {
+            let checklist = crabrl::coverage::Checklist::load(&checklist)
+                .with_context(|| format!("Failed to load checklist {}", checklist.display()))?;
+            let doc = Parser::new()
+                .parse_file(&input)
+                .with_context(|| format!("Failed to parse {}", input.display()))?;
+            let coverage = crabrl::coverage::check_coverage(&doc, &checklist);
+
+            let total = coverage.tagged.len() + coverage.missing.len();
+            let rows = coverage
+                .tagged
+                .iter()
+                .map(|c| {
+                    vec![
+                        "tagged".to_string(),
+                        c.concept.clone(),
+                        c.contexts.join(", "),
+                    ]
+                })
+                .chain(
+                    coverage
+                        .missing
+                        .iter()
+                        .map(|c| vec!["missing".to_string(), c.to_string(), String::new()]),
+                )
+                .collect();
+            let report = Report::new("coverage", &input.display().to_string())
+                .with_status(
+                    coverage.missing.is_empty(),
+                    &format!(
+                        "{}/{} concepts tagged ({:.0}%)",
+                        coverage.tagged.len(),
+                        total,
+                        coverage.ratio() * 100.0
+                    ),
+                )
+                .with_summary("Tagged", coverage.tagged.len())
+                .with_summary("Missing", coverage.missing.len())
+                .with_table(&["status", "concept", "contexts"], rows)
+                .with_data(coverage.to_json());
+            let format = if json { "json" } else { format.as_str() };
+            print_report(&report, format)?;
+            if !coverage.missing.is_empty() {
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Query {
+            input,
+            concept,
+            period,
+            dimension,
+            json,
+            format,
+        } => {
+            use crabrl::query::{FactQuery, PeriodFilter};
+            let mut query = FactQuery::new();
+            if let Some(concept) = concept {
+                query = query.with_concept(concept);
+            }
+            if let Some(period) = period {
+                let filter = PeriodFilter::parse(&period).with_context(|| {
+                    format!(
+                        "Invalid period {}; expected e.g. 2023, 2023-12-31 or 2023-01-01..2023-12-31",
+                        period
+                    )
+                })?;
+                query = query.with_period(filter);
+            }
+            for spec in &dimension {
+                let (axis, member) = spec
+                    .split_once('=')
+                    .with_context(|| format!("Invalid dimension {}; expected Axis=Member", spec))?;
+                query = query.with_dimension(axis.trim(), member.trim());
+            }
+
+            let doc = Parser::new()
+                .parse_file(&input)
+                .with_context(|| format!("Failed to parse {}", input.display()))?;
+            let facts: Vec<_> = query.select(&doc).collect();
+
+            let rows = facts
+                .iter()
+                .map(|fact| {
+                    let mut value = fact.value().to_string();
+                    if fact.value().is_nil() {
+                        value = "(nil)".to_string();
+                    } else if value.chars().count() > 40 {
+                        value = format!("{}…", value.chars().take(39).collect::<String>());
+                    }
+                    vec![
+                        fact.concept().to_string(),
+                        value,
+                        fact.unit().map(|u| u.canonical()).unwrap_or_default(),
+                        fact.context().map(|c| c.id.clone()).unwrap_or_default(),
+                        fact.decimals().map(|d| d.to_string()).unwrap_or_default(),
+                    ]
+                })
+                .collect();
+            let report = Report::new("query", &input.display().to_string())
+                .with_status(
+                    !facts.is_empty(),
+                    match facts.len() {
+                        0 => "No matching facts",
+                        _ => "",
+                    },
+                )
+                .with_summary("Facts", facts.len())
+                .with_table(&["Concept", "Value", "Unit", "Context", "Decimals"], rows)
+                .with_data(crabrl::query::to_json(&facts));
+            let format = if json { "json" } else { format.as_str() };
+            print_report(&report, format)?;
+        }
+
+        Commands::Extract {
+            inputs,
+            mapping,
+            compress,
+        } => {
+            let mapping = crabrl::mapping::Mapping::load(&mapping)
+                .with_context(|| format!("Failed to load mapping {}", mapping.display()))?;
+            let parser = Parser::new();
+            let mut dataset = crabrl::mapping::Dataset::default();
+            for input in &inputs {
+                let doc = parser
+                    .parse_file(input)
+                    .with_context(|| format!("Failed to parse {}", input.display()))?;
+                let extracted = mapping.extract(&doc);
+                dataset.fields = extracted.fields.clone();
+                dataset.extend(extracted);
+            }
+            let mut out = open_output(None, compress.as_deref())?;
+            dataset.write_csv(&mut out)?;
+            finish_output(out)?;
+        }
+
         Commands::Bench { input, iterations } => {
             let parser = Parser::new();
 
@@ -175,6 +980,69 @@ fn main() -> Result<()> {
                 doc_facts as f64 / mean.as_secs_f64()
             );
         }
+
+        #[cfg(feature = "http")]
+        Commands::Bundle { output, user_agent } => {
+            let dir = output
+                .or_else(crabrl::catalog::Catalog::default_dir)
+                .context("No output directory and no cache directory available")?;
+            let mut config = crabrl::http::HttpConfig::new();
+            if let Some(user_agent) = user_agent {
+                config = config.with_user_agent(&user_agent);
+            }
+            let client = crabrl::http::HttpClient::new(config);
+            crabrl::catalog::build_bundle(&client, &dir)
+                .with_context(|| format!("Failed to build bundle in {}", dir.display()))?;
+            println!(
+                "{} {} schemas in {}",
+                "✓".green().bold(),
+                crabrl::catalog::CORE_SCHEMAS.len(),
+                dir.display()
+            );
+        }
+
+        #[cfg(feature = "http")]
+        Commands::Fixtures {
+            command:
+                FixturesCommand::Pull {
+                    tier,
+                    dir,
+                    manifest,
+                    user_agent,
+                },
+        } => {
+            use crabrl::fixtures::{PullStatus, Tier};
+            let tier = Tier::parse(&tier).with_context(|| {
+                format!("Unknown tier {}; expected small, medium or huge", tier)
+            })?;
+            let mut fixtures = crabrl::fixtures::builtin();
+            if let Some(manifest) = manifest {
+                fixtures.extend(
+                    crabrl::fixtures::load_manifest(&manifest)
+                        .with_context(|| format!("Failed to read {}", manifest.display()))?,
+                );
+            }
+            fixtures.retain(|f| f.tier <= tier);
+
+            let mut config = crabrl::http::HttpConfig::new();
+            if let Some(user_agent) = user_agent {
+                config = config.with_user_agent(&user_agent);
+            }
+            let client = crabrl::http::HttpClient::new(config);
+            let pulled = crabrl::fixtures::pull(&client, &dir, &fixtures)
+                .with_context(|| format!("Failed to pull fixtures into {}", dir.display()))?;
+            let downloaded = pulled
+                .iter()
+                .filter(|(_, status)| *status == PullStatus::Downloaded)
+                .count();
+            println!(
+                "{} {} fixtures in {} ({} downloaded)",
+                "✓".green().bold(),
+                pulled.len(),
+                dir.display(),
+                downloaded
+            );
+        }
     }
 
     Ok(())
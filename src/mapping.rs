@@ -0,0 +1,265 @@
+//! Declarative concept mappings for dataset extraction
+//!
+//! A mapping file names the output fields and, for each, the concepts that
+//! may report it in order of preference:
+//!
+//! ```toml
+//! [[field]]
+//! name = "revenue"
+//! concepts = ["us-gaap:Revenues", "us-gaap:SalesRevenueNet"]
+//! period = "duration"
+//!
+//! [[field]]
+//! name = "iphone_revenue"
+//! concepts = ["us-gaap:RevenueFromContractWithCustomerExcludingAssessedTax"]
+//! dimensions = { "srt:ProductOrServiceAxis" = "aapl:IPhoneMember" }
+//! ```
+//!
+//! Extraction yields one row per entity and period. A field takes the value
+//! of the first listed concept that has a fact for the row; facts must carry
+//! exactly the listed dimension members, so a field without `dimensions`
+//! only takes entity-wide totals.
+
+use crate::export::csv_field;
+use crate::model::{Document, FactValue, Period};
+use crate::{Error, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Mapping {
+    #[serde(rename = "field")]
+    pub fields: Vec<FieldMapping>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldMapping {
+    pub name: String,
+    /// Concept QNames, most preferred first
+    pub concepts: Vec<String>,
+    /// Dimension QName to member QName (or typed member value)
+    #[serde(default)]
+    pub dimensions: BTreeMap<String, String>,
+    #[serde(default)]
+    pub period: Option<PeriodKind>,
+    /// Canonical unit, e.g. `iso4217:USD`
+    #[serde(default)]
+    pub unit: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PeriodKind {
+    Instant,
+    Duration,
+}
+
+impl FieldMapping {
+    /// Preference rank of a fact for this field, `None` if it does not match
+    fn rank(
+        &self,
+        concept: &str,
+        period: &Period,
+        unit: Option<&str>,
+        members: &[(&str, &str)],
+    ) -> Option<usize> {
+        let kind_matches = matches!(
+            (self.period, period),
+            (None, _)
+                | (Some(PeriodKind::Instant), Period::Instant { .. })
+                | (Some(PeriodKind::Duration), Period::Duration { .. })
+        );
+        let unit_matches = self.unit.as_deref().is_none_or(|u| Some(u) == unit);
+        let members_match = members.len() == self.dimensions.len()
+            && members
+                .iter()
+                .all(|(dim, member)| self.dimensions.get(*dim).is_some_and(|m| m == member));
+        if !(kind_matches && unit_matches && members_match) {
+            return None;
+        }
+        self.concepts.iter().position(|c| c == concept)
+    }
+}
+
+/// Extracted values, one row per entity and period
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dataset {
+    /// Field names, in mapping order
+    pub fields: Vec<String>,
+    pub rows: Vec<DatasetRow>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetRow {
+    pub entity: String,
+    pub period_start: Option<String>,
+    pub period_end: Option<String>,
+    /// Parallel to [`Dataset::fields`]
+    pub values: Vec<Option<FactValue>>,
+}
+
+impl Mapping {
+    pub fn from_toml(text: &str) -> Result<Self> {
+        let mapping: Mapping =
+            toml::from_str(text).map_err(|e| Error::Parse(format!("Invalid mapping: {}", e)))?;
+        if let Some(field) = mapping.fields.iter().find(|f| f.concepts.is_empty()) {
+            return Err(Error::Parse(format!(
+                "Invalid mapping: field {} lists no concepts",
+                field.name
+            )));
+        }
+        Ok(mapping)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_toml(&std::fs::read_to_string(path)?)
+    }
+
+    /// Materialize the mapped fields of `doc`
+    pub fn extract(&self, doc: &Document) -> Dataset {
+        type RowKey = (String, Option<String>, Option<String>);
+        // Best (rank, value) per row and field
+        let mut rows: BTreeMap<RowKey, Vec<Option<(usize, FactValue)>>> = BTreeMap::new();
+
+        for fact in doc.iter_facts() {
+            let Some(context) = fact.context() else {
+                continue;
+            };
+            let unit = fact.unit().map(|u| u.canonical());
+            let members = context.dimensions();
+            for (field, mapping) in self.fields.iter().enumerate() {
+                let Some(rank) =
+                    mapping.rank(fact.concept(), &context.period, unit.as_deref(), &members)
+                else {
+                    continue;
+                };
+                let (start, end) = match &context.period {
+                    Period::Instant { date, .. } => (None, Some(date.clone())),
+                    Period::Duration { start, end, .. } => (Some(start.clone()), Some(end.clone())),
+                    Period::Forever => (None, None),
+                };
+                let key = (context.entity.identifier.clone(), start, end);
+                let cells = rows
+                    .entry(key)
+                    .or_insert_with(|| vec![None; self.fields.len()]);
+                if cells[field].as_ref().is_none_or(|(best, _)| rank < *best) {
+                    cells[field] = Some((rank, fact.value().clone()));
+                }
+            }
+        }
+
+        Dataset {
+            fields: self.fields.iter().map(|f| f.name.clone()).collect(),
+            rows: rows
+                .into_iter()
+                .map(|((entity, period_start, period_end), cells)| DatasetRow {
+                    entity,
+                    period_start,
+                    period_end,
+                    values: cells.into_iter().map(|c| c.map(|(_, v)| v)).collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Dataset {
+    /// Append the rows of another dataset with the same fields
+    pub fn extend(&mut self, other: Dataset) {
+        self.rows.extend(other.rows);
+    }
+
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let header: Vec<String> = ["entity", "period_start", "period_end"]
+            .iter()
+            .map(|c| c.to_string())
+            .chain(self.fields.iter().map(|f| csv_field(f)))
+            .collect();
+        writeln!(writer, "{}", header.join(","))?;
+        for row in &self.rows {
+            let cells: Vec<String> = [
+                Some(row.entity.clone()),
+                row.period_start.clone(),
+                row.period_end.clone(),
+            ]
+            .into_iter()
+            .chain(row.values.iter().map(|v| v.as_ref().map(|v| v.to_string())))
+            .map(|cell| cell.as_deref().map(csv_field).unwrap_or_default())
+            .collect();
+            writeln!(writer, "{}", cells.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    #[test]
+    fn test_mapping_fallbacks_and_dimensions() {
+        let mapping = Mapping::from_toml(
+            r#"
+[[field]]
+name = "revenue"
+concepts = ["us-gaap:Revenues", "us-gaap:SalesRevenueNet"]
+period = "duration"
+
+[[field]]
+name = "services"
+concepts = ["us-gaap:Revenues"]
+dimensions = { "srt:ProductOrServiceAxis" = "us-gaap:ServiceMember" }
+
+[[field]]
+name = "cash"
+concepts = ["us-gaap:Cash"]
+unit = "iso4217:USD"
+"#,
+        )
+        .unwrap();
+
+        let doc = Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:xbrldi="http://xbrl.org/2006/xbrldi"
+    xmlns:us-gaap="http://fasb.org/us-gaap/2023" xmlns:srt="http://fasb.org/srt/2023">
+  <xbrli:context id="FY22"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2022-01-01</xbrli:startDate><xbrli:endDate>2022-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:context id="FY23"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2023-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:context id="FY23Services"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">1</xbrli:identifier>
+    <xbrli:segment><xbrldi:explicitMember dimension="srt:ProductOrServiceAxis">us-gaap:ServiceMember</xbrldi:explicitMember></xbrli:segment></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2023-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:context id="End23"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <us-gaap:SalesRevenueNet contextRef="FY22" unitRef="USD" decimals="0">90</us-gaap:SalesRevenueNet>
+  <us-gaap:SalesRevenueNet contextRef="FY23" unitRef="USD" decimals="0">99</us-gaap:SalesRevenueNet>
+  <us-gaap:Revenues contextRef="FY23" unitRef="USD" decimals="0">100</us-gaap:Revenues>
+  <us-gaap:Revenues contextRef="FY23Services" unitRef="USD" decimals="0">40</us-gaap:Revenues>
+  <us-gaap:Cash contextRef="End23" unitRef="USD" decimals="0">7</us-gaap:Cash>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+
+        let mut csv = Vec::new();
+        mapping.extract(&doc).write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "entity,period_start,period_end,revenue,services,cash\n\
+             1,,2023-12-31,,,7\n\
+             1,2022-01-01,2022-12-31,90,,\n\
+             1,2023-01-01,2023-12-31,100,40,\n"
+        );
+    }
+
+    #[test]
+    fn test_mapping_rejects_unknown_keys() {
+        assert!(Mapping::from_toml("[[field]]\nname = \"x\"\nconcept = [\"a:B\"]").is_err());
+        assert!(Mapping::from_toml("[[field]]\nname = \"x\"\nconcepts = []").is_err());
+    }
+}
@@ -1,32 +1,193 @@
-use std::collections::HashMap;
+use crate::linkbase::label_semantics;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use rust_decimal::prelude::ToPrimitive;
+pub use rust_decimal::Decimal;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 
 // ============================================================================
 // Core XBRL Data Structures - Full Specification Support
 // ============================================================================
 
+/// Copy-on-write storage shared between clones. Cloning only bumps a
+/// reference count; the first mutation through a clone whose data is still
+/// shared copies it. Reads and writes go through `Deref` and `DerefMut`, so
+/// a `Shared<Vec<T>>` is used like the `Vec` itself.
+#[derive(Default)]
+pub struct Shared<T>(Arc<T>);
+
+impl<T> Shared<T> {
+    pub fn new(value: T) -> Self {
+        Self(Arc::new(value))
+    }
+
+    /// Whether both hold the same data, as clones do until one is mutated
+    pub fn ptr_eq(this: &Self, other: &Self) -> bool {
+        Arc::ptr_eq(&this.0, &other.0)
+    }
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T> Deref for Shared<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Clone> DerefMut for Shared<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        Arc::make_mut(&mut self.0)
+    }
+}
+
+impl<T> From<T> for Shared<T> {
+    fn from(value: T) -> Self {
+        Self::new(value)
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Shared<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: PartialEq> PartialEq for Shared<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Shared<T>
+where
+    &'a T: IntoIterator,
+{
+    type Item = <&'a T as IntoIterator>::Item;
+    type IntoIter = <&'a T as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.as_ref().into_iter()
+    }
+}
+
+impl<'a, T: Clone> IntoIterator for &'a mut Shared<T>
+where
+    &'a mut T: IntoIterator,
+{
+    type Item = <&'a mut T as IntoIterator>::Item;
+    type IntoIter = <&'a mut T as IntoIterator>::IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Arc::make_mut(&mut self.0).into_iter()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for Shared<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de>> serde::Deserialize<'de> for Shared<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Self::new)
+    }
+}
+
 #[repr(C, align(64))]
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FactStorage {
     pub concept_ids: Vec<u32>,
-    pub context_ids: Vec<u16>,
-    pub unit_ids: Vec<u16>,
+    pub context_ids: Vec<u32>,
+    pub unit_ids: Vec<u32>,
     pub values: Vec<FactValue>,
     pub decimals: Vec<Option<i8>>,
     pub ids: Vec<Option<String>>,
     pub footnote_refs: Vec<Vec<String>>,
+    /// Power of ten an iXBRL `scale` multiplied the reported value by to get
+    /// the stored base-unit value; `None` when it was reported unscaled
+    pub scales: Vec<Option<i8>>,
+    /// Tuple holding the fact, as a path from [`Document::tuples`]: the
+    /// index of the top-level tuple, then of each nested tuple within its
+    /// parent's `facts`. Empty for facts outside tuples.
+    pub tuple_paths: Vec<Vec<u32>>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FactValue {
     Text(String),
-    Decimal(f64),
+    /// Exact decimal value of a numeric fact
+    Decimal(Decimal),
     Integer(i64),
     Boolean(bool),
     Date(String),
     DateTime(String),
+    /// Value of a fraction item, kept as reported rather than divided
+    Fraction {
+        numerator: Decimal,
+        denominator: Decimal,
+    },
     Nil,
 }
 
+impl FactValue {
+    /// Numeric interpretation of the value, if it has one. Fractions have
+    /// none, as most have no exact decimal form.
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        match self {
+            FactValue::Decimal(v) => Some(*v),
+            FactValue::Integer(v) => Some(Decimal::from(*v)),
+            _ => None,
+        }
+    }
+
+    /// Numeric value as a float, for statistics and export; use
+    /// [`FactValue::as_decimal`] for arithmetic that must be exact
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            FactValue::Decimal(v) => v.to_f64(),
+            FactValue::Integer(v) => Some(*v as f64),
+            FactValue::Fraction {
+                numerator,
+                denominator,
+            } if !denominator.is_zero() => Some(numerator.to_f64()? / denominator.to_f64()?),
+            _ => None,
+        }
+    }
+
+    pub fn is_nil(&self) -> bool {
+        matches!(self, FactValue::Nil)
+    }
+}
+
+impl std::fmt::Display for FactValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FactValue::Text(s) | FactValue::Date(s) | FactValue::DateTime(s) => f.write_str(s),
+            FactValue::Decimal(v) => write!(f, "{}", v),
+            FactValue::Integer(v) => write!(f, "{}", v),
+            FactValue::Boolean(v) => write!(f, "{}", v),
+            FactValue::Fraction {
+                numerator,
+                denominator,
+            } => write!(f, "{}/{}", numerator, denominator),
+            FactValue::Nil => Ok(()),
+        }
+    }
+}
+
 impl FactStorage {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
@@ -37,6 +198,8 @@ impl FactStorage {
             decimals: Vec::with_capacity(capacity),
             ids: Vec::with_capacity(capacity),
             footnote_refs: Vec::with_capacity(capacity),
+            scales: Vec::with_capacity(capacity),
+            tuple_paths: Vec::with_capacity(capacity),
         }
     }
 
@@ -45,6 +208,26 @@ impl FactStorage {
         self.concept_ids.len()
     }
 
+    pub fn push(
+        &mut self,
+        concept_id: u32,
+        context_id: u32,
+        unit_id: u32,
+        value: FactValue,
+        decimals: Option<i8>,
+        id: Option<String>,
+    ) {
+        self.concept_ids.push(concept_id);
+        self.context_ids.push(context_id);
+        self.unit_ids.push(unit_id);
+        self.values.push(value);
+        self.decimals.push(decimals);
+        self.ids.push(id);
+        self.footnote_refs.push(Vec::new());
+        self.scales.push(None);
+        self.tuple_paths.push(Vec::new());
+    }
+
     pub fn is_empty(&self) -> bool {
         self.concept_ids.is_empty()
     }
@@ -52,6 +235,7 @@ impl FactStorage {
 
 // Full fact representation with all XBRL features
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Fact {
     pub id: Option<String>,
     pub concept: String,
@@ -67,6 +251,7 @@ pub struct Fact {
 
 // Context with full dimension support
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Context {
     pub id: String,
     pub entity: Entity,
@@ -74,7 +259,90 @@ pub struct Context {
     pub scenario: Option<Scenario>,
 }
 
+impl Context {
+    /// All explicit dimension members from segment and scenario, sorted by dimension
+    pub fn dimensions(&self) -> Vec<(&str, &str)> {
+        let mut dims: Vec<(&str, &str)> = Vec::new();
+        if let Some(segment) = &self.entity.segment {
+            dims.extend(
+                segment
+                    .explicit_members
+                    .iter()
+                    .map(|m| (m.dimension.as_str(), m.member.as_str())),
+            );
+            dims.extend(
+                segment
+                    .typed_members
+                    .iter()
+                    .map(|m| (m.dimension.as_str(), m.value.as_str())),
+            );
+        }
+        if let Some(scenario) = &self.scenario {
+            dims.extend(
+                scenario
+                    .explicit_members
+                    .iter()
+                    .map(|m| (m.dimension.as_str(), m.member.as_str())),
+            );
+            dims.extend(
+                scenario
+                    .typed_members
+                    .iter()
+                    .map(|m| (m.dimension.as_str(), m.value.as_str())),
+            );
+        }
+        dims.sort();
+        dims
+    }
+
+    /// Key shared by s-equal contexts: the same entity, periods covering
+    /// the same time, and the same segment and scenario members
+    pub fn equivalence_key(&self) -> ContextKey {
+        fn members(explicit: &[DimensionMember], typed: &[TypedMember]) -> Vec<(String, String)> {
+            let mut members: Vec<(String, String)> = explicit
+                .iter()
+                .map(|m| (m.dimension.clone(), m.member.trim().to_string()))
+                .chain(
+                    typed
+                        .iter()
+                        .map(|m| (m.dimension.clone(), m.value.trim().to_string())),
+                )
+                .collect();
+            members.sort();
+            members
+        }
+        ContextKey {
+            scheme: self.entity.scheme.trim().to_string(),
+            identifier: self.entity.identifier.trim().to_string(),
+            period: self.period.canonical(),
+            segment: self
+                .entity
+                .segment
+                .as_ref()
+                .map(|s| members(&s.explicit_members, &s.typed_members))
+                .unwrap_or_default(),
+            scenario: self
+                .scenario
+                .as_ref()
+                .map(|s| members(&s.explicit_members, &s.typed_members))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// See [`Context::equivalence_key`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ContextKey {
+    pub scheme: String,
+    pub identifier: String,
+    /// [`Period::canonical`]
+    pub period: String,
+    pub segment: Vec<(String, String)>,
+    pub scenario: Vec<(String, String)>,
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Entity {
     pub identifier: String,
     pub scheme: String,
@@ -83,45 +351,284 @@ pub struct Entity {
 
 // Dimensional data support
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Segment {
     pub explicit_members: Vec<DimensionMember>,
     pub typed_members: Vec<TypedMember>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DimensionMember {
     pub dimension: String,
     pub member: String,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TypedMember {
     pub dimension: String,
     pub value: String, // XML content
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Scenario {
     pub explicit_members: Vec<DimensionMember>,
     pub typed_members: Vec<TypedMember>,
 }
 
-// Period with forever support
-#[derive(Debug, Clone)]
+/// Period of a context. Dates keep their lexical form for round-tripping,
+/// next to the moments they denote, parsed once when the period is built
+/// with [`Period::instant`] or [`Period::between`]; moments are `None` for
+/// dates that do not parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Period {
-    Instant { date: String },
-    Duration { start: String, end: String },
+    Instant {
+        date: String,
+        /// Moment of the instant: the end of a date-only `date`
+        at: Option<NaiveDateTime>,
+    },
+    Duration {
+        start: String,
+        end: String,
+        /// Moment the duration starts: the start of a date-only `start`
+        start_at: Option<NaiveDateTime>,
+        /// Moment the duration ends: the end of a date-only `end`
+        end_at: Option<NaiveDateTime>,
+    },
     Forever,
 }
 
+/// Parse an `xbrli:dateUnion` value. A date without a time means the start
+/// of that day, or with `end_of_day` the end of it, i.e. midnight starting
+/// the next day, as for instants and end dates (XBRL 2.1 §4.7.2). Values
+/// with a timezone are converted to UTC.
+pub fn parse_date_union(value: &str, end_of_day: bool) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Some(datetime.naive_utc());
+    }
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f") {
+        return Some(datetime);
+    }
+    let next_midnight = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .ok()?
+            .succ_opt()?
+            .and_hms_opt(0, 0, 0)
+    };
+    if let Some(date) = value.strip_suffix("T24:00:00") {
+        return next_midnight(date);
+    }
+    let date = value.strip_suffix('Z').unwrap_or(value);
+    if end_of_day {
+        next_midnight(date)
+    } else {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .ok()?
+            .and_hms_opt(0, 0, 0)
+    }
+}
+
+impl Period {
+    pub fn instant(date: impl Into<String>) -> Self {
+        let date = date.into();
+        Period::Instant {
+            at: parse_date_union(&date, true),
+            date,
+        }
+    }
+
+    pub fn between(start: impl Into<String>, end: impl Into<String>) -> Self {
+        let (start, end) = (start.into(), end.into());
+        Period::Duration {
+            start_at: parse_date_union(&start, false),
+            end_at: parse_date_union(&end, true),
+            start,
+            end,
+        }
+    }
+
+    /// Start date as written; `None` for instants and forever
+    pub fn lexical_start(&self) -> Option<&str> {
+        match self {
+            Period::Duration { start, .. } => Some(start),
+            _ => None,
+        }
+    }
+
+    /// End date (or instant) as written; `None` for forever
+    pub fn lexical_end(&self) -> Option<&str> {
+        match self {
+            Period::Instant { date, .. } | Period::Duration { end: date, .. } => Some(date),
+            Period::Forever => None,
+        }
+    }
+
+    /// Moment the period starts; an instant starts where it ends
+    pub fn effective_start(&self) -> Option<NaiveDateTime> {
+        match self {
+            Period::Duration { start_at, .. } => *start_at,
+            Period::Instant { at, .. } => *at,
+            Period::Forever => None,
+        }
+    }
+
+    /// Moment the period ends: a date-only end date or instant means the
+    /// end of that day
+    pub fn effective_end(&self) -> Option<NaiveDateTime> {
+        match self {
+            Period::Duration { end_at, .. } => *end_at,
+            Period::Instant { at, .. } => *at,
+            Period::Forever => None,
+        }
+    }
+
+    /// Length of a duration; `2023-01-01` to `2023-12-31` is 365 days
+    pub fn duration(&self) -> Option<chrono::Duration> {
+        match self {
+            Period::Duration { .. } => Some(self.effective_end()? - self.effective_start()?),
+            Period::Instant { .. } => Some(chrono::Duration::zero()),
+            Period::Forever => None,
+        }
+    }
+
+    /// [`Period::duration`] in whole days
+    pub fn duration_days(&self) -> Option<i64> {
+        self.duration().map(|d| d.num_days())
+    }
+
+    /// First day of the period; an instant's day is the one it ends
+    pub fn start_date(&self) -> Option<NaiveDate> {
+        match self {
+            Period::Duration { .. } => self.effective_start().map(|start| start.date()),
+            _ => self.end_date(),
+        }
+    }
+
+    /// Last day the period covers, so an end written as `2024-01-01T00:00:00`
+    /// is the day `2023-12-31`
+    pub fn end_date(&self) -> Option<NaiveDate> {
+        let end = self.effective_end()?;
+        Some((end - chrono::Duration::nanoseconds(1)).date())
+    }
+
+    /// Whether `date` falls within the period: any day of a duration from
+    /// its start to its end date, the day of an instant, every day of
+    /// forever. Periods whose dates do not parse contain nothing.
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        if self.is_forever() {
+            return true;
+        }
+        match (self.start_date(), self.end_date()) {
+            (Some(start), Some(end)) => start <= date && date <= end,
+            _ => false,
+        }
+    }
+
+    pub fn is_forever(&self) -> bool {
+        matches!(self, Period::Forever)
+    }
+
+    /// Lexical form that is equal for periods covering the same time:
+    /// `forever`, an instant's end date, or `start/end`
+    pub fn canonical(&self) -> String {
+        match self {
+            Period::Instant { date, at } => end_lexical(date, *at),
+            Period::Duration {
+                start,
+                end,
+                start_at,
+                end_at,
+            } => format!(
+                "{}/{}",
+                start_lexical(start, *start_at),
+                end_lexical(end, *end_at)
+            ),
+            Period::Forever => "forever".to_string(),
+        }
+    }
+
+    /// Whether both periods cover the same time, however they are written:
+    /// instant `2023-12-31` equals instant `2024-01-01T00:00:00`
+    pub fn same_as(&self, other: &Period) -> bool {
+        match (self, other) {
+            (Period::Forever, Period::Forever) => true,
+            (Period::Instant { .. }, Period::Instant { .. })
+            | (Period::Duration { .. }, Period::Duration { .. }) => {
+                let (start, end) = (self.effective_start(), self.effective_end());
+                start.is_some()
+                    && end.is_some()
+                    && (start, end) == (other.effective_start(), other.effective_end())
+            }
+            _ => self == other,
+        }
+    }
+}
+
+/// Effective period bounds written back in their shortest lexical form:
+/// midnight starts as dates, midnight ends as the date of the day they close
+pub fn canonical_start(value: &str) -> String {
+    start_lexical(value, parse_date_union(value, false))
+}
+
+/// See [`canonical_start`]
+pub fn canonical_end(value: &str) -> String {
+    end_lexical(value, parse_date_union(value, true))
+}
+
+/// Shortest lexical form of the start `moment` written as `value`
+fn start_lexical(value: &str, moment: Option<NaiveDateTime>) -> String {
+    match moment {
+        Some(datetime) if datetime.time() == NaiveTime::MIN => datetime.date().to_string(),
+        Some(datetime) => datetime.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        None => value.to_string(),
+    }
+}
+
+/// Shortest lexical form of the end `moment` written as `value`
+fn end_lexical(value: &str, moment: Option<NaiveDateTime>) -> String {
+    match moment {
+        Some(datetime) if datetime.time() == NaiveTime::MIN => datetime
+            .date()
+            .pred_opt()
+            .map_or_else(|| value.to_string(), |d| d.to_string()),
+        Some(datetime) => datetime.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        None => value.to_string(),
+    }
+}
+
 // Complex unit support with divide/multiply
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Unit {
     pub id: String,
     pub unit_type: UnitType,
 }
 
+impl Unit {
+    /// Canonical string form, e.g. `iso4217:USD` or `iso4217:USD/xbrli:shares`
+    pub fn canonical(&self) -> String {
+        fn join(measures: &[Measure]) -> String {
+            let mut names: Vec<String> = measures.iter().map(Measure::qname).collect();
+            names.sort();
+            names.join("*")
+        }
+
+        match &self.unit_type {
+            UnitType::Simple(measures) | UnitType::Multiply(measures) => join(measures),
+            UnitType::Divide {
+                numerator,
+                denominator,
+            } => format!("{}/{}", join(numerator), join(denominator)),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum UnitType {
     Simple(Vec<Measure>),
     Divide {
@@ -132,13 +639,50 @@ pub enum UnitType {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Measure {
+    /// Prefix of the measure's QName, empty when it has none
+    pub prefix: String,
+    /// Namespace URI the QName resolves to, the in-scope default namespace
+    /// for unprefixed measures; empty when the prefix is not bound
     pub namespace: String,
     pub name: String,
 }
 
+impl Measure {
+    /// The measure written as `qname`, its prefix resolved in `namespaces`
+    pub fn from_qname(qname: &str, namespaces: &HashMap<String, String>) -> Self {
+        let qname = qname.trim();
+        let (prefix, name) = qname.split_once(':').unwrap_or(("", qname));
+        Self {
+            prefix: prefix.to_string(),
+            namespace: namespaces.get(prefix).cloned().unwrap_or_default(),
+            name: name.to_string(),
+        }
+    }
+
+    pub fn qname(&self) -> String {
+        if self.prefix.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}:{}", self.prefix, self.name)
+        }
+    }
+
+    /// Whether the measure is in namespace `uri`; measures with an unbound
+    /// prefix are matched on the prefix conventionally bound to it
+    pub fn in_namespace(&self, uri: &str, conventional_prefix: &str) -> bool {
+        if self.namespace.is_empty() {
+            self.prefix == conventional_prefix
+        } else {
+            self.namespace == uri
+        }
+    }
+}
+
 // Tuple support for structured data
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tuple {
     pub id: Option<String>,
     pub name: String,
@@ -146,6 +690,7 @@ pub struct Tuple {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FactOrTuple {
     Fact(Fact),
     Tuple(Box<Tuple>),
@@ -153,6 +698,7 @@ pub enum FactOrTuple {
 
 // Footnote support
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Footnote {
     pub id: String,
     pub role: Option<String>,
@@ -161,41 +707,382 @@ pub struct Footnote {
     pub fact_refs: Vec<String>,
 }
 
-// Fraction support
+/// Arcrole of the standard arcs from facts to their footnotes
+pub const FACT_FOOTNOTE_ARCROLE: &str = "http://www.xbrl.org/2003/arcrole/fact-footnote";
+
+/// End point of a footnote arc: a footnote resource or, in custom
+/// fact-to-fact networks, another fact
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FootnoteTarget {
+    /// A footnote, by [`Footnote::id`]
+    Footnote(String),
+    /// A fact, by id
+    Fact(String),
+}
+
+/// One arc of a footnote link, expanded to a single source fact and target.
+/// An arc whose `xlink:from` or `xlink:to` label is shared by several
+/// locators or footnotes yields one `FootnoteArc` per pair.
 #[derive(Debug, Clone)]
-pub struct FractionValue {
-    pub numerator: f64,
-    pub denominator: f64,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FootnoteArc {
+    /// Role of the enclosing footnote link
+    pub role: Option<String>,
+    /// Id of the source fact
+    pub from: String,
+    pub to: FootnoteTarget,
+    pub arcrole: String,
+    pub order: f32,
 }
 
 // Schema and taxonomy support
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Schema {
     pub target_namespace: String,
     pub elements: HashMap<String, SchemaElement>,
     pub types: HashMap<String, SchemaType>,
     pub imports: Vec<SchemaImport>,
+    pub role_types: Vec<RoleType>,
+    pub arcrole_types: Vec<ArcroleType>,
+    /// Content of the named model groups (`xsd:group name=`), by name
+    pub groups: HashMap<String, Particle>,
+    /// URI or path the schema was loaded from, when known
+    pub location: Option<String>,
+}
+
+/// `link:roleType` declaring an extended link role
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoleType {
+    pub role_uri: String,
+    /// `id` attribute, the fragment generic label locators point at
+    pub id: Option<String>,
+    pub definition: Option<String>,
+    /// Elements the role may be used on, such as `link:presentationLink`
+    pub used_on: Vec<String>,
+}
+
+impl RoleType {
+    /// Whether the declaration allows the role on the element with local
+    /// name `element`; one without any `usedOn` allows none
+    pub fn used_on(&self, element: &str) -> bool {
+        self.used_on
+            .iter()
+            .any(|u| u.rsplit(':').next() == Some(element))
+    }
+}
+
+/// `link:arcroleType` declaring an arcrole
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArcroleType {
+    pub arcrole_uri: String,
+    pub id: Option<String>,
+    /// `cyclesAllowed`: `any`, `undirected` or `none`
+    pub cycles_allowed: String,
+    pub definition: Option<String>,
+    /// Arc elements the arcrole may be used on, such as `link:definitionArc`
+    pub used_on: Vec<String>,
+}
+
+impl ArcroleType {
+    /// Whether the declaration allows the arcrole on the arc element with
+    /// local name `element`
+    pub fn used_on(&self, element: &str) -> bool {
+        self.used_on
+            .iter()
+            .any(|u| u.rsplit(':').next() == Some(element))
+    }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SchemaElement {
     pub name: String,
+    /// `id` attribute, the fragment linkbase locators point at
+    pub id: Option<String>,
     pub element_type: String,
     pub substitution_group: Option<String>,
     pub period_type: Option<String>,
     pub balance: Option<String>,
     pub abstract_element: bool,
     pub nillable: bool,
+    /// Content model of an anonymous complex type, as tuples declare it
+    pub content: Option<Particle>,
+}
+
+/// `xbrli:balance` of a monetary concept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Balance {
+    Debit,
+    Credit,
+}
+
+impl Balance {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "debit" => Some(Balance::Debit),
+            "credit" => Some(Balance::Credit),
+            _ => None,
+        }
+    }
+
+    pub fn opposite(self) -> Self {
+        match self {
+            Balance::Debit => Balance::Credit,
+            Balance::Credit => Balance::Debit,
+        }
+    }
+}
+
+/// How a numeric fact reads on a statement, see [`FactView::natural_sign`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NaturalSign {
+    /// -1, 0 or 1: the sign the statement shows the value with
+    pub presented: i8,
+    /// Side the amount is posted to, when the concept has a balance
+    pub side: Option<Balance>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SchemaType {
     pub name: String,
     pub base_type: Option<String>,
     pub restrictions: Vec<TypeRestriction>,
+    /// Content model of a complex type with element content
+    pub content: Option<Particle>,
+}
+
+/// `minOccurs` and `maxOccurs` of a particle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Occurs {
+    pub min: u32,
+    /// `None` for `unbounded`
+    pub max: Option<u32>,
+}
+
+impl Default for Occurs {
+    fn default() -> Self {
+        Self {
+            min: 1,
+            max: Some(1),
+        }
+    }
+}
+
+/// Part of a complex type's content model. Element and group names are
+/// namespace-qualified, resolved with the prefixes of the declaring schema.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Particle {
+    /// An element declared locally, or a global one referenced with `ref`
+    Element {
+        namespace: String,
+        name: String,
+        occurs: Occurs,
+    },
+    Sequence {
+        particles: Vec<Particle>,
+        occurs: Occurs,
+    },
+    Choice {
+        particles: Vec<Particle>,
+        occurs: Occurs,
+    },
+    All {
+        particles: Vec<Particle>,
+        occurs: Occurs,
+    },
+    /// A reference to a named model group, see [`Schema::groups`]
+    Group {
+        namespace: String,
+        name: String,
+        occurs: Occurs,
+    },
+}
+
+impl Particle {
+    pub fn occurs(&self) -> Occurs {
+        match self {
+            Particle::Element { occurs, .. }
+            | Particle::Sequence { occurs, .. }
+            | Particle::Choice { occurs, .. }
+            | Particle::All { occurs, .. }
+            | Particle::Group { occurs, .. } => *occurs,
+        }
+    }
+
+    /// Namespace and local name of every element the particle mentions, in
+    /// document order; unexpanded group references contribute none
+    pub fn elements(&self) -> Vec<(&str, &str)> {
+        let mut elements = Vec::new();
+        let mut stack = vec![self];
+        while let Some(particle) = stack.pop() {
+            match particle {
+                Particle::Element {
+                    namespace, name, ..
+                } => elements.push((namespace.as_str(), name.as_str())),
+                Particle::Sequence { particles, .. }
+                | Particle::Choice { particles, .. }
+                | Particle::All { particles, .. } => stack.extend(particles.iter().rev()),
+                Particle::Group { .. } => {}
+            }
+        }
+        elements
+    }
+
+    /// Match child elements, as namespace and local name in document order,
+    /// against the particle. Group references must already be expanded, as
+    /// [`Document::content_model`] does; any left over match nothing.
+    pub fn match_children(&self, children: &[(&str, &str)]) -> Result<(), ContentMismatch> {
+        let mut matcher = ContentMatcher {
+            children,
+            furthest: 0,
+            failures: Vec::new(),
+        };
+        if matcher
+            .ends(self, BTreeSet::from([0]))
+            .contains(&children.len())
+        {
+            return Ok(());
+        }
+        let position = matcher.furthest;
+        let mut expected = Vec::new();
+        for (at, namespace, name) in matcher.failures {
+            let element = (namespace.to_string(), name.to_string());
+            if at == position && !expected.contains(&element) {
+                expected.push(element);
+            }
+        }
+        Err(ContentMismatch { position, expected })
+    }
+}
+
+/// Where child elements stop matching a content model, see
+/// [`Particle::match_children`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentMismatch {
+    /// Index of the first child the model does not allow there; the number
+    /// of children when required content is missing after the last one
+    pub position: usize,
+    /// Namespace and local name of each element the model would accept at
+    /// `position`; empty when it allows no further children
+    pub expected: Vec<(String, String)>,
+}
+
+/// Matches children by tracking every position a particle can end at, so
+/// optional and repeated particles need no backtracking
+struct ContentMatcher<'a, 'p> {
+    children: &'a [(&'a str, &'a str)],
+    /// One past the last child any element particle matched
+    furthest: usize,
+    /// Element particles tried and not matched, by child position
+    failures: Vec<(usize, &'p str, &'p str)>,
+}
+
+impl<'p> ContentMatcher<'_, 'p> {
+    /// Positions `particle` can end at, repeated within its occurrence
+    /// bounds, when started at any of `starts`
+    fn ends(&mut self, particle: &'p Particle, starts: BTreeSet<usize>) -> BTreeSet<usize> {
+        let occurs = particle.occurs();
+        let mut ends = BTreeSet::new();
+        if occurs.min == 0 {
+            ends.extend(&starts);
+        }
+        let mut seen = BTreeSet::new();
+        let mut current = starts;
+        let mut count = 0;
+        while !current.is_empty() && occurs.max.is_none_or(|max| count < max) {
+            count += 1;
+            let next = self.once(particle, &current);
+            if count >= occurs.min {
+                ends.extend(&next);
+                // Further repetitions reach no new position
+                if next.is_subset(&seen) {
+                    break;
+                }
+            }
+            seen.extend(&next);
+            current = next;
+        }
+        ends
+    }
+
+    /// Positions one occurrence of `particle` can end at
+    fn once(&mut self, particle: &'p Particle, starts: &BTreeSet<usize>) -> BTreeSet<usize> {
+        match particle {
+            Particle::Element {
+                namespace, name, ..
+            } => {
+                let mut ends = BTreeSet::new();
+                for &start in starts {
+                    if self.children.get(start) == Some(&(namespace.as_str(), name.as_str())) {
+                        self.furthest = self.furthest.max(start + 1);
+                        ends.insert(start + 1);
+                    } else {
+                        self.failures.push((start, namespace, name));
+                    }
+                }
+                ends
+            }
+            Particle::Sequence { particles, .. } => {
+                let mut current = starts.clone();
+                for particle in particles {
+                    current = self.ends(particle, current);
+                }
+                current
+            }
+            Particle::Choice { particles, .. } => {
+                let mut ends = BTreeSet::new();
+                for particle in particles {
+                    ends.extend(self.ends(particle, starts.clone()));
+                }
+                ends
+            }
+            Particle::All { particles, .. } => {
+                // Each particle at most once, in any order
+                let mut ends = BTreeSet::new();
+                let mut visited = HashSet::new();
+                let mut queue: Vec<_> = starts
+                    .iter()
+                    .map(|&start| (start, vec![false; particles.len()]))
+                    .collect();
+                while let Some((position, used)) = queue.pop() {
+                    if !visited.insert((position, used.clone())) {
+                        continue;
+                    }
+                    let complete = particles
+                        .iter()
+                        .zip(&used)
+                        .all(|(particle, used)| *used || particle.occurs().min == 0);
+                    if complete {
+                        ends.insert(position);
+                    }
+                    for (i, particle) in particles.iter().enumerate() {
+                        if used[i] {
+                            continue;
+                        }
+                        for end in self.ends(particle, BTreeSet::from([position])) {
+                            let mut used = used.clone();
+                            used[i] = true;
+                            queue.push((end, used));
+                        }
+                    }
+                }
+                ends
+            }
+            Particle::Group { .. } => BTreeSet::new(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TypeRestriction {
     MinInclusive(String),
     MaxInclusive(String),
@@ -209,6 +1096,7 @@ pub enum TypeRestriction {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SchemaImport {
     pub namespace: String,
     pub schema_location: String,
@@ -216,12 +1104,14 @@ pub struct SchemaImport {
 
 // Linkbase support
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Linkbase {
     pub role: String,
     pub links: Vec<Link>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Link {
     Presentation(PresentationLink),
     Calculation(CalculationLink),
@@ -231,31 +1121,43 @@ pub enum Link {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PresentationLink {
+    pub role: String,
     pub from: String,
     pub to: String,
     pub order: f32,
     pub priority: Option<i32>,
     pub use_attribute: Option<String>,
+    pub preferred_label: Option<String>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CalculationLink {
+    pub role: String,
     pub from: String,
     pub to: String,
     pub weight: f64,
     pub order: f32,
+    pub priority: Option<i32>,
+    pub use_attribute: Option<String>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DefinitionLink {
+    pub role: String,
     pub from: String,
     pub to: String,
     pub arcrole: String,
     pub order: f32,
+    pub priority: Option<i32>,
+    pub use_attribute: Option<String>,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LabelLink {
     pub concept: String,
     pub label: String,
@@ -264,12 +1166,14 @@ pub struct LabelLink {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ReferenceLink {
     pub concept: String,
     pub reference: Reference,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Reference {
     pub role: String,
     pub parts: HashMap<String, String>,
@@ -277,23 +1181,182 @@ pub struct Reference {
 
 // Main document structure with full XBRL support
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Document {
-    pub facts: FactStorage,
-    pub contexts: Vec<Context>,
-    pub units: Vec<Unit>,
+    // Facts, contexts, units, schemas and relationships are shared between
+    // clones until one of them is modified
+    pub facts: Shared<FactStorage>,
+    pub contexts: Shared<Vec<Context>>,
+    pub units: Shared<Vec<Unit>>,
     pub tuples: Vec<Tuple>,
     pub footnotes: Vec<Footnote>,
-    pub presentation_links: Vec<PresentationLink>,
-    pub calculation_links: Vec<CalculationLink>,
-    pub definition_links: Vec<DefinitionLink>,
-    pub label_links: Vec<LabelLink>,
-    pub reference_links: Vec<ReferenceLink>,
-    pub custom_links: Vec<Link>,
-    pub role_types: Vec<String>,
-    pub arcrole_types: Vec<String>,
-    pub schemas: Vec<Schema>,
+    /// Every footnote arc, including ones with custom arcroles and arcs
+    /// between facts
+    pub footnote_arcs: Vec<FootnoteArc>,
+    pub presentation_links: Shared<Vec<PresentationLink>>,
+    pub calculation_links: Shared<Vec<CalculationLink>>,
+    pub definition_links: Shared<Vec<DefinitionLink>>,
+    pub label_links: Shared<Vec<LabelLink>>,
+    pub reference_links: Shared<Vec<ReferenceLink>>,
+    /// Generic labels of extended link roles; `concept` holds the role URI
+    pub role_labels: Shared<Vec<LabelLink>>,
+    pub custom_links: Shared<Vec<Link>>,
+    /// Roles declared by the loaded schemas, one per role URI; see
+    /// [`Document::add_schema`]
+    pub role_types: Vec<RoleType>,
+    /// Arcroles declared by the loaded schemas, one per arcrole URI
+    pub arcrole_types: Vec<ArcroleType>,
+    /// Schema each role and arcrole URI of the loaded linkbases points at
+    /// through `link:roleRef` or `link:arcroleRef`
+    pub role_refs: HashMap<String, String>,
+    pub schemas: Shared<Vec<Schema>>,
     pub dimensions: Vec<DimensionMember>,
     pub concept_names: Vec<String>,
+    /// Namespace prefixes of the document's QNames (prefix to URI): those
+    /// declared on the instance root, plus a distinct prefix for every
+    /// namespace bound only on nested elements. QNames using a prefix that
+    /// was redeclared in a nested scope are rewritten to one of these.
+    pub namespaces: HashMap<String, String>,
+    pub schema_ref: Option<String>,
+    pub provenance: Option<Provenance>,
+    /// Source offsets of facts, contexts and units, when parsed from XML
+    pub source_map: Option<SourceMap>,
+    /// Elements skipped by a parser with error recovery enabled
+    pub diagnostics: Vec<ParseDiagnostic>,
+    /// Features of the input that were read past without being processed,
+    /// each listed once
+    pub unsupported: Vec<Unsupported>,
+}
+
+/// A feature crabrl skipped, so results derived from the document may be
+/// incomplete
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Unsupported {
+    /// An extended link other than the five XBRL 2.1 linkbases and generic
+    /// links, such as a table linkbase; holds its element name
+    CustomLinkbase(String),
+    /// An arc whose arcrole has no meaning to crabrl
+    Arcrole(String),
+    /// Formula linkbase resources; crabrl evaluates no assertions
+    Formula,
+    /// A schema a `link:roleRef` or `link:arcroleRef` points at that is not
+    /// in the loaded DTS, so its roles could not be checked; holds its
+    /// location
+    IncompleteDts(String),
+}
+
+impl std::fmt::Display for Unsupported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Unsupported::CustomLinkbase(name) => write!(f, "custom linkbase <{}> skipped", name),
+            Unsupported::Arcrole(arcrole) => write!(f, "arcrole {} not interpreted", arcrole),
+            Unsupported::Formula => f.write_str("formula linkbase not evaluated"),
+            Unsupported::IncompleteDts(schema) => {
+                write!(f, "schema {} declaring referenced roles not loaded", schema)
+            }
+        }
+    }
+}
+
+/// A problem the parser recovered from
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseDiagnostic {
+    /// Start of the element that was skipped
+    pub location: SourcePosition,
+    pub message: String,
+    pub severity: crate::Severity,
+}
+
+/// 1-based line and column (in bytes) of a byte offset in the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourcePosition {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Where parsed elements start in the source bytes
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SourceMap {
+    /// Parallel to `Document.facts`
+    pub fact_offsets: Vec<usize>,
+    /// Parallel to `Document.contexts`
+    pub context_offsets: Vec<usize>,
+    /// Parallel to `Document.units`
+    pub unit_offsets: Vec<usize>,
+    /// Elements carrying an `id` attribute, in document order
+    pub ids: Vec<(String, usize)>,
+    line_starts: Vec<usize>,
+}
+
+impl SourceMap {
+    pub fn new(source: &[u8]) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(memchr::memchr_iter(b'\n', source).map(|i| i + 1));
+        Self {
+            line_starts,
+            ..Self::default()
+        }
+    }
+
+    pub fn position(&self, offset: usize) -> SourcePosition {
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let line_start = self
+            .line_starts
+            .get(line.wrapping_sub(1))
+            .copied()
+            .unwrap_or(0);
+        SourcePosition {
+            offset,
+            line: line.max(1),
+            column: offset - line_start + 1,
+        }
+    }
+
+    /// Offset of the last element with `id`; for duplicates that is the
+    /// offending one
+    pub fn id_offset(&self, id: &str) -> Option<usize> {
+        self.ids
+            .iter()
+            .rev()
+            .find(|(i, _)| i == id)
+            .map(|(_, offset)| *offset)
+    }
+}
+
+/// Where a document came from, so values can be traced back to the exact
+/// source bytes
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Provenance {
+    pub uri: Option<String>,
+    pub sha256: String,
+    pub parsed_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Provenance {
+    pub fn from_bytes(data: &[u8]) -> Self {
+        use sha2::{Digest, Sha256};
+        Self::from_digest(&Sha256::digest(data))
+    }
+
+    /// Provenance of input whose SHA-256 was computed as it was read
+    pub(crate) fn from_digest(digest: &[u8]) -> Self {
+        Self {
+            uri: None,
+            sha256: digest.iter().map(|b| format!("{:02x}", b)).collect(),
+            parsed_at: chrono::Utc::now(),
+        }
+    }
+
+    pub fn with_uri(mut self, uri: impl Into<String>) -> Self {
+        self.uri = Some(uri.into());
+        self
+    }
 }
 
 impl Default for Document {
@@ -305,43 +1368,687 @@ impl Default for Document {
 impl Document {
     pub fn new() -> Self {
         Self {
-            facts: FactStorage::with_capacity(10000),
-            contexts: Vec::with_capacity(100),
-            units: Vec::with_capacity(50),
+            facts: Shared::new(FactStorage::with_capacity(10000)),
+            contexts: Shared::new(Vec::with_capacity(100)),
+            units: Shared::new(Vec::with_capacity(50)),
             tuples: Vec::new(),
             footnotes: Vec::new(),
-            presentation_links: Vec::new(),
-            calculation_links: Vec::new(),
-            definition_links: Vec::new(),
-            label_links: Vec::new(),
-            reference_links: Vec::new(),
-            custom_links: Vec::new(),
+            footnote_arcs: Vec::new(),
+            presentation_links: Shared::default(),
+            calculation_links: Shared::default(),
+            definition_links: Shared::default(),
+            label_links: Shared::default(),
+            reference_links: Shared::default(),
+            role_labels: Shared::default(),
+            custom_links: Shared::default(),
             role_types: Vec::new(),
             arcrole_types: Vec::new(),
-            schemas: Vec::new(),
+            role_refs: HashMap::new(),
+            schemas: Shared::default(),
             dimensions: Vec::new(),
             concept_names: Vec::new(),
+            namespaces: HashMap::new(),
+            schema_ref: None,
+            provenance: None,
+            source_map: None,
+            diagnostics: Vec::new(),
+            unsupported: Vec::new(),
         }
     }
 
     pub fn with_capacity(facts: usize, contexts: usize, units: usize) -> Self {
         Self {
-            facts: FactStorage::with_capacity(facts),
-            contexts: Vec::with_capacity(contexts),
-            units: Vec::with_capacity(units),
+            facts: Shared::new(FactStorage::with_capacity(facts)),
+            contexts: Shared::new(Vec::with_capacity(contexts)),
+            units: Shared::new(Vec::with_capacity(units)),
             tuples: Vec::new(),
             footnotes: Vec::new(),
-            presentation_links: Vec::new(),
-            calculation_links: Vec::new(),
-            definition_links: Vec::new(),
-            label_links: Vec::new(),
-            reference_links: Vec::new(),
-            custom_links: Vec::new(),
+            footnote_arcs: Vec::new(),
+            presentation_links: Shared::default(),
+            calculation_links: Shared::default(),
+            definition_links: Shared::default(),
+            label_links: Shared::default(),
+            reference_links: Shared::default(),
+            role_labels: Shared::default(),
+            custom_links: Shared::default(),
             role_types: Vec::new(),
             arcrole_types: Vec::new(),
-            schemas: Vec::new(),
+            role_refs: HashMap::new(),
+            schemas: Shared::default(),
             dimensions: Vec::new(),
             concept_names: Vec::new(),
+            namespaces: HashMap::new(),
+            schema_ref: None,
+            provenance: None,
+            source_map: None,
+            diagnostics: Vec::new(),
+            unsupported: Vec::new(),
         }
     }
 }
+
+// Borrowed view over a single fact in the columnar storage
+#[derive(Clone, Copy)]
+pub struct FactView<'a> {
+    doc: &'a Document,
+    index: usize,
+}
+
+impl<'a> FactView<'a> {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Concept QName, or an empty string when the concept table is not populated
+    pub fn concept(&self) -> &'a str {
+        let concept_id = self.doc.facts.concept_ids[self.index] as usize;
+        self.doc
+            .concept_names
+            .get(concept_id)
+            .map(String::as_str)
+            .unwrap_or("")
+    }
+
+    pub fn context(&self) -> Option<&'a Context> {
+        self.doc
+            .contexts
+            .get(self.doc.facts.context_ids[self.index] as usize)
+    }
+
+    /// Unit ids are 1-based in storage; 0 means the fact has no unit
+    pub fn unit(&self) -> Option<&'a Unit> {
+        match self.doc.facts.unit_ids[self.index] {
+            0 => None,
+            id => self.doc.units.get(id as usize - 1),
+        }
+    }
+
+    pub fn value(&self) -> &'a FactValue {
+        &self.doc.facts.values[self.index]
+    }
+
+    pub fn decimals(&self) -> Option<i8> {
+        self.doc.facts.decimals[self.index]
+    }
+
+    /// iXBRL `scale` applied to the value, see [`FactStorage::scales`]
+    pub fn scale(&self) -> Option<i8> {
+        self.doc.facts.scales.get(self.index).copied().flatten()
+    }
+
+    /// Path to the tuple holding the fact, see [`FactStorage::tuple_paths`]
+    pub fn tuple_path(&self) -> &'a [u32] {
+        self.doc
+            .facts
+            .tuple_paths
+            .get(self.index)
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Innermost tuple holding the fact
+    pub fn tuple(&self) -> Option<&'a Tuple> {
+        self.doc.tuple(self.tuple_path())
+    }
+
+    pub fn id(&self) -> Option<&'a str> {
+        self.doc
+            .facts
+            .ids
+            .get(self.index)
+            .and_then(|id| id.as_deref())
+    }
+
+    /// Namespace URI of the fact's concept
+    pub fn namespace(&self) -> Option<&'a str> {
+        self.doc.resolve_qname(self.concept()).map(|(ns, _)| ns)
+    }
+
+    /// Provenance of the document this fact was read from
+    pub fn provenance(&self) -> Option<&'a Provenance> {
+        self.doc.provenance.as_ref()
+    }
+
+    /// Schema declaration of the concept, see [`Document::concept_info`]
+    pub fn concept_info(&self) -> Option<&'a SchemaElement> {
+        self.doc.concept_info(self.concept())
+    }
+
+    /// Balance of the concept, from the loaded schemas
+    pub fn balance(&self) -> Option<Balance> {
+        self.concept_info()?
+            .balance
+            .as_deref()
+            .and_then(Balance::parse)
+    }
+
+    /// Sign of the value as a reader of the statement understands it.
+    ///
+    /// Values are stored signed against the concept's balance: a positive
+    /// amount is posted to the concept's own side, a negative one to the
+    /// opposite side (a contra amount). Statements that subtract an amount
+    /// show it under a negated `preferred_label` role, which reverses the
+    /// presented sign without changing the side. `None` for non-numeric facts.
+    pub fn natural_sign(&self, preferred_label: Option<&str>) -> Option<NaturalSign> {
+        let value = self.value().as_decimal()?;
+        let raw: i8 = if value.is_zero() {
+            0
+        } else if value.is_sign_negative() {
+            -1
+        } else {
+            1
+        };
+        let negated = preferred_label.is_some_and(|role| label_semantics(role).negated);
+        let side = self
+            .balance()
+            .filter(|_| raw != 0)
+            .map(|b| if raw < 0 { b.opposite() } else { b });
+        Some(NaturalSign {
+            presented: if negated { -raw } else { raw },
+            side,
+        })
+    }
+}
+
+impl Document {
+    /// Record the source location on the document's provenance
+    pub fn with_source_uri(mut self, uri: impl Into<String>) -> Self {
+        self.provenance = Some(
+            self.provenance
+                .take()
+                .unwrap_or_else(|| Provenance::from_bytes(&[]))
+                .with_uri(uri),
+        );
+        self
+    }
+
+    /// Concept id for `name`, adding it to the concept table if needed
+    pub fn intern_concept(&mut self, name: &str) -> u32 {
+        match self.concept_names.iter().position(|n| n == name) {
+            Some(id) => id as u32,
+            None => {
+                self.concept_names.push(name.to_string());
+                (self.concept_names.len() - 1) as u32
+            }
+        }
+    }
+
+    /// Namespace URI and local name of a QName taken from this document
+    pub fn resolve_qname<'a>(&'a self, qname: &'a str) -> Option<(&'a str, &'a str)> {
+        let (prefix, local) = qname.split_once(':').unwrap_or(("", qname));
+        let namespace = self.namespaces.get(prefix)?;
+        Some((namespace.as_str(), local))
+    }
+
+    /// Schema declaration of the concept `qname`, from the loaded schemas
+    /// whose target namespace the QName's prefix is bound to. `None` when
+    /// no schemas were loaded or none declares the concept.
+    pub fn concept_info(&self, qname: &str) -> Option<&SchemaElement> {
+        let (namespace, local) = self.resolve_qname(qname)?;
+        self.schemas
+            .iter()
+            .filter(|s| s.target_namespace == namespace)
+            .find_map(|s| s.elements.get(local))
+    }
+
+    /// Content model of the tuple concept `qname`: its anonymous complex
+    /// type, or the named type its `type` refers to, with references to
+    /// model groups replaced by the groups' content
+    pub fn content_model(&self, qname: &str) -> Option<Particle> {
+        let (namespace, local) = self.resolve_qname(qname)?;
+        let (schema, element) = self
+            .schemas
+            .iter()
+            .filter(|s| s.target_namespace == namespace)
+            .find_map(|s| Some((s, s.elements.get(local)?)))?;
+        let content = element.content.as_ref().or_else(|| {
+            let type_name = element.element_type.rsplit(':').next()?;
+            schema
+                .types
+                .get(type_name)
+                .or_else(|| self.schemas.iter().find_map(|s| s.types.get(type_name)))?
+                .content
+                .as_ref()
+        })?;
+        Some(self.expand_groups(content, 0))
+    }
+
+    fn expand_groups(&self, particle: &Particle, depth: usize) -> Particle {
+        // Groups may not refer to themselves; the limit only stops bad input
+        const MAX_GROUP_DEPTH: usize = 32;
+        let expand = |particles: &[Particle]| {
+            particles
+                .iter()
+                .map(|p| self.expand_groups(p, depth))
+                .collect()
+        };
+        match particle {
+            Particle::Group {
+                namespace,
+                name,
+                occurs,
+            } if depth < MAX_GROUP_DEPTH => {
+                let group = self
+                    .schemas
+                    .iter()
+                    .filter(|s| &s.target_namespace == namespace)
+                    .find_map(|s| s.groups.get(name));
+                match group {
+                    Some(content) => Particle::Sequence {
+                        particles: vec![self.expand_groups(content, depth + 1)],
+                        occurs: *occurs,
+                    },
+                    None => particle.clone(),
+                }
+            }
+            Particle::Sequence { particles, occurs } => Particle::Sequence {
+                particles: expand(particles),
+                occurs: *occurs,
+            },
+            Particle::Choice { particles, occurs } => Particle::Choice {
+                particles: expand(particles),
+                occurs: *occurs,
+            },
+            Particle::All { particles, occurs } => Particle::All {
+                particles: expand(particles),
+                occurs: *occurs,
+            },
+            Particle::Element { .. } | Particle::Group { .. } => particle.clone(),
+        }
+    }
+
+    #[deprecated(note = "renamed to concept_info")]
+    pub fn schema_element(&self, qname: &str) -> Option<&SchemaElement> {
+        self.concept_info(qname)
+    }
+
+    pub fn fact(&self, index: usize) -> Option<FactView<'_>> {
+        (index < self.facts.len()).then_some(FactView { doc: self, index })
+    }
+
+    pub fn iter_facts(&self) -> impl Iterator<Item = FactView<'_>> {
+        (0..self.facts.len()).map(move |index| FactView { doc: self, index })
+    }
+
+    /// Tuple at `path`, see [`FactStorage::tuple_paths`]; `None` for an
+    /// empty path
+    pub fn tuple(&self, path: &[u32]) -> Option<&Tuple> {
+        let (first, rest) = path.split_first()?;
+        let mut tuple = self.tuples.get(*first as usize)?;
+        for index in rest {
+            tuple = match tuple.facts.get(*index as usize)? {
+                FactOrTuple::Tuple(nested) => nested,
+                FactOrTuple::Fact(_) => return None,
+            };
+        }
+        Some(tuple)
+    }
+
+    /// Every tuple with its path, nested ones right after their parent
+    pub fn tuples_iter(&self) -> impl Iterator<Item = (Vec<u32>, &Tuple)> {
+        let mut stack: Vec<(Vec<u32>, &Tuple)> = self
+            .tuples
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, t)| (vec![i as u32], t))
+            .collect();
+        std::iter::from_fn(move || {
+            let (path, tuple) = stack.pop()?;
+            for (i, child) in tuple.facts.iter().enumerate().rev() {
+                if let FactOrTuple::Tuple(nested) = child {
+                    let mut child_path = path.clone();
+                    child_path.push(i as u32);
+                    stack.push((child_path, nested));
+                }
+            }
+            Some((path, tuple))
+        })
+    }
+
+    /// Facts of the tuple at `path`, nested tuples' facts excluded
+    pub fn tuple_facts<'d>(&'d self, path: &'d [u32]) -> impl Iterator<Item = FactView<'d>> {
+        self.iter_facts().filter(move |f| f.tuple_path() == path)
+    }
+
+    /// Record on each fact the ids of the footnotes whose arcs point at it.
+    /// Footnote links usually follow the facts, so the parser runs this once
+    /// everything is read; run it again after adding facts or footnotes.
+    pub fn resolve_footnotes(&mut self) {
+        let facts = &mut *self.facts;
+        for refs in &mut facts.footnote_refs {
+            refs.clear();
+        }
+        if self.footnotes.is_empty() {
+            return;
+        }
+
+        let mut by_id: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, id) in facts.ids.iter().enumerate() {
+            if let Some(id) = id {
+                by_id.entry(id.as_str()).or_default().push(i);
+            }
+        }
+        for footnote in &self.footnotes {
+            for fact_ref in &footnote.fact_refs {
+                for &i in by_id.get(fact_ref.as_str()).into_iter().flatten() {
+                    if !facts.footnote_refs[i].contains(&footnote.id) {
+                        facts.footnote_refs[i].push(footnote.id.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Merge s-equal contexts into the first of each group and point their
+    /// facts at it. Returns `(removed, kept)` context id pairs in document
+    /// order.
+    pub fn normalize_contexts(&mut self) -> Vec<(String, String)> {
+        use std::collections::hash_map::Entry;
+        let mut first: HashMap<ContextKey, usize> = HashMap::new();
+        // New index of every context, and whether it is kept
+        let mut remap: Vec<u32> = Vec::with_capacity(self.contexts.len());
+        let mut keep = Vec::with_capacity(self.contexts.len());
+        let mut merged = Vec::new();
+        let mut kept = 0;
+        for (i, context) in self.contexts.iter().enumerate() {
+            match first.entry(context.equivalence_key()) {
+                Entry::Occupied(e) => {
+                    let target = *e.get();
+                    merged.push((context.id.clone(), self.contexts[target].id.clone()));
+                    remap.push(remap[target]);
+                    keep.push(false);
+                }
+                Entry::Vacant(e) => {
+                    e.insert(i);
+                    remap.push(kept);
+                    keep.push(true);
+                    kept += 1;
+                }
+            }
+        }
+        if merged.is_empty() {
+            return merged;
+        }
+
+        for id in &mut self.facts.context_ids {
+            if let Some(&new) = remap.get(*id as usize) {
+                *id = new;
+            }
+        }
+        let mut flags = keep.iter();
+        self.contexts.retain(|_| *flags.next().unwrap_or(&true));
+        if let Some(map) = &mut self.source_map {
+            let mut flags = keep.iter();
+            map.context_offsets
+                .retain(|_| *flags.next().unwrap_or(&true));
+        }
+        merged
+    }
+
+    /// Footnotes attached to the fact with `id`, in document order
+    pub fn footnotes_for_fact(&self, fact_id: &str) -> Vec<&Footnote> {
+        self.footnotes
+            .iter()
+            .filter(|f| f.fact_refs.iter().any(|r| r == fact_id))
+            .collect()
+    }
+
+    /// Footnote arcs leaving the fact with `id`, in document order
+    pub fn footnote_arcs_from<'d>(
+        &'d self,
+        fact_id: &'d str,
+    ) -> impl Iterator<Item = &'d FootnoteArc> {
+        self.footnote_arcs.iter().filter(move |a| a.from == fact_id)
+    }
+
+    /// Ids of the facts that arcs with `arcrole` lead to from the fact with
+    /// `id`, for fact-to-fact footnote networks
+    pub fn linked_facts<'d>(
+        &'d self,
+        fact_id: &'d str,
+        arcrole: &'d str,
+    ) -> impl Iterator<Item = &'d str> {
+        self.footnote_arcs_from(fact_id)
+            .filter(move |a| a.arcrole == arcrole)
+            .filter_map(|a| match &a.to {
+                FootnoteTarget::Fact(id) => Some(id.as_str()),
+                FootnoteTarget::Footnote(_) => None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provenance_digest() {
+        let provenance = Provenance::from_bytes(b"abc").with_uri("filing.xml");
+        assert_eq!(
+            provenance.sha256,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        let doc = Document {
+            provenance: Some(provenance),
+            ..Document::new()
+        };
+        assert_eq!(
+            doc.provenance.as_ref().and_then(|p| p.uri.as_deref()),
+            Some("filing.xml")
+        );
+    }
+
+    #[test]
+    fn test_period_end_of_day() {
+        let instant = |date: &str| Period::instant(date);
+        let at = |value: &str| parse_date_union(value, false);
+
+        // A date-only instant is the end of its day, a dateTime the moment
+        // written; a date-only start is the start of its day
+        assert_eq!(
+            instant("2023-12-31"),
+            Period::Instant {
+                date: "2023-12-31".to_string(),
+                at: at("2024-01-01T00:00:00"),
+            }
+        );
+        let Period::Instant { at: midnight, .. } = instant("2023-12-31T00:00:00") else {
+            unreachable!()
+        };
+        assert_eq!(midnight, at("2023-12-31T00:00:00"));
+        let Period::Instant { at: eastern, .. } = instant("2023-12-31T19:00:00-05:00") else {
+            unreachable!()
+        };
+        assert_eq!(eastern, at("2024-01-01T00:00:00"));
+        let Period::Duration {
+            start_at, end_at, ..
+        } = Period::between("2023-01-01", "2023-12-31T12:00:00")
+        else {
+            unreachable!()
+        };
+        assert_eq!(start_at, at("2023-01-01T00:00:00"));
+        assert_eq!(end_at, at("2023-12-31T12:00:00"));
+        assert!(matches!(
+            instant("2023-02-30"),
+            Period::Instant { at: None, .. }
+        ));
+
+        let year = Period::between("2023-01-01", "2023-12-31".to_string());
+        assert_eq!(year.duration(), Some(chrono::Duration::days(365)));
+        assert_eq!(year.lexical_end(), Some("2023-12-31"));
+        assert_eq!(
+            year.effective_end(),
+            parse_date_union("2024-01-01T00:00:00", false)
+        );
+        assert_eq!(year.effective_end(), instant("2023-12-31").effective_end());
+
+        assert!(instant("2023-12-31").same_as(&instant("2024-01-01T00:00:00")));
+        assert!(instant("2023-12-31").same_as(&instant("2023-12-31T24:00:00")));
+        assert!(!instant("2023-12-31").same_as(&instant("2023-12-31T00:00:00")));
+        assert!(instant("2023-12-31T19:00:00-05:00").same_as(&instant("2023-12-31")));
+
+        assert_eq!(canonical_end("2024-01-01T00:00:00"), "2023-12-31");
+        assert_eq!(canonical_start("2023-01-01T00:00:00"), "2023-01-01");
+        assert_eq!(canonical_end("2023-06-30T12:00:00"), "2023-06-30T12:00:00");
+
+        let day = |d: &str| NaiveDate::parse_from_str(d, "%Y-%m-%d").unwrap();
+        assert_eq!(year.duration_days(), Some(365));
+        assert_eq!(year.start_date(), Some(day("2023-01-01")));
+        assert_eq!(year.end_date(), Some(day("2023-12-31")));
+        assert!(year.contains(day("2023-12-31")) && !year.contains(day("2024-01-01")));
+        let midnight = instant("2024-01-01T00:00:00");
+        assert_eq!(midnight.end_date(), Some(day("2023-12-31")));
+        assert!(midnight.contains(day("2023-12-31")));
+        assert!(!instant("not a date").contains(day("2023-12-31")));
+        assert!(Period::Forever.contains(day("1900-01-01")));
+
+        assert!(Period::Forever.same_as(&Period::Forever));
+        assert!(!Period::Forever.same_as(&instant("2023-12-31")));
+        assert_eq!(Period::Forever.duration(), None);
+        assert_eq!(
+            instant("2024-01-01T00:00:00").canonical(),
+            instant("2023-12-31").canonical()
+        );
+    }
+
+    #[test]
+    fn test_natural_sign() {
+        let mut doc = crate::Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2023-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <a:Cost contextRef="c" unitRef="USD" decimals="0">60</a:Cost>
+  <a:Cost contextRef="c" unitRef="USD" decimals="0">-5</a:Cost>
+  <a:Name contextRef="c">Example</a:Name>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+        doc.schemas.push(
+            crate::dts::parse_schema(
+                br#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema" xmlns:xbrli="http://www.xbrl.org/2003/instance" targetNamespace="http://example.com/a">
+  <xs:element name="Cost" type="xbrli:monetaryItemType" xbrli:balance="debit" xbrli:periodType="duration"/>
+</xs:schema>"#,
+            )
+            .unwrap(),
+        );
+
+        let negated = Some(crate::linkbase::NEGATED_LABEL_ROLE);
+        let cost = doc.fact(0).unwrap();
+        assert_eq!(cost.balance(), Some(Balance::Debit));
+        assert_eq!(
+            cost.natural_sign(negated),
+            Some(NaturalSign {
+                presented: -1,
+                side: Some(Balance::Debit)
+            })
+        );
+        let contra = doc.fact(1).unwrap().natural_sign(None).unwrap();
+        assert_eq!((contra.presented, contra.side), (-1, Some(Balance::Credit)));
+        assert_eq!(doc.fact(2).unwrap().natural_sign(None), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let doc = crate::Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <a:Cash contextRef="c" unitRef="USD" decimals="2">12345678901234567.89</a:Cash>
+  <a:Name contextRef="c">Example</a:Name>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+
+        let json = serde_json::to_string(&doc).unwrap();
+        let back: Document = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.facts.values, doc.facts.values);
+        assert_eq!(
+            back.fact(0).unwrap().value().as_decimal(),
+            "12345678901234567.89".parse().ok()
+        );
+        assert_eq!(back.contexts[0].id, "c");
+        assert_eq!(back.namespaces, doc.namespaces);
+    }
+
+    #[test]
+    fn test_shared_storage() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Document>();
+
+        let doc = crate::Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <a:Name contextRef="c">Example</a:Name>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+
+        // Clones share their storage until one is modified
+        let mut copy = doc.clone();
+        assert!(Shared::ptr_eq(&copy.facts, &doc.facts));
+        assert!(Shared::ptr_eq(&copy.contexts, &doc.contexts));
+        copy.facts.values[0] = FactValue::Text("Changed".into());
+        assert!(!Shared::ptr_eq(&copy.facts, &doc.facts));
+        assert!(Shared::ptr_eq(&copy.contexts, &doc.contexts));
+        assert_eq!(doc.fact(0).unwrap().value().to_string(), "Example");
+        assert_eq!(copy.fact(0).unwrap().value().to_string(), "Changed");
+
+        let ids: Vec<&str> = (&doc.contexts).into_iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["c"]);
+    }
+
+    #[test]
+    fn test_normalize_contexts() {
+        let context = |id: &str, period: &str, member: &str| {
+            format!(
+                r#"<xbrli:context id="{id}"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier>{member}</xbrli:entity>
+    <xbrli:period>{period}</xbrli:period></xbrli:context>
+"#
+            )
+        };
+        let fy = "<xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2023-12-31</xbrli:endDate>";
+        let segment = r#"<xbrli:segment><xbrldi:explicitMember dimension="a:Axis">a:Member</xbrldi:explicitMember></xbrli:segment>"#;
+        let xml = format!(
+            r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:xbrldi="http://xbrl.org/2006/xbrldi" xmlns:a="http://example.com/a">
+{}{}{}{}  <a:Revenue contextRef="FY">1</a:Revenue>
+  <a:Revenue contextRef="FY_Seg">2</a:Revenue>
+  <a:Cost contextRef="FY_copy">3</a:Cost>
+  <a:Cost contextRef="FY_Seg_copy">4</a:Cost>
+</xbrli:xbrl>"#,
+            context("FY", fy, ""),
+            context("FY_Seg", fy, segment),
+            // Same period, written with a midnight end
+            context(
+                "FY_copy",
+                "<xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2024-01-01T00:00:00</xbrli:endDate>",
+                ""
+            ),
+            context("FY_Seg_copy", fy, segment),
+        );
+        let mut doc = crate::Parser::new().parse_str(&xml).unwrap();
+        let merged = doc.normalize_contexts();
+        assert_eq!(
+            merged,
+            [
+                ("FY_copy".to_string(), "FY".to_string()),
+                ("FY_Seg_copy".to_string(), "FY_Seg".to_string()),
+            ]
+        );
+        assert_eq!(doc.contexts.len(), 2);
+        assert_eq!(doc.source_map.as_ref().unwrap().context_offsets.len(), 2);
+        let contexts: Vec<_> = doc
+            .iter_facts()
+            .map(|f| f.context().unwrap().id.as_str())
+            .collect();
+        assert_eq!(contexts, ["FY", "FY_Seg", "FY", "FY_Seg"]);
+        assert!(doc.normalize_contexts().is_empty());
+    }
+}
@@ -0,0 +1,214 @@
+//! Relationship networks
+//!
+//! Arcs are resolved per base set, the arcs of one extended link role and
+//! arcrole, as XBRL 2.1 §3.5.3.9.7 describes: of equivalent relationships
+//! (same base set, source and target) only those with the highest
+//! `priority` count, and a `use="prohibited"` arc among them removes the
+//! relationship. Otherwise the last of them overrides the others. An arc
+//! prohibited in an extension taxonomy thus drops out of the presentation,
+//! calculation and definition networks built on top of the DTS.
+
+use crate::model::{CalculationLink, DefinitionLink, Document, PresentationLink};
+use std::collections::HashMap;
+
+pub const PARENT_CHILD_ARCROLE: &str = "http://www.xbrl.org/2003/arcrole/parent-child";
+pub const SUMMATION_ITEM_ARCROLE: &str = "http://www.xbrl.org/2003/arcrole/summation-item";
+
+/// An arc of a relationship network
+pub trait Relationship {
+    /// Extended link role and arcrole
+    fn base_set(&self) -> (&str, &str);
+    fn from(&self) -> &str;
+    fn to(&self) -> &str;
+    /// `priority`, 0 when absent
+    fn priority(&self) -> i32;
+    /// Whether the arc has `use="prohibited"`
+    fn prohibited(&self) -> bool;
+}
+
+fn is_prohibited(use_attribute: &Option<String>) -> bool {
+    use_attribute.as_deref() == Some("prohibited")
+}
+
+impl Relationship for PresentationLink {
+    fn base_set(&self) -> (&str, &str) {
+        (&self.role, PARENT_CHILD_ARCROLE)
+    }
+
+    fn from(&self) -> &str {
+        &self.from
+    }
+
+    fn to(&self) -> &str {
+        &self.to
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority.unwrap_or(0)
+    }
+
+    fn prohibited(&self) -> bool {
+        is_prohibited(&self.use_attribute)
+    }
+}
+
+impl Relationship for CalculationLink {
+    fn base_set(&self) -> (&str, &str) {
+        (&self.role, SUMMATION_ITEM_ARCROLE)
+    }
+
+    fn from(&self) -> &str {
+        &self.from
+    }
+
+    fn to(&self) -> &str {
+        &self.to
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority.unwrap_or(0)
+    }
+
+    fn prohibited(&self) -> bool {
+        is_prohibited(&self.use_attribute)
+    }
+}
+
+impl Relationship for DefinitionLink {
+    fn base_set(&self) -> (&str, &str) {
+        (&self.role, &self.arcrole)
+    }
+
+    fn from(&self) -> &str {
+        &self.from
+    }
+
+    fn to(&self) -> &str {
+        &self.to
+    }
+
+    fn priority(&self) -> i32 {
+        self.priority.unwrap_or(0)
+    }
+
+    fn prohibited(&self) -> bool {
+        is_prohibited(&self.use_attribute)
+    }
+}
+
+/// Effective relationships among `arcs`, in the document order of the first
+/// arc of each; prohibiting arcs and the arcs they override are left out
+pub fn resolve<'a, R: Relationship>(arcs: impl IntoIterator<Item = &'a R>) -> Vec<&'a R> {
+    // Keyed by role, arcrole, source and target
+    let mut equivalent: HashMap<(&str, &str, &str, &str), Vec<&R>> = HashMap::new();
+    let mut first_seen = Vec::new();
+    for arc in arcs {
+        let (role, arcrole) = arc.base_set();
+        let key = (role, arcrole, arc.from(), arc.to());
+        let group = equivalent.entry(key).or_default();
+        if group.is_empty() {
+            first_seen.push(key);
+        }
+        group.push(arc);
+    }
+
+    first_seen
+        .into_iter()
+        .filter_map(|key| {
+            let arcs = &equivalent[&key];
+            let top = arcs.iter().map(|a| a.priority()).max()?;
+            let winners: Vec<_> = arcs.iter().filter(|a| a.priority() == top).collect();
+            let prohibited = winners.iter().any(|a| a.prohibited());
+            (!prohibited).then(|| *winners[winners.len() - 1])
+        })
+        .collect()
+}
+
+impl Document {
+    /// Effective presentation relationships of the extended link `role`,
+    /// or of every role when `None`
+    pub fn presentation_network(&self, role: Option<&str>) -> Vec<&PresentationLink> {
+        resolve(
+            self.presentation_links
+                .iter()
+                .filter(|l| role.is_none_or(|r| r == l.role)),
+        )
+    }
+
+    /// Effective calculation relationships of the extended link `role`, or
+    /// of every role when `None`
+    pub fn calculation_network(&self, role: Option<&str>) -> Vec<&CalculationLink> {
+        resolve(
+            self.calculation_links
+                .iter()
+                .filter(|l| role.is_none_or(|r| r == l.role)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linkbase::LinkbaseProcessor;
+
+    #[test]
+    fn test_prohibition_and_override() {
+        let base = br#"<link:linkbase xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink">
+  <link:calculationLink xlink:type="extended" xlink:role="http://example.com/role/IS">
+    <link:loc xlink:type="locator" xlink:href="a.xsd#a_GrossProfit" xlink:label="gp"/>
+    <link:loc xlink:type="locator" xlink:href="a.xsd#a_Revenue" xlink:label="rev"/>
+    <link:loc xlink:type="locator" xlink:href="a.xsd#a_Cost" xlink:label="cost"/>
+    <link:calculationArc xlink:type="arc" xlink:arcrole="http://www.xbrl.org/2003/arcrole/summation-item" xlink:from="gp" xlink:to="rev" weight="1.0"/>
+    <link:calculationArc xlink:type="arc" xlink:arcrole="http://www.xbrl.org/2003/arcrole/summation-item" xlink:from="gp" xlink:to="cost" weight="-1.0"/>
+  </link:calculationLink>
+  <link:definitionLink xlink:type="extended" xlink:role="http://example.com/role/Defs">
+    <link:loc xlink:type="locator" xlink:href="a.xsd#a_Revenue" xlink:label="rev"/>
+    <link:loc xlink:type="locator" xlink:href="a.xsd#a_Cost" xlink:label="cost"/>
+    <link:definitionArc xlink:type="arc" xlink:arcrole="http://www.xbrl.org/2003/arcrole/requires-element" xlink:from="rev" xlink:to="cost"/>
+    <link:definitionArc xlink:type="arc" xlink:arcrole="http://www.xbrl.org/2003/arcrole/general-special" xlink:from="rev" xlink:to="cost"/>
+  </link:definitionLink>
+</link:linkbase>"#;
+        let extension = br#"<link:linkbase xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink">
+  <link:calculationLink xlink:type="extended" xlink:role="http://example.com/role/IS">
+    <link:loc xlink:type="locator" xlink:href="a.xsd#a_GrossProfit" xlink:label="gp"/>
+    <link:loc xlink:type="locator" xlink:href="a.xsd#a_Cost" xlink:label="cost"/>
+    <link:loc xlink:type="locator" xlink:href="a.xsd#a_Revenue" xlink:label="rev"/>
+    <link:calculationArc xlink:type="arc" xlink:arcrole="http://www.xbrl.org/2003/arcrole/summation-item" xlink:from="gp" xlink:to="cost" weight="-1.0" use="prohibited" priority="1"/>
+    <link:calculationArc xlink:type="arc" xlink:arcrole="http://www.xbrl.org/2003/arcrole/summation-item" xlink:from="gp" xlink:to="rev" weight="1.0" use="prohibited"/>
+    <link:calculationArc xlink:type="arc" xlink:arcrole="http://www.xbrl.org/2003/arcrole/summation-item" xlink:from="gp" xlink:to="rev" weight="2.0" use="optional" priority="2"/>
+  </link:calculationLink>
+  <link:definitionLink xlink:type="extended" xlink:role="http://example.com/role/Defs">
+    <link:loc xlink:type="locator" xlink:href="a.xsd#a_Revenue" xlink:label="rev"/>
+    <link:loc xlink:type="locator" xlink:href="a.xsd#a_Cost" xlink:label="cost"/>
+    <link:definitionArc xlink:type="arc" xlink:arcrole="http://www.xbrl.org/2003/arcrole/requires-element" xlink:from="rev" xlink:to="cost" use="prohibited" priority="1"/>
+  </link:definitionLink>
+</link:linkbase>"#;
+        let mut doc = Document::new();
+        for linkbase in [&base[..], &extension[..]] {
+            let mut processor = LinkbaseProcessor::new();
+            processor.parse_linkbase(linkbase).unwrap();
+            processor.apply_to(&mut doc);
+        }
+        assert_eq!(doc.calculation_links.len(), 5);
+
+        // The cost arc is prohibited; the revenue arc is overridden at a
+        // higher priority than its prohibition
+        let calculations = doc.calculation_network(None);
+        let found: Vec<_> = calculations
+            .iter()
+            .map(|l| (l.to.as_str(), l.weight))
+            .collect();
+        assert_eq!(found, [("a:Revenue", 2.0)]);
+
+        // Prohibiting one arcrole leaves the other base set alone
+        let definitions: Vec<_> = doc
+            .definition_network(crate::definition::REQUIRES_ELEMENT_ARCROLE, None)
+            .collect();
+        assert!(definitions.is_empty());
+        assert_eq!(
+            doc.definition_network(crate::definition::GENERAL_SPECIAL_ARCROLE, None)
+                .count(),
+            1
+        );
+    }
+}
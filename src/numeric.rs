@@ -0,0 +1,106 @@
+//! Decimals-aware comparison of numeric values
+//!
+//! A reported value stands for every value that rounds to it at its
+//! `decimals` (XBRL 2.1 §4.6.5), so two values are compared at the lower
+//! precision of the two rather than with a fixed tolerance. `None` decimals
+//! mean an exact value (`INF`). `precision` converts to decimals through
+//! [`decimals_from_precision`].
+
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// Round to `decimals` places, ties to even; `None` means exact. Negative
+/// `decimals` round to tens, hundreds, ...
+pub fn round(value: Decimal, decimals: Option<i8>) -> Decimal {
+    const TIES_EVEN: RoundingStrategy = RoundingStrategy::MidpointNearestEven;
+    match decimals {
+        Some(d) if d >= 0 => value.round_dp_with_strategy(d as u32, TIES_EVEN),
+        Some(d) => match unit(d) {
+            Some(unit) => (value / unit).round_dp_with_strategy(0, TIES_EVEN) * unit,
+            // Coarser than any representable value
+            None => Decimal::ZERO,
+        },
+        None => value,
+    }
+}
+
+/// `10^-decimals`, the size of one rounding step
+fn unit(decimals: i8) -> Option<Decimal> {
+    if decimals >= 0 {
+        let scale = u32::from(decimals.unsigned_abs()).min(28);
+        return Some(Decimal::new(1, scale));
+    }
+    10i128
+        .checked_pow(u32::from(decimals.unsigned_abs()))
+        .and_then(|u| Decimal::try_from_i128_with_scale(u, 0).ok())
+}
+
+/// Decimals equivalent to `precision` significant digits of `value`
+/// (XBRL 2.1 §4.6.6); zero is exact at any precision
+pub fn decimals_from_precision(value: Decimal, precision: u32) -> Option<i8> {
+    if value.is_zero() {
+        return None;
+    }
+    // floor(log10(|value|)) from the digits of the mantissa and the scale
+    let digits = value.mantissa().unsigned_abs().ilog10() as i64 + 1;
+    let magnitude = digits - 1 - i64::from(value.scale());
+    i8::try_from(i64::from(precision) - magnitude - 1).ok()
+}
+
+/// The lower precision of two `decimals`
+pub fn lower_precision(a: Option<i8>, b: Option<i8>) -> Option<i8> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+/// Range of values that round to `value` at `decimals`, bounds included
+pub fn interval(value: Decimal, decimals: Option<i8>) -> (Decimal, Decimal) {
+    match decimals.and_then(unit) {
+        Some(unit) => {
+            let half = unit / Decimal::TWO;
+            (value - half, value + half)
+        }
+        None => (value, value),
+    }
+}
+
+/// Whether two reported values agree when both are rounded to the lower
+/// of their precisions
+pub fn consistent(a: Decimal, a_decimals: Option<i8>, b: Decimal, b_decimals: Option<i8>) -> bool {
+    let decimals = lower_precision(a_decimals, b_decimals);
+    round(a, decimals) == round(b, decimals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_decimals_semantics() {
+        assert_eq!(round(d("1234567"), Some(-3)), d("1235000"));
+        assert_eq!(round(d("2.5"), Some(0)), d("2"));
+        assert_eq!(round(d("1.2345"), Some(2)), d("1.23"));
+        assert_eq!(round(d("1"), Some(-30)), Decimal::ZERO);
+
+        assert_eq!(decimals_from_precision(d("1234567"), 4), Some(-3));
+        assert_eq!(decimals_from_precision(d("0.012345"), 3), Some(4));
+        assert_eq!(decimals_from_precision(d("5"), 1), Some(0));
+        assert_eq!(decimals_from_precision(Decimal::ZERO, 3), None);
+
+        assert_eq!(
+            interval(d("1235000"), Some(-3)),
+            (d("1234500"), d("1235500"))
+        );
+        assert_eq!(interval(d("1.5"), None), (d("1.5"), d("1.5")));
+
+        assert!(consistent(d("1234567"), Some(0), d("1235000"), Some(-3)));
+        assert!(!consistent(d("1234567"), Some(0), d("1234000"), Some(-3)));
+        assert!(!consistent(d("1.01"), None, d("1.02"), None));
+    }
+}
@@ -0,0 +1,87 @@
+//! Open Information Model (OIM) report formats
+
+pub mod csv;
+pub mod json;
+
+use crate::model::{Document, Measure, Unit, UnitType};
+use std::collections::{BTreeMap, HashMap};
+
+/// Prefix declarations for an OIM report of `doc`, and the prefix of each
+/// entity scheme. Entity schemes and prefixes of concepts and measures
+/// without a namespace in `doc.namespaces` are given generated
+/// declarations so the report stays self-describing.
+fn prefixes(doc: &Document) -> (BTreeMap<String, String>, HashMap<&str, String>) {
+    let mut namespaces: BTreeMap<String, String> = doc
+        .namespaces
+        .iter()
+        .map(|(p, u)| (p.clone(), u.clone()))
+        .collect();
+    let mut scheme_prefixes: HashMap<&str, String> = HashMap::new();
+    for context in &doc.contexts {
+        let scheme = context.entity.scheme.as_str();
+        if scheme_prefixes.contains_key(scheme) {
+            continue;
+        }
+        let prefix = match namespaces.iter().find(|(_, uri)| *uri == scheme) {
+            Some((prefix, _)) => prefix.clone(),
+            None => {
+                let prefix =
+                    if scheme == "http://www.sec.gov/CIK" && !namespaces.contains_key("cik") {
+                        "cik".to_string()
+                    } else {
+                        format!("scheme{}", scheme_prefixes.len() + 1)
+                    };
+                namespaces.insert(prefix.clone(), scheme.to_string());
+                prefix
+            }
+        };
+        scheme_prefixes.insert(scheme, prefix);
+    }
+
+    let mut qnames: Vec<String> = doc.concept_names.clone();
+    for unit in &doc.units {
+        match &unit.unit_type {
+            UnitType::Simple(m) | UnitType::Multiply(m) => {
+                qnames.extend(m.iter().map(Measure::qname))
+            }
+            UnitType::Divide {
+                numerator,
+                denominator,
+            } => qnames.extend(numerator.iter().chain(denominator).map(Measure::qname)),
+        }
+    }
+    let qnames: Vec<&str> = qnames.iter().map(String::as_str).collect();
+    declare_prefixes(&mut namespaces, &qnames);
+    (namespaces, scheme_prefixes)
+}
+
+/// Declare a placeholder namespace for each prefix of `qnames` not declared
+fn declare_prefixes(namespaces: &mut BTreeMap<String, String>, qnames: &[&str]) {
+    for (prefix, _) in qnames.iter().filter_map(|q| q.split_once(':')) {
+        namespaces
+            .entry(prefix.to_string())
+            .or_insert_with(|| format!("urn:undeclared:{}", prefix));
+    }
+}
+
+/// OIM unit string: measures joined with `*`, a `/` before the denominator
+fn unit_string(unit: &Unit) -> String {
+    fn join(measures: &[Measure]) -> String {
+        let names: Vec<String> = measures.iter().map(Measure::qname).collect();
+        if names.len() > 1 {
+            format!("({})", names.join("*"))
+        } else {
+            names.concat()
+        }
+    }
+
+    match &unit.unit_type {
+        UnitType::Simple(measures) | UnitType::Multiply(measures) => join(measures)
+            .trim_matches(|c| c == '(' || c == ')')
+            .to_string(),
+        UnitType::Divide {
+            numerator,
+            denominator,
+        } => format!("{}/{}", join(numerator), join(denominator)),
+    }
+}
@@ -0,0 +1,557 @@
+//! xBRL-CSV packages
+//!
+//! A package is a JSON metadata file plus the CSV tables it describes.
+//! Reading covers what fact tables use in practice: the core dimensions
+//! (`concept`, `entity`, `period`, `unit`) and taxonomy dimensions set on a
+//! table template or a column, either literally or through `$column`
+//! parameter references, and `decimals` likewise. Writing produces a single
+//! open `facts` table with one column per property.
+
+use crate::export::csv_field;
+use crate::model::*;
+use crate::parser::{is_iso_date, parse_decimal};
+use crate::{Error, Result};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+pub const DOCUMENT_TYPE: &str = "https://xbrl.org/2021/xbrl-csv";
+
+const FACTS_TABLE: &str = "facts";
+
+/// Read a package from its metadata file; table URLs are resolved relative
+/// to it
+pub fn read_package<P: AsRef<Path>>(metadata: P) -> Result<Document> {
+    let metadata = metadata.as_ref();
+    let base = metadata.parent().unwrap_or(Path::new("."));
+    let json = std::fs::read_to_string(metadata)?;
+    read_from(&json, |url| Ok(std::fs::read_to_string(base.join(url))?))
+}
+
+/// Build a document from metadata JSON; `load_table` returns the CSV text
+/// for a table URL
+pub fn read_from(
+    metadata: &str,
+    mut load_table: impl FnMut(&str) -> Result<String>,
+) -> Result<Document> {
+    let meta: Value = serde_json::from_str(metadata)
+        .map_err(|e| Error::Parse(format!("Invalid xBRL-CSV metadata: {}", e)))?;
+    let info = &meta["documentInfo"];
+    match info["documentType"].as_str() {
+        Some(t) if t.ends_with("/xbrl-csv") => {}
+        other => {
+            return Err(Error::Parse(format!(
+                "Not an xBRL-CSV document type: {}",
+                other.unwrap_or("(missing)")
+            )))
+        }
+    }
+
+    let mut builder = Builder::default();
+    if let Some(namespaces) = info["namespaces"].as_object() {
+        for (prefix, uri) in namespaces {
+            if let Some(uri) = uri.as_str() {
+                builder
+                    .doc
+                    .namespaces
+                    .insert(prefix.clone(), uri.to_string());
+            }
+        }
+    }
+    builder.doc.schema_ref = info["taxonomy"][0].as_str().map(str::to_string);
+
+    let tables = meta["tables"]
+        .as_object()
+        .ok_or_else(|| Error::Parse("xBRL-CSV metadata has no tables".to_string()))?;
+    for (name, table) in tables {
+        let template_name = table["template"].as_str().unwrap_or(name);
+        let template = &meta["tableTemplates"][template_name];
+        if !template.is_object() {
+            return Err(Error::Parse(format!(
+                "Unknown table template: {}",
+                template_name
+            )));
+        }
+        let url = table["url"]
+            .as_str()
+            .ok_or_else(|| Error::Parse(format!("Table {} has no url", name)))?;
+        builder.read_table(template, &load_table(url)?)?;
+    }
+
+    Ok(builder.doc)
+}
+
+#[derive(Default)]
+struct Builder {
+    doc: Document,
+    contexts: HashMap<String, u32>,
+    units: HashMap<String, u32>,
+}
+
+impl Builder {
+    fn read_table(&mut self, template: &Value, text: &str) -> Result<()> {
+        let mut rows = parse_rows(text).into_iter();
+        let Some(header) = rows.next() else {
+            return Ok(());
+        };
+        let index: HashMap<&str, usize> = header
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.as_str(), i))
+            .collect();
+        let columns = template["columns"].as_object().cloned().unwrap_or_default();
+
+        // Columns referenced as `$name` hold properties; the rest hold facts
+        let mut referenced = HashSet::new();
+        collect_references(template, &mut referenced);
+        let fact_columns: Vec<(&String, &Value)> = columns
+            .iter()
+            .filter(|(name, _)| !referenced.contains(name.as_str()))
+            .collect();
+
+        for row in rows {
+            let cell = |name: &str| {
+                index
+                    .get(name)
+                    .and_then(|&i| row.get(i))
+                    .map(String::as_str)
+                    .filter(|v| !v.is_empty() && *v != "#none")
+            };
+            let resolve = |value: &Value| -> Option<String> {
+                match value {
+                    Value::String(s) => match s.strip_prefix('$') {
+                        Some(column) if !column.starts_with('$') => {
+                            cell(column).map(str::to_string)
+                        }
+                        Some(literal) => Some(literal.to_string()),
+                        None => Some(s.clone()),
+                    },
+                    Value::Number(n) => Some(n.to_string()),
+                    _ => None,
+                }
+            };
+
+            for (column, definition) in &fact_columns {
+                let Some(raw) = cell(column) else {
+                    continue;
+                };
+                let mut dimensions = BTreeMap::new();
+                for source in [&template["dimensions"], &definition["dimensions"]] {
+                    for (dim, value) in source.as_object().into_iter().flatten() {
+                        match resolve(value) {
+                            Some(v) => dimensions.insert(dim.clone(), v),
+                            None => dimensions.remove(dim),
+                        };
+                    }
+                }
+                let decimals = definition
+                    .get("decimals")
+                    .or_else(|| template.get("decimals"))
+                    .and_then(resolve)
+                    .and_then(|d| d.parse::<i8>().ok());
+                self.add_fact(column, raw, dimensions, decimals)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn add_fact(
+        &mut self,
+        column: &str,
+        raw: &str,
+        mut dimensions: BTreeMap<String, String>,
+        decimals: Option<i8>,
+    ) -> Result<()> {
+        let concept = dimensions
+            .remove("concept")
+            .ok_or_else(|| Error::Parse(format!("Fact in column {} has no concept", column)))?;
+        if let Some((prefix, _)) = concept.split_once(':') {
+            if !self.doc.namespaces.contains_key(prefix) {
+                return Err(Error::Namespace {
+                    prefix: prefix.to_string(),
+                    message: format!("concept {} uses an undeclared prefix", concept),
+                });
+            }
+        }
+        let entity = dimensions.remove("entity");
+        let period = dimensions.remove("period");
+        let unit = dimensions.remove("unit");
+        dimensions.remove("language");
+
+        let (Some(entity), Some(period)) = (entity, period) else {
+            return Err(Error::Parse(format!(
+                "Fact {} has no entity or period",
+                concept
+            )));
+        };
+        let context = self.context(&entity, &period, &dimensions);
+        let unit_id = match &unit {
+            Some(unit) => self.unit(unit),
+            None => 0,
+        };
+        let value = match raw {
+            "#nil" => FactValue::Nil,
+            "#empty" => FactValue::Text(String::new()),
+            _ if unit.is_some() => parse_decimal(raw)
+                .map(FactValue::Decimal)
+                .unwrap_or_else(|| FactValue::Text(raw.to_string())),
+            "true" | "false" => FactValue::Boolean(raw == "true"),
+            _ if is_iso_date(raw) => FactValue::Date(raw.to_string()),
+            _ => FactValue::Text(raw.to_string()),
+        };
+
+        let concept_id = self.doc.intern_concept(&concept);
+        self.doc
+            .facts
+            .push(concept_id, context, unit_id, value, decimals, None);
+        Ok(())
+    }
+
+    fn context(&mut self, entity: &str, period: &str, dims: &BTreeMap<String, String>) -> u32 {
+        let key = format!("{}|{}|{:?}", entity, period, dims);
+        if let Some(&id) = self.contexts.get(&key) {
+            return id;
+        }
+
+        let (scheme, identifier) = match entity.split_once(':') {
+            Some((prefix, id)) => (
+                self.doc
+                    .namespaces
+                    .get(prefix)
+                    .cloned()
+                    .unwrap_or_else(|| prefix.to_string()),
+                id.to_string(),
+            ),
+            None => (String::new(), entity.to_string()),
+        };
+        let period = match period.split_once('/') {
+            Some((start, end)) => Period::between(start.to_string(), end.to_string()),
+            None if period == "forever" => Period::Forever,
+            None => Period::instant(period.to_string()),
+        };
+
+        // Explicit members are QNames with a declared prefix; anything else
+        // is taken as a typed member value
+        let mut segment = Segment {
+            explicit_members: Vec::new(),
+            typed_members: Vec::new(),
+        };
+        for (dimension, value) in dims {
+            let is_qname = value
+                .split_once(':')
+                .is_some_and(|(prefix, _)| self.doc.namespaces.contains_key(prefix));
+            if is_qname {
+                segment.explicit_members.push(DimensionMember {
+                    dimension: dimension.clone(),
+                    member: value.clone(),
+                });
+            } else {
+                segment.typed_members.push(TypedMember {
+                    dimension: dimension.clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+
+        let id = self.doc.contexts.len() as u32;
+        self.doc.contexts.push(Context {
+            id: format!("c{}", id + 1),
+            entity: Entity {
+                identifier,
+                scheme,
+                segment: (!dims.is_empty()).then_some(segment),
+            },
+            period,
+            scenario: None,
+        });
+        self.contexts.insert(key, id);
+        id
+    }
+
+    /// 1-based unit id for an OIM unit string (`iso4217:USD/xbrli:shares`)
+    fn unit(&mut self, text: &str) -> u32 {
+        if let Some(&id) = self.units.get(text) {
+            return id;
+        }
+
+        let measures = |s: &str| -> Vec<Measure> {
+            s.trim_matches(|c| c == '(' || c == ')')
+                .split('*')
+                .map(|m| Measure::from_qname(m, &self.doc.namespaces))
+                .collect()
+        };
+        let unit_type = match text.split_once('/') {
+            Some((numerator, denominator)) => UnitType::Divide {
+                numerator: measures(numerator),
+                denominator: measures(denominator),
+            },
+            None => {
+                let measures = measures(text);
+                if measures.len() == 1 {
+                    UnitType::Simple(measures)
+                } else {
+                    UnitType::Multiply(measures)
+                }
+            }
+        };
+
+        let id = format!("u{}", self.doc.units.len() + 1);
+        self.doc.units.push(Unit { id, unit_type });
+        let id = self.doc.units.len() as u32;
+        self.units.insert(text.to_string(), id);
+        id
+    }
+}
+
+/// Column names used in `$column` references anywhere in a template
+fn collect_references<'a>(value: &'a Value, out: &mut HashSet<&'a str>) {
+    match value {
+        Value::String(s) => {
+            if let Some(column) = s.strip_prefix('$').filter(|c| !c.starts_with('$')) {
+                out.insert(column);
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|v| collect_references(v, out)),
+        Value::Object(map) => map.values().for_each(|v| collect_references(v, out)),
+        _ => {}
+    }
+}
+
+/// RFC 4180 rows: quoted fields may contain commas, quotes and newlines
+fn parse_rows(text: &str) -> Vec<Vec<String>> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(ch),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+/// Metadata JSON and the `facts` table CSV for `doc`.
+///
+/// Entity schemes and prefixes without a namespace in `doc.namespaces` are
+/// given generated declarations so the package stays self-describing.
+pub fn to_package(doc: &Document) -> (Value, String) {
+    let (mut namespaces, scheme_prefixes) = super::prefixes(doc);
+
+    let dimensions: BTreeSet<&str> = doc
+        .contexts
+        .iter()
+        .flat_map(|c| c.dimensions().into_iter().map(|(dim, _)| dim))
+        .collect();
+    let dimension_column = |dim: &str| dim.replace(':', "_");
+
+    let mut csv = String::new();
+    let mut header = vec!["concept", "entity", "period", "unit", "decimals"];
+    let dimension_columns: Vec<String> = dimensions.iter().map(|d| dimension_column(d)).collect();
+    header.extend(dimension_columns.iter().map(String::as_str));
+    header.push("value");
+    csv.push_str(&header.join(","));
+    csv.push('\n');
+
+    for fact in doc.iter_facts() {
+        let Some(context) = fact.context() else {
+            continue;
+        };
+        let dims: HashMap<&str, &str> = context.dimensions().into_iter().collect();
+        let period = match &context.period {
+            Period::Instant { date, .. } => date.clone(),
+            Period::Duration { start, end, .. } => format!("{}/{}", start, end),
+            Period::Forever => "forever".to_string(),
+        };
+        let value = match fact.value() {
+            FactValue::Nil => "#nil".to_string(),
+            FactValue::Text(s) if s.is_empty() => "#empty".to_string(),
+            other => other.to_string(),
+        };
+        let mut cells = vec![
+            fact.concept().to_string(),
+            format!(
+                "{}:{}",
+                scheme_prefixes[context.entity.scheme.as_str()],
+                context.entity.identifier
+            ),
+            period,
+            fact.unit().map(super::unit_string).unwrap_or_default(),
+            fact.decimals().map(|d| d.to_string()).unwrap_or_default(),
+        ];
+        cells.extend(
+            dimensions
+                .iter()
+                .map(|dim| dims.get(dim).map(|m| m.to_string()).unwrap_or_default()),
+        );
+        cells.push(value);
+        csv.push_str(
+            &cells
+                .iter()
+                .map(|c| csv_field(c))
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+        csv.push('\n');
+    }
+
+    // Dimensions need their prefixes declared like concepts and measures
+    let dimension_qnames: Vec<&str> = dimensions.iter().copied().collect();
+    super::declare_prefixes(&mut namespaces, &dimension_qnames);
+
+    let mut columns = Map::new();
+    let mut template_dimensions = Map::new();
+    for property in ["concept", "entity", "period", "unit"] {
+        columns.insert(property.to_string(), json!({}));
+        template_dimensions.insert(property.to_string(), json!(format!("${}", property)));
+    }
+    columns.insert("decimals".to_string(), json!({}));
+    for (dim, column) in dimensions.iter().zip(&dimension_columns) {
+        columns.insert(column.clone(), json!({}));
+        template_dimensions.insert(dim.to_string(), json!(format!("${}", column)));
+    }
+    columns.insert("value".to_string(), json!({}));
+
+    let mut info = json!({
+        "documentType": DOCUMENT_TYPE,
+        "namespaces": namespaces,
+    });
+    if let Some(schema_ref) = &doc.schema_ref {
+        info["taxonomy"] = json!([schema_ref]);
+    }
+    let metadata = json!({
+        "documentInfo": info,
+        "tableTemplates": {
+            FACTS_TABLE: {
+                "columns": columns,
+                "dimensions": template_dimensions,
+                "decimals": "$decimals",
+            }
+        },
+        "tables": {
+            FACTS_TABLE: { "template": FACTS_TABLE, "url": format!("{}.csv", FACTS_TABLE) }
+        }
+    });
+    (metadata, csv)
+}
+
+/// Write `metadata.json` and `facts.csv` into `dir`; returns the metadata path
+pub fn write_package<P: AsRef<Path>>(doc: &Document, dir: P) -> Result<PathBuf> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir)?;
+    let (metadata, csv) = to_package(doc);
+    std::fs::write(dir.join(format!("{}.csv", FACTS_TABLE)), csv)?;
+    let path = dir.join("metadata.json");
+    let json = serde_json::to_string_pretty(&metadata)
+        .map_err(|e| Error::Export(format!("Failed to serialize metadata: {}", e)))?;
+    std::fs::write(&path, json)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    #[test]
+    fn test_round_trip() {
+        let doc = Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:xbrldi="http://xbrl.org/2006/xbrldi"
+                    xmlns:us-gaap="http://fasb.org/us-gaap/2023" xmlns:iso4217="http://www.xbrl.org/2003/iso4217">
+  <xbrli:context id="FY23"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier>
+    <xbrli:segment><xbrldi:explicitMember dimension="us-gaap:StatementBusinessSegmentsAxis">us-gaap:AmericasMember</xbrldi:explicitMember></xbrli:segment>
+  </xbrli:entity><xbrli:period><xbrli:startDate>2022-10-01</xbrli:startDate><xbrli:endDate>2023-09-30</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:context id="I23"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-09-30</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <us-gaap:Revenues contextRef="FY23" unitRef="USD" decimals="-6">162560000000</us-gaap:Revenues>
+  <us-gaap:Assets contextRef="I23" unitRef="USD" decimals="-6" xsi:nil="true"/>
+  <us-gaap:SegmentNote contextRef="I23">Segments, "as reported"</us-gaap:SegmentNote>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+
+        let (metadata, csv) = to_package(&doc);
+        assert_eq!(
+            metadata["documentInfo"]["namespaces"]["cik"],
+            "http://www.sec.gov/CIK"
+        );
+        let read = read_from(&metadata.to_string(), |url| {
+            assert_eq!(url, "facts.csv");
+            Ok(csv.clone())
+        })
+        .unwrap();
+
+        assert_eq!(read.normalized_facts(), doc.normalized_facts());
+        assert_eq!(read.contexts.len(), 2);
+        assert_eq!(read.units.len(), 1);
+    }
+
+    #[test]
+    fn test_read_column_per_concept() {
+        let metadata = r#"{
+            "documentInfo": {
+                "documentType": "https://xbrl.org/2021/xbrl-csv",
+                "namespaces": {"us-gaap": "http://fasb.org/us-gaap/2023", "lei": "http://standards.iso.org/iso/17442", "iso4217": "http://www.xbrl.org/2003/iso4217"}
+            },
+            "tableTemplates": {
+                "balances": {
+                    "columns": {
+                        "year": {},
+                        "Assets": {"dimensions": {"concept": "us-gaap:Assets"}},
+                        "Equity": {"dimensions": {"concept": "us-gaap:StockholdersEquity"}, "decimals": 0}
+                    },
+                    "dimensions": {"entity": "lei:5493001KJTIIGC8Y1R12", "period": "$year", "unit": "iso4217:EUR"},
+                    "decimals": -3
+                }
+            },
+            "tables": {"balances": {"url": "balances.csv"}}
+        }"#;
+        let doc = read_from(metadata, |_| {
+            Ok("year,Assets,Equity\r\n2023-12-31,1000,\n2022-12-31,900,#nil\n".to_string())
+        })
+        .unwrap();
+
+        let facts = doc.normalized_facts();
+        assert_eq!(facts.len(), 3);
+        assert_eq!(facts[0].concept, "us-gaap:Assets");
+        assert_eq!(facts[0].entity_scheme, "http://standards.iso.org/iso/17442");
+        assert_eq!(facts[0].period_end.as_deref(), Some("2023-12-31"));
+        assert_eq!(facts[0].decimals, Some(-3));
+        assert_eq!(facts[2].concept, "us-gaap:StockholdersEquity");
+        assert!(facts[2].value.is_nil());
+        assert_eq!(facts[2].decimals, Some(0));
+
+        let unbound = metadata.replace("\"us-gaap\": \"http://fasb.org/us-gaap/2023\", ", "");
+        let err = read_from(&unbound, |_| {
+            Ok("year,Assets,Equity\n2023-12-31,1,2\n".to_string())
+        })
+        .err()
+        .unwrap();
+        assert_eq!(err.kind(), crate::ErrorKind::Namespace);
+        assert!(matches!(err, Error::Namespace { prefix, .. } if prefix == "us-gaap"));
+    }
+}
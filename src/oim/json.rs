@@ -0,0 +1,162 @@
+//! xBRL-JSON reports
+//!
+//! Each fact is an object keyed by its id, or by a generated `f<index>`
+//! for facts without one, holding its `value`, `decimals` and
+//! `dimensions`: `concept`, `entity`, `period` and `unit`, then the
+//! taxonomy dimensions. Periods are written as date-times, so an instant
+//! `2023-12-31` becomes `2024-01-01T00:00:00`; forever periods, which OIM
+//! does not have, get no `period`.
+
+use crate::model::*;
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+
+pub const DOCUMENT_TYPE: &str = "https://xbrl.org/2021/xbrl-json";
+
+fn date_time(moment: chrono::NaiveDateTime) -> String {
+    moment.format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+/// OIM period string; the lexical dates when they do not parse
+fn period_string(period: &Period) -> Option<String> {
+    let (start, end) = (period.effective_start(), period.effective_end());
+    match (period, start, end) {
+        (Period::Forever, _, _) => None,
+        (Period::Instant { .. }, _, Some(end)) => Some(date_time(end)),
+        (Period::Duration { .. }, Some(start), Some(end)) => {
+            Some(format!("{}/{}", date_time(start), date_time(end)))
+        }
+        _ => Some(period.canonical()),
+    }
+}
+
+/// The xBRL-JSON report of `doc`'s facts; facts whose context is missing
+/// are left out
+pub fn to_json(doc: &Document) -> Value {
+    let (mut namespaces, scheme_prefixes) = super::prefixes(doc);
+    let dimensions: Vec<&str> = doc
+        .contexts
+        .iter()
+        .flat_map(|c| c.dimensions().into_iter().map(|(dim, _)| dim))
+        .collect();
+    super::declare_prefixes(&mut namespaces, &dimensions);
+
+    let mut ids: HashSet<String> = doc
+        .iter_facts()
+        .filter_map(|f| f.id())
+        .map(str::to_string)
+        .collect();
+    let mut facts = Map::new();
+    for fact in doc.iter_facts() {
+        let Some(context) = fact.context() else {
+            continue;
+        };
+        let mut dimensions = Map::new();
+        dimensions.insert("concept".to_string(), json!(fact.concept()));
+        dimensions.insert(
+            "entity".to_string(),
+            json!(format!(
+                "{}:{}",
+                scheme_prefixes[context.entity.scheme.as_str()],
+                context.entity.identifier
+            )),
+        );
+        if let Some(period) = period_string(&context.period) {
+            dimensions.insert("period".to_string(), json!(period));
+        }
+        if let Some(unit) = fact.unit() {
+            dimensions.insert("unit".to_string(), json!(super::unit_string(unit)));
+        }
+        for (dimension, member) in context.dimensions() {
+            dimensions.insert(dimension.to_string(), json!(member));
+        }
+
+        let value = match fact.value() {
+            FactValue::Nil => Value::Null,
+            other => json!(other.to_string()),
+        };
+        let mut object = json!({ "value": value, "dimensions": dimensions });
+        if let Some(decimals) = fact.decimals() {
+            object["decimals"] = json!(decimals);
+        }
+
+        let id = match fact.id() {
+            Some(id) => id.to_string(),
+            None => {
+                let mut id = format!("f{}", fact.index());
+                while ids.contains(&id) {
+                    id.push('_');
+                }
+                ids.insert(id.clone());
+                id
+            }
+        };
+        facts.insert(id, object);
+    }
+
+    let mut info = json!({
+        "documentType": DOCUMENT_TYPE,
+        "namespaces": namespaces,
+    });
+    if let Some(schema_ref) = &doc.schema_ref {
+        info["taxonomy"] = json!([schema_ref]);
+    }
+    json!({ "documentInfo": info, "facts": facts })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    #[test]
+    fn test_xbrl_json() {
+        let doc = Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:xbrldi="http://xbrl.org/2006/xbrldi" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+                    xmlns:us-gaap="http://fasb.org/us-gaap/2023" xmlns:iso4217="http://www.xbrl.org/2003/iso4217">
+  <link:schemaRef xlink:type="simple" xlink:href="a.xsd"/>
+  <xbrli:context id="FY23"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2023-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:context id="I23"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier>
+    <xbrli:segment><xbrldi:explicitMember dimension="us-gaap:StatementBusinessSegmentsAxis">us-gaap:ServiceMember</xbrldi:explicitMember></xbrli:segment></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <us-gaap:Revenues id="f0" contextRef="FY23" unitRef="USD" decimals="-6">383285000000</us-gaap:Revenues>
+  <us-gaap:Assets id="f2" contextRef="I23" unitRef="USD" decimals="-6">1000000</us-gaap:Assets>
+  <us-gaap:Liabilities contextRef="I23" unitRef="USD" xsi:nil="true"/>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+        let json = to_json(&doc);
+        let info = &json["documentInfo"];
+        assert_eq!(info["documentType"], DOCUMENT_TYPE);
+        assert_eq!(info["namespaces"]["cik"], "http://www.sec.gov/CIK");
+        assert_eq!(info["taxonomy"][0], "a.xsd");
+
+        let facts = json["facts"].as_object().unwrap();
+        // The generated id skips the one a fact already has
+        let ids: Vec<_> = facts.keys().map(String::as_str).collect();
+        assert_eq!(ids, ["f0", "f2", "f2_"]);
+        assert_eq!(
+            facts["f0"],
+            json!({
+                "value": "383285000000",
+                "decimals": -6,
+                "dimensions": {
+                    "concept": "us-gaap:Revenues",
+                    "entity": "cik:0000320193",
+                    "period": "2023-01-01T00:00:00/2024-01-01T00:00:00",
+                    "unit": "iso4217:USD",
+                }
+            })
+        );
+        let assets = &facts["f2"]["dimensions"];
+        assert_eq!(assets["period"], "2024-01-01T00:00:00");
+        assert_eq!(
+            assets["us-gaap:StatementBusinessSegmentsAxis"],
+            "us-gaap:ServiceMember"
+        );
+        assert!(facts["f2_"]["value"].is_null());
+    }
+}
@@ -0,0 +1,149 @@
+//! Parser configuration
+
+use crate::transform::{TransformPipeline, TransformTarget, ValueTransform};
+
+/// Options controlling what the parser materializes
+#[derive(Debug, Clone, Default)]
+pub struct ParserOptions {
+    pub concept_filter: Option<ConceptFilter>,
+    /// Applied to fact values before they are typed
+    pub transforms: TransformPipeline,
+}
+
+impl ParserOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_concept_filter(mut self, filter: ConceptFilter) -> Self {
+        self.concept_filter = Some(filter);
+        self
+    }
+
+    /// Add a value transform for the facts matching `target`; transforms
+    /// run in the order they were added
+    pub fn with_transform(
+        mut self,
+        target: TransformTarget,
+        transform: impl ValueTransform + 'static,
+    ) -> Self {
+        self.transforms = self.transforms.with(target, transform);
+        self
+    }
+
+    /// Whether a fact for `qname` should be materialized
+    #[inline]
+    pub fn wants_concept(&self, qname: &str, namespace: Option<&str>) -> bool {
+        self.concept_filter
+            .as_ref()
+            .is_none_or(|f| f.allows(qname, namespace))
+    }
+}
+
+/// Pattern matched against a concept QName.
+///
+/// Accepted forms:
+/// - `*` matches every concept
+/// - `dei:*` matches every concept with the `dei` prefix
+/// - `us-gaap:Revenues` matches one QName exactly
+/// - `*:Revenues` matches a local name under any prefix
+/// - `{http://xbrl.sec.gov/dei/2023}*` matches a namespace URI (Clark notation)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConceptPattern {
+    Any,
+    Prefix(String),
+    QName(String),
+    LocalName(String),
+    Namespace(String),
+}
+
+impl ConceptPattern {
+    pub fn parse(pattern: &str) -> Self {
+        let pattern = pattern.trim();
+        if pattern == "*" {
+            return ConceptPattern::Any;
+        }
+        if let Some(rest) = pattern.strip_prefix('{') {
+            if let Some((uri, local)) = rest.split_once('}') {
+                if local == "*" || local.is_empty() {
+                    return ConceptPattern::Namespace(uri.to_string());
+                }
+            }
+        }
+        if let Some(prefix) = pattern.strip_suffix(":*") {
+            return ConceptPattern::Prefix(prefix.to_string());
+        }
+        if let Some(local) = pattern.strip_prefix("*:") {
+            return ConceptPattern::LocalName(local.to_string());
+        }
+        ConceptPattern::QName(pattern.to_string())
+    }
+
+    pub fn matches(&self, qname: &str, namespace: Option<&str>) -> bool {
+        match self {
+            ConceptPattern::Any => true,
+            ConceptPattern::Prefix(prefix) => qname
+                .split_once(':')
+                .is_some_and(|(p, _)| p == prefix.as_str()),
+            ConceptPattern::QName(name) => qname == name,
+            ConceptPattern::LocalName(local) => qname.rsplit(':').next() == Some(local.as_str()),
+            ConceptPattern::Namespace(uri) => namespace == Some(uri.as_str()),
+        }
+    }
+}
+
+/// Include/exclude filter on concept QNames.
+///
+/// An empty include list admits every concept; excludes always win.
+#[derive(Debug, Clone, Default)]
+pub struct ConceptFilter {
+    include: Vec<ConceptPattern>,
+    exclude: Vec<ConceptPattern>,
+}
+
+impl ConceptFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn include(mut self, pattern: &str) -> Self {
+        self.include.push(ConceptPattern::parse(pattern));
+        self
+    }
+
+    pub fn exclude(mut self, pattern: &str) -> Self {
+        self.exclude.push(ConceptPattern::parse(pattern));
+        self
+    }
+
+    pub fn allows(&self, qname: &str, namespace: Option<&str>) -> bool {
+        let included =
+            self.include.is_empty() || self.include.iter().any(|p| p.matches(qname, namespace));
+        included && !self.exclude.iter().any(|p| p.matches(qname, namespace))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concept_filter() {
+        let filter = ConceptFilter::new()
+            .include("dei:*")
+            .include("us-gaap:Revenues")
+            .exclude("dei:EntityAddressLine1");
+
+        assert!(filter.allows("dei:DocumentType", None));
+        assert!(filter.allows("us-gaap:Revenues", None));
+        assert!(!filter.allows("us-gaap:Assets", None));
+        assert!(!filter.allows("dei:EntityAddressLine1", None));
+    }
+
+    #[test]
+    fn test_namespace_pattern() {
+        let filter = ConceptFilter::new().include("{http://xbrl.sec.gov/dei/2023}*");
+        assert!(filter.allows("dei:DocumentType", Some("http://xbrl.sec.gov/dei/2023")));
+        assert!(!filter.allows("dei:DocumentType", None));
+    }
+}
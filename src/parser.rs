@@ -1,351 +1,1063 @@
-// Full XBRL 2.1 compliant parser with all features
-use crate::{model::*, Error, Result};
-use compact_str::CompactString;
-#[cfg(feature = "mmap")]
-use memmap2::Mmap;
-use std::fs::File;
-use std::path::Path;
+//! Full XBRL 2.1 instance parser
+//!
+//! Reads contexts (entity, segment, scenario, period), units (including
+//! divide), facts with nil/decimals/precision, fractions, tuples and footnote
+//! links into the columnar [`Document`] model. Optional passes load the
+//! referenced schemas and linkbases and validate the result.
+
+use crate::allocator::ArenaAllocator;
+use crate::catalog::Catalog;
+use crate::dts::{DtsLoader, NetworkPolicy};
+use crate::interner::StringInterner;
+use crate::ixbrl::{is_inline_xbrl, InlineParser};
+use crate::numeric::decimals_from_precision;
+use crate::simd::SimdScanner;
+use crate::transform::TransformFact;
+use crate::{model::*, Error, ParserOptions, Result};
+use rust_decimal::Decimal;
 use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const XBRLI_NS: &str = "http://www.xbrl.org/2003/instance";
+const LINK_NS: &str = "http://www.xbrl.org/2003/linkbase";
+const XBRLDI_NS: &str = "http://xbrl.org/2006/xbrldi";
+const XLINK_NS: &str = "http://www.w3.org/1999/xlink";
+
+/// Context or unit id stored for a fact whose reference does not resolve
+pub(crate) const UNRESOLVED: u32 = u32::MAX;
+
+/// Elements reported by [`Parser::parse_streaming`] as they are parsed
+#[derive(Debug, Clone, Copy)]
+pub enum ParseEvent<'e> {
+    Context(&'e Context),
+    Unit(&'e Unit),
+    /// A fact outside any tuple. Its contextRef and unitRef may point at
+    /// contexts and units that follow it in the document.
+    Fact(&'e Fact),
+    Footnote(&'e Footnote),
+    /// An arc of a footnote link, reported after the link's footnotes
+    FootnoteArc(&'e FootnoteArc),
+    /// A top-level tuple, with everything nested in it
+    Tuple(&'e Tuple),
+}
+
+/// Receives [`ParseEvent`]s; an error stops parsing
+pub trait ParseHandler {
+    fn handle(&mut self, event: ParseEvent<'_>) -> Result<()>;
+}
+
+impl<F> ParseHandler for F
+where
+    F: FnMut(ParseEvent<'_>) -> Result<()>,
+{
+    fn handle(&mut self, event: ParseEvent<'_>) -> Result<()> {
+        self(event)
+    }
+}
+
+/// What an XML input holds, judged by its root element
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputKind {
+    /// An instance, or anything else that is not a taxonomy document
+    Instance,
+    /// An `xsd:schema`, such as a taxonomy entry point
+    Schema,
+    /// A `link:linkbase`
+    Linkbase,
+}
+
+/// Kind of `data` from the local name of its root element, skipping the
+/// XML declaration, comments, processing instructions and a DOCTYPE
+pub fn detect_input(data: &[u8]) -> InputKind {
+    let head = &data[..data.len().min(64 * 1024)];
+    let mut rest = head;
+    while let Some(start) = memchr::memchr(b'<', rest) {
+        rest = &rest[start + 1..];
+        if rest.starts_with(b"!--") {
+            match memchr::memmem::find(rest, b"-->") {
+                Some(end) => rest = &rest[end..],
+                None => break,
+            }
+            continue;
+        }
+        if rest.first().is_some_and(|&b| b == b'?' || b == b'!') {
+            continue;
+        }
+        let end = rest
+            .iter()
+            .position(|&b| b.is_ascii_whitespace() || b == b'>' || b == b'/')
+            .unwrap_or(rest.len());
+        let name = &rest[..end];
+        let local = name.rsplit(|&b| b == b':').next().unwrap_or(name);
+        return match local {
+            b"schema" => InputKind::Schema,
+            b"linkbase" => InputKind::Linkbase,
+            _ => InputKind::Instance,
+        };
+    }
+    InputKind::Instance
+}
+
+/// Initial arena size when streaming; the arena only holds rebound QNames
+const STREAMING_ARENA_BYTES: usize = 1 << 20;
+
+/// Read buffer of [`Parser::parse_streaming`]
+const STREAM_READ_BYTES: usize = 1 << 16;
 
+/// Throughput figures of one parse, from [`Parser::parse_file_with_stats`]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseStats {
+    /// Size of the input as read
+    pub bytes: usize,
+    /// Start tags in the input, envelopes and skipped elements included
+    pub elements: usize,
+    pub facts: usize,
+    pub contexts: usize,
+    pub units: usize,
+    /// Reading (or mapping) the file
+    pub read: Duration,
+    /// Detecting the encoding and transcoding non-UTF-8 input
+    pub decode: Duration,
+    /// Scanning the markup into contexts, units and facts
+    pub scan: Duration,
+    /// Resolving context and unit references and footnotes
+    pub resolve: Duration,
+    /// Strict validation, zero unless [`Parser::with_validation`] is on
+    pub validate: Duration,
+    pub total: Duration,
+    /// Bytes the parse arena reserved
+    pub arena_bytes: usize,
+}
+
+impl ParseStats {
+    pub fn bytes_per_sec(&self) -> f64 {
+        self.bytes as f64 / self.total.as_secs_f64()
+    }
+
+    pub fn facts_per_sec(&self) -> f64 {
+        self.facts as f64 / self.total.as_secs_f64()
+    }
+}
+
+#[derive(Clone)]
 pub struct Parser {
-    allocator: ArenaAllocator,
+    options: ParserOptions,
     parallel: bool,
     validate: bool,
     load_schemas: bool,
     load_linkbases: bool,
+    error_recovery: bool,
+    interner: Option<StringInterner>,
+    #[cfg(feature = "parallel")]
+    pool: Option<std::sync::Arc<rayon::ThreadPool>>,
 }
 
 impl Parser {
     pub fn new() -> Self {
         Self {
-            allocator: ArenaAllocator::new(),
+            options: ParserOptions::default(),
             parallel: true,
             validate: false,
             load_schemas: false,
             load_linkbases: false,
+            error_recovery: false,
+            interner: None,
+            #[cfg(feature = "parallel")]
+            pool: None,
         }
     }
 
+    pub fn with_options(mut self, options: ParserOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Fail parsing when strict validation finds errors
     pub fn with_validation(mut self, validate: bool) -> Self {
         self.validate = validate;
         self
     }
 
+    /// Use rayon in [`Parser::parse_files`] (requires the `parallel` feature)
     pub fn with_parallel(mut self, parallel: bool) -> Self {
         self.parallel = parallel;
         self
     }
 
+    /// Run [`Parser::parse_files`] in `pool` instead of rayon's global pool;
+    /// implies [`Parser::with_parallel`]. See [`crate::pool`].
+    #[cfg(feature = "parallel")]
+    pub fn with_thread_pool(mut self, pool: std::sync::Arc<rayon::ThreadPool>) -> Self {
+        self.parallel = true;
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Load the schemas of the instance's DTS (`schemaRef` and everything it
+    /// imports or includes). Remote documents not in the offline catalog are
+    /// skipped rather than fetched.
     pub fn with_schema_loading(mut self, load: bool) -> Self {
         self.load_schemas = load;
         self
     }
 
+    /// Load the linkbases of the instance's DTS. Remote documents not in the
+    /// offline catalog are skipped rather than fetched.
     pub fn with_linkbase_loading(mut self, load: bool) -> Self {
         self.load_linkbases = load;
         self
     }
 
+    /// Skip elements that fail to parse instead of failing the document.
+    /// Each skipped element is recorded in [`Document::diagnostics`].
+    pub fn with_error_recovery(mut self, recover: bool) -> Self {
+        self.error_recovery = recover;
+        self
+    }
+
+    /// Intern concept QNames into `interner` instead of a per-document
+    /// table, so documents parsed with the same handle share one copy
+    pub fn with_interner(mut self, interner: StringInterner) -> Self {
+        self.interner = Some(interner);
+        self
+    }
+
+    pub fn parse_str(&self, content: &str) -> Result<Document> {
+        self.parse_bytes(content.as_bytes())
+    }
+
     pub fn parse_file<P: AsRef<Path>>(&self, path: P) -> Result<Document> {
         let path = path.as_ref();
-        let content = std::fs::read(path)?;
-        self.parse_bytes_with_path(&content, Some(path.to_path_buf()))
+        let content = read_input(path)?;
+        Ok(self
+            .parse_bytes_with_path(&content, Some(path))?
+            .with_source_uri(path.display().to_string()))
+    }
+
+    /// [`Parser::parse_file`], also returning how long each phase took and
+    /// how much was read and allocated
+    pub fn parse_file_with_stats<P: AsRef<Path>>(&self, path: P) -> Result<(Document, ParseStats)> {
+        let start = Instant::now();
+        let path = path.as_ref();
+        let content = read_input(path)?;
+        let mut stats = ParseStats {
+            read: start.elapsed(),
+            ..ParseStats::default()
+        };
+        let doc = self
+            .parse_with(&content, Some(path), None, Some(&mut stats))?
+            .with_source_uri(path.display().to_string());
+        stats.total = start.elapsed();
+        Ok((doc, stats))
+    }
+
+    /// Parse a file, handing contexts, units, facts, footnotes and tuples to
+    /// `handler` as they are read instead of collecting them.
+    ///
+    /// Facts, footnotes and tuples are not kept, so huge instances can be
+    /// aggregated without materializing them. The returned document holds
+    /// the rest: contexts, units, namespaces and the schemaRef. The file is
+    /// read as parsing goes, see [`Parser::parse_reader_streaming`], and
+    /// diagnostics carry no line numbers.
+    pub fn parse_streaming<P: AsRef<Path>, H: ParseHandler>(
+        &self,
+        path: P,
+        handler: &mut H,
+    ) -> Result<Document> {
+        let path = path.as_ref();
+        let reader =
+            std::io::BufReader::with_capacity(STREAM_READ_BYTES, std::fs::File::open(path)?);
+        Ok(self
+            .stream_reader(reader, Some(path), handler)?
+            .with_source_uri(path.display().to_string()))
+    }
+
+    /// [`Parser::parse_streaming`] over a reader.
+    ///
+    /// The input is read as parsing goes: whenever the reader has to be
+    /// asked for more, the children of the xbrl root read completely so far
+    /// are parsed and dropped, so memory holds the prolog, one element in
+    /// progress and the contexts and units. UTF-16 input, taxonomy documents
+    /// and input without an xbrl root are read whole and parsed as by
+    /// [`Parser::parse_bytes_streaming`].
+    pub fn parse_reader_streaming<R: BufRead, H: ParseHandler>(
+        &self,
+        reader: R,
+        handler: &mut H,
+    ) -> Result<Document> {
+        self.stream_reader(reader, None, handler)
+    }
+
+    /// [`Parser::parse_streaming`] over bytes
+    pub fn parse_bytes_streaming<H: ParseHandler>(
+        &self,
+        data: &[u8],
+        handler: &mut H,
+    ) -> Result<Document> {
+        self.parse_with(data, None, Some(handler), None)
+    }
+
+    /// Parse several files, in parallel when the `parallel` feature is on.
+    /// Results are always returned in the order of `paths`.
+    pub fn parse_files<P: AsRef<Path> + Sync>(&self, paths: &[P]) -> Vec<Result<Document>> {
+        #[cfg(feature = "parallel")]
+        if self.parallel {
+            use rayon::prelude::*;
+            return crate::pool::install(self.pool.as_deref(), || {
+                paths.par_iter().map(|p| self.parse_file(p)).collect()
+            });
+        }
+
+        paths.iter().map(|p| self.parse_file(p)).collect()
+    }
+
+    /// Parse every XBRL instance embedded in one stream.
+    ///
+    /// Handles concatenated instances, instances wrapped in envelopes (SOAP
+    /// and similar) and the EDGAR `.txt` dissemination format, where each
+    /// document's payload sits inside an `<XBRL>` block. Inline XBRL payloads
+    /// are parsed with [`InlineParser`].
+    pub fn parse_multi(&self, data: &[u8]) -> Result<Vec<Document>> {
+        let text = String::from_utf8_lossy(data);
+        let mut docs = Vec::new();
+
+        for chunk in embedded_instances(&text) {
+            if is_inline_xbrl(chunk.as_bytes()) {
+                docs.push(
+                    InlineParser::new()
+                        .with_options(self.options.clone())
+                        .parse_bytes(chunk.as_bytes())?,
+                );
+            } else {
+                docs.push(self.parse_bytes(chunk.as_bytes())?);
+            }
+        }
+
+        Ok(docs)
     }
 
     pub fn parse_bytes(&self, data: &[u8]) -> Result<Document> {
         self.parse_bytes_with_path(data, None)
     }
-    
-    fn parse_bytes_with_path(&self, data: &[u8], path: Option<std::path::PathBuf>) -> Result<Document> {
-        // Skip BOM if present
-        let data = if data.starts_with(&[0xEF, 0xBB, 0xBF]) {
-            &data[3..]
+
+    fn parse_bytes_with_path(&self, data: &[u8], path: Option<&Path>) -> Result<Document> {
+        self.parse_with(data, path, None, None)
+    }
+
+    fn stream_reader<R: BufRead>(
+        &self,
+        reader: R,
+        path: Option<&Path>,
+        handler: &mut dyn ParseHandler,
+    ) -> Result<Document> {
+        let mut input = StreamInput::new(reader);
+        while input.data.len() < 4 && input.fill()? {}
+        if input.data.starts_with(b"\xEF\xBB\xBF") {
+            input.skip_bom();
+        }
+
+        // Find the start tag of the xbrl root, keeping everything before it
+        // and the envelope elements it is nested in
+        let mut ancestors: Vec<Vec<u8>> = Vec::new();
+        let mut document_element = true;
+        let mut pos = 0;
+        let ascii_compatible = input_encoding(&input.data).0.is_ascii_compatible();
+        let root = loop {
+            if !ascii_compatible {
+                break None;
+            }
+            let Some((markup, end)) = next_markup(&input.data, pos) else {
+                if input.fill()? {
+                    continue;
+                }
+                break None;
+            };
+            pos = end;
+            match markup {
+                Markup::Start { name, .. }
+                    if document_element
+                        && matches!(local_name_bytes(name), b"schema" | b"linkbase") =>
+                {
+                    break None
+                }
+                Markup::Start { name, empty } if is_xbrl_root_bytes(name) => {
+                    break (!empty).then(|| name.to_vec())
+                }
+                Markup::Start { name, empty } => {
+                    document_element = false;
+                    if !empty {
+                        ancestors.push(name.to_vec());
+                    }
+                }
+                Markup::End => {
+                    ancestors.pop();
+                }
+                Markup::Other => {}
+            }
+        };
+        let head = &input.data[..pos];
+        let Some(root) = root.filter(|_| {
+            declared_encoding(head).is_none_or(encoding_rs::Encoding::is_ascii_compatible)
+        }) else {
+            input.read_to_end()?;
+            let mut doc = self.parse_with(&input.data, path, Some(handler), None)?;
+            doc.provenance = Some(input.provenance());
+            return Ok(doc);
+        };
+
+        // Each batch of complete children is parsed as its own instance:
+        // the head, the children and the end tags of the root and envelopes
+        let mut batch = Batch {
+            head: head.to_vec(),
+            tail: Vec::new(),
+            origin: SourceOrigin::of_head(head, input.offset),
+        };
+        for name in std::iter::once(&root).chain(ancestors.iter().rev()) {
+            batch.tail.extend_from_slice(b"</");
+            batch.tail.extend_from_slice(name);
+            batch.tail.push(b'>');
+        }
+        input.consume(pos);
+        let parser = Parser {
+            validate: false,
+            ..self.clone()
+        };
+
+        let mut doc = None;
+        let mut depth = 0usize;
+        let mut pos = 0;
+        let mut ready = 0;
+        let closed = loop {
+            let Some((markup, end)) = next_markup(&input.data, pos) else {
+                // Parse what is complete before waiting for more input
+                if ready > 0 {
+                    batch.origin.at(&input);
+                    parser.parse_batch(&batch, &input.data[..ready], path, handler, &mut doc)?;
+                    input.consume(ready);
+                    pos -= ready;
+                    ready = 0;
+                }
+                if input.fill()? {
+                    continue;
+                }
+                break false;
+            };
+            match markup {
+                Markup::Start { empty: false, .. } => depth += 1,
+                Markup::End if depth == 0 => break true,
+                Markup::End => depth -= 1,
+                _ => {}
+            }
+            pos = end;
+            if depth == 0 {
+                ready = pos;
+            }
+        };
+        if ready > 0 || doc.is_none() {
+            batch.origin.at(&input);
+            parser.parse_batch(&batch, &input.data[..ready], path, handler, &mut doc)?;
+            input.consume(ready);
+        }
+        let mut doc = doc.unwrap_or_default();
+
+        if !closed {
+            let at_byte = input.offset + input.data.len();
+            if !self.error_recovery {
+                return Err(Error::Truncated { at_byte });
+            }
+            doc.diagnostics.push(ParseDiagnostic {
+                location: SourcePosition {
+                    offset: input.offset,
+                    line: input.line,
+                    column: input.column,
+                },
+                message: format!(
+                    "Input ends at byte {} before the xbrl root is closed",
+                    at_byte
+                ),
+                severity: crate::Severity::Error,
+            });
+        }
+        input.skip_to_end()?;
+        doc.provenance = Some(input.provenance());
+
+        if self.validate {
+            crate::validator::XbrlValidator::new()
+                .strict()
+                .validate(&doc)?;
+        }
+        Ok(doc)
+    }
+
+    /// Parse the complete children `children` as one instance and add its
+    /// contexts, units and diagnostics to `doc`, or make it `doc`
+    fn parse_batch(
+        &self,
+        batch: &Batch,
+        children: &[u8],
+        path: Option<&Path>,
+        handler: &mut dyn ParseHandler,
+        doc: &mut Option<Document>,
+    ) -> Result<()> {
+        let mut data = Vec::with_capacity(batch.head.len() + children.len() + batch.tail.len());
+        data.extend_from_slice(&batch.head);
+        data.extend_from_slice(children);
+        data.extend_from_slice(&batch.tail);
+        let schemas = doc.as_ref().map(|doc| &doc.schemas);
+        let mut part = self
+            .parse_with_schemas(&data, path, Some(handler), None, schemas)
+            .map_err(|e| batch.origin.shift_error(e))?;
+
+        let first = doc.is_none();
+        let head_len = batch.head.len();
+        part.diagnostics
+            .retain(|d| first || d.location.offset >= head_len);
+        for diagnostic in &mut part.diagnostics {
+            let offset = batch.origin.shift(diagnostic.location).offset;
+            diagnostic.location = SourceMap::default().position(offset);
+        }
+        let Some(doc) = doc else {
+            *doc = Some(part);
+            return Ok(());
+        };
+
+        // Keep the document of the batch with the schemaRef, which holds the
+        // DTS, with what the batches before it found in front
+        if doc.schema_ref.is_none() && part.schema_ref.is_some() {
+            std::mem::swap(doc, &mut part);
+            std::mem::swap(&mut doc.contexts, &mut part.contexts);
+            std::mem::swap(&mut doc.units, &mut part.units);
+            std::mem::swap(&mut doc.diagnostics, &mut part.diagnostics);
+        }
+        doc.contexts.extend(part.contexts.iter().cloned());
+        doc.units.extend(part.units.iter().cloned());
+        for (prefix, uri) in part.namespaces {
+            doc.namespaces.entry(prefix).or_insert(uri);
+        }
+        doc.diagnostics.append(&mut part.diagnostics);
+        for unsupported in part.unsupported {
+            if !doc.unsupported.contains(&unsupported) {
+                doc.unsupported.push(unsupported);
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_with(
+        &self,
+        data: &[u8],
+        path: Option<&Path>,
+        handler: Option<&mut dyn ParseHandler>,
+        stats: Option<&mut ParseStats>,
+    ) -> Result<Document> {
+        self.parse_with_schemas(data, path, handler, stats, None)
+    }
+
+    /// [`Parser::parse_with`], typing facts by `schemas` when the DTS was
+    /// loaded by an earlier batch of the same input
+    fn parse_with_schemas(
+        &self,
+        data: &[u8],
+        path: Option<&Path>,
+        handler: Option<&mut dyn ParseHandler>,
+        stats: Option<&mut ParseStats>,
+        schemas: Option<&Shared<Vec<Schema>>>,
+    ) -> Result<Document> {
+        // UTF-8 input is parsed in place, BOM skipped; anything else is
+        // transcoded first and offsets then refer to the transcoded text
+        let start = Instant::now();
+        let decoded = decode_input(data)?;
+        let decoded_at = Instant::now();
+        let (source, body) = match &decoded {
+            std::borrow::Cow::Borrowed(body) => (data, *body),
+            std::borrow::Cow::Owned(text) => (text.as_slice(), text.as_slice()),
+        };
+
+        // Taxonomy documents become a document without facts that holds the
+        // schema or the linkbase's relationships
+        let taxonomy = match detect_input(body) {
+            InputKind::Instance => None,
+            InputKind::Schema => {
+                let mut doc = Document::new();
+                doc.add_schema(crate::dts::parse_schema(body)?);
+                Some(doc)
+            }
+            InputKind::Linkbase => {
+                let mut doc = Document::new();
+                let mut processor = crate::linkbase::LinkbaseProcessor::new();
+                processor.parse_linkbase(body)?;
+                processor.apply_to(&mut doc);
+                Some(doc)
+            }
+        };
+        if let Some(mut doc) = taxonomy {
+            doc.provenance = Some(Provenance::from_bytes(data));
+            if let Some(stats) = stats {
+                stats.decode = decoded_at - start;
+                stats.scan = decoded_at.elapsed();
+                stats.fill(data, body, &doc, 0);
+            }
+            return Ok(doc);
+        }
+
+        // One arena per document keeps `Parser` Sync; the interner may be
+        // shared across documents. Streaming keeps nothing per fact, so it
+        // neither sizes the arena by the input nor indexes its lines.
+        let streaming = handler.is_some();
+        let capacity = if streaming {
+            body.len().min(STREAMING_ARENA_BYTES)
         } else {
-            data
+            body.len()
+        };
+        let allocator = match &self.interner {
+            Some(interner) => ArenaAllocator::with_interner(capacity, interner.clone()),
+            None => ArenaAllocator::with_capacity(capacity),
         };
-        
-        let mut parser = FullXbrlParser::new(data, &self.allocator);
-        parser.validate = self.validate;
+        let mut parser = FullXbrlParser::new(body, &allocator, &self.options);
+        parser.source_base = source.len() - body.len();
+        if !streaming {
+            parser.source_map = SourceMap::new(source);
+        }
         parser.load_schemas = self.load_schemas;
         parser.load_linkbases = self.load_linkbases;
-        parser.file_path = path;
-        parser.parse()
+        parser.error_recovery = self.error_recovery;
+        parser.base_path = path;
+        parser.handler = handler.map(|h| h as &mut dyn ParseHandler);
+        if let Some(schemas) = schemas {
+            parser.doc.schemas = schemas.clone();
+        }
+
+        parser.scan()?;
+        let scanned_at = Instant::now();
+        let mut doc = parser.finish();
+        doc.provenance = Some(Provenance::from_bytes(data));
+        let resolved_at = Instant::now();
+
+        let mut validate = Duration::ZERO;
+        if self.validate {
+            crate::validator::XbrlValidator::new()
+                .strict()
+                .validate(&doc)?;
+            validate = resolved_at.elapsed();
+        }
+
+        if let Some(stats) = stats {
+            stats.decode = decoded_at - start;
+            stats.scan = scanned_at - decoded_at;
+            stats.resolve = resolved_at - scanned_at;
+            stats.validate = validate;
+            stats.fill(data, body, &doc, allocator.allocated_bytes());
+        }
+        Ok(doc)
+    }
+}
+
+impl ParseStats {
+    /// Sizes and counts of a finished parse; the elements are counted here,
+    /// in a pass of their own, rather than while scanning
+    fn fill(&mut self, data: &[u8], body: &[u8], doc: &Document, arena_bytes: usize) {
+        self.bytes = data.len();
+        self.elements = memchr::memchr_iter(b'<', body)
+            .filter(|&i| !matches!(body.get(i + 1), Some(b'/' | b'?' | b'!')))
+            .count();
+        self.facts = doc.facts.len();
+        self.contexts = doc.contexts.len();
+        self.units = doc.units.len();
+        self.arena_bytes = arena_bytes;
     }
 }
 
 struct FullXbrlParser<'a> {
     scanner: SimdScanner<'a>,
     allocator: &'a ArenaAllocator,
+    options: &'a ParserOptions,
     doc: Document,
+    // Prefixes of the document's QNames (prefix to URI): the bindings in
+    // scope at the root plus those introduced for redeclared prefixes
+    namespaces: HashMap<&'a str, &'a str>,
+    scopes: NamespaceScopes<'a>,
+    // Bindings in scope at the root; QNames are only rewritten beyond it
+    root_scope: usize,
     in_xbrl_root: bool,
-    current_tuple_stack: Vec<Tuple>,
-    validate: bool,
+    // Open tuples with the tag name that closes them
+    current_tuple_stack: Vec<(&'a str, Tuple)>,
+    // contextRef / unitRef per stored fact; contexts and units may follow
+    // the facts that use them, so ids are resolved at the end
+    fact_refs: Vec<(&'a str, Option<&'a str>)>,
+    // Concept QName to document concept id; the (possibly shared) interner
+    // is only consulted the first time a name appears in the document
+    concept_ids: HashMap<&'a str, u32>,
+    source_map: SourceMap,
+    // Offset of the input within the source (a stripped byte-order mark)
+    source_base: usize,
+    // Source offset of the `<` of the element being parsed
+    element_start: usize,
     load_schemas: bool,
     load_linkbases: bool,
-    file_path: Option<std::path::PathBuf>,
+    error_recovery: bool,
+    // Instance file that relative schemaRefs resolve against
+    base_path: Option<&'a Path>,
+    // Set when streaming: receives facts, footnotes and tuples instead of
+    // the document
+    handler: Option<&'a mut dyn ParseHandler>,
 }
 
 // Include base parsing methods
 include!("parser_base.rs");
 
 impl<'a> FullXbrlParser<'a> {
-    fn new(data: &'a [u8], allocator: &'a ArenaAllocator) -> Self {
+    fn new(data: &'a [u8], allocator: &'a ArenaAllocator, options: &'a ParserOptions) -> Self {
         Self {
             scanner: SimdScanner::new(data),
             allocator,
+            options,
             doc: Document::new(),
+            namespaces: HashMap::new(),
+            scopes: NamespaceScopes::default(),
+            root_scope: 0,
             in_xbrl_root: false,
             current_tuple_stack: Vec::new(),
-            validate: false,
+            fact_refs: Vec::new(),
+            concept_ids: HashMap::new(),
+            source_map: SourceMap::default(),
+            source_base: 0,
+            element_start: 0,
             load_schemas: false,
             load_linkbases: false,
-            file_path: None,
+            error_recovery: false,
+            base_path: None,
+            handler: None,
         }
     }
 
-    fn parse(&mut self) -> Result<Document> {
-        self.scanner.skip_whitespace();
-        
-        while !self.scanner.is_eof() {
-            self.scanner.skip_whitespace();
-            
-            if self.scanner.peek() != Some(b'<') {
-                // Skip text content between tags
-                while self.scanner.peek() != Some(b'<') && !self.scanner.is_eof() {
-                    self.scanner.advance(1);
-                }
-                continue;
-            }
-            
-            self.scanner.advance(1); // consume '<'
-            
-            if self.scanner.peek() == Some(b'?') {
-                self.skip_processing_instruction()?;
-            } else if self.scanner.peek() == Some(b'!') {
-                if self.peek_ahead(3) == Some(b"!--") {
-                    self.skip_comment()?;
-                } else if self.peek_ahead(8) == Some(b"![CDATA[") {
-                    // We're in an element, handle CDATA
-                    continue;
-                } else {
-                    self.skip_doctype()?;
+    /// Read the elements up to the end of the xbrl root
+    fn scan(&mut self) -> Result<()> {
+        let mut closed = false;
+        while let Some(pos) = self.scanner.find_next(b'<') {
+            self.scanner.pos = pos + 1;
+            let depth = self.scopes.frames.len();
+
+            let step = match self.scanner.peek() {
+                Some(b'?') => self.skip_processing_instruction().map(|_| false),
+                Some(b'!') => self.skip_markup_declaration().map(|_| false),
+                Some(b'/') => self.parse_end_tag(),
+                _ => self.parse_element(),
+            };
+            match step {
+                Ok(true) => {
+                    closed = true;
+                    break;
                 }
-            } else if self.scanner.peek() == Some(b'/') {
-                // Closing tag
-                self.scanner.advance(1); // consume '/'
-                let tag_name = self.read_tag_name()?;
-                self.skip_to_tag_end()?;
-                
-                // Check if we're closing the xbrl root
-                if tag_name == "xbrl" || tag_name.ends_with(":xbrl") {
-                    self.in_xbrl_root = false;
-                    break; // Done parsing
+                Ok(false) => {}
+                // An element the input ends in; everything before it stands
+                Err(Error::XmlSyntax { position: None, .. }) if self.cut_off_at(pos) => {
+                    return self.truncated(Some(pos));
                 }
-                
-                // Check if we're closing a tuple
-                if !self.current_tuple_stack.is_empty() {
-                    let last_tuple = self.current_tuple_stack.last().unwrap();
-                    if tag_name == last_tuple.name || tag_name.ends_with(&format!(":{}", last_tuple.name)) {
-                        let tuple = self.current_tuple_stack.pop().unwrap();
-                        
-                        if self.current_tuple_stack.is_empty() {
-                            self.document.tuples.push(tuple);
-                        } else {
-                            let parent = self.current_tuple_stack.last_mut().unwrap();
-                            parent.facts.push(FactOrTuple::Tuple(Box::new(tuple)));
-                        }
-                    }
+                Err(Error::XmlSyntax {
+                    message,
+                    position: None,
+                    ..
+                }) if self.error_recovery => self.skip_malformed(pos, depth, message),
+                Err(Error::Namespace { message, .. }) if self.error_recovery => {
+                    self.skip_malformed(pos, depth, message)
                 }
-            } else {
-                // Opening tag
-                self.parse_element()?;
+                Err(Error::XmlSyntax {
+                    message,
+                    position: None,
+                    ..
+                }) => return Err(self.locate(message)),
+                Err(e) => return Err(e),
             }
         }
-        
-        // Perform validation if requested
-        if self.validate {
-            self.document.validate();
+        if self.in_xbrl_root && !closed {
+            return self.truncated(None);
+        }
+        if !self.in_xbrl_root {
+            return self.missing_root();
         }
-        
-        Ok(std::mem::take(&mut self.document))
+        Ok(())
     }
 
-    fn parse_element(&mut self) -> Result<()> {
-        let tag_name = self.read_tag_name()?;
-        
-        // Check for xbrl root element
-        if tag_name == "xbrl" || tag_name.ends_with(":xbrl") {
-            self.parse_xbrl_root()?;
-            self.in_xbrl_root = true;
-            return Ok(());
+    /// The input has no xbrl root: truncated when it ends before any
+    /// element starts, a syntax error at the document element otherwise
+    fn missing_root(&mut self) -> Result<()> {
+        let data = self.scanner.data;
+        if only_prolog(data) {
+            return self.truncated(None);
         }
-        
-        // Only parse these elements if we're inside xbrl root
+        let mut pos = 0;
+        let element = loop {
+            match next_markup(data, pos) {
+                Some((Markup::Start { name, .. }, _)) => break Some(name),
+                Some((_, end)) => pos = end,
+                None => break None,
+            }
+        };
+        let message = match element {
+            _ if is_inline_xbrl(data) => {
+                "Inline XBRL document; parse it with InlineParser".to_string()
+            }
+            Some(name) => format!(
+                "No xbrl root element; the document element is <{}>",
+                String::from_utf8_lossy(name)
+            ),
+            None => "No xbrl root element; the input holds no XML elements".to_string(),
+        };
+        self.scanner.pos = if element.is_some() { pos } else { 0 };
+        Err(self.locate(message))
+    }
+
+    /// Whether the input ends inside the element whose `<` is at `pos`: no
+    /// end tag of the xbrl root it belongs to follows, or, before the root,
+    /// nothing but the markup at `pos` does
+    fn cut_off_at(&self, pos: usize) -> bool {
+        let rest = &self.scanner.data[pos..];
         if !self.in_xbrl_root {
-            self.skip_element_from_tag()?;
-            return Ok(());
+            return next_markup(rest, 0).is_none();
+        }
+        !memchr::memmem::find_iter(rest, b"</").any(|at| {
+            let name = &rest[at + 2..];
+            let end = name
+                .iter()
+                .position(|b| b.is_ascii_whitespace() || *b == b'>')
+                .unwrap_or(name.len());
+            end < name.len() && std::str::from_utf8(&name[..end]).is_ok_and(is_xbrl_root)
+        })
+    }
+
+    /// [`Error::Truncated`], or with error recovery a diagnostic at the
+    /// start of the element cut off, or at the end when none was
+    fn truncated(&mut self, element: Option<usize>) -> Result<()> {
+        let at_byte = self.source_base + self.scanner.data.len();
+        if !self.error_recovery {
+            return Err(Error::Truncated { at_byte });
         }
-        
-        // Parse XBRL elements
-        if tag_name.ends_with(":context") || tag_name == "context" {
-            self.parse_context()?;
-        } else if tag_name.ends_with(":unit") || tag_name == "unit" {
-            self.parse_unit()?;
-        } else if tag_name.ends_with(":schemaRef") || tag_name == "schemaRef" {
-            self.parse_schema_ref()?;
-        } else if tag_name.ends_with(":footnoteLink") || tag_name == "footnoteLink" {
-            self.parse_footnote_link()?;
-        } else if tag_name.contains(':') {
-            // This could be a fact or a tuple
-            // Check if it's a known non-fact element (but allow xbrli:context and xbrli:unit)
-            let is_structural = tag_name.starts_with("link:") || 
-                                tag_name.starts_with("xbrldi:") ||
-                                (tag_name.starts_with("xbrli:") && 
-                                 !tag_name.ends_with(":context") && 
-                                 !tag_name.ends_with(":unit"));
-            if !is_structural {
-                // Try to determine if it's a tuple by looking ahead
-                if self.is_tuple(&tag_name) {
-                    self.parse_tuple(tag_name)?;
+        self.doc.diagnostics.push(ParseDiagnostic {
+            location: self
+                .source_map
+                .position(element.map_or(at_byte, |pos| self.source_base + pos)),
+            message: format!(
+                "Input ends at byte {} before the xbrl root is {}",
+                at_byte,
+                if self.in_xbrl_root {
+                    "closed"
                 } else {
-                    self.parse_fact(tag_name)?;
+                    "opened"
                 }
-            } else {
-                self.skip_element_from_tag()?;
-            }
-        } else {
-            self.skip_element_from_tag()?;
-        }
-        
+            ),
+            severity: crate::Severity::Error,
+        });
         Ok(())
     }
 
-    fn parse_context(&mut self) -> Result<()> {
-        let attrs = self.parse_attributes()?;
-        let id = attrs.iter()
-            .find(|(n, _)| *n == "id")
-            .map(|(_, v)| CompactString::from(*v))
-            .ok_or_else(|| Error::Parse("Context missing id".to_string()))?;
-        
+    /// The document, with references between what was scanned resolved
+    fn finish(mut self) -> Document {
+        self.resolve_fact_refs();
+        self.doc.resolve_footnotes();
+        self.doc.namespaces = self
+            .namespaces
+            .iter()
+            .filter(|(prefix, _)| !prefix.is_empty())
+            .map(|(prefix, uri)| (prefix.to_string(), uri.to_string()))
+            .collect();
+        self.doc.source_map = Some(self.source_map);
+        self.doc
+    }
+
+    /// Consume an end tag; true once the xbrl root is closed
+    fn parse_end_tag(&mut self) -> Result<bool> {
+        self.scanner.advance(1);
+        let tag_name = self.read_tag_name()?;
         self.skip_to_tag_end()?;
-        
-        // Initialize context components
+
+        if is_xbrl_root(tag_name) {
+            return Ok(true);
+        }
+        // Only tuples are left open by `parse_element`
+        self.close_tuple(tag_name)?;
+        Ok(false)
+    }
+
+    /// `message` as an [`Error::XmlSyntax`] at the scanner position. Lines are
+    /// counted here, on the error path, rather than tracked while scanning.
+    fn locate(&self, message: String) -> Error {
+        const CONTEXT: usize = 40;
+        let data = self.scanner.data;
+        let pos = self.scanner.pos.min(data.len());
+        let line_start = memchr::memrchr(b'\n', &data[..pos]).map_or(0, |i| i + 1);
+        let line_end = memchr::memchr(b'\n', &data[pos..]).map_or(data.len(), |i| pos + i);
+        let from = line_start.max(pos.saturating_sub(CONTEXT));
+        let to = line_end.min(pos + CONTEXT);
+        Error::XmlSyntax {
+            message,
+            position: Some(SourcePosition {
+                offset: self.source_base + pos,
+                line: memchr::memchr_iter(b'\n', &data[..pos]).count() + 1,
+                column: pos - line_start + 1,
+            }),
+            snippet: Some(String::from_utf8_lossy(&data[from..to]).trim().to_string()),
+        }
+    }
+
+    /// Record a diagnostic for the element whose `<` is at `pos` and move
+    /// past it, or past its start tag if even that cannot be skipped
+    fn skip_malformed(&mut self, pos: usize, depth: usize, message: String) {
+        let location = self.source_map.position(self.source_base + pos);
+        self.doc.diagnostics.push(ParseDiagnostic {
+            location,
+            message,
+            severity: crate::Severity::Error,
+        });
+        self.scopes.truncate(depth);
+
+        self.scanner.pos = pos + 1;
+        let skipped = match self.scanner.peek() {
+            Some(b'/' | b'?' | b'!') => false,
+            _ => self
+                .read_tag_name()
+                .and_then(|_| self.skip_element_from_tag())
+                .is_ok(),
+        };
+        if !skipped {
+            self.scanner.pos = pos + 1;
+        }
+    }
+
+    /// Parse one element; true when it is an empty, self-closing xbrl root
+    fn parse_element(&mut self) -> Result<bool> {
+        let tag_name = self.read_tag_name()?;
+        self.element_start = self.source_base + self.scanner.pos - tag_name.len() - 1;
+
+        if is_xbrl_root(tag_name) {
+            self.in_xbrl_root = true;
+            return self.parse_xbrl_root();
+        }
+
+        // Descend through envelopes until the xbrl root is found
+        if !self.in_xbrl_root {
+            self.skip_to_tag_end()?;
+            return Ok(false);
+        }
+
+        let attrs = self.parse_attributes()?;
+        self.scopes.enter(&attrs);
+
+        // Items carry a contextRef; anything else is a tuple, which stays
+        // open (with its scope) until the main loop sees its end tag
+        if !self.is_structural(tag_name) && attr(&attrs, "contextRef").is_none() {
+            self.parse_tuple(tag_name, &attrs)?;
+            return Ok(false);
+        }
+
+        if self.is_named(tag_name, XBRLI_NS, "context") {
+            self.parse_context(&attrs)?;
+        } else if self.is_named(tag_name, XBRLI_NS, "unit") {
+            self.parse_unit(&attrs)?;
+        } else if self.is_named(tag_name, LINK_NS, "schemaRef") {
+            self.parse_schema_ref(&attrs)?;
+        } else if self.is_named(tag_name, LINK_NS, "footnoteLink") {
+            self.parse_footnote_link(&attrs)?;
+        } else if self.is_structural(tag_name) {
+            self.skip_element_from_tag()?;
+        } else {
+            self.check_prefix(tag_name, "fact")?;
+            self.parse_fact(tag_name, &attrs)?;
+        }
+        self.scopes.leave();
+        Ok(false)
+    }
+
+    fn parse_context(&mut self, attrs: &[(&'a str, &'a str)]) -> Result<()> {
+        let start = self.element_start;
+        let id = attr(attrs, "id")
+            .ok_or_else(|| Error::xml("Context missing id"))?
+            .to_string();
+
+        if self.finish_start_tag()? {
+            return Ok(());
+        }
+
         let mut entity = None;
         let mut period = None;
         let mut scenario = None;
-        
-        // Parse context children
-        loop {
-            self.scanner.skip_whitespace();
-            
-            // Skip any text content
-            while self.scanner.peek() != Some(b'<') && !self.scanner.is_eof() {
-                self.scanner.advance(1);
-            }
-            
-            if self.scanner.is_eof() {
-                break;
-            }
-            
-            let saved_pos = self.scanner.pos;
-            self.scanner.advance(1); // consume '<'
-            
-            if self.scanner.peek() == Some(b'/') {
-                // Closing tag - check if it's our context
-                self.scanner.advance(1);
-                let tag = self.read_tag_name()?;
-                if tag.ends_with("context") || tag == "context" {
-                    self.skip_to_tag_end()?;
-                    break;
+
+        while let Some(tag) = self.next_child()? {
+            match local_name(tag) {
+                "entity" => entity = Some(self.parse_entity()?),
+                "period" => period = self.parse_period()?,
+                "scenario" => {
+                    let (explicit_members, typed_members) = self.parse_members()?;
+                    scenario = Some(Scenario {
+                        explicit_members,
+                        typed_members,
+                    });
                 }
-                // Not our closing tag, restore and skip this element
-                self.scanner.pos = saved_pos;
-                break;
-            }
-            
-            // Parse child element
-            let tag = self.read_tag_name()?;
-            
-            if tag.ends_with("entity") {
-                entity = Some(self.parse_entity()?);
-            } else if tag.ends_with("period") {
-                period = Some(self.parse_period()?);
-            } else if tag.ends_with("scenario") {
-                scenario = Some(self.parse_scenario()?);
-            } else {
-                self.skip_element_from_tag()?;
+                _ => self.skip_element_from_tag()?,
             }
         }
-        
+
         if let (Some(entity), Some(period)) = (entity, period) {
-            self.document.contexts.push(Context {
+            // Context ids are u32 with u32::MAX marking unresolved references
+            if self.doc.contexts.len() >= UNRESOLVED as usize {
+                return Err(Error::Limit {
+                    what: "contexts",
+                    max: UNRESOLVED as usize,
+                });
+            }
+            self.source_map.context_offsets.push(start);
+            self.source_map.ids.push((id.clone(), start));
+            self.doc.contexts.push(Context {
                 id,
                 entity,
                 period,
                 scenario,
             });
+            if let (Some(handler), Some(context)) = (&mut self.handler, self.doc.contexts.last()) {
+                handler.handle(ParseEvent::Context(context))?;
+            }
         }
-        
+
         Ok(())
     }
 
     fn parse_entity(&mut self) -> Result<Entity> {
-        let _attrs = self.parse_attributes()?;
-        self.skip_to_tag_end()?;
-        
-        let mut identifier = CompactString::new("");
-        let mut scheme = CompactString::new("");
+        let mut identifier = String::new();
+        let mut scheme = String::new();
         let mut segment = None;
-        
-        // Parse entity children
-        loop {
-            self.scanner.skip_whitespace();
-            
-            // Skip any text content
-            while self.scanner.peek() != Some(b'<') && !self.scanner.is_eof() {
-                self.scanner.advance(1);
-            }
-            
-            if self.scanner.is_eof() {
-                break;
-            }
-            
-            let saved_pos = self.scanner.pos;
-            self.scanner.advance(1); // consume '<'
-            
-            if self.scanner.peek() == Some(b'/') {
-                // Closing tag
-                self.scanner.advance(1);
-                let tag = self.read_tag_name()?;
-                if tag.ends_with("entity") || tag == "entity" {
-                    self.skip_to_tag_end()?;
-                    break;
+
+        let attrs = self.parse_attributes()?;
+        if self.finish_start_tag()? {
+            return Ok(Entity {
+                identifier,
+                scheme,
+                segment,
+            });
+        }
+        self.scopes.enter(&attrs);
+
+        while let Some(tag) = self.next_child()? {
+            match local_name(tag) {
+                "identifier" => {
+                    let attrs = self.parse_attributes()?;
+                    scheme = attr(&attrs, "scheme").unwrap_or_default().to_string();
+                    let self_closing = self.finish_start_tag()?;
+                    identifier = self.read_leaf_text(self_closing)?;
                 }
-                self.scanner.pos = saved_pos;
-                break;
-            }
-            
-            let tag = self.read_tag_name()?;
-            
-            if tag.ends_with("identifier") {
-                let attrs = self.parse_attributes()?;
-                scheme = attrs.iter()
-                    .find(|(n, _)| *n == "scheme")
-                    .map(|(_, v)| CompactString::from(*v))
-                    .unwrap_or_default();
-                
-                self.skip_to_tag_end()?;
-                identifier = CompactString::from(self.read_text_content()?);
-                
-                // Skip closing tag
-                self.skip_closing_tag("identifier")?;
-            } else if tag.ends_with("segment") {
-                segment = Some(self.parse_segment()?);
-            } else {
-                self.skip_element_from_tag()?;
+                "segment" => {
+                    let (explicit_members, typed_members) = self.parse_members()?;
+                    segment = Some(Segment {
+                        explicit_members,
+                        typed_members,
+                    });
+                }
+                _ => self.skip_element_from_tag()?,
             }
         }
-        
+        self.scopes.leave();
+
         Ok(Entity {
             identifier,
             scheme,
@@ -353,1200 +1065,2008 @@ impl<'a> FullXbrlParser<'a> {
         })
     }
 
-    fn parse_segment(&mut self) -> Result<Segment> {
-        let _attrs = self.parse_attributes()?;
-        self.skip_to_tag_end()?;
-        
+    /// Dimension members of a segment or scenario
+    fn parse_members(&mut self) -> Result<(Vec<DimensionMember>, Vec<TypedMember>)> {
         let mut explicit_members = Vec::new();
         let mut typed_members = Vec::new();
-        
-        // Parse segment children
-        loop {
-            self.scanner.skip_whitespace();
-            
-            // Skip any text content until we find a tag
-            while self.scanner.peek() != Some(b'<') && !self.scanner.is_eof() {
-                self.scanner.advance(1);
-            }
-            
-            if self.scanner.is_eof() {
-                break;
-            }
-            
-            let saved_pos = self.scanner.pos;
-            self.scanner.advance(1); // consume '<'
-            
-            // Check for comment
-            if self.scanner.peek() == Some(b'!') {
-                if self.peek_ahead(3) == Some(b"!--") {
-                    self.scanner.pos = saved_pos;
-                    self.scanner.advance(1); // skip '<'
-                    self.skip_comment()?;
-                    continue;
+
+        let attrs = self.parse_attributes()?;
+        if self.finish_start_tag()? {
+            return Ok((explicit_members, typed_members));
+        }
+        self.scopes.enter(&attrs);
+
+        while let Some(tag) = self.next_child()? {
+            let attrs = self.parse_attributes()?;
+            self.scopes.enter(&attrs);
+            let dimension = attr(&attrs, "dimension").unwrap_or_default();
+            let dimension = self.canonical_qname(dimension);
+            let self_closing = self.finish_start_tag()?;
+            match local_name(tag) {
+                "explicitMember" => {
+                    let member = self.read_leaf_text(self_closing)?;
+                    let member = self.canonical_qname(&member);
+                    explicit_members.push(DimensionMember { dimension, member });
                 }
-            }
-            
-            if self.scanner.peek() == Some(b'/') {
-                // Closing tag
-                self.scanner.advance(1);
-                let tag = self.read_tag_name()?;
-                if tag.ends_with("segment") || tag == "segment" {
-                    self.skip_to_tag_end()?;
-                    break;
+                "typedMember" => {
+                    // The typed value is XML content; keep its markup
+                    let value = self.read_leaf_text(self_closing)?;
+                    typed_members.push(TypedMember { dimension, value });
                 }
-                // Not our closing tag - should not happen in well-formed XML
-                self.scanner.pos = saved_pos;
-                break;
-            }
-            
-            let tag = self.read_tag_name()?;
-            
-            if tag.ends_with("explicitMember") {
-                let attrs = self.parse_attributes()?;
-                let dimension = attrs.iter()
-                    .find(|(n, _)| *n == "dimension")
-                    .map(|(_, v)| CompactString::from(*v))
-                    .unwrap_or_default();
-                
-                self.skip_to_tag_end()?;
-                let member = CompactString::from(self.read_text_content()?);
-                
-                explicit_members.push(DimensionMember { dimension, member });
-                self.skip_closing_tag("explicitMember")?;
-            } else if tag.ends_with("typedMember") {
-                let attrs = self.parse_attributes()?;
-                let dimension = attrs.iter()
-                    .find(|(n, _)| *n == "dimension")
-                    .map(|(_, v)| CompactString::from(*v))
-                    .unwrap_or_default();
-                
-                self.skip_to_tag_end()?;
-                // Read the entire XML content as typed member value
-                let value = self.read_xml_content_until_closing("typedMember")?;
-                
-                typed_members.push(TypedMember { dimension, value });
-                self.skip_closing_tag("typedMember")?;
-            } else {
-                self.skip_element_from_tag()?;
+                _ => self.skip_content(self_closing)?,
             }
+            self.scopes.leave();
         }
-        
-        Ok(Segment {
-            explicit_members,
-            typed_members,
-        })
+        self.scopes.leave();
+
+        Ok((explicit_members, typed_members))
     }
 
-    fn parse_scenario(&mut self) -> Result<Scenario> {
-        let _attrs = self.parse_attributes()?;
-        self.skip_to_tag_end()?;
-        
-        let mut explicit_members = Vec::new();
-        let mut typed_members = Vec::new();
-        
-        // Parse scenario children (same structure as segment)
-        loop {
-            self.scanner.skip_whitespace();
-            
-            // Skip any text content until we find a tag
-            while self.scanner.peek() != Some(b'<') && !self.scanner.is_eof() {
-                self.scanner.advance(1);
-            }
-            
-            if self.scanner.is_eof() {
-                break;
-            }
-            
-            let saved_pos = self.scanner.pos;
-            self.scanner.advance(1); // consume '<'
-            
-            // Check for comment
-            if self.scanner.peek() == Some(b'!') {
-                if self.peek_ahead(3) == Some(b"!--") {
-                    self.scanner.pos = saved_pos;
-                    self.scanner.advance(1);
-                    self.skip_comment()?;
-                    continue;
-                }
-            }
-            
-            if self.scanner.peek() == Some(b'/') {
-                // Closing tag
-                self.scanner.advance(1);
-                let tag = self.read_tag_name()?;
-                if tag.ends_with("scenario") || tag == "scenario" {
-                    self.skip_to_tag_end()?;
-                    break;
-                }
-                self.scanner.pos = saved_pos;
-                break;
-            }
-            
-            let tag = self.read_tag_name()?;
-            
-            if tag.ends_with("explicitMember") {
-                let attrs = self.parse_attributes()?;
-                let dimension = attrs.iter()
-                    .find(|(n, _)| *n == "dimension")
-                    .map(|(_, v)| CompactString::from(*v))
-                    .unwrap_or_default();
-                
-                self.skip_to_tag_end()?;
-                let member = CompactString::from(self.read_text_content()?);
-                
-                explicit_members.push(DimensionMember { dimension, member });
-                self.skip_closing_tag("explicitMember")?;
-            } else if tag.ends_with("typedMember") {
-                let attrs = self.parse_attributes()?;
-                let dimension = attrs.iter()
-                    .find(|(n, _)| *n == "dimension")
-                    .map(|(_, v)| CompactString::from(*v))
-                    .unwrap_or_default();
-                
-                self.skip_to_tag_end()?;
-                let value = self.read_xml_content_until_closing("typedMember")?;
-                
-                typed_members.push(TypedMember { dimension, value });
-                self.skip_closing_tag("typedMember")?;
-            } else {
-                self.skip_element_from_tag()?;
-            }
+    fn parse_period(&mut self) -> Result<Option<Period>> {
+        self.parse_attributes()?;
+        if self.finish_start_tag()? {
+            return Ok(None);
         }
-        
-        Ok(Scenario {
-            explicit_members,
-            typed_members,
-        })
-    }
 
-    fn parse_period(&mut self) -> Result<Period> {
-        let _attrs = self.parse_attributes()?;
-        self.skip_to_tag_end()?;
-        
         let mut instant = None;
         let mut start_date = None;
         let mut end_date = None;
         let mut forever = false;
-        
-        // Parse period children
-        loop {
-            self.scanner.skip_whitespace();
-            
-            if self.scanner.peek() != Some(b'<') {
-                break;
-            }
-            
-            let saved_pos = self.scanner.pos;
-            self.scanner.advance(1);
-            
-            if self.scanner.peek() == Some(b'/') {
-                // Closing tag
-                self.scanner.advance(1);
-                let tag = self.read_tag_name()?;
-                if tag.ends_with("period") {
-                    self.skip_to_tag_end()?;
-                    break;
-                }
-                self.scanner.pos = saved_pos;
-                break;
-            }
-            
-            let tag = self.read_tag_name()?;
-            
-            if tag.ends_with("instant") {
-                self.skip_to_tag_end()?;
-                instant = Some(CompactString::from(self.read_text_content()?));
-                self.skip_closing_tag("instant")?;
-            } else if tag.ends_with("startDate") {
-                self.skip_to_tag_end()?;
-                start_date = Some(CompactString::from(self.read_text_content()?));
-                self.skip_closing_tag("startDate")?;
-            } else if tag.ends_with("endDate") {
-                self.skip_to_tag_end()?;
-                end_date = Some(CompactString::from(self.read_text_content()?));
-                self.skip_closing_tag("endDate")?;
-            } else if tag.ends_with("forever") {
-                forever = true;
-                self.skip_element_from_tag()?;
-            } else {
-                self.skip_element_from_tag()?;
+
+        while let Some(tag) = self.next_child()? {
+            self.parse_attributes()?;
+            let self_closing = self.finish_start_tag()?;
+            let text = self.read_leaf_text(self_closing)?;
+            match local_name(tag) {
+                "instant" => instant = Some(text),
+                "startDate" => start_date = Some(text),
+                "endDate" => end_date = Some(text),
+                "forever" => forever = true,
+                _ => {}
             }
         }
-        
-        Ok(Period {
-            instant,
-            start_date,
-            end_date,
-            forever,
+
+        Ok(match (instant, start_date, end_date) {
+            (Some(date), _, _) => Some(Period::instant(date)),
+            (None, Some(start), Some(end)) => Some(Period::between(start, end)),
+            _ if forever => Some(Period::Forever),
+            _ => None,
         })
     }
 
-    fn parse_unit(&mut self) -> Result<()> {
-        let attrs = self.parse_attributes()?;
-        let id = attrs.iter()
-            .find(|(n, _)| *n == "id")
-            .map(|(_, v)| CompactString::from(*v))
-            .ok_or_else(|| Error::Parse("Unit missing id".to_string()))?;
-        
-        self.skip_to_tag_end()?;
-        
-        let mut unit_type = None;
-        
-        // Parse unit children
-        loop {
-            self.scanner.skip_whitespace();
-            
-            if self.scanner.peek() != Some(b'<') {
-                break;
-            }
-            
-            let saved_pos = self.scanner.pos;
-            self.scanner.advance(1);
-            
-            if self.scanner.peek() == Some(b'/') {
-                // Closing tag
-                self.scanner.advance(1);
-                let tag = self.read_tag_name()?;
-                if tag.ends_with("unit") {
-                    self.skip_to_tag_end()?;
-                    break;
-                }
-                self.scanner.pos = saved_pos;
-                break;
-            }
-            
-            let tag = self.read_tag_name()?;
-            
-            if tag.ends_with("measure") {
-                // Simple unit
-                self.skip_to_tag_end()?;
-                let measure_text = self.read_text_content()?;
-                let measure = self.parse_measure(measure_text);
-                
-                if unit_type.is_none() {
-                    unit_type = Some(UnitType::Simple(vec![measure]));
-                } else if let Some(UnitType::Simple(ref mut measures)) = unit_type {
-                    measures.push(measure);
-                }
-                
-                self.skip_closing_tag("measure")?;
-            } else if tag.ends_with("divide") {
-                // Complex division unit
-                unit_type = Some(self.parse_unit_divide()?);
-            } else {
-                self.skip_element_from_tag()?;
+    fn parse_unit(&mut self, attrs: &[(&'a str, &'a str)]) -> Result<()> {
+        let start = self.element_start;
+        let id = attr(attrs, "id")
+            .ok_or_else(|| Error::xml("Unit missing id"))?
+            .to_string();
+
+        if self.finish_start_tag()? {
+            return Ok(());
+        }
+
+        let mut measures = Vec::new();
+        let mut divide = None;
+
+        while let Some(tag) = self.next_child()? {
+            match local_name(tag) {
+                "measure" => measures.push(self.parse_measure()?),
+                "divide" => divide = Some(self.parse_unit_divide()?),
+                _ => self.skip_element_from_tag()?,
             }
         }
-        
-        if let Some(unit_type) = unit_type {
-            self.document.units.push(Unit { id, unit_type });
+
+        // Several measures directly under the unit are multiplied
+        let unit_type = match divide {
+            Some(divide) => divide,
+            None if measures.len() > 1 => UnitType::Multiply(measures),
+            None if !measures.is_empty() => UnitType::Simple(measures),
+            None => return Ok(()),
+        };
+        // Unit ids start at 1, 0 meaning no unit
+        if self.doc.units.len() >= UNRESOLVED as usize - 1 {
+            return Err(Error::Limit {
+                what: "units",
+                max: UNRESOLVED as usize - 1,
+            });
+        }
+        self.source_map.unit_offsets.push(start);
+        self.source_map.ids.push((id.clone(), start));
+        self.doc.units.push(Unit { id, unit_type });
+        if let (Some(handler), Some(unit)) = (&mut self.handler, self.doc.units.last()) {
+            handler.handle(ParseEvent::Unit(unit))?;
         }
-        
+
         Ok(())
     }
 
     fn parse_unit_divide(&mut self) -> Result<UnitType> {
-        let _attrs = self.parse_attributes()?;
-        self.skip_to_tag_end()?;
-        
         let mut numerator = Vec::new();
         let mut denominator = Vec::new();
-        
-        // Parse divide children
-        loop {
-            self.scanner.skip_whitespace();
-            
-            if self.scanner.peek() != Some(b'<') {
-                break;
-            }
-            
-            let saved_pos = self.scanner.pos;
-            self.scanner.advance(1);
-            
-            if self.scanner.peek() == Some(b'/') {
-                // Closing tag
-                self.scanner.advance(1);
-                let tag = self.read_tag_name()?;
-                if tag.ends_with("divide") {
-                    self.skip_to_tag_end()?;
-                    break;
+
+        let attrs = self.parse_attributes()?;
+        if !self.finish_start_tag()? {
+            self.scopes.enter(&attrs);
+            while let Some(tag) = self.next_child()? {
+                match local_name(tag) {
+                    "unitNumerator" => numerator = self.parse_unit_measures()?,
+                    "unitDenominator" => denominator = self.parse_unit_measures()?,
+                    _ => self.skip_element_from_tag()?,
                 }
-                self.scanner.pos = saved_pos;
-                break;
-            }
-            
-            let tag = self.read_tag_name()?;
-            
-            if tag.ends_with("unitNumerator") {
-                self.skip_to_tag_end()?;
-                numerator = self.parse_unit_measures()?;
-                self.skip_closing_tag("unitNumerator")?;
-            } else if tag.ends_with("unitDenominator") {
-                self.skip_to_tag_end()?;
-                denominator = self.parse_unit_measures()?;
-                self.skip_closing_tag("unitDenominator")?;
-            } else {
-                self.skip_element_from_tag()?;
             }
+            self.scopes.leave();
         }
-        
-        Ok(UnitType::Divide { numerator, denominator })
+
+        Ok(UnitType::Divide {
+            numerator,
+            denominator,
+        })
     }
 
     fn parse_unit_measures(&mut self) -> Result<Vec<Measure>> {
         let mut measures = Vec::new();
-        
-        loop {
-            self.scanner.skip_whitespace();
-            
-            if self.scanner.peek() != Some(b'<') {
-                break;
-            }
-            
-            let saved_pos = self.scanner.pos;
-            self.scanner.advance(1);
-            
-            if self.scanner.peek() == Some(b'/') {
-                // End of measures
-                self.scanner.pos = saved_pos;
-                break;
-            }
-            
-            let tag = self.read_tag_name()?;
-            
-            if tag.ends_with("measure") {
-                self.skip_to_tag_end()?;
-                let measure_text = self.read_text_content()?;
-                measures.push(self.parse_measure(measure_text));
-                self.skip_closing_tag("measure")?;
+
+        let attrs = self.parse_attributes()?;
+        if self.finish_start_tag()? {
+            return Ok(measures);
+        }
+        self.scopes.enter(&attrs);
+
+        while let Some(tag) = self.next_child()? {
+            if local_name(tag) == "measure" {
+                measures.push(self.parse_measure()?);
             } else {
-                self.scanner.pos = saved_pos;
-                break;
+                self.skip_element_from_tag()?;
             }
         }
-        
+        self.scopes.leave();
+
         Ok(measures)
     }
 
-    fn parse_measure(&self, text: &str) -> Measure {
-        let (namespace, name) = if let Some(colon_pos) = text.find(':') {
-            (
-                CompactString::from(&text[..colon_pos]),
-                CompactString::from(&text[colon_pos + 1..])
-            )
-        } else {
-            (CompactString::new(""), CompactString::from(text))
+    /// A `measure` element whose name has been read
+    fn parse_measure(&mut self) -> Result<Measure> {
+        let attrs = self.parse_attributes()?;
+        self.scopes.enter(&attrs);
+        let self_closing = self.finish_start_tag()?;
+        let text = self.read_leaf_text(self_closing)?;
+        let namespace = self.namespace_of(text.trim()).unwrap_or_default();
+        let text = self.canonical_qname(text.trim());
+        self.scopes.leave();
+
+        let (mut prefix, name) = match text.split_once(':') {
+            Some((prefix, name)) => (prefix.to_string(), name.to_string()),
+            None => (String::new(), text.to_string()),
         };
-        
-        Measure { namespace, name }
-    }
+        // Unprefixed measures take the prefix the document binds to their
+        // default namespace, so `shares` reads as `xbrli:shares`
+        if prefix.is_empty() && !namespace.is_empty() {
+            if let Some(bound) = self
+                .namespaces
+                .iter()
+                .filter(|(p, uri)| **uri == namespace && !p.is_empty())
+                .map(|(p, _)| *p)
+                .min()
+            {
+                prefix = bound.to_string();
+            }
+        }
 
-    // Continue in next part...
-}// Parser part 2: Facts, Tuples, Footnotes, and Helper Functions
+        Ok(Measure {
+            prefix,
+            namespace: namespace.to_string(),
+            name,
+        })
+    }
+}
 
 impl<'a> FullXbrlParser<'a> {
-    fn parse_fact(&mut self, tag_name: &str) -> Result<()> {
-        let attrs = self.parse_attributes()?;
-        
-        // Check for xsi:nil attribute
-        let is_nil = attrs.iter()
-            .any(|(n, v)| *n == "xsi:nil" && (*v == "true" || *v == "1"));
-        
+    fn parse_fact(&mut self, tag_name: &'a str, attrs: &[(&'a str, &'a str)]) -> Result<()> {
+        let start = self.element_start;
+        let is_nil = attrs
+            .iter()
+            .any(|(n, v)| n.ends_with(":nil") && (*v == "true" || *v == "1"));
         let nil_reason = if is_nil {
-            attrs.iter()
-                .find(|(n, _)| *n == "nilReason")
-                .map(|(_, v)| CompactString::from(*v))
+            attr(attrs, "nilReason").map(str::to_string)
         } else {
             None
         };
-        
-        let context_ref = attrs.iter()
-            .find(|(n, _)| *n == "contextRef")
-            .map(|(_, v)| CompactString::from(*v));
-        
-        let unit_ref = attrs.iter()
-            .find(|(n, _)| *n == "unitRef")
-            .map(|(_, v)| CompactString::from(*v));
-        
-        let id = attrs.iter()
-            .find(|(n, _)| *n == "id")
-            .map(|(_, v)| CompactString::from(*v));
-        
-        let decimals = attrs.iter()
-            .find(|(n, _)| *n == "decimals")
-            .and_then(|(_, v)| v.parse::<i8>().ok());
-        
-        let precision = attrs.iter()
-            .find(|(n, _)| *n == "precision")
-            .and_then(|(_, v)| v.parse::<u8>().ok());
-        
-        // Check if it's a self-closing tag
-        let is_self_closing = self.check_self_closing();
-        
-        self.skip_to_tag_end()?;
-        
-        let value = if is_self_closing || is_nil {
+        let context_ref = attr(attrs, "contextRef").unwrap_or_default();
+        let unit_ref = attr(attrs, "unitRef");
+        let id = attr(attrs, "id").map(str::to_string);
+        let decimals = attr(attrs, "decimals").and_then(|v| v.parse::<i8>().ok());
+        let precision = attr(attrs, "precision").and_then(|v| v.parse::<u8>().ok());
+
+        let self_closing = self.finish_start_tag()?;
+        let fraction = !self_closing
+            && self
+                .peek_tag_name()
+                .is_some_and(|tag| local_name(tag) == "numerator");
+        let value = if self_closing {
             String::new()
+        } else if fraction {
+            self.parse_fraction_value()?
         } else {
-            // Check for special fact types (fraction, mixed content)
-            let value = if self.scanner.peek() == Some(b'<') {
-                // Check if it's a fraction
-                if self.peek_tag_name()?.ends_with("numerator") {
-                    self.parse_fraction_value()?
-                } else {
-                    // Mixed content or nested elements
-                    self.read_mixed_content_until_closing(tag_name)?
-                }
-            } else {
-                // Simple text content (may include CDATA)
-                self.read_text_content_with_cdata()?
-            };
-            
-            // Skip closing tag if not self-closing
-            if !is_self_closing {
-                self.skip_closing_tag(tag_name)?;
-            }
-            
-            value
+            self.read_content()?
         };
-        
-        if let Some(context_ref) = context_ref {
+
+        if !self
+            .options
+            .wants_concept(tag_name, self.namespace_of(tag_name))
+        {
+            return Ok(());
+        }
+        let value = match fraction {
+            true => value,
+            false => self
+                .transform_value(tag_name, unit_ref.is_some(), &value)
+                .unwrap_or(value),
+        };
+        let tag_name = match self.rebind_qname(tag_name) {
+            Some(qname) => self.allocator.alloc_str(&qname),
+            None => tag_name,
+        };
+
+        // Facts inside a tuple belong to the tuple, and are also stored in
+        // the fact table with the path to it
+        let mut tuple_path = Vec::new();
+        if self.handler.is_some() || !self.current_tuple_stack.is_empty() {
             let fact = Fact {
-                id,
-                concept: CompactString::from(tag_name),
-                context_ref,
-                unit_ref,
+                id: id.clone(),
+                concept: tag_name.to_string(),
+                context_ref: context_ref.to_string(),
+                unit_ref: unit_ref.map(str::to_string),
                 value: value.clone(),
                 decimals,
                 precision,
                 nil: is_nil,
                 nil_reason,
-                footnote_refs: Vec::new(), // Will be populated by footnote links
+                footnote_refs: Vec::new(),
             };
-            
-            // If we're inside a tuple, add to tuple instead of document
             if !self.current_tuple_stack.is_empty() {
-                let tuple = self.current_tuple_stack.last_mut().unwrap();
+                // Open tuples take the next free index in their parent
+                tuple_path.push(self.doc.tuples.len() as u32);
+                let stack = &self.current_tuple_stack;
+                tuple_path.extend(
+                    stack[..stack.len() - 1]
+                        .iter()
+                        .map(|(_, t)| t.facts.len() as u32),
+                );
+            }
+            if let Some((_, tuple)) = self.current_tuple_stack.last_mut() {
                 tuple.facts.push(FactOrTuple::Fact(fact));
-            } else {
-                // Add to document facts
-                let concept_id = self.allocator.intern_string(tag_name);
-                let context_id = self.get_or_create_context_id(&fact.context_ref)?;
-                let unit_id = fact.unit_ref.as_ref()
-                    .and_then(|u| self.get_or_create_unit_id(u).ok())
-                    .unwrap_or(0);
-                
-                let (value_type, fact_value) = self.parse_fact_value(&value, is_nil)?;
-                
-                let mut flags = 0u8;
-                if is_nil {
-                    flags |= FactFlags::NIL.bits();
-                }
-                if precision.is_some() {
-                    flags |= FactFlags::HAS_PRECISION.bits();
-                }
-                if decimals.is_some() {
-                    flags |= FactFlags::HAS_DECIMALS.bits();
-                }
-                if !self.current_tuple_stack.is_empty() {
-                    flags |= FactFlags::IN_TUPLE.bits();
-                }
-                
-                self.document.facts.push(CompactFact {
-                    concept_id,
-                    context_id,
-                    unit_id,
-                    value_type,
-                    flags,
-                    padding: [0; 6],
-                    value: fact_value,
-                });
+            } else if let Some(handler) = &mut self.handler {
+                handler.handle(ParseEvent::Fact(&fact))?;
             }
+            if self.handler.is_some() {
+                return Ok(());
+            }
+        }
+
+        let concept_id = self.intern_concept(tag_name);
+        let fact_value = match parse_fraction(&value) {
+            Some((numerator, denominator)) if fraction && !is_nil => FactValue::Fraction {
+                numerator,
+                denominator,
+            },
+            _ => self.parse_fact_value(&value, is_nil, unit_ref.is_some()),
+        };
+        // precision="0" says nothing about the value, INF fails to parse
+        let decimals = decimals.or_else(|| {
+            let precision = precision.filter(|&p| p > 0)?;
+            decimals_from_precision(fact_value.as_decimal()?, u32::from(precision))
+        });
+        self.source_map.fact_offsets.push(start);
+        if let Some(id) = &id {
+            self.source_map.ids.push((id.clone(), start));
         }
-        
+        self.doc
+            .facts
+            .push(concept_id, UNRESOLVED, 0, fact_value, decimals, id);
+        if !tuple_path.is_empty() {
+            *self.doc.facts.tuple_paths.last_mut().unwrap() = tuple_path;
+        }
+        self.fact_refs.push((context_ref, unit_ref));
+
         Ok(())
     }
 
-    fn parse_tuple(&mut self, tag_name: &str) -> Result<()> {
-        let attrs = self.parse_attributes()?;
-        
-        let id = attrs.iter()
-            .find(|(n, _)| *n == "id")
-            .map(|(_, v)| CompactString::from(*v));
-        
-        self.skip_to_tag_end()?;
-        
-        // Create new tuple and push to stack
+    fn parse_tuple(&mut self, tag_name: &'a str, attrs: &[(&'a str, &'a str)]) -> Result<()> {
         let tuple = Tuple {
-            id,
-            name: CompactString::from(tag_name),
+            id: attr(attrs, "id").map(str::to_string),
+            name: self.canonical_qname(tag_name),
             facts: Vec::new(),
         };
-        
-        self.current_tuple_stack.push(tuple);
-        
-        // The tuple will be popped when we encounter its closing tag
-        
+        self.current_tuple_stack.push((tag_name, tuple));
+
+        // Otherwise the tuple is closed when the main loop sees its end tag
+        if self.finish_start_tag()? {
+            self.close_tuple(tag_name)?;
+        }
+
         Ok(())
     }
 
-    fn parse_footnote_link(&mut self) -> Result<()> {
-        let attrs = self.parse_attributes()?;
-        
-        let role = attrs.iter()
-            .find(|(n, _)| n.ends_with("role"))
-            .map(|(_, v)| CompactString::from(*v));
-        
-        self.skip_to_tag_end()?;
-        
-        let mut footnotes_map: HashMap<String, Footnote> = HashMap::new();
-        let mut fact_footnote_links: Vec<(String, String)> = Vec::new();
-        
-        // Parse footnote link children
-        loop {
-            self.scanner.skip_whitespace();
-            
-            if self.scanner.peek() != Some(b'<') {
-                break;
-            }
-            
-            let saved_pos = self.scanner.pos;
-            self.scanner.advance(1);
-            
-            if self.scanner.peek() == Some(b'/') {
-                // Closing tag
-                self.scanner.advance(1);
-                let tag = self.read_tag_name()?;
-                if tag.ends_with("footnoteLink") {
-                    self.skip_to_tag_end()?;
-                    break;
+    fn close_tuple(&mut self, tag_name: &str) -> Result<()> {
+        if self
+            .current_tuple_stack
+            .last()
+            .is_none_or(|(tag, _)| *tag != tag_name)
+        {
+            return Ok(());
+        }
+
+        let (_, tuple) = self.current_tuple_stack.pop().unwrap();
+        self.scopes.leave();
+        match (self.current_tuple_stack.last_mut(), &mut self.handler) {
+            (Some((_, parent)), _) => parent.facts.push(FactOrTuple::Tuple(Box::new(tuple))),
+            (None, Some(handler)) => handler.handle(ParseEvent::Tuple(&tuple))?,
+            (None, None) => self.doc.tuples.push(tuple),
+        }
+        Ok(())
+    }
+
+    fn parse_footnote_link(&mut self, attrs: &[(&'a str, &'a str)]) -> Result<()> {
+        let role = attr(attrs, "xlink:role").map(str::to_string);
+
+        if self.finish_start_tag()? {
+            return Ok(());
+        }
+
+        // xlink labels of locators -> fact ids, footnotes in document order
+        let mut locators: HashMap<&str, Vec<&str>> = HashMap::new();
+        let mut footnotes: Vec<(&str, Footnote)> = Vec::new();
+        let mut arcs: Vec<(&str, &str, &str, f32)> = Vec::new();
+
+        while let Some(tag) = self.next_child()? {
+            let attrs = self.parse_attributes()?;
+            let self_closing = self.finish_start_tag()?;
+            let label = attr(&attrs, "xlink:label").unwrap_or_default();
+
+            match local_name(tag) {
+                "loc" => {
+                    if let Some(href) = attr(&attrs, "xlink:href") {
+                        let fact_id = href.rsplit('#').next().unwrap_or(href);
+                        locators.entry(label).or_default().push(fact_id);
+                    }
+                    self.skip_content(self_closing)?;
+                }
+                "footnote" => {
+                    let content = self.read_leaf_text(self_closing)?;
+                    footnotes.push((
+                        label,
+                        Footnote {
+                            id: attr(&attrs, "id").unwrap_or(label).to_string(),
+                            role: attr(&attrs, "xlink:role")
+                                .map(str::to_string)
+                                .or_else(|| role.clone()),
+                            lang: attr(&attrs, "xml:lang").map(str::to_string),
+                            content,
+                            fact_refs: Vec::new(),
+                        },
+                    ));
+                }
+                "footnoteArc" => {
+                    if let (Some(from), Some(to)) =
+                        (attr(&attrs, "xlink:from"), attr(&attrs, "xlink:to"))
+                    {
+                        let arcrole =
+                            attr(&attrs, "xlink:arcrole").unwrap_or(FACT_FOOTNOTE_ARCROLE);
+                        let order = attr(&attrs, "order")
+                            .and_then(|o| o.parse().ok())
+                            .unwrap_or(1.0);
+                        arcs.push((from, to, arcrole, order));
+                    }
+                    self.skip_content(self_closing)?;
                 }
-                self.scanner.pos = saved_pos;
-                break;
+                _ => self.skip_content(self_closing)?,
             }
-            
-            let tag = self.read_tag_name()?;
-            
-            if tag.ends_with("footnote") {
-                let attrs = self.parse_attributes()?;
-                
-                let id = attrs.iter()
-                    .find(|(n, _)| n.ends_with("label") || *n == "id")
-                    .map(|(_, v)| v.to_string())
-                    .unwrap_or_default();
-                
-                let lang = attrs.iter()
-                    .find(|(n, _)| n.ends_with("lang"))
-                    .map(|(_, v)| CompactString::from(*v));
-                
-                self.skip_to_tag_end()?;
-                let content = self.read_text_content_with_cdata()?;
-                self.skip_closing_tag("footnote")?;
-                
-                footnotes_map.insert(id.clone(), Footnote {
-                    id: CompactString::from(id),
-                    role: role.clone(),
-                    lang,
-                    content,
-                    fact_refs: Vec::new(),
-                });
-            } else if tag.ends_with("footnoteArc") {
-                let attrs = self.parse_attributes()?;
-                
-                let from = attrs.iter()
-                    .find(|(n, _)| n.ends_with("from"))
-                    .map(|(_, v)| v.to_string())
-                    .unwrap_or_default();
-                
-                let to = attrs.iter()
-                    .find(|(n, _)| n.ends_with("to"))
-                    .map(|(_, v)| v.to_string())
-                    .unwrap_or_default();
-                
-                fact_footnote_links.push((from, to));
-                self.skip_element_from_tag()?;
-            } else {
-                self.skip_element_from_tag()?;
+        }
+
+        // A label may be shared by several locators or footnotes, so every
+        // arc expands to each source fact and each target
+        let mut expanded = Vec::new();
+        for (from, to, arcrole, order) in arcs {
+            let fact_ids = locators.get(from).map(Vec::as_slice).unwrap_or_default();
+            let mut targets = Vec::new();
+            for (_, footnote) in footnotes.iter_mut().filter(|(label, _)| *label == to) {
+                footnote
+                    .fact_refs
+                    .extend(fact_ids.iter().map(|id| id.to_string()));
+                targets.push(FootnoteTarget::Footnote(footnote.id.clone()));
+            }
+            for id in locators.get(to).into_iter().flatten() {
+                targets.push(FootnoteTarget::Fact(id.to_string()));
+            }
+            for from in fact_ids {
+                for to in &targets {
+                    expanded.push(FootnoteArc {
+                        role: role.clone(),
+                        from: from.to_string(),
+                        to: to.clone(),
+                        arcrole: arcrole.to_string(),
+                        order,
+                    });
+                }
             }
         }
-        
-        // Process footnote links
-        for (fact_ref, footnote_ref) in fact_footnote_links {
-            if let Some(footnote) = footnotes_map.get_mut(&footnote_ref) {
-                footnote.fact_refs.push(CompactString::from(fact_ref));
+        for (_, footnote) in footnotes {
+            match &mut self.handler {
+                Some(handler) => handler.handle(ParseEvent::Footnote(&footnote))?,
+                None => self.doc.footnotes.push(footnote),
             }
         }
-        
-        // Add footnotes to document
-        for (_, footnote) in footnotes_map {
-            self.document.footnotes.push(footnote);
+        for arc in expanded {
+            match &mut self.handler {
+                Some(handler) => handler.handle(ParseEvent::FootnoteArc(&arc))?,
+                None => self.doc.footnote_arcs.push(arc),
+            }
         }
-        
+
         Ok(())
     }
 
     fn parse_fraction_value(&mut self) -> Result<String> {
         let mut numerator = String::new();
         let mut denominator = String::new();
-        
-        loop {
-            self.scanner.skip_whitespace();
-            
-            if self.scanner.peek() != Some(b'<') {
-                break;
-            }
-            
-            let saved_pos = self.scanner.pos;
-            self.scanner.advance(1);
-            
-            if self.scanner.peek() == Some(b'/') {
-                self.scanner.pos = saved_pos;
-                break;
-            }
-            
-            let tag = self.read_tag_name()?;
-            
-            if tag.ends_with("numerator") {
-                self.skip_to_tag_end()?;
-                numerator = self.read_text_content()?.to_string();
-                self.skip_closing_tag("numerator")?;
-            } else if tag.ends_with("denominator") {
-                self.skip_to_tag_end()?;
-                denominator = self.read_text_content()?.to_string();
-                self.skip_closing_tag("denominator")?;
-            } else {
-                self.skip_element_from_tag()?;
+
+        while let Some(tag) = self.next_child()? {
+            self.parse_attributes()?;
+            let self_closing = self.finish_start_tag()?;
+            let text = self.read_leaf_text(self_closing)?;
+            match local_name(tag) {
+                "numerator" => numerator = text,
+                "denominator" => denominator = text,
+                _ => {}
             }
         }
-        
-        // Return as fraction string
-        Ok(format!("{}/{}", numerator, denominator))
+
+        // As `numerator/denominator`, see [`parse_fraction`]
+        Ok(format!("{}/{}", numerator.trim(), denominator.trim()))
     }
 
-    fn parse_fact_value(&self, value: &str, is_nil: bool) -> Result<(u8, FactValue)> {
+    fn parse_fact_value(&self, value: &str, is_nil: bool, numeric: bool) -> FactValue {
         if is_nil {
-            return Ok((ValueType::Nil as u8, FactValue { integer: 0 }));
-        }
-        
-        if value.is_empty() {
-            return Ok((ValueType::String as u8, FactValue { string_id: 0 }));
-        }
-        
-        // Check for fraction
-        if value.contains('/') && !value.contains(' ') {
-            if let Some((num, den)) = value.split_once('/') {
-                if num.parse::<f64>().is_ok() && den.parse::<f64>().is_ok() {
-                    return Ok((ValueType::Fraction as u8, FactValue { string_id: self.allocator.intern_string(value) }));
-                }
+            return FactValue::Nil;
+        }
+
+        let value = value.trim();
+        if numeric {
+            // Handle parentheses for negative numbers
+            let cleaned = match value.strip_prefix('(').and_then(|v| v.strip_suffix(')')) {
+                Some(inner) => format!("-{}", inner),
+                None => value.to_string(),
+            };
+            if let Some(decimal) = parse_decimal(&cleaned) {
+                return FactValue::Decimal(decimal);
             }
         }
-        
-        // Handle parentheses for negative numbers
-        let cleaned_value = if value.starts_with('(') && value.ends_with(')') {
-            format!("-{}", &value[1..value.len()-1])
-        } else {
-            value.to_string()
-        };
-        
-        // Try parsing as number
-        if let Ok(decimal) = cleaned_value.parse::<f64>() {
-            Ok((ValueType::Decimal as u8, FactValue { decimal }))
-        } else if let Ok(integer) = cleaned_value.parse::<i64>() {
-            Ok((ValueType::Integer as u8, FactValue { integer }))
-        } else if value == "true" || value == "false" {
-            let boolean = if value == "true" { 1 } else { 0 };
-            Ok((ValueType::Boolean as u8, FactValue { boolean }))
-        } else {
-            // Store as string
-            let string_id = self.allocator.intern_string(value);
-            Ok((ValueType::String as u8, FactValue { string_id }))
+
+        match value {
+            "true" => FactValue::Boolean(true),
+            "false" => FactValue::Boolean(false),
+            // Non-numeric items keep their text, so leading zeros in
+            // identifiers such as a CIK survive
+            _ if is_iso_date(value) => FactValue::Date(value.to_string()),
+            _ => FactValue::Text(value.to_string()),
         }
     }
 
-    fn parse_xbrl_root(&mut self) -> Result<()> {
+    /// Whether the root start tag is self-closing
+    fn parse_xbrl_root(&mut self) -> Result<bool> {
         let attrs = self.parse_attributes()?;
-        
-        for (name, value) in attrs {
-            if name.starts_with("xmlns") {
-                let ns_name = if name.len() > 6 && name.chars().nth(5) == Some(':') {
-                    CompactString::from(&name[6..])
-                } else {
-                    CompactString::new("")
-                };
-                self.document.namespaces.insert(ns_name, CompactString::from(value));
+        self.scopes.enter(&attrs);
+        self.root_scope = self.scopes.bindings.len();
+        self.namespaces = self.scopes.bindings.iter().copied().collect();
+
+        self.finish_start_tag()
+    }
+
+    fn parse_schema_ref(&mut self, attrs: &[(&'a str, &'a str)]) -> Result<()> {
+        let self_closing = self.finish_start_tag()?;
+
+        if let Some(href) = attr(attrs, "xlink:href") {
+            self.doc.schema_ref = Some(href.to_string());
+            // schemaRef precedes the facts, so their item types are known
+            // by the time values are transformed
+            if self.load_schemas || self.load_linkbases {
+                self.load_dts(href)?;
             }
         }
-        
-        self.skip_to_tag_end()?;
-        Ok(())
+
+        self.skip_content(self_closing)
     }
 
-    fn parse_schema_ref(&mut self) -> Result<()> {
-        let attrs = self.parse_attributes()?;
-        if let Some((_, href)) = attrs.iter().find(|(n, _)| n.ends_with("href")) {
-            self.document.schema_ref = Some(CompactString::from(*href));
-            
-            // If schema loading is enabled, load the schema
-            if self.load_schemas {
-                self.load_schema_from_ref(href)?;
+    /// Discover the DTS from a schemaRef and load the parts this parser was
+    /// asked for. Relative references resolve against the instance file, or
+    /// the working directory when parsing from memory.
+    fn load_dts(&mut self, schema_ref: &str) -> Result<()> {
+        let entry = match self.base_path.and_then(Path::parent) {
+            Some(dir) if !schema_ref.contains("://") => {
+                dir.join(schema_ref).to_string_lossy().into_owned()
             }
+            _ => schema_ref.to_string(),
+        };
+        // An instance whose own schema is missing is still usable
+        if !entry.contains("://") && !Path::new(&entry).is_file() {
+            return Ok(());
+        }
+
+        let dts = DtsLoader::new()
+            .with_catalog(Catalog::bundled())
+            .with_network_policy(NetworkPolicy::WarnAndSkip)
+            .discover(&entry)?;
+        if self.load_schemas {
+            self.doc.load_dts_schemas(&dts)?;
+        }
+        if self.load_linkbases {
+            self.doc.load_dts_linkbases(&dts)?;
         }
-        self.skip_element_from_tag()?;
         Ok(())
     }
 
-    fn load_schema_from_ref(&mut self, schema_location: &str) -> Result<()> {
-        // Parse schema location to handle relative and absolute paths
-        let schema_path = if schema_location.starts_with("http://") || schema_location.starts_with("https://") {
-            // Remote schema - would need HTTP client to fetch
-            // For now, we'll try to find it locally in a schemas directory
-            let filename = schema_location.split('/').last().unwrap_or("schema.xsd");
-            format!("schemas/{}", filename)
-        } else if schema_location.starts_with("/") {
-            // Absolute path
-            schema_location.to_string()
-        } else {
-            // Relative path - resolve relative to the current XBRL file
-            if let Some(base_dir) = self.file_path.as_ref().and_then(|p| p.parent()) {
-                base_dir.join(schema_location).to_string_lossy().to_string()
+    /// Schema type of the concept `qname`, from the loaded schemas
+    fn item_type(&self, qname: &str) -> Option<&str> {
+        let namespace = self.namespace_of(qname)?;
+        self.doc
+            .schemas
+            .iter()
+            .filter(|s| s.target_namespace == namespace)
+            .find_map(|s| s.elements.get(local_name(qname)))
+            .map(|e| e.element_type.as_str())
+    }
+
+    /// `value` after the configured transforms, or `None` if unchanged
+    fn transform_value(&self, qname: &str, numeric: bool, value: &str) -> Option<String> {
+        let transforms = &self.options.transforms;
+        if transforms.is_empty() {
+            return None;
+        }
+        let fact = TransformFact {
+            concept: qname,
+            namespace: self.namespace_of(qname),
+            item_type: if transforms.uses_item_types() {
+                self.item_type(qname)
             } else {
-                schema_location.to_string()
-            }
+                None
+            },
+            numeric,
         };
-        
-        // Check if schema file exists
-        let schema_path = std::path::Path::new(&schema_path);
-        if !schema_path.exists() {
-            // Schema not found locally - this is common for remote schemas
-            // In production, we would download and cache them
-            return Ok(());
+        transforms.apply(&fact, value)
+    }
+
+    /// Document-local concept id for `name`
+    fn intern_concept(&mut self, name: &'a str) -> u32 {
+        if let Some(&id) = self.concept_ids.get(name) {
+            return id;
         }
-        
-        // Load and parse the schema
-        let schema_content = std::fs::read(schema_path)?;
-        self.parse_schema_content(&schema_content)?;
-        
-        Ok(())
+        self.allocator.intern_string(name);
+        let id = self.doc.concept_names.len() as u32;
+        self.doc.concept_names.push(name.to_string());
+        self.concept_ids.insert(name, id);
+        id
     }
-    
-    fn parse_schema_content(&mut self, content: &[u8]) -> Result<()> {
-        let mut schema = Schema {
-            target_namespace: CompactString::new(""),
-            elements: HashMap::new(),
-            types: HashMap::new(),
-            imports: Vec::new(),
+
+    fn resolve_fact_refs(&mut self) {
+        let contexts: HashMap<&str, u32> = self
+            .doc
+            .contexts
+            .iter()
+            .enumerate()
+            .map(|(i, c)| (c.id.as_str(), i as u32))
+            .collect();
+        // 0 means no unit
+        let units: HashMap<&str, u32> = self
+            .doc
+            .units
+            .iter()
+            .enumerate()
+            .map(|(i, u)| (u.id.as_str(), (i + 1) as u32))
+            .collect();
+
+        for (i, (context_ref, unit_ref)) in self.fact_refs.iter().enumerate() {
+            self.doc.facts.context_ids[i] =
+                contexts.get(context_ref).copied().unwrap_or(UNRESOLVED);
+            self.doc.facts.unit_ids[i] = match unit_ref {
+                Some(unit_ref) => units.get(unit_ref).copied().unwrap_or(UNRESOLVED),
+                None => 0,
+            };
+        }
+    }
+
+    /// [`Error::Namespace`] if the prefix of the `what` QName is not bound
+    /// in scope. Fact names must resolve; measure QNames may still use a
+    /// conventional prefix, see [`Measure::in_namespace`].
+    fn check_prefix(&self, qname: &str, what: &str) -> Result<()> {
+        match qname.split_once(':') {
+            Some((prefix, _)) if self.scopes.resolve(prefix).is_none() => Err(Error::Namespace {
+                prefix: prefix.to_string(),
+                message: format!("{} {} uses an undeclared prefix", what, qname),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Namespace of `qname` under the declarations in scope
+    fn namespace_of(&self, qname: &str) -> Option<&'a str> {
+        let prefix = qname.split_once(':').map(|(p, _)| p).unwrap_or("");
+        self.scopes.resolve(prefix)
+    }
+
+    /// `qname` rewritten to use a document-level prefix when its own prefix
+    /// is bound differently in scope than at the root; `None` if it already
+    /// means the same thing document-wide
+    fn rebind_qname(&mut self, qname: &str) -> Option<String> {
+        if self.scopes.bindings.len() == self.root_scope {
+            return None;
+        }
+        let (prefix, local) = qname.split_once(':').unwrap_or(("", qname));
+        let uri = self.scopes.resolve(prefix)?;
+        if self.namespaces.get(prefix) == Some(&uri) {
+            return None;
+        }
+
+        let existing = self
+            .namespaces
+            .iter()
+            .filter(|(p, u)| **u == uri && !p.is_empty())
+            .map(|(p, _)| *p)
+            .min();
+        let rebound = match existing {
+            Some(p) => p,
+            None => {
+                let base = if prefix.is_empty() { "ns" } else { prefix };
+                let fresh = match self.namespaces.contains_key(base) {
+                    false => base.to_string(),
+                    true => (1..)
+                        .map(|n| format!("{}{}", base, n))
+                        .find(|p| !self.namespaces.contains_key(p.as_str()))
+                        .unwrap(),
+                };
+                let fresh = self.allocator.alloc_str(&fresh);
+                self.namespaces.insert(fresh, uri);
+                fresh
+            }
         };
-        
-        // Basic XSD parsing using quick-xml
-        let mut reader = quick_xml::Reader::from_reader(content);
-        reader.trim_text(true);
-        
-        let mut buf = Vec::new();
-        let mut current_element: Option<SchemaElement> = None;
-        let mut current_type: Option<SchemaType> = None;
-        
-        loop {
-            match reader.read_event_into(&mut buf) {
-                Ok(Event::Start(ref e)) | Ok(Event::Empty(ref e)) => {
-                    let tag_name = e.name();
-                    let local_name = std::str::from_utf8(tag_name.local_name().as_ref())
-                        .unwrap_or("");
-                    
-                    match local_name {
-                        "schema" => {
-                            // Extract target namespace
-                            for attr in e.attributes().flatten() {
-                                let key = std::str::from_utf8(attr.key.as_ref()).unwrap_or("");
-                                if key == "targetNamespace" {
-                                    let value = std::str::from_utf8(&attr.value).unwrap_or("");
-                                    schema.target_namespace = CompactString::new(value);
-                                }
-                            }
-                        }
-                        "element" => {
-                            let mut element = SchemaElement {
-                                name: CompactString::new(""),
-                                element_type: CompactString::new(""),
-                                substitution_group: None,
-                                period_type: None,
-                                balance: None,
-                                abstract_element: false,
-                                nillable: false,
-                            };
-                            
-                            for attr in e.attributes().flatten() {
-                                let key = std::str::from_utf8(attr.key.as_ref()).unwrap_or("");
-                                let value = std::str::from_utf8(&attr.value).unwrap_or("");
-                                
-                                match key {
-                                    "name" => element.name = CompactString::new(value),
-                                    "type" => element.element_type = CompactString::new(value),
-                                    "substitutionGroup" => element.substitution_group = Some(CompactString::new(value)),
-                                    "periodType" => element.period_type = Some(CompactString::new(value)),
-                                    "balance" => element.balance = Some(CompactString::new(value)),
-                                    "abstract" => element.abstract_element = value == "true",
-                                    "nillable" => element.nillable = value == "true",
-                                    _ => {}
-                                }
-                            }
-                            
-                            if !element.name.is_empty() {
-                                if matches!(e, Event::Empty(_)) {
-                                    // Self-closing element tag
-                                    schema.elements.insert(element.name.clone(), element);
-                                } else {
-                                    current_element = Some(element);
-                                }
-                            }
-                        }
-                        "complexType" | "simpleType" => {
-                            let mut schema_type = SchemaType {
-                                name: CompactString::new(""),
-                                base_type: None,
-                                restrictions: Vec::new(),
-                            };
-                            
-                            for attr in e.attributes().flatten() {
-                                let key = std::str::from_utf8(attr.key.as_ref()).unwrap_or("");
-                                let value = std::str::from_utf8(&attr.value).unwrap_or("");
-                                
-                                if key == "name" {
-                                    schema_type.name = CompactString::new(value);
-                                }
-                            }
-                            
-                            if !schema_type.name.is_empty() {
-                                current_type = Some(schema_type);
-                            }
-                        }
-                        "restriction" => {
-                            if let Some(ref mut t) = current_type {
-                                for attr in e.attributes().flatten() {
-                                    let key = std::str::from_utf8(attr.key.as_ref()).unwrap_or("");
-                                    let value = std::str::from_utf8(&attr.value).unwrap_or("");
-                                    
-                                    if key == "base" {
-                                        t.base_type = Some(CompactString::new(value));
-                                    }
-                                }
-                            }
-                        }
-                        "minInclusive" | "maxInclusive" | "minExclusive" | "maxExclusive" | 
-                        "pattern" | "length" | "minLength" | "maxLength" => {
-                            if let Some(ref mut t) = current_type {
-                                for attr in e.attributes().flatten() {
-                                    let key = std::str::from_utf8(attr.key.as_ref()).unwrap_or("");
-                                    let value = std::str::from_utf8(&attr.value).unwrap_or("");
-                                    
-                                    if key == "value" {
-                                        let restriction = match local_name {
-                                            "minInclusive" => TypeRestriction::MinInclusive(value.to_string()),
-                                            "maxInclusive" => TypeRestriction::MaxInclusive(value.to_string()),
-                                            "minExclusive" => TypeRestriction::MinExclusive(value.to_string()),
-                                            "maxExclusive" => TypeRestriction::MaxExclusive(value.to_string()),
-                                            "pattern" => TypeRestriction::Pattern(value.to_string()),
-                                            "length" => TypeRestriction::Length(value.parse().unwrap_or(0)),
-                                            "minLength" => TypeRestriction::MinLength(value.parse().unwrap_or(0)),
-                                            "maxLength" => TypeRestriction::MaxLength(value.parse().unwrap_or(0)),
-                                            _ => continue,
-                                        };
-                                        t.restrictions.push(restriction);
-                                    }
-                                }
-                            }
-                        }
-                        "enumeration" => {
-                            if let Some(ref mut t) = current_type {
-                                for attr in e.attributes().flatten() {
-                                    let key = std::str::from_utf8(attr.key.as_ref()).unwrap_or("");
-                                    let value = std::str::from_utf8(&attr.value).unwrap_or("");
-                                    
-                                    if key == "value" {
-                                        // Find or create enumeration restriction
-                                        let mut found = false;
-                                        for restriction in &mut t.restrictions {
-                                            if let TypeRestriction::Enumeration(ref mut values) = restriction {
-                                                values.push(value.to_string());
-                                                found = true;
-                                                break;
-                                            }
-                                        }
-                                        if !found {
-                                            t.restrictions.push(TypeRestriction::Enumeration(vec![value.to_string()]));
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        "import" => {
-                            let mut import = SchemaImport {
-                                namespace: CompactString::new(""),
-                                schema_location: CompactString::new(""),
-                            };
-                            
-                            for attr in e.attributes().flatten() {
-                                let key = std::str::from_utf8(attr.key.as_ref()).unwrap_or("");
-                                let value = std::str::from_utf8(&attr.value).unwrap_or("");
-                                
-                                match key {
-                                    "namespace" => import.namespace = CompactString::new(value),
-                                    "schemaLocation" => import.schema_location = CompactString::new(value),
-                                    _ => {}
-                                }
-                            }
-                            
-                            if !import.namespace.is_empty() || !import.schema_location.is_empty() {
-                                schema.imports.push(import);
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-                Ok(Event::End(ref e)) => {
-                    let tag_name = e.name();
-                    let local_name = std::str::from_utf8(tag_name.local_name().as_ref())
-                        .unwrap_or("");
-                    
-                    match local_name {
-                        "element" => {
-                            if let Some(element) = current_element.take() {
-                                schema.elements.insert(element.name.clone(), element);
-                            }
-                        }
-                        "complexType" | "simpleType" => {
-                            if let Some(schema_type) = current_type.take() {
-                                schema.types.insert(schema_type.name.clone(), schema_type);
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-                Ok(Event::Eof) => break,
-                Err(e) => return Err(Error::Parse(format!("Schema parse error: {}", e))),
-                _ => {}
+        (rebound != prefix).then(|| format!("{}:{}", rebound, local))
+    }
+
+    fn canonical_qname(&mut self, qname: &str) -> String {
+        self.rebind_qname(qname)
+            .unwrap_or_else(|| qname.to_string())
+    }
+
+    /// Whether `tag_name` has local name `local` in `namespace`. Undeclared
+    /// prefixes are matched on the local name alone.
+    fn is_named(&self, tag_name: &str, namespace: &str, local: &str) -> bool {
+        local_name(tag_name) == local
+            && self.namespace_of(tag_name).is_none_or(|ns| ns == namespace)
+    }
+
+    /// Elements from the XBRL infrastructure namespaces, which are never facts
+    fn is_structural(&self, tag_name: &str) -> bool {
+        match self.namespace_of(tag_name) {
+            Some(ns) => [XBRLI_NS, LINK_NS, XBRLDI_NS, XLINK_NS].contains(&ns),
+            None => match tag_name.split_once(':') {
+                Some((prefix, _)) => ["xbrli", "link", "xbrldi", "xlink"].contains(&prefix),
+                None => true,
+            },
+        }
+    }
+}
+
+/// Namespace declarations in scope, innermost last
+#[derive(Default)]
+struct NamespaceScopes<'a> {
+    bindings: Vec<(&'a str, &'a str)>,
+    // Length of `bindings` when each open element was entered
+    frames: Vec<usize>,
+}
+
+impl<'a> NamespaceScopes<'a> {
+    /// Enter an element, bringing its `xmlns` attributes into scope
+    fn enter(&mut self, attrs: &[(&'a str, &'a str)]) {
+        self.frames.push(self.bindings.len());
+        for (name, value) in attrs {
+            if *name == "xmlns" {
+                self.bindings.push(("", value));
+            } else if let Some(prefix) = name.strip_prefix("xmlns:") {
+                self.bindings.push((prefix, value));
             }
-            buf.clear();
         }
-        
-        // Add the parsed schema to the document
-        self.doc.schemas.push(schema);
-        
-        // Process imports recursively if schema loading is enabled
-        if self.load_schemas {
-            let imports = self.doc.schemas.last().unwrap().imports.clone();
-            for import in imports {
-                if !import.schema_location.is_empty() {
-                    self.load_schema_from_ref(&import.schema_location)?;
+    }
+
+    fn leave(&mut self) {
+        if let Some(mark) = self.frames.pop() {
+            self.bindings.truncate(mark);
+        }
+    }
+
+    /// Leave every element entered beyond the first `depth`
+    fn truncate(&mut self, depth: usize) {
+        while self.frames.len() > depth {
+            self.leave();
+        }
+    }
+
+    /// URI bound to `prefix` ("" for the default namespace); `xmlns=""`
+    /// undeclares the default
+    fn resolve(&self, prefix: &str) -> Option<&'a str> {
+        self.bindings
+            .iter()
+            .rev()
+            .find(|(p, _)| *p == prefix)
+            .map(|(_, uri)| *uri)
+            .filter(|uri| !uri.is_empty())
+    }
+}
+
+#[cfg(feature = "mmap")]
+fn read_input(path: &Path) -> Result<memmap2::Mmap> {
+    let file = std::fs::File::open(path)?;
+    // SAFETY: the mapping is read-only and dropped once parsed; the file
+    // must not be truncated by another process meanwhile
+    Ok(unsafe { memmap2::Mmap::map(&file)? })
+}
+
+#[cfg(not(feature = "mmap"))]
+fn read_input(path: &Path) -> Result<Vec<u8>> {
+    Ok(std::fs::read(path)?)
+}
+
+/// The input as UTF-8 without byte-order mark, transcoding UTF-16 (by BOM
+/// or by how `<?xml` is encoded) and encodings named in the XML declaration
+fn decode_input(data: &[u8]) -> Result<std::borrow::Cow<'_, [u8]>> {
+    let (encoding, body) = input_encoding(data);
+    if encoding == encoding_rs::UTF_8 {
+        return Ok(std::borrow::Cow::Borrowed(body));
+    }
+    encoding
+        .decode_without_bom_handling_and_without_replacement(body)
+        .map(|text| std::borrow::Cow::Owned(text.into_owned().into_bytes()))
+        .ok_or_else(|| Error::xml(format!("Invalid {} input", encoding.name())))
+}
+
+/// Encoding of `data` and the input after its byte-order mark
+fn input_encoding(data: &[u8]) -> (&'static encoding_rs::Encoding, &[u8]) {
+    match encoding_rs::Encoding::for_bom(data) {
+        Some((encoding, bom)) => (encoding, &data[bom..]),
+        None if data.starts_with(b"<\0?\0") => (encoding_rs::UTF_16LE, data),
+        None if data.starts_with(b"\0<\0?") => (encoding_rs::UTF_16BE, data),
+        None => (declared_encoding(data).unwrap_or(encoding_rs::UTF_8), data),
+    }
+}
+
+/// Encoding named by the `encoding` pseudo-attribute of the XML declaration
+fn declared_encoding(data: &[u8]) -> Option<&'static encoding_rs::Encoding> {
+    let head = data.strip_prefix(b"<?xml")?;
+    let decl = &head[..memchr::memmem::find(head, b"?>")?];
+    let at = memchr::memmem::find(decl, b"encoding")?;
+    let rest = decl[at + b"encoding".len()..].trim_ascii_start();
+    let rest = rest.strip_prefix(b"=")?.trim_ascii_start();
+    let quote = *rest.first().filter(|q| matches!(q, b'"' | b'\''))?;
+    let value = &rest[1..];
+    let label = &value[..memchr::memchr(quote, value)?];
+    encoding_rs::Encoding::for_label(label)
+}
+
+fn is_xbrl_root(tag_name: &str) -> bool {
+    tag_name == "xbrl" || tag_name.ends_with(":xbrl")
+}
+
+fn local_name(tag_name: &str) -> &str {
+    tag_name.rsplit(':').next().unwrap_or(tag_name)
+}
+
+fn attr<'a>(attrs: &[(&'a str, &'a str)], name: &str) -> Option<&'a str> {
+    attrs.iter().find(|(n, _)| *n == name).map(|(_, v)| *v)
+}
+
+/// Exact value of an `xs:decimal` or `xs:double` lexical form, normalized so
+/// that `1000.00` and `1000` compare and print alike. `None` for values
+/// outside the range of [`Decimal`], `INF` and `NaN`.
+pub(crate) fn parse_decimal(value: &str) -> Option<Decimal> {
+    let value = value.strip_prefix('+').unwrap_or(value);
+    value
+        .parse::<Decimal>()
+        .or_else(|_| Decimal::from_scientific(value))
+        .ok()
+        .map(|d| d.normalize())
+}
+
+/// Numerator and denominator of a fraction item's `numerator/denominator`
+/// text, as facts in tuples and streamed facts hold it
+pub(crate) fn parse_fraction(value: &str) -> Option<(Decimal, Decimal)> {
+    let (numerator, denominator) = value.split_once('/')?;
+    Some((parse_decimal(numerator)?, parse_decimal(denominator)?))
+}
+
+pub(crate) fn is_iso_date(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes.len() == 10
+        && bytes[4] == b'-'
+        && bytes[7] == b'-'
+        && bytes
+            .iter()
+            .enumerate()
+            .all(|(i, b)| i == 4 || i == 7 || b.is_ascii_digit())
+}
+
+fn is_xbrl_root_bytes(tag_name: &[u8]) -> bool {
+    tag_name == b"xbrl" || tag_name.ends_with(b":xbrl")
+}
+
+fn local_name_bytes(tag_name: &[u8]) -> &[u8] {
+    tag_name.rsplit(|&b| b == b':').next().unwrap_or(tag_name)
+}
+
+/// Input of [`Parser::parse_reader_streaming`]: the bytes read but not yet
+/// parsed, where they start in the source and a digest of all bytes read
+struct StreamInput<R> {
+    reader: R,
+    data: Vec<u8>,
+    // Offset, line and column of `data[0]`
+    offset: usize,
+    line: usize,
+    column: usize,
+    hasher: sha2::Sha256,
+}
+
+impl<R: BufRead> StreamInput<R> {
+    fn new(reader: R) -> Self {
+        use sha2::Digest;
+        Self {
+            reader,
+            data: Vec::new(),
+            offset: 0,
+            line: 1,
+            column: 1,
+            hasher: sha2::Sha256::new(),
+        }
+    }
+
+    /// Append the reader's next block; false at the end of the input
+    fn fill(&mut self) -> Result<bool> {
+        use sha2::Digest;
+        loop {
+            match self.reader.fill_buf() {
+                Ok(block) => {
+                    let len = block.len();
+                    self.hasher.update(block);
+                    self.data.extend_from_slice(block);
+                    self.reader.consume(len);
+                    return Ok(len > 0);
                 }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
             }
         }
-        
+    }
+
+    fn read_to_end(&mut self) -> Result<()> {
+        while self.fill()? {}
         Ok(())
     }
-    
-    fn is_tuple(&mut self, _tag_name: &str) -> bool {
-        // Look ahead to see if this element contains other facts
-        // For now, we'll use a simple heuristic: if it doesn't have contextRef, it might be a tuple
-        let attrs = match self.peek_attributes() {
-            Ok(attrs) => attrs,
-            Err(_) => return false,
-        };
-        
-        !attrs.iter().any(|(n, _)| *n == "contextRef")
-    }
-
-    fn get_or_create_context_id(&self, context_ref: &str) -> Result<u16> {
-        self.document.contexts.iter()
-            .position(|c| c.id == context_ref)
-            .map(|i| i as u16)
-            .ok_or_else(|| Error::NotFound(format!("Context: {}", context_ref)))
-    }
-
-    fn get_or_create_unit_id(&self, unit_ref: &str) -> Result<u16> {
-        self.document.units.iter()
-            .position(|u| u.id == unit_ref)
-            .map(|i| (i + 1) as u16) // 0 means no unit
-            .ok_or_else(|| Error::NotFound(format!("Unit: {}", unit_ref)))
-    }
-
-    // Helper methods for reading content
-
-    fn read_text_content_with_cdata(&mut self) -> Result<String> {
-        let mut content = String::new();
-        
-        while !self.scanner.is_eof() {
-            if self.scanner.peek() == Some(b'<') {
-                // Check for CDATA
-                if self.peek_ahead(9) == Some(b"<![CDATA[") {
-                    self.scanner.advance(9);
-                    // Read until ]]>
-                    let start = self.scanner.pos;
-                    while !self.scanner.is_eof() {
-                        if self.scanner.peek() == Some(b']') {
-                            if self.peek_ahead(3) == Some(b"]]>") {
-                                let cdata = std::str::from_utf8(&self.scanner.data[start..self.scanner.pos])
-                                    .map_err(|_| Error::Parse("Invalid UTF-8 in CDATA".to_string()))?;
-                                content.push_str(cdata);
-                                self.scanner.advance(3);
-                                break;
-                            }
-                        }
-                        self.scanner.advance(1);
-                    }
-                } else {
-                    // End of text content
-                    break;
-                }
-            } else {
-                // Regular text
-                let start = self.scanner.pos;
-                while self.scanner.peek() != Some(b'<') && !self.scanner.is_eof() {
-                    self.scanner.advance(1);
-                }
-                let text = std::str::from_utf8(&self.scanner.data[start..self.scanner.pos])
-                    .map_err(|_| Error::Parse("Invalid UTF-8 in text".to_string()))?;
-                content.push_str(text);
+
+    /// Read the rest of the input without keeping it
+    fn skip_to_end(&mut self) -> Result<()> {
+        loop {
+            self.consume(self.data.len());
+            if !self.fill()? {
+                return Ok(());
             }
         }
-        
-        // Decode HTML entities
-        Ok(self.decode_entities(&content))
-    }
-
-    fn read_mixed_content_until_closing(&mut self, tag_name: &str) -> Result<String> {
-        let mut content = String::new();
-        let mut depth = 1;
-        
-        while depth > 0 && !self.scanner.is_eof() {
-            if self.scanner.peek() == Some(b'<') {
-                // Check what kind of tag
-                if self.peek_ahead(2) == Some(b"</") {
-                    // Closing tag
-                    let saved_pos = self.scanner.pos;
-                    self.scanner.advance(2);
-                    let tag = self.read_tag_name()?;
-                    if tag == tag_name || tag.ends_with(&format!(":{}", tag_name)) {
-                        depth -= 1;
-                        if depth == 0 {
-                            self.scanner.pos = saved_pos;
-                            break;
-                        }
-                    }
-                    self.scanner.pos = saved_pos;
-                    content.push('<');
-                    self.scanner.advance(1);
-                } else if self.peek_ahead(9) == Some(b"<![CDATA[") {
-                    // CDATA section
-                    self.scanner.advance(9);
-                    let start = self.scanner.pos;
-                    while !self.scanner.is_eof() {
-                        if self.peek_ahead(3) == Some(b"]]>") {
-                            let cdata = std::str::from_utf8(&self.scanner.data[start..self.scanner.pos])
-                                .map_err(|_| Error::Parse("Invalid UTF-8 in CDATA".to_string()))?;
-                            content.push_str(cdata);
-                            self.scanner.advance(3);
-                            break;
-                        }
-                        self.scanner.advance(1);
-                    }
-                } else {
-                    // Opening tag or other
-                    content.push('<');
-                    self.scanner.advance(1);
-                }
-            } else {
-                // Regular character
-                if let Some(ch) = self.scanner.peek() {
-                    content.push(ch as char);
-                    self.scanner.advance(1);
-                }
+    }
+
+    fn skip_bom(&mut self) {
+        self.consume(3);
+        self.column = 1;
+    }
+
+    /// Drop the first `len` bytes of `data`
+    fn consume(&mut self, len: usize) {
+        let consumed = &self.data[..len];
+        match memchr::memrchr(b'\n', consumed) {
+            Some(last) => {
+                self.line += memchr::memchr_iter(b'\n', consumed).count();
+                self.column = len - last;
             }
+            None => self.column += len,
         }
-        
-        Ok(self.decode_entities(&content))
+        self.offset += len;
+        self.data.drain(..len);
     }
 
-    fn read_xml_content_until_closing(&mut self, tag_name: &str) -> Result<String> {
-        // Similar to mixed content but preserves XML structure
-        self.read_mixed_content_until_closing(tag_name)
+    fn provenance(&self) -> Provenance {
+        use sha2::Digest;
+        Provenance::from_digest(&self.hasher.clone().finalize())
     }
+}
+
+/// What the children of the xbrl root are wrapped in to parse them as an
+/// instance of their own
+struct Batch {
+    // Everything up to and including the root's start tag
+    head: Vec<u8>,
+    // End tags of the root and the envelopes around it
+    tail: Vec<u8>,
+    origin: SourceOrigin,
+}
 
-    fn decode_entities(&self, text: &str) -> String {
-        text.replace("&amp;", "&")
-            .replace("&lt;", "<")
-            .replace("&gt;", ">")
-            .replace("&quot;", "\"")
-            .replace("&apos;", "'")
-            .replace("&#39;", "'")
+/// Where a batch's children start in the source, to move positions in the
+/// batch back to the source
+#[derive(Debug, Default)]
+struct SourceOrigin {
+    // Bytes before the head in the source: a byte-order mark
+    skipped: usize,
+    head_len: usize,
+    head_line: usize,
+    head_column: usize,
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl SourceOrigin {
+    fn of_head(head: &[u8], skipped: usize) -> Self {
+        let line_start = memchr::memrchr(b'\n', head).map_or(0, |i| i + 1);
+        Self {
+            skipped,
+            head_len: head.len(),
+            head_line: memchr::memchr_iter(b'\n', head).count() + 1,
+            head_column: head.len() - line_start + 1,
+            ..Self::default()
+        }
     }
 
-    fn peek_ahead(&self, n: usize) -> Option<&'a [u8]> {
-        if self.scanner.pos + n <= self.scanner.data.len() {
-            Some(&self.scanner.data[self.scanner.pos..self.scanner.pos + n])
-        } else {
-            None
+    /// Children start at the first byte of `input`
+    fn at<R>(&mut self, input: &StreamInput<R>) {
+        self.offset = input.offset;
+        self.line = input.line;
+        self.column = input.column;
+    }
+
+    fn shift(&self, position: SourcePosition) -> SourcePosition {
+        if position.offset < self.head_len {
+            return SourcePosition {
+                offset: self.skipped + position.offset,
+                ..position
+            };
+        }
+        SourcePosition {
+            offset: self.offset + position.offset - self.head_len,
+            line: self.line + position.line.saturating_sub(self.head_line),
+            column: if position.line == self.head_line {
+                self.column + position.column.saturating_sub(self.head_column)
+            } else {
+                position.column
+            },
         }
     }
 
-    fn peek_tag_name(&mut self) -> Result<String> {
-        let saved_pos = self.scanner.pos;
-        self.scanner.skip_whitespace();
-        
-        if self.scanner.peek() == Some(b'<') {
-            self.scanner.advance(1);
-            let tag = self.read_tag_name()?.to_string();
-            self.scanner.pos = saved_pos;
-            Ok(tag)
-        } else {
-            self.scanner.pos = saved_pos;
-            Err(Error::Parse("Expected tag".to_string()))
+    fn shift_error(&self, error: Error) -> Error {
+        match error {
+            Error::XmlSyntax {
+                message,
+                position: Some(position),
+                snippet,
+            } => Error::XmlSyntax {
+                message,
+                position: Some(self.shift(position)),
+                snippet,
+            },
+            other => other,
         }
     }
+}
 
-    fn peek_attributes(&mut self) -> Result<Vec<(&'a str, &'a str)>> {
-        let saved_pos = self.scanner.pos;
-        let attrs = self.parse_attributes();
-        self.scanner.pos = saved_pos;
-        attrs
+/// Markup, or a run of text, in partly read input
+enum Markup<'b> {
+    Start {
+        name: &'b [u8],
+        empty: bool,
+    },
+    End,
+    /// Text, comments, CDATA sections, processing instructions and
+    /// declarations
+    Other,
+}
+
+/// Whether `data` holds no element and no text but whitespace: nothing, or
+/// at most a prolog, possibly cut off
+fn only_prolog(data: &[u8]) -> bool {
+    let mut pos = 0;
+    loop {
+        match next_markup(data, pos) {
+            None => return true,
+            Some((Markup::Other, end))
+                if data[pos..end].iter().all(u8::is_ascii_whitespace)
+                    || data[pos] == b'<' && !data[pos..].starts_with(b"<![CDATA[") =>
+            {
+                pos = end
+            }
+            Some(_) => return false,
+        }
     }
+}
 
-    fn check_self_closing(&self) -> bool {
-        // Check if the previous characters indicate self-closing tag
-        if self.scanner.pos >= 2 {
-            self.scanner.data[self.scanner.pos - 2] == b'/' && self.scanner.data[self.scanner.pos - 1] == b'>'
-        } else {
-            false
+/// The markup at `pos` and the offset just past it, or `None` when `data`
+/// ends before it does
+fn next_markup(data: &[u8], pos: usize) -> Option<(Markup<'_>, usize)> {
+    let rest = &data[pos..];
+    if *rest.first()? != b'<' {
+        let end = memchr::memchr(b'<', rest).unwrap_or(rest.len());
+        return Some((Markup::Other, pos + end));
+    }
+    // Too short to tell a comment or CDATA section from a tag
+    if rest.len() < 9 && memchr::memchr(b'>', rest).is_none() {
+        return None;
+    }
+    let past = |needle: &[u8], from: usize| {
+        memchr::memmem::find(&rest[from..], needle).map(|at| pos + from + at + needle.len())
+    };
+    if rest.starts_with(b"<!--") {
+        return Some((Markup::Other, past(b"-->", 4)?));
+    }
+    if rest.starts_with(b"<![CDATA[") {
+        return Some((Markup::Other, past(b"]]>", 9)?));
+    }
+    if rest.starts_with(b"<?") {
+        return Some((Markup::Other, past(b"?>", 2)?));
+    }
+
+    // Tags end at the first `>` outside quoted attribute values, and a
+    // DOCTYPE outside its internal subset
+    let declaration = rest[1] == b'!';
+    let (mut quote, mut subset) = (None, 0usize);
+    let end = rest.iter().position(|&b| {
+        match quote {
+            Some(q) => quote = (b != q).then_some(q),
+            None => match b {
+                b'"' | b'\'' => quote = Some(b),
+                b'[' if declaration => subset += 1,
+                b']' if declaration => subset = subset.saturating_sub(1),
+                b'>' => return subset == 0,
+                _ => {}
+            },
+        }
+        false
+    })?;
+    let markup = match rest[1] {
+        b'/' => Markup::End,
+        b'!' => Markup::Other,
+        _ => {
+            let tag = &rest[1..end];
+            let name_end = tag
+                .iter()
+                .position(|b| b.is_ascii_whitespace() || *b == b'/')
+                .unwrap_or(tag.len());
+            Markup::Start {
+                name: &tag[..name_end],
+                empty: tag.ends_with(b"/"),
+            }
         }
+    };
+    Some((markup, pos + end + 1))
+}
+
+/// Slices of `text` that each hold one instance document
+fn embedded_instances(text: &str) -> Vec<&str> {
+    // EDGAR dissemination format: schemas and linkbases are wrapped in
+    // <XBRL> too, so keep only the blocks that carry an instance
+    if text.contains("<XBRL>") {
+        return text
+            .split("<XBRL>")
+            .skip(1)
+            .filter_map(|block| block.split("</XBRL>").next())
+            .filter(|block| is_inline_xbrl(block.as_bytes()) || !instance_roots(block).is_empty())
+            .collect();
     }
 
-    fn skip_closing_tag(&mut self, tag_name: &str) -> Result<()> {
-        self.scanner.skip_whitespace();
-        if self.scanner.peek() == Some(b'<') {
-            self.scanner.advance(1);
-            if self.scanner.peek() == Some(b'/') {
-                self.scanner.advance(1);
-                let tag = self.read_tag_name()?;
-                if tag == tag_name || tag.ends_with(tag_name) || tag_name.ends_with(&tag) {
-                    self.skip_to_tag_end()?;
-                    return Ok(());
+    instance_roots(text)
+}
+
+/// Every `<xbrl>` / `<prefix:xbrl>` element in `text`, start tag to end tag
+fn instance_roots(text: &str) -> Vec<&str> {
+    let mut roots = Vec::new();
+    let mut pos = 0;
+
+    while let Some(offset) = text[pos..].find('<') {
+        let start = pos + offset;
+        let name_len = text[start + 1..]
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .unwrap_or(text.len() - start - 1);
+        let name = &text[start + 1..start + 1 + name_len];
+
+        if is_xbrl_root(name) {
+            let close = format!("</{}>", name);
+            match text[start..].find(&close) {
+                Some(end) => {
+                    let end = start + end + close.len();
+                    roots.push(&text[start..end]);
+                    pos = end;
+                    continue;
                 }
+                None => break,
             }
         }
-        Ok(())
+        pos = start + 1;
+    }
+
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INSTANCE: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance"
+    xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink"
+    xmlns:xbrldi="http://xbrl.org/2006/xbrldi" xmlns:iso4217="http://www.xbrl.org/2003/iso4217"
+    xmlns:dei="http://xbrl.sec.gov/dei/2023" xmlns:us-gaap="http://fasb.org/us-gaap/2023"
+    xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+  <link:schemaRef xlink:type="simple" xlink:href="aapl-20230930.xsd"/>
+  <!-- facts may precede the contexts they use -->
+  <us-gaap:Revenues id="f1" contextRef="FY23" unitRef="usd" decimals="-6">383285000000</us-gaap:Revenues>
+  <dei:EntityCentralIndexKey contextRef="FY23">0000320193</dei:EntityCentralIndexKey>
+  <us-gaap:Liabilities contextRef="FY23_seg" unitRef="usd" xsi:nil="true"/>
+  <us-gaap:EarningsPerShareBasic contextRef="FY23" unitRef="usdPerShare" decimals="2">(6.16)</us-gaap:EarningsPerShareBasic>
+  <xbrli:context id="FY23">
+    <xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2022-09-25</xbrli:startDate><xbrli:endDate>2023-09-30</xbrli:endDate></xbrli:period>
+  </xbrli:context>
+  <xbrli:context id="FY23_seg">
+    <xbrli:entity>
+      <xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier>
+      <xbrli:segment><xbrldi:explicitMember dimension="us-gaap:StatementBusinessSegmentsAxis">aapl:AmericasSegmentMember</xbrldi:explicitMember></xbrli:segment>
+    </xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-09-30</xbrli:instant></xbrli:period>
+  </xbrli:context>
+  <xbrli:unit id="usd"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <xbrli:unit id="usdPerShare">
+    <xbrli:divide>
+      <xbrli:unitNumerator><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unitNumerator>
+      <xbrli:unitDenominator><xbrli:measure>xbrli:shares</xbrli:measure></xbrli:unitDenominator>
+    </xbrli:divide>
+  </xbrli:unit>
+  <link:footnoteLink xlink:type="extended" xlink:role="http://www.xbrl.org/2003/role/link">
+    <link:loc xlink:type="locator" xlink:href="#f1" xlink:label="fact1"/>
+    <link:footnote xlink:type="resource" xlink:label="fn1" xml:lang="en-US">Net of <b>returns</b> &amp; allowances</link:footnote>
+    <link:footnoteArc xlink:type="arc" xlink:from="fact1" xlink:to="fn1"/>
+  </link:footnoteLink>
+</xbrli:xbrl>"##;
+
+    #[test]
+    fn test_parse_instance() {
+        let doc = Parser::new().parse_str(INSTANCE).unwrap();
+
+        assert_eq!(doc.schema_ref.as_deref(), Some("aapl-20230930.xsd"));
+        assert_eq!(doc.contexts.len(), 2);
+        assert_eq!(doc.units.len(), 2);
+        assert_eq!(doc.units[1].canonical(), "iso4217:USD/xbrli:shares");
+        assert_eq!(
+            doc.contexts[1].dimensions(),
+            [(
+                "us-gaap:StatementBusinessSegmentsAxis",
+                "aapl:AmericasSegmentMember"
+            )]
+        );
+        // Period dates are parsed with the context
+        let moment = |value: &str| parse_date_union(value, false);
+        assert_eq!(
+            doc.contexts[0].period,
+            Period::Duration {
+                start: "2022-09-25".to_string(),
+                end: "2023-09-30".to_string(),
+                start_at: moment("2022-09-25T00:00:00"),
+                end_at: moment("2023-10-01T00:00:00"),
+            }
+        );
+        assert!(matches!(
+            doc.contexts[1].period,
+            Period::Instant { at, .. } if at == moment("2023-10-01T00:00:00")
+        ));
+
+        let facts: Vec<_> = doc.iter_facts().collect();
+        assert_eq!(facts.len(), 4);
+        assert_eq!(facts[0].concept(), "us-gaap:Revenues");
+        assert_eq!(facts[0].context().unwrap().id, "FY23");
+        assert_eq!(facts[0].unit().unwrap().id, "usd");
+        assert_eq!(facts[0].value().as_f64(), Some(383285000000.0));
+        assert_eq!(facts[0].decimals(), Some(-6));
+        assert_eq!(facts[1].value().to_string(), "0000320193");
+        assert!(facts[1].unit().is_none());
+        assert!(facts[2].value().is_nil());
+        assert_eq!(facts[2].context().unwrap().id, "FY23_seg");
+        assert_eq!(facts[3].value().as_f64(), Some(-6.16));
+
+        assert_eq!(doc.footnotes.len(), 1);
+        assert_eq!(
+            doc.footnotes[0].content,
+            "Net of <b>returns</b> &amp; allowances"
+        );
+        assert_eq!(doc.footnotes[0].fact_refs, ["f1"]);
+        assert_eq!(doc.facts.footnote_refs[0], ["fn1"]);
+        assert_eq!(doc.footnotes_for_fact("f1")[0].id, "fn1");
+        assert!(doc.footnotes_for_fact("f2").is_empty());
+
+        // Resolving again does not duplicate references
+        let mut doc = doc;
+        doc.resolve_footnotes();
+        assert_eq!(doc.facts.footnote_refs[0], ["fn1"]);
     }
 
-    fn skip_doctype(&mut self) -> Result<()> {
-        // Skip DOCTYPE declaration
-        while !self.scanner.is_eof() {
-            if self.scanner.peek() == Some(b'>') {
-                self.scanner.advance(1);
-                break;
+    #[test]
+    fn test_fraction_facts() {
+        let doc = Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="pure"><xbrli:measure>xbrli:pure</xbrli:measure></xbrli:unit>
+  <a:Ratio contextRef="c" unitRef="pure">
+    <xbrli:numerator> 1 </xbrli:numerator>
+    <xbrli:denominator>3</xbrli:denominator>
+  </a:Ratio>
+  <a:Text contextRef="c">1/3</a:Text>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+
+        let ratio = doc.fact(0).unwrap();
+        assert_eq!(
+            ratio.value(),
+            &FactValue::Fraction {
+                numerator: Decimal::ONE,
+                denominator: Decimal::from(3),
             }
-            self.scanner.advance(1);
+        );
+        assert_eq!(ratio.value().to_string(), "1/3");
+        assert_eq!(ratio.value().as_decimal(), None);
+        assert!((ratio.value().as_f64().unwrap() - 1.0 / 3.0).abs() < 1e-12);
+        assert_eq!(doc.fact(1).unwrap().value(), &FactValue::Text("1/3".into()));
+
+        let table = doc.fact_table();
+        assert_eq!(table.numerator, [Some("1".to_string()), None]);
+        assert_eq!(table.denominator, [Some("3".to_string()), None]);
+
+        // Written back as numerator and denominator, without decimals
+        let xml = doc.to_xml().unwrap();
+        assert!(!xml.contains("decimals"));
+        let back = Parser::new().parse_str(&xml).unwrap();
+        assert_eq!(back.fact(0).unwrap().value(), ratio.value());
+    }
+
+    #[test]
+    fn test_footnote_arcs() {
+        let doc = Parser::new()
+            .parse_str(
+                r##"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:link="http://www.xbrl.org/2003/linkbase"
+    xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:a="http://example.com/a">
+  <a:Revenue id="f1" contextRef="c1">100</a:Revenue>
+  <a:Revenue id="f2" contextRef="c1">200</a:Revenue>
+  <a:Revenue id="f3" contextRef="c1">300</a:Revenue>
+  <link:footnoteLink xlink:type="extended" xlink:role="http://www.xbrl.org/2003/role/link">
+    <link:loc xlink:type="locator" xlink:href="#f1" xlink:label="both"/>
+    <link:loc xlink:type="locator" xlink:href="#f2" xlink:label="both"/>
+    <link:loc xlink:type="locator" xlink:href="#f3" xlink:label="total"/>
+    <link:footnote xlink:type="resource" xlink:label="n1" id="fn1">Restated.</link:footnote>
+    <link:footnoteArc xlink:type="arc" xlink:arcrole="http://www.xbrl.org/2003/arcrole/fact-footnote" xlink:from="both" xlink:to="n1"/>
+    <link:footnoteArc xlink:type="arc" xlink:arcrole="http://example.com/arcrole/fact-explanatoryFact" xlink:from="both" xlink:to="total" order="2"/>
+  </link:footnoteLink>
+</xbrli:xbrl>"##,
+            )
+            .unwrap();
+
+        assert_eq!(doc.footnotes[0].fact_refs, ["f1", "f2"]);
+        assert_eq!(doc.footnote_arcs.len(), 4);
+        let to_footnote: Vec<_> = doc.footnote_arcs_from("f2").map(|a| &a.to).collect();
+        assert_eq!(
+            to_footnote,
+            [
+                &FootnoteTarget::Footnote("fn1".into()),
+                &FootnoteTarget::Fact("f3".into())
+            ]
+        );
+        assert_eq!(doc.footnote_arcs[0].arcrole, FACT_FOOTNOTE_ARCROLE);
+
+        let explanatory = "http://example.com/arcrole/fact-explanatoryFact";
+        let linked: Vec<_> = doc.linked_facts("f1", explanatory).collect();
+        assert_eq!(linked, ["f3"]);
+        assert_eq!(doc.footnote_arcs[3].order, 2.0);
+        assert!(doc.linked_facts("f3", explanatory).next().is_none());
+        assert!(doc.footnotes_for_fact("f3").is_empty());
+    }
+
+    #[test]
+    fn test_entity_and_character_references() {
+        let doc = Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="c&#x31;"><xbrli:entity><xbrli:identifier scheme="http://example.com/?a=1&amp;b=2">X&#x26;Y</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <a:Note contextRef="c1" id="n&#10;1
+2">R&amp;D &#8212; &#x2014; &#65;&#0;&#+1;</a:Note>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+        let context = &doc.contexts[0];
+        assert_eq!(context.id, "c1");
+        assert_eq!(context.entity.scheme, "http://example.com/?a=1&b=2");
+        assert_eq!(context.entity.identifier, "X&Y");
+
+        let fact = doc.fact(0).unwrap();
+        assert_eq!(fact.context().unwrap().id, "c1");
+        assert_eq!(fact.value().to_string(), "R&D \u{2014} \u{2014} A&#0;&#+1;");
+        // Literal line breaks in attributes normalize to spaces, references do not
+        assert_eq!(fact.id(), Some("n\n1 2"));
+    }
+
+    #[test]
+    fn test_parse_utf16_and_declared_encodings() {
+        let instance = |encoding: &str| {
+            format!(
+                r#"<?xml version="1.0" encoding="{}"?>
+<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://example.com">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <a:Name contextRef="c">Société Générale</a:Name>
+</xbrli:xbrl>"#,
+                encoding
+            )
+        };
+        let utf16 = |text: String, le: bool| -> Vec<u8> {
+            text.encode_utf16()
+                .flat_map(|u| if le { u.to_le_bytes() } else { u.to_be_bytes() })
+                .collect()
+        };
+
+        let with_bom = [&[0xFF, 0xFE][..], &utf16(instance("UTF-16"), true)].concat();
+        let without_bom = utf16(instance("UTF-16"), false);
+        let latin1 = encoding_rs::WINDOWS_1252
+            .encode(&instance("ISO-8859-1"))
+            .0
+            .into_owned();
+        for data in [with_bom, without_bom, latin1] {
+            let doc = Parser::new().parse_bytes(&data).unwrap();
+            assert_eq!(doc.fact(0).unwrap().value().to_string(), "Société Générale");
         }
-        Ok(())
+
+        let invalid = [&[0xFF, 0xFE][..], b"<\0x"].concat();
+        assert!(Parser::new().parse_bytes(&invalid).is_err());
+    }
+
+    #[test]
+    fn test_error_recovery() {
+        let broken = r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://example.com">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:context><xbrli:entity><xbrli:identifier scheme="http://example.com">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2022-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <a:Cash contextRef="c" decimals=0>1</a:Cash>
+  <a:Assets contextRef="c">2</a:Assets>
+</xbrli:xbrl>"#;
+        assert!(Parser::new().parse_str(broken).is_err());
+
+        let doc = Parser::new()
+            .with_error_recovery(true)
+            .parse_str(broken)
+            .unwrap();
+        assert_eq!(doc.contexts.len(), 1);
+        let concepts: Vec<_> = doc.iter_facts().map(|f| f.concept()).collect();
+        assert_eq!(concepts, ["a:Assets"]);
+        assert_eq!(doc.fact(0).unwrap().context().unwrap().id, "c");
+
+        let lines: Vec<_> = doc.diagnostics.iter().map(|d| d.location.line).collect();
+        assert_eq!(lines, [4, 6]);
+        assert_eq!(doc.diagnostics[0].message, "Context missing id");
+        assert_eq!(doc.diagnostics[1].severity, crate::Severity::Error);
+    }
+
+    #[test]
+    fn test_truncated_input() {
+        let full = r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <a:Cash contextRef="c" decimals="0">100</a:Cash>
+  <a:Debt contextRef="c" decimals="0">250</a:Debt>
+</xbrli:xbrl>"#;
+        assert_eq!(Parser::new().parse_str(full).unwrap().facts.len(), 2);
+
+        // Cut inside the second fact's value, between the facts, and
+        // inside the root's start tag
+        let debt = full.find("<a:Debt").unwrap();
+        for (cut, facts) in [(debt + 38, 1), (debt, 1), (20, 0)] {
+            let truncated = &full[..cut];
+            match Parser::new().parse_str(truncated) {
+                Err(Error::Truncated { at_byte }) => assert_eq!(at_byte, cut),
+                other => panic!("cut at {}: {:?}", cut, other.map(|d| d.facts.len())),
+            }
+
+            let doc = Parser::new()
+                .with_error_recovery(true)
+                .parse_str(truncated)
+                .unwrap();
+            assert_eq!(doc.facts.len(), facts);
+            assert_eq!(doc.diagnostics.len(), 1);
+            assert!(doc.diagnostics[0]
+                .message
+                .contains("before the xbrl root is closed"));
+        }
+        let doc = Parser::new()
+            .with_error_recovery(true)
+            .parse_str(&full[..debt + 38])
+            .unwrap();
+        assert_eq!(doc.diagnostics[0].location.line, 5);
+
+        // Malformed markup in a complete document is not a truncation
+        let broken = full.replace(r#"decimals="0">250"#, r#"decimals=0>250"#);
+        assert!(matches!(
+            Parser::new().parse_str(&broken),
+            Err(Error::XmlSyntax { .. })
+        ));
+        assert!(Parser::new()
+            .parse_str(r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance"/>"#)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_input_without_xbrl_root() {
+        let streamed = |input: &str| {
+            Parser::new()
+                .parse_reader_streaming(input.as_bytes(), &mut |_: ParseEvent<'_>| Ok(()))
+                .err()
+                .unwrap()
+        };
+
+        // Nothing past the prolog: the root was cut off before it opened
+        let declaration = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n";
+        let comment = format!("{}<!-- generated", declaration);
+        for input in ["", "  \n", declaration, &comment, "<xbrli:xb"] {
+            for error in [
+                Parser::new().parse_str(input).err().unwrap(),
+                streamed(input),
+            ] {
+                match error {
+                    Error::Truncated { at_byte } => assert_eq!(at_byte, input.len()),
+                    other => panic!("{:?}: {:?}", input, other),
+                }
+            }
+            let doc = Parser::new()
+                .with_error_recovery(true)
+                .parse_str(input)
+                .unwrap();
+            assert!(doc.diagnostics[0]
+                .message
+                .contains("before the xbrl root is opened"));
+        }
+
+        // Anything else is not an instance
+        let inline = r#"<html xmlns="http://www.w3.org/1999/xhtml" xmlns:ix="http://www.xbrl.org/2013/inlineXBRL"><body/></html>"#;
+        for (input, message) in [
+            ("hello", "holds no XML elements"),
+            (
+                "<html><body>hello</body></html>",
+                "document element is <html>",
+            ),
+            ("<?xml version=\"1.0\"?>\n<a:Cash>1</a:Cash>", "<a:Cash>"),
+            (inline, "InlineParser"),
+        ] {
+            for error in [
+                Parser::new().parse_str(input).err().unwrap(),
+                streamed(input),
+            ] {
+                assert_eq!(error.kind(), crate::ErrorKind::XmlSyntax, "{:?}", input);
+                assert!(error.to_string().contains(message), "{}", error);
+            }
+            assert!(Parser::new()
+                .with_error_recovery(true)
+                .parse_str(input)
+                .is_err());
+        }
+        let error = Parser::new()
+            .parse_str("<?xml version=\"1.0\"?>\n<a:Cash>1</a:Cash>")
+            .err()
+            .unwrap();
+        assert_eq!(error.position().map(|p| (p.line, p.column)), Some((2, 1)));
+    }
+
+    #[test]
+    fn test_parse_error_position() {
+        let err = Parser::new()
+            .parse_str(
+                "<xbrli:xbrl xmlns:xbrli=\"http://www.xbrl.org/2003/instance\">\n  <xbrli:unit id=\"u\"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>\n  <a:Cash contextRef=c>1</a:Cash>\n</xbrli:xbrl>",
+            )
+            .err()
+            .unwrap();
+        let Error::XmlSyntax {
+            message,
+            position: Some(position),
+            snippet: Some(snippet),
+        } = &err
+        else {
+            panic!("unexpected error: {}", err);
+        };
+        assert_eq!(message, "Expected quote in attribute");
+        assert_eq!((position.line, position.column), (3, 22));
+        assert_eq!(snippet, "<a:Cash contextRef=c>1</a:Cash>");
+        assert_eq!(err.kind(), crate::ErrorKind::XmlSyntax);
+        assert!(err
+            .to_string()
+            .starts_with("Parse error at line 3, column 22"));
+    }
+
+    #[test]
+    fn test_tuples_and_unresolved_refs() {
+        let doc = Parser::new()
+            .parse_str(
+                r#"<xbrl xmlns:ex="http://example.com/ex">
+  <ex:Address id="t1">
+    <ex:Street contextRef="c">1 Infinite Loop</ex:Street>
+    <ex:City contextRef="c">Cupertino</ex:City>
+  </ex:Address>
+  <ex:Count contextRef="missing" unitRef="none">1</ex:Count>
+</xbrl>"#,
+            )
+            .unwrap();
+
+        assert_eq!(doc.tuples.len(), 1);
+        assert_eq!(doc.tuples[0].id.as_deref(), Some("t1"));
+        assert_eq!(doc.tuples[0].facts.len(), 2);
+        // Tuple contents are stored too, so their references are checked
+        assert_eq!(doc.facts.len(), 3);
+        assert_eq!(doc.facts.tuple_paths[0], [0]);
+        assert!(doc.facts.tuple_paths[2].is_empty());
+        assert_eq!(doc.facts.context_ids[2], UNRESOLVED);
+        assert_eq!(doc.facts.unit_ids[2], UNRESOLVED);
+        assert_eq!(crate::validator::XbrlValidator::new().check(&doc).len(), 4);
+    }
+
+    #[test]
+    fn test_more_contexts_and_units_than_u16_ids() {
+        const COUNT: usize = 70_000;
+        let mut xml = String::from(
+            r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">"#,
+        );
+        for i in 0..COUNT {
+            xml.push_str(&format!(
+                r#"<xbrli:context id="c{i}"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">1</xbrli:identifier></xbrli:entity><xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context><xbrli:unit id="u{i}"><xbrli:measure>a:m{i}</xbrli:measure></xbrli:unit>"#
+            ));
+        }
+        xml.push_str(&format!(
+            r#"<a:Cash contextRef="c{last}" unitRef="u{last}" decimals="0">1</a:Cash></xbrli:xbrl>"#,
+            last = COUNT - 1
+        ));
+
+        let doc = Parser::new().parse_str(&xml).unwrap();
+        assert_eq!(doc.contexts.len(), COUNT);
+        assert_eq!(doc.units.len(), COUNT);
+        let fact = doc.fact(0).unwrap();
+        assert_eq!(fact.context().unwrap().id, format!("c{}", COUNT - 1));
+        assert_eq!(fact.unit().unwrap().id, format!("u{}", COUNT - 1));
+    }
+
+    #[test]
+    fn test_nested_tuple_paths() {
+        let doc = Parser::new()
+            .parse_str(
+                r#"<xbrl xmlns:ex="http://example.com/ex">
+  <context id="c"><entity><identifier scheme="http://example.com">1</identifier></entity>
+    <period><instant>2023-12-31</instant></period></context>
+  <ex:Note id="n0"><ex:Text contextRef="c">first</ex:Text></ex:Note>
+  <ex:Officers id="t1">
+    <ex:Count contextRef="c">2</ex:Count>
+    <ex:Officer><ex:Name contextRef="c">A</ex:Name></ex:Officer>
+    <ex:Officer><ex:Name contextRef="c">B</ex:Name></ex:Officer>
+    <ex:Officer><ex:Name contextRef="c">B</ex:Name></ex:Officer>
+  </ex:Officers>
+</xbrl>"#,
+            )
+            .unwrap();
+        let paths: Vec<_> = doc.iter_facts().map(|f| f.tuple_path().to_vec()).collect();
+        assert_eq!(
+            paths,
+            [vec![0], vec![1], vec![1, 1], vec![1, 2], vec![1, 3]]
+        );
+        assert_eq!(doc.fact(3).unwrap().tuple().unwrap().name, "ex:Officer");
+
+        let tuples: Vec<_> = doc
+            .tuples_iter()
+            .map(|(path, t)| (path, t.name.as_str()))
+            .collect();
+        assert_eq!(
+            tuples,
+            [
+                (vec![0], "ex:Note"),
+                (vec![1], "ex:Officers"),
+                (vec![1, 1], "ex:Officer"),
+                (vec![1, 2], "ex:Officer"),
+                (vec![1, 3], "ex:Officer"),
+            ]
+        );
+        let names: Vec<_> = doc
+            .tuple_facts(&[1, 2])
+            .map(|f| f.value().to_string())
+            .collect();
+        assert_eq!(names, ["B"]);
+        // Facts of different tuples are not duplicates of each other
+        let errors = crate::validator::XbrlValidator::new().strict().check(&doc);
+        assert!(errors.iter().all(|e| !e.rule().contains("duplicate")));
+    }
+
+    #[test]
+    fn test_value_transforms_by_item_type() {
+        use crate::transform::{IxtFormat, StripFootnoteMarkers, TransformTarget};
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("ex.xsd"),
+            r#"<xsd:schema xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xbrli="http://www.xbrl.org/2003/instance" targetNamespace="http://example.com/ex">
+  <xsd:element name="Revenue" type="xbrli:monetaryItemType"/>
+  <xsd:element name="Shares" type="xbrli:sharesItemType"/>
+</xsd:schema>"#,
+        )
+        .unwrap();
+        let instance = dir.path().join("ex.xml");
+        std::fs::write(
+            &instance,
+            r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:ex="http://example.com/ex">
+  <link:schemaRef xlink:type="simple" xlink:href="ex.xsd"/>
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="u"><xbrli:measure>iso4217:EUR</xbrli:measure></xbrli:unit>
+  <ex:Revenue contextRef="c" unitRef="u" decimals="2">1.234,50</ex:Revenue>
+  <ex:Shares contextRef="c" unitRef="u" decimals="0">1000</ex:Shares>
+  <ex:Name contextRef="c">Example AG (1)</ex:Name>
+</xbrli:xbrl>"#,
+        )
+        .unwrap();
+
+        let options = ParserOptions::new()
+            .with_transform(
+                TransformTarget::ItemType("monetaryItemType".to_string()),
+                IxtFormat::new("ixt:num-comma-decimal"),
+            )
+            .with_transform(TransformTarget::NonNumeric, StripFootnoteMarkers);
+        let doc = Parser::new()
+            .with_options(options)
+            .with_schema_loading(true)
+            .parse_file(&instance)
+            .unwrap();
+
+        assert_eq!(doc.fact(0).unwrap().value().as_f64(), Some(1234.5));
+        assert_eq!(doc.fact(1).unwrap().value().as_f64(), Some(1000.0));
+        assert_eq!(doc.fact(2).unwrap().value().to_string(), "Example AG");
+    }
+
+    #[test]
+    fn test_nested_namespace_scopes() {
+        let doc = Parser::new()
+            .parse_str(
+                r#"<xbrl xmlns="http://www.xbrl.org/2003/instance" xmlns:ex="http://example.com/2023">
+  <context id="c" xmlns:dim="http://example.com/2023">
+    <entity><identifier scheme="http://www.sec.gov/CIK">1</identifier>
+      <segment><xbrldi:explicitMember xmlns:xbrldi="http://xbrl.org/2006/xbrldi" dimension="dim:SegmentAxis">dim:EuropeMember</xbrldi:explicitMember></segment>
+    </entity>
+    <period><instant>2023-12-31</instant></period>
+  </context>
+  <unit id="u"><measure xmlns:cur="http://www.xbrl.org/2003/iso4217">cur:USD</measure></unit>
+  <ex:Revenue contextRef="c" unitRef="u" decimals="0">10</ex:Revenue>
+  <ex:Revenue xmlns:ex="http://example.com/2024" contextRef="c" unitRef="u" decimals="0">20</ex:Revenue>
+  <other:Assets xmlns:other="http://example.com/2023" contextRef="c" unitRef="u" decimals="0">30</other:Assets>
+  <t:Address xmlns:t="http://example.com/2024"><t:City contextRef="c">Cupertino</t:City></t:Address>
+  <ex:Liabilities contextRef="c" unitRef="u" decimals="0">40</ex:Liabilities>
+</xbrl>"#,
+            )
+            .unwrap();
+
+        let concepts: Vec<&str> = doc.iter_facts().map(|f| f.concept()).collect();
+        assert_eq!(
+            concepts,
+            [
+                "ex:Revenue",
+                "ex1:Revenue",
+                "ex:Assets",
+                "ex1:City",
+                "ex:Liabilities"
+            ]
+        );
+        assert_eq!(doc.namespaces["ex1"], "http://example.com/2024");
+        assert_eq!(
+            doc.fact(1).unwrap().namespace(),
+            Some("http://example.com/2024")
+        );
+        assert_eq!(doc.tuples[0].name, "ex1:Address");
+
+        let members = &doc.contexts[0]
+            .entity
+            .segment
+            .as_ref()
+            .unwrap()
+            .explicit_members;
+        assert_eq!(members[0].dimension, "ex:SegmentAxis");
+        assert_eq!(members[0].member, "ex:EuropeMember");
+        let UnitType::Simple(measures) = &doc.units[0].unit_type else {
+            panic!("expected a simple unit");
+        };
+        assert_eq!(measures[0].namespace, "http://www.xbrl.org/2003/iso4217");
+    }
+
+    #[test]
+    fn test_parse_files_keeps_input_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths: Vec<_> = (0..16)
+            .map(|i| {
+                let path = dir.path().join(format!("{}.xml", i));
+                let facts = "<us-gaap:Assets contextRef=\"c\">1</us-gaap:Assets>".repeat(i);
+                std::fs::write(
+                    &path,
+                    format!(
+                        "<xbrl xmlns:us-gaap=\"http://fasb.org/us-gaap/2023\">{}</xbrl>",
+                        facts
+                    ),
+                )
+                .unwrap();
+                path
+            })
+            .collect();
+
+        let docs = Parser::new().parse_files(&paths);
+        for (i, doc) in docs.into_iter().enumerate() {
+            assert_eq!(doc.unwrap().facts.len(), i);
+        }
+    }
+
+    #[test]
+    fn test_source_positions() {
+        let xml = "\u{feff}<xbrl xmlns:a=\"http://example.com/a\">\n  <context id=\"c\"><entity><identifier scheme=\"s\">1</identifier></entity>\
+                   <period><instant>2023-12-31</instant></period></context>\n  \
+                   <a:X id=\"f1\" contextRef=\"missing\">1</a:X>\n</xbrl>";
+        let doc = Parser::new().parse_str(xml).unwrap();
+        let map = doc.source_map.as_ref().unwrap();
+        assert_eq!(&xml.as_bytes()[map.context_offsets[0]..][..8], b"<context");
+        assert_eq!(map.position(map.fact_offsets[0]).line, 3);
+        assert_eq!(map.position(map.fact_offsets[0]).column, 3);
+
+        let result = crate::Validator::sec_edgar().validate(&doc).unwrap();
+        let finding = result
+            .findings
+            .iter()
+            .find(|f| f.code == "xbrl.2.1.contextRef")
+            .unwrap();
+        assert_eq!(finding.position.map(|p| (p.line, p.column)), Some((3, 3)));
+        assert!(matches!(
+            finding.error,
+            crate::validator::ValidationError::InvalidContextRef { fact_index: 0, .. }
+        ));
+    }
+
+    #[test]
+    fn test_shared_interner() {
+        let interner = StringInterner::new();
+        let parser = Parser::new().with_interner(interner.clone());
+        let a = parser
+            .parse_str("<xbrl xmlns:a=\"http://example.com/a\"><a:X contextRef=\"c\">1</a:X><a:Y contextRef=\"c\">2</a:Y></xbrl>")
+            .unwrap();
+        let b = parser
+            .parse_str("<xbrl xmlns:a=\"http://example.com/a\"><a:Y contextRef=\"c\">3</a:Y><a:Y contextRef=\"c\">4</a:Y></xbrl>")
+            .unwrap();
+
+        assert_eq!(interner.len(), 2);
+        assert_eq!(b.concept_names, ["a:Y"]);
+        assert_eq!(b.facts.concept_ids, [0, 0]);
+        assert_eq!(a.fact(1).unwrap().concept(), "a:Y");
     }
 
-    // Implement remaining base methods from parser.rs
-    // ... (include all the base parsing methods like read_tag_name, parse_attributes, etc.)
+    #[test]
+    fn test_parse_multi_concatenated() {
+        let fact = "<us-gaap:Assets contextRef=\"c\">1</us-gaap:Assets>";
+        let stream = format!(
+            "<soap:Envelope><soap:Body><xbrli:xbrl xmlns:us-gaap=\"{ns}\">{f}</xbrli:xbrl></soap:Body></soap:Envelope>\
+             <xbrl xmlns:us-gaap=\"{ns}\">{f}{f}</xbrl>",
+            ns = "http://fasb.org/us-gaap/2023",
+            f = fact
+        );
+
+        let docs = Parser::new().parse_multi(stream.as_bytes()).unwrap();
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0].facts.len(), 1);
+        assert_eq!(docs[1].facts.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_multi_edgar_txt() {
+        let stream = "<SEC-DOCUMENT>\n<DOCUMENT>\n<TYPE>EX-101.SCH\n<TEXT>\n<XBRL>\n\
+            <xsd:schema></xsd:schema>\n</XBRL>\n</TEXT>\n</DOCUMENT>\n\
+            <DOCUMENT>\n<TYPE>EX-101.INS\n<TEXT>\n<XBRL>\n<?xml version=\"1.0\"?>\n\
+            <xbrli:xbrl xmlns:dei=\"http://xbrl.sec.gov/dei/2023\"><dei:DocumentType contextRef=\"c\">10-K</dei:DocumentType></xbrli:xbrl>\n\
+            </XBRL>\n</TEXT>\n</DOCUMENT>\n</SEC-DOCUMENT>";
+
+        let docs = Parser::new().parse_multi(stream.as_bytes()).unwrap();
+        assert_eq!(docs.len(), 1);
+        assert_eq!(docs[0].concept_names, ["dei:DocumentType"]);
+    }
+
+    #[test]
+    fn test_parse_streaming() {
+        let xml = r##"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:link="http://www.xbrl.org/2003/linkbase"
+    xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:a="http://example.com/a">
+  <a:Revenue contextRef="c1" unitRef="USD" decimals="0" id="f1">100</a:Revenue>
+  <xbrli:context id="c1"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <a:Revenue contextRef="c1" unitRef="USD" decimals="0">250</a:Revenue>
+  <a:Address><a:City contextRef="c1">Cupertino</a:City></a:Address>
+  <link:footnoteLink xlink:type="extended" xlink:role="http://www.xbrl.org/2003/role/link">
+    <link:loc xlink:type="locator" xlink:href="#f1" xlink:label="l1"/>
+    <link:footnote xlink:type="resource" xlink:label="n1" id="fn1">Restated.</link:footnote>
+    <link:footnoteArc xlink:type="arc" xlink:from="l1" xlink:to="n1"/>
+  </link:footnoteLink>
+</xbrli:xbrl>"##;
+
+        let mut events = Vec::new();
+        let mut total = 0.0;
+        let doc = Parser::new()
+            .parse_bytes_streaming(xml.as_bytes(), &mut |event: ParseEvent<'_>| {
+                match event {
+                    ParseEvent::Context(c) => events.push(format!("context {}", c.id)),
+                    ParseEvent::Unit(u) => events.push(format!("unit {}", u.id)),
+                    ParseEvent::Fact(f) => {
+                        total += f.value.parse::<f64>().unwrap_or_default();
+                        events.push(format!("fact {}", f.concept));
+                    }
+                    ParseEvent::Footnote(f) => {
+                        events.push(format!("footnote {} {:?}", f.id, f.fact_refs))
+                    }
+                    ParseEvent::FootnoteArc(a) => events.push(format!("arc {} {:?}", a.from, a.to)),
+                    ParseEvent::Tuple(t) => events.push(format!("tuple {}", t.name)),
+                }
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(
+            events,
+            [
+                "fact a:Revenue",
+                "context c1",
+                "unit USD",
+                "fact a:Revenue",
+                "tuple a:Address",
+                "footnote fn1 [\"f1\"]",
+                "arc f1 Footnote(\"fn1\")",
+            ]
+        );
+        assert_eq!(total, 350.0);
+        assert!(doc.facts.is_empty() && doc.tuples.is_empty() && doc.footnotes.is_empty());
+        assert!(doc.footnote_arcs.is_empty());
+        assert_eq!(doc.contexts.len(), 1);
+
+        let stop = Parser::new().parse_bytes_streaming(xml.as_bytes(), &mut |_: ParseEvent<'_>| {
+            Err(Error::Parse("stop".to_string()))
+        });
+        assert!(stop.is_err());
+    }
+
+    #[test]
+    fn test_parse_reader_streaming() {
+        let (mut expected, mut streamed) = (Vec::new(), Vec::new());
+        let whole = Parser::new()
+            .parse_bytes_streaming(INSTANCE.as_bytes(), &mut |event: ParseEvent<'_>| {
+                expected.push(format!("{:?}", event));
+                Ok(())
+            })
+            .unwrap();
+        // Blocks of 16 bytes split every element across reads
+        let read = Parser::new()
+            .parse_reader_streaming(
+                std::io::BufReader::with_capacity(16, INSTANCE.as_bytes()),
+                &mut |event: ParseEvent<'_>| {
+                    streamed.push(format!("{:?}", event));
+                    Ok(())
+                },
+            )
+            .unwrap();
+        assert_eq!(streamed, expected);
+        assert_eq!(read.contexts.len(), 2);
+        assert_eq!(read.units.len(), 2);
+        assert_eq!(read.namespaces, whole.namespaces);
+        assert_eq!(read.schema_ref.as_deref(), Some("aapl-20230930.xsd"));
+        assert_eq!(
+            read.provenance.unwrap().sha256,
+            whole.provenance.unwrap().sha256
+        );
+
+        // Input ending inside the root
+        let cut = INSTANCE.find("<xbrli:unit id=\"usdPerShare\"").unwrap() + 10;
+        let truncated = std::io::BufReader::with_capacity(16, &INSTANCE.as_bytes()[..cut]);
+        match Parser::new().parse_reader_streaming(truncated, &mut |_: ParseEvent<'_>| Ok(())) {
+            Err(Error::Truncated { at_byte }) => assert_eq!(at_byte, cut),
+            other => panic!("{:?}", other.map(|d| d.units.len())),
+        }
+        let truncated = std::io::BufReader::with_capacity(16, &INSTANCE.as_bytes()[..cut]);
+        let doc = Parser::new()
+            .with_error_recovery(true)
+            .parse_reader_streaming(truncated, &mut |_: ParseEvent<'_>| Ok(()))
+            .unwrap();
+        assert_eq!(doc.units.len(), 1);
+        let location = doc.diagnostics[0].location;
+        assert_eq!((location.line, location.column), (25, 3));
+
+        // Syntax errors point into the source, not the batch
+        let broken = "<xbrli:xbrl xmlns:xbrli=\"http://www.xbrl.org/2003/instance\">\n  <xbrli:unit id=\"u\"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>\n  <a:Cash contextRef=c>1</a:Cash>\n</xbrli:xbrl>";
+        let err = Parser::new()
+            .parse_reader_streaming(
+                std::io::BufReader::with_capacity(16, broken.as_bytes()),
+                &mut |_: ParseEvent<'_>| Ok(()),
+            )
+            .err()
+            .unwrap();
+        let Error::XmlSyntax {
+            position: Some(position),
+            ..
+        } = err
+        else {
+            panic!("unexpected error: {}", err);
+        };
+        assert_eq!((position.line, position.column), (3, 22));
+        assert_eq!(position.offset, broken.find("c>1").unwrap());
+    }
+
+    #[test]
+    fn test_taxonomy_documents() {
+        let schema = br#"<?xml version="1.0"?>
+<!-- entry point -->
+<xsd:schema xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xbrli="http://www.xbrl.org/2003/instance" targetNamespace="http://example.com/a">
+  <xsd:element name="Revenues" id="a_Revenues" type="xbrli:monetaryItemType" xbrli:periodType="duration"/>
+</xsd:schema>"#;
+        assert_eq!(detect_input(schema), InputKind::Schema);
+        let doc = Parser::new().parse_bytes(schema).unwrap();
+        assert!(doc.facts.is_empty());
+        assert!(doc.schemas[0].elements.contains_key("Revenues"));
+
+        let linkbase = br#"<link:linkbase xmlns:link="http://www.xbrl.org/2003/linkbase" xmlns:xlink="http://www.w3.org/1999/xlink">
+  <link:calculationLink xlink:type="extended" xlink:role="http://example.com/role/IS">
+    <link:loc xlink:type="locator" xlink:href="a.xsd#a_Profit" xlink:label="p"/>
+    <link:loc xlink:type="locator" xlink:href="a.xsd#a_Revenues" xlink:label="r"/>
+    <link:calculationArc xlink:type="arc" xlink:arcrole="http://www.xbrl.org/2003/arcrole/summation-item" xlink:from="p" xlink:to="r" weight="1"/>
+  </link:calculationLink>
+</link:linkbase>"#;
+        assert_eq!(detect_input(linkbase), InputKind::Linkbase);
+        let doc = Parser::new().parse_bytes(linkbase).unwrap();
+        assert_eq!(doc.calculation_links[0].to, "a:Revenues");
+
+        assert_eq!(
+            detect_input(b"<xbrli:xbrl xmlns:xbrli=\"http://www.xbrl.org/2003/instance\"/>"),
+            InputKind::Instance
+        );
+    }
+
+    #[test]
+    fn test_parse_file_with_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("stats.xml");
+        let xml = r#"<?xml version="1.0"?>
+<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <!-- two facts -->
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="u"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <a:Cash contextRef="c" unitRef="u" decimals="0">1</a:Cash>
+  <a:Debt contextRef="c" unitRef="u" decimals="0">2</a:Debt>
+</xbrli:xbrl>"#;
+        std::fs::write(&path, xml).unwrap();
+
+        let (doc, stats) = Parser::new().parse_file_with_stats(&path).unwrap();
+        assert_eq!(doc.facts.len(), 2);
+        assert_eq!(stats.bytes, xml.len());
+        assert_eq!(stats.elements, 10);
+        assert_eq!((stats.facts, stats.contexts, stats.units), (2, 1, 1));
+        assert!(stats.arena_bytes >= xml.len());
+        assert_eq!(stats.validate, Duration::ZERO);
+        assert!(
+            stats.read + stats.decode + stats.scan + stats.resolve + stats.validate <= stats.total
+        );
+        assert!(stats.facts_per_sec() > 0.0);
+    }
 }
@@ -5,230 +5,326 @@ impl<'a> FullXbrlParser<'a> {
     fn read_tag_name(&mut self) -> Result<&'a str> {
         let start = self.scanner.pos;
         while let Some(ch) = self.scanner.peek() {
-            if ch == b' ' || ch == b'>' || ch == b'/' || ch == b'\t' || ch == b'\n' || ch == b'\r' {
+            if ch == b'>' || ch == b'/' || ch.is_ascii_whitespace() {
                 break;
             }
             self.scanner.advance(1);
         }
         let end = self.scanner.pos;
-        
+
         if start == end {
-            return Err(Error::Parse("Empty tag name".to_string()));
+            return Err(Error::xml("Empty tag name".to_string()));
         }
-        
-        std::str::from_utf8(&self.scanner.data[start..end])
-            .map_err(|_| Error::Parse("Invalid UTF-8 in tag name".to_string()))
+
+        self.str_slice(start, end)
     }
 
     #[inline(always)]
     fn parse_attributes(&mut self) -> Result<Vec<(&'a str, &'a str)>> {
         let mut attrs = Vec::new();
-        
+
         loop {
             self.scanner.skip_whitespace();
-            
+
             match self.scanner.peek() {
-                Some(b'>') => {
-                    // End of tag
-                    break;
-                }
+                Some(b'>') => break,
                 Some(b'/') => {
                     // Self-closing tag
                     self.scanner.advance(1);
                     if self.scanner.peek() == Some(b'>') {
                         break;
                     }
+                    continue;
                 }
-                None => return Err(Error::Parse("Unexpected EOF in attributes".to_string())),
+                None => return Err(Error::xml("Unexpected EOF in attributes".to_string())),
                 _ => {}
             }
-            
+
             let name_start = self.scanner.pos;
             while let Some(ch) = self.scanner.peek() {
-                if ch == b'=' || ch == b' ' || ch == b'>' || ch == b'/' {
+                if ch == b'=' || ch == b'>' || ch == b'/' || ch.is_ascii_whitespace() {
                     break;
                 }
                 self.scanner.advance(1);
             }
-            
+
             if self.scanner.pos == name_start {
                 break; // No more attributes
             }
-            
-            let name = std::str::from_utf8(&self.scanner.data[name_start..self.scanner.pos])
-                .map_err(|_| Error::Parse("Invalid UTF-8 in attribute name".to_string()))?;
-            
+
+            let name = self.str_slice(name_start, self.scanner.pos)?;
+
             self.scanner.skip_whitespace();
-            
             if self.scanner.peek() != Some(b'=') {
                 continue;
             }
             self.scanner.advance(1);
-            
             self.scanner.skip_whitespace();
-            
-            let quote = self.scanner.peek()
-                .ok_or_else(|| Error::Parse("Expected quote".to_string()))?;
-            
+
+            let quote = self
+                .scanner
+                .peek()
+                .ok_or_else(|| Error::xml("Expected quote".to_string()))?;
             if quote != b'"' && quote != b'\'' {
-                return Err(Error::Parse("Expected quote in attribute".to_string()));
+                return Err(Error::xml("Expected quote in attribute".to_string()));
             }
-            
             self.scanner.advance(1);
+
             let value_start = self.scanner.pos;
-            
-            while let Some(ch) = self.scanner.peek() {
-                if ch == quote {
-                    break;
-                }
-                self.scanner.advance(1);
-            }
-            
-            let value = std::str::from_utf8(&self.scanner.data[value_start..self.scanner.pos])
-                .map_err(|_| Error::Parse("Invalid UTF-8 in attribute value".to_string()))?;
-            
-            self.scanner.advance(1); // Skip closing quote
-            
+            let value_end = self
+                .scanner
+                .find_next(quote)
+                .ok_or_else(|| Error::xml("Unterminated attribute value".to_string()))?;
+            let value = match decode_attribute(self.str_slice(value_start, value_end)?) {
+                std::borrow::Cow::Borrowed(value) => value,
+                std::borrow::Cow::Owned(value) => self.allocator.alloc_str(&value),
+            };
+            self.scanner.pos = value_end + 1; // Skip closing quote
+
             attrs.push((name, value));
         }
-        
+
         Ok(attrs)
     }
 
     #[inline(always)]
     fn skip_to_tag_end(&mut self) -> Result<()> {
-        while let Some(ch) = self.scanner.peek() {
-            if ch == b'>' {
-                self.scanner.advance(1);
-                return Ok(());
+        match self.scanner.find_next(b'>') {
+            Some(pos) => {
+                self.scanner.pos = pos + 1;
+                Ok(())
             }
-            self.scanner.advance(1);
+            None => Err(Error::xml("Expected '>'".to_string())),
         }
-        Err(Error::Parse("Expected '>'".to_string()))
     }
 
+    /// Consume the rest of the start tag; true if it was self-closing
     #[inline(always)]
-    fn read_text_content(&mut self) -> Result<&'a str> {
-        let start = self.scanner.pos;
-        while let Some(ch) = self.scanner.peek() {
-            if ch == b'<' {
-                break;
+    fn finish_start_tag(&mut self) -> Result<bool> {
+        self.skip_to_tag_end()?;
+        Ok(self.scanner.data[self.scanner.pos - 2] == b'/')
+    }
+
+    /// Move to the next child start tag and return its name, or `None` once
+    /// the parent's end tag has been consumed
+    fn next_child(&mut self) -> Result<Option<&'a str>> {
+        loop {
+            let pos = self
+                .scanner
+                .find_next(b'<')
+                .ok_or_else(|| Error::xml("Unexpected end of document".to_string()))?;
+            self.scanner.pos = pos + 1;
+
+            match self.scanner.peek() {
+                Some(b'/') => {
+                    self.scanner.advance(1);
+                    self.read_tag_name()?;
+                    self.skip_to_tag_end()?;
+                    return Ok(None);
+                }
+                Some(b'?') => self.skip_processing_instruction()?,
+                Some(b'!') => self.skip_markup_declaration()?,
+                _ => return self.read_tag_name().map(Some),
             }
-            self.scanner.advance(1);
         }
-        
-        let text = std::str::from_utf8(&self.scanner.data[start..self.scanner.pos])
-            .map_err(|_| Error::Parse("Invalid UTF-8 in text content".to_string()))?;
-        
-        Ok(text.trim())
     }
 
-    #[inline(always)]
-    fn skip_element_from_tag(&mut self) -> Result<()> {
-        // We've already read the tag name, now skip to end of opening tag
-        self.skip_to_tag_end()?;
-        
-        // Check if it was self-closing
-        if self.scanner.pos >= 2 && self.scanner.data[self.scanner.pos - 2] == b'/' {
-            return Ok(()); // Self-closing tag, we're done
+    /// Trimmed content of an element whose start tag has been consumed
+    fn read_leaf_text(&mut self, self_closing: bool) -> Result<String> {
+        if self_closing {
+            return Ok(String::new());
         }
-        
-        // Skip element content and find matching closing tag
-        let mut depth = 1;
-        
-        while depth > 0 && !self.scanner.is_eof() {
-            // Find next tag
-            while let Some(ch) = self.scanner.peek() {
-                if ch == b'<' {
-                    break;
-                }
+        let content = self.read_content()?;
+        Ok(content.trim().to_string())
+    }
+
+    /// Content of the current element up to and including its end tag.
+    /// Text is entity-decoded; element content is returned as markup.
+    fn read_content(&mut self) -> Result<String> {
+        let start = self.scanner.pos;
+        let text = self.read_text_content_with_cdata()?;
+
+        if self.peek_ahead(2) == Some(b"</") {
+            self.scanner.advance(2);
+            self.read_tag_name()?;
+            self.skip_to_tag_end()?;
+            return Ok(text);
+        }
+
+        self.scanner.pos = start;
+        Ok(self.read_raw_content()?.to_string())
+    }
+
+    /// Text and CDATA sections up to the next tag
+    fn read_text_content_with_cdata(&mut self) -> Result<String> {
+        let mut content = String::new();
+
+        loop {
+            let end = self
+                .scanner
+                .find_next(b'<')
+                .unwrap_or(self.scanner.data.len());
+            content.push_str(&decode_entities(self.str_slice(self.scanner.pos, end)?));
+            self.scanner.pos = end;
+
+            if self.peek_ahead(9) == Some(b"<![CDATA[") {
+                self.scanner.advance(9);
+                let close = self
+                    .scanner
+                    .find_pattern(b"]]>")
+                    .ok_or_else(|| Error::xml("Unclosed CDATA section".to_string()))?;
+                content.push_str(self.str_slice(self.scanner.pos, close)?);
+                self.scanner.pos = close + 3;
+            } else if self.peek_ahead(4) == Some(b"<!--") {
                 self.scanner.advance(1);
+                self.skip_markup_declaration()?;
+            } else {
+                return Ok(content);
             }
-            
-            if self.scanner.is_eof() {
-                break;
-            }
-            
-            self.scanner.advance(1); // consume '<'
-            
-            if self.scanner.peek() == Some(b'/') {
-                depth -= 1;
-            } else if self.scanner.peek() != Some(b'!') && self.scanner.peek() != Some(b'?') {
-                // Check if it's a self-closing tag
-                let mut is_self_closing = false;
-                let _saved_pos = self.scanner.pos;
-                
-                // Skip to end of tag to check
-                while let Some(ch) = self.scanner.peek() {
-                    if ch == b'/' {
-                        if self.scanner.pos + 1 < self.scanner.data.len() 
-                            && self.scanner.data[self.scanner.pos + 1] == b'>' {
-                            is_self_closing = true;
-                        }
-                    }
-                    if ch == b'>' {
-                        self.scanner.advance(1);
-                        break;
+        }
+    }
+
+    /// Raw markup up to the end tag of the current element, which is consumed
+    fn read_raw_content(&mut self) -> Result<&'a str> {
+        let start = self.scanner.pos;
+        let mut depth = 1;
+
+        loop {
+            let pos = self
+                .scanner
+                .find_next(b'<')
+                .ok_or_else(|| Error::xml("Unclosed element".to_string()))?;
+            self.scanner.pos = pos + 1;
+
+            match self.scanner.peek() {
+                Some(b'/') => {
+                    self.skip_to_tag_end()?;
+                    depth -= 1;
+                    if depth == 0 {
+                        return self.str_slice(start, pos);
                     }
-                    self.scanner.advance(1);
                 }
-                
-                if !is_self_closing {
-                    depth += 1;
-                }
-                
-                continue;
-            }
-            
-            // Skip to end of this tag
-            while let Some(ch) = self.scanner.peek() {
-                if ch == b'>' {
-                    self.scanner.advance(1);
-                    break;
+                Some(b'?') => self.skip_processing_instruction()?,
+                Some(b'!') => self.skip_markup_declaration()?,
+                _ => {
+                    if !self.finish_start_tag()? {
+                        depth += 1;
+                    }
                 }
-                self.scanner.advance(1);
             }
         }
-        
+    }
+
+    fn skip_content(&mut self, self_closing: bool) -> Result<()> {
+        if !self_closing {
+            self.read_raw_content()?;
+        }
         Ok(())
     }
 
+    #[inline(always)]
+    fn skip_element_from_tag(&mut self) -> Result<()> {
+        // We've already read the tag name, now skip to end of opening tag
+        let self_closing = self.finish_start_tag()?;
+        self.skip_content(self_closing)
+    }
+
     #[inline(always)]
     fn skip_processing_instruction(&mut self) -> Result<()> {
-        // Skip until ?>
-        while !self.scanner.is_eof() {
-            if self.scanner.peek() == Some(b'?') {
-                self.scanner.advance(1);
-                if self.scanner.peek() == Some(b'>') {
-                    self.scanner.advance(1);
-                    return Ok(());
-                }
-            } else {
-                self.scanner.advance(1);
+        match self.scanner.find_pattern(b"?>") {
+            Some(pos) => {
+                self.scanner.pos = pos + 2;
+                Ok(())
             }
+            None => Err(Error::xml("Unclosed processing instruction".to_string())),
         }
-        Err(Error::Parse("Unclosed processing instruction".to_string()))
     }
 
+    /// Skip a comment, CDATA section or DOCTYPE; the scanner is on the `!`
     #[inline(always)]
-    fn skip_comment(&mut self) -> Result<()> {
-        // Skip until -->
-        while !self.scanner.is_eof() {
-            if self.scanner.peek() == Some(b'-') {
-                self.scanner.advance(1);
-                if self.scanner.peek() == Some(b'-') {
-                    self.scanner.advance(1);
-                    if self.scanner.peek() == Some(b'>') {
-                        self.scanner.advance(1);
-                        return Ok(());
-                    }
-                }
-            } else {
-                self.scanner.advance(1);
+    fn skip_markup_declaration(&mut self) -> Result<()> {
+        let (terminator, what) = if self.peek_ahead(3) == Some(b"!--") {
+            (&b"-->"[..], "comment")
+        } else if self.peek_ahead(8) == Some(b"![CDATA[") {
+            (&b"]]>"[..], "CDATA section")
+        } else {
+            (&b">"[..], "declaration")
+        };
+
+        match self.scanner.find_pattern(terminator) {
+            Some(pos) => {
+                self.scanner.pos = pos + terminator.len();
+                Ok(())
+            }
+            None => Err(Error::xml(format!("Unclosed {}", what))),
+        }
+    }
+
+    /// Name of the next child start tag, without consuming anything
+    fn peek_tag_name(&mut self) -> Option<&'a str> {
+        let saved_pos = self.scanner.pos;
+        self.scanner.skip_whitespace();
+
+        let mut name = None;
+        if self.scanner.peek() == Some(b'<') {
+            self.scanner.advance(1);
+            if !matches!(self.scanner.peek(), Some(b'/' | b'!' | b'?')) {
+                name = self.read_tag_name().ok();
+            }
+        }
+        self.scanner.pos = saved_pos;
+        name
+    }
+
+    #[inline(always)]
+    fn peek_ahead(&self, n: usize) -> Option<&'a [u8]> {
+        self.scanner.data.get(self.scanner.pos..self.scanner.pos + n)
+    }
+
+    #[inline(always)]
+    fn str_slice(&self, start: usize, end: usize) -> Result<&'a str> {
+        std::str::from_utf8(&self.scanner.data[start..end])
+            .map_err(|_| Error::xml(format!("Invalid UTF-8 at byte {}", start)))
+    }
+}
+
+/// Replace the predefined XML entities and character references
+fn decode_entities(text: &str) -> std::borrow::Cow<'_, str> {
+    if !text.contains('&') {
+        return std::borrow::Cow::Borrowed(text);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let decoded = rest.find(';').and_then(|semi| {
+            let ch = match &rest[1..semi] {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                entity => char_reference(entity),
+            };
+            ch.map(|ch| (ch, semi))
+        });
+
+        match decoded {
+            Some((ch, semi)) => {
+                out.push(ch);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
             }
         }
-        Err(Error::Parse("Unclosed comment".to_string()))
     }
+    out.push_str(rest);
+    std::borrow::Cow::Owned(out)
 }
 
 impl Default for Parser {
@@ -236,3 +332,33 @@ impl Default for Parser {
         Self::new()
     }
 }
+
+/// Character of a `#NNN` or `#xHH` reference; `None` for malformed
+/// references and code points XML does not allow
+fn char_reference(entity: &str) -> Option<char> {
+    let (digits, radix) = match entity.strip_prefix("#x") {
+        Some(hex) => (hex, 16),
+        None => (entity.strip_prefix('#')?, 10),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(radix)) {
+        return None;
+    }
+    let code = u32::from_str_radix(digits, radix).ok()?;
+    // XML 1.0 `Char`; surrogates are rejected by `char::from_u32`
+    matches!(code, 0x9 | 0xA | 0xD | 0x20..=0xFFFD | 0x10000..)
+        .then(|| char::from_u32(code))
+        .flatten()
+}
+
+/// Attribute value as XML normalizes it: literal tabs and line breaks
+/// become spaces, then references are replaced. Whitespace written as a
+/// character reference is kept.
+fn decode_attribute(value: &str) -> std::borrow::Cow<'_, str> {
+    if !value.contains(['\t', '\n', '\r']) {
+        return decode_entities(value);
+    }
+    let normalized = value
+        .replace("\r\n", " ")
+        .replace(['\t', '\n', '\r'], " ");
+    std::borrow::Cow::Owned(decode_entities(&normalized).into_owned())
+}
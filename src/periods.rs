@@ -0,0 +1,252 @@
+//! Period queries over facts
+//!
+//! [`PeriodIndex`] is an interval tree over context periods: the contexts
+//! sorted by start form an implicit balanced tree whose nodes carry the
+//! latest end of their subtree, so a range query visits O(log n + k)
+//! contexts. [`Document::facts`] builds one on the first query.
+
+use crate::model::{Document, FactView, Period};
+use chrono::{NaiveDate, NaiveDateTime};
+use std::ops::{Bound, RangeBounds};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy)]
+struct Interval {
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    instant: bool,
+    context: u32,
+}
+
+impl Interval {
+    /// Durations must share some time with `[from, to)`; an instant
+    /// must fall in `(from, to]`, as it is the end of its day
+    fn overlaps(&self, from: NaiveDateTime, to: NaiveDateTime) -> bool {
+        if self.instant {
+            from < self.end && self.end <= to
+        } else {
+            self.start < to && from < self.end
+        }
+    }
+}
+
+/// Contexts indexed by effective period; contexts whose dates do not parse
+/// are left out
+#[derive(Debug, Clone, Default)]
+pub struct PeriodIndex {
+    intervals: Vec<Interval>,
+    // Latest end in the subtree rooted at each position
+    max_end: Vec<NaiveDateTime>,
+    // Facts of each context, in document order
+    facts: Vec<Vec<usize>>,
+}
+
+impl PeriodIndex {
+    pub fn new(doc: &Document) -> Self {
+        let mut intervals: Vec<Interval> = doc
+            .contexts
+            .iter()
+            .enumerate()
+            .filter_map(|(i, context)| {
+                let (start, end) = match &context.period {
+                    Period::Forever => (NaiveDateTime::MIN, NaiveDateTime::MAX),
+                    period => (period.effective_start()?, period.effective_end()?),
+                };
+                Some(Interval {
+                    start,
+                    end,
+                    instant: matches!(context.period, Period::Instant { .. }),
+                    context: i as u32,
+                })
+            })
+            .collect();
+        intervals.sort_by_key(|i| (i.start, i.end));
+
+        let mut max_end = vec![NaiveDateTime::MIN; intervals.len()];
+        fn augment(intervals: &[Interval], max_end: &mut [NaiveDateTime], lo: usize, hi: usize) {
+            if lo >= hi {
+                return;
+            }
+            let mid = lo + (hi - lo) / 2;
+            augment(intervals, max_end, lo, mid);
+            augment(intervals, max_end, mid + 1, hi);
+            let children = [(lo, mid), (mid + 1, hi)]
+                .into_iter()
+                .filter(|(lo, hi)| lo < hi)
+                .map(|(lo, hi)| max_end[lo + (hi - lo) / 2]);
+            max_end[mid] = children.fold(intervals[mid].end, NaiveDateTime::max);
+        }
+        augment(&intervals, &mut max_end, 0, intervals.len());
+
+        let mut facts = vec![Vec::new(); doc.contexts.len()];
+        for (i, &context) in doc.facts.context_ids.iter().enumerate() {
+            if let Some(facts) = facts.get_mut(context as usize) {
+                facts.push(i);
+            }
+        }
+
+        Self {
+            intervals,
+            max_end,
+            facts,
+        }
+    }
+
+    /// Ids of the contexts whose period overlaps `range`, ascending
+    pub fn overlapping<R: RangeBounds<NaiveDate>>(&self, range: R) -> Vec<u32> {
+        let (from, to) = bounds(&range);
+        let mut contexts = Vec::new();
+        self.collect(0, self.intervals.len(), from, to, &mut contexts);
+        contexts.sort_unstable();
+        contexts
+    }
+
+    fn collect(
+        &self,
+        lo: usize,
+        hi: usize,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        out: &mut Vec<u32>,
+    ) {
+        if lo >= hi {
+            return;
+        }
+        let mid = lo + (hi - lo) / 2;
+        // Nothing in this subtree ends after `from`
+        if self.max_end[mid] < from {
+            return;
+        }
+        self.collect(lo, mid, from, to, out);
+        let interval = &self.intervals[mid];
+        if interval.start > to {
+            // Everything to the right starts later still
+            return;
+        }
+        if interval.overlaps(from, to) {
+            out.push(interval.context);
+        }
+        self.collect(mid + 1, hi, from, to, out);
+    }
+}
+
+/// Inclusive dates as `[from, to]` moments: a range of days runs from the
+/// start of its first day to the end of its last
+fn bounds<R: RangeBounds<NaiveDate>>(range: &R) -> (NaiveDateTime, NaiveDateTime) {
+    let start_of = |date: &NaiveDate| date.and_time(chrono::NaiveTime::MIN);
+    let end_of = |date: &NaiveDate| {
+        date.succ_opt()
+            .map_or(NaiveDateTime::MAX, |next| start_of(&next))
+    };
+    let from = match range.start_bound() {
+        Bound::Included(date) => start_of(date),
+        Bound::Excluded(date) => end_of(date),
+        Bound::Unbounded => NaiveDateTime::MIN,
+    };
+    let to = match range.end_bound() {
+        Bound::Included(date) => end_of(date),
+        Bound::Excluded(date) => start_of(date),
+        Bound::Unbounded => NaiveDateTime::MAX,
+    };
+    (from, to)
+}
+
+/// Fact queries over a document; indexes are built on first use and kept
+/// for the life of the view
+pub struct Facts<'a> {
+    doc: &'a Document,
+    periods: OnceLock<PeriodIndex>,
+}
+
+impl<'a> Facts<'a> {
+    pub fn period_index(&self) -> &PeriodIndex {
+        self.periods.get_or_init(|| PeriodIndex::new(self.doc))
+    }
+
+    /// Facts whose context period overlaps `range`, in document order.
+    /// `2023-01-01..=2023-12-31` matches durations sharing a day of 2023,
+    /// instants dated in 2023 and forever periods.
+    pub fn overlapping<R: RangeBounds<NaiveDate>>(&self, range: R) -> Vec<FactView<'a>> {
+        let index = self.period_index();
+        let mut facts: Vec<usize> = index
+            .overlapping(range)
+            .into_iter()
+            .flat_map(|context| index.facts[context as usize].iter().copied())
+            .collect();
+        facts.sort_unstable();
+        facts.into_iter().filter_map(|i| self.doc.fact(i)).collect()
+    }
+}
+
+impl Document {
+    /// Query view over the facts; see [`Facts`]
+    pub fn facts(&self) -> Facts<'_> {
+        Facts {
+            doc: self,
+            periods: OnceLock::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    #[test]
+    fn test_overlapping_periods() {
+        let mut xml = String::from(
+            r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="FY22"><xbrli:entity><xbrli:identifier scheme="http://example.com">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2022-01-01</xbrli:startDate><xbrli:endDate>2022-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:context id="I22"><xbrli:entity><xbrli:identifier scheme="http://example.com">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2022-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:context id="Ever"><xbrli:entity><xbrli:identifier scheme="http://example.com">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:forever/></xbrli:period></xbrli:context>
+"#,
+        );
+        // Quarters from 2020 to 2024
+        for q in 0..20 {
+            let (year, month) = (2020 + q / 4, 1 + 3 * (q as u32 % 4));
+            xml.push_str(&format!(
+                r#"  <xbrli:context id="Q{q}"><xbrli:entity><xbrli:identifier scheme="http://example.com">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>{year}-{month:02}-01</xbrli:startDate><xbrli:endDate>{end}</xbrli:endDate></xbrli:period></xbrli:context>
+  <a:Sales contextRef="Q{q}">{q}</a:Sales>
+"#,
+                end = NaiveDate::from_ymd_opt(year, month, 1)
+                    .and_then(|start| start.checked_add_months(chrono::Months::new(3)))
+                    .and_then(|next| next.pred_opt())
+                    .unwrap(),
+            ));
+        }
+        xml.push_str(
+            r#"  <a:Revenue contextRef="FY22">1</a:Revenue>
+  <a:Cash contextRef="I22">2</a:Cash>
+  <a:Name contextRef="Ever">x</a:Name>
+</xbrli:xbrl>"#,
+        );
+        let doc = Parser::new().parse_str(&xml).unwrap();
+        let facts = doc.facts();
+
+        let d = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        let found = |range: std::ops::RangeInclusive<NaiveDate>| {
+            facts
+                .overlapping(range)
+                .iter()
+                .map(|f| format!("{}@{}", f.concept(), f.context().unwrap().id))
+                .collect::<Vec<_>>()
+        };
+        // Q3 2022 overlaps the fiscal year, and forever covers it all
+        assert_eq!(
+            found(d(2022, 8, 1)..=d(2022, 8, 31)),
+            ["a:Sales@Q10", "a:Revenue@FY22", "a:Name@Ever"]
+        );
+        // The instant is the end of its day; the next quarter starts after it
+        assert_eq!(
+            found(d(2022, 12, 31)..=d(2022, 12, 31)),
+            ["a:Sales@Q11", "a:Revenue@FY22", "a:Cash@I22", "a:Name@Ever"]
+        );
+        assert_eq!(facts.overlapping(d(2021, 1, 1)..d(2022, 1, 1)).len(), 5);
+        assert_eq!(facts.overlapping(..).len(), doc.facts.len());
+    }
+}
@@ -0,0 +1,83 @@
+//! Thread pools for parallel work
+//!
+//! [`Parser::parse_files`] and the validator's checks run on rayon's global
+//! pool unless given one of their own with `with_thread_pool`. A service
+//! embedding crabrl can pass the pool it already sizes for the machine, or
+//! a small dedicated one, instead of competing with its own workers for
+//! every core.
+//!
+//! [`Parser::parse_files`]: crate::Parser::parse_files
+
+use crate::{Error, Result};
+use std::sync::Arc;
+
+pub use rayon::ThreadPool;
+
+/// A dedicated pool of `threads` threads; 0 lets rayon pick, as it does for
+/// the global pool
+pub fn thread_pool(threads: usize) -> Result<Arc<ThreadPool>> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .thread_name(|i| format!("crabrl-{}", i))
+        .build()
+        .map(Arc::new)
+        .map_err(|e| Error::Io(std::io::Error::other(e)))
+}
+
+/// Run `op` in `pool`, or in the caller's pool when `None`
+pub(crate) fn install<R: Send>(pool: Option<&ThreadPool>, op: impl FnOnce() -> R + Send) -> R {
+    match pool {
+        Some(pool) => pool.install(op),
+        None => op(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::XbrlValidator;
+    use crate::Parser;
+
+    #[test]
+    fn test_injected_thread_pool() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths: Vec<_> = (0..4)
+            .map(|i| {
+                let path = dir.path().join(format!("{}.xml", i));
+                std::fs::write(
+                    &path,
+                    format!(
+                        r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">{i}</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <a:Cash contextRef="c" unitRef="missing" decimals="0">{i}</a:Cash>
+</xbrli:xbrl>"#
+                    ),
+                )
+                .unwrap();
+                path
+            })
+            .collect();
+
+        let pool = thread_pool(2).unwrap();
+        assert_eq!(pool.current_num_threads(), 2);
+        let docs = Parser::new()
+            .with_thread_pool(pool.clone())
+            .parse_files(&paths);
+        let values: Vec<_> = docs
+            .iter()
+            .map(|d| d.as_ref().unwrap().fact(0).unwrap().value().to_string())
+            .collect();
+        assert_eq!(values, ["0", "1", "2", "3"]);
+
+        // The same findings, in the same order, as on the global pool
+        let doc = docs[0].as_ref().unwrap();
+        let pooled = XbrlValidator::new().with_thread_pool(pool).check(doc);
+        let global = XbrlValidator::new().check(doc);
+        assert!(!pooled.is_empty());
+        assert_eq!(
+            pooled.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+            global.iter().map(|e| e.to_string()).collect::<Vec<_>>()
+        );
+    }
+}
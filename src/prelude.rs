@@ -0,0 +1,43 @@
+//! The supported public API in one import
+//!
+//! ```
+//! use crabrl::prelude::*;
+//!
+//! fn check(doc: &mut Document, taxonomy: &Taxonomy) -> Result<Report> {
+//!     doc.load_dts(taxonomy)?;
+//!     let result = Validator::sec_edgar().validate(doc)?;
+//!     Ok(Report::validation("filing.xml", &result))
+//! }
+//!
+//! let doc = Parser::new().parse_str(
+//!     r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance"/>"#,
+//! )?;
+//! let facts: Vec<FactView> = doc.iter_facts().collect();
+//! assert!(facts.is_empty());
+//! # let _ = check;
+//! # Ok::<(), Error>(())
+//! ```
+//!
+//! brings in what parsing, querying, validating and reporting need. Items
+//! here only change with a major version; modules outside it may be
+//! reorganised between minor releases.
+
+pub use crate::audit::AuditLog;
+/// The taxonomy of a document, the schemas and linkbases its DTS discovery
+/// found, see [`DtsLoader::discover`]
+pub use crate::dts::Dts as Taxonomy;
+pub use crate::dts::{Dts, DtsLoader, NetworkPolicy};
+pub use crate::export::ExportOptions;
+pub use crate::model::{
+    Balance, Context, Decimal, Document, Fact, FactValue, FactView, Period, Schema, SchemaElement,
+    Unit, UnitType,
+};
+pub use crate::options::{ConceptFilter, ParserOptions};
+pub use crate::parser::{detect_input, InputKind, ParseEvent, ParseHandler, Parser};
+pub use crate::report::{Formatter, Report};
+#[cfg(feature = "tokio")]
+pub use crate::stream::FactEvent;
+pub use crate::validator::ValidationError;
+pub use crate::{
+    Error, ErrorKind, Finding, Result, Severity, ValidationConfig, ValidationResult, Validator,
+};
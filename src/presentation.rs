@@ -0,0 +1,255 @@
+//! Presentation trees
+//!
+//! Trees are built from the effective relationships of one extended link
+//! role, after prohibition and override, see [`crate::network`].
+
+use crate::linkbase::LABEL_ROLE;
+use crate::model::{Document, Period, PresentationLink};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// A concept in a presentation tree, with its children in `order`
+#[derive(Debug, Clone, PartialEq)]
+pub struct PresentationNode {
+    pub concept: String,
+    /// Label role the statement shows the concept with; `None` for roots
+    /// and for the standard label
+    pub preferred_label: Option<String>,
+    pub order: f32,
+    pub children: Vec<PresentationNode>,
+}
+
+impl PresentationNode {
+    /// This node and its descendants, depth first, with their depth
+    pub fn walk(&self) -> Vec<(usize, &PresentationNode)> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![(0, self)];
+        while let Some((depth, node)) = stack.pop() {
+            nodes.push((depth, node));
+            stack.extend(node.children.iter().rev().map(|c| (depth + 1, c)));
+        }
+        nodes
+    }
+}
+
+impl Document {
+    /// Extended link roles with presentation arcs, in document order
+    pub fn presentation_roles(&self) -> Vec<&str> {
+        let mut seen = HashSet::new();
+        self.presentation_links
+            .iter()
+            .map(|l| l.role.as_str())
+            .filter(|role| seen.insert(*role))
+            .collect()
+    }
+
+    /// Concept trees of one presentation role, roots in document order
+    pub fn presentation_tree(&self, role: &str) -> Vec<PresentationNode> {
+        let links = self.presentation_network(Some(role));
+        let mut children: HashMap<&str, Vec<&PresentationLink>> = HashMap::new();
+        for link in &links {
+            children.entry(link.from.as_str()).or_default().push(link);
+        }
+        for siblings in children.values_mut() {
+            siblings.sort_by(|a, b| a.order.total_cmp(&b.order));
+        }
+
+        let targets: HashSet<&str> = links.iter().map(|l| l.to.as_str()).collect();
+        let mut roots = Vec::new();
+        for link in &links {
+            if !targets.contains(link.from.as_str()) && !roots.contains(&link.from.as_str()) {
+                roots.push(link.from.as_str());
+            }
+        }
+
+        fn build(
+            concept: &str,
+            link: Option<&PresentationLink>,
+            children: &HashMap<&str, Vec<&PresentationLink>>,
+            ancestors: &mut Vec<String>,
+        ) -> PresentationNode {
+            ancestors.push(concept.to_string());
+            let mut nodes = Vec::new();
+            for l in children.get(concept).into_iter().flatten() {
+                // A cycle would never end; cut it where it closes
+                if !ancestors.contains(&l.to) {
+                    nodes.push(build(&l.to, Some(l), children, ancestors));
+                }
+            }
+            ancestors.pop();
+            PresentationNode {
+                concept: concept.to_string(),
+                preferred_label: link
+                    .and_then(|l| l.preferred_label.clone())
+                    .filter(|role| role != LABEL_ROLE),
+                order: link.map_or(0.0, |l| l.order),
+                children: nodes,
+            }
+        }
+
+        roots
+            .into_iter()
+            .map(|root| build(root, None, &children, &mut Vec::new()))
+            .collect()
+    }
+}
+
+/// Statement of one presentation role as text: its title when the role has
+/// one, then concepts indented by depth with their labels in `lang`, and one
+/// column per period of the non-dimensional facts reported for them
+pub fn render_presentation(doc: &Document, role: &str, lang: &str) -> String {
+    let tree = doc.presentation_tree(role);
+    let rows: Vec<(usize, &PresentationNode)> = tree.iter().flat_map(|n| n.walk()).collect();
+
+    // Value per (concept, period), first fact wins
+    let concepts: HashSet<&str> = rows.iter().map(|(_, n)| n.concept.as_str()).collect();
+    let mut periods: Vec<&Period> = Vec::new();
+    let mut values: HashMap<(&str, String), String> = HashMap::new();
+    for fact in doc.iter_facts() {
+        let Some(context) = fact.context() else {
+            continue;
+        };
+        if !concepts.contains(fact.concept()) || !context.dimensions().is_empty() {
+            continue;
+        }
+        if !periods.iter().any(|p| p.same_as(&context.period)) {
+            periods.push(&context.period);
+        }
+        values
+            .entry((fact.concept(), context.period.canonical()))
+            .or_insert_with(|| fact.value().to_string());
+    }
+    // Latest period first, longer durations first among those ending together
+    periods.sort_by(|a, b| {
+        b.effective_end()
+            .cmp(&a.effective_end())
+            .then(a.effective_start().cmp(&b.effective_start()))
+    });
+    let headers: Vec<String> = periods.iter().map(|p| p.canonical()).collect();
+
+    let labels: Vec<String> = rows
+        .iter()
+        .map(|(depth, node)| {
+            let role = node.preferred_label.as_deref().unwrap_or(LABEL_ROLE);
+            let label = doc.label_for(&node.concept, role, lang);
+            format!("{}{}", "  ".repeat(*depth), label.unwrap_or(&node.concept))
+        })
+        .collect();
+    let label_width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+    let widths: Vec<usize> = headers
+        .iter()
+        .map(|header| {
+            rows.iter()
+                .filter_map(|(_, n)| values.get(&(n.concept.as_str(), header.clone())))
+                .map(|v| v.chars().count())
+                .chain([header.len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let mut out = String::new();
+    if let Some(title) = doc.role_label(role, lang) {
+        let _ = writeln!(out, "{}\n", title);
+    }
+    let _ = write!(out, "{:label_width$}", "");
+    for (header, width) in headers.iter().zip(&widths) {
+        let _ = write!(out, "  {:>width$}", header);
+    }
+    out.push('\n');
+    for ((_, node), label) in rows.iter().zip(&labels) {
+        let _ = write!(out, "{:label_width$}", label);
+        for (header, width) in headers.iter().zip(&widths) {
+            let value = values
+                .get(&(node.concept.as_str(), header.clone()))
+                .map_or("", String::as_str);
+            let _ = write!(out, "  {:>width$}", value);
+        }
+        out.truncate(out.trim_end().len());
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    const ROLE: &str = "http://example.com/role/BalanceSheet";
+
+    fn arc(from: &str, to: &str, order: f32) -> PresentationLink {
+        PresentationLink {
+            role: ROLE.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+            order,
+            priority: None,
+            use_attribute: None,
+            preferred_label: None,
+        }
+    }
+
+    #[test]
+    fn test_presentation_tree() {
+        let mut doc = Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="FY23"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:context id="FY22"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2022-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <a:Cash contextRef="FY22" unitRef="USD" decimals="0">5</a:Cash>
+  <a:Cash contextRef="FY23" unitRef="USD" decimals="0">7</a:Cash>
+  <a:Assets contextRef="FY23" unitRef="USD" decimals="0">10</a:Assets>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+        doc.presentation_links.extend([
+            arc("a:BalanceSheetAbstract", "a:Assets", 1.0),
+            arc("a:Assets", "a:Receivables", 2.0),
+            arc("a:Assets", "a:Cash", 1.0),
+            arc("a:Assets", "a:Goodwill", 3.0),
+            // A higher priority prohibition removes Goodwill
+            PresentationLink {
+                priority: Some(1),
+                use_attribute: Some("prohibited".to_string()),
+                ..arc("a:Assets", "a:Goodwill", 3.0)
+            },
+            PresentationLink {
+                preferred_label: Some(crate::linkbase::TOTAL_LABEL_ROLE.to_string()),
+                ..arc("a:Other", "a:Assets", 1.0)
+            },
+        ]);
+        doc.presentation_links.push(PresentationLink {
+            role: "http://example.com/role/Other".to_string(),
+            ..arc("a:X", "a:Y", 1.0)
+        });
+
+        assert_eq!(doc.presentation_roles().len(), 2);
+        let tree = doc.presentation_tree(ROLE);
+        let roots: Vec<_> = tree.iter().map(|n| n.concept.as_str()).collect();
+        assert_eq!(roots, ["a:BalanceSheetAbstract", "a:Other"]);
+        let assets = &tree[0].children[0];
+        let children: Vec<_> = assets.children.iter().map(|n| n.concept.as_str()).collect();
+        assert_eq!(children, ["a:Cash", "a:Receivables"]);
+        assert_eq!(
+            tree[1].children[0].preferred_label.as_deref(),
+            Some(crate::linkbase::TOTAL_LABEL_ROLE)
+        );
+
+        let text = render_presentation(&doc, ROLE, "en");
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(
+            lines[0].split_whitespace().collect::<Vec<_>>(),
+            ["2023-12-31", "2022-12-31"]
+        );
+        assert_eq!(lines[1], "a:BalanceSheetAbstract");
+        assert!(lines[3].starts_with("    a:Cash"));
+        assert_eq!(
+            lines[3].split_whitespace().collect::<Vec<_>>(),
+            ["a:Cash", "7", "5"]
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! Fact queries
+//!
+//! A [`FactQuery`] selects the facts of a document by concept, period and
+//! dimension members; every criterion left out matches all facts.
+//! Concepts, dimensions and members are given as QNames with the
+//! instance's prefixes (`us-gaap:Revenues`) or as bare local names
+//! (`Revenues`). Facts must carry every queried member but may carry
+//! others, so a query without dimensions also finds segment facts.
+
+use crate::model::{Document, FactValue, FactView, Period};
+use chrono::NaiveDate;
+use serde_json::{json, Map, Value};
+
+/// Which periods a query matches
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PeriodFilter {
+    Forever,
+    /// Instants on, and durations ending on, a day starting with this
+    /// `YYYY`, `YYYY-MM` or `YYYY-MM-DD` prefix
+    Ending(String),
+    /// Exactly this duration, both days included
+    Between(NaiveDate, NaiveDate),
+}
+
+impl PeriodFilter {
+    /// `forever`, `2023`, `2023-12`, `2023-12-31` or
+    /// `2023-01-01..2023-12-31`
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if s == "forever" {
+            return Some(PeriodFilter::Forever);
+        }
+        if let Some((start, end)) = s.split_once("..") {
+            let date = |d: &str| NaiveDate::parse_from_str(d.trim(), "%Y-%m-%d").ok();
+            return Some(PeriodFilter::Between(date(start)?, date(end)?));
+        }
+        let valid = match s.len() {
+            4 => s.parse::<u16>().is_ok(),
+            7 => NaiveDate::parse_from_str(&format!("{}-01", s), "%Y-%m-%d").is_ok(),
+            10 => NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok(),
+            _ => false,
+        };
+        valid.then(|| PeriodFilter::Ending(s.to_string()))
+    }
+
+    pub fn matches(&self, period: &Period) -> bool {
+        match self {
+            PeriodFilter::Forever => period.is_forever(),
+            PeriodFilter::Ending(prefix) => {
+                !period.is_forever()
+                    && period
+                        .end_date()
+                        .is_some_and(|end| end.to_string().starts_with(prefix.as_str()))
+            }
+            PeriodFilter::Between(start, end) => {
+                matches!(period, Period::Duration { .. })
+                    && period.start_date() == Some(*start)
+                    && period.end_date() == Some(*end)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FactQuery {
+    concept: Option<String>,
+    period: Option<PeriodFilter>,
+    dimensions: Vec<(String, String)>,
+}
+
+/// Whether `qname` is `name`, or has `name` as its local name
+fn names(qname: &str, name: &str) -> bool {
+    qname == name || (!name.contains(':') && qname.rsplit(':').next() == Some(name))
+}
+
+impl FactQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_concept(mut self, concept: impl Into<String>) -> Self {
+        self.concept = Some(concept.into());
+        self
+    }
+
+    pub fn with_period(mut self, period: PeriodFilter) -> Self {
+        self.period = Some(period);
+        self
+    }
+
+    /// Require the member `member` of the dimension `dimension`; for typed
+    /// dimensions `member` is the value as written
+    pub fn with_dimension(
+        mut self,
+        dimension: impl Into<String>,
+        member: impl Into<String>,
+    ) -> Self {
+        self.dimensions.push((dimension.into(), member.into()));
+        self
+    }
+
+    /// Whether `fact` meets every criterion; facts whose context is missing
+    /// only match queries on the concept alone
+    pub fn matches(&self, fact: &FactView<'_>) -> bool {
+        if let Some(concept) = &self.concept {
+            if !names(fact.concept(), concept) {
+                return false;
+            }
+        }
+        if self.period.is_none() && self.dimensions.is_empty() {
+            return true;
+        }
+        let Some(context) = fact.context() else {
+            return false;
+        };
+        if let Some(period) = &self.period {
+            if !period.matches(&context.period) {
+                return false;
+            }
+        }
+        let members = context.dimensions();
+        self.dimensions.iter().all(|(dimension, member)| {
+            members
+                .iter()
+                .any(|(d, m)| names(d, dimension) && (names(m, member) || m.trim() == member))
+        })
+    }
+
+    /// Matching facts of `doc`, in document order
+    pub fn select<'d>(&'d self, doc: &'d Document) -> impl Iterator<Item = FactView<'d>> + 'd {
+        doc.iter_facts().filter(move |fact| self.matches(fact))
+    }
+}
+
+/// JSON array of `facts` with their value, unit, context, period,
+/// decimals and dimension members; nil values are null
+pub fn to_json(facts: &[FactView<'_>]) -> Value {
+    facts
+        .iter()
+        .map(|fact| {
+            let context = fact.context();
+            json!({
+                "concept": fact.concept(),
+                "value": match fact.value() {
+                    FactValue::Nil => Value::Null,
+                    value => json!(value.to_string()),
+                },
+                "unit": fact.unit().map(|u| u.canonical()),
+                "context": context.map(|c| c.id.as_str()),
+                "period": context.map(|c| c.period.canonical()),
+                "decimals": fact.decimals(),
+                "dimensions": context.map_or_else(Map::new, |c| {
+                    c.dimensions()
+                        .into_iter()
+                        .map(|(dimension, member)| (dimension.to_string(), json!(member)))
+                        .collect()
+                }),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    #[test]
+    fn test_fact_query() {
+        let doc = Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:xbrldi="http://xbrl.org/2006/xbrldi" xmlns:us-gaap="http://fasb.org/us-gaap/2023" xmlns:iso4217="http://www.xbrl.org/2003/iso4217">
+  <xbrli:context id="FY23"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2023-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:context id="FY22"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2022-01-01</xbrli:startDate><xbrli:endDate>2022-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:context id="FY23_US"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">1</xbrli:identifier>
+    <xbrli:segment><xbrldi:explicitMember dimension="us-gaap:StatementGeographicalAxis">us-gaap:US</xbrldi:explicitMember></xbrli:segment></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2024-01-01T00:00:00</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:context id="I23"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <us-gaap:Revenues contextRef="FY23" unitRef="USD" decimals="-6">100</us-gaap:Revenues>
+  <us-gaap:Revenues contextRef="FY22" unitRef="USD" decimals="-6">90</us-gaap:Revenues>
+  <us-gaap:Revenues contextRef="FY23_US" unitRef="USD" decimals="-6">60</us-gaap:Revenues>
+  <us-gaap:Assets contextRef="I23" unitRef="USD" decimals="-6">500</us-gaap:Assets>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+        let values = |query: &FactQuery| -> Vec<String> {
+            query.select(&doc).map(|f| f.value().to_string()).collect()
+        };
+
+        let revenues = FactQuery::new().with_concept("us-gaap:Revenues");
+        assert_eq!(values(&revenues), ["100", "90", "60"]);
+        // The period written as a date-time still ends on 2023-12-31
+        let fy23 = revenues
+            .clone()
+            .with_period(PeriodFilter::parse("2023").unwrap());
+        assert_eq!(values(&fy23), ["100", "60"]);
+        let us = fy23
+            .clone()
+            .with_dimension("StatementGeographicalAxis", "US");
+        assert_eq!(values(&us), ["60"]);
+        assert!(values(&fy23.with_dimension("StatementGeographicalAxis", "EU")).is_empty());
+
+        let between = PeriodFilter::parse("2022-01-01..2022-12-31").unwrap();
+        assert_eq!(values(&FactQuery::new().with_period(between)), ["90"]);
+        assert_eq!(
+            values(
+                &FactQuery::new()
+                    .with_concept("Assets")
+                    .with_period(PeriodFilter::parse("2023-12").unwrap())
+            ),
+            ["500"]
+        );
+        assert_eq!(PeriodFilter::parse("23"), None);
+        assert_eq!(PeriodFilter::parse("2023-13"), None);
+
+        let facts: Vec<_> = us.select(&doc).collect();
+        assert_eq!(
+            to_json(&facts),
+            json!([{
+                "concept": "us-gaap:Revenues",
+                "value": "60",
+                "unit": "iso4217:USD",
+                "context": "FY23_US",
+                "period": "2023-01-01/2023-12-31",
+                "decimals": -6,
+                "dimensions": { "us-gaap:StatementGeographicalAxis": "us-gaap:US" },
+            }])
+        );
+    }
+}
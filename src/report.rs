@@ -0,0 +1,827 @@
+//! Report rendering for the CLI
+//!
+//! Commands describe their outcome as a [`Report`]; a [`Formatter`] turns it
+//! into text, JSON, CSV, SARIF, JUnit XML or HTML, so every command supports
+//! every format.
+
+use crate::export::csv_field;
+use crate::{Finding, Severity, ValidationResult};
+use serde_json::{json, Value};
+use std::fmt::Write;
+
+/// Escape text for HTML/XML element content and attribute values
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+const HTML_STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; margin: 2rem; color: #1f2328; }
+h1 { font-size: 1.4rem; margin-bottom: 0.2rem; }
+.meta { color: #59636e; margin-bottom: 1.5rem; }
+.cards { display: flex; gap: 1rem; margin-bottom: 1.5rem; }
+.card { border: 1px solid #d1d9e0; border-radius: 6px; padding: 0.8rem 1.2rem; min-width: 8rem; }
+.card .value { font-size: 1.6rem; font-weight: 600; }
+.card.status-valid .value { color: #1a7f37; }
+.card.status-invalid .value, .card.errors .value { color: #d1242f; }
+.card.warnings .value { color: #9a6700; }
+.filters { margin-bottom: 0.8rem; display: flex; gap: 0.5rem; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; padding: 0.4rem 0.6rem; border-bottom: 1px solid #d1d9e0; vertical-align: top; }
+th { background: #f6f8fa; }
+.sev { font-weight: 600; text-transform: uppercase; font-size: 0.8rem; }
+.sev-error { color: #d1242f; }
+.sev-warning { color: #9a6700; }
+";
+
+const HTML_SCRIPT: &str = "
+function applyFilter() {
+  var text = document.getElementById('filter-text').value.toLowerCase();
+  var sev = document.getElementById('filter-severity').value;
+  document.querySelectorAll('#findings tbody tr').forEach(function (row) {
+    var matchesText = row.textContent.toLowerCase().indexOf(text) !== -1;
+    var matchesSev = !sev || row.dataset.severity === sev;
+    row.style.display = matchesText && matchesSev ? '' : 'none';
+  });
+}
+";
+
+fn location_cell(finding: &Finding) -> String {
+    let position = finding
+        .position
+        .map(|p| format!(" line {}, column {}", p.line, p.column))
+        .unwrap_or_default();
+    match finding.location.as_deref() {
+        Some(location) => {
+            let target = location.split(' ').next().unwrap_or(location);
+            format!(
+                "<a href=\"{}\">{}</a>{}",
+                escape(target),
+                escape(location),
+                position
+            )
+        }
+        None => position.trim_start().to_string(),
+    }
+}
+
+/// Output of one CLI command, rendered by any [`Formatter`]
+#[derive(Debug, Clone)]
+pub struct Report {
+    /// Subcommand that produced the report, e.g. `validate`
+    pub command: String,
+    pub source: String,
+    pub ok: bool,
+    /// Headline such as `Document is valid`
+    pub status: String,
+    /// Named counts and timings, in display order
+    pub summary: Vec<(String, Value)>,
+    /// Listing after the summary, such as facts or changes
+    pub lines: Vec<String>,
+    /// Names of the columns of `rows`
+    pub columns: Vec<String>,
+    /// Tabular listing, one cell per column
+    pub rows: Vec<Vec<String>>,
+    /// Structured result, such as exported facts, written by the JSON
+    /// formatter as `data`
+    pub data: Option<Value>,
+    pub findings: Vec<Finding>,
+    /// Rules checked, one JUnit test case each; the command alone when empty
+    pub rules: Vec<&'static str>,
+    pub duration_ms: u64,
+}
+
+impl Report {
+    pub fn new(command: &str, source: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            source: source.to_string(),
+            ok: true,
+            status: String::new(),
+            summary: Vec::new(),
+            lines: Vec::new(),
+            columns: Vec::new(),
+            rows: Vec::new(),
+            data: None,
+            findings: Vec::new(),
+            rules: Vec::new(),
+            duration_ms: 0,
+        }
+    }
+
+    /// Report of a validation run
+    pub fn validation(source: &str, result: &ValidationResult) -> Self {
+        let mut report = Self::new("validate", source)
+            .with_status(
+                result.is_valid,
+                if result.is_valid {
+                    "Document is valid"
+                } else {
+                    "Validation failed"
+                },
+            )
+            .with_summary("Errors", result.errors.len())
+            .with_summary("Warnings", result.warnings.len())
+            .with_summary("Facts validated", result.stats.facts_validated)
+            .with_duration(result.stats.duration_ms);
+        if !result.unsupported.is_empty() {
+            report = report.with_summary("Unsupported features", result.unsupported.len());
+        }
+        for feature in &result.unsupported {
+            report = report.with_line(format!("Not supported: {}", feature));
+        }
+        report.findings = result.findings.clone();
+        report.rules = crate::validator::RULES.to_vec();
+        report
+    }
+
+    pub fn with_status(mut self, ok: bool, status: &str) -> Self {
+        self.ok = ok;
+        self.status = status.to_string();
+        self
+    }
+
+    pub fn with_summary(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.summary.push((name.to_string(), value.into()));
+        self
+    }
+
+    pub fn with_line(mut self, line: String) -> Self {
+        self.lines.push(line);
+        self
+    }
+
+    pub fn with_table(mut self, columns: &[&str], rows: Vec<Vec<String>>) -> Self {
+        self.columns = columns.iter().map(|c| c.to_string()).collect();
+        self.rows = rows;
+        self
+    }
+
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    pub fn with_duration(mut self, duration_ms: u64) -> Self {
+        self.duration_ms = duration_ms;
+        self
+    }
+
+    fn errors(&self) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == Severity::Error)
+    }
+
+    fn warnings(&self) -> impl Iterator<Item = &Finding> {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == Severity::Warning)
+    }
+}
+
+/// Summary values without the quotes JSON puts around strings
+fn display(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+/// Header and rows of the report's table, padded to common column widths.
+/// Columns empty in every row are left out; numeric columns line up on the
+/// right.
+fn aligned(report: &Report) -> Vec<String> {
+    fn cell(row: &[String], i: usize) -> &str {
+        row.get(i).map(String::as_str).unwrap_or("")
+    }
+    if report.rows.is_empty() {
+        return Vec::new();
+    }
+    let columns: Vec<(usize, usize, bool)> = (0..report.columns.len())
+        .filter(|&i| report.rows.iter().any(|row| !cell(row, i).is_empty()))
+        .map(|i| {
+            let width = report
+                .rows
+                .iter()
+                .map(|row| cell(row, i).chars().count())
+                .chain([report.columns[i].chars().count()])
+                .max()
+                .unwrap_or(0);
+            let numeric = report
+                .rows
+                .iter()
+                .map(|row| cell(row, i))
+                .filter(|c| !c.is_empty())
+                .all(|c| c.parse::<f64>().is_ok());
+            (i, width, numeric)
+        })
+        .collect();
+    let line = |row: &[String]| {
+        columns
+            .iter()
+            .map(|&(i, width, numeric)| match numeric {
+                true => format!("{:>width$}", cell(row, i), width = width),
+                false => format!("{:<width$}", cell(row, i), width = width),
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+    std::iter::once(line(&report.columns))
+        .chain(report.rows.iter().map(|row| line(row)))
+        .collect()
+}
+
+/// Renders a [`Report`]; every command supports every formatter
+pub trait Formatter {
+    fn format(&self, report: &Report) -> String;
+}
+
+/// Names accepted by [`formatter`]
+pub const FORMATS: &[&str] = &["text", "json", "csv", "sarif", "junit", "html"];
+
+/// The formatter called `name`, one of [`FORMATS`]
+pub fn formatter(name: &str, color: bool) -> Option<Box<dyn Formatter>> {
+    Some(match name {
+        "text" => Box::new(TextFormatter { color }),
+        "json" => Box::new(JsonFormatter),
+        "csv" => Box::new(CsvFormatter),
+        "sarif" => Box::new(SarifFormatter),
+        "junit" => Box::new(JunitFormatter),
+        "html" => Box::new(HtmlFormatter),
+        _ => return None,
+    })
+}
+
+/// Terminal output, listing at most five errors
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextFormatter {
+    /// Color marks with ANSI escapes
+    pub color: bool,
+}
+
+impl TextFormatter {
+    fn paint(&self, text: &str, code: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+impl Formatter for TextFormatter {
+    fn format(&self, report: &Report) -> String {
+        let mut out = String::new();
+        let mark = if report.ok {
+            self.paint("✓", "1;32")
+        } else {
+            self.paint("✗", "1;31")
+        };
+        let _ = write!(out, "{} {}", mark, report.source);
+        if !report.status.is_empty() {
+            let _ = write!(out, " - {}", report.status);
+        }
+        out.push('\n');
+        for (name, value) in &report.summary {
+            let _ = writeln!(out, "  {}: {}", name, display(value));
+        }
+        for line in &report.lines {
+            let _ = writeln!(out, "  {}", line);
+        }
+        let mut table = aligned(report).into_iter();
+        if let Some(header) = table.next() {
+            let _ = writeln!(out, "  {}", self.paint(&header, "1"));
+        }
+        for line in table {
+            let _ = writeln!(out, "  {}", line);
+        }
+
+        let errors: Vec<&Finding> = report.errors().collect();
+        for finding in errors.iter().take(5) {
+            let _ = writeln!(
+                out,
+                "  {} [{}] {}",
+                self.paint("ERROR:", "31"),
+                finding.code,
+                finding.message
+            );
+            if let Some(p) = finding.position {
+                let _ = writeln!(out, "    --> {}:{}:{}", report.source, p.line, p.column);
+            }
+        }
+        if errors.len() > 5 {
+            let _ = writeln!(out, "  ... and {} more errors", errors.len() - 5);
+        }
+        out
+    }
+}
+
+fn finding_json(finding: &Finding) -> Value {
+    json!({
+        "severity": finding.severity.as_str(),
+        "rule": finding.rule,
+        "code": finding.code,
+        "citation": finding.citation,
+        "message": finding.message,
+        "location": finding.location,
+        "line": finding.position.map(|p| p.line),
+        "column": finding.position.map(|p| p.column),
+    })
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonFormatter;
+
+impl Formatter for JsonFormatter {
+    fn format(&self, report: &Report) -> String {
+        let summary: serde_json::Map<String, Value> = report.summary.iter().cloned().collect();
+        let mut json = json!({
+            "command": report.command,
+            "source": report.source,
+            "ok": report.ok,
+            "status": report.status,
+            "summary": summary,
+            "lines": report.lines,
+            "findings": report.findings.iter().map(finding_json).collect::<Vec<_>>(),
+            "duration_ms": report.duration_ms,
+        });
+        // The structured result, else the table as one object per row
+        if let Some(data) = &report.data {
+            json["data"] = data.clone();
+        } else if !report.rows.is_empty() {
+            let rows: Vec<serde_json::Map<String, Value>> = report
+                .rows
+                .iter()
+                .map(|row| {
+                    report
+                        .columns
+                        .iter()
+                        .zip(row)
+                        .map(|(column, cell)| (column.clone(), Value::from(cell.as_str())))
+                        .collect()
+                })
+                .collect();
+            json["rows"] = json!(rows);
+        }
+        let mut out = serde_json::to_string_pretty(&json).unwrap_or_default();
+        out.push('\n');
+        out
+    }
+}
+
+/// CSV of the report's table, or of its summary when it has no table
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CsvFormatter;
+
+impl Formatter for CsvFormatter {
+    fn format(&self, report: &Report) -> String {
+        let line = |cells: &mut dyn Iterator<Item = &str>| {
+            let mut line = cells.map(csv_field).collect::<Vec<_>>().join(",");
+            line.push('\n');
+            line
+        };
+        if report.rows.is_empty() {
+            let mut out = line(&mut ["name", "value"].into_iter());
+            for (name, value) in &report.summary {
+                out.push_str(&line(&mut [name.as_str(), &display(value)].into_iter()));
+            }
+            return out;
+        }
+        let mut out = line(&mut report.columns.iter().map(String::as_str));
+        for row in &report.rows {
+            out.push_str(&line(&mut row.iter().map(String::as_str)));
+        }
+        out
+    }
+}
+
+/// SARIF 2.1.0, for code scanning dashboards; each finding code is a rule
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SarifFormatter;
+
+impl Formatter for SarifFormatter {
+    fn format(&self, report: &Report) -> String {
+        let mut rules: Vec<&Finding> = Vec::new();
+        for finding in &report.findings {
+            if !rules.iter().any(|r| r.code == finding.code) {
+                rules.push(finding);
+            }
+        }
+        let rules: Vec<Value> = rules
+            .iter()
+            .map(|f| {
+                json!({
+                    "id": f.code,
+                    "name": f.rule,
+                    "shortDescription": { "text": f.citation },
+                })
+            })
+            .collect();
+        let results: Vec<Value> = report
+            .findings
+            .iter()
+            .map(|f| {
+                let mut location = json!({ "artifactLocation": { "uri": report.source } });
+                if let Some(p) = f.position {
+                    location["region"] = json!({ "startLine": p.line, "startColumn": p.column });
+                }
+                json!({
+                    "ruleId": f.code,
+                    "level": f.severity.as_str(),
+                    "message": { "text": f.message },
+                    "locations": [{ "physicalLocation": location }],
+                })
+            })
+            .collect();
+        let json = json!({
+            "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "crabrl",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": env!("CARGO_PKG_REPOSITORY"),
+                        "rules": rules,
+                    }
+                },
+                "results": results,
+            }],
+        });
+        let mut out = serde_json::to_string_pretty(&json).unwrap_or_default();
+        out.push('\n');
+        out
+    }
+}
+
+/// Standalone HTML page with summary cards and a filterable findings table
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HtmlFormatter;
+
+impl Formatter for HtmlFormatter {
+    fn format(&self, report: &Report) -> String {
+        let mut html = String::new();
+        let status = if report.ok { "valid" } else { "invalid" };
+
+        let _ = write!(
+            html,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>crabrl {command} report - {source}</title>\n<style>{style}</style>\n</head>\n<body>\n\
+             <h1>crabrl {command} report</h1>\n<div class=\"meta\">{source} &middot; crabrl {version}</div>\n",
+            command = escape(&report.command),
+            source = escape(&report.source),
+            style = HTML_STYLE,
+            version = env!("CARGO_PKG_VERSION"),
+        );
+
+        let _ = writeln!(
+            html,
+            "<div class=\"cards\">\n\
+             <div class=\"card status-{status}\"><div>Status</div><div class=\"value\">{status}</div></div>"
+        );
+        for (name, value) in &report.summary {
+            let _ = writeln!(
+                html,
+                "<div class=\"card {class}\"><div>{name}</div><div class=\"value\">{value}</div></div>",
+                class = escape(&name.to_lowercase().replace(' ', "-")),
+                name = escape(name),
+                value = escape(&display(value)),
+            );
+        }
+        let _ = write!(
+            html,
+            "<div class=\"card\"><div>Duration</div><div class=\"value\">{} ms</div></div>\n</div>\n",
+            report.duration_ms
+        );
+
+        if !report.lines.is_empty() {
+            html.push_str("<pre>\n");
+            for line in &report.lines {
+                html.push_str(&escape(line));
+                html.push('\n');
+            }
+            html.push_str("</pre>\n");
+        }
+        if !report.rows.is_empty() {
+            html.push_str("<table class=\"rows\">\n<thead><tr>");
+            for column in &report.columns {
+                let _ = write!(html, "<th>{}</th>", escape(column));
+            }
+            html.push_str("</tr></thead>\n<tbody>\n");
+            for row in &report.rows {
+                html.push_str("<tr>");
+                for cell in row {
+                    let _ = write!(html, "<td>{}</td>", escape(cell));
+                }
+                html.push_str("</tr>\n");
+            }
+            html.push_str("</tbody>\n</table>\n");
+        }
+
+        html.push_str(
+            "<div class=\"filters\">\n\
+             <input id=\"filter-text\" type=\"search\" placeholder=\"Filter findings\" oninput=\"applyFilter()\">\n\
+             <select id=\"filter-severity\" onchange=\"applyFilter()\">\
+             <option value=\"\">All severities</option>\
+             <option value=\"error\">Errors</option>\
+             <option value=\"warning\">Warnings</option></select>\n</div>\n\
+             <table id=\"findings\">\n<thead><tr><th>Severity</th><th>Rule</th><th>Message</th>\
+             <th>Specification</th><th>Location</th></tr></thead>\n<tbody>\n",
+        );
+
+        // Errors first, then warnings, each in document order
+        for finding in report.errors().chain(report.warnings()) {
+            let severity = finding.severity.as_str();
+            let _ = writeln!(
+                html,
+                "<tr data-severity=\"{severity}\"><td class=\"sev sev-{severity}\">{severity}</td>\
+                 <td><code>{rule}</code></td><td>{message}</td><td>{citation}</td><td>{location}</td></tr>",
+                rule = finding.rule,
+                message = escape(&finding.message),
+                citation = escape(finding.citation),
+                location = location_cell(finding),
+            );
+        }
+        if report.findings.is_empty() {
+            html.push_str("<tr><td colspan=\"5\">No findings</td></tr>\n");
+        }
+
+        let _ = write!(
+            html,
+            "</tbody>\n</table>\n<script>{}</script>\n</body>\n</html>\n",
+            HTML_SCRIPT
+        );
+        html
+    }
+}
+
+/// JUnit XML with one test case per rule, so CI servers show each failing
+/// rule on its own
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JunitFormatter;
+
+impl Formatter for JunitFormatter {
+    fn format(&self, report: &Report) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let command = escape(&report.command);
+        let time = report.duration_ms as f64 / 1000.0;
+        let single = [command.as_str()];
+        let cases: &[&str] = if report.rules.is_empty() {
+            &single
+        } else {
+            &report.rules
+        };
+        // Without rules, every finding belongs to the one test case
+        let of_rule = |rule: &str| -> Vec<&Finding> {
+            report
+                .findings
+                .iter()
+                .filter(|f| report.rules.is_empty() || f.rule == rule)
+                .collect()
+        };
+        let failed = |rule: &str| {
+            of_rule(rule).iter().any(|f| f.severity == Severity::Error)
+                || (report.rules.is_empty() && !report.ok)
+        };
+        let failures = cases.iter().filter(|rule| failed(rule)).count();
+
+        let _ = writeln!(
+            xml,
+            "<testsuites name=\"crabrl\" tests=\"{tests}\" failures=\"{failures}\" time=\"{time:.3}\">\n  \
+             <testsuite name=\"{source}\" tests=\"{tests}\" failures=\"{failures}\" errors=\"0\" time=\"{time:.3}\">",
+            tests = cases.len(),
+            source = escape(&report.source),
+        );
+
+        for rule in cases {
+            let findings = of_rule(rule);
+            let errors: Vec<&&Finding> = findings
+                .iter()
+                .filter(|f| f.severity == Severity::Error)
+                .collect();
+            let warnings: Vec<&&Finding> = findings
+                .iter()
+                .filter(|f| f.severity == Severity::Warning)
+                .collect();
+
+            let _ = write!(
+                xml,
+                "    <testcase classname=\"crabrl.{}\" name=\"{}\"",
+                command, rule
+            );
+            if !failed(rule) && warnings.is_empty() {
+                xml.push_str("/>\n");
+                continue;
+            }
+            xml.push_str(">\n");
+
+            if let Some(first) = errors.first() {
+                let _ = write!(
+                    xml,
+                    "      <failure message=\"{}\" type=\"{}\">",
+                    escape(&format!("{} finding(s): {}", errors.len(), first.message)),
+                    escape(first.citation),
+                );
+                for finding in &errors {
+                    xml.push_str(&escape(&finding_line(finding)));
+                    xml.push('\n');
+                }
+                xml.push_str("</failure>\n");
+            } else if failed(rule) {
+                let _ = writeln!(
+                    xml,
+                    "      <failure message=\"{}\"/>",
+                    escape(&report.status)
+                );
+            }
+            if !warnings.is_empty() {
+                xml.push_str("      <system-out>");
+                for finding in &warnings {
+                    xml.push_str(&escape(&finding_line(finding)));
+                    xml.push('\n');
+                }
+                xml.push_str("</system-out>\n");
+            }
+            xml.push_str("    </testcase>\n");
+        }
+
+        xml.push_str("  </testsuite>\n</testsuites>\n");
+        xml
+    }
+}
+
+/// Standalone HTML report for one validated document
+pub fn render_html(source: &str, result: &ValidationResult) -> String {
+    HtmlFormatter.format(&Report::validation(source, result))
+}
+
+/// JUnit XML with one test case per validation rule
+pub fn render_junit(source: &str, result: &ValidationResult) -> String {
+    JunitFormatter.format(&Report::validation(source, result))
+}
+
+fn finding_line(finding: &Finding) -> String {
+    let mut line = format!(
+        "[{}] {}: {} ({})",
+        finding.severity.as_str(),
+        finding.code,
+        finding.message,
+        finding.citation
+    );
+    if let Some(location) = &finding.location {
+        let _ = write!(line, " at {}", location);
+    }
+    if let Some(p) = finding.position {
+        let _ = write!(line, " (line {}, column {})", p.line, p.column);
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ValidationStats;
+
+    fn sample_result() -> ValidationResult {
+        ValidationResult {
+            is_valid: false,
+            errors: vec!["Duplicate id: <c1>".to_string()],
+            warnings: Vec::new(),
+            findings: vec![Finding {
+                severity: Severity::Error,
+                rule: "duplicate-id",
+                code: "xbrl.2.1.duplicateId",
+                citation: "XBRL 2.1 §4.7.1",
+                message: "Duplicate id: <c1>".to_string(),
+                location: Some("filing.xml#f1".to_string()),
+                position: Some(crate::model::SourcePosition {
+                    offset: 120,
+                    line: 4,
+                    column: 3,
+                }),
+                error: crate::validator::ValidationError::DuplicateId {
+                    id: "<c1>".to_string(),
+                },
+            }],
+            audit: None,
+            unsupported: Vec::new(),
+            stats: ValidationStats {
+                facts_validated: 10,
+                duration_ms: 1,
+            },
+        }
+    }
+
+    #[test]
+    fn test_render_html() {
+        let html = render_html("filing.xml", &sample_result());
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("Duplicate id: &lt;c1&gt;"));
+        assert!(html.contains("<a href=\"filing.xml#f1\">"));
+        assert!(html.contains("data-severity=\"error\""));
+    }
+
+    #[test]
+    fn test_render_junit() {
+        let xml = render_junit("filing.xml", &sample_result());
+        assert!(xml.contains("tests=\"23\" failures=\"1\""));
+        assert!(xml.contains("<testcase classname=\"crabrl.validate\" name=\"context-ref\"/>"));
+        assert!(xml.contains("<failure message=\"1 finding(s): Duplicate id: &lt;c1&gt;\""));
+        assert!(xml.contains("xbrl.2.1.duplicateId: Duplicate id"));
+        assert!(xml.contains("at filing.xml#f1 (line 4, column 3)"));
+    }
+
+    #[test]
+    fn test_formatters() {
+        let report = Report::validation("filing.xml", &sample_result());
+        for name in FORMATS {
+            assert!(!formatter(name, false).unwrap().format(&report).is_empty());
+        }
+        assert!(formatter("xml", false).is_none());
+
+        let text = TextFormatter { color: false }.format(&report);
+        assert!(text.starts_with("✗ filing.xml - Validation failed\n  Errors: 1\n"));
+        assert!(text.contains(
+            "  ERROR: [xbrl.2.1.duplicateId] Duplicate id: <c1>\n    --> filing.xml:4:3\n"
+        ));
+
+        let sarif: Value = serde_json::from_str(&SarifFormatter.format(&report)).unwrap();
+        let result = &sarif["runs"][0]["results"][0];
+        assert_eq!(result["ruleId"], "xbrl.2.1.duplicateId");
+        assert_eq!(result["level"], "error");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["region"]["startLine"],
+            4
+        );
+
+        // Commands without rules report as a single test case
+        let diff = Report::new("diff", "a.xml -> b.xml")
+            .with_status(false, "1 changed")
+            .with_summary("Changed", 1)
+            .with_line("~ a:Revenue 2023 = 1 -> 2".to_string());
+        let json: Value = serde_json::from_str(&JsonFormatter.format(&diff)).unwrap();
+        assert_eq!(json["summary"]["Changed"], 1);
+        assert_eq!(json["ok"], false);
+        let junit = JunitFormatter.format(&diff);
+        assert!(junit.contains("tests=\"1\" failures=\"1\""));
+        assert!(junit.contains("<failure message=\"1 changed\"/>"));
+    }
+
+    #[test]
+    fn test_table() {
+        let report = Report::new("query", "filing.xml")
+            .with_summary("Facts", 2)
+            .with_table(
+                &["Concept", "Value", "Unit"],
+                vec![
+                    vec!["us-gaap:Assets".into(), "1000".into(), String::new()],
+                    vec![
+                        "dei:EntityRegistrantName".into(),
+                        "-5".into(),
+                        String::new(),
+                    ],
+                ],
+            );
+
+        // Empty columns are dropped and numeric ones right-aligned
+        let text = TextFormatter { color: false }.format(&report);
+        eprintln!("{text:?}");
+        assert!(text.ends_with(
+            "  Concept                   Value\n  us-gaap:Assets             1000\n  dei:EntityRegistrantName     -5\n"
+        ));
+
+        let csv = CsvFormatter.format(&report);
+        assert_eq!(
+            csv,
+            "Concept,Value,Unit\nus-gaap:Assets,1000,\ndei:EntityRegistrantName,-5,\n"
+        );
+        let summary =
+            CsvFormatter.format(&Report::new("query", "filing.xml").with_summary("Facts", 2));
+        assert_eq!(summary, "name,value\nFacts,2\n");
+
+        // Without structured data, JSON lists the rows as objects
+        let json: Value = serde_json::from_str(&JsonFormatter.format(&report)).unwrap();
+        assert_eq!(json["rows"][0]["Concept"], "us-gaap:Assets");
+        assert_eq!(json["rows"][1]["Value"], "-5");
+        let json: Value = serde_json::from_str(
+            &JsonFormatter
+                .format(&report.with_data(serde_json::json!([{"concept": "us-gaap:Assets"}]))),
+        )
+        .unwrap();
+        assert_eq!(json["data"][0]["concept"], "us-gaap:Assets");
+        assert!(json.get("rows").is_none());
+    }
+}
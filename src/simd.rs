@@ -1,12 +1,14 @@
-use memchr::{memchr, memchr2, memchr3};
+//! Byte scanning primitives used by the parser
+//!
+//! AVX2 paths are only compiled on x86_64 and selected at runtime.
+
+use memchr::{memchr, memchr3};
+#[cfg(target_arch = "x86_64")]
 use std::arch::x86_64::*;
 
 const XML_TAG_START: u8 = b'<';
 const XML_TAG_END: u8 = b'>';
-const XML_SLASH: u8 = b'/';
 const XML_QUOTE: u8 = b'"';
-const XML_EQUALS: u8 = b'=';
-const XML_SPACE: u8 = b' ';
 
 #[inline(always)]
 pub fn find_tag_start(haystack: &[u8]) -> Option<usize> {
@@ -28,6 +30,11 @@ pub fn find_any_delimiter(haystack: &[u8]) -> Option<usize> {
     memchr3(XML_TAG_START, XML_TAG_END, XML_QUOTE, haystack)
 }
 
+/// # Safety
+///
+/// The CPU must support AVX2. Loads are unaligned and always within
+/// `haystack`.
+#[cfg(target_arch = "x86_64")]
 #[target_feature(enable = "avx2")]
 #[inline]
 pub unsafe fn find_pattern_avx2(haystack: &[u8], pattern: &[u8]) -> Option<usize> {
@@ -47,8 +54,9 @@ pub unsafe fn find_pattern_avx2(haystack: &[u8], pattern: &[u8]) -> Option<usize
             for bit_pos in 0..32 {
                 if (mask & (1 << bit_pos)) != 0 {
                     let pos = i + bit_pos;
-                    if pos + pattern.len() <= haystack.len() 
-                        && &haystack[pos..pos + pattern.len()] == pattern {
+                    if pos + pattern.len() <= haystack.len()
+                        && &haystack[pos..pos + pattern.len()] == pattern
+                    {
                         return Some(pos);
                     }
                 }
@@ -67,6 +75,10 @@ pub unsafe fn find_pattern_avx2(haystack: &[u8], pattern: &[u8]) -> Option<usize
     None
 }
 
+/// # Safety
+///
+/// The CPU must support AVX2.
+#[cfg(target_arch = "x86_64")]
 #[target_feature(enable = "avx2")]
 #[inline]
 pub unsafe fn skip_whitespace_avx2(data: &[u8], mut pos: usize) -> usize {
@@ -77,19 +89,19 @@ pub unsafe fn skip_whitespace_avx2(data: &[u8], mut pos: usize) -> usize {
 
     while pos + 32 <= data.len() {
         let chunk = _mm256_loadu_si256(data.as_ptr().add(pos) as *const _);
-        
+
         let is_space = _mm256_cmpeq_epi8(chunk, space);
         let is_tab = _mm256_cmpeq_epi8(chunk, tab);
         let is_newline = _mm256_cmpeq_epi8(chunk, newline);
         let is_carriage = _mm256_cmpeq_epi8(chunk, carriage);
-        
+
         let is_whitespace = _mm256_or_si256(
             _mm256_or_si256(is_space, is_tab),
-            _mm256_or_si256(is_newline, is_carriage)
+            _mm256_or_si256(is_newline, is_carriage),
         );
-        
+
         let mask = _mm256_movemask_epi8(is_whitespace);
-        
+
         if mask != -1 {
             for i in 0..32 {
                 if (mask & (1 << i)) == 0 {
@@ -97,7 +109,7 @@ pub unsafe fn skip_whitespace_avx2(data: &[u8], mut pos: usize) -> usize {
                 }
             }
         }
-        
+
         pos += 32;
     }
 
@@ -115,7 +127,8 @@ pub unsafe fn skip_whitespace_avx2(data: &[u8], mut pos: usize) -> usize {
 pub fn skip_whitespace(data: &[u8], mut pos: usize) -> usize {
     #[cfg(target_arch = "x86_64")]
     {
-        if is_x86_feature_detected!("avx2") && data.len() - pos >= 32 {
+        if is_x86_feature_detected!("avx2") && data.len().saturating_sub(pos) >= 32 {
+            // SAFETY: AVX2 support was just detected
             return unsafe { skip_whitespace_avx2(data, pos) };
         }
     }
@@ -131,14 +144,20 @@ pub fn skip_whitespace(data: &[u8], mut pos: usize) -> usize {
 
 #[inline(always)]
 pub fn find_pattern(haystack: &[u8], pattern: &[u8]) -> Option<usize> {
+    if pattern.is_empty() {
+        return None;
+    }
+
     #[cfg(target_arch = "x86_64")]
     {
         if is_x86_feature_detected!("avx2") && haystack.len() >= 32 {
+            // SAFETY: AVX2 support was just detected
             return unsafe { find_pattern_avx2(haystack, pattern) };
         }
     }
 
-    haystack.windows(pattern.len())
+    haystack
+        .windows(pattern.len())
         .position(|window| window == pattern)
 }
 
@@ -0,0 +1,116 @@
+//! Per-concept fact statistics
+//!
+//! [`Document::concept_stats`] summarises the facts of each concept: how
+//! many there are, the range and mean of the numeric ones and how many
+//! distinct contexts report it. Values are compared as exact decimals.
+
+use crate::model::{Decimal, Document};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Facts reported for one concept
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConceptStats {
+    pub concept: String,
+    pub facts: usize,
+    pub nil: usize,
+    /// Facts with a numeric value, which `min`, `max` and `mean` cover
+    pub numeric: usize,
+    pub min: Option<Decimal>,
+    pub max: Option<Decimal>,
+    /// `None` also when the sum overflows
+    pub mean: Option<Decimal>,
+    /// Distinct contexts the concept is reported in
+    pub contexts: usize,
+}
+
+#[derive(Default)]
+struct Accumulator {
+    facts: usize,
+    nil: usize,
+    numeric: usize,
+    min: Option<Decimal>,
+    max: Option<Decimal>,
+    sum: Option<Decimal>,
+    contexts: BTreeSet<u32>,
+}
+
+impl Document {
+    /// Statistics of every concept with facts, ordered by concept name
+    pub fn concept_stats(&self) -> Vec<ConceptStats> {
+        let mut by_concept: BTreeMap<&str, Accumulator> = BTreeMap::new();
+        for fact in self.iter_facts() {
+            let acc = by_concept.entry(fact.concept()).or_default();
+            acc.facts += 1;
+            acc.contexts.insert(self.facts.context_ids[fact.index()]);
+            let value = fact.value();
+            if value.is_nil() {
+                acc.nil += 1;
+            }
+            let Some(number) = value.as_decimal() else {
+                continue;
+            };
+            acc.min = Some(acc.min.map_or(number, |min| min.min(number)));
+            acc.max = Some(acc.max.map_or(number, |max| max.max(number)));
+            acc.sum = match acc.numeric {
+                0 => Some(number),
+                _ => acc.sum.and_then(|sum| sum.checked_add(number)),
+            };
+            acc.numeric += 1;
+        }
+
+        by_concept
+            .into_iter()
+            .map(|(concept, acc)| ConceptStats {
+                concept: concept.to_string(),
+                facts: acc.facts,
+                nil: acc.nil,
+                numeric: acc.numeric,
+                min: acc.min,
+                max: acc.max,
+                mean: acc
+                    .sum
+                    .and_then(|sum| sum.checked_div(Decimal::from(acc.numeric))),
+                contexts: acc.contexts.len(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Parser;
+
+    #[test]
+    fn test_concept_stats() {
+        let doc = Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="c1"><xbrli:entity><xbrli:identifier scheme="http://example.com">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:context id="c2"><xbrli:entity><xbrli:identifier scheme="http://example.com">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2022-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="usd"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <a:Cash contextRef="c1" unitRef="usd" decimals="0">100</a:Cash>
+  <a:Cash contextRef="c2" unitRef="usd" decimals="0">-50.5</a:Cash>
+  <a:Cash contextRef="c2" unitRef="usd" xsi:nil="true"/>
+  <a:Name contextRef="c1">Acme</a:Name>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+        let stats = doc.concept_stats();
+        assert_eq!(stats.len(), 2);
+
+        let cash = &stats[0];
+        assert_eq!(cash.concept, "a:Cash");
+        assert_eq!((cash.facts, cash.nil, cash.numeric), (3, 1, 2));
+        assert_eq!(cash.min, Some("-50.5".parse().unwrap()));
+        assert_eq!(cash.max, Some("100".parse().unwrap()));
+        assert_eq!(cash.mean, Some("24.75".parse().unwrap()));
+        assert_eq!(cash.contexts, 2);
+
+        let name = &stats[1];
+        assert_eq!((name.facts, name.numeric, name.contexts), (1, 0, 1));
+        assert_eq!(name.mean, None);
+    }
+}
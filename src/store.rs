@@ -0,0 +1,203 @@
+//! Content-addressed store of parsed documents
+//!
+//! Snapshots are keyed by the SHA-256 of the source document, so a filing
+//! is parsed once however many jobs read it. An append-only index records
+//! the entity, form type and period of every snapshot for lookups without
+//! opening them.
+//!
+//! Layout: `objects/<2 hex>/<sha256>.json` holds the serialized
+//! [`Document`], `index.ndjson` one [`SnapshotEntry`] per line.
+
+use crate::corpus::CorpusEntry;
+use crate::model::{Document, Provenance};
+use crate::{Error, Parser, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Index record of one stored snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    pub sha256: String,
+    /// Source URI the document was first stored from
+    pub source: Option<String>,
+    /// Zero-padded CIK from dei, or the entity identifier of the contexts
+    pub cik: Option<String>,
+    pub document_type: Option<String>,
+    pub period_end: Option<NaiveDate>,
+    pub facts: usize,
+}
+
+pub struct SnapshotStore {
+    root: PathBuf,
+}
+
+impl SnapshotStore {
+    /// Open the store at `root`, creating it if needed
+    pub fn open<P: AsRef<Path>>(root: P) -> Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        std::fs::create_dir_all(root.join("objects"))?;
+        Ok(Self { root })
+    }
+
+    fn object_path(&self, sha256: &str) -> PathBuf {
+        let shard = sha256.get(..2).unwrap_or("00");
+        self.root
+            .join("objects")
+            .join(shard)
+            .join(format!("{}.json", sha256))
+    }
+
+    pub fn contains(&self, sha256: &str) -> bool {
+        self.object_path(sha256).is_file()
+    }
+
+    /// Store `doc` under the digest of its source; documents without
+    /// [`Provenance`] cannot be addressed and are rejected
+    pub fn put(&self, doc: &Document) -> Result<SnapshotEntry> {
+        let sha256 = doc
+            .provenance
+            .as_ref()
+            .map(|p| p.sha256.clone())
+            .ok_or_else(|| Error::Export("document has no provenance to key it by".into()))?;
+        let corpus = CorpusEntry::from_document(PathBuf::new(), doc, None);
+        let entry = SnapshotEntry {
+            sha256: sha256.clone(),
+            source: doc.provenance.as_ref().and_then(|p| p.uri.clone()),
+            cik: corpus.cik,
+            document_type: corpus.document_type,
+            period_end: corpus.period_end,
+            facts: doc.facts.len(),
+        };
+        if self.contains(&sha256) {
+            return Ok(entry);
+        }
+
+        // Write then rename, so readers never see a partial snapshot
+        let path = self.object_path(&sha256);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let partial = path.with_extension(format!("json.{}.tmp", std::process::id()));
+        let json = serde_json::to_vec(doc).map_err(|e| Error::Export(e.to_string()))?;
+        std::fs::write(&partial, json)?;
+        std::fs::rename(&partial, &path)?;
+
+        let mut line = serde_json::to_string(&entry).map_err(|e| Error::Export(e.to_string()))?;
+        line.push('\n');
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.root.join("index.ndjson"))?
+            .write_all(line.as_bytes())?;
+        Ok(entry)
+    }
+
+    pub fn get(&self, sha256: &str) -> Result<Option<Document>> {
+        let path = self.object_path(sha256);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let doc = serde_json::from_slice(&std::fs::read(&path)?)
+            .map_err(|e| Error::Parse(format!("{}: {}", path.display(), e)))?;
+        Ok(Some(doc))
+    }
+
+    /// The stored snapshot of the file at `path`, parsing and storing it
+    /// first when the store does not have it yet
+    pub fn parse_file<P: AsRef<Path>>(&self, parser: &Parser, path: P) -> Result<Document> {
+        let path = path.as_ref();
+        let sha256 = Provenance::from_bytes(&std::fs::read(path)?).sha256;
+        if let Some(doc) = self.get(&sha256)? {
+            return Ok(doc);
+        }
+        let doc = parser.parse_file(path)?;
+        self.put(&doc)?;
+        Ok(doc)
+    }
+
+    /// Every indexed snapshot, once each, in the order first stored
+    pub fn entries(&self) -> Result<Vec<SnapshotEntry>> {
+        let index = match std::fs::read_to_string(self.root.join("index.ndjson")) {
+            Ok(index) => index,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut entries: Vec<SnapshotEntry> = Vec::new();
+        let mut seen = HashSet::new();
+        for line in index.lines().filter(|l| !l.trim().is_empty()) {
+            let entry: SnapshotEntry = serde_json::from_str(line)
+                .map_err(|e| Error::Parse(format!("snapshot index: {}", e)))?;
+            if seen.insert(entry.sha256.clone()) {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Indexed snapshots matching every given criterion; the form type
+    /// matches with or without an `/A` suffix
+    pub fn find(
+        &self,
+        cik: Option<&str>,
+        form: Option<&str>,
+        period_end: Option<NaiveDate>,
+    ) -> Result<Vec<SnapshotEntry>> {
+        let base = |form: &str| form.trim().trim_end_matches("/A").to_string();
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter(|e| cik.is_none_or(|cik| e.cik.as_deref() == Some(cik)))
+            .filter(|e| {
+                form.is_none_or(|form| e.document_type.as_deref().map(base) == Some(base(form)))
+            })
+            .filter(|e| period_end.is_none_or(|end| e.period_end == Some(end)))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let filing = dir.path().join("filing.xml");
+        std::fs::write(
+            &filing,
+            r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:dei="http://xbrl.sec.gov/dei/2023">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2023-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <dei:DocumentType contextRef="c">10-K</dei:DocumentType>
+  <dei:DocumentPeriodEndDate contextRef="c">2023-12-31</dei:DocumentPeriodEndDate>
+</xbrli:xbrl>"#,
+        )
+        .unwrap();
+
+        let store = SnapshotStore::open(dir.path().join("store")).unwrap();
+        let parser = Parser::new();
+        let doc = store.parse_file(&parser, &filing).unwrap();
+        let sha256 = doc.provenance.as_ref().unwrap().sha256.clone();
+        assert!(store.contains(&sha256));
+
+        // A second read comes from the store and is not indexed twice
+        let again = store.parse_file(&parser, &filing).unwrap();
+        assert_eq!(again.facts.values, doc.facts.values);
+        store.put(&again).unwrap();
+        let entries = store.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].cik.as_deref(), Some("0000320193"));
+        assert_eq!(entries[0].facts, 2);
+
+        let end = NaiveDate::from_ymd_opt(2023, 12, 31);
+        assert_eq!(store.find(None, Some("10-K/A"), end).unwrap().len(), 1);
+        assert!(store
+            .find(Some("0000000001"), None, None)
+            .unwrap()
+            .is_empty());
+        assert!(store.get(&"0".repeat(64)).unwrap().is_none());
+    }
+}
@@ -0,0 +1,228 @@
+//! Async stream of parsed facts
+//!
+//! [`Parser::parse_stream`] reads an instance from a tokio reader and yields
+//! owned [`FactEvent`]s through a bounded channel. The parser runs on the
+//! blocking pool and waits whenever the channel is full, so a slow consumer
+//! holds parsing back instead of letting parsed facts pile up. The input is
+//! handed to the parser in blocks as it is read, through
+//! [`Parser::parse_reader_streaming`], so facts are yielded before the
+//! reader reaches its end; neither the input nor the facts, footnotes and
+//! tuples are ever held whole.
+
+use crate::model::{Context, Fact, Footnote, FootnoteArc, Tuple, Unit};
+use crate::{Error, ParseEvent, Parser, Result};
+use std::io::{self, BufRead, Read};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::Stream;
+
+/// Events parsed ahead of the consumer before the parser waits
+pub const STREAM_CAPACITY: usize = 256;
+
+/// Size of the blocks read from the async reader
+const BLOCK_BYTES: usize = 1 << 16;
+
+/// Blocks read ahead of the parser before the reader waits
+const BLOCK_CAPACITY: usize = 4;
+
+/// Owned counterpart of [`ParseEvent`]
+#[derive(Debug, Clone)]
+pub enum FactEvent {
+    Context(Context),
+    Unit(Unit),
+    Fact(Fact),
+    Footnote(Footnote),
+    FootnoteArc(FootnoteArc),
+    Tuple(Tuple),
+}
+
+impl From<ParseEvent<'_>> for FactEvent {
+    fn from(event: ParseEvent<'_>) -> Self {
+        match event {
+            ParseEvent::Context(context) => FactEvent::Context(context.clone()),
+            ParseEvent::Unit(unit) => FactEvent::Unit(unit.clone()),
+            ParseEvent::Fact(fact) => FactEvent::Fact(fact.clone()),
+            ParseEvent::Footnote(footnote) => FactEvent::Footnote(footnote.clone()),
+            ParseEvent::FootnoteArc(arc) => FactEvent::FootnoteArc(arc.clone()),
+            ParseEvent::Tuple(tuple) => FactEvent::Tuple(tuple.clone()),
+        }
+    }
+}
+
+/// Blocking [`BufRead`] over the blocks an async reader task sends
+struct BlockReader {
+    blocks: mpsc::Receiver<io::Result<Vec<u8>>>,
+    block: Vec<u8>,
+    pos: usize,
+}
+
+impl Read for BlockReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let available = self.fill_buf()?;
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for BlockReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        if self.pos == self.block.len() {
+            // A closed channel is the end of the input
+            match self.blocks.blocking_recv() {
+                Some(block) => {
+                    self.block = block?;
+                    self.pos = 0;
+                }
+                None => self.block.clear(),
+            }
+        }
+        Ok(&self.block[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.block.len());
+    }
+}
+
+impl Parser {
+    /// Parse an instance read from `reader`, yielding its events as they are
+    /// parsed. A read or parse error is the last item of the stream.
+    ///
+    /// Must be called within a Tokio runtime. Dropping the stream stops the
+    /// parser at its next event.
+    pub fn parse_stream<R>(&self, mut reader: R) -> impl Stream<Item = Result<FactEvent>> + Send
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let parser = self.clone();
+        let (tx, rx) = mpsc::channel(STREAM_CAPACITY);
+        let (block_tx, blocks) = mpsc::channel(BLOCK_CAPACITY);
+        tokio::spawn(async move {
+            loop {
+                let mut block = vec![0; BLOCK_BYTES];
+                let read = match reader.read(&mut block).await {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        block.truncate(n);
+                        Ok(block)
+                    }
+                    Err(e) => Err(e),
+                };
+                let failed = read.is_err();
+                // The parser is gone once it has finished or failed
+                if block_tx.send(read).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+        tokio::task::spawn_blocking(move || {
+            let reader = BlockReader {
+                blocks,
+                block: Vec::new(),
+                pos: 0,
+            };
+            let result = parser.parse_reader_streaming(reader, &mut |event: ParseEvent<'_>| {
+                tx.blocking_send(Ok(event.into()))
+                    .map_err(|_| Error::Parse("fact stream dropped".to_string()))
+            });
+            if let Err(e) = result {
+                let _ = tx.blocking_send(Err(e));
+            }
+        });
+        ReceiverStream::new(rx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_stream::StreamExt;
+
+    #[tokio::test]
+    async fn test_parse_stream() {
+        let mut xml = String::from(
+            r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+"#,
+        );
+        // More facts than the channel holds, so the parser has to wait
+        for i in 0..STREAM_CAPACITY * 2 {
+            xml.push_str(&format!(
+                "  <a:Cash contextRef=\"c\" unitRef=\"USD\" decimals=\"0\">{i}</a:Cash>\n"
+            ));
+        }
+        xml.push_str("</xbrli:xbrl>");
+
+        let xml = xml.into_bytes();
+        let events: Vec<_> = Parser::new()
+            .parse_stream(std::io::Cursor::new(xml.clone()))
+            .collect()
+            .await;
+        assert!(matches!(events[0], Ok(FactEvent::Context(ref c)) if c.id == "c"));
+        assert!(matches!(events[1], Ok(FactEvent::Unit(_))));
+        let facts: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                Ok(FactEvent::Fact(fact)) => Some(fact),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(facts.len(), STREAM_CAPACITY * 2);
+        assert_eq!(facts[7].value.to_string(), "7");
+
+        // Dropping the stream early releases the waiting parser
+        let mut stream = Box::pin(Parser::new().parse_stream(std::io::Cursor::new(xml.clone())));
+        assert!(stream.next().await.unwrap().is_ok());
+        drop(stream);
+
+        // A parse error ends the stream
+        let mut broken = xml[..xml.len() - "</xbrli:xbrl>".len()].to_vec();
+        broken.extend_from_slice(b"<a:Cash contextRef=\"c\" decimals=0>1</a:Cash></xbrli:xbrl>");
+        let events: Vec<_> = Parser::new()
+            .parse_stream(std::io::Cursor::new(broken))
+            .collect()
+            .await;
+        assert_eq!(events.len(), STREAM_CAPACITY * 2 + 3);
+        assert!(events.last().unwrap().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_parse_stream_before_eof() {
+        use std::time::Duration;
+        use tokio::io::AsyncWriteExt;
+
+        let head = r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">1</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <a:Cash contextRef="c" decimals="0">1</a:Cash>
+"#;
+        let (mut writer, reader) = tokio::io::duplex(1 << 16);
+        let mut stream = Box::pin(Parser::new().parse_stream(reader));
+        writer.write_all(head.as_bytes()).await.unwrap();
+
+        // The fact arrives while the writer still holds the rest
+        let mut first = Vec::new();
+        while first.len() < 2 {
+            let event = tokio::time::timeout(Duration::from_secs(10), stream.next())
+                .await
+                .expect("fact before the end of the input");
+            first.push(event.unwrap().unwrap());
+        }
+        assert!(matches!(first[0], FactEvent::Context(_)));
+        assert!(matches!(first[1], FactEvent::Fact(ref f) if f.value == "1"));
+
+        writer
+            .write_all(b"  <a:Cash contextRef=\"c\" decimals=\"0\">2</a:Cash>\n</xbrli:xbrl>")
+            .await
+            .unwrap();
+        drop(writer);
+        let rest: Vec<_> = stream.collect().await;
+        assert_eq!(rest.len(), 1, "{:?}", rest);
+        assert!(matches!(rest[0], Ok(FactEvent::Fact(ref f)) if f.value == "2"));
+    }
+}
@@ -0,0 +1,236 @@
+//! Fact value transforms applied while parsing
+//!
+//! A [`TransformPipeline`] holds [`ValueTransform`]s, each bound to the facts
+//! it targets: a concept pattern, an item type from the DTS, or numeric and
+//! non-numeric facts in general. Matching transforms rewrite a fact's lexical
+//! value in order, before it is typed, so dirty values can be normalized
+//! without a post-processing pass.
+
+use crate::options::ConceptPattern;
+use std::sync::Arc;
+
+/// Rewrites a fact's lexical value
+pub trait ValueTransform: Send + Sync {
+    /// The new value, or `None` to keep `value` unchanged
+    fn transform(&self, value: &str) -> Option<String>;
+}
+
+impl<F> ValueTransform for F
+where
+    F: Fn(&str) -> Option<String> + Send + Sync,
+{
+    fn transform(&self, value: &str) -> Option<String> {
+        self(value)
+    }
+}
+
+/// Facts a transform applies to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransformTarget {
+    All,
+    Concept(ConceptPattern),
+    /// Schema type of the concept, e.g. `monetaryItemType` or
+    /// `xbrli:sharesItemType`. Only matches when the schemas were loaded
+    /// before the facts, as [`crate::Parser::with_schema_loading`] does.
+    ItemType(String),
+    /// Facts with a unit
+    Numeric,
+    NonNumeric,
+}
+
+impl TransformTarget {
+    /// Target concepts matching a [`ConceptPattern`] string
+    pub fn concept(pattern: &str) -> Self {
+        TransformTarget::Concept(ConceptPattern::parse(pattern))
+    }
+
+    fn matches(&self, fact: &TransformFact<'_>) -> bool {
+        match self {
+            TransformTarget::All => true,
+            TransformTarget::Concept(pattern) => pattern.matches(fact.concept, fact.namespace),
+            TransformTarget::ItemType(name) => fact.item_type.is_some_and(|t| {
+                t == name || t.rsplit(':').next() == Some(name.rsplit(':').next().unwrap_or(name))
+            }),
+            TransformTarget::Numeric => fact.numeric,
+            TransformTarget::NonNumeric => !fact.numeric,
+        }
+    }
+}
+
+/// What a transform target is matched against
+#[derive(Debug, Clone, Copy)]
+pub struct TransformFact<'a> {
+    pub concept: &'a str,
+    pub namespace: Option<&'a str>,
+    /// Schema type of the concept, when known
+    pub item_type: Option<&'a str>,
+    pub numeric: bool,
+}
+
+/// Ordered transforms with their targets
+#[derive(Clone, Default)]
+pub struct TransformPipeline {
+    rules: Vec<(TransformTarget, Arc<dyn ValueTransform>)>,
+}
+
+impl std::fmt::Debug for TransformPipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.rules.iter().map(|(target, _)| target))
+            .finish()
+    }
+}
+
+impl TransformPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(
+        mut self,
+        target: TransformTarget,
+        transform: impl ValueTransform + 'static,
+    ) -> Self {
+        self.rules.push((target, Arc::new(transform)));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Whether any target needs the concept's item type
+    pub fn uses_item_types(&self) -> bool {
+        self.rules
+            .iter()
+            .any(|(target, _)| matches!(target, TransformTarget::ItemType(_)))
+    }
+
+    /// Run every matching transform over `value`; `None` if none changed it
+    pub fn apply(&self, fact: &TransformFact<'_>, value: &str) -> Option<String> {
+        let mut current: Option<String> = None;
+        for (target, transform) in &self.rules {
+            if !target.matches(fact) {
+                continue;
+            }
+            if let Some(next) = transform.transform(current.as_deref().unwrap_or(value)) {
+                current = Some(next);
+            }
+        }
+        current
+    }
+}
+
+/// Removes footnote markers such as `(1)`, `[a]`, `*` or `†` trailing a value
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StripFootnoteMarkers;
+
+impl ValueTransform for StripFootnoteMarkers {
+    fn transform(&self, value: &str) -> Option<String> {
+        let mut rest = value.trim_end();
+        loop {
+            let trimmed = rest.trim_end_matches(['*', '†', '‡', '§']).trim_end();
+            let trimmed = strip_bracketed_marker(trimmed).unwrap_or(trimmed);
+            if trimmed.len() == rest.len() {
+                break;
+            }
+            rest = trimmed.trim_end();
+        }
+        (rest.len() != value.len() && !rest.is_empty()).then(|| rest.to_string())
+    }
+}
+
+// A short `(1)` / `[a]` / `(i)` marker at the end of `value`. Longer
+// parenthesized text, such as a negative amount `(1,234)`, is kept.
+fn strip_bracketed_marker(value: &str) -> Option<&str> {
+    let (open, close) = match value.chars().last()? {
+        ')' => ('(', ')'),
+        ']' => ('[', ']'),
+        _ => return None,
+    };
+    let start = value.rfind(open)?;
+    let marker = &value[start + 1..value.len() - close.len_utf8()];
+    let is_marker = !marker.is_empty()
+        && marker.len() <= 3
+        && marker.chars().all(|c| c.is_ascii_alphanumeric());
+    // Only a marker when something precedes it
+    (is_marker && start > 0).then(|| &value[..start])
+}
+
+/// An Inline XBRL numeric format (`ixt:num-dot-decimal`,
+/// `ixt:num-comma-decimal`, `ixt:fixed-zero`, ...) applied to plain values
+#[derive(Debug, Clone)]
+pub struct IxtFormat {
+    format: String,
+}
+
+impl IxtFormat {
+    pub fn new(format: &str) -> Self {
+        Self {
+            format: format.to_string(),
+        }
+    }
+}
+
+impl ValueTransform for IxtFormat {
+    fn transform(&self, value: &str) -> Option<String> {
+        let negative = value.trim_start().starts_with('-')
+            || value.trim().starts_with('(') && value.trim().ends_with(')');
+        let number = crate::ixbrl::transform_number(value.trim(), Some(&self.format))?;
+        Some(if negative && !number.is_zero() {
+            format!("-{}", number)
+        } else {
+            number.to_string()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fact(concept: &str, numeric: bool) -> TransformFact<'_> {
+        TransformFact {
+            concept,
+            namespace: None,
+            item_type: numeric.then_some("xbrli:monetaryItemType"),
+            numeric,
+        }
+    }
+
+    #[test]
+    fn test_pipeline_targets_and_order() {
+        let pipeline = TransformPipeline::new()
+            .with(TransformTarget::NonNumeric, StripFootnoteMarkers)
+            .with(
+                TransformTarget::ItemType("monetaryItemType".to_string()),
+                IxtFormat::new("ixt:num-comma-decimal"),
+            )
+            .with(TransformTarget::concept("dei:*"), |v: &str| {
+                Some(v.to_uppercase())
+            });
+
+        assert_eq!(
+            pipeline.apply(&fact("dei:TradingSymbol", false), "aapl (1)"),
+            Some("AAPL".to_string())
+        );
+        assert_eq!(
+            pipeline.apply(&fact("us-gaap:Revenues", true), "1.234,5"),
+            Some("1234.5".to_string())
+        );
+        assert_eq!(pipeline.apply(&fact("us-gaap:Note", false), "None"), None);
+    }
+
+    #[test]
+    fn test_strip_footnote_markers() {
+        let strip = StripFootnoteMarkers;
+        assert_eq!(strip.transform("1,234 (a)").as_deref(), Some("1,234"));
+        assert_eq!(
+            strip.transform("Apple Inc.*†").as_deref(),
+            Some("Apple Inc.")
+        );
+        assert_eq!(strip.transform("Nasdaq [1]").as_deref(), Some("Nasdaq"));
+        assert_eq!(strip.transform("(1,234)"), None);
+        assert_eq!(strip.transform("(1)"), None);
+    }
+}
@@ -1,21 +1,44 @@
 // Comprehensive XBRL validation
-use crate::{model::*, Error, Result};
-use std::collections::HashSet;
+use crate::audit::CheckRecord;
+use crate::efm::{RulePack, ISO4217_NAMESPACE};
+use crate::identifier::{IdentifierRegistry, CIK_SCHEME};
+use crate::numeric::consistent;
+use crate::{model::*, Error, Result, Severity};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub enum ValidationError {
     InvalidContextRef {
         fact_index: usize,
-        context_id: u16,
+        context_id: u32,
     },
     InvalidUnitRef {
         fact_index: usize,
-        unit_id: u16,
+        unit_id: u32,
     },
     CalculationInconsistency {
         concept: String,
-        expected: f64,
-        actual: f64,
+        /// Extended link role of the summation
+        role: String,
+        context_id: String,
+        expected: Decimal,
+        actual: Decimal,
+        /// The summation's total fact
+        fact_index: usize,
+        /// Child facts that contributed to `expected`
+        contributing: Vec<usize>,
+    },
+    /// A summation whose total is reported without any of its children in
+    /// a context, or whose children are reported without the total
+    IncompleteSummation {
+        concept: String,
+        role: String,
+        context_id: String,
+        /// `true` when the children are present and the total is not
+        missing_total: bool,
+        /// The total, or the first child fact when the total is missing
+        fact_index: usize,
     },
     InvalidDataType {
         concept: String,
@@ -28,18 +51,586 @@ pub enum ValidationError {
     DuplicateId {
         id: String,
     },
+    /// A fact repeating an earlier fact's value at the same precision
+    CompleteDuplicate {
+        concept: String,
+        context_id: String,
+        value: String,
+        /// The first fact of the duplicate set
+        first_index: usize,
+        fact_index: usize,
+    },
+    /// A numeric duplicate whose value agrees with the earlier fact's when
+    /// both are rounded to the lower precision
+    ConsistentDuplicate {
+        concept: String,
+        context_id: String,
+        first_value: String,
+        value: String,
+        first_index: usize,
+        fact_index: usize,
+    },
+    /// A duplicate that contradicts the earlier fact
+    InconsistentDuplicate {
+        concept: String,
+        context_id: String,
+        first_value: String,
+        value: String,
+        first_index: usize,
+        fact_index: usize,
+    },
+    /// A context s-equal to an earlier one; see
+    /// [`Document::normalize_contexts`]
+    EquivalentContexts {
+        context_id: String,
+        equivalent_to: String,
+    },
+    /// A dei fact outside its enumeration or lexical pattern
+    InvalidDeiValue {
+        concept: String,
+        value: String,
+        expected: String,
+        fact_index: usize,
+    },
+    /// A context whose period does not line up with the reporting period
+    /// of the document
+    MisalignedPeriod {
+        context_id: String,
+        /// Canonical form of the period, see [`Period::canonical`]
+        period: String,
+        expected: String,
+        /// The first fact reported in the context
+        fact_index: usize,
+    },
+    /// A concept reported in one context in units of different kinds, or
+    /// in several currencies without an exchange rate disclosed alongside
+    MixedUnits {
+        concept: String,
+        context_id: String,
+        /// Canonical forms of the units, in order of first use
+        units: Vec<String>,
+        /// The first fact in a unit other than the first one
+        fact_index: usize,
+    },
+    /// A numeric fact whose `decimals` is implausible for what it measures
+    ImplausibleDecimals {
+        concept: String,
+        decimals: i8,
+        reason: &'static str,
+        fact_index: usize,
+    },
+    /// An alias fact whose value differs from its essence fact in the same
+    /// context and unit, see [`crate::definition`]
+    EssenceAliasInconsistent {
+        essence: String,
+        alias: String,
+        context_id: String,
+        essence_value: String,
+        alias_value: String,
+        /// The alias fact
+        fact_index: usize,
+    },
+    /// A definition arc between concepts its arcrole does not allow, see
+    /// [`crate::definition::check_definition_arcs`]
+    InvalidDefinitionArc {
+        arcrole: String,
+        from: String,
+        to: String,
+        detail: String,
+    },
+    /// A concept reported without any fact of a concept it requires
+    RequiredElementMissing {
+        concept: String,
+        required: String,
+        /// The first fact of `concept`
+        fact_index: usize,
+    },
+    /// An extended link role used in a linkbase without a roleType in the
+    /// loaded schemas that allows it there, see
+    /// [`crate::linkbase::check_role_types`]
+    UndeclaredRole {
+        role: String,
+        /// The extended link element, such as `link:presentationLink`
+        element: String,
+        /// `true` when a roleType exists but its `usedOn` omits `element`
+        declared: bool,
+    },
+    /// An arcrole used in a linkbase without an arcroleType in the loaded
+    /// schemas that allows it there
+    UndeclaredArcrole {
+        arcrole: String,
+        /// The arc element, such as `link:definitionArc`
+        element: String,
+        declared: bool,
+    },
+    /// A fact its concept's schema declaration does not allow, see
+    /// [`Document::concept_info`]
+    ConceptMismatch {
+        concept: String,
+        constraint: ConceptConstraint,
+        /// What the declaration requires and what the fact does
+        detail: String,
+        fact_index: usize,
+    },
+    /// A tuple whose children its concept's content model does not allow,
+    /// see [`Particle::match_children`]
+    TupleContentInvalid {
+        tuple: String,
+        /// The first child out of place; `None` when required content is
+        /// missing after the last child
+        child: Option<String>,
+        /// Index of `child` among the tuple's children
+        position: usize,
+        /// Elements the content model accepts at `position`
+        expected: Vec<String>,
+        /// The offending child when it is a fact
+        fact_index: Option<usize>,
+    },
+    /// An EDGAR Filer Manual requirement, see [`crate::efm`]
+    EfmViolation {
+        /// Whether the rule fails a filing or only flags it for review
+        severity: Severity,
+        /// Error code such as `efm.6.5.20.requiredDei`
+        code: &'static str,
+        /// EFM section such as `EFM 6.5.20`
+        section: &'static str,
+        message: String,
+        fact_index: Option<usize>,
+    },
+}
+
+/// Attribute of a concept declaration checked against its facts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConceptConstraint {
+    /// `xbrli:periodType` against the context period
+    PeriodType,
+    /// `xbrli:balance`, which only monetary concepts carry
+    Balance,
+    /// Abstract concepts have no facts
+    Abstract,
+    /// Nil facts need a nillable concept
+    Nillable,
+}
+
+/// Every rule identifier returned by [`ValidationError::rule`]
+pub const RULES: &[&str] = &[
+    "context-ref",
+    "unit-ref",
+    "calculation",
+    "calculation-completeness",
+    "datatype",
+    "required-element",
+    "duplicate-id",
+    "complete-duplicate",
+    "consistent-duplicate",
+    "inconsistent-duplicate",
+    "duplicate-context",
+    "dei-value",
+    "period-alignment",
+    "mixed-units",
+    "decimals",
+    "concept-metadata",
+    "tuple-content",
+    "essence-alias",
+    "requires-element",
+    "definition-arc",
+    "role-type",
+    "arcrole-type",
+    "efm",
+];
+
+impl ValidationError {
+    /// Stable rule identifier used in reports
+    pub fn rule(&self) -> &'static str {
+        match self {
+            ValidationError::InvalidContextRef { .. } => "context-ref",
+            ValidationError::InvalidUnitRef { .. } => "unit-ref",
+            ValidationError::CalculationInconsistency { .. } => "calculation",
+            ValidationError::IncompleteSummation { .. } => "calculation-completeness",
+            ValidationError::InvalidDataType { .. } => "datatype",
+            ValidationError::MissingRequiredElement { .. } => "required-element",
+            ValidationError::DuplicateId { .. } => "duplicate-id",
+            ValidationError::CompleteDuplicate { .. } => "complete-duplicate",
+            ValidationError::ConsistentDuplicate { .. } => "consistent-duplicate",
+            ValidationError::InconsistentDuplicate { .. } => "inconsistent-duplicate",
+            ValidationError::EquivalentContexts { .. } => "duplicate-context",
+            ValidationError::InvalidDeiValue { .. } => "dei-value",
+            ValidationError::MisalignedPeriod { .. } => "period-alignment",
+            ValidationError::MixedUnits { .. } => "mixed-units",
+            ValidationError::ImplausibleDecimals { .. } => "decimals",
+            ValidationError::ConceptMismatch { .. } => "concept-metadata",
+            ValidationError::TupleContentInvalid { .. } => "tuple-content",
+            ValidationError::EssenceAliasInconsistent { .. } => "essence-alias",
+            ValidationError::RequiredElementMissing { .. } => "requires-element",
+            ValidationError::InvalidDefinitionArc { .. } => "definition-arc",
+            ValidationError::UndeclaredRole { .. } => "role-type",
+            ValidationError::UndeclaredArcrole { .. } => "arcrole-type",
+            ValidationError::EfmViolation { .. } => "efm",
+        }
+    }
+
+    /// Machine-readable error code, namespaced by specification
+    pub fn code(&self) -> &'static str {
+        match self {
+            ValidationError::InvalidContextRef { .. } => "xbrl.2.1.contextRef",
+            ValidationError::InvalidUnitRef { .. } => "xbrl.2.1.unitRef",
+            ValidationError::CalculationInconsistency { .. } => "calc.1.1.summationInconsistent",
+            ValidationError::IncompleteSummation { .. } => "calc.1.1.summationIncomplete",
+            ValidationError::InvalidDataType { .. } => "xbrl.2.1.datatype",
+            ValidationError::MissingRequiredElement { .. } => "efm.6.5.20.requiredElement",
+            ValidationError::DuplicateId { .. } => "xbrl.2.1.duplicateId",
+            ValidationError::CompleteDuplicate { .. } => "oim.1.0.completeDuplicate",
+            ValidationError::ConsistentDuplicate { .. } => "oim.1.0.consistentDuplicate",
+            ValidationError::InconsistentDuplicate { .. } => "oim.1.0.inconsistentDuplicate",
+            ValidationError::EquivalentContexts { .. } => "xbrl.2.1.contextEquivalent",
+            ValidationError::InvalidDeiValue { .. } => "efm.6.5.20.deiValue",
+            ValidationError::MisalignedPeriod { .. } => "efm.6.5.19.periodAlignment",
+            ValidationError::MixedUnits { .. } => "xbrl.2.1.unitsInconsistent",
+            ValidationError::ImplausibleDecimals { .. } => "xbrl.2.1.decimalsImplausible",
+            ValidationError::ConceptMismatch { constraint, .. } => match constraint {
+                ConceptConstraint::PeriodType => "xbrl.2.1.periodType",
+                ConceptConstraint::Balance => "xbrl.2.1.balance",
+                ConceptConstraint::Abstract => "xbrl.2.1.abstract",
+                ConceptConstraint::Nillable => "xbrl.2.1.nillable",
+            },
+            ValidationError::TupleContentInvalid { .. } => "xbrl.2.1.tupleContent",
+            ValidationError::EssenceAliasInconsistent { .. } => "xbrl.2.1.essenceAliasInconsistent",
+            ValidationError::RequiredElementMissing { .. } => "xbrl.2.1.requiresElement",
+            ValidationError::InvalidDefinitionArc { .. } => "xbrl.2.1.definitionArc",
+            ValidationError::UndeclaredRole { .. } => "xbrl.2.1.roleNotDeclared",
+            ValidationError::UndeclaredArcrole { .. } => "xbrl.2.1.arcroleNotDeclared",
+            ValidationError::EfmViolation { code, .. } => code,
+        }
+    }
+
+    /// Specification section the rule is derived from
+    pub fn citation(&self) -> &'static str {
+        match self {
+            ValidationError::InvalidContextRef { .. } => "XBRL 2.1 §4.6.1",
+            ValidationError::InvalidUnitRef { .. } => "XBRL 2.1 §4.6.2",
+            ValidationError::CalculationInconsistency { .. } => "Calculations 1.1 §2.3",
+            ValidationError::IncompleteSummation { .. } => "Calculations 1.1 §2.2",
+            ValidationError::InvalidDataType { .. } => "XBRL 2.1 §5.1.1",
+            ValidationError::MissingRequiredElement { .. } => "EFM 6.5.20",
+            ValidationError::DuplicateId { .. } => "XBRL 2.1 §4.7.1",
+            ValidationError::CompleteDuplicate { .. }
+            | ValidationError::ConsistentDuplicate { .. }
+            | ValidationError::InconsistentDuplicate { .. } => "OIM 1.0 duplicate facts",
+            ValidationError::EquivalentContexts { .. } => "XBRL 2.1 §4.10",
+            ValidationError::InvalidDeiValue { .. } => "EFM 6.5.20",
+            ValidationError::MisalignedPeriod { .. } => "EFM 6.5.19",
+            ValidationError::MixedUnits { .. } => "XBRL 2.1 §4.6.2",
+            ValidationError::ImplausibleDecimals { .. } => "XBRL 2.1 §4.6.5",
+            ValidationError::ConceptMismatch { constraint, .. } => match constraint {
+                ConceptConstraint::PeriodType => "XBRL 2.1 §5.1.1.1",
+                ConceptConstraint::Balance => "XBRL 2.1 §5.1.1.2",
+                ConceptConstraint::Abstract => "XBRL 2.1 §4.6",
+                ConceptConstraint::Nillable => "XBRL 2.1 §4.6",
+            },
+            ValidationError::TupleContentInvalid { .. } => "XBRL 2.1 §4.9",
+            ValidationError::EssenceAliasInconsistent { .. } => "XBRL 2.1 §5.2.6.2.2",
+            ValidationError::RequiredElementMissing { .. } => "XBRL 2.1 §5.2.6.2.4",
+            ValidationError::InvalidDefinitionArc { .. } => "XBRL 2.1 §5.2.6.2",
+            ValidationError::UndeclaredRole { .. } => "XBRL 2.1 §5.1.3",
+            ValidationError::UndeclaredArcrole { .. } => "XBRL 2.1 §5.1.4",
+            ValidationError::EfmViolation { section, .. } => section,
+        }
+    }
+
+    /// How serious a finding of the rule is. Checks that only flag values
+    /// for review, such as redundant duplicates or heuristics, are warnings;
+    /// strict validation fails a document on its errors only.
+    pub fn severity(&self) -> Severity {
+        match self {
+            ValidationError::IncompleteSummation { .. }
+            | ValidationError::CompleteDuplicate { .. }
+            | ValidationError::ConsistentDuplicate { .. }
+            | ValidationError::EquivalentContexts { .. }
+            | ValidationError::MixedUnits { .. }
+            | ValidationError::ImplausibleDecimals { .. }
+            | ValidationError::MisalignedPeriod { .. } => Severity::Warning,
+            ValidationError::EfmViolation { severity, .. } => *severity,
+            _ => Severity::Error,
+        }
+    }
+
+    /// Index of the offending fact, when the error is about a single fact
+    pub fn fact_index(&self) -> Option<usize> {
+        match self {
+            ValidationError::InvalidContextRef { fact_index, .. }
+            | ValidationError::InvalidUnitRef { fact_index, .. }
+            | ValidationError::CalculationInconsistency { fact_index, .. }
+            | ValidationError::IncompleteSummation { fact_index, .. }
+            | ValidationError::InvalidDeiValue { fact_index, .. }
+            | ValidationError::MisalignedPeriod { fact_index, .. }
+            | ValidationError::MixedUnits { fact_index, .. }
+            | ValidationError::ImplausibleDecimals { fact_index, .. }
+            | ValidationError::ConceptMismatch { fact_index, .. }
+            | ValidationError::EssenceAliasInconsistent { fact_index, .. }
+            | ValidationError::RequiredElementMissing { fact_index, .. }
+            | ValidationError::CompleteDuplicate { fact_index, .. }
+            | ValidationError::ConsistentDuplicate { fact_index, .. }
+            | ValidationError::InconsistentDuplicate { fact_index, .. } => Some(*fact_index),
+            ValidationError::TupleContentInvalid { fact_index, .. }
+            | ValidationError::EfmViolation { fact_index, .. } => *fact_index,
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationError::InvalidContextRef {
+                fact_index,
+                context_id,
+            } => write!(
+                f,
+                "Fact {} references unknown context {}",
+                fact_index, context_id
+            ),
+            ValidationError::InvalidUnitRef {
+                fact_index,
+                unit_id,
+            } => write!(f, "Fact {} references unknown unit {}", fact_index, unit_id),
+            ValidationError::CalculationInconsistency {
+                concept,
+                role,
+                context_id,
+                expected,
+                actual,
+                ..
+            } => write!(
+                f,
+                "Calculation inconsistency for {} in context {} ({}): expected {}, found {}",
+                concept, context_id, role, expected, actual
+            ),
+            ValidationError::IncompleteSummation {
+                concept,
+                role,
+                context_id,
+                missing_total,
+                ..
+            } => write!(
+                f,
+                "Incomplete summation for {} in context {} ({}): {}",
+                concept,
+                context_id,
+                role,
+                if *missing_total {
+                    "children are reported without the total"
+                } else {
+                    "the total is reported without any children"
+                }
+            ),
+            ValidationError::InvalidDataType {
+                concept,
+                expected_type,
+                actual_value,
+            } => write!(
+                f,
+                "{} should be a {}, found {}",
+                concept, expected_type, actual_value
+            ),
+            ValidationError::MissingRequiredElement { element } => {
+                write!(f, "Missing required element: {}", element)
+            }
+            ValidationError::DuplicateId { id } => write!(f, "Duplicate id: {}", id),
+            ValidationError::CompleteDuplicate {
+                concept,
+                context_id,
+                value,
+                first_index,
+                fact_index,
+            } => write!(
+                f,
+                "Fact {} duplicates fact {}: {} = {} in context {}",
+                fact_index, first_index, concept, value, context_id
+            ),
+            ValidationError::ConsistentDuplicate {
+                concept,
+                context_id,
+                first_value,
+                value,
+                ..
+            } => write!(
+                f,
+                "Consistent duplicate {} in context {}: {} and {} agree at the lower precision",
+                concept, context_id, first_value, value
+            ),
+            ValidationError::InconsistentDuplicate {
+                concept,
+                context_id,
+                first_value,
+                value,
+                ..
+            } => write!(
+                f,
+                "Inconsistent duplicate {} in context {}: {} and {}",
+                concept, context_id, first_value, value
+            ),
+            ValidationError::EquivalentContexts {
+                context_id,
+                equivalent_to,
+            } => write!(
+                f,
+                "Context {} has the same entity, period and dimensions as context {}",
+                context_id, equivalent_to
+            ),
+            ValidationError::InvalidDeiValue {
+                concept,
+                value,
+                expected,
+                ..
+            } => write!(
+                f,
+                "Invalid value {:?} for {}: expected {}",
+                value, concept, expected
+            ),
+            ValidationError::MisalignedPeriod {
+                context_id,
+                period,
+                expected,
+                ..
+            } => write!(
+                f,
+                "Context {} ({}) is not aligned with the reporting period: expected {}",
+                context_id, period, expected
+            ),
+            ValidationError::MixedUnits {
+                concept,
+                context_id,
+                units,
+                ..
+            } => write!(
+                f,
+                "{} is reported in context {} in {}",
+                concept,
+                context_id,
+                units.join(" and ")
+            ),
+            ValidationError::ImplausibleDecimals {
+                concept,
+                decimals,
+                reason,
+                ..
+            } => write!(
+                f,
+                "{} is reported with decimals={}, which is implausible: {}",
+                concept, decimals, reason
+            ),
+            ValidationError::ConceptMismatch {
+                concept, detail, ..
+            } => write!(f, "{} {}", concept, detail),
+            ValidationError::EssenceAliasInconsistent {
+                essence,
+                alias,
+                context_id,
+                essence_value,
+                alias_value,
+                ..
+            } => write!(
+                f,
+                "{} = {} is an alias of {} = {} in context {}, but the values differ",
+                alias, alias_value, essence, essence_value, context_id
+            ),
+            ValidationError::RequiredElementMissing {
+                concept, required, ..
+            } => write!(
+                f,
+                "{} is reported, which requires {}, but no {} fact is reported",
+                concept, required, required
+            ),
+            ValidationError::InvalidDefinitionArc {
+                arcrole,
+                from,
+                to,
+                detail,
+            } => write!(
+                f,
+                "{} arc from {} to {}: {}",
+                arcrole.rsplit('/').next().unwrap_or(arcrole),
+                from,
+                to,
+                detail
+            ),
+            ValidationError::UndeclaredRole {
+                role,
+                element,
+                declared,
+            } => write!(
+                f,
+                "Role {} is used on {} but {}",
+                role,
+                element,
+                if *declared {
+                    "its roleType does not list it in usedOn"
+                } else {
+                    "no loaded schema declares it"
+                }
+            ),
+            ValidationError::UndeclaredArcrole {
+                arcrole,
+                element,
+                declared,
+            } => write!(
+                f,
+                "Arcrole {} is used on {} but {}",
+                arcrole,
+                element,
+                if *declared {
+                    "its arcroleType does not list it in usedOn"
+                } else {
+                    "no loaded schema declares it"
+                }
+            ),
+            ValidationError::TupleContentInvalid {
+                tuple,
+                child,
+                position,
+                expected,
+                ..
+            } => {
+                match child {
+                    Some(child) => {
+                        write!(f, "Tuple {} has {} at position {}", tuple, child, position)?
+                    }
+                    None => write!(f, "Tuple {} ends after {} children", tuple, position)?,
+                }
+                if expected.is_empty() {
+                    write!(f, " but its content model allows no further children")
+                } else {
+                    write!(
+                        f,
+                        " but its content model expects {}",
+                        expected.join(" or ")
+                    )
+                }
+            }
+            ValidationError::EfmViolation {
+                section, message, ..
+            } => write!(f, "{}: {}", section, message),
+        }
+    }
 }
 
 pub struct XbrlValidator {
     strict_mode: bool,
-    #[allow(dead_code)]
     check_calculations: bool,
     check_duplicates: bool,
+    redundant_duplicates: bool,
     check_contexts: bool,
     check_units: bool,
-    #[allow(dead_code)]
+    check_decimals: bool,
+    check_periods: bool,
+    check_definitions: bool,
+    check_role_types: bool,
+    allow_forever: bool,
+    rule_pack: Option<Arc<RulePack>>,
+    identifiers: Option<Arc<IdentifierRegistry>>,
     check_datatypes: bool,
-    decimal_tolerance: f64,
+    #[cfg(feature = "parallel")]
+    pool: Option<Arc<rayon::ThreadPool>>,
 }
 
 impl Default for XbrlValidator {
@@ -48,10 +639,19 @@ impl Default for XbrlValidator {
             strict_mode: false,
             check_calculations: true,
             check_duplicates: true,
+            redundant_duplicates: false,
             check_contexts: true,
             check_units: true,
+            check_decimals: true,
+            check_periods: false,
+            check_definitions: true,
+            check_role_types: true,
+            allow_forever: true,
+            rule_pack: None,
+            identifiers: None,
             check_datatypes: true,
-            decimal_tolerance: 0.01,
+            #[cfg(feature = "parallel")]
+            pool: None,
         }
     }
 }
@@ -66,46 +666,351 @@ impl XbrlValidator {
         self
     }
 
-    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
-        self.decimal_tolerance = tolerance;
+    /// Also run the EDGAR Filer Manual checks of [`crate::efm`]
+    pub fn with_efm(self) -> Self {
+        self.with_rule_pack(RulePack::shared())
+    }
+
+    /// Run the EDGAR Filer Manual checks with a precompiled pack
+    pub fn with_rule_pack(mut self, pack: Arc<RulePack>) -> Self {
+        self.rule_pack = Some(pack);
         self
     }
 
-    pub fn validate(&self, doc: &mut Document) -> Result<()> {
-        let mut validation_errors = Vec::new();
+    /// Check entity identifiers against the validators of their schemes
+    pub fn with_identifier_schemes(mut self, registry: Arc<IdentifierRegistry>) -> Self {
+        self.identifiers = Some(registry);
+        self
+    }
 
-        // Context validation
-        if self.check_contexts {
-            validation_errors.extend(self.validate_contexts(doc));
-        }
+    /// Run the checks in `pool` instead of rayon's global pool, see
+    /// [`crate::pool`]
+    #[cfg(feature = "parallel")]
+    pub fn with_thread_pool(mut self, pool: Arc<rayon::ThreadPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
 
-        // Unit validation
-        if self.check_units {
-            validation_errors.extend(self.validate_units(doc));
-        }
+    /// Also report complete and consistent duplicates, as warnings. Inline
+    /// XBRL repeats a fact wherever its value is shown, so these are off by
+    /// default; only inconsistent duplicates are errors.
+    pub fn with_redundant_duplicates(mut self) -> Self {
+        self.redundant_duplicates = true;
+        self
+    }
 
-        // Fact validation
-        validation_errors.extend(self.validate_facts(doc));
+    /// Cross-check reporting-period contexts against the DEI reporting
+    /// period, see [`crate::dei::validate_period_alignment`]. EDGAR
+    /// filings only; other filings have no DEI period to align to.
+    pub fn with_period_alignment(mut self) -> Self {
+        self.check_periods = true;
+        self
+    }
 
-        // Duplicate detection
-        if self.check_duplicates {
-            validation_errors.extend(self.check_duplicate_facts(doc));
-        }
+    /// Report contexts with an `xbrli:forever` period, which some
+    /// regulators do not accept
+    pub fn forbid_forever(mut self) -> Self {
+        self.allow_forever = false;
+        self
+    }
 
-        // Return error in strict mode if any validation errors
-        if self.strict_mode && !validation_errors.is_empty() {
-            return Err(Error::Validation(format!(
-                "Validation failed with {} errors",
-                validation_errors.len()
-            )));
+    /// Values are compared at their reported `decimals`, see
+    /// [`crate::numeric`]; a fixed tolerance no longer applies
+    #[deprecated(note = "values are compared at their reported decimals")]
+    pub fn with_tolerance(self, _tolerance: f64) -> Self {
+        self
+    }
+
+    pub fn validate(&self, doc: &Document) -> Result<()> {
+        let errors = self
+            .check(doc)
+            .iter()
+            .filter(|e| e.severity() == Severity::Error)
+            .count();
+
+        // Return error in strict mode if any finding is an error
+        if self.strict_mode && errors > 0 {
+            return Err(Error::validation(
+                "validation.failed",
+                format!("Validation failed with {} errors", errors),
+            ));
         }
 
         Ok(())
     }
 
+    /// Run all enabled checks and return every finding
+    pub fn check(&self, doc: &Document) -> Vec<ValidationError> {
+        self.check_audited(doc).0
+    }
+
+    /// Run all checks, returning the findings and a record of every check
+    /// in run order, disabled ones included
+    pub fn check_audited(&self, doc: &Document) -> (Vec<ValidationError>, Vec<CheckRecord>) {
+        type Check = fn(&XbrlValidator, &Document) -> Vec<ValidationError>;
+        let facts = doc.facts.len();
+        let run = |name: &'static str,
+                   rules: &'static [&'static str],
+                   enabled: bool,
+                   check: Check,
+                   inputs: Vec<(&'static str, usize)>| {
+            let start = std::time::Instant::now();
+            let errors = if enabled {
+                check(self, doc)
+            } else {
+                Vec::new()
+            };
+            let record = CheckRecord {
+                check: name,
+                rules,
+                executed: enabled,
+                duration_us: start.elapsed().as_micros() as u64,
+                inputs,
+                findings: errors.len(),
+            };
+            (errors, record)
+        };
+        let contexts = || {
+            run(
+                "contexts",
+                &[
+                    "duplicate-id",
+                    "duplicate-context",
+                    "required-element",
+                    "datatype",
+                ],
+                self.check_contexts,
+                Self::validate_contexts,
+                vec![("contexts", doc.contexts.len())],
+            )
+        };
+        let units = || {
+            run(
+                "units",
+                &["duplicate-id", "required-element"],
+                self.check_units,
+                Self::validate_units,
+                vec![("units", doc.units.len())],
+            )
+        };
+        let fact_refs = || {
+            run(
+                "fact-references",
+                &["context-ref", "unit-ref", "datatype"],
+                true,
+                Self::validate_facts,
+                vec![("facts", facts)],
+            )
+        };
+        let duplicates = || {
+            run(
+                "duplicate-facts",
+                &[
+                    "complete-duplicate",
+                    "consistent-duplicate",
+                    "inconsistent-duplicate",
+                ],
+                self.check_duplicates,
+                Self::check_duplicate_facts,
+                vec![("facts", facts)],
+            )
+        };
+        let mixed_units = || {
+            run(
+                "mixed-units",
+                &["mixed-units"],
+                self.check_units,
+                Self::check_mixed_units,
+                vec![("units", doc.units.len()), ("facts", facts)],
+            )
+        };
+        let calculations = || {
+            run(
+                "calculations",
+                &["calculation", "calculation-completeness"],
+                self.check_calculations,
+                Self::validate_calculations,
+                vec![
+                    ("calculation_links", doc.calculation_links.len()),
+                    ("facts", facts),
+                ],
+            )
+        };
+        let decimals = || {
+            run(
+                "decimals",
+                &["decimals"],
+                self.check_decimals,
+                Self::check_decimals,
+                vec![("facts", facts)],
+            )
+        };
+
+        let concepts = || {
+            run(
+                "concept-metadata",
+                &["concept-metadata"],
+                self.check_datatypes,
+                Self::check_concepts,
+                vec![("schemas", doc.schemas.len()), ("facts", facts)],
+            )
+        };
+
+        let tuples = || {
+            run(
+                "tuples",
+                &["tuple-content"],
+                self.check_datatypes,
+                Self::check_tuples,
+                vec![("schemas", doc.schemas.len()), ("tuples", doc.tuples.len())],
+            )
+        };
+
+        let definitions = || {
+            run(
+                "definitions",
+                &["essence-alias", "requires-element", "definition-arc"],
+                self.check_definitions,
+                |_, doc| {
+                    let mut errors = crate::definition::check_essence_alias(doc);
+                    errors.extend(crate::definition::check_requires_element(doc));
+                    errors.extend(crate::definition::check_definition_arcs(doc));
+                    errors
+                },
+                vec![
+                    ("definition_links", doc.definition_links.len()),
+                    ("facts", facts),
+                ],
+            )
+        };
+
+        let role_types = || {
+            run(
+                "role-types",
+                &["role-type", "arcrole-type"],
+                self.check_role_types,
+                |_, doc| crate::linkbase::check_role_types(doc),
+                vec![
+                    ("role_types", doc.role_types.len()),
+                    ("arcrole_types", doc.arcrole_types.len()),
+                ],
+            )
+        };
+
+        let periods = || {
+            run(
+                "period-alignment",
+                &["period-alignment"],
+                self.check_periods,
+                |_, doc| crate::dei::validate_period_alignment(doc),
+                vec![("contexts", doc.contexts.len()), ("facts", facts)],
+            )
+        };
+
+        let efm = || {
+            run(
+                "efm",
+                &["efm"],
+                self.rule_pack.is_some(),
+                |v, doc| {
+                    v.rule_pack
+                        .as_ref()
+                        .map(|p| p.check(doc))
+                        .unwrap_or_default()
+                },
+                vec![("contexts", doc.contexts.len()), ("facts", facts)],
+            )
+        };
+
+        #[cfg(feature = "parallel")]
+        let (
+            ((contexts, units), (facts, (duplicates, mixed_units))),
+            (
+                (calculations, periods),
+                ((decimals, (concepts, tuples)), (definitions, (role_types, efm))),
+            ),
+        ) = crate::pool::install(self.pool.as_deref(), || {
+            rayon::join(
+                || {
+                    rayon::join(
+                        || rayon::join(contexts, units),
+                        || rayon::join(fact_refs, || rayon::join(duplicates, mixed_units)),
+                    )
+                },
+                || {
+                    rayon::join(
+                        || rayon::join(calculations, periods),
+                        || {
+                            rayon::join(
+                                || rayon::join(decimals, || rayon::join(concepts, tuples)),
+                                || rayon::join(definitions, || rayon::join(role_types, efm)),
+                            )
+                        },
+                    )
+                },
+            )
+        });
+
+        #[cfg(not(feature = "parallel"))]
+        let (
+            contexts,
+            units,
+            facts,
+            duplicates,
+            mixed_units,
+            calculations,
+            periods,
+            decimals,
+            concepts,
+            tuples,
+            definitions,
+            role_types,
+            efm,
+        ) = (
+            contexts(),
+            units(),
+            fact_refs(),
+            duplicates(),
+            mixed_units(),
+            calculations(),
+            periods(),
+            decimals(),
+            concepts(),
+            tuples(),
+            definitions(),
+            role_types(),
+            efm(),
+        );
+
+        // Concatenate in a fixed order so the output never depends on scheduling
+        let (errors, records): (Vec<_>, Vec<_>) = [
+            contexts,
+            units,
+            facts,
+            duplicates,
+            mixed_units,
+            calculations,
+            periods,
+            decimals,
+            concepts,
+            tuples,
+            definitions,
+            role_types,
+            efm,
+        ]
+        .into_iter()
+        .unzip();
+        (errors.concat(), records)
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict_mode
+    }
+
     fn validate_contexts(&self, doc: &Document) -> Vec<ValidationError> {
         let mut errors = Vec::new();
         let mut context_ids = HashSet::new();
+        let mut equivalent: HashMap<ContextKey, &str> = HashMap::new();
 
         for ctx in &doc.contexts {
             // Check for duplicate context IDs
@@ -113,6 +1018,13 @@ impl XbrlValidator {
                 errors.push(ValidationError::DuplicateId {
                     id: ctx.id.to_string(),
                 });
+            } else if let Some(first) = equivalent.get(&ctx.equivalence_key()) {
+                errors.push(ValidationError::EquivalentContexts {
+                    context_id: ctx.id.clone(),
+                    equivalent_to: first.to_string(),
+                });
+            } else {
+                equivalent.insert(ctx.equivalence_key(), &ctx.id);
             }
 
             // Validate entity identifier
@@ -123,16 +1035,37 @@ impl XbrlValidator {
             }
 
             // Validate period
-            if let Period::Duration { start, end } = &ctx.period {
-                if start > end {
-                    errors.push(ValidationError::InvalidDataType {
-                        concept: format!("context_{}", ctx.id),
-                        expected_type: "valid period".to_string(),
-                        actual_value: format!("start {} > end {}", start, end),
-                    });
-                }
+            // Date-only ends are the end of their day, so a duration from a
+            // date to the same date is one day long
+            let invalid = match &ctx.period {
+                Period::Duration { start, end, .. } => match ctx.period.duration() {
+                    Some(duration) if duration <= chrono::Duration::zero() => {
+                        Some(format!("start {} > end {}", start, end))
+                    }
+                    Some(_) => None,
+                    None => Some(format!("start {} or end {} is not a date", start, end)),
+                },
+                Period::Instant { date, .. } => ctx
+                    .period
+                    .effective_end()
+                    .is_none()
+                    .then(|| format!("instant {} is not a date", date)),
+                Period::Forever => None,
+            };
+            if let Some(actual_value) = invalid {
+                errors.push(ValidationError::InvalidDataType {
+                    concept: format!("context_{}", ctx.id),
+                    expected_type: "valid period".to_string(),
+                    actual_value,
+                });
             }
         }
+        if !self.allow_forever {
+            errors.extend(forever_contexts(doc));
+        }
+        if let Some(registry) = &self.identifiers {
+            errors.extend(registry.check(doc));
+        }
 
         errors
     }
@@ -207,28 +1140,376 @@ impl XbrlValidator {
             }
         }
 
+        // Fraction items have a nonZeroDecimal denominator
+        for fact in doc.iter_facts() {
+            if let FactValue::Fraction { denominator, .. } = fact.value() {
+                if denominator.is_zero() {
+                    errors.push(ValidationError::InvalidDataType {
+                        concept: fact.concept().to_string(),
+                        expected_type: "fraction with a nonzero denominator".to_string(),
+                        actual_value: fact.value().to_string(),
+                    });
+                }
+            }
+        }
+
         errors
     }
 
-    fn check_duplicate_facts(&self, doc: &Document) -> Vec<ValidationError> {
+    fn validate_calculations(&self, doc: &Document) -> Vec<ValidationError> {
+        let mut errors = crate::calculation::check_calculations(doc);
+        errors.extend(crate::calculation::check_completeness(doc));
+        errors
+    }
+
+    /// Concepts reported in one context in several units. Different
+    /// currencies are accepted when the context also discloses an exchange
+    /// rate: a fact of an `...ExchangeRate...` concept, or one in a unit
+    /// dividing one of the currencies by another.
+    fn check_mixed_units(&self, doc: &Document) -> Vec<ValidationError> {
+        let is_currency = |m: &Measure| m.in_namespace(ISO4217_NAMESPACE, "iso4217");
+
+        // Units per concept and context, and the currency pairs of the
+        // exchange rates disclosed in each context
+        let mut reported: HashMap<(u32, u32), Vec<(String, usize)>> = HashMap::new();
+        let mut rates: HashMap<u32, Vec<Option<(&str, &str)>>> = HashMap::new();
+        for fact in doc.iter_facts() {
+            let (Some(unit), Some(_)) = (fact.unit(), fact.value().as_decimal()) else {
+                continue;
+            };
+            let context = doc.facts.context_ids[fact.index()];
+            let local = fact.concept().rsplit(':').next().unwrap_or_default();
+            if let UnitType::Divide {
+                numerator,
+                denominator,
+            } = &unit.unit_type
+            {
+                if let ([n], [d]) = (numerator.as_slice(), denominator.as_slice()) {
+                    if is_currency(n) && is_currency(d) {
+                        rates
+                            .entry(context)
+                            .or_default()
+                            .push(Some((n.name.as_str(), d.name.as_str())));
+                    }
+                }
+            } else if local.contains("ExchangeRate") {
+                // A rate without currency units covers every pair
+                rates.entry(context).or_default().push(None);
+            }
+            let units = reported
+                .entry((doc.facts.concept_ids[fact.index()], context))
+                .or_default();
+            let canonical = unit.canonical();
+            if !units.iter().any(|(u, _)| *u == canonical) {
+                units.push((canonical, fact.index()));
+            }
+        }
+
+        let mut groups: Vec<_> = reported.into_iter().filter(|(_, u)| u.len() > 1).collect();
+        groups.sort_by_key(|(_, units)| units[1].1);
         let mut errors = Vec::new();
-        let mut fact_keys = HashSet::new();
+        for ((_, context), units) in groups {
+            let fact = doc.fact(units[1].1).expect("indexes come from iter_facts");
+            let currencies: Option<Vec<&str>> = units
+                .iter()
+                .map(|(_, i)| match &doc.fact(*i)?.unit()?.unit_type {
+                    UnitType::Simple(m) if m.len() == 1 && is_currency(&m[0]) => {
+                        Some(m[0].name.as_str())
+                    }
+                    _ => None,
+                })
+                .collect();
+            let disclosed = currencies.is_some_and(|currencies| {
+                rates.get(&context).is_some_and(|rates| {
+                    rates.iter().any(|rate| match rate {
+                        None => true,
+                        Some((n, d)) => currencies.contains(n) && currencies.contains(d),
+                    })
+                })
+            });
+            if disclosed {
+                continue;
+            }
+            errors.push(ValidationError::MixedUnits {
+                concept: fact.concept().to_string(),
+                context_id: fact.context().map(|c| c.id.clone()).unwrap_or_default(),
+                units: units.into_iter().map(|(u, _)| u).collect(),
+                fact_index: fact.index(),
+            });
+        }
+        errors
+    }
 
-        for i in 0..doc.facts.len() {
-            if i < doc.facts.concept_ids.len() && i < doc.facts.context_ids.len() {
-                let key = (doc.facts.concept_ids[i], doc.facts.context_ids[i]);
-                if !fact_keys.insert(key) && self.strict_mode {
-                    errors.push(ValidationError::DuplicateId {
-                        id: format!("Duplicate fact at index {}", i),
-                    });
+    /// Numeric facts whose `decimals` cannot describe their value: share
+    /// counts in the millions with fractional precision, per-share amounts
+    /// rounded to hundreds or more, and values that round to zero
+    /// Facts against the declarations of their concepts in the loaded
+    /// schemas; concepts no schema declares are skipped
+    fn check_concepts(&self, doc: &Document) -> Vec<ValidationError> {
+        let is_currency = |m: &Measure| m.in_namespace(ISO4217_NAMESPACE, "iso4217");
+        let mut errors = Vec::new();
+        for fact in doc.iter_facts() {
+            let Some(element) = fact.concept_info() else {
+                continue;
+            };
+            let mut mismatch = |constraint, detail: String| {
+                errors.push(ValidationError::ConceptMismatch {
+                    concept: fact.concept().to_string(),
+                    constraint,
+                    detail,
+                    fact_index: fact.index(),
+                })
+            };
+
+            if element.abstract_element {
+                mismatch(
+                    ConceptConstraint::Abstract,
+                    "is abstract and cannot be reported".to_string(),
+                );
+                continue;
+            }
+            if fact.value().is_nil() && !element.nillable {
+                mismatch(
+                    ConceptConstraint::Nillable,
+                    "is not nillable but is reported nil".to_string(),
+                );
+            }
+            if let (Some(period_type), Some(context)) = (&element.period_type, fact.context()) {
+                let instant = matches!(context.period, Period::Instant { .. });
+                if (period_type == "instant") != instant {
+                    mismatch(
+                        ConceptConstraint::PeriodType,
+                        format!(
+                            "has periodType {} but context {} has a{} period",
+                            period_type,
+                            context.id,
+                            if instant { "n instant" } else { " duration" }
+                        ),
+                    );
                 }
             }
+            if let (Some(balance), Some(unit)) = (&element.balance, fact.unit()) {
+                let monetary = matches!(&unit.unit_type, UnitType::Simple(m) if m.len() == 1 && is_currency(&m[0]));
+                if !monetary {
+                    mismatch(
+                        ConceptConstraint::Balance,
+                        format!(
+                            "has balance {} but is reported in {}, not a currency",
+                            balance,
+                            unit.canonical()
+                        ),
+                    );
+                }
+            }
+        }
+        errors
+    }
+
+    /// Children of every tuple against its concept's content model in the
+    /// loaded schemas; tuples no schema declares are skipped
+    fn check_tuples(&self, doc: &Document) -> Vec<ValidationError> {
+        let qname = |namespace: &str, name: &str| match doc
+            .namespaces
+            .iter()
+            .filter(|(_, ns)| *ns == namespace)
+            .map(|(prefix, _)| prefix)
+            .min()
+        {
+            Some(prefix) if !prefix.is_empty() => format!("{}:{}", prefix, name),
+            Some(_) => name.to_string(),
+            None => format!("{{{}}}{}", namespace, name),
+        };
+        let mut errors = Vec::new();
+        for (path, tuple) in doc.tuples_iter() {
+            let Some(model) = doc.content_model(&tuple.name) else {
+                continue;
+            };
+            let names: Vec<&str> = tuple
+                .facts
+                .iter()
+                .map(|child| match child {
+                    FactOrTuple::Fact(fact) => fact.concept.as_str(),
+                    FactOrTuple::Tuple(nested) => nested.name.as_str(),
+                })
+                .collect();
+            let Some(children) = names
+                .iter()
+                .map(|name| doc.resolve_qname(name))
+                .collect::<Option<Vec<_>>>()
+            else {
+                continue;
+            };
+            let Err(mismatch) = model.match_children(&children) else {
+                continue;
+            };
+            // Facts of the tuple are stored in the order of its children
+            let fact_index = match tuple.facts.get(mismatch.position) {
+                Some(FactOrTuple::Fact(_)) => {
+                    let preceding = tuple.facts[..mismatch.position]
+                        .iter()
+                        .filter(|child| matches!(child, FactOrTuple::Fact(_)))
+                        .count();
+                    doc.tuple_facts(&path).nth(preceding).map(|f| f.index())
+                }
+                _ => None,
+            };
+            errors.push(ValidationError::TupleContentInvalid {
+                tuple: tuple.name.clone(),
+                child: names.get(mismatch.position).map(|name| name.to_string()),
+                position: mismatch.position,
+                expected: mismatch
+                    .expected
+                    .iter()
+                    .map(|(namespace, name)| qname(namespace, name))
+                    .collect(),
+                fact_index,
+            });
+        }
+        errors
+    }
+
+    fn check_decimals(&self, doc: &Document) -> Vec<ValidationError> {
+        let is_shares = |m: &[Measure]| m.len() == 1 && m[0].name == "shares";
+        let mut errors = Vec::new();
+        for fact in doc.iter_facts() {
+            let (Some(unit), Some(decimals), Some(value)) =
+                (fact.unit(), fact.decimals(), fact.value().as_decimal())
+            else {
+                continue;
+            };
+            let magnitude = value.abs();
+            let reason = match &unit.unit_type {
+                UnitType::Simple(m)
+                    if is_shares(m) && decimals > 0 && magnitude >= Decimal::from(1_000_000) =>
+                {
+                    "share counts in the millions are not reported to fractions of a share"
+                }
+                UnitType::Divide { denominator, .. } if is_shares(denominator) && decimals < -2 => {
+                    "per-share amounts are not rounded to hundreds or more"
+                }
+                // Half a rounding step or more would round up, not to zero
+                _ if decimals < 0
+                    && !magnitude.is_zero()
+                    && decimals >= -28
+                    && magnitude * Decimal::TWO
+                        < Decimal::from_i128_with_scale(10i128.pow(-decimals as u32), 0) =>
+                {
+                    "the value rounds to zero at this precision"
+                }
+                _ => continue,
+            };
+            errors.push(ValidationError::ImplausibleDecimals {
+                concept: fact.concept().to_string(),
+                decimals,
+                reason,
+                fact_index: fact.index(),
+            });
+        }
+        errors
+    }
+
+    /// Duplicates per OIM: facts with the same concept, s-equal contexts
+    /// and equal units. Each is compared with the first of its group;
+    /// complete and consistent duplicates are only reported when asked for
+    /// with [`XbrlValidator::with_redundant_duplicates`].
+    fn check_duplicate_facts(&self, doc: &Document) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        let context_keys: Vec<ContextKey> =
+            doc.contexts.iter().map(Context::equivalence_key).collect();
+        let unit_keys: Vec<String> = doc.units.iter().map(Unit::canonical).collect();
+        // Duplicates share a parent: the root or the same tuple
+        type Key<'k> = (u32, &'k ContextKey, Option<&'k str>, &'k [u32]);
+        let mut first: HashMap<Key, usize> = HashMap::new();
+
+        for fact in doc.iter_facts() {
+            let i = fact.index();
+            // Unresolved references are reported by the fact checks
+            let Some(context) = context_keys.get(doc.facts.context_ids[i] as usize) else {
+                continue;
+            };
+            let unit = match doc.facts.unit_ids[i] {
+                0 => None,
+                id => match unit_keys.get(id as usize - 1) {
+                    Some(unit) => Some(unit.as_str()),
+                    None => continue,
+                },
+            };
+            let key = (
+                doc.facts.concept_ids[i],
+                context,
+                unit,
+                doc.facts.tuple_paths[i].as_slice(),
+            );
+            let Some(&j) = first.get(&key) else {
+                first.insert(key, i);
+                continue;
+            };
+
+            let (a, b) = (&doc.facts.values[j], &doc.facts.values[i]);
+            let (da, db) = (doc.facts.decimals[j], doc.facts.decimals[i]);
+            let numbers = unit.and(a.as_decimal()).zip(b.as_decimal());
+            let class = if a.is_nil() || b.is_nil() {
+                if a.is_nil() && b.is_nil() {
+                    Duplicate::Complete
+                } else {
+                    Duplicate::Inconsistent
+                }
+            } else if a == b && da == db {
+                Duplicate::Complete
+            } else if let Some((x, y)) = numbers {
+                if consistent(x, da, y, db) {
+                    Duplicate::Consistent
+                } else {
+                    Duplicate::Inconsistent
+                }
+            } else {
+                Duplicate::Inconsistent
+            };
+
+            let concept = fact.concept().to_string();
+            let context_id = fact.context().map(|c| c.id.clone()).unwrap_or_default();
+            errors.push(match class {
+                Duplicate::Complete if self.redundant_duplicates => {
+                    ValidationError::CompleteDuplicate {
+                        concept,
+                        context_id,
+                        value: b.to_string(),
+                        first_index: j,
+                        fact_index: i,
+                    }
+                }
+                Duplicate::Consistent if self.redundant_duplicates => {
+                    ValidationError::ConsistentDuplicate {
+                        concept,
+                        context_id,
+                        first_value: a.to_string(),
+                        value: b.to_string(),
+                        first_index: j,
+                        fact_index: i,
+                    }
+                }
+                Duplicate::Inconsistent => ValidationError::InconsistentDuplicate {
+                    concept,
+                    context_id,
+                    first_value: a.to_string(),
+                    value: b.to_string(),
+                    first_index: j,
+                    fact_index: i,
+                },
+                _ => continue,
+            });
         }
 
         errors
     }
 }
 
+enum Duplicate {
+    Complete,
+    Consistent,
+    Inconsistent,
+}
+
 // Type alias for validation rules
 type ValidationRule = Box<dyn Fn(&Document) -> Vec<ValidationError>>;
 
@@ -284,10 +1565,26 @@ impl ValidationContext {
     }
 }
 
+/// One finding per context with an `xbrli:forever` period
+pub fn forever_contexts(doc: &Document) -> Vec<ValidationError> {
+    doc.contexts
+        .iter()
+        .filter(|c| c.period.is_forever())
+        .map(|c| ValidationError::InvalidDataType {
+            concept: format!("context_{}", c.id),
+            expected_type: "dated period".to_string(),
+            actual_value: "forever".to_string(),
+        })
+        .collect()
+}
+
 // SEC EDGAR specific validation rules
 pub fn sec_validation_rules(doc: &Document) -> Vec<ValidationError> {
     let mut errors = Vec::new();
 
+    // EDGAR filings report every fact as of an instant or for a duration
+    errors.extend(forever_contexts(doc));
+
     // Check for required DEI contexts
     let mut has_current_period = false;
     let mut has_entity_info = false;
@@ -302,20 +1599,17 @@ pub fn sec_validation_rules(doc: &Document) -> Vec<ValidationError> {
             has_current_period = true;
         }
 
-        // Validate CIK format (10 digits)
-        if ctx.entity.scheme.contains("sec.gov/CIK") {
+        if IdentifierRegistry::shared()
+            .find(&ctx.entity.scheme)
+            .is_some_and(|s| s.scheme == CIK_SCHEME)
+        {
             has_entity_info = true;
-            let cik = &ctx.entity.identifier;
-            if cik.len() != 10 || !cik.chars().all(|c| c.is_ascii_digit()) {
-                errors.push(ValidationError::InvalidDataType {
-                    concept: "CIK".to_string(),
-                    expected_type: "10-digit number".to_string(),
-                    actual_value: cik.to_string(),
-                });
-            }
         }
     }
 
+    // Identifier formats, CIK among them, by declared scheme
+    errors.extend(IdentifierRegistry::shared().check(doc));
+
     // Check for DEI elements in facts
     for i in 0..doc.facts.concept_ids.len() {
         if i < doc.concept_names.len() {
@@ -348,6 +1642,14 @@ pub fn sec_validation_rules(doc: &Document) -> Vec<ValidationError> {
         });
     }
 
+    // EFM checks, dei value enumerations included
+    errors.extend(crate::efm::check_efm(doc));
+
+    // Cover-page tagging must be complete for each share class
+    errors.extend(crate::dei::validate_share_classes(doc));
+    errors.extend(crate::dei::validate_fiscal_focus(doc));
+    errors.extend(crate::dei::validate_period_alignment(doc));
+
     // Validate segment reporting if present
     for ctx in &doc.contexts {
         if let Some(segment) = &ctx.entity.segment {
@@ -365,7 +1667,7 @@ pub fn sec_validation_rules(doc: &Document) -> Vec<ValidationError> {
     }
 
     // Validate calculation consistency for monetary items
-    let mut monetary_facts: Vec<(usize, f64)> = Vec::new();
+    let mut monetary_facts: Vec<(usize, Decimal)> = Vec::new();
     for i in 0..doc.facts.len() {
         if i < doc.facts.values.len() {
             if let FactValue::Decimal(val) = &doc.facts.values[i] {
@@ -386,15 +1688,8 @@ pub fn sec_validation_rules(doc: &Document) -> Vec<ValidationError> {
 
     // Basic calculation validation - check for reasonable values
     for (idx, value) in monetary_facts {
-        if value.is_nan() || value.is_infinite() {
-            errors.push(ValidationError::InvalidDataType {
-                concept: format!("fact_{}", idx),
-                expected_type: "valid monetary amount".to_string(),
-                actual_value: format!("{}", value),
-            });
-        }
         // Check for suspiciously large values (> $10 trillion)
-        if value.abs() > 10_000_000_000_000.0 {
+        if value.abs() > Decimal::from(10_000_000_000_000i64) {
             errors.push(ValidationError::InvalidDataType {
                 concept: format!("fact_{}", idx),
                 expected_type: "reasonable monetary amount".to_string(),
@@ -418,7 +1713,7 @@ pub fn ifrs_validation_rules(doc: &Document) -> Vec<ValidationError> {
     for ctx in &doc.contexts {
         // Check for reporting period
         match &ctx.period {
-            Period::Duration { start, end: _ } => {
+            Period::Duration { start, .. } => {
                 has_reporting_period = true;
                 // IFRS requires comparative information
                 if start.contains("PY")
@@ -428,10 +1723,8 @@ pub fn ifrs_validation_rules(doc: &Document) -> Vec<ValidationError> {
                     has_comparative_period = true;
                 }
             }
-            Period::Instant { date } => {
-                if !date.is_empty() {
-                    has_reporting_period = true;
-                }
+            Period::Instant { date, .. } if !date.is_empty() => {
+                has_reporting_period = true;
             }
             _ => {}
         }
@@ -442,6 +1735,8 @@ pub fn ifrs_validation_rules(doc: &Document) -> Vec<ValidationError> {
         }
     }
 
+    errors.extend(IdentifierRegistry::shared().check(doc));
+
     // Required contexts validation
     if !has_reporting_period {
         errors.push(ValidationError::MissingRequiredElement {
@@ -599,3 +1894,536 @@ pub fn ifrs_validation_rules(doc: &Document) -> Vec<ValidationError> {
 
     errors
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(id: &str, identifier: &str) -> Context {
+        Context {
+            id: id.to_string(),
+            entity: Entity {
+                identifier: identifier.to_string(),
+                scheme: "http://www.sec.gov/CIK".to_string(),
+                segment: None,
+            },
+            period: Period::instant("2023-09-30"),
+            scenario: None,
+        }
+    }
+
+    #[test]
+    fn test_check_order_is_deterministic() {
+        let mut doc = Document::new();
+        doc.contexts.push(context("c1", ""));
+        doc.contexts.push(context("c1", "0000320193"));
+        doc.units.push(Unit {
+            id: "u1".to_string(),
+            unit_type: UnitType::Simple(Vec::new()),
+        });
+        let concept = doc.intern_concept("us-gaap:Assets");
+        for i in 0..100 {
+            doc.facts.push(
+                concept,
+                i % 4,
+                0,
+                FactValue::Decimal(Decimal::ONE),
+                None,
+                None,
+            );
+        }
+
+        let validator = XbrlValidator::new().strict().with_redundant_duplicates();
+        let rules: Vec<_> = validator
+            .check(&doc)
+            .iter()
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(
+            rules[0],
+            "Missing required element: Entity identifier for context c1"
+        );
+        assert_eq!(rules[1], "Duplicate id: c1");
+        assert_eq!(rules[2], "Missing required element: Measures for unit u1");
+        assert_eq!(rules[3], "Fact 2 references unknown context 2");
+        assert_eq!(
+            rules.last().unwrap(),
+            "Fact 97 duplicates fact 1: us-gaap:Assets = 1 in context c1"
+        );
+
+        for _ in 0..20 {
+            let again: Vec<_> = validator
+                .check(&doc)
+                .iter()
+                .map(|e| e.to_string())
+                .collect();
+            assert_eq!(again, rules);
+        }
+    }
+
+    #[test]
+    fn test_forever_contexts() {
+        let mut doc = Document::new();
+        for id in ["f1", "f2"] {
+            let mut ctx = context(id, "0000320193");
+            ctx.period = Period::Forever;
+            doc.contexts.push(ctx);
+        }
+        let concept = doc.intern_concept("dei:EntityRegistrantName");
+        for context_id in 0..2 {
+            doc.facts.push(
+                concept,
+                context_id,
+                0,
+                FactValue::Text("Apple Inc.".into()),
+                None,
+                None,
+            );
+        }
+
+        let errors = XbrlValidator::new()
+            .strict()
+            .with_redundant_duplicates()
+            .check(&doc);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(
+            errors[0].to_string(),
+            "Context f2 has the same entity, period and dimensions as context f1"
+        );
+        assert_eq!(
+            errors[1].to_string(),
+            "Fact 1 duplicates fact 0: dei:EntityRegistrantName = Apple Inc. in context f2"
+        );
+
+        let errors = XbrlValidator::new().forbid_forever().check(&doc);
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            errors[1].to_string(),
+            "context_f1 should be a dated period, found forever"
+        );
+    }
+
+    #[test]
+    fn test_malformed_period_dates() {
+        let mut doc = Document::new();
+        for (id, period) in [
+            ("i", Period::instant("2023-02-30")),
+            (
+                "d",
+                Period::between("2023-01-01", "2023-12-31T25:00:00".to_string()),
+            ),
+            (
+                "ok",
+                Period::between("2023-01-01", "2023-12-31".to_string()),
+            ),
+        ] {
+            let mut ctx = context(id, "0000320193");
+            ctx.period = period;
+            doc.contexts.push(ctx);
+        }
+
+        let invalid: Vec<_> = XbrlValidator::new()
+            .check(&doc)
+            .into_iter()
+            .filter_map(|e| match e {
+                ValidationError::InvalidDataType {
+                    concept,
+                    actual_value,
+                    ..
+                } => Some((concept, actual_value)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            invalid,
+            [
+                (
+                    "context_i".to_string(),
+                    "instant 2023-02-30 is not a date".to_string()
+                ),
+                (
+                    "context_d".to_string(),
+                    "start 2023-01-01 or end 2023-12-31T25:00:00 is not a date".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_implausible_decimals() {
+        let doc = crate::Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:iso4217="http://www.xbrl.org/2003/iso4217" xmlns:a="http://example.com/a">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <xbrli:unit id="shares"><xbrli:measure>xbrli:shares</xbrli:measure></xbrli:unit>
+  <xbrli:unit id="USDPerShare"><xbrli:divide><xbrli:unitNumerator><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unitNumerator>
+    <xbrli:unitDenominator><xbrli:measure>xbrli:shares</xbrli:measure></xbrli:unitDenominator></xbrli:divide></xbrli:unit>
+  <a:SharesOutstanding contextRef="c" unitRef="shares" decimals="2">15550061000</a:SharesOutstanding>
+  <a:TreasuryShares contextRef="c" unitRef="shares" decimals="2">1250.5</a:TreasuryShares>
+  <a:EarningsPerShare contextRef="c" unitRef="USDPerShare" decimals="-9">6.16</a:EarningsPerShare>
+  <a:Dividends contextRef="c" unitRef="USDPerShare" decimals="2">0.96</a:Dividends>
+  <a:Fees contextRef="c" unitRef="USD" decimals="-6">250000</a:Fees>
+  <a:Revenue contextRef="c" unitRef="USD" decimals="-6">383285000000</a:Revenue>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+        let errors = XbrlValidator::new().check(&doc);
+        let flagged: Vec<_> = errors
+            .iter()
+            .filter(|e| e.rule() == "decimals")
+            .filter_map(|e| e.fact_index())
+            .collect();
+        assert_eq!(flagged, [0, 2, 4]);
+        assert_eq!(
+            errors[0].to_string(),
+            "a:SharesOutstanding is reported with decimals=2, which is implausible: share counts in the millions are not reported to fractions of a share"
+        );
+
+        // Severity comes from the rule: strict validation keeps these
+        // heuristics as warnings and fails only on the missing DocumentType
+        let severities = |result: &crate::ValidationResult, rule: &str| -> Vec<Severity> {
+            result
+                .findings
+                .iter()
+                .filter(|f| f.rule == rule)
+                .map(|f| f.severity)
+                .collect()
+        };
+        let result = crate::Validator::sec_edgar().validate(&doc).unwrap();
+        assert_eq!(severities(&result, "decimals"), [Severity::Warning; 3]);
+        assert_eq!(severities(&result, "efm"), [Severity::Error]);
+        assert!(!result.is_valid);
+        let result = crate::Validator::new().validate(&doc).unwrap();
+        assert!(result
+            .findings
+            .iter()
+            .all(|f| f.severity == Severity::Warning));
+        assert!(result.is_valid);
+    }
+
+    #[test]
+    fn test_mixed_units() {
+        let doc = crate::Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:iso4217="http://www.xbrl.org/2003/iso4217" xmlns:a="http://example.com/a">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:context id="fx"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2022-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <xbrli:unit id="EUR"><xbrli:measure>iso4217:EUR</xbrli:measure></xbrli:unit>
+  <xbrli:unit id="shares"><xbrli:measure>xbrli:shares</xbrli:measure></xbrli:unit>
+  <xbrli:unit id="EURPerUSD"><xbrli:divide><xbrli:unitNumerator><xbrli:measure>iso4217:EUR</xbrli:measure></xbrli:unitNumerator>
+    <xbrli:unitDenominator><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unitDenominator></xbrli:divide></xbrli:unit>
+  <a:Cash contextRef="c" unitRef="USD" decimals="0">100</a:Cash>
+  <a:Cash contextRef="c" unitRef="EUR" decimals="0">92</a:Cash>
+  <a:Equity contextRef="c" unitRef="USD" decimals="0">100</a:Equity>
+  <a:Equity contextRef="c" unitRef="shares" decimals="0">100</a:Equity>
+  <a:Cash contextRef="fx" unitRef="USD" decimals="0">100</a:Cash>
+  <a:Cash contextRef="fx" unitRef="EUR" decimals="0">90</a:Cash>
+  <a:Rate contextRef="fx" unitRef="EURPerUSD" decimals="4">0.9</a:Rate>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+        let errors: Vec<_> = XbrlValidator::new()
+            .check(&doc)
+            .into_iter()
+            .filter(|e| e.rule() == "mixed-units")
+            .collect();
+        let flagged: Vec<_> = errors.iter().filter_map(|e| e.fact_index()).collect();
+        assert_eq!(flagged, [1, 3]);
+        assert_eq!(
+            errors[0].to_string(),
+            "a:Cash is reported in context c in iso4217:USD and iso4217:EUR"
+        );
+    }
+
+    #[test]
+    fn test_zero_denominator() {
+        let doc = crate::Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="pure"><xbrli:measure>xbrli:pure</xbrli:measure></xbrli:unit>
+  <a:Ratio contextRef="c" unitRef="pure"><xbrli:numerator>1</xbrli:numerator><xbrli:denominator>3</xbrli:denominator></a:Ratio>
+  <a:Share contextRef="c" unitRef="pure"><xbrli:numerator>1</xbrli:numerator><xbrli:denominator>0</xbrli:denominator></a:Share>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+        let errors: Vec<_> = XbrlValidator::new()
+            .check(&doc)
+            .into_iter()
+            .filter(|e| e.rule() == "datatype")
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("a:Share") && errors[0].contains("1/0"));
+    }
+
+    #[test]
+    fn test_concept_metadata() {
+        let mut doc = crate::Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xmlns:iso4217="http://www.xbrl.org/2003/iso4217" xmlns:a="http://example.com/a">
+  <xbrli:context id="i"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:context id="d"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2023-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <xbrli:unit id="shares"><xbrli:measure>xbrli:shares</xbrli:measure></xbrli:unit>
+  <a:Assets contextRef="i" unitRef="USD" decimals="0">100</a:Assets>
+  <a:Assets contextRef="d" unitRef="shares" decimals="0">100</a:Assets>
+  <a:Revenue contextRef="d" unitRef="USD" xsi:nil="true"/>
+  <a:StatementAbstract contextRef="d">x</a:StatementAbstract>
+  <a:Undeclared contextRef="d">x</a:Undeclared>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+        let element = |name: &str, period_type: &str, balance: Option<&str>| SchemaElement {
+            name: name.to_string(),
+            id: None,
+            element_type: "xbrli:monetaryItemType".to_string(),
+            substitution_group: Some("xbrli:item".to_string()),
+            period_type: Some(period_type.to_string()),
+            balance: balance.map(str::to_string),
+            abstract_element: name.ends_with("Abstract"),
+            nillable: false,
+            content: None,
+        };
+        doc.schemas.push(Schema {
+            target_namespace: "http://example.com/a".to_string(),
+            elements: [
+                element("Assets", "instant", Some("debit")),
+                element("Revenue", "duration", Some("credit")),
+                element("StatementAbstract", "duration", None),
+            ]
+            .into_iter()
+            .map(|e| (e.name.clone(), e))
+            .collect(),
+            types: HashMap::new(),
+            imports: Vec::new(),
+            role_types: Vec::new(),
+            arcrole_types: Vec::new(),
+            groups: HashMap::new(),
+            location: None,
+        });
+        assert_eq!(
+            doc.concept_info("a:Assets").unwrap().balance.as_deref(),
+            Some("debit")
+        );
+        assert!(doc.concept_info("a:Undeclared").is_none());
+
+        let errors: Vec<_> = XbrlValidator::new()
+            .check(&doc)
+            .into_iter()
+            .filter(|e| e.rule() == "concept-metadata")
+            .collect();
+        let found: Vec<_> = errors
+            .iter()
+            .map(|e| (e.fact_index().unwrap(), e.code()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (1, "xbrl.2.1.periodType"),
+                (1, "xbrl.2.1.balance"),
+                (2, "xbrl.2.1.nillable"),
+                (3, "xbrl.2.1.abstract"),
+            ]
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "a:Assets has periodType instant but context d has a duration period"
+        );
+    }
+
+    #[test]
+    fn test_tuple_content() {
+        let mut doc = crate::Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:a="http://example.com/a">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <a:Address><a:Street contextRef="c">1 Main</a:Street><a:City contextRef="c">X</a:City></a:Address>
+  <a:Address><a:Street contextRef="c">1 Main</a:Street><a:Street contextRef="c">Unit 2</a:Street><a:Zip contextRef="c">1</a:Zip><a:Country contextRef="c">Y</a:Country></a:Address>
+  <a:Address><a:City contextRef="c">X</a:City></a:Address>
+  <a:Address><a:Street contextRef="c">1 Main</a:Street><a:City contextRef="c">X</a:City><a:Country contextRef="c">Y</a:Country><a:Country contextRef="c">Z</a:Country></a:Address>
+  <a:Address><a:Street contextRef="c">1 Main</a:Street></a:Address>
+  <a:Contact><a:Email contextRef="c">e</a:Email><a:Phone contextRef="c">p</a:Phone></a:Contact>
+  <a:Contact><a:Email contextRef="c">e</a:Email></a:Contact>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+        doc.add_schema(
+            crate::dts::parse_schema(
+                br#"<xsd:schema xmlns:xsd="http://www.w3.org/2001/XMLSchema" xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:ex="http://example.com/a" targetNamespace="http://example.com/a">
+  <xsd:element name="Street" type="xbrli:stringItemType" substitutionGroup="xbrli:item" xbrli:periodType="instant"/>
+  <xsd:element name="City" type="xbrli:stringItemType" substitutionGroup="xbrli:item" xbrli:periodType="instant"/>
+  <xsd:element name="Zip" type="xbrli:stringItemType" substitutionGroup="xbrli:item" xbrli:periodType="instant"/>
+  <xsd:element name="Country" type="xbrli:stringItemType" substitutionGroup="xbrli:item" xbrli:periodType="instant"/>
+  <xsd:element name="Phone" type="xbrli:stringItemType" substitutionGroup="xbrli:item" xbrli:periodType="instant"/>
+  <xsd:element name="Email" type="xbrli:stringItemType" substitutionGroup="xbrli:item" xbrli:periodType="instant"/>
+  <xsd:element name="Address" substitutionGroup="xbrli:tuple">
+    <xsd:complexType><xsd:sequence>
+      <xsd:element ref="ex:Street" maxOccurs="unbounded"/>
+      <xsd:choice><xsd:element ref="ex:City"/><xsd:element ref="ex:Zip"/></xsd:choice>
+      <xsd:element ref="ex:Country" minOccurs="0"/>
+    </xsd:sequence></xsd:complexType>
+  </xsd:element>
+  <xsd:element name="Contact" substitutionGroup="xbrli:tuple">
+    <xsd:complexType><xsd:all>
+      <xsd:element ref="ex:Phone"/>
+      <xsd:element ref="ex:Email" minOccurs="0"/>
+    </xsd:all></xsd:complexType>
+  </xsd:element>
+</xsd:schema>"#,
+            )
+            .unwrap(),
+        );
+
+        let errors: Vec<_> = XbrlValidator::new()
+            .check(&doc)
+            .into_iter()
+            .filter(|e| e.rule() == "tuple-content")
+            .collect();
+        let found: Vec<_> = errors
+            .iter()
+            .map(|e| match e {
+                ValidationError::TupleContentInvalid {
+                    child,
+                    position,
+                    expected,
+                    fact_index,
+                    ..
+                } => (child.as_deref(), *position, expected.clone(), *fact_index),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (Some("a:City"), 0, vec!["a:Street".to_string()], Some(6)),
+                (Some("a:Country"), 3, vec![], Some(10)),
+                (
+                    None,
+                    1,
+                    vec![
+                        "a:Street".to_string(),
+                        "a:City".to_string(),
+                        "a:Zip".to_string()
+                    ],
+                    None
+                ),
+                (None, 1, vec!["a:Phone".to_string()], None),
+            ]
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "Tuple a:Address has a:City at position 0 but its content model expects a:Street"
+        );
+        assert_eq!(errors[0].code(), "xbrl.2.1.tupleContent");
+    }
+
+    #[test]
+    fn test_duplicate_classification() {
+        let doc = crate::Parser::new()
+            .parse_str(
+                r#"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:iso4217="http://www.xbrl.org/2003/iso4217" xmlns:a="http://example.com/a">
+  <xbrli:context id="c"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2023-12-31</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:context id="c2"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:instant>2024-01-01T00:00:00</xbrli:instant></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <xbrli:unit id="EUR"><xbrli:measure>iso4217:EUR</xbrli:measure></xbrli:unit>
+  <a:Cash contextRef="c" unitRef="USD" decimals="0">1234567</a:Cash>
+  <a:Cash contextRef="c2" unitRef="USD" decimals="0">1234567</a:Cash>
+  <a:Cash contextRef="c" unitRef="USD" decimals="-3">1235000</a:Cash>
+  <a:Cash contextRef="c" unitRef="USD" decimals="-3">1234000</a:Cash>
+  <a:Cash contextRef="c" unitRef="EUR" decimals="0">1</a:Cash>
+  <a:Name contextRef="c">Example</a:Name>
+  <a:Name contextRef="c">Other</a:Name>
+</xbrli:xbrl>"#,
+            )
+            .unwrap();
+        let duplicates = |validator: XbrlValidator| {
+            validator
+                .check(&doc)
+                .iter()
+                .filter(|e| e.rule().ends_with("-duplicate"))
+                .map(|e| (e.rule(), e.fact_index().unwrap()))
+                .collect::<Vec<_>>()
+        };
+
+        // The EUR fact has a different unit and no duplicate
+        assert_eq!(
+            duplicates(XbrlValidator::new().with_redundant_duplicates()),
+            [
+                ("complete-duplicate", 1),
+                ("consistent-duplicate", 2),
+                ("inconsistent-duplicate", 3),
+                ("inconsistent-duplicate", 6),
+            ]
+        );
+        let errors = XbrlValidator::new().check(&doc);
+        let inconsistent: Vec<_> = errors
+            .iter()
+            .filter(|e| e.rule() == "inconsistent-duplicate")
+            .map(|e| e.to_string())
+            .collect();
+        assert_eq!(
+            inconsistent,
+            [
+                "Inconsistent duplicate a:Cash in context c: 1234567 and 1234000",
+                "Inconsistent duplicate a:Name in context c: Example and Other",
+            ]
+        );
+        assert_eq!(duplicates(XbrlValidator::new()).len(), 2);
+        assert_eq!(duplicates(XbrlValidator::new().strict()).len(), 2);
+    }
+
+    #[test]
+    fn test_repeated_inline_facts_pass_sec_edgar() {
+        // Inline XBRL shows revenue in the highlights, in millions, and in
+        // the statement, in thousands
+        let doc = crate::InlineParser::new()
+            .parse_bytes(
+                br#"<html xmlns="http://www.w3.org/1999/xhtml" xmlns:ix="http://www.xbrl.org/2013/inlineXBRL"
+      xmlns:ixt="http://www.xbrl.org/inlineXBRL/transformation/2020-02-12"
+      xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:iso4217="http://www.xbrl.org/2003/iso4217"
+      xmlns:dei="http://xbrl.sec.gov/dei/2023" xmlns:us-gaap="http://fasb.org/us-gaap/2023">
+<body>
+<div style="display:none"><ix:header><ix:resources>
+  <xbrli:context id="Q3"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000320193</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2023-07-02</xbrli:startDate><xbrli:endDate>2023-09-30</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:unit id="usd"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+</ix:resources></ix:header></div>
+<p><ix:nonNumeric name="dei:DocumentType" contextRef="Q3">8-K</ix:nonNumeric>
+  <ix:nonNumeric name="dei:AmendmentFlag" contextRef="Q3">false</ix:nonNumeric>
+  <ix:nonNumeric name="dei:DocumentPeriodEndDate" contextRef="Q3">2023-09-30</ix:nonNumeric>
+  <ix:nonNumeric name="dei:EntityRegistrantName" contextRef="Q3">Apple Inc.</ix:nonNumeric>
+  <ix:nonNumeric name="dei:EntityCentralIndexKey" contextRef="Q3">0000320193</ix:nonNumeric></p>
+<p>Revenue was $<ix:nonFraction name="us-gaap:Revenues" contextRef="Q3" unitRef="usd" decimals="-6" scale="6" format="ixt:num-dot-decimal">89,498</ix:nonFraction> million.</p>
+<p>Revenue was $<ix:nonFraction name="us-gaap:Revenues" contextRef="Q3" unitRef="usd" decimals="-6" scale="6" format="ixt:num-dot-decimal">89,498</ix:nonFraction> million.</p>
+<td><ix:nonFraction name="us-gaap:Revenues" contextRef="Q3" unitRef="usd" decimals="-3" scale="3" format="ixt:num-dot-decimal">89,498,000</ix:nonFraction></td>
+</body></html>"#,
+            )
+            .unwrap();
+        assert_eq!(doc.facts.len(), 8);
+
+        let result = crate::Validator::sec_edgar().validate(&doc).unwrap();
+        assert!(result.is_valid, "{:?}", result.errors);
+        assert!(result.warnings.is_empty(), "{:?}", result.warnings);
+
+        // Asked for, the repeats are warnings that still pass
+        let errors = XbrlValidator::new()
+            .strict()
+            .with_efm()
+            .with_redundant_duplicates();
+        let rules: Vec<_> = errors.check(&doc).iter().map(|e| e.rule()).collect();
+        assert_eq!(rules, ["complete-duplicate", "consistent-duplicate"]);
+        assert!(errors.validate(&doc).is_ok());
+    }
+}
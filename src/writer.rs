@@ -0,0 +1,465 @@
+//! XBRL 2.1 instance serialization
+//!
+//! [`InstanceWriter`] writes a [`Document`] back out as an instance: the
+//! schemaRef, contexts, units, facts (with contextRef, unitRef, decimals and
+//! id), tuples and a footnote link. Parsing the output yields the same facts,
+//! so the writer doubles as a round-trip check for the parser.
+
+use crate::model::*;
+use crate::Result;
+use quick_xml::escape::escape;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+/// Namespaces every instance may need, declared unless the document binds
+/// the prefix itself
+const STANDARD_NAMESPACES: &[(&str, &str)] = &[
+    ("xbrli", "http://www.xbrl.org/2003/instance"),
+    ("link", "http://www.xbrl.org/2003/linkbase"),
+    ("xlink", "http://www.w3.org/1999/xlink"),
+    ("xsi", "http://www.w3.org/2001/XMLSchema-instance"),
+    ("xbrldi", "http://xbrl.org/2006/xbrldi"),
+    ("iso4217", "http://www.xbrl.org/2003/iso4217"),
+];
+
+const FOOTNOTE_LINK_ROLE: &str = "http://www.xbrl.org/2003/role/link";
+const FOOTNOTE_ROLE: &str = "http://www.xbrl.org/2003/role/footnote";
+
+#[derive(Debug, Clone, Default)]
+pub struct InstanceWriter {
+    namespaces: BTreeMap<String, String>,
+}
+
+impl InstanceWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare a namespace on the root in addition to the document's own
+    pub fn with_namespace(mut self, prefix: &str, uri: &str) -> Self {
+        self.namespaces.insert(prefix.to_string(), uri.to_string());
+        self
+    }
+
+    pub fn write<W: Write>(&self, doc: &Document, out: &mut W) -> Result<()> {
+        writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        write!(out, "<xbrli:xbrl")?;
+        for (prefix, uri) in self.namespaces(doc) {
+            write!(out, "\n  xmlns:{}=\"{}\"", prefix, escape(uri.as_str()))?;
+        }
+        writeln!(out, ">")?;
+
+        if let Some(schema_ref) = &doc.schema_ref {
+            writeln!(
+                out,
+                r#"  <link:schemaRef xlink:type="simple" xlink:href="{}"/>"#,
+                escape(schema_ref.as_str())
+            )?;
+        }
+        for context in &doc.contexts {
+            write_context(out, context)?;
+        }
+        for unit in &doc.units {
+            write_unit(out, unit)?;
+        }
+        // Tuple contents are written with their tuples
+        for fact in doc.iter_facts().filter(|f| f.tuple_path().is_empty()) {
+            write_fact(out, &fact)?;
+        }
+        for tuple in &doc.tuples {
+            write_tuple(out, tuple, 1)?;
+        }
+        write_footnotes(out, doc)?;
+
+        writeln!(out, "</xbrli:xbrl>")?;
+        Ok(())
+    }
+
+    pub fn to_xml(&self, doc: &Document) -> Result<String> {
+        let mut out = Vec::new();
+        self.write(doc, &mut out)?;
+        Ok(String::from_utf8_lossy(&out).into_owned())
+    }
+
+    pub fn write_file<P: AsRef<Path>>(&self, doc: &Document, path: P) -> Result<()> {
+        let mut out = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write(doc, &mut out)?;
+        out.flush()?;
+        Ok(())
+    }
+
+    fn namespaces(&self, doc: &Document) -> BTreeMap<String, String> {
+        let mut namespaces: BTreeMap<String, String> = STANDARD_NAMESPACES
+            .iter()
+            .map(|(prefix, uri)| (prefix.to_string(), uri.to_string()))
+            .collect();
+        namespaces.extend(
+            doc.namespaces
+                .iter()
+                .filter(|(prefix, _)| !prefix.is_empty())
+                .map(|(prefix, uri)| (prefix.clone(), uri.clone())),
+        );
+        namespaces.extend(self.namespaces.clone());
+        namespaces
+    }
+}
+
+impl Document {
+    /// Serialize as an XBRL 2.1 instance
+    pub fn to_xml(&self) -> Result<String> {
+        InstanceWriter::new().to_xml(self)
+    }
+}
+
+// Element content that was kept as markup by the parser is written back
+// verbatim; plain text is escaped
+fn content(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.trim_start().starts_with('<') {
+        value.into()
+    } else {
+        escape(value)
+    }
+}
+
+fn write_context<W: Write>(out: &mut W, context: &Context) -> Result<()> {
+    writeln!(
+        out,
+        r#"  <xbrli:context id="{}">"#,
+        escape(context.id.as_str())
+    )?;
+    writeln!(out, "    <xbrli:entity>")?;
+    writeln!(
+        out,
+        r#"      <xbrli:identifier scheme="{}">{}</xbrli:identifier>"#,
+        escape(context.entity.scheme.as_str()),
+        escape(context.entity.identifier.as_str())
+    )?;
+    if let Some(segment) = &context.entity.segment {
+        write_members(
+            out,
+            "segment",
+            &segment.explicit_members,
+            &segment.typed_members,
+        )?;
+    }
+    writeln!(out, "    </xbrli:entity>")?;
+
+    match &context.period {
+        Period::Instant { date, .. } => writeln!(
+            out,
+            "    <xbrli:period><xbrli:instant>{}</xbrli:instant></xbrli:period>",
+            escape(date.as_str())
+        )?,
+        Period::Duration { start, end, .. } => writeln!(
+            out,
+            "    <xbrli:period><xbrli:startDate>{}</xbrli:startDate><xbrli:endDate>{}</xbrli:endDate></xbrli:period>",
+            escape(start.as_str()),
+            escape(end.as_str())
+        )?,
+        Period::Forever => writeln!(out, "    <xbrli:period><xbrli:forever/></xbrli:period>")?,
+    }
+
+    if let Some(scenario) = &context.scenario {
+        write_members(
+            out,
+            "scenario",
+            &scenario.explicit_members,
+            &scenario.typed_members,
+        )?;
+    }
+    writeln!(out, "  </xbrli:context>")?;
+    Ok(())
+}
+
+fn write_members<W: Write>(
+    out: &mut W,
+    container: &str,
+    explicit: &[DimensionMember],
+    typed: &[TypedMember],
+) -> Result<()> {
+    writeln!(out, "      <xbrli:{}>", container)?;
+    for member in explicit {
+        writeln!(
+            out,
+            r#"        <xbrldi:explicitMember dimension="{}">{}</xbrldi:explicitMember>"#,
+            escape(member.dimension.as_str()),
+            escape(member.member.as_str())
+        )?;
+    }
+    for member in typed {
+        writeln!(
+            out,
+            r#"        <xbrldi:typedMember dimension="{}">{}</xbrldi:typedMember>"#,
+            escape(member.dimension.as_str()),
+            content(&member.value)
+        )?;
+    }
+    writeln!(out, "      </xbrli:{}>", container)?;
+    Ok(())
+}
+
+fn write_unit<W: Write>(out: &mut W, unit: &Unit) -> Result<()> {
+    fn measures<W: Write>(out: &mut W, measures: &[Measure], indent: &str) -> Result<()> {
+        for measure in measures {
+            writeln!(
+                out,
+                "{}<xbrli:measure>{}</xbrli:measure>",
+                indent,
+                escape(measure.qname().as_str())
+            )?;
+        }
+        Ok(())
+    }
+
+    writeln!(out, r#"  <xbrli:unit id="{}">"#, escape(unit.id.as_str()))?;
+    match &unit.unit_type {
+        UnitType::Simple(simple) | UnitType::Multiply(simple) => measures(out, simple, "    ")?,
+        UnitType::Divide {
+            numerator,
+            denominator,
+        } => {
+            writeln!(out, "    <xbrli:divide>")?;
+            writeln!(out, "      <xbrli:unitNumerator>")?;
+            measures(out, numerator, "        ")?;
+            writeln!(out, "      </xbrli:unitNumerator>")?;
+            writeln!(out, "      <xbrli:unitDenominator>")?;
+            measures(out, denominator, "        ")?;
+            writeln!(out, "      </xbrli:unitDenominator>")?;
+            writeln!(out, "    </xbrli:divide>")?;
+        }
+    }
+    writeln!(out, "  </xbrli:unit>")?;
+    Ok(())
+}
+
+fn write_fact<W: Write>(out: &mut W, fact: &FactView<'_>) -> Result<()> {
+    let concept = fact.concept();
+    write!(out, "  <{}", concept)?;
+    if let Some(context) = fact.context() {
+        write!(out, r#" contextRef="{}""#, escape(context.id.as_str()))?;
+    }
+    if let Some(unit) = fact.unit() {
+        write!(out, r#" unitRef="{}""#, escape(unit.id.as_str()))?;
+        // Numeric items need decimals or precision; unknown means exact.
+        // Fractions take neither.
+        if !fact.value().is_nil() && !matches!(fact.value(), FactValue::Fraction { .. }) {
+            match fact.decimals() {
+                Some(decimals) => write!(out, r#" decimals="{}""#, decimals)?,
+                None => write!(out, r#" decimals="INF""#)?,
+            }
+        }
+    }
+    if let Some(id) = fact.id() {
+        write!(out, r#" id="{}""#, escape(id))?;
+    }
+    if let FactValue::Fraction {
+        numerator,
+        denominator,
+    } = fact.value()
+    {
+        writeln!(
+            out,
+            "><xbrli:numerator>{}</xbrli:numerator><xbrli:denominator>{}</xbrli:denominator></{}>",
+            numerator, denominator, concept
+        )?;
+    } else if fact.value().is_nil() {
+        writeln!(out, r#" xsi:nil="true"/>"#)?;
+    } else {
+        writeln!(
+            out,
+            ">{}</{}>",
+            escape(fact.value().to_string().as_str()),
+            concept
+        )?;
+    }
+    Ok(())
+}
+
+fn write_tuple<W: Write>(out: &mut W, tuple: &Tuple, depth: usize) -> Result<()> {
+    let indent = "  ".repeat(depth);
+    write!(out, "{}<{}", indent, tuple.name)?;
+    if let Some(id) = &tuple.id {
+        write!(out, r#" id="{}""#, escape(id.as_str()))?;
+    }
+    writeln!(out, ">")?;
+    for child in &tuple.facts {
+        match child {
+            FactOrTuple::Tuple(nested) => write_tuple(out, nested, depth + 1)?,
+            FactOrTuple::Fact(fact) => {
+                write!(
+                    out,
+                    r#"{}  <{} contextRef="{}""#,
+                    indent,
+                    fact.concept,
+                    escape(fact.context_ref.as_str())
+                )?;
+                if let Some(unit) = &fact.unit_ref {
+                    write!(out, r#" unitRef="{}""#, escape(unit.as_str()))?;
+                }
+                match (fact.decimals, fact.precision) {
+                    (Some(decimals), _) => write!(out, r#" decimals="{}""#, decimals)?,
+                    (None, Some(precision)) => write!(out, r#" precision="{}""#, precision)?,
+                    (None, None) if fact.unit_ref.is_some() && !fact.nil => {
+                        write!(out, r#" decimals="INF""#)?
+                    }
+                    (None, None) => {}
+                }
+                if let Some(id) = &fact.id {
+                    write!(out, r#" id="{}""#, escape(id.as_str()))?;
+                }
+                if fact.nil {
+                    writeln!(out, r#" xsi:nil="true"/>"#)?;
+                } else {
+                    writeln!(out, ">{}</{}>", escape(fact.value.as_str()), fact.concept)?;
+                }
+            }
+        }
+    }
+    writeln!(out, "{}</{}>", indent, tuple.name)?;
+    Ok(())
+}
+
+fn write_footnotes<W: Write>(out: &mut W, doc: &Document) -> Result<()> {
+    // Arcs as parsed, plus standard ones for references added without an arc
+    let mut arcs: Vec<(&str, &FootnoteTarget, &str, f32)> = doc
+        .footnote_arcs
+        .iter()
+        .map(|a| (a.from.as_str(), &a.to, a.arcrole.as_str(), a.order))
+        .collect();
+    let targets: Vec<FootnoteTarget> = doc
+        .footnotes
+        .iter()
+        .map(|f| FootnoteTarget::Footnote(f.id.clone()))
+        .collect();
+    for (footnote, target) in doc.footnotes.iter().zip(&targets) {
+        for fact_id in &footnote.fact_refs {
+            if !arcs
+                .iter()
+                .any(|(from, to, ..)| from == fact_id && *to == target)
+            {
+                arcs.push((fact_id, target, FACT_FOOTNOTE_ARCROLE, 1.0));
+            }
+        }
+    }
+    if doc.footnotes.is_empty() && arcs.is_empty() {
+        return Ok(());
+    }
+    writeln!(
+        out,
+        r#"  <link:footnoteLink xlink:type="extended" xlink:role="{}">"#,
+        FOOTNOTE_LINK_ROLE
+    )?;
+
+    // One locator per fact an arc starts or ends at, labelled by position
+    let mut fact_labels: BTreeMap<&str, String> = BTreeMap::new();
+    for (from, to, ..) in &arcs {
+        let to = match to {
+            FootnoteTarget::Fact(id) => Some(id.as_str()),
+            FootnoteTarget::Footnote(_) => None,
+        };
+        for fact_id in std::iter::once(*from).chain(to) {
+            let next = format!("fact_{}", fact_labels.len() + 1);
+            fact_labels.entry(fact_id).or_insert(next);
+        }
+    }
+    for (fact_id, label) in &fact_labels {
+        writeln!(
+            out,
+            r##"    <link:loc xlink:type="locator" xlink:href="#{}" xlink:label="{}"/>"##,
+            escape(fact_id),
+            label
+        )?;
+    }
+
+    let mut footnote_labels: BTreeMap<&str, String> = BTreeMap::new();
+    for (i, footnote) in doc.footnotes.iter().enumerate() {
+        let label = format!("footnote_{}", i + 1);
+        writeln!(
+            out,
+            r#"    <link:footnote xlink:type="resource" xlink:label="{}" xlink:role="{}" xml:lang="{}" id="{}">{}</link:footnote>"#,
+            label,
+            escape(footnote.role.as_deref().unwrap_or(FOOTNOTE_ROLE)),
+            escape(footnote.lang.as_deref().unwrap_or("en")),
+            escape(footnote.id.as_str()),
+            content(&footnote.content)
+        )?;
+        footnote_labels.entry(footnote.id.as_str()).or_insert(label);
+    }
+
+    for (from, to, arcrole, order) in arcs {
+        let to = match to {
+            FootnoteTarget::Fact(id) => &fact_labels[id.as_str()],
+            FootnoteTarget::Footnote(id) => match footnote_labels.get(id.as_str()) {
+                Some(label) => label,
+                // The footnote was removed
+                None => continue,
+            },
+        };
+        writeln!(
+            out,
+            r#"    <link:footnoteArc xlink:type="arc" xlink:arcrole="{}" xlink:from="{}" xlink:to="{}" order="{}"/>"#,
+            escape(arcrole),
+            fact_labels[from],
+            to,
+            order
+        )?;
+    }
+    writeln!(out, "  </link:footnoteLink>")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    const SAMPLE: &str = r##"<xbrli:xbrl xmlns:xbrli="http://www.xbrl.org/2003/instance" xmlns:link="http://www.xbrl.org/2003/linkbase"
+    xmlns:xlink="http://www.w3.org/1999/xlink" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"
+    xmlns:xbrldi="http://xbrl.org/2006/xbrldi" xmlns:iso4217="http://www.xbrl.org/2003/iso4217"
+    xmlns:us-gaap="http://fasb.org/us-gaap/2023" xmlns:srt="http://fasb.org/srt/2023" xmlns:ex="http://example.com/ex">
+  <link:schemaRef xlink:type="simple" xlink:href="ex-20231231.xsd"/>
+  <xbrli:context id="FY"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2023-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:context id="FYServices"><xbrli:entity><xbrli:identifier scheme="http://www.sec.gov/CIK">0000000001</xbrli:identifier>
+    <xbrli:segment><xbrldi:explicitMember dimension="srt:ProductOrServiceAxis">us-gaap:ServiceMember</xbrldi:explicitMember></xbrli:segment></xbrli:entity>
+    <xbrli:period><xbrli:startDate>2023-01-01</xbrli:startDate><xbrli:endDate>2023-12-31</xbrli:endDate></xbrli:period></xbrli:context>
+  <xbrli:unit id="USD"><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unit>
+  <xbrli:unit id="USDPerShare"><xbrli:divide><xbrli:unitNumerator><xbrli:measure>iso4217:USD</xbrli:measure></xbrli:unitNumerator>
+    <xbrli:unitDenominator><xbrli:measure>xbrli:shares</xbrli:measure></xbrli:unitDenominator></xbrli:divide></xbrli:unit>
+  <us-gaap:Revenues contextRef="FY" unitRef="USD" decimals="-6" id="rev">383285000000</us-gaap:Revenues>
+  <us-gaap:Revenues contextRef="FYServices" unitRef="USD" decimals="-6">85200000000</us-gaap:Revenues>
+  <us-gaap:EarningsPerShareBasic contextRef="FY" unitRef="USDPerShare" decimals="2" id="eps">6.16</us-gaap:EarningsPerShareBasic>
+  <us-gaap:Goodwill contextRef="FY" unitRef="USD" xsi:nil="true"/>
+  <ex:Note contextRef="FY">Revenue &amp; other &lt;income&gt;</ex:Note>
+  <link:footnoteLink xlink:type="extended" xlink:role="http://www.xbrl.org/2003/role/link">
+    <link:loc xlink:type="locator" xlink:href="#rev" xlink:label="l1"/>
+    <link:footnote xlink:type="resource" xlink:label="f1" xml:lang="en" id="fn1">Includes services.</link:footnote>
+    <link:loc xlink:type="locator" xlink:href="#eps" xlink:label="l2"/>
+    <link:footnoteArc xlink:type="arc" xlink:arcrole="http://www.xbrl.org/2003/arcrole/fact-footnote" xlink:from="l1" xlink:to="f1"/>
+    <link:footnoteArc xlink:type="arc" xlink:arcrole="http://example.com/arcrole/fact-explanatoryFact" xlink:from="l2" xlink:to="l1"/>
+  </link:footnoteLink>
+</xbrli:xbrl>"##;
+
+    #[test]
+    fn test_round_trip() {
+        let doc = Parser::new().parse_str(SAMPLE).unwrap();
+        let xml = doc.to_xml().unwrap();
+        let back = Parser::new().parse_str(&xml).unwrap();
+
+        assert_eq!(back.normalized_facts(), doc.normalized_facts());
+        assert_eq!(back.schema_ref.as_deref(), Some("ex-20231231.xsd"));
+        assert_eq!(back.units[1].canonical(), "iso4217:USD/xbrli:shares");
+        assert_eq!(back.footnotes.len(), 1);
+        assert_eq!(back.footnotes[0].fact_refs, ["rev"]);
+        assert_eq!(back.facts.footnote_refs[0], ["fn1"]);
+        assert_eq!(back.footnote_arcs.len(), 2);
+        let explanatory = "http://example.com/arcrole/fact-explanatoryFact";
+        assert_eq!(
+            back.linked_facts("eps", explanatory).collect::<Vec<_>>(),
+            ["rev"]
+        );
+        assert_eq!(back.fact(3).unwrap().value(), &FactValue::Nil);
+        assert!(xml.contains("Revenue &amp; other &lt;income&gt;"));
+    }
+}